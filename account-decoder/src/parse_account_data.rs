@@ -1,6 +1,7 @@
 use crate::{
     parse_bpf_loader::parse_bpf_upgradeable_loader,
     parse_config::parse_config,
+    parse_idl::{get_idl_parser, IdlError},
     parse_nonce::parse_nonce,
     parse_stake::parse_stake,
     parse_sysvar::parse_sysvar,
@@ -53,6 +54,9 @@ pub enum ParseAccountError {
 
     #[error("Serde json error")]
     SerdeJsonError(#[from] serde_json::error::Error),
+
+    #[error("IDL error: {0}")]
+    IdlError(#[from] IdlError),
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
@@ -86,9 +90,17 @@ pub fn parse_account_data(
     data: &[u8],
     additional_data: Option<AccountAdditionalData>,
 ) -> Result<ParsedAccount, ParseAccountError> {
-    let program_name = PARSABLE_PROGRAM_IDS
-        .get(program_id)
-        .ok_or(ParseAccountError::ProgramNotParsable)?;
+    let program_name = match PARSABLE_PROGRAM_IDS.get(program_id) {
+        Some(program_name) => program_name,
+        None => {
+            let parser = get_idl_parser(program_id).ok_or(ParseAccountError::ProgramNotParsable)?;
+            return Ok(ParsedAccount {
+                program: parser.program_name(),
+                parsed: parser.parse_account(data)?,
+                space: data.len() as u64,
+            });
+        }
+    };
     let additional_data = additional_data.unwrap_or_default();
     let parsed_json = match program_name {
         ParsableAccount::BpfUpgradeableLoader => {
@@ -152,4 +164,34 @@ mod test {
         assert_eq!(parsed.program, "nonce".to_string());
         assert_eq!(parsed.space, State::size() as u64);
     }
+
+    #[test]
+    fn test_parse_account_data_idl() {
+        let account_pubkey = solana_sdk::pubkey::new_rand();
+        let program_id = solana_sdk::pubkey::new_rand();
+        let idl = serde_json::from_value(serde_json::json!({
+            "name": "counter",
+            "accounts": [{
+                "name": "Counter",
+                "type": {"kind": "struct", "fields": [{"name": "count", "type": "u64"}]},
+                "discriminator": [1],
+            }],
+        }))
+        .unwrap();
+        let data = [1, 5, 0, 0, 0, 0, 0, 0, 0];
+        assert!(parse_account_data(&account_pubkey, &program_id, &data, None).is_err());
+
+        crate::parse_idl::register_idl_parser(
+            program_id,
+            crate::parse_idl::IdlParser::new(idl).unwrap(),
+        );
+        let parsed = parse_account_data(&account_pubkey, &program_id, &data, None).unwrap();
+        assert_eq!(parsed.program, "counter".to_string());
+        assert_eq!(
+            parsed.parsed,
+            serde_json::json!({"type": "counter", "info": {"count": 5}})
+        );
+        assert_eq!(parsed.space, 9);
+        assert!(parse_account_data(&account_pubkey, &program_id, &[2], None).is_err());
+    }
 }
@@ -0,0 +1,856 @@
+//! Decoding of third-party program accounts and instructions from IDL descriptions
+//!
+//! IDLs follow the layout emitted by Anchor: borsh-serialized structs and enums, with accounts
+//! and instructions identified by an 8-byte discriminator prefix.  The discriminator defaults to
+//! Anchor's (`sha256("account:<Name>")` and `sha256("global:<snake_name>")`), and can be
+//! overridden per account or instruction with an explicit `discriminator` byte array for
+//! programs that use plain borsh with their own tags.
+use {
+    inflector::Inflector,
+    serde_json::{json, Map, Value},
+    solana_sdk::{hash::hash, pubkey::Pubkey},
+    std::{
+        collections::HashMap,
+        convert::TryInto,
+        fs::File,
+        io,
+        path::Path,
+        str::FromStr,
+        sync::{Arc, RwLock},
+    },
+    thiserror::Error,
+};
+
+const DISCRIMINATOR_LENGTH: usize = 8;
+
+lazy_static! {
+    static ref IDL_PARSERS: RwLock<HashMap<Pubkey, Arc<IdlParser>>> = RwLock::new(HashMap::new());
+}
+
+#[derive(Error, Debug)]
+pub enum IdlError {
+    #[error("I/O error: {0}")]
+    Io(#[from] io::Error),
+
+    #[error("Invalid IDL: {0}")]
+    InvalidIdl(#[from] serde_json::error::Error),
+
+    #[error("IDL type {0} is not defined")]
+    UndefinedType(String),
+
+    #[error("IDL type {0} is recursive")]
+    RecursiveType(String),
+
+    #[error("IDL has no program address, specify one")]
+    MissingProgramAddress,
+
+    #[error("Invalid program address: {0}")]
+    InvalidProgramAddress(String),
+
+    #[error("No {0} matches the data discriminator")]
+    UnknownDiscriminator(&'static str),
+
+    #[error("Data too short for {0}")]
+    DataTooShort(String),
+
+    #[error("Invalid variant {1} for enum {0}")]
+    InvalidEnumVariant(String, u8),
+
+    #[error("Invalid {0} value")]
+    InvalidValue(&'static str),
+
+    #[error("Instruction expects {0} accounts")]
+    NotEnoughAccounts(usize),
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+pub struct Idl {
+    pub name: String,
+    #[serde(default)]
+    pub instructions: Vec<IdlInstruction>,
+    #[serde(default)]
+    pub accounts: Vec<IdlTypeDefinition>,
+    #[serde(default)]
+    pub types: Vec<IdlTypeDefinition>,
+    #[serde(default)]
+    pub metadata: Option<IdlMetadata>,
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+pub struct IdlMetadata {
+    pub address: Option<String>,
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+pub struct IdlInstruction {
+    pub name: String,
+    #[serde(default)]
+    pub accounts: Vec<IdlAccountItem>,
+    #[serde(default)]
+    pub args: Vec<IdlField>,
+    #[serde(default)]
+    pub discriminator: Option<Vec<u8>>,
+}
+
+/// An instruction account, or a named group of accounts
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+pub struct IdlAccountItem {
+    pub name: String,
+    #[serde(default)]
+    pub accounts: Vec<IdlAccountItem>,
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+pub struct IdlField {
+    pub name: String,
+    #[serde(rename = "type")]
+    pub ty: IdlType,
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+pub struct IdlTypeDefinition {
+    pub name: String,
+    #[serde(rename = "type")]
+    pub ty: IdlTypeDefinitionTy,
+    #[serde(default)]
+    pub discriminator: Option<Vec<u8>>,
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+#[serde(rename_all = "lowercase", tag = "kind")]
+pub enum IdlTypeDefinitionTy {
+    Struct { fields: Vec<IdlField> },
+    Enum { variants: Vec<IdlEnumVariant> },
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+pub struct IdlEnumVariant {
+    pub name: String,
+    #[serde(default)]
+    pub fields: Option<IdlEnumFields>,
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+#[serde(untagged)]
+pub enum IdlEnumFields {
+    Named(Vec<IdlField>),
+    Tuple(Vec<IdlType>),
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub enum IdlType {
+    Bool,
+    U8,
+    I8,
+    U16,
+    I16,
+    U32,
+    I32,
+    F32,
+    U64,
+    I64,
+    F64,
+    U128,
+    I128,
+    Bytes,
+    String,
+    PublicKey,
+    Vec(Box<IdlType>),
+    Option(Box<IdlType>),
+    Array(Box<IdlType>, usize),
+    Defined(String),
+}
+
+/// Decodes the accounts and instructions of a single program described by an `Idl`
+#[derive(Debug)]
+pub struct IdlParser {
+    idl: Idl,
+    types: HashMap<String, IdlTypeDefinitionTy>,
+    account_discriminators: Vec<Vec<u8>>,
+    instruction_discriminators: Vec<Vec<u8>>,
+}
+
+impl IdlParser {
+    pub fn new(idl: Idl) -> Result<Self, IdlError> {
+        let types: HashMap<_, _> = idl
+            .types
+            .iter()
+            .chain(idl.accounts.iter())
+            .map(|definition| (definition.name.clone(), definition.ty.clone()))
+            .collect();
+
+        // Reject IDLs referring to undefined types up front, rather than on every decode
+        let mut used_types = vec![];
+        for definition in idl.types.iter().chain(idl.accounts.iter()) {
+            used_types.extend(field_types(&definition.ty));
+        }
+        for instruction in &idl.instructions {
+            used_types.extend(instruction.args.iter().map(|arg| &arg.ty));
+        }
+        for ty in used_types {
+            if let Some(name) = defined_types(ty).find(|name| !types.contains_key(*name)) {
+                return Err(IdlError::UndefinedType(name.to_string()));
+            }
+        }
+
+        // Decoding recurses into defined types, so a cycle between them would recurse until
+        // the data runs out, or overflow the stack first
+        let mut visited = HashMap::new();
+        for name in types.keys() {
+            check_recursion(name, &types, &mut visited)?;
+        }
+
+        let account_discriminators =
+            idl.accounts
+                .iter()
+                .map(|account| {
+                    account.discriminator.clone().unwrap_or_else(|| {
+                        anchor_discriminator(&format!("account:{}", account.name))
+                    })
+                })
+                .collect();
+        let instruction_discriminators = idl
+            .instructions
+            .iter()
+            .map(|instruction| {
+                instruction.discriminator.clone().unwrap_or_else(|| {
+                    anchor_discriminator(&format!("global:{}", instruction.name.to_snake_case()))
+                })
+            })
+            .collect();
+        Ok(Self {
+            idl,
+            types,
+            account_discriminators,
+            instruction_discriminators,
+        })
+    }
+
+    pub fn idl(&self) -> &Idl {
+        &self.idl
+    }
+
+    /// The program name reported in `jsonParsed` output
+    pub fn program_name(&self) -> String {
+        self.idl.name.to_kebab_case()
+    }
+
+    /// Decode account data into `{"type": ..., "info": ...}`
+    pub fn parse_account(&self, data: &[u8]) -> Result<Value, IdlError> {
+        let (index, discriminator) = find_discriminator(&self.account_discriminators, data)
+            .ok_or(IdlError::UnknownDiscriminator("account"))?;
+        let account = &self.idl.accounts[index];
+        let mut reader = BorshReader::new(&data[discriminator.len()..]);
+        let info = self.decode_type_definition(&account.name, &account.ty, &mut reader)?;
+        Ok(json!({
+            "type": account.name.to_camel_case(),
+            "info": info,
+        }))
+    }
+
+    /// Decode instruction data and accounts into `{"type": ..., "info": ...}`
+    pub fn parse_instruction(&self, data: &[u8], accounts: &[Pubkey]) -> Result<Value, IdlError> {
+        let (index, discriminator) = find_discriminator(&self.instruction_discriminators, data)
+            .ok_or(IdlError::UnknownDiscriminator("instruction"))?;
+        let instruction = &self.idl.instructions[index];
+        let mut reader = BorshReader::new(&data[discriminator.len()..]);
+        let mut info = Map::new();
+        for arg in &instruction.args {
+            info.insert(arg.name.to_camel_case(), self.decode(&arg.ty, &mut reader)?);
+        }
+
+        let mut account_names = vec![];
+        flatten_account_names(&instruction.accounts, &mut account_names);
+        if accounts.len() < account_names.len() {
+            return Err(IdlError::NotEnoughAccounts(account_names.len()));
+        }
+        for (name, pubkey) in account_names.into_iter().zip(accounts) {
+            info.insert(name.to_camel_case(), json!(pubkey.to_string()));
+        }
+
+        Ok(json!({
+            "type": instruction.name.to_camel_case(),
+            "info": info,
+        }))
+    }
+
+    fn decode_type_definition(
+        &self,
+        name: &str,
+        ty: &IdlTypeDefinitionTy,
+        reader: &mut BorshReader,
+    ) -> Result<Value, IdlError> {
+        match ty {
+            IdlTypeDefinitionTy::Struct { fields } => self.decode_fields(fields, reader),
+            IdlTypeDefinitionTy::Enum { variants } => {
+                let index = reader.read_u8(name)?;
+                let variant = variants
+                    .get(index as usize)
+                    .ok_or_else(|| IdlError::InvalidEnumVariant(name.to_string(), index))?;
+                let variant_name = variant.name.to_camel_case();
+                match &variant.fields {
+                    None => Ok(json!(variant_name)),
+                    Some(IdlEnumFields::Named(fields)) => {
+                        Ok(json!({ variant_name: self.decode_fields(fields, reader)? }))
+                    }
+                    Some(IdlEnumFields::Tuple(tys)) => {
+                        let values = tys
+                            .iter()
+                            .map(|ty| self.decode(ty, reader))
+                            .collect::<Result<Vec<_>, _>>()?;
+                        Ok(json!({ variant_name: values }))
+                    }
+                }
+            }
+        }
+    }
+
+    fn decode_fields(
+        &self,
+        fields: &[IdlField],
+        reader: &mut BorshReader,
+    ) -> Result<Value, IdlError> {
+        let mut object = Map::new();
+        for field in fields {
+            object.insert(field.name.to_camel_case(), self.decode(&field.ty, reader)?);
+        }
+        Ok(Value::Object(object))
+    }
+
+    fn decode(&self, ty: &IdlType, reader: &mut BorshReader) -> Result<Value, IdlError> {
+        Ok(match ty {
+            IdlType::Bool => match reader.read_u8("bool")? {
+                0 => json!(false),
+                1 => json!(true),
+                _ => return Err(IdlError::InvalidValue("bool")),
+            },
+            IdlType::U8 => json!(reader.read_u8("u8")?),
+            IdlType::I8 => json!(reader.read_u8("i8")? as i8),
+            IdlType::U16 => json!(u16::from_le_bytes(reader.read_array("u16")?)),
+            IdlType::I16 => json!(i16::from_le_bytes(reader.read_array("i16")?)),
+            IdlType::U32 => json!(u32::from_le_bytes(reader.read_array("u32")?)),
+            IdlType::I32 => json!(i32::from_le_bytes(reader.read_array("i32")?)),
+            IdlType::F32 => json!(f32::from_le_bytes(reader.read_array("f32")?)),
+            IdlType::U64 => json!(u64::from_le_bytes(reader.read_array("u64")?)),
+            IdlType::I64 => json!(i64::from_le_bytes(reader.read_array("i64")?)),
+            IdlType::F64 => json!(f64::from_le_bytes(reader.read_array("f64")?)),
+            // 128-bit integers don't fit in JSON numbers
+            IdlType::U128 => json!(u128::from_le_bytes(reader.read_array("u128")?).to_string()),
+            IdlType::I128 => json!(i128::from_le_bytes(reader.read_array("i128")?).to_string()),
+            IdlType::Bytes => {
+                let len = reader.read_len("bytes")?;
+                json!(base64::encode(reader.read_bytes(len, "bytes")?))
+            }
+            IdlType::String => {
+                let len = reader.read_len("string")?;
+                let bytes = reader.read_bytes(len, "string")?;
+                json!(std::str::from_utf8(bytes).map_err(|_| IdlError::InvalidValue("string"))?)
+            }
+            IdlType::PublicKey => {
+                json!(Pubkey::new(reader.read_bytes(32, "publicKey")?).to_string())
+            }
+            IdlType::Vec(ty) => {
+                let len = reader.read_len("vec")?;
+                self.check_len(ty, len, reader, "vec")?;
+                let values = (0..len)
+                    .map(|_| self.decode(ty, reader))
+                    .collect::<Result<Vec<_>, _>>()?;
+                Value::Array(values)
+            }
+            IdlType::Option(ty) => match reader.read_u8("option")? {
+                0 => Value::Null,
+                1 => self.decode(ty, reader)?,
+                _ => return Err(IdlError::InvalidValue("option")),
+            },
+            IdlType::Array(ty, len) => {
+                self.check_len(ty, *len, reader, "array")?;
+                let values = (0..*len)
+                    .map(|_| self.decode(ty, reader))
+                    .collect::<Result<Vec<_>, _>>()?;
+                Value::Array(values)
+            }
+            IdlType::Defined(name) => {
+                let definition = self
+                    .types
+                    .get(name)
+                    .ok_or_else(|| IdlError::UndefinedType(name.clone()))?;
+                self.decode_type_definition(name, definition, reader)?
+            }
+        })
+    }
+
+    /// Reject sequence lengths the remaining data can't hold, before decoding any element
+    fn check_len(
+        &self,
+        ty: &IdlType,
+        len: usize,
+        reader: &BorshReader,
+        what: &'static str,
+    ) -> Result<(), IdlError> {
+        match self.min_size(ty) {
+            // Like borsh, refuse sequences of zero-sized elements, whose length is unbounded
+            0 if len > 0 => Err(IdlError::InvalidValue(what)),
+            min_size if len.saturating_mul(min_size) > reader.data.len() => {
+                Err(IdlError::DataTooShort(what.to_string()))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// The fewest bytes a value of type `ty` can be serialized in
+    fn min_size(&self, ty: &IdlType) -> usize {
+        match ty {
+            IdlType::Bool | IdlType::U8 | IdlType::I8 | IdlType::Option(_) => 1,
+            IdlType::U16 | IdlType::I16 => 2,
+            IdlType::U32 | IdlType::I32 | IdlType::F32 => 4,
+            IdlType::Bytes | IdlType::String | IdlType::Vec(_) => 4,
+            IdlType::U64 | IdlType::I64 | IdlType::F64 => 8,
+            IdlType::U128 | IdlType::I128 => 16,
+            IdlType::PublicKey => 32,
+            IdlType::Array(ty, len) => len.saturating_mul(self.min_size(ty)),
+            IdlType::Defined(name) => match self.types.get(name) {
+                Some(IdlTypeDefinitionTy::Struct { fields }) => {
+                    fields.iter().fold(0, |size, field| {
+                        size.saturating_add(self.min_size(&field.ty))
+                    })
+                }
+                Some(IdlTypeDefinitionTy::Enum { variants }) => {
+                    let min_variant_size = variants
+                        .iter()
+                        .map(|variant| {
+                            let tys: Vec<_> = match &variant.fields {
+                                Some(IdlEnumFields::Named(fields)) => {
+                                    fields.iter().map(|field| &field.ty).collect()
+                                }
+                                Some(IdlEnumFields::Tuple(tys)) => tys.iter().collect(),
+                                None => vec![],
+                            };
+                            tys.into_iter()
+                                .fold(0usize, |size, ty| size.saturating_add(self.min_size(ty)))
+                        })
+                        .min()
+                        .unwrap_or(0);
+                    min_variant_size.saturating_add(1)
+                }
+                None => 0,
+            },
+        }
+    }
+}
+
+struct BorshReader<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> BorshReader<'a> {
+    fn new(data: &'a [u8]) -> Self {
+        Self { data }
+    }
+
+    fn read_bytes(&mut self, len: usize, what: &str) -> Result<&'a [u8], IdlError> {
+        if self.data.len() < len {
+            return Err(IdlError::DataTooShort(what.to_string()));
+        }
+        let (bytes, rest) = self.data.split_at(len);
+        self.data = rest;
+        Ok(bytes)
+    }
+
+    fn read_array<const N: usize>(&mut self, what: &str) -> Result<[u8; N], IdlError> {
+        Ok(self.read_bytes(N, what)?.try_into().unwrap())
+    }
+
+    fn read_u8(&mut self, what: &str) -> Result<u8, IdlError> {
+        Ok(self.read_bytes(1, what)?[0])
+    }
+
+    /// Borsh prefixes dynamically sized values with a u32 length
+    fn read_len(&mut self, what: &str) -> Result<usize, IdlError> {
+        Ok(u32::from_le_bytes(self.read_array(what)?) as usize)
+    }
+}
+
+/// The types of the fields of a struct, or of all the variants of an enum
+fn field_types(definition: &IdlTypeDefinitionTy) -> Vec<&IdlType> {
+    match definition {
+        IdlTypeDefinitionTy::Struct { fields } => fields.iter().map(|field| &field.ty).collect(),
+        IdlTypeDefinitionTy::Enum { variants } => variants
+            .iter()
+            .flat_map(|variant| match &variant.fields {
+                Some(IdlEnumFields::Named(fields)) => {
+                    fields.iter().map(|field| &field.ty).collect()
+                }
+                Some(IdlEnumFields::Tuple(tys)) => tys.iter().collect(),
+                None => vec![],
+            })
+            .collect(),
+    }
+}
+
+/// The names of the defined types `ty` refers to
+fn defined_types(ty: &IdlType) -> Box<dyn Iterator<Item = &str> + '_> {
+    match ty {
+        IdlType::Vec(ty) | IdlType::Option(ty) | IdlType::Array(ty, _) => defined_types(ty),
+        IdlType::Defined(name) => Box::new(std::iter::once(name.as_str())),
+        _ => Box::new(std::iter::empty()),
+    }
+}
+
+/// Depth-first search for a cycle through the defined type `name`.  `visited` maps each type
+/// seen to whether its search has finished.
+fn check_recursion<'a>(
+    name: &'a str,
+    types: &'a HashMap<String, IdlTypeDefinitionTy>,
+    visited: &mut HashMap<&'a str, bool>,
+) -> Result<(), IdlError> {
+    match visited.get(name) {
+        Some(true) => return Ok(()),
+        Some(false) => return Err(IdlError::RecursiveType(name.to_string())),
+        None => {}
+    }
+    visited.insert(name, false);
+    if let Some(definition) = types.get(name) {
+        for ty in field_types(definition) {
+            for child in defined_types(ty) {
+                check_recursion(child, types, visited)?;
+            }
+        }
+    }
+    visited.insert(name, true);
+    Ok(())
+}
+
+fn anchor_discriminator(preimage: &str) -> Vec<u8> {
+    hash(preimage.as_bytes()).to_bytes()[..DISCRIMINATOR_LENGTH].to_vec()
+}
+
+fn find_discriminator<'a>(
+    discriminators: &'a [Vec<u8>],
+    data: &[u8],
+) -> Option<(usize, &'a Vec<u8>)> {
+    discriminators
+        .iter()
+        .enumerate()
+        .find(|(_, discriminator)| data.starts_with(discriminator))
+}
+
+fn flatten_account_names<'a>(accounts: &'a [IdlAccountItem], names: &mut Vec<&'a str>) {
+    for account in accounts {
+        if account.accounts.is_empty() {
+            names.push(&account.name);
+        } else {
+            flatten_account_names(&account.accounts, names);
+        }
+    }
+}
+
+/// Register a parser for `program_id`, replacing any previously registered one
+pub fn register_idl_parser(program_id: Pubkey, parser: IdlParser) {
+    IDL_PARSERS
+        .write()
+        .unwrap()
+        .insert(program_id, Arc::new(parser));
+}
+
+pub fn get_idl_parser(program_id: &Pubkey) -> Option<Arc<IdlParser>> {
+    IDL_PARSERS.read().unwrap().get(program_id).cloned()
+}
+
+/// Load and register an IDL from a `[PROGRAM_ID=]FILE` specification.  Without a program id
+/// the IDL's `metadata.address` is used.
+pub fn register_idl_file(spec: &str) -> Result<Pubkey, IdlError> {
+    let (program_id, path) = match spec.split_once('=') {
+        Some((program_id, path)) => (Some(program_id), path),
+        None => (None, spec),
+    };
+    let idl: Idl = serde_json::from_reader(File::open(Path::new(path))?)?;
+    let program_id = program_id
+        .or_else(|| idl.metadata.as_ref()?.address.as_deref())
+        .ok_or(IdlError::MissingProgramAddress)?;
+    let program_id = Pubkey::from_str(program_id)
+        .map_err(|_| IdlError::InvalidProgramAddress(program_id.to_string()))?;
+    register_idl_parser(program_id, IdlParser::new(idl)?);
+    Ok(program_id)
+}
+
+#[cfg(test)]
+mod test {
+    use {super::*, std::io::Write};
+
+    fn test_idl() -> Idl {
+        serde_json::from_value(json!({
+            "name": "vesting_escrow",
+            "instructions": [
+                {
+                    "name": "createVesting",
+                    "accounts": [
+                        {"name": "authority", "isMut": false, "isSigner": true},
+                        {
+                            "name": "vault",
+                            "accounts": [
+                                {"name": "vaultAccount", "isMut": true, "isSigner": false},
+                                {"name": "tokenProgram", "isMut": false, "isSigner": false}
+                            ]
+                        }
+                    ],
+                    "args": [
+                        {"name": "amount", "type": "u64"},
+                        {"name": "schedule", "type": {"vec": {"defined": "Tranche"}}}
+                    ]
+                },
+                {
+                    "name": "close",
+                    "accounts": [],
+                    "args": [],
+                    "discriminator": [7]
+                }
+            ],
+            "accounts": [
+                {
+                    "name": "Vesting",
+                    "type": {
+                        "kind": "struct",
+                        "fields": [
+                            {"name": "beneficiary", "type": "publicKey"},
+                            {"name": "total_amount", "type": "u128"},
+                            {"name": "memo", "type": {"option": "string"}},
+                            {"name": "status", "type": {"defined": "Status"}},
+                            {"name": "seed", "type": {"array": ["u8", 2]}}
+                        ]
+                    }
+                }
+            ],
+            "types": [
+                {
+                    "name": "Tranche",
+                    "type": {
+                        "kind": "struct",
+                        "fields": [
+                            {"name": "unlockTimestamp", "type": "i64"},
+                            {"name": "amount", "type": "u64"}
+                        ]
+                    }
+                },
+                {
+                    "name": "Status",
+                    "type": {
+                        "kind": "enum",
+                        "variants": [
+                            {"name": "Active"},
+                            {"name": "Revoked", "fields": [{"name": "slot", "type": "u64"}]},
+                            {"name": "Paused", "fields": ["bool"]}
+                        ]
+                    }
+                }
+            ]
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_parse_idl_account() {
+        let parser = IdlParser::new(test_idl()).unwrap();
+        assert_eq!(parser.program_name(), "vesting-escrow");
+
+        let beneficiary = Pubkey::new_unique();
+        let mut data = anchor_discriminator("account:Vesting");
+        data.extend_from_slice(beneficiary.as_ref());
+        data.extend_from_slice(&u128::MAX.to_le_bytes());
+        data.push(1);
+        data.extend_from_slice(&4u32.to_le_bytes());
+        data.extend_from_slice(b"seed");
+        data.push(1);
+        data.extend_from_slice(&42u64.to_le_bytes());
+        data.extend_from_slice(&[3, 4]);
+        assert_eq!(
+            parser.parse_account(&data).unwrap(),
+            json!({
+                "type": "vesting",
+                "info": {
+                    "beneficiary": beneficiary.to_string(),
+                    "totalAmount": u128::MAX.to_string(),
+                    "memo": "seed",
+                    "status": {"revoked": {"slot": 42}},
+                    "seed": [3, 4],
+                },
+            })
+        );
+
+        // Unit enum variant and empty option
+        let mut data = anchor_discriminator("account:Vesting");
+        data.extend_from_slice(beneficiary.as_ref());
+        data.extend_from_slice(&0u128.to_le_bytes());
+        data.extend_from_slice(&[0, 0, 3, 4]);
+        assert_eq!(
+            parser.parse_account(&data).unwrap()["info"]["status"],
+            json!("active")
+        );
+
+        assert!(matches!(
+            parser.parse_account(&data[..data.len() - 1]),
+            Err(IdlError::DataTooShort(_))
+        ));
+        assert!(matches!(
+            parser.parse_account(&[0; 64]),
+            Err(IdlError::UnknownDiscriminator("account"))
+        ));
+    }
+
+    #[test]
+    fn test_parse_idl_instruction() {
+        let parser = IdlParser::new(test_idl()).unwrap();
+        let accounts = vec![
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+        ];
+
+        let mut data = anchor_discriminator("global:create_vesting");
+        data.extend_from_slice(&100u64.to_le_bytes());
+        data.extend_from_slice(&1u32.to_le_bytes());
+        data.extend_from_slice(&(-5i64).to_le_bytes());
+        data.extend_from_slice(&100u64.to_le_bytes());
+        assert_eq!(
+            parser.parse_instruction(&data, &accounts).unwrap(),
+            json!({
+                "type": "createVesting",
+                "info": {
+                    "amount": 100,
+                    "schedule": [{"unlockTimestamp": -5, "amount": 100}],
+                    "authority": accounts[0].to_string(),
+                    "vaultAccount": accounts[1].to_string(),
+                    "tokenProgram": accounts[2].to_string(),
+                },
+            })
+        );
+        assert!(matches!(
+            parser.parse_instruction(&data, &accounts[..2]),
+            Err(IdlError::NotEnoughAccounts(3))
+        ));
+
+        assert_eq!(
+            parser.parse_instruction(&[7], &[]).unwrap(),
+            json!({"type": "close", "info": {}})
+        );
+    }
+
+    #[test]
+    fn test_idl_undefined_type() {
+        let mut idl = test_idl();
+        idl.types.pop();
+        assert!(matches!(
+            IdlParser::new(idl),
+            Err(IdlError::UndefinedType(name)) if name == "Status"
+        ));
+    }
+
+    #[test]
+    fn test_idl_recursive_type() {
+        let recursive_idl = |types| {
+            serde_json::from_value::<Idl>(json!({"name": "recursive", "types": types})).unwrap()
+        };
+        let struct_type = |name, ty| {
+            json!({
+                "name": name,
+                "type": {"kind": "struct", "fields": [{"name": "inner", "type": ty}]},
+            })
+        };
+
+        // A { a: A }
+        let idl = recursive_idl(json!([struct_type("A", json!({"defined": "A"}))]));
+        assert!(matches!(
+            IdlParser::new(idl),
+            Err(IdlError::RecursiveType(_))
+        ));
+
+        // A -> B -> A, through a vec
+        let idl = recursive_idl(json!([
+            struct_type("A", json!({"defined": "B"})),
+            struct_type("B", json!({"vec": {"defined": "A"}})),
+        ]));
+        assert!(matches!(
+            IdlParser::new(idl),
+            Err(IdlError::RecursiveType(_))
+        ));
+
+        // Shared, but not recursive, references are fine
+        let idl = recursive_idl(json!([
+            struct_type("A", json!({"array": [{"defined": "B"}, 2]})),
+            struct_type("B", json!({"defined": "C"})),
+            struct_type("C", json!("u8")),
+        ]));
+        assert!(IdlParser::new(idl).is_ok());
+    }
+
+    #[test]
+    fn test_idl_vec_length() {
+        let idl = serde_json::from_value(json!({
+            "name": "lengths",
+            "accounts": [{
+                "name": "Lengths",
+                "type": {
+                    "kind": "struct",
+                    "fields": [
+                        {"name": "empty", "type": {"vec": {"array": ["u8", 0]}}},
+                        {"name": "units", "type": {"vec": {"defined": "Unit"}}},
+                        {"name": "wide", "type": {"vec": "u64"}},
+                    ]
+                }
+            }],
+            "types": [{"name": "Unit", "type": {"kind": "struct", "fields": []}}],
+        }))
+        .unwrap();
+        let parser = IdlParser::new(idl).unwrap();
+        let account = |lengths: [u32; 3], tail: &[u8]| {
+            let mut data = anchor_discriminator("account:Lengths");
+            for len in &lengths {
+                data.extend_from_slice(&len.to_le_bytes());
+            }
+            data.extend_from_slice(tail);
+            data
+        };
+
+        assert_eq!(
+            parser.parse_account(&account([0, 0, 1], &[0; 8])).unwrap()["info"],
+            json!({"empty": [], "units": [], "wide": [0]})
+        );
+        // Zero-sized elements don't consume any data, so their length can't be bounded by it
+        assert!(matches!(
+            parser.parse_account(&account([u32::MAX, 0, 0], &[])),
+            Err(IdlError::InvalidValue("vec"))
+        ));
+        assert!(matches!(
+            parser.parse_account(&account([0, u32::MAX, 0], &[])),
+            Err(IdlError::InvalidValue("vec"))
+        ));
+        // Two u64s need more than the 9 bytes left
+        assert!(matches!(
+            parser.parse_account(&account([0, 0, 2], &[0; 9])),
+            Err(IdlError::DataTooShort(what)) if what == "vec"
+        ));
+    }
+
+    #[test]
+    fn test_register_idl_file() {
+        let program_id = Pubkey::new_unique();
+        let mut file = tempfile::NamedTempFile::new().unwrap();
+        file.write_all(
+            serde_json::to_string(&json!({"name": "empty"}))
+                .unwrap()
+                .as_bytes(),
+        )
+        .unwrap();
+        let path = file.path().to_str().unwrap().to_string();
+
+        assert!(matches!(
+            register_idl_file(&path),
+            Err(IdlError::MissingProgramAddress)
+        ));
+        assert_eq!(
+            register_idl_file(&format!("{}={}", program_id, path)).unwrap(),
+            program_id
+        );
+        assert_eq!(get_idl_parser(&program_id).unwrap().program_name(), "empty");
+    }
+}
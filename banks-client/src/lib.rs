@@ -6,8 +6,10 @@
 //! cumbersome to use.
 
 use borsh::BorshDeserialize;
-use futures::{future::join_all, Future, FutureExt};
-pub use solana_banks_interface::{BanksClient as TarpcClient, TransactionStatus};
+use futures::{future::join_all, stream, Future, FutureExt, Stream};
+pub use solana_banks_interface::{
+    AccountFilter, BanksClient as TarpcClient, TransactionSimulation, TransactionStatus,
+};
 use solana_banks_interface::{BanksRequest, BanksResponse};
 use solana_program::{
     clock::Slot, fee_calculator::FeeCalculator, hash::Hash, program_pack::Pack, pubkey::Pubkey,
@@ -112,6 +114,47 @@ impl BanksClient {
             .get_account_with_commitment_and_context(ctx, address, commitment)
     }
 
+    pub fn simulate_transaction_with_commitment_and_context(
+        &mut self,
+        ctx: Context,
+        transaction: Transaction,
+        commitment: CommitmentLevel,
+    ) -> impl Future<Output = io::Result<TransactionSimulation>> + '_ {
+        self.inner
+            .simulate_transaction_with_commitment_and_context(ctx, transaction, commitment)
+    }
+
+    pub fn get_multiple_accounts_with_commitment_and_context(
+        &mut self,
+        ctx: Context,
+        addresses: Vec<Pubkey>,
+        commitment: CommitmentLevel,
+    ) -> impl Future<Output = io::Result<Vec<Option<Account>>>> + '_ {
+        self.inner
+            .get_multiple_accounts_with_commitment_and_context(ctx, addresses, commitment)
+    }
+
+    pub fn get_program_accounts_with_commitment_and_context(
+        &mut self,
+        ctx: Context,
+        program_id: Pubkey,
+        filters: Vec<AccountFilter>,
+        commitment: CommitmentLevel,
+    ) -> impl Future<Output = io::Result<Vec<(Pubkey, Account)>>> + '_ {
+        self.inner
+            .get_program_accounts_with_commitment_and_context(ctx, program_id, filters, commitment)
+    }
+
+    pub fn get_slot_update_with_context(
+        &mut self,
+        ctx: Context,
+        last_slot: Slot,
+        commitment: CommitmentLevel,
+    ) -> impl Future<Output = io::Result<Slot>> + '_ {
+        self.inner
+            .get_slot_update_with_context(ctx, last_slot, commitment)
+    }
+
     /// Send a transaction and return immediately. The server will resend the
     /// transaction until either it is accepted by the cluster or the transaction's
     /// blockhash expires.
@@ -203,6 +246,57 @@ impl BanksClient {
         self.process_transactions_with_commitment(transactions, CommitmentLevel::default())
     }
 
+    /// Run a transaction against the bank at the given commitment level without
+    /// committing it, and return its result, log messages and compute units consumed.
+    pub fn simulate_transaction_with_commitment(
+        &mut self,
+        transaction: Transaction,
+        commitment: CommitmentLevel,
+    ) -> impl Future<Output = io::Result<TransactionSimulation>> + '_ {
+        self.simulate_transaction_with_commitment_and_context(
+            context::current(),
+            transaction,
+            commitment,
+        )
+    }
+
+    /// Run a transaction against the most recent processed bank without committing it.
+    pub fn simulate_transaction(
+        &mut self,
+        transaction: Transaction,
+    ) -> impl Future<Output = io::Result<TransactionSimulation>> + '_ {
+        self.simulate_transaction_with_commitment(transaction, CommitmentLevel::Processed)
+    }
+
+    /// Return a stream of slots at the given commitment level, starting with the
+    /// current slot and followed by each newer slot as it is observed. Slots may
+    /// be skipped if they advance faster than the stream is polled. The stream
+    /// ends after yielding the first error.
+    pub fn slot_updates(
+        &mut self,
+        commitment: CommitmentLevel,
+    ) -> impl Stream<Item = io::Result<Slot>> {
+        stream::unfold(Some((self.clone(), None)), move |state| async move {
+            let (mut client, last_slot) = state?;
+            loop {
+                let slot = client
+                    .get_slot_update_with_context(
+                        context::current(),
+                        last_slot.unwrap_or_default(),
+                        commitment,
+                    )
+                    .await;
+                match slot {
+                    Ok(slot) if last_slot.map(|last_slot| slot > last_slot).unwrap_or(true) => {
+                        return Some((Ok(slot), Some((client, Some(slot)))));
+                    }
+                    Ok(_) => continue,
+                    Err(err) => return Some((Err(err), None)),
+                }
+            }
+        })
+    }
+
     /// Return the most recent rooted slot. All transactions at or below this slot
     /// are said to be finalized. The cluster will not fork to a higher slot.
     pub fn get_root_slot(&mut self) -> impl Future<Output = io::Result<Slot>> + '_ {
@@ -234,6 +328,55 @@ impl BanksClient {
         self.get_account_with_commitment(address, CommitmentLevel::default())
     }
 
+    /// Return the accounts at the given addresses at the slot corresponding to the
+    /// given commitment level, in the same order. Accounts not found are None.
+    pub fn get_multiple_accounts_with_commitment(
+        &mut self,
+        addresses: Vec<Pubkey>,
+        commitment: CommitmentLevel,
+    ) -> impl Future<Output = io::Result<Vec<Option<Account>>>> + '_ {
+        self.get_multiple_accounts_with_commitment_and_context(
+            context::current(),
+            addresses,
+            commitment,
+        )
+    }
+
+    /// Return the accounts at the given addresses at the time of the most recent
+    /// root slot, in the same order. Accounts not found are None.
+    pub fn get_multiple_accounts(
+        &mut self,
+        addresses: Vec<Pubkey>,
+    ) -> impl Future<Output = io::Result<Vec<Option<Account>>>> + '_ {
+        self.get_multiple_accounts_with_commitment(addresses, CommitmentLevel::default())
+    }
+
+    /// Return the accounts owned by the given program that match all of the given
+    /// filters, at the slot corresponding to the given commitment level.
+    pub fn get_program_accounts_with_commitment(
+        &mut self,
+        program_id: Pubkey,
+        filters: Vec<AccountFilter>,
+        commitment: CommitmentLevel,
+    ) -> impl Future<Output = io::Result<Vec<(Pubkey, Account)>>> + '_ {
+        self.get_program_accounts_with_commitment_and_context(
+            context::current(),
+            program_id,
+            filters,
+            commitment,
+        )
+    }
+
+    /// Return the accounts owned by the given program that match all of the given
+    /// filters, at the time of the most recent root slot.
+    pub fn get_program_accounts(
+        &mut self,
+        program_id: Pubkey,
+        filters: Vec<AccountFilter>,
+    ) -> impl Future<Output = io::Result<Vec<(Pubkey, Account)>>> + '_ {
+        self.get_program_accounts_with_commitment(program_id, filters, CommitmentLevel::default())
+    }
+
     /// Return the unpacked account data at the given address
     /// If the account is not found, an error is returned
     pub fn get_packed_account_data<T: Pack>(
@@ -331,12 +474,15 @@ pub async fn start_tcp_client<T: ToSocketAddrs>(addr: T) -> io::Result<BanksClie
 #[cfg(test)]
 mod tests {
     use super::*;
-    use solana_banks_server::banks_server::start_local_server;
+    use futures::StreamExt;
+    use solana_banks_server::banks_server::{
+        start_local_server, start_local_server_with_slot_update_timeout,
+    };
     use solana_runtime::{
         bank::Bank, bank_forks::BankForks, commitment::BlockCommitmentCache,
         genesis_utils::create_genesis_config,
     };
-    use solana_sdk::{message::Message, signature::Signer, system_instruction};
+    use solana_sdk::{message::Message, signature::Signer, system_instruction, system_program};
     use std::sync::{Arc, RwLock};
     use tarpc::transport;
     use tokio::{runtime::Runtime, time::sleep};
@@ -420,4 +566,70 @@ mod tests {
             Ok(())
         })
     }
+
+    #[test]
+    fn test_banks_server_queries() -> io::Result<()> {
+        let genesis = create_genesis_config(10);
+        let bank = Bank::new_for_tests(&genesis.genesis_config);
+        let slot = bank.slot();
+        let block_commitment_cache = Arc::new(RwLock::new(
+            BlockCommitmentCache::new_for_tests_with_slots(slot, slot),
+        ));
+        let bank_forks = Arc::new(RwLock::new(BankForks::new(bank)));
+
+        let mint_pubkey = genesis.mint_keypair.pubkey();
+        let bob_pubkey = solana_sdk::pubkey::new_rand();
+        let instruction = system_instruction::transfer(&mint_pubkey, &bob_pubkey, 1);
+        let message = Message::new(&[instruction], Some(&mint_pubkey));
+
+        Runtime::new()?.block_on(async {
+            // Don't wait long for a new slot before the first update
+            let client_transport = start_local_server_with_slot_update_timeout(
+                bank_forks.clone(),
+                block_commitment_cache.clone(),
+                Duration::from_millis(100),
+            )
+            .await;
+            let mut banks_client = start_client(client_transport).await?;
+
+            let accounts = banks_client
+                .get_multiple_accounts(vec![mint_pubkey, bob_pubkey])
+                .await?;
+            assert_eq!(accounts[0].as_ref().unwrap().lamports, 10);
+            assert_eq!(accounts[1], None);
+
+            let program_accounts = banks_client
+                .get_program_accounts(system_program::id(), vec![AccountFilter::DataSize(0)])
+                .await?;
+            assert!(program_accounts
+                .iter()
+                .any(|(pubkey, _)| *pubkey == mint_pubkey));
+            let program_accounts = banks_client
+                .get_program_accounts(system_program::id(), vec![AccountFilter::DataSize(1)])
+                .await?;
+            assert!(program_accounts.is_empty());
+
+            // Simulation reports the outcome without committing the transaction
+            let recent_blockhash = banks_client.get_recent_blockhash().await?;
+            let transaction = Transaction::new(&[&genesis.mint_keypair], message, recent_blockhash);
+            let simulation = banks_client.simulate_transaction(transaction).await?;
+            assert_eq!(simulation.result, Ok(()));
+            assert!(!simulation.logs.is_empty());
+            assert_eq!(banks_client.get_balance(bob_pubkey).await?, 0);
+
+            let mut slot_updates = Box::pin(banks_client.slot_updates(CommitmentLevel::Processed));
+            assert_eq!(slot_updates.next().await.unwrap()?, slot);
+            {
+                let parent = bank_forks.read().unwrap().working_bank();
+                let bank = Bank::new_from_parent(&parent, &Pubkey::default(), slot + 1);
+                bank_forks.write().unwrap().insert(bank);
+                block_commitment_cache
+                    .write()
+                    .unwrap()
+                    .set_all_slots(slot + 1, slot);
+            }
+            assert_eq!(slot_updates.next().await.unwrap()?, slot + 1);
+            Ok(())
+        })
+    }
 }
@@ -25,6 +25,37 @@ pub struct TransactionStatus {
     pub confirmation_status: Option<TransactionConfirmationStatus>,
 }
 
+/// The outcome of running a transaction against a bank without committing it
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct TransactionSimulation {
+    pub result: transaction::Result<()>,
+    pub logs: Vec<String>,
+    pub units_consumed: u64,
+}
+
+/// A filter on program account data. Program accounts are returned only if
+/// they match every filter given.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum AccountFilter {
+    /// Match accounts whose data is exactly this many bytes
+    DataSize(u64),
+    /// Match accounts whose data at `offset` is equal to `bytes`
+    Memcmp { offset: usize, bytes: Vec<u8> },
+}
+
+impl AccountFilter {
+    pub fn allows(&self, data: &[u8]) -> bool {
+        match self {
+            AccountFilter::DataSize(size) => data.len() as u64 == *size,
+            AccountFilter::Memcmp { offset, bytes } => offset
+                .checked_add(bytes.len())
+                .and_then(|end| data.get(*offset..end))
+                .map(|data| data == bytes.as_slice())
+                .unwrap_or(false),
+        }
+    }
+}
+
 #[tarpc::service]
 pub trait Banks {
     async fn send_transaction_with_context(transaction: Transaction);
@@ -43,6 +74,22 @@ pub trait Banks {
         address: Pubkey,
         commitment: CommitmentLevel,
     ) -> Option<Account>;
+    async fn simulate_transaction_with_commitment_and_context(
+        transaction: Transaction,
+        commitment: CommitmentLevel,
+    ) -> TransactionSimulation;
+    async fn get_multiple_accounts_with_commitment_and_context(
+        addresses: Vec<Pubkey>,
+        commitment: CommitmentLevel,
+    ) -> Vec<Option<Account>>;
+    async fn get_program_accounts_with_commitment_and_context(
+        program_id: Pubkey,
+        filters: Vec<AccountFilter>,
+        commitment: CommitmentLevel,
+    ) -> Vec<(Pubkey, Account)>;
+    /// Returns the slot at the given commitment level as soon as it is greater
+    /// than `last_slot`, or the current slot if that does not happen shortly
+    async fn get_slot_update_with_context(last_slot: Slot, commitment: CommitmentLevel) -> Slot;
 }
 
 #[cfg(test)]
@@ -55,4 +102,21 @@ mod tests {
         let (client_transport, _server_transport) = transport::channel::unbounded();
         BanksClient::new(client::Config::default(), client_transport);
     }
+
+    #[test]
+    fn test_account_filter() {
+        let data = [1, 2, 3, 4];
+        assert!(AccountFilter::DataSize(4).allows(&data));
+        assert!(!AccountFilter::DataSize(3).allows(&data));
+        let memcmp = |offset, bytes: &[u8]| AccountFilter::Memcmp {
+            offset,
+            bytes: bytes.to_vec(),
+        };
+        assert!(memcmp(0, &[1, 2]).allows(&data));
+        assert!(memcmp(2, &[3, 4]).allows(&data));
+        assert!(memcmp(4, &[]).allows(&data));
+        assert!(!memcmp(1, &[1, 2]).allows(&data));
+        assert!(!memcmp(3, &[4, 5]).allows(&data));
+        assert!(!memcmp(usize::MAX, &[1]).allows(&data));
+    }
 }
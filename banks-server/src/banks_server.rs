@@ -1,21 +1,28 @@
 use crate::send_transaction_service::{SendTransactionService, TransactionInfo};
 use bincode::{deserialize, serialize};
 use futures::{future, prelude::stream::StreamExt};
+use log::*;
 use solana_banks_interface::{
-    Banks, BanksRequest, BanksResponse, TransactionConfirmationStatus, TransactionStatus,
+    AccountFilter, Banks, BanksRequest, BanksResponse, TransactionConfirmationStatus,
+    TransactionSimulation, TransactionStatus,
+};
+use solana_runtime::{
+    bank::{Bank, TransactionSimulationResult},
+    bank_forks::BankForks,
+    commitment::BlockCommitmentCache,
 };
-use solana_runtime::{bank::Bank, bank_forks::BankForks, commitment::BlockCommitmentCache};
 use solana_sdk::{
-    account::Account,
+    account::{Account, ReadableAccount},
     clock::Slot,
     commitment_config::CommitmentLevel,
     fee_calculator::FeeCalculator,
     hash::Hash,
     pubkey::Pubkey,
     signature::Signature,
-    transaction::{self, Transaction},
+    transaction::{self, SanitizedTransaction, Transaction},
 };
 use std::{
+    convert::TryFrom,
     io,
     net::{Ipv4Addr, SocketAddr},
     sync::{
@@ -23,7 +30,7 @@ use std::{
         Arc, RwLock,
     },
     thread::Builder,
-    time::Duration,
+    time::{Duration, Instant},
 };
 use tarpc::{
     context::Context,
@@ -35,11 +42,16 @@ use tarpc::{
 use tokio::time::sleep;
 use tokio_serde::formats::Bincode;
 
+// How long get_slot_update waits for a new slot before returning the current
+// one, which must be well within the client's request deadline.
+const DEFAULT_SLOT_UPDATE_TIMEOUT: Duration = Duration::from_secs(5);
+
 #[derive(Clone)]
 struct BanksServer {
     bank_forks: Arc<RwLock<BankForks>>,
     block_commitment_cache: Arc<RwLock<BlockCommitmentCache>>,
     transaction_sender: Sender<TransactionInfo>,
+    slot_update_timeout: Duration,
 }
 
 impl BanksServer {
@@ -56,6 +68,7 @@ impl BanksServer {
             bank_forks,
             block_commitment_cache,
             transaction_sender,
+            slot_update_timeout: DEFAULT_SLOT_UPDATE_TIMEOUT,
         }
     }
 
@@ -78,6 +91,7 @@ impl BanksServer {
     fn new_loopback(
         bank_forks: Arc<RwLock<BankForks>>,
         block_commitment_cache: Arc<RwLock<BlockCommitmentCache>>,
+        slot_update_timeout: Duration,
     ) -> Self {
         let (transaction_sender, transaction_receiver) = channel();
         let bank = bank_forks.read().unwrap().working_bank();
@@ -92,7 +106,10 @@ impl BanksServer {
             .name("solana-bank-forks-client".to_string())
             .spawn(move || Self::run(server_bank_forks, transaction_receiver))
             .unwrap();
-        Self::new(bank_forks, block_commitment_cache, transaction_sender)
+        Self {
+            slot_update_timeout,
+            ..Self::new(bank_forks, block_commitment_cache, transaction_sender)
+        }
     }
 
     fn slot(&self, commitment: CommitmentLevel) -> Slot {
@@ -260,13 +277,113 @@ impl Banks for BanksServer {
         let bank = self.bank(commitment);
         bank.get_account(&address).map(Account::from)
     }
+
+    async fn simulate_transaction_with_commitment_and_context(
+        self,
+        _: Context,
+        transaction: Transaction,
+        commitment: CommitmentLevel,
+    ) -> TransactionSimulation {
+        let bank = self.bank(commitment);
+        let sanitized_transaction =
+            verify_transaction(&transaction, bank.libsecp256k1_0_5_upgrade_enabled())
+                .and_then(|_| SanitizedTransaction::try_from(transaction));
+        match sanitized_transaction {
+            Ok(sanitized_transaction) => {
+                // The working bank of a loopback server is never frozen
+                let TransactionSimulationResult {
+                    result,
+                    logs,
+                    units_consumed,
+                    ..
+                } = bank.simulate_transaction_unchecked(sanitized_transaction);
+                TransactionSimulation {
+                    result,
+                    logs,
+                    units_consumed,
+                }
+            }
+            Err(err) => TransactionSimulation {
+                result: Err(err),
+                logs: vec![],
+                units_consumed: 0,
+            },
+        }
+    }
+
+    async fn get_multiple_accounts_with_commitment_and_context(
+        self,
+        _: Context,
+        addresses: Vec<Pubkey>,
+        commitment: CommitmentLevel,
+    ) -> Vec<Option<Account>> {
+        let bank = self.bank(commitment);
+        addresses
+            .iter()
+            .map(|address| bank.get_account(address).map(Account::from))
+            .collect()
+    }
+
+    async fn get_program_accounts_with_commitment_and_context(
+        self,
+        _: Context,
+        program_id: Pubkey,
+        filters: Vec<AccountFilter>,
+        commitment: CommitmentLevel,
+    ) -> Vec<(Pubkey, Account)> {
+        let bank = self.bank(commitment);
+        bank.get_filtered_program_accounts(&program_id, |account| {
+            filters.iter().all(|filter| filter.allows(account.data()))
+        })
+        .unwrap_or_else(|err| {
+            warn!(
+                "get_program_accounts scan of {} failed: {}",
+                program_id, err
+            );
+            vec![]
+        })
+        .into_iter()
+        .map(|(pubkey, account)| (pubkey, Account::from(account)))
+        .collect()
+    }
+
+    async fn get_slot_update_with_context(
+        self,
+        _: Context,
+        last_slot: Slot,
+        commitment: CommitmentLevel,
+    ) -> Slot {
+        let start = Instant::now();
+        let mut slot = self.slot(commitment);
+        while slot <= last_slot && start.elapsed() < self.slot_update_timeout {
+            sleep(Duration::from_millis(100)).await;
+            slot = self.slot(commitment);
+        }
+        slot
+    }
 }
 
 pub async fn start_local_server(
     bank_forks: Arc<RwLock<BankForks>>,
     block_commitment_cache: Arc<RwLock<BlockCommitmentCache>>,
 ) -> UnboundedChannel<Response<BanksResponse>, ClientMessage<BanksRequest>> {
-    let banks_server = BanksServer::new_loopback(bank_forks, block_commitment_cache);
+    start_local_server_with_slot_update_timeout(
+        bank_forks,
+        block_commitment_cache,
+        DEFAULT_SLOT_UPDATE_TIMEOUT,
+    )
+    .await
+}
+
+/// Like `start_local_server`, with the time `get_slot_update` waits for a
+/// new slot before returning the current one
+pub async fn start_local_server_with_slot_update_timeout(
+    bank_forks: Arc<RwLock<BankForks>>,
+    block_commitment_cache: Arc<RwLock<BlockCommitmentCache>>,
+    slot_update_timeout: Duration,
+) -> UnboundedChannel<Response<BanksResponse>, ClientMessage<BanksRequest>> {
+    let banks_server =
+        BanksServer::new_loopback(bank_forks, block_commitment_cache, slot_update_timeout);
     let (client_transport, server_transport) = transport::channel::unbounded();
     let server = server::BaseChannel::with_defaults(server_transport).execute(banks_server.serve());
     tokio::spawn(server);
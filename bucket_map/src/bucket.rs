@@ -0,0 +1,314 @@
+//! An open addressed hash table of pubkeys stored in `BucketStorage`
+//!
+//! The index storage holds an `IndexEntry` per key. Values are variable length byte strings
+//! held in data storages of increasing cell sizes, and an index entry records which data cell
+//! holds its value.
+
+use {
+    crate::{bucket_storage::BucketStorage, RefCount},
+    solana_sdk::pubkey::Pubkey,
+    std::{
+        collections::{hash_map::DefaultHasher, BinaryHeap},
+        hash::{Hash, Hasher},
+        mem::size_of,
+        ops::RangeBounds,
+        path::PathBuf,
+        sync::Arc,
+    },
+};
+
+/// Payload size of the smallest data cells. Data storage `i` holds values of up to
+/// `MIN_DATA_SIZE << i` bytes.
+const MIN_DATA_SIZE: u64 = 64;
+
+/// Initial number of cells of a storage, as a power of two
+const DEFAULT_CAPACITY_POW2: u8 = 5;
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+struct IndexEntry {
+    key: Pubkey,
+    ref_count: RefCount,
+    /// Data storage holding the value
+    data_bucket: u64,
+    /// Cell holding the value within its data storage
+    data_location: u64,
+    /// Length of the value in bytes
+    data_len: u64,
+}
+
+#[derive(Debug)]
+pub struct Bucket {
+    drives: Arc<Vec<PathBuf>>,
+    /// Number of cells probed for a key before the storage has to grow
+    max_search: u64,
+    index: BucketStorage,
+    data: Vec<BucketStorage>,
+}
+
+impl Bucket {
+    pub fn new(drives: Arc<Vec<PathBuf>>, max_search: u8) -> Self {
+        let index = BucketStorage::new(
+            &drives,
+            size_of::<IndexEntry>() as u64,
+            DEFAULT_CAPACITY_POW2,
+        );
+        Self {
+            drives,
+            max_search: max_search as u64,
+            index,
+            data: vec![],
+        }
+    }
+
+    fn hash(key: &Pubkey) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        key.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    /// The non-zero uid marking cells owned by `key`
+    fn uid(key: &Pubkey) -> u64 {
+        Self::hash(key) | 1
+    }
+
+    /// The cells probed for `key` in `storage`
+    fn probe(storage: &BucketStorage, key: &Pubkey, max_search: u64) -> impl Iterator<Item = u64> {
+        let num_cells = storage.num_cells();
+        let start = Self::hash(key) % num_cells;
+        (start..start + max_search.min(num_cells)).map(move |ix| ix % num_cells)
+    }
+
+    fn find_entry(&self, key: &Pubkey) -> Option<(IndexEntry, u64)> {
+        Self::probe(&self.index, key, self.max_search).find_map(|ix| {
+            if self.index.is_free(ix) {
+                return None;
+            }
+            let entry: IndexEntry = self.index.get(ix);
+            if entry.key == *key {
+                Some((entry, ix))
+            } else {
+                None
+            }
+        })
+    }
+
+    pub fn contains_key(&self, key: &Pubkey) -> bool {
+        self.find_entry(key).is_some()
+    }
+
+    pub fn read_value(&self, key: &Pubkey) -> Option<(&[u8], RefCount)> {
+        let (entry, _) = self.find_entry(key)?;
+        Some((self.entry_value(&entry), entry.ref_count))
+    }
+
+    fn entry_value(&self, entry: &IndexEntry) -> &[u8] {
+        self.data[entry.data_bucket as usize].get_bytes(entry.data_location, entry.data_len)
+    }
+
+    /// Number of keys in the bucket
+    pub fn len(&self) -> u64 {
+        self.index.used()
+    }
+
+    pub fn keys(&self) -> Vec<Pubkey> {
+        (0..self.index.num_cells())
+            .filter(|ix| !self.index.is_free(*ix))
+            .map(|ix| self.index.get::<IndexEntry>(ix).key)
+            .collect()
+    }
+
+    /// Up to `limit` of the lowest keys in `range`, sorted, along with their values and ref
+    /// counts. The values of the other keys are not read.
+    pub fn items_in_range<R: RangeBounds<Pubkey>>(
+        &self,
+        range: Option<&R>,
+        limit: usize,
+    ) -> Vec<(Pubkey, &[u8], RefCount)> {
+        // the lowest keys found so far, highest on top
+        let mut lowest = BinaryHeap::new();
+        for ix in 0..self.index.num_cells() {
+            if self.index.is_free(ix) {
+                continue;
+            }
+            let entry: IndexEntry = self.index.get(ix);
+            if !range
+                .map(|range| range.contains(&entry.key))
+                .unwrap_or(true)
+            {
+                continue;
+            }
+            if lowest.len() < limit {
+                lowest.push((entry.key, ix));
+            } else if matches!(lowest.peek(), Some((highest, _)) if entry.key < *highest) {
+                lowest.pop();
+                lowest.push((entry.key, ix));
+            }
+        }
+        lowest
+            .into_sorted_vec()
+            .into_iter()
+            .map(|(_, ix)| {
+                let entry: IndexEntry = self.index.get(ix);
+                (entry.key, self.entry_value(&entry), entry.ref_count)
+            })
+            .collect()
+    }
+
+    pub fn write_value(&mut self, key: &Pubkey, value: &[u8], ref_count: RefCount) {
+        let data_bucket = Self::data_bucket_for_len(value.len() as u64);
+        let (mut entry, ix) = match self.find_entry(key) {
+            Some((entry, ix)) => {
+                if entry.data_bucket != data_bucket {
+                    self.data[entry.data_bucket as usize].free(entry.data_location, Self::uid(key));
+                }
+                (entry, ix)
+            }
+            None => {
+                let entry = IndexEntry {
+                    key: *key,
+                    // no data cell yet
+                    data_bucket: u64::MAX,
+                    ..IndexEntry::default()
+                };
+                (entry, self.allocate_index(key))
+            }
+        };
+        if entry.data_bucket != data_bucket {
+            entry.data_bucket = data_bucket;
+            entry.data_location = self.allocate_data(key, data_bucket);
+        }
+        entry.data_len = value.len() as u64;
+        entry.ref_count = ref_count;
+        self.data[data_bucket as usize].put_bytes(entry.data_location, value);
+        self.index.put(ix, &entry);
+    }
+
+    pub fn delete_key(&mut self, key: &Pubkey) {
+        if let Some((entry, ix)) = self.find_entry(key) {
+            let uid = Self::uid(key);
+            self.data[entry.data_bucket as usize].free(entry.data_location, uid);
+            self.index.free(ix, uid);
+        }
+    }
+
+    fn data_bucket_for_len(len: u64) -> u64 {
+        let mut data_bucket = 0;
+        while MIN_DATA_SIZE << data_bucket < len {
+            data_bucket += 1;
+        }
+        data_bucket
+    }
+
+    /// Allocate a free cell in `storage` for `key`, if one is within reach of its hash
+    fn allocate_cell(storage: &mut BucketStorage, key: &Pubkey, max_search: u64) -> Option<u64> {
+        let uid = Self::uid(key);
+        let ix = Self::probe(storage, key, max_search).find(|ix| storage.is_free(*ix))?;
+        storage.allocate(ix, uid).unwrap();
+        Some(ix)
+    }
+
+    fn allocate_index(&mut self, key: &Pubkey) -> u64 {
+        loop {
+            if let Some(ix) = Self::allocate_cell(&mut self.index, key, self.max_search) {
+                return ix;
+            }
+            self.grow_index();
+        }
+    }
+
+    fn allocate_data(&mut self, key: &Pubkey, data_bucket: u64) -> u64 {
+        while self.data.len() <= data_bucket as usize {
+            let payload_size = MIN_DATA_SIZE << self.data.len();
+            self.data.push(BucketStorage::new(
+                &self.drives,
+                payload_size,
+                DEFAULT_CAPACITY_POW2,
+            ));
+        }
+        loop {
+            let storage = &mut self.data[data_bucket as usize];
+            if let Some(ix) = Self::allocate_cell(storage, key, self.max_search) {
+                return ix;
+            }
+            // index entries point at data cells by location, so growing keeps cells in place
+            *storage = BucketStorage::new_resized(&self.drives, storage, storage.capacity_pow2 + 1);
+        }
+    }
+
+    /// Double the index capacity until every entry can be rehashed into it
+    fn grow_index(&mut self) {
+        let mut capacity_pow2 = self.index.capacity_pow2;
+        'grow: loop {
+            capacity_pow2 += 1;
+            let mut index =
+                BucketStorage::new(&self.drives, size_of::<IndexEntry>() as u64, capacity_pow2);
+            for ix in 0..self.index.num_cells() {
+                if self.index.is_free(ix) {
+                    continue;
+                }
+                let entry: IndexEntry = self.index.get(ix);
+                match Self::allocate_cell(&mut index, &entry.key, self.max_search) {
+                    Some(new_ix) => index.put(new_ix, &entry),
+                    None => continue 'grow,
+                }
+            }
+            self.index = index;
+            return;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, tempfile::TempDir};
+
+    #[test]
+    fn test_bucket_grow() {
+        let tmpdir = TempDir::new().unwrap();
+        let drives = Arc::new(vec![tmpdir.path().to_path_buf()]);
+        let mut bucket = Bucket::new(drives, 4);
+        let keys: Vec<_> = (0..1000).map(|_| Pubkey::new_unique()).collect();
+        for (i, key) in keys.iter().enumerate() {
+            // values of varying sizes spread over several data storages
+            bucket.write_value(key, &vec![i as u8; i % 300], i as RefCount);
+        }
+        assert_eq!(bucket.len(), 1000);
+        assert!(bucket.index.capacity_pow2 > DEFAULT_CAPACITY_POW2);
+        assert_eq!(bucket.data.len(), 4);
+        for (i, key) in keys.iter().enumerate() {
+            assert_eq!(
+                bucket.read_value(key),
+                Some((&vec![i as u8; i % 300][..], i as RefCount))
+            );
+        }
+
+        // move a value to a bigger data storage and back
+        bucket.write_value(&keys[1], &[9; 200], 5);
+        assert_eq!(bucket.read_value(&keys[1]), Some((&[9; 200][..], 5)));
+        bucket.write_value(&keys[1], &[8; 2], 6);
+        assert_eq!(bucket.read_value(&keys[1]), Some((&[8; 2][..], 6)));
+        assert_eq!(bucket.len(), 1000);
+
+        for key in &keys[..500] {
+            bucket.delete_key(key);
+        }
+        assert_eq!(bucket.len(), 500);
+        assert!(!bucket.contains_key(&keys[0]));
+        assert!(bucket.contains_key(&keys[999]));
+        let mut remaining = bucket.keys();
+        remaining.sort();
+        let mut expected = keys[500..].to_vec();
+        expected.sort();
+        assert_eq!(remaining, expected);
+        let range = expected[10]..expected[20];
+        let items = bucket.items_in_range(Some(&range), 5);
+        assert_eq!(
+            items.iter().map(|(key, ..)| *key).collect::<Vec<_>>(),
+            expected[10..15].to_vec()
+        );
+        assert_eq!(bucket.items_in_range(Some(&range), 100).len(), 10);
+        assert!(bucket.items_in_range(Some(&range), 0).is_empty());
+        assert_eq!(bucket.data.iter().map(|data| data.used()).sum::<u64>(), 500);
+    }
+}
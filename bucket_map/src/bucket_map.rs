@@ -0,0 +1,334 @@
+//! A pubkey keyed map of slices with ref counts, stored in memory-mapped files
+//!
+//! Keys are split into buckets by their leading bits, so bucket `i` holds the same keys as bin
+//! `i` of an index binned by `PubkeyBinCalculator16` with as many bins.
+
+use {
+    crate::{bucket::Bucket, RefCount},
+    log::*,
+    serde::{de::DeserializeOwned, Serialize},
+    solana_sdk::pubkey::Pubkey,
+    std::{
+        fmt::Debug,
+        fs,
+        marker::PhantomData,
+        ops::RangeBounds,
+        path::PathBuf,
+        sync::{Arc, RwLock},
+    },
+    tempfile::TempDir,
+};
+
+pub const DEFAULT_MAX_SEARCH: u8 = 32;
+
+/// Subdirectory of each drive holding the bucket files
+const BUCKET_MAP_DIR: &str = "bucket_map";
+
+#[derive(Debug, Default, Clone)]
+pub struct BucketMapConfig {
+    /// Number of buckets, a power of two
+    pub max_buckets: usize,
+    /// Directories the bucket files are spread over, in a subdirectory of each. A temporary
+    /// directory is used if unset.
+    pub drives: Option<Vec<PathBuf>>,
+    /// Number of cells probed for a key before a bucket grows
+    pub max_search: Option<u8>,
+}
+
+impl BucketMapConfig {
+    pub fn new(max_buckets: usize) -> Self {
+        Self {
+            max_buckets,
+            ..Self::default()
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct BucketItem<T> {
+    pub pubkey: Pubkey,
+    pub ref_count: RefCount,
+    pub slot_list: Vec<T>,
+}
+
+#[derive(Debug)]
+pub struct BucketMap<T> {
+    buckets: Vec<RwLock<Option<Bucket>>>,
+    drives: Arc<Vec<PathBuf>>,
+    max_buckets_pow2: u8,
+    max_search: u8,
+    _temp_dir: Option<TempDir>,
+    _phantom: PhantomData<T>,
+}
+
+impl<T: Serialize + DeserializeOwned> BucketMap<T> {
+    pub fn new(config: BucketMapConfig) -> Self {
+        assert!(config.max_buckets.is_power_of_two());
+        let (drives, temp_dir) = match config.drives {
+            Some(drives) => {
+                let drives: Vec<_> = drives
+                    .iter()
+                    .map(|drive| drive.join(BUCKET_MAP_DIR))
+                    .collect();
+                Self::erase_previous_drives(&drives);
+                (drives, None)
+            }
+            None => {
+                let temp_dir = TempDir::new().expect("Unable to create bucket map directory");
+                (vec![temp_dir.path().to_path_buf()], Some(temp_dir))
+            }
+        };
+        assert!(!drives.is_empty());
+        Self {
+            buckets: (0..config.max_buckets).map(|_| RwLock::default()).collect(),
+            drives: Arc::new(drives),
+            max_buckets_pow2: config.max_buckets.trailing_zeros() as u8,
+            max_search: config.max_search.unwrap_or(DEFAULT_MAX_SEARCH),
+            _temp_dir: temp_dir,
+            _phantom: PhantomData,
+        }
+    }
+
+    /// Bucket files don't outlive the process that wrote them, so clear out any left behind.
+    /// Only the bucket map's own subdirectories are erased.
+    fn erase_previous_drives(drives: &[PathBuf]) {
+        for drive in drives {
+            if drive.exists() {
+                info!("Erasing bucket map directory {:?}", drive);
+                fs::remove_dir_all(drive).unwrap_or_else(|err| {
+                    panic!("Unable to erase bucket map directory {:?}: {}", drive, err)
+                });
+            }
+            fs::create_dir_all(drive).unwrap_or_else(|err| {
+                panic!("Unable to create bucket map directory {:?}: {}", drive, err)
+            });
+        }
+    }
+
+    pub fn num_buckets(&self) -> usize {
+        self.buckets.len()
+    }
+
+    /// The bucket holding `pubkey`
+    pub fn bucket_ix(&self, pubkey: &Pubkey) -> usize {
+        if self.max_buckets_pow2 == 0 {
+            return 0;
+        }
+        let mut leading = [0u8; 8];
+        leading.copy_from_slice(&pubkey.as_ref()[..8]);
+        (u64::from_be_bytes(leading) >> (64 - self.max_buckets_pow2)) as usize
+    }
+
+    fn serialize(slot_list: &[T]) -> Vec<u8> {
+        bincode::serialize(slot_list).unwrap()
+    }
+
+    fn deserialize(data: &[u8]) -> Vec<T> {
+        bincode::deserialize(data).expect("bucket map value is corrupt")
+    }
+
+    pub fn contains_key(&self, pubkey: &Pubkey) -> bool {
+        self.buckets[self.bucket_ix(pubkey)]
+            .read()
+            .unwrap()
+            .as_ref()
+            .map(|bucket| bucket.contains_key(pubkey))
+            .unwrap_or_default()
+    }
+
+    pub fn read_value(&self, pubkey: &Pubkey) -> Option<(Vec<T>, RefCount)> {
+        let bucket = self.buckets[self.bucket_ix(pubkey)].read().unwrap();
+        let (data, ref_count) = bucket.as_ref()?.read_value(pubkey)?;
+        Some((Self::deserialize(data), ref_count))
+    }
+
+    pub fn insert(&self, pubkey: &Pubkey, value: (&[T], RefCount)) {
+        let mut bucket = self.buckets[self.bucket_ix(pubkey)].write().unwrap();
+        bucket
+            .get_or_insert_with(|| Bucket::new(self.drives.clone(), self.max_search))
+            .write_value(pubkey, &Self::serialize(value.0), value.1);
+    }
+
+    /// Replace the value of `pubkey` with the result of `updatefn`, which is passed the
+    /// current value. The key is deleted if `updatefn` returns None.
+    pub fn update<F>(&self, pubkey: &Pubkey, updatefn: F)
+    where
+        F: FnOnce(Option<(Vec<T>, RefCount)>) -> Option<(Vec<T>, RefCount)>,
+    {
+        let mut bucket = self.buckets[self.bucket_ix(pubkey)].write().unwrap();
+        let bucket =
+            bucket.get_or_insert_with(|| Bucket::new(self.drives.clone(), self.max_search));
+        let current = bucket
+            .read_value(pubkey)
+            .map(|(data, ref_count)| (Self::deserialize(data), ref_count));
+        match updatefn(current) {
+            Some((slot_list, ref_count)) => {
+                bucket.write_value(pubkey, &Self::serialize(&slot_list), ref_count)
+            }
+            None => bucket.delete_key(pubkey),
+        }
+    }
+
+    pub fn delete_key(&self, pubkey: &Pubkey) {
+        if let Some(bucket) = self.buckets[self.bucket_ix(pubkey)]
+            .write()
+            .unwrap()
+            .as_mut()
+        {
+            bucket.delete_key(pubkey);
+        }
+    }
+
+    /// The keys of bucket `ix`, in no particular order
+    pub fn keys(&self, ix: usize) -> Vec<Pubkey> {
+        self.buckets[ix]
+            .read()
+            .unwrap()
+            .as_ref()
+            .map(|bucket| bucket.keys())
+            .unwrap_or_default()
+    }
+
+    /// Up to `limit` of the lowest items of bucket `ix` in `range`, sorted by pubkey
+    pub fn items_in_range<R: RangeBounds<Pubkey>>(
+        &self,
+        ix: usize,
+        range: Option<&R>,
+        limit: usize,
+    ) -> Vec<BucketItem<T>> {
+        self.buckets[ix]
+            .read()
+            .unwrap()
+            .as_ref()
+            .map(|bucket| {
+                bucket
+                    .items_in_range(range, limit)
+                    .into_iter()
+                    .map(|(pubkey, data, ref_count)| BucketItem {
+                        pubkey,
+                        ref_count,
+                        slot_list: Self::deserialize(data),
+                    })
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    /// Number of keys in bucket `ix`
+    pub fn bucket_len(&self, ix: usize) -> u64 {
+        self.buckets[ix]
+            .read()
+            .unwrap()
+            .as_ref()
+            .map(|bucket| bucket.len())
+            .unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bucket_map() {
+        let map = BucketMap::<(u64, u64)>::new(BucketMapConfig::new(4));
+        let key = Pubkey::new_unique();
+        assert_eq!(map.read_value(&key), None);
+        assert!(!map.contains_key(&key));
+
+        map.insert(&key, (&[(1, 10)], 1));
+        assert_eq!(map.read_value(&key), Some((vec![(1, 10)], 1)));
+        assert!(map.contains_key(&key));
+
+        map.update(&key, |current| {
+            let (mut slot_list, ref_count) = current.unwrap();
+            slot_list.push((2, 20));
+            Some((slot_list, ref_count + 1))
+        });
+        assert_eq!(map.read_value(&key), Some((vec![(1, 10), (2, 20)], 2)));
+        assert_eq!(map.bucket_len(map.bucket_ix(&key)), 1);
+
+        map.update(&key, |_| None);
+        assert_eq!(map.read_value(&key), None);
+
+        map.insert(&key, (&[], 0));
+        assert_eq!(map.read_value(&key), Some((vec![], 0)));
+        map.delete_key(&key);
+        assert_eq!(map.read_value(&key), None);
+        assert_eq!(map.bucket_len(map.bucket_ix(&key)), 0);
+    }
+
+    #[test]
+    fn test_bucket_map_buckets() {
+        let map = BucketMap::<u64>::new(BucketMapConfig::new(4));
+        let mut keys = vec![];
+        for leading in 0..=255u8 {
+            let mut key = [7u8; 32];
+            key[0] = leading;
+            let key = Pubkey::new(&key);
+            map.insert(&key, (&[leading as u64], 1));
+            keys.push(key);
+        }
+        assert_eq!(map.num_buckets(), 4);
+        for ix in 0..4 {
+            assert_eq!(map.bucket_len(ix), 64);
+            let mut bucket_keys = map.keys(ix);
+            bucket_keys.sort();
+            assert_eq!(bucket_keys, keys[ix * 64..(ix + 1) * 64].to_vec());
+        }
+
+        let items = map.items_in_range(1, Some(&(keys[70]..keys[80])), 2);
+        assert_eq!(
+            items,
+            vec![
+                BucketItem {
+                    pubkey: keys[70],
+                    ref_count: 1,
+                    slot_list: vec![70]
+                },
+                BucketItem {
+                    pubkey: keys[71],
+                    ref_count: 1,
+                    slot_list: vec![71]
+                },
+            ]
+        );
+        assert_eq!(
+            map.items_in_range::<std::ops::RangeFull>(3, None, usize::MAX)
+                .len(),
+            64
+        );
+
+        let single = BucketMap::<u64>::new(BucketMapConfig::new(1));
+        assert_eq!(single.bucket_ix(&keys[255]), 0);
+    }
+
+    #[test]
+    fn test_bucket_map_drives() {
+        let tmpdir = TempDir::new().unwrap();
+        let drives = vec![tmpdir.path().join("a"), tmpdir.path().join("b")];
+        let bucket_dirs: Vec<_> = drives
+            .iter()
+            .map(|drive| drive.join(BUCKET_MAP_DIR))
+            .collect();
+        fs::create_dir_all(&bucket_dirs[0]).unwrap();
+        fs::write(bucket_dirs[0].join("stale.bucket"), b"stale").unwrap();
+        // files of others next to the bucket map are left alone
+        fs::write(drives[0].join("keep"), b"keep").unwrap();
+        let map = BucketMap::<u64>::new(BucketMapConfig {
+            drives: Some(drives.clone()),
+            ..BucketMapConfig::new(2)
+        });
+        assert!(!bucket_dirs[0].join("stale.bucket").exists());
+        assert!(drives[0].join("keep").exists());
+        assert!(bucket_dirs[1].exists());
+        for _ in 0..10 {
+            map.insert(&Pubkey::new_unique(), (&[1], 1));
+        }
+        let files = bucket_dirs
+            .iter()
+            .map(|drive| fs::read_dir(drive).unwrap().count())
+            .sum::<usize>();
+        assert!(files > 0);
+    }
+}
@@ -0,0 +1,203 @@
+//! A memory-mapped file divided into fixed size cells
+//!
+//! Every cell starts with a `u64` header holding the uid of the cell's owner, or zero if the
+//! cell is free. The rest of the cell is the payload.
+
+use {
+    memmap2::MmapMut,
+    rand::{thread_rng, Rng},
+    std::{
+        fs::{remove_file, OpenOptions},
+        mem::size_of,
+        path::PathBuf,
+        ptr,
+        sync::Arc,
+    },
+};
+
+const HEADER_SIZE: u64 = size_of::<u64>() as u64;
+
+/// The uid of a free cell
+const UID_UNLOCKED: u64 = 0;
+
+#[derive(Debug, PartialEq)]
+pub enum BucketStorageError {
+    AlreadyAllocated,
+}
+
+#[derive(Debug)]
+pub struct BucketStorage {
+    path: PathBuf,
+    mmap: MmapMut,
+    cell_size: u64,
+    pub capacity_pow2: u8,
+    used: u64,
+}
+
+impl Drop for BucketStorage {
+    fn drop(&mut self) {
+        let _ = remove_file(&self.path);
+    }
+}
+
+impl BucketStorage {
+    /// Create a storage of `2^capacity_pow2` cells, each able to hold `payload_size` bytes, in a
+    /// randomly chosen drive
+    pub fn new(drives: &Arc<Vec<PathBuf>>, payload_size: u64, capacity_pow2: u8) -> Self {
+        // keep the headers 8 byte aligned
+        let cell_size = HEADER_SIZE + (payload_size + HEADER_SIZE - 1) / HEADER_SIZE * HEADER_SIZE;
+        let (mmap, path) = Self::new_map(drives, cell_size << capacity_pow2);
+        Self {
+            path,
+            mmap,
+            cell_size,
+            capacity_pow2,
+            used: 0,
+        }
+    }
+
+    /// Create a storage with room for `2^capacity_pow2` cells holding the contents of `other`.
+    /// Cells keep their index.
+    pub fn new_resized(drives: &Arc<Vec<PathBuf>>, other: &Self, capacity_pow2: u8) -> Self {
+        assert!(capacity_pow2 >= other.capacity_pow2);
+        let mut new_storage = Self::new(drives, other.payload_size(), capacity_pow2);
+        new_storage.mmap[..other.mmap.len()].copy_from_slice(&other.mmap);
+        new_storage.used = other.used;
+        new_storage
+    }
+
+    fn new_map(drives: &Arc<Vec<PathBuf>>, bytes: u64) -> (MmapMut, PathBuf) {
+        let mut rng = thread_rng();
+        let drive = &drives[rng.gen_range(0, drives.len())];
+        let path = drive.join(format!("{:032x}.bucket", rng.gen::<u128>()));
+        let file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create_new(true)
+            .open(&path)
+            .unwrap_or_else(|err| panic!("Unable to create bucket file {:?}: {}", path, err));
+        // the file is sparse, so only the cells that are written take up space on disk
+        file.set_len(bytes)
+            .unwrap_or_else(|err| panic!("Unable to size bucket file {:?}: {}", path, err));
+        let mmap = unsafe { MmapMut::map_mut(&file) }
+            .unwrap_or_else(|err| panic!("Unable to map bucket file {:?}: {}", path, err));
+        (mmap, path)
+    }
+
+    pub fn num_cells(&self) -> u64 {
+        1 << self.capacity_pow2
+    }
+
+    pub fn payload_size(&self) -> u64 {
+        self.cell_size - HEADER_SIZE
+    }
+
+    /// Number of allocated cells
+    pub fn used(&self) -> u64 {
+        self.used
+    }
+
+    fn offset(&self, ix: u64) -> usize {
+        assert!(ix < self.num_cells(), "cell {} out of range", ix);
+        (ix * self.cell_size) as usize
+    }
+
+    pub fn uid(&self, ix: u64) -> u64 {
+        let offset = self.offset(ix);
+        unsafe { ptr::read_unaligned(self.mmap[offset..].as_ptr() as *const u64) }
+    }
+
+    fn set_uid(&mut self, ix: u64, uid: u64) {
+        let offset = self.offset(ix);
+        unsafe { ptr::write_unaligned(self.mmap[offset..].as_mut_ptr() as *mut u64, uid) }
+    }
+
+    pub fn is_free(&self, ix: u64) -> bool {
+        self.uid(ix) == UID_UNLOCKED
+    }
+
+    pub fn allocate(&mut self, ix: u64, uid: u64) -> Result<(), BucketStorageError> {
+        assert!(uid != UID_UNLOCKED);
+        if !self.is_free(ix) {
+            return Err(BucketStorageError::AlreadyAllocated);
+        }
+        self.set_uid(ix, uid);
+        self.used += 1;
+        Ok(())
+    }
+
+    pub fn free(&mut self, ix: u64, uid: u64) {
+        assert_eq!(self.uid(ix), uid, "cell {} is not owned by {}", ix, uid);
+        self.set_uid(ix, UID_UNLOCKED);
+        self.used -= 1;
+    }
+
+    fn payload_range(&self, ix: u64, len: u64) -> std::ops::Range<usize> {
+        assert!(len <= self.payload_size());
+        let start = self.offset(ix) + HEADER_SIZE as usize;
+        start..start + len as usize
+    }
+
+    /// Read a plain-old-data value from the payload of cell `ix`
+    pub fn get<T: Copy>(&self, ix: u64) -> T {
+        let range = self.payload_range(ix, size_of::<T>() as u64);
+        unsafe { ptr::read_unaligned(self.mmap[range].as_ptr() as *const T) }
+    }
+
+    /// Write a plain-old-data value to the payload of cell `ix`
+    pub fn put<T: Copy>(&mut self, ix: u64, value: &T) {
+        let range = self.payload_range(ix, size_of::<T>() as u64);
+        unsafe { ptr::write_unaligned(self.mmap[range].as_mut_ptr() as *mut T, *value) }
+    }
+
+    pub fn get_bytes(&self, ix: u64, len: u64) -> &[u8] {
+        &self.mmap[self.payload_range(ix, len)]
+    }
+
+    pub fn put_bytes(&mut self, ix: u64, data: &[u8]) {
+        let range = self.payload_range(ix, data.len() as u64);
+        self.mmap[range].copy_from_slice(data);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, tempfile::TempDir};
+
+    #[test]
+    fn test_bucket_storage() {
+        let tmpdir = TempDir::new().unwrap();
+        let drives = Arc::new(vec![tmpdir.path().to_path_buf()]);
+        let mut storage = BucketStorage::new(&drives, 12, 2);
+        assert_eq!(storage.num_cells(), 4);
+        assert_eq!(storage.payload_size(), 16);
+        assert!(storage.is_free(3));
+
+        storage.allocate(3, 7).unwrap();
+        assert_eq!(
+            storage.allocate(3, 8),
+            Err(BucketStorageError::AlreadyAllocated)
+        );
+        assert_eq!(storage.uid(3), 7);
+        assert_eq!(storage.used(), 1);
+        storage.put(3, &(1u64, 2u64));
+        assert_eq!(storage.get::<(u64, u64)>(3), (1, 2));
+        storage.put_bytes(2, &[5; 16]);
+        assert_eq!(storage.get_bytes(2, 3), &[5, 5, 5]);
+
+        let resized = BucketStorage::new_resized(&drives, &storage, 3);
+        assert_eq!(resized.num_cells(), 8);
+        assert_eq!(resized.used(), 1);
+        assert_eq!(resized.uid(3), 7);
+        assert_eq!(resized.get::<(u64, u64)>(3), (1, 2));
+        assert!(resized.is_free(4));
+
+        storage.free(3, 7);
+        assert!(storage.is_free(3));
+        assert_eq!(storage.used(), 0);
+
+        drop(storage);
+        drop(resized);
+        assert_eq!(std::fs::read_dir(tmpdir.path()).unwrap().count(), 0);
+    }
+}
@@ -0,0 +1,6 @@
+#![allow(clippy::integer_arithmetic)]
+mod bucket;
+pub mod bucket_map;
+mod bucket_storage;
+
+pub type RefCount = u64;
@@ -17,7 +17,7 @@ use {
     solana_clap_utils::keypair::SignOnly,
     solana_client::rpc_response::{
         RpcAccountBalance, RpcContactInfo, RpcInflationGovernor, RpcInflationRate, RpcKeyedAccount,
-        RpcSupply, RpcVoteAccountInfo,
+        RpcPathSibling, RpcSupply, RpcTransactionProof, RpcVoteAccountInfo,
     },
     solana_sdk::{
         clock::{Epoch, Slot, UnixTimestamp},
@@ -2363,6 +2363,125 @@ impl fmt::Display for CliTransactionConfirmation {
     }
 }
 
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CliRentExemptTopUpAccount {
+    pub address: String,
+    pub lamports: u64,
+    pub rent_exempt_minimum: u64,
+    pub top_up: u64,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CliRentExemptTopUp {
+    pub accounts: Vec<CliRentExemptTopUpAccount>,
+    pub total_top_up: u64,
+    pub dry_run: bool,
+    pub signatures: Vec<String>,
+}
+
+impl QuietDisplay for CliRentExemptTopUp {}
+impl VerboseDisplay for CliRentExemptTopUp {}
+
+impl fmt::Display for CliRentExemptTopUp {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.accounts.is_empty() {
+            return writeln!(f, "No accounts need topping up");
+        }
+        writeln!(f)?;
+        writeln!(
+            f,
+            "{}",
+            style(format!(
+                "{:<44}  {:>22}  {:>22}",
+                "Address", "Balance", "Top-up"
+            ))
+            .bold()
+        )?;
+        for account in &self.accounts {
+            writeln!(
+                f,
+                "{:<44}  {:>22}  {:>22}",
+                account.address,
+                build_balance_message(account.lamports, false, true),
+                build_balance_message(account.top_up, false, true),
+            )?;
+        }
+        writeln!(f)?;
+        writeln_name_value(
+            f,
+            "Total top-up:",
+            &build_balance_message(self.total_top_up, false, true),
+        )?;
+        if self.dry_run {
+            writeln!(f, "Dry run, nothing was transferred")?;
+        }
+        for signature in &self.signatures {
+            writeln_name_value(f, "Signature:", signature)?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CliTransactionProof {
+    #[serde(flatten)]
+    pub proof: RpcTransactionProof,
+    /// Hash of the entry, recomputed from the signature and the Merkle path
+    pub entry_hash: String,
+    /// Blockhash the proof leads to through PoH
+    pub proof_blockhash: String,
+    /// Blockhash of the finalized block in `slot`
+    pub blockhash: String,
+}
+
+impl QuietDisplay for CliTransactionProof {}
+impl VerboseDisplay for CliTransactionProof {}
+
+impl fmt::Display for CliTransactionProof {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let proof = &self.proof;
+        writeln!(f)?;
+        writeln_name_value(f, "Signature:", &proof.signature)?;
+        writeln_name_value(f, "Slot:", &proof.slot.to_string())?;
+        writeln_name_value(f, "Entry:", &proof.entry_index.to_string())?;
+        writeln!(f, "Merkle path to the entry's transactions root:")?;
+        for sibling in &proof.path {
+            match sibling {
+                RpcPathSibling::Left(hash) => writeln!(f, "  left:  {}", hash)?,
+                RpcPathSibling::Right(hash) => writeln!(f, "  right: {}", hash)?,
+            }
+        }
+        writeln!(f, "PoH from the previous entry:")?;
+        writeln!(f, "  start: {}", proof.start_hash)?;
+        writeln!(
+            f,
+            "  {} hash(es) and the transactions root: {}",
+            proof.num_hashes, self.entry_hash
+        )?;
+        writeln!(f, "PoH from the entry to the blockhash:")?;
+        for segment in &proof.segments {
+            match &segment.mixin {
+                Some(mixin) => writeln!(f, "  {} hash(es), mixin {}", segment.num_hashes, mixin)?,
+                None => writeln!(f, "  {} hash(es)", segment.num_hashes)?,
+            }
+        }
+        writeln_name_value(f, "Proof blockhash:", &self.proof_blockhash)?;
+        writeln_name_value(f, "Block blockhash:", &self.blockhash)?;
+        if self.proof_blockhash == self.blockhash {
+            writeln!(f, "Proof verified")
+        } else {
+            writeln!(
+                f,
+                "Proof does not lead to the blockhash of slot {}",
+                proof.slot
+            )
+        }
+    }
+}
+
 #[derive(Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct CliGossipNode {
@@ -339,6 +339,7 @@ pub enum CliCommand {
         use_lamports_unit: bool,
     },
     Confirm(Signature),
+    ConfirmProof(Signature),
     CreateAddressWithSeed {
         from_pubkey: Option<Pubkey>,
         seed: String,
@@ -351,6 +352,13 @@ pub enum CliCommand {
         output_file: Option<String>,
         use_lamports_unit: bool,
     },
+    TopUpRentExempt {
+        accounts: Vec<Pubkey>,
+        owner: Option<Pubkey>,
+        from: SignerIndex,
+        fee_payer: SignerIndex,
+        dry_run: bool,
+    },
     Transfer {
         amount: SpendAmount,
         to: Pubkey,
@@ -820,7 +828,11 @@ pub fn parse_command(
         ("balance", Some(matches)) => parse_balance(matches, default_signer, wallet_manager),
         ("confirm", Some(matches)) => match matches.value_of("signature").unwrap().parse() {
             Ok(signature) => Ok(CliCommandInfo {
-                command: CliCommand::Confirm(signature),
+                command: if matches.is_present("proof") {
+                    CliCommand::ConfirmProof(signature)
+                } else {
+                    CliCommand::Confirm(signature)
+                },
                 signers: vec![],
             }),
             _ => Err(CliError::BadParameter("Invalid signature".to_string())),
@@ -836,6 +848,9 @@ pub fn parse_command(
                 signers: vec![],
             })
         }
+        ("top-up-rent-exempt", Some(matches)) => {
+            parse_top_up_rent_exempt(matches, default_signer, wallet_manager)
+        }
         ("transfer", Some(matches)) => parse_transfer(matches, default_signer, wallet_manager),
         //
         ("", None) => {
@@ -1466,9 +1481,27 @@ pub fn process_command(config: &CliConfig) -> ProcessResult {
         } => process_balance(&rpc_client, config, pubkey, *use_lamports_unit),
         // Confirm the last client transaction by signature
         CliCommand::Confirm(signature) => process_confirm(&rpc_client, config, signature),
+        CliCommand::ConfirmProof(signature) => {
+            process_confirm_proof(&rpc_client, config, signature)
+        }
         CliCommand::DecodeTransaction(transaction) => {
             process_decode_transaction(config, transaction)
         }
+        CliCommand::TopUpRentExempt {
+            accounts,
+            owner,
+            from,
+            fee_payer,
+            dry_run,
+        } => process_top_up_rent_exempt(
+            &rpc_client,
+            config,
+            accounts,
+            owner.as_ref(),
+            *from,
+            *fee_payer,
+            *dry_run,
+        ),
         CliCommand::ResolveSigner(path) => {
             if let Some(path) = path {
                 Ok(path.to_string())
@@ -1780,6 +1813,19 @@ mod tests {
                 signers: vec![],
             }
         );
+        let test_confirm_proof = test_commands.clone().get_matches_from(vec![
+            "test",
+            "confirm",
+            &signature_string,
+            "--proof",
+        ]);
+        assert_eq!(
+            parse_command(&test_confirm_proof, &default_signer, &mut None).unwrap(),
+            CliCommandInfo {
+                command: CliCommand::ConfirmProof(signature),
+                signers: vec![],
+            }
+        );
         let test_bad_signature = test_commands
             .clone()
             .get_matches_from(vec!["test", "confirm", "deadbeef"]);
@@ -2228,6 +2274,74 @@ mod tests {
         assert!(process_command(&config).is_err());
     }
 
+    #[test]
+    fn test_parse_top_up_rent_exempt() {
+        let test_commands = get_clap_app("test", "desc", "version");
+
+        let default_keypair = Keypair::new();
+        let default_keypair_file = make_tmp_path("keypair_file");
+        write_keypair_file(&default_keypair, &default_keypair_file).unwrap();
+        let default_signer = DefaultSigner::new("", &default_keypair_file);
+
+        let account0 = solana_sdk::pubkey::new_rand();
+        let account1 = solana_sdk::pubkey::new_rand();
+        let test_top_up = test_commands.clone().get_matches_from(vec![
+            "test",
+            "top-up-rent-exempt",
+            &account0.to_string(),
+            &account1.to_string(),
+        ]);
+        assert_eq!(
+            parse_command(&test_top_up, &default_signer, &mut None).unwrap(),
+            CliCommandInfo {
+                command: CliCommand::TopUpRentExempt {
+                    accounts: vec![account0, account1],
+                    owner: None,
+                    from: 0,
+                    fee_payer: 0,
+                    dry_run: false,
+                },
+                signers: vec![read_keypair_file(&default_keypair_file).unwrap().into()],
+            }
+        );
+
+        let owner = solana_sdk::pubkey::new_rand();
+        let test_top_up = test_commands.clone().get_matches_from(vec![
+            "test",
+            "top-up-rent-exempt",
+            "--owner",
+            &owner.to_string(),
+            "--dry-run",
+        ]);
+        assert_eq!(
+            parse_command(&test_top_up, &default_signer, &mut None).unwrap(),
+            CliCommandInfo {
+                command: CliCommand::TopUpRentExempt {
+                    accounts: vec![],
+                    owner: Some(owner),
+                    from: 0,
+                    fee_payer: 0,
+                    dry_run: true,
+                },
+                signers: vec![read_keypair_file(&default_keypair_file).unwrap().into()],
+            }
+        );
+
+        // accounts and --owner are exclusive, and one of them is required
+        let test_top_up = test_commands.clone().get_matches_from_safe(vec![
+            "test",
+            "top-up-rent-exempt",
+            &account0.to_string(),
+            "--owner",
+            &owner.to_string(),
+        ]);
+        assert!(test_top_up.is_err());
+        let test_top_up = test_commands
+            .clone()
+            .get_matches_from_safe(vec!["test", "top-up-rent-exempt"]);
+        assert!(test_top_up.is_err());
+    }
+
     #[test]
     fn test_parse_transfer_subcommand() {
         let test_commands = get_clap_app("test", "desc", "version");
@@ -1,4 +1,8 @@
 use crate::{
+    checks::{
+        check_account_for_balance_with_commitment, check_account_for_multiple_fees_with_commitment,
+        check_account_for_spend_multiple_fees_with_commitment,
+    },
     cli::{
         log_instruction_custom_error, request_and_confirm_airdrop, CliCommand, CliCommandInfo,
         CliConfig, CliError, ProcessResult,
@@ -7,7 +11,7 @@ use crate::{
     nonce::check_nonce_account,
     spend_utils::{resolve_spend_tx_and_check_account_balances, SpendAmount},
 };
-use clap::{value_t_or_exit, App, Arg, ArgMatches, SubCommand};
+use clap::{value_t_or_exit, App, Arg, ArgGroup, ArgMatches, SubCommand};
 use solana_account_decoder::{UiAccount, UiAccountEncoding};
 use solana_clap_utils::{
     fee_payer::*,
@@ -19,27 +23,32 @@ use solana_clap_utils::{
     offline::*,
 };
 use solana_cli_output::{
-    display::build_balance_message, return_signers_with_config, CliAccount,
-    CliSignatureVerificationStatus, CliTransaction, CliTransactionConfirmation, OutputFormat,
-    ReturnSignersConfig,
+    display::build_balance_message, return_signers_with_config, CliAccount, CliRentExemptTopUp,
+    CliRentExemptTopUpAccount, CliSignatureVerificationStatus, CliTransaction,
+    CliTransactionConfirmation, CliTransactionProof, OutputFormat, ReturnSignersConfig,
 };
 use solana_client::{
-    blockhash_query::BlockhashQuery, nonce_utils, rpc_client::RpcClient,
-    rpc_config::RpcTransactionConfig, rpc_response::RpcKeyedAccount,
+    blockhash_query::BlockhashQuery,
+    nonce_utils,
+    rpc_client::RpcClient,
+    rpc_config::{RpcBlockConfig, RpcRentPayingAccountsConfig, RpcTransactionConfig},
+    rpc_request::MAX_MULTIPLE_ACCOUNTS,
+    rpc_response::RpcKeyedAccount,
 };
 use solana_remote_wallet::remote_wallet::RemoteWalletManager;
 use solana_sdk::{
     commitment_config::CommitmentConfig,
     message::Message,
-    pubkey::Pubkey,
+    native_token::lamports_to_sol,
+    pubkey::{ParsePubkeyError, Pubkey},
     signature::Signature,
     stake,
     system_instruction::{self, SystemError},
     system_program,
     transaction::Transaction,
 };
-use solana_transaction_status::{EncodedTransaction, UiTransactionEncoding};
-use std::{fmt::Write as FmtWrite, fs::File, io::Write, sync::Arc};
+use solana_transaction_status::{EncodedTransaction, TransactionDetails, UiTransactionEncoding};
+use std::{fmt::Write as FmtWrite, fs::File, io::Write, str::FromStr, sync::Arc};
 
 pub trait WalletSubCommands {
     fn wallet_subcommands(self) -> Self;
@@ -129,6 +138,16 @@ impl WalletSubCommands for App<'_, '_> {
                         .required(true)
                         .help("The transaction signature to confirm"),
                 )
+                .arg(
+                    Arg::with_name("proof")
+                        .long("proof")
+                        .takes_value(false)
+                        .help(
+                            "Show a proof that the transaction is included in its finalized \
+                             block, from the signature through the entry's Merkle root and PoH \
+                             to the blockhash",
+                        ),
+                )
                 .after_help(// Formatted specifically for the manually-indented heredoc string
                    "Note: This will show more detailed information for finalized transactions with verbose mode (-v/--verbose).\
                   \n\
@@ -267,6 +286,41 @@ impl WalletSubCommands for App<'_, '_> {
                 .arg(memo_arg())
                 .arg(fee_payer_arg()),
         )
+        .subcommand(
+            SubCommand::with_name("top-up-rent-exempt")
+                .about("Transfer to rent-paying accounts the lamports they lack to be rent exempt")
+                .arg(
+                    pubkey!(Arg::with_name("accounts")
+                        .index(1)
+                        .value_name("ACCOUNT_ADDRESS")
+                        .multiple(true),
+                        "Accounts to top up. "),
+                )
+                .arg(
+                    pubkey!(Arg::with_name("owner")
+                        .long("owner")
+                        .value_name("PROGRAM_ID"),
+                        "Top up every rent-paying account owned by this program. "),
+                )
+                .group(
+                    ArgGroup::with_name("top_up_accounts")
+                        .args(&["accounts", "owner"])
+                        .required(true),
+                )
+                .arg(
+                    pubkey!(Arg::with_name("from")
+                        .long("from")
+                        .value_name("FROM_ADDRESS"),
+                        "Source account of funds (if different from client local account). "),
+                )
+                .arg(
+                    Arg::with_name("dry_run")
+                        .long("dry-run")
+                        .takes_value(false)
+                        .help("Only show the accounts and amounts that would be topped up"),
+                )
+                .arg(fee_payer_arg()),
+        )
     }
 }
 
@@ -435,6 +489,31 @@ pub fn parse_transfer(
     })
 }
 
+pub fn parse_top_up_rent_exempt(
+    matches: &ArgMatches<'_>,
+    default_signer: &DefaultSigner,
+    wallet_manager: &mut Option<Arc<RemoteWalletManager>>,
+) -> Result<CliCommandInfo, CliError> {
+    let accounts =
+        pubkeys_of_multiple_signers(matches, "accounts", wallet_manager)?.unwrap_or_default();
+    let owner = pubkey_of_signer(matches, "owner", wallet_manager)?;
+    let (fee_payer, fee_payer_pubkey) = signer_of(matches, FEE_PAYER_ARG.name, wallet_manager)?;
+    let (from, from_pubkey) = signer_of(matches, "from", wallet_manager)?;
+    let signer_info =
+        default_signer.generate_unique_signers(vec![fee_payer, from], matches, wallet_manager)?;
+
+    Ok(CliCommandInfo {
+        command: CliCommand::TopUpRentExempt {
+            accounts,
+            owner,
+            from: signer_info.index_of(from_pubkey).unwrap(),
+            fee_payer: signer_info.index_of(fee_payer_pubkey).unwrap(),
+            dry_run: matches.is_present("dry_run"),
+        },
+        signers: signer_info.signers,
+    })
+}
+
 pub fn process_show_account(
     rpc_client: &RpcClient,
     config: &CliConfig,
@@ -593,6 +672,50 @@ pub fn process_confirm(
     }
 }
 
+pub fn process_confirm_proof(
+    rpc_client: &RpcClient,
+    config: &CliConfig,
+    signature: &Signature,
+) -> ProcessResult {
+    let rpc_proof = rpc_client
+        .get_transaction_proof(signature)
+        .map_err(|err| {
+            CliError::RpcRequestError(format!("Unable to get transaction proof: {}", err))
+        })?
+        .ok_or_else(|| {
+            CliError::RpcRequestError(format!(
+                "Transaction {} not found in a finalized block",
+                signature
+            ))
+        })?;
+    let proof = rpc_proof
+        .parse()
+        .ok_or_else(|| CliError::RpcRequestError("Malformed transaction proof".to_string()))?;
+    if proof.signature != *signature {
+        return Err(CliError::RpcRequestError(format!(
+            "Transaction proof is for {}, not {}",
+            proof.signature, signature
+        ))
+        .into());
+    }
+    let block = rpc_client.get_block_with_config(
+        proof.slot,
+        RpcBlockConfig {
+            transaction_details: Some(TransactionDetails::None),
+            rewards: Some(false),
+            commitment: Some(CommitmentConfig::finalized()),
+            ..RpcBlockConfig::default()
+        },
+    )?;
+    let cli_proof = CliTransactionProof {
+        proof: rpc_proof,
+        entry_hash: proof.entry_hash().to_string(),
+        proof_blockhash: proof.blockhash().to_string(),
+        blockhash: block.blockhash,
+    };
+    Ok(config.output_format.formatted_string(&cli_proof))
+}
+
 #[allow(clippy::unnecessary_wraps)]
 pub fn process_decode_transaction(config: &CliConfig, transaction: &Transaction) -> ProcessResult {
     let sigverify_status = CliSignatureVerificationStatus::verify_transaction(transaction);
@@ -623,6 +746,147 @@ pub fn process_create_address_with_seed(
     Ok(address.to_string())
 }
 
+// Keeps each transaction well under the packet size
+const MAX_TOP_UPS_PER_TRANSACTION: usize = 20;
+
+pub fn process_top_up_rent_exempt(
+    rpc_client: &RpcClient,
+    config: &CliConfig,
+    accounts: &[Pubkey],
+    owner: Option<&Pubkey>,
+    from: SignerIndex,
+    fee_payer: SignerIndex,
+    dry_run: bool,
+) -> ProcessResult {
+    let mut top_ups = vec![];
+    if let Some(owner) = owner {
+        let rent_paying_accounts = rpc_client
+            .get_rent_paying_accounts_with_config(
+                owner,
+                RpcRentPayingAccountsConfig {
+                    commitment: Some(config.commitment),
+                },
+            )?
+            .value;
+        for account in rent_paying_accounts {
+            top_ups.push((
+                Pubkey::from_str(&account.address)?,
+                account.lamports,
+                account.rent_exempt_minimum,
+            ));
+        }
+    }
+    for chunk in accounts.chunks(MAX_MULTIPLE_ACCOUNTS) {
+        let fetched_accounts = rpc_client
+            .get_multiple_accounts_with_commitment(chunk, config.commitment)?
+            .value;
+        for (pubkey, account) in chunk.iter().zip(fetched_accounts) {
+            let account = account.ok_or_else(|| format!("Account {} does not exist", pubkey))?;
+            let rent_exempt_minimum =
+                rpc_client.get_minimum_balance_for_rent_exemption(account.data.len())?;
+            if account.executable || account.lamports >= rent_exempt_minimum {
+                continue;
+            }
+            top_ups.push((*pubkey, account.lamports, rent_exempt_minimum));
+        }
+    }
+
+    let top_ups: Vec<_> = top_ups
+        .into_iter()
+        .map(
+            |(pubkey, lamports, rent_exempt_minimum)| CliRentExemptTopUpAccount {
+                address: pubkey.to_string(),
+                lamports,
+                rent_exempt_minimum,
+                top_up: rent_exempt_minimum.saturating_sub(lamports),
+            },
+        )
+        .collect();
+    let mut output = CliRentExemptTopUp {
+        total_top_up: top_ups.iter().map(|account| account.top_up).sum(),
+        accounts: top_ups,
+        dry_run,
+        signatures: vec![],
+    };
+    if dry_run || output.accounts.is_empty() {
+        return Ok(config.output_format.formatted_string(&output));
+    }
+
+    let from = config.signers[from];
+    let fee_payer = config.signers[fee_payer];
+    let recent_blockhash = rpc_client.get_latest_blockhash()?;
+    let messages = output
+        .accounts
+        .chunks(MAX_TOP_UPS_PER_TRANSACTION)
+        .map(|chunk| {
+            let ixs = chunk
+                .iter()
+                .map(|account| {
+                    Ok(system_instruction::transfer(
+                        &from.pubkey(),
+                        &Pubkey::from_str(&account.address)?,
+                        account.top_up,
+                    ))
+                })
+                .collect::<Result<Vec<_>, ParsePubkeyError>>()?;
+            Ok(Message::new(&ixs, Some(&fee_payer.pubkey())))
+        })
+        .collect::<Result<Vec<_>, ParsePubkeyError>>()?;
+    let message_refs: Vec<_> = messages.iter().collect();
+    if from.pubkey() == fee_payer.pubkey() {
+        check_account_for_spend_multiple_fees_with_commitment(
+            rpc_client,
+            &from.pubkey(),
+            output.total_top_up,
+            &recent_blockhash,
+            &message_refs,
+            config.commitment,
+        )?;
+    } else {
+        if !check_account_for_balance_with_commitment(
+            rpc_client,
+            &from.pubkey(),
+            output.total_top_up,
+            config.commitment,
+        )? {
+            return Err(CliError::InsufficientFundsForSpend(
+                lamports_to_sol(output.total_top_up),
+                from.pubkey(),
+            )
+            .into());
+        }
+        check_account_for_multiple_fees_with_commitment(
+            rpc_client,
+            &fee_payer.pubkey(),
+            &recent_blockhash,
+            &message_refs,
+            config.commitment,
+        )?;
+    }
+
+    for message in messages {
+        // Confirming every transaction can outlast a single blockhash
+        let result = rpc_client.get_latest_blockhash().and_then(|blockhash| {
+            let mut tx = Transaction::new_unsigned(message);
+            tx.try_sign(&config.signers, blockhash)?;
+            rpc_client.send_and_confirm_transaction_with_spinner(&tx)
+        });
+        match result {
+            Ok(signature) => output.signatures.push(signature.to_string()),
+            Err(err) if output.signatures.is_empty() => return Err(err.into()),
+            Err(err) => {
+                return Err(format!(
+                    "{}; top-up transactions already confirmed: {}",
+                    err,
+                    output.signatures.join(", ")
+                )
+                .into())
+            }
+        }
+    }
+    Ok(config.output_format.formatted_string(&output))
+}
+
 #[allow(clippy::too_many_arguments)]
 pub fn process_transfer(
     rpc_client: &RpcClient,
@@ -1820,6 +1820,44 @@ impl RpcClient {
         )
     }
 
+    /// Fetch the balances of `address` before and after each of the most recent transactions
+    /// that touched it, newest first
+    pub fn get_account_history(
+        &self,
+        address: &Pubkey,
+    ) -> ClientResult<Vec<RpcAccountHistoryEntry>> {
+        self.get_account_history_with_config(address, RpcAccountHistoryConfig::default())
+    }
+
+    pub fn get_account_history_with_config(
+        &self,
+        address: &Pubkey,
+        config: RpcAccountHistoryConfig,
+    ) -> ClientResult<Vec<RpcAccountHistoryEntry>> {
+        let commitment = config.commitment.unwrap_or_default();
+        let commitment = self.maybe_map_commitment(commitment)?;
+        let config = RpcAccountHistoryConfig {
+            commitment: Some(commitment),
+            ..config
+        };
+        self.send(
+            RpcRequest::GetAccountHistory,
+            json!([address.to_string(), config]),
+        )
+    }
+
+    /// Fetch a proof that the transaction with `signature` is included in the finalized block
+    /// it landed in, or None if it has not been finalized
+    pub fn get_transaction_proof(
+        &self,
+        signature: &Signature,
+    ) -> ClientResult<Option<RpcTransactionProof>> {
+        self.send(
+            RpcRequest::GetTransactionProof,
+            json!([signature.to_string()]),
+        )
+    }
+
     #[deprecated(
         since = "1.7.0",
         note = "Please use RpcClient::get_transaction() instead"
@@ -2152,6 +2190,30 @@ impl RpcClient {
         )
     }
 
+    /// Fetch a proof that the account of `pubkey` is included in the accounts hash of the node's
+    /// last snapshot, whose slot is the response context slot, or None if the account does not
+    /// exist. The node must have been started with `--enable-rpc-account-proofs`.
+    pub fn get_account_proof(&self, pubkey: &Pubkey) -> RpcResult<Option<RpcAccountProof>> {
+        self.send(RpcRequest::GetAccountProof, json!([pubkey.to_string()]))
+    }
+
+    /// Fetch the accounts owned by the program `owner` that are charged rent, ordered by address
+    pub fn get_rent_paying_accounts_with_config(
+        &self,
+        owner: &Pubkey,
+        config: RpcRentPayingAccountsConfig,
+    ) -> RpcResult<Vec<RpcRentPayingAccount>> {
+        let commitment = config.commitment.unwrap_or_default();
+        let commitment = self.maybe_map_commitment(commitment)?;
+        let config = RpcRentPayingAccountsConfig {
+            commitment: Some(commitment),
+        };
+        self.send(
+            RpcRequest::GetRentPayingAccounts,
+            json!([owner.to_string(), config]),
+        )
+    }
+
     pub fn get_program_accounts(&self, pubkey: &Pubkey) -> ClientResult<Vec<(Pubkey, Account)>> {
         self.get_program_accounts_with_config(
             pubkey,
@@ -13,6 +13,8 @@ pub enum RpcRequest {
     },
     DeregisterNode,
     GetAccountInfo,
+    GetAccountProof,
+    GetAccountHistory,
     GetBalance,
     GetBlock,
     GetBlockHeight,
@@ -40,6 +42,7 @@ pub enum RpcRequest {
         note = "Please use RpcRequest::GetTransaction instead"
     )]
     GetConfirmedTransaction,
+    GetDuplicateSlotProofs,
     GetEpochInfo,
     GetEpochSchedule,
     #[deprecated(
@@ -79,6 +82,7 @@ pub enum RpcRequest {
     )]
     GetRecentBlockhash,
     GetRecentPerformanceSamples,
+    GetRentPayingAccounts,
     GetSnapshotSlot,
     GetSignaturesForAddress,
     GetSignatureStatuses,
@@ -96,6 +100,7 @@ pub enum RpcRequest {
     GetTokenAccountsByOwner,
     GetTokenSupply,
     GetTransaction,
+    GetTransactionProof,
     GetTransactionCount,
     GetVersion,
     GetVoteAccounts,
@@ -115,6 +120,8 @@ impl fmt::Display for RpcRequest {
             RpcRequest::Custom { method } => method,
             RpcRequest::DeregisterNode => "deregisterNode",
             RpcRequest::GetAccountInfo => "getAccountInfo",
+            RpcRequest::GetAccountProof => "getAccountProof",
+            RpcRequest::GetAccountHistory => "getAccountHistory",
             RpcRequest::GetBalance => "getBalance",
             RpcRequest::GetBlock => "getBlock",
             RpcRequest::GetBlockHeight => "getBlockHeight",
@@ -128,6 +135,7 @@ impl fmt::Display for RpcRequest {
             RpcRequest::GetConfirmedBlocksWithLimit => "getConfirmedBlocksWithLimit",
             RpcRequest::GetConfirmedSignaturesForAddress2 => "getConfirmedSignaturesForAddress2",
             RpcRequest::GetConfirmedTransaction => "getConfirmedTransaction",
+            RpcRequest::GetDuplicateSlotProofs => "getDuplicateSlotProofs",
             RpcRequest::GetEpochInfo => "getEpochInfo",
             RpcRequest::GetEpochSchedule => "getEpochSchedule",
             RpcRequest::GetFeeCalculatorForBlockhash => "getFeeCalculatorForBlockhash",
@@ -151,6 +159,7 @@ impl fmt::Display for RpcRequest {
             RpcRequest::GetProgramAccounts => "getProgramAccounts",
             RpcRequest::GetRecentBlockhash => "getRecentBlockhash",
             RpcRequest::GetRecentPerformanceSamples => "getRecentPerformanceSamples",
+            RpcRequest::GetRentPayingAccounts => "getRentPayingAccounts",
             RpcRequest::GetSnapshotSlot => "getSnapshotSlot",
             RpcRequest::GetSignaturesForAddress => "getSignaturesForAddress",
             RpcRequest::GetSignatureStatuses => "getSignatureStatuses",
@@ -168,6 +177,7 @@ impl fmt::Display for RpcRequest {
             RpcRequest::GetTokenAccountsByOwner => "getTokenAccountsByOwner",
             RpcRequest::GetTokenSupply => "getTokenSupply",
             RpcRequest::GetTransaction => "getTransaction",
+            RpcRequest::GetTransactionProof => "getTransactionProof",
             RpcRequest::GetTransactionCount => "getTransactionCount",
             RpcRequest::GetVersion => "getVersion",
             RpcRequest::GetVoteAccounts => "getVoteAccounts",
@@ -188,10 +198,12 @@ pub const MAX_GET_SIGNATURE_STATUSES_QUERY_ITEMS: usize = 256;
 pub const MAX_GET_CONFIRMED_SIGNATURES_FOR_ADDRESS_SLOT_RANGE: u64 = 10_000;
 pub const MAX_GET_CONFIRMED_BLOCKS_RANGE: u64 = 500_000;
 pub const MAX_GET_CONFIRMED_SIGNATURES_FOR_ADDRESS2_LIMIT: usize = 1_000;
+pub const MAX_GET_ACCOUNT_HISTORY_LIMIT: usize = 100;
 pub const MAX_MULTIPLE_ACCOUNTS: usize = 100;
 pub const NUM_LARGEST_ACCOUNTS: usize = 20;
 pub const MAX_GET_PROGRAM_ACCOUNT_FILTERS: usize = 4;
 pub const MAX_GET_SLOT_LEADERS: usize = 5000;
+pub const MAX_GET_DUPLICATE_SLOT_PROOFS: usize = 100;
 
 // Validators that are this number of slots behind are considered delinquent
 pub const DELINQUENT_VALIDATOR_SLOT_DISTANCE: u64 = 128;
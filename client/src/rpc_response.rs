@@ -1,11 +1,15 @@
 use {
     crate::client_error,
-    solana_account_decoder::{parse_token::UiTokenAmount, UiAccount},
+    solana_account_decoder::{parse_token::UiTokenAmount, UiAccount, UiAccountData},
+    solana_entry::transaction_proof::{PohSegment, TransactionProof},
+    solana_merkle_tree::PathSibling,
     solana_sdk::{
+        account_proof::{AccountProof, MerkleProofLevel},
         clock::{Epoch, Slot, UnixTimestamp},
         fee_calculator::{FeeCalculator, FeeRateGovernor},
         hash::Hash,
         inflation::Inflation,
+        signature::Signature,
         transaction::{Result, TransactionError},
     },
     solana_transaction_status::{
@@ -352,6 +356,20 @@ pub struct RpcAccountBalance {
     pub lamports: u64,
 }
 
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct RpcRentPayingAccount {
+    pub address: String,
+    pub owner: String,
+    pub lamports: u64,
+    pub space: u64,
+    /// Balance at which the account would be rent exempt
+    pub rent_exempt_minimum: u64,
+    pub rent_per_epoch: u64,
+    /// Epochs of rent the balance pays for before the account is deleted
+    pub epochs_until_deletion: u64,
+}
+
 #[derive(Serialize, Deserialize, Clone, Debug)]
 #[serde(rename_all = "camelCase")]
 pub struct RpcSupply {
@@ -378,6 +396,158 @@ pub struct RpcStakeActivation {
     pub inactive: u64,
 }
 
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct RpcMerkleProofLevel {
+    pub index: usize,
+    pub siblings: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct RpcAccountProof {
+    pub pubkey: String,
+    pub slot: Slot,
+    pub account_hash: String,
+    pub accounts_hash: String,
+    pub levels: Vec<RpcMerkleProofLevel>,
+}
+
+impl RpcAccountProof {
+    pub fn new(proof: &AccountProof, accounts_hash: &Hash) -> Self {
+        Self {
+            pubkey: proof.pubkey.to_string(),
+            slot: proof.slot,
+            account_hash: proof.account_hash.to_string(),
+            accounts_hash: accounts_hash.to_string(),
+            levels: proof
+                .levels
+                .iter()
+                .map(|level| RpcMerkleProofLevel {
+                    index: level.index,
+                    siblings: level.siblings.iter().map(Hash::to_string).collect(),
+                })
+                .collect(),
+        }
+    }
+
+    /// The proof and the accounts hash it leads to, or None if any field fails to parse
+    pub fn parse(&self) -> Option<(AccountProof, Hash)> {
+        let levels = self
+            .levels
+            .iter()
+            .map(|level| {
+                Some(MerkleProofLevel {
+                    index: level.index,
+                    siblings: level
+                        .siblings
+                        .iter()
+                        .map(|sibling| sibling.parse().ok())
+                        .collect::<Option<_>>()?,
+                })
+            })
+            .collect::<Option<_>>()?;
+        let proof = AccountProof {
+            pubkey: self.pubkey.parse().ok()?,
+            slot: self.slot,
+            account_hash: self.account_hash.parse().ok()?,
+            levels,
+        };
+        Some((proof, self.accounts_hash.parse().ok()?))
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub enum RpcPathSibling {
+    Left(String),
+    Right(String),
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct RpcPohSegment {
+    pub num_hashes: u64,
+    pub mixin: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct RpcTransactionProof {
+    pub signature: String,
+    pub slot: Slot,
+    pub entry_index: usize,
+    pub start_hash: String,
+    pub num_hashes: u64,
+    pub path: Vec<RpcPathSibling>,
+    pub segments: Vec<RpcPohSegment>,
+}
+
+impl From<&TransactionProof> for RpcTransactionProof {
+    fn from(proof: &TransactionProof) -> Self {
+        Self {
+            signature: proof.signature.to_string(),
+            slot: proof.slot,
+            entry_index: proof.entry_index,
+            start_hash: proof.start_hash.to_string(),
+            num_hashes: proof.num_hashes,
+            path: proof
+                .path
+                .iter()
+                .map(|sibling| match sibling {
+                    PathSibling::Left(hash) => RpcPathSibling::Left(hash.to_string()),
+                    PathSibling::Right(hash) => RpcPathSibling::Right(hash.to_string()),
+                })
+                .collect(),
+            segments: proof
+                .segments
+                .iter()
+                .map(|segment| RpcPohSegment {
+                    num_hashes: segment.num_hashes,
+                    mixin: segment.mixin.as_ref().map(Hash::to_string),
+                })
+                .collect(),
+        }
+    }
+}
+
+impl RpcTransactionProof {
+    /// The proof, or None if any field fails to parse
+    pub fn parse(&self) -> Option<TransactionProof> {
+        let path = self
+            .path
+            .iter()
+            .map(|sibling| match sibling {
+                RpcPathSibling::Left(hash) => hash.parse().ok().map(PathSibling::Left),
+                RpcPathSibling::Right(hash) => hash.parse().ok().map(PathSibling::Right),
+            })
+            .collect::<Option<_>>()?;
+        let segments = self
+            .segments
+            .iter()
+            .map(|segment| {
+                let mixin = match &segment.mixin {
+                    Some(mixin) => Some(mixin.parse().ok()?),
+                    None => None,
+                };
+                Some(PohSegment {
+                    num_hashes: segment.num_hashes,
+                    mixin,
+                })
+            })
+            .collect::<Option<_>>()?;
+        Some(TransactionProof {
+            signature: self.signature.parse::<Signature>().ok()?,
+            slot: self.slot,
+            entry_index: self.entry_index,
+            start_hash: self.start_hash.parse().ok()?,
+            num_hashes: self.num_hashes,
+            path,
+            segments,
+        })
+    }
+}
+
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
 #[serde(rename_all = "camelCase")]
 pub struct RpcTokenAccountBalance {
@@ -397,6 +567,26 @@ pub struct RpcConfirmedTransactionStatusWithSignature {
     pub confirmation_status: Option<TransactionConfirmationStatus>,
 }
 
+/// The state of an account around one of the transactions that touched it
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RpcAccountHistoryEntry {
+    pub signature: String,
+    pub slot: Slot,
+    pub err: Option<TransactionError>,
+    pub block_time: Option<UnixTimestamp>,
+    pub pre_lamports: Option<u64>,
+    pub post_lamports: Option<u64>,
+    /// Only set for token accounts
+    pub token_mint: Option<String>,
+    pub pre_token_amount: Option<UiTokenAmount>,
+    pub post_token_amount: Option<UiTokenAmount>,
+    /// Hash of the account after the transaction, if the node recorded the account write
+    pub hash: Option<String>,
+    /// Data of the account after the transaction, if requested and recorded by the node
+    pub data: Option<UiAccountData>,
+}
+
 #[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct RpcPerfSample {
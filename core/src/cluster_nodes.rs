@@ -118,15 +118,35 @@ impl ClusterNodes<BroadcastStage> {
 }
 
 impl ClusterNodes<RetransmitStage> {
-    pub(crate) fn get_retransmit_peers(
-        &self,
-        shred_seed: [u8; 32],
-        fanout: usize,
-        slot_leader: Option<Pubkey>,
-    ) -> (
-        Vec<&ContactInfo>, // neighbors
-        Vec<&ContactInfo>, // children
-    ) {
+    // Cluster nodes derived from the stake distribution alone, as if every
+    // staked node were present in gossip. These have no contact-info and so
+    // can only be used to compute the turbine tree.
+    fn new_from_stakes(stakes: &HashMap<Pubkey, u64>) -> Self {
+        let nodes: Vec<_> = stakes
+            .iter()
+            .filter(|(_, stake)| **stake > 0)
+            .map(|(&pubkey, &stake)| Node {
+                node: NodeId::from(pubkey),
+                stake,
+            })
+            .sorted_by_key(|node| Reverse((node.stake, node.pubkey())))
+            .collect();
+        let index = nodes
+            .iter()
+            .enumerate()
+            .map(|(index, node)| (node.stake.max(1), index))
+            .collect();
+        ClusterNodes {
+            pubkey: Pubkey::default(),
+            nodes,
+            index,
+            _phantom: PhantomData::default(),
+        }
+    }
+
+    // Indices into self.nodes, in the order of the stake-weighted shuffle
+    // which determines the turbine tree of the shred.
+    fn get_shuffled_index(&self, shred_seed: [u8; 32], slot_leader: Option<Pubkey>) -> Vec<usize> {
         // Exclude leader from list of nodes.
         let index = self.index.iter().copied();
         let (weights, index): (Vec<u64>, Vec<usize>) = match slot_leader {
@@ -142,10 +162,20 @@ impl ClusterNodes<RetransmitStage> {
                 .filter(|(_, i)| self.nodes[*i].pubkey() != slot_leader)
                 .unzip(),
         };
-        let index: Vec<_> = {
-            let shuffle = weighted_shuffle(weights.into_iter(), shred_seed);
-            shuffle.into_iter().map(|i| index[i]).collect()
-        };
+        let shuffle = weighted_shuffle(weights.into_iter(), shred_seed);
+        shuffle.into_iter().map(|i| index[i]).collect()
+    }
+
+    pub(crate) fn get_retransmit_peers(
+        &self,
+        shred_seed: [u8; 32],
+        fanout: usize,
+        slot_leader: Option<Pubkey>,
+    ) -> (
+        Vec<&ContactInfo>, // neighbors
+        Vec<&ContactInfo>, // children
+    ) {
+        let index = self.get_shuffled_index(shred_seed, slot_leader);
         let self_index = index
             .iter()
             .position(|i| self.nodes[*i].pubkey() == self.pubkey)
@@ -165,6 +195,175 @@ impl ClusterNodes<RetransmitStage> {
         };
         (get_contact_infos(neighbors), get_contact_infos(children))
     }
+
+    /// Returns the full turbine retransmit tree of the shred with the given
+    /// seed, as computed by every node in get_retransmit_peers.
+    pub fn get_turbine_tree(
+        &self,
+        shred_seed: [u8; 32],
+        fanout: usize,
+        slot_leader: Option<Pubkey>,
+    ) -> TurbineTree {
+        let index = self.get_shuffled_index(shred_seed, slot_leader);
+        let pubkey = |i: usize| self.nodes[index[i]].pubkey().to_string();
+        let mut layer = 0;
+        let mut layer_end = fanout;
+        let mut layer_size = fanout;
+        let nodes = (0..index.len())
+            .map(|i| {
+                if i == layer_end {
+                    layer += 1;
+                    layer_size = layer_size.saturating_mul(fanout);
+                    layer_end = layer_end.saturating_add(layer_size);
+                }
+                let anchor = i - i % fanout;
+                // Inverse of compute_retransmit_peers: children of node j are
+                // at (anchor(j) + 1) * fanout + offset(j) + k * fanout.
+                let parent = if i == 0 {
+                    slot_leader.map(|slot_leader| slot_leader.to_string())
+                } else if i < fanout {
+                    Some(pubkey(0))
+                } else {
+                    let anchor = (i / fanout - 1) / fanout * fanout;
+                    Some(pubkey(anchor + i % fanout))
+                };
+                let (neighbors, children) = compute_retransmit_peers(fanout, i, &index);
+                let mut retransmit_peers = Vec::new();
+                if anchor == i {
+                    retransmit_peers.extend(neighbors.into_iter().skip(1));
+                }
+                retransmit_peers.extend(children);
+                let node = &self.nodes[index[i]];
+                TurbineTreeNode {
+                    pubkey: node.pubkey().to_string(),
+                    stake: node.stake,
+                    tvu: node.contact_info().map(|node| node.tvu.to_string()),
+                    position: i,
+                    layer,
+                    neighborhood: i / fanout,
+                    anchor: anchor == i,
+                    parent,
+                    retransmit_peers: retransmit_peers
+                        .into_iter()
+                        .map(|i| self.nodes[i].pubkey().to_string())
+                        .collect(),
+                }
+            })
+            .collect();
+        TurbineTree {
+            slot_leader: slot_leader.map(|slot_leader| slot_leader.to_string()),
+            fanout,
+            nodes,
+        }
+    }
+}
+
+/// Reconstructs the turbine retransmit tree of a shred offline, from the
+/// stake distribution of its epoch. Unstaked nodes are not included.
+pub fn get_turbine_tree_from_stakes(
+    stakes: &HashMap<Pubkey, u64>,
+    shred_seed: [u8; 32],
+    fanout: usize,
+    slot_leader: Pubkey,
+) -> TurbineTree {
+    ClusterNodes::<RetransmitStage>::new_from_stakes(stakes).get_turbine_tree(
+        shred_seed,
+        fanout,
+        Some(slot_leader),
+    )
+}
+
+/// A node's position in the turbine retransmit tree of a shred
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TurbineTreeNode {
+    pub pubkey: String,
+    pub stake: u64,
+    pub tvu: Option<String>,
+    /// Index of the node in the stake-weighted shuffle
+    pub position: usize,
+    /// Layer 0 holds the first `fanout` nodes, layer 1 the next `fanout^2`...
+    pub layer: usize,
+    pub neighborhood: usize,
+    /// The first node of each neighborhood also retransmits to its neighbors
+    pub anchor: bool,
+    /// The node this node receives the shred from; the slot leader for the root
+    pub parent: Option<String>,
+    /// Nodes this node retransmits the shred to
+    pub retransmit_peers: Vec<String>,
+}
+
+/// The turbine retransmit tree of a shred
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TurbineTree {
+    pub slot_leader: Option<String>,
+    pub fanout: usize,
+    pub nodes: Vec<TurbineTreeNode>,
+}
+
+impl TurbineTree {
+    pub fn get_node(&self, pubkey: &Pubkey) -> Option<&TurbineTreeNode> {
+        let pubkey = pubkey.to_string();
+        self.nodes.iter().find(|node| node.pubkey == pubkey)
+    }
+
+    /// Graphviz rendering of the tree, with each neighborhood drawn as a
+    /// cluster. Edges from anchors to their neighbors are dashed.
+    pub fn to_dot(&self, highlight: Option<&Pubkey>) -> String {
+        let highlight = highlight.map(|pubkey| pubkey.to_string());
+        let mut dot = vec!["digraph {".to_string(), "  rankdir=LR;".to_string()];
+        if let Some(slot_leader) = &self.slot_leader {
+            dot.push(format!(
+                "  \"{}\" [label=\"leader\\n{}\",shape=box,style=filled,fillcolor=lightblue];",
+                slot_leader, slot_leader
+            ));
+        }
+        for (neighborhood, nodes) in &self.nodes.iter().group_by(|node| node.neighborhood) {
+            dot.push(format!("  subgraph cluster_{} {{", neighborhood));
+            dot.push(format!(
+                "    label=\"layer {} neighborhood {}\";",
+                self.nodes[neighborhood * self.fanout].layer,
+                neighborhood
+            ));
+            for node in nodes {
+                let style = if highlight.as_ref() == Some(&node.pubkey) {
+                    ",style=filled,fillcolor=orange"
+                } else {
+                    ""
+                };
+                dot.push(format!(
+                    "    \"{}\" [label=\"{}\\n{}\\nstake: {}\"{}];",
+                    node.pubkey, node.position, node.pubkey, node.stake, style
+                ));
+            }
+            dot.push("  }".to_string());
+        }
+        if let (Some(slot_leader), Some(root)) = (&self.slot_leader, self.nodes.first()) {
+            dot.push(format!("  \"{}\" -> \"{}\";", slot_leader, root.pubkey));
+        }
+        for node in &self.nodes {
+            let num_neighbors = if node.anchor {
+                self.nodes
+                    .iter()
+                    .skip(node.position + 1)
+                    .take_while(|peer| peer.neighborhood == node.neighborhood)
+                    .count()
+            } else {
+                0
+            };
+            for (i, peer) in node.retransmit_peers.iter().enumerate() {
+                let style = if i < num_neighbors {
+                    " [style=dashed]"
+                } else {
+                    ""
+                };
+                dot.push(format!("  \"{}\" -> \"{}\"{};", node.pubkey, peer, style));
+            }
+        }
+        dot.push("}".to_string());
+        dot.join("\n")
+    }
 }
 
 fn new_cluster_nodes<T: 'static>(
@@ -475,6 +674,61 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_turbine_tree() {
+        let mut rng = rand::thread_rng();
+        let (nodes, stakes, cluster_info) = make_cluster(&mut rng);
+        let this_node = cluster_info.my_contact_info();
+        let cluster_nodes = new_cluster_nodes::<RetransmitStage>(&cluster_info, &stakes);
+        let slot_leader = nodes[1..].choose(&mut rng).unwrap().id;
+        let mut shred_seed = [0u8; 32];
+        rng.fill(&mut shred_seed[..]);
+        for fanout in [1, 2, 7, 200] {
+            let tree = cluster_nodes.get_turbine_tree(shred_seed, fanout, Some(slot_leader));
+            assert_eq!(tree.nodes.len(), nodes.len() - 1);
+            assert!(tree.get_node(&slot_leader).is_none());
+            // Retransmit peers of this node match what retransmit stage computes.
+            let (neighbors, children) =
+                cluster_nodes.get_retransmit_peers(shred_seed, fanout, Some(slot_leader));
+            let node = tree.get_node(&this_node.id).unwrap();
+            let expected: Vec<_> = if node.anchor {
+                neighbors.iter().skip(1).chain(&children).collect()
+            } else {
+                children.iter().collect()
+            };
+            assert_eq!(
+                node.retransmit_peers,
+                expected
+                    .into_iter()
+                    .map(|node| node.id.to_string())
+                    .collect::<Vec<_>>()
+            );
+            // Every node but the root is a retransmit peer of its parent.
+            let positions: HashMap<_, _> = tree
+                .nodes
+                .iter()
+                .map(|node| (node.pubkey.clone(), node.position))
+                .collect();
+            assert_eq!(tree.nodes[0].parent, Some(slot_leader.to_string()));
+            for node in tree.nodes.iter().skip(1) {
+                let parent = &tree.nodes[positions[node.parent.as_ref().unwrap()]];
+                assert!(parent.position < node.position);
+                assert!(parent.retransmit_peers.contains(&node.pubkey));
+                assert!(parent.layer + 1 >= node.layer);
+            }
+            let dot = tree.to_dot(Some(&this_node.id));
+            assert!(dot.starts_with("digraph {"));
+        }
+        // Offline reconstruction only includes staked nodes.
+        let tree = get_turbine_tree_from_stakes(&stakes, shred_seed, 200, slot_leader);
+        let num_staked = stakes
+            .iter()
+            .filter(|(pubkey, stake)| **stake > 0 && **pubkey != slot_leader)
+            .count();
+        assert_eq!(tree.nodes.len(), num_staked);
+        assert!(tree.nodes.iter().all(|node| node.stake > 0));
+    }
+
     #[test]
     fn test_cluster_nodes_broadcast() {
         let mut rng = rand::thread_rng();
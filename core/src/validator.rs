@@ -33,6 +33,7 @@ use {
     solana_ledger::{
         bank_forks_utils,
         blockstore::{Blockstore, BlockstoreSignals, CompletedSlotsReceiver, PurgeType},
+        blockstore_account_write_recorder::BlockstoreAccountWriteRecorder,
         blockstore_db::BlockstoreRecoveryMode,
         blockstore_processor::{self, TransactionStatusSender},
         leader_schedule::FixedSchedule,
@@ -57,6 +58,7 @@ use {
         transaction_status_service::TransactionStatusService,
     },
     solana_runtime::{
+        account_write_log::AccountWriteRecorder,
         accounts_db::AccountShrinkThreshold,
         accounts_index::{AccountSecondaryIndexes, AccountsIndexConfig},
         bank::Bank,
@@ -153,6 +155,13 @@ pub struct ValidatorConfig {
     pub validator_exit: Arc<RwLock<Exit>>,
     pub no_wait_for_vote_to_start_leader: bool,
     pub accounts_shrink_ratio: AccountShrinkThreshold,
+    /// Record the accounts written by each transaction in the blockstore
+    pub account_write_log: bool,
+    /// Include account data in the account write log
+    pub account_write_log_data: bool,
+    /// Where to keep the finished passes of accounts hash calculations, so that interrupted
+    /// calculations resume
+    pub accounts_hash_cache_path: Option<PathBuf>,
 }
 
 impl Default for ValidatorConfig {
@@ -211,6 +220,9 @@ impl Default for ValidatorConfig {
             no_wait_for_vote_to_start_leader: true,
             accounts_shrink_ratio: AccountShrinkThreshold::default(),
             accounts_index_config: None,
+            account_write_log: false,
+            account_write_log_data: false,
+            accounts_hash_cache_path: None,
         }
     }
 }
@@ -272,6 +284,7 @@ pub struct Validator {
     tvu: Tvu,
     ip_echo_server: Option<solana_net_utils::IpEchoServer>,
     pub cluster_info: Arc<ClusterInfo>,
+    pub bank_forks: Arc<RwLock<BankForks>>,
 }
 
 // in the distant future, get rid of ::new()/exit() and use Result properly...
@@ -777,7 +790,7 @@ impl Validator {
             &exit,
             node.info.shred_version,
             vote_tracker,
-            bank_forks,
+            bank_forks.clone(),
             verified_vote_sender,
             gossip_verified_vote_hash_sender,
             replay_vote_receiver,
@@ -810,6 +823,7 @@ impl Validator {
             ip_echo_server,
             validator_exit: config.validator_exit.clone(),
             cluster_info,
+            bank_forks,
         }
     }
 
@@ -1139,10 +1153,17 @@ fn new_banks_from_ledger(
         debug_keys: config.debug_keys.clone(),
         account_indexes: config.account_indexes.clone(),
         accounts_db_caching_enabled: config.accounts_db_caching_enabled,
-        accounts_index_config: config.accounts_index_config,
+        accounts_index_config: config.accounts_index_config.clone(),
         shrink_ratio: config.accounts_shrink_ratio,
         accounts_db_test_hash_calculation: config.accounts_db_test_hash_calculation,
         accounts_db_skip_shrink: config.accounts_db_skip_shrink,
+        account_write_recorder: config.account_write_log.then(|| {
+            Arc::new(BlockstoreAccountWriteRecorder::new(
+                blockstore.clone(),
+                config.account_write_log_data,
+            )) as Arc<dyn AccountWriteRecorder>
+        }),
+        accounts_hash_cache_path: config.accounts_hash_cache_path.clone(),
         ..blockstore_processor::ProcessOptions::default()
     };
 
@@ -1239,6 +1260,14 @@ fn new_banks_from_ledger(
 
     bank_forks.set_snapshot_config(config.snapshot_config.clone());
     bank_forks.set_accounts_hash_interval_slots(config.accounts_hash_interval_slots);
+    // The accounts hashes of snapshot requests resume from the cache too
+    bank_forks
+        .root_bank()
+        .set_accounts_hash_cache_path(config.accounts_hash_cache_path.clone());
+    // getAccountProof proves against the tree of the last snapshot's accounts hash
+    bank_forks
+        .root_bank()
+        .set_retain_accounts_merkle_tree(config.rpc_config.enable_rpc_account_proofs);
 
     if let Some(blockstore_root_scan) = blockstore_root_scan {
         if let Err(err) = blockstore_root_scan.join() {
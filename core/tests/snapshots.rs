@@ -204,6 +204,7 @@ mod tests {
             false,
             false,
             Some(solana_runtime::accounts_index::ACCOUNTS_INDEX_CONFIG_FOR_TESTING),
+            None,
         )
         .unwrap();
 
@@ -832,6 +833,7 @@ mod tests {
             false,
             false,
             Some(solana_runtime::accounts_index::ACCOUNTS_INDEX_CONFIG_FOR_TESTING),
+            None,
         )?;
 
         assert_eq!(bank, &deserialized_bank);
@@ -1012,6 +1014,7 @@ mod tests {
             false,
             false,
             Some(solana_runtime::accounts_index::ACCOUNTS_INDEX_CONFIG_FOR_TESTING),
+            None,
         )
         .unwrap();
 
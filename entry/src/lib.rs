@@ -1,5 +1,6 @@
 #![allow(clippy::integer_arithmetic)]
 pub mod entry;
 pub mod poh;
+pub mod transaction_proof;
 
 extern crate log;
@@ -0,0 +1,211 @@
+//! Proofs that a transaction is included in a slot, checkable against the slot's blockhash
+//!
+//! An entry commits to its transactions through the Merkle root of their signatures, which is
+//! mixed into the PoH hash chain, and the last entry hash of a slot is its blockhash. A
+//! `TransactionProof` holds the Merkle path from a signature to its entry's root and the PoH
+//! hashing that leads from the previous entry, through the entry, to the blockhash.
+
+use {
+    crate::entry::Entry,
+    solana_merkle_tree::{compute_root_from_path, MerkleTree, PathSibling},
+    solana_sdk::{
+        clock::Slot,
+        hash::{hash, hashv, Hash},
+        signature::Signature,
+    },
+};
+
+/// `num_hashes` sequential hashes, followed by hashing in `mixin` if any
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct PohSegment {
+    pub num_hashes: u64,
+    pub mixin: Option<Hash>,
+}
+
+impl PohSegment {
+    pub fn apply(&self, start_hash: &Hash) -> Hash {
+        let mut poh_hash = *start_hash;
+        for _ in 0..self.num_hashes {
+            poh_hash = hash(poh_hash.as_ref());
+        }
+        match self.mixin {
+            Some(mixin) => hashv(&[poh_hash.as_ref(), mixin.as_ref()]),
+            None => poh_hash,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TransactionProof {
+    pub signature: Signature,
+    pub slot: Slot,
+    /// Index of the entry holding the transaction within the slot
+    pub entry_index: usize,
+    /// Hash of the entry before, or the parent slot's blockhash for the first entry
+    pub start_hash: Hash,
+    /// Hashes of the entry before mixing in the root of its transactions
+    pub num_hashes: u64,
+    /// Path from `signature` to the root of the entry's transactions
+    pub path: Vec<PathSibling>,
+    /// Hashing from the entry's hash to the blockhash, with runs of ticks folded together
+    pub segments: Vec<PohSegment>,
+}
+
+impl TransactionProof {
+    /// The proof that the transaction whose first signature is `signature` is in `entries`, the
+    /// entries of `slot` in order, or None if it is not. `start_hash` is the parent slot's
+    /// blockhash.
+    pub fn new(
+        slot: Slot,
+        entries: &[Entry],
+        start_hash: Hash,
+        signature: &Signature,
+    ) -> Option<Self> {
+        let entry_index = entries.iter().position(|entry| {
+            entry
+                .transactions
+                .iter()
+                .any(|tx| tx.signatures.first() == Some(signature))
+        })?;
+        let entry = &entries[entry_index];
+        let signatures: Vec<_> = entry
+            .transactions
+            .iter()
+            .flat_map(|tx| tx.signatures.iter())
+            .collect();
+        let leaf_index = signatures.iter().position(|s| *s == signature)?;
+        let path = MerkleTree::new(&signatures)
+            .find_path(leaf_index)?
+            .siblings();
+
+        let start_hash = entry_index
+            .checked_sub(1)
+            .map(|i| entries[i].hash)
+            .unwrap_or(start_hash);
+        let mut segments: Vec<PohSegment> = vec![];
+        for entry in &entries[entry_index + 1..] {
+            let segment = entry_segment(entry);
+            match segments.last_mut() {
+                Some(last) if last.mixin.is_none() => {
+                    last.num_hashes += segment.num_hashes;
+                    last.mixin = segment.mixin;
+                }
+                _ => segments.push(segment),
+            }
+        }
+        Some(Self {
+            signature: *signature,
+            slot,
+            entry_index,
+            start_hash,
+            num_hashes: entry.num_hashes,
+            path,
+            segments,
+        })
+    }
+
+    /// The hash of the entry holding the transaction
+    pub fn entry_hash(&self) -> Hash {
+        PohSegment {
+            num_hashes: self.num_hashes.saturating_sub(1),
+            mixin: Some(compute_root_from_path(self.signature, &self.path)),
+        }
+        .apply(&self.start_hash)
+    }
+
+    /// The blockhash the proof leads to
+    pub fn blockhash(&self) -> Hash {
+        self.segments
+            .iter()
+            .fold(self.entry_hash(), |poh_hash, segment| {
+                segment.apply(&poh_hash)
+            })
+    }
+
+    /// Whether the transaction is included in the slot with blockhash `blockhash`
+    pub fn verify(&self, blockhash: &Hash) -> bool {
+        self.blockhash() == *blockhash
+    }
+}
+
+fn entry_segment(entry: &Entry) -> PohSegment {
+    if entry.transactions.is_empty() {
+        PohSegment {
+            num_hashes: entry.num_hashes,
+            mixin: None,
+        }
+    } else {
+        PohSegment {
+            num_hashes: entry.num_hashes.saturating_sub(1),
+            mixin: Some(crate::entry::hash_transactions(&entry.transactions)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::entry::{create_ticks, next_entry},
+        solana_sdk::{
+            signature::{Keypair, Signer},
+            system_transaction,
+        },
+    };
+
+    fn make_entries(start_hash: &Hash) -> Vec<Entry> {
+        let mut entries = create_ticks(3, 4, *start_hash);
+        for num_txs in 1..5 {
+            let transactions = (0..num_txs)
+                .map(|_| {
+                    let keypair = Keypair::new();
+                    system_transaction::transfer(&keypair, &keypair.pubkey(), 1, *start_hash)
+                })
+                .collect();
+            entries.push(next_entry(&entries.last().unwrap().hash, 3, transactions));
+            let ticks = create_ticks(2, 4, entries.last().unwrap().hash);
+            entries.extend(ticks);
+        }
+        entries
+    }
+
+    #[test]
+    fn test_transaction_proof() {
+        let start_hash = Hash::new_unique();
+        let entries = make_entries(&start_hash);
+        let blockhash = entries.last().unwrap().hash;
+        for (entry_index, entry) in entries.iter().enumerate() {
+            for tx in &entry.transactions {
+                let proof =
+                    TransactionProof::new(7, &entries, start_hash, &tx.signatures[0]).unwrap();
+                assert_eq!(proof.entry_index, entry_index);
+                assert_eq!(proof.entry_hash(), entry.hash);
+                assert!(proof.verify(&blockhash));
+                assert!(!proof.verify(&start_hash));
+
+                let mut bad_proof = proof.clone();
+                bad_proof.signature = Signature::new(&[1u8; 64]);
+                assert!(!bad_proof.verify(&blockhash));
+                let mut bad_proof = proof;
+                bad_proof.num_hashes += 1;
+                assert!(!bad_proof.verify(&blockhash));
+            }
+        }
+        assert!(
+            TransactionProof::new(7, &entries, start_hash, &Signature::new(&[1u8; 64])).is_none()
+        );
+    }
+
+    #[test]
+    fn test_transaction_proof_folds_ticks() {
+        let start_hash = Hash::new_unique();
+        let entries = make_entries(&start_hash);
+        let tx = &entries[3].transactions[0];
+        let proof = TransactionProof::new(0, &entries, start_hash, &tx.signatures[0]).unwrap();
+        assert_eq!(proof.entry_index, 3);
+        // the ticks before each later transaction entry fold into it, leaving the final ticks
+        assert_eq!(proof.segments.len(), 4);
+        assert_eq!(proof.segments[3].mixin, None);
+        assert!(proof.verify(&entries.last().unwrap().hash));
+    }
+}
@@ -1,9 +1,14 @@
 use {
     clap::{crate_description, crate_name, values_t, App, Arg},
     log::*,
-    solana_clap_utils::input_parsers::{lamports_of_sol, value_of},
+    solana_clap_utils::{
+        input_parsers::{lamports_of_sol, value_of},
+        input_validators::is_port,
+    },
     solana_faucet::{
         faucet::{run_faucet, Faucet, FAUCET_PORT},
+        json_rpc::run_faucet_json_rpc,
+        limits_store::FaucetLimitsStore,
         socketaddr,
     },
     solana_sdk::signature::read_keypair_file,
@@ -56,6 +61,43 @@ async fn main() {
                 .takes_value(true)
                 .help("Request limit for a single request, in SOL"),
         )
+        .arg(
+            Arg::with_name("per_address_cap")
+                .long("per-address-cap")
+                .value_name("NUM")
+                .takes_value(true)
+                .help(
+                    "Request limit for time slice for any single recipient address, in SOL \
+                    [default: --per-time-cap]",
+                ),
+        )
+        .arg(
+            Arg::with_name("limits_path")
+                .long("limits-path")
+                .value_name("PATH")
+                .takes_value(true)
+                .help(
+                    "File in which to persist request totals, so that limits for the current \
+                    time slice survive a restart",
+                ),
+        )
+        .arg(
+            Arg::with_name("json_rpc_port")
+                .long("json-rpc-port")
+                .value_name("PORT")
+                .takes_value(true)
+                .validator(is_port)
+                .help("Also serve airdrop requests as JSON RPC over HTTP on this port"),
+        )
+        .arg(
+            Arg::with_name("json_rpc_allowed_origin")
+                .long("json-rpc-allowed-origin")
+                .value_name("ORIGIN")
+                .takes_value(true)
+                .multiple(true)
+                .requires("json_rpc_port")
+                .help("Allow browsers to make JSON RPC requests from this origin"),
+        )
         .arg(
             Arg::with_name("allowed_ip")
                 .long("allow-ip")
@@ -75,6 +117,14 @@ async fn main() {
     let time_slice = value_of(&matches, "slice");
     let per_time_cap = lamports_of_sol(&matches, "per_time_cap");
     let per_request_cap = lamports_of_sol(&matches, "per_request_cap");
+    let per_address_cap = lamports_of_sol(&matches, "per_address_cap");
+    let json_rpc_port: Option<u16> = value_of(&matches, "json_rpc_port");
+    let json_rpc_allowed_origins: HashSet<_> = matches
+        .values_of("json_rpc_allowed_origin")
+        .into_iter()
+        .flatten()
+        .map(str::to_string)
+        .collect();
 
     let allowed_ips: HashSet<_> = values_t!(matches.values_of("allowed_ip"), IpAddr)
         .unwrap_or_default()
@@ -83,21 +133,38 @@ async fn main() {
 
     let faucet_addr = socketaddr!(0, FAUCET_PORT);
 
-    let faucet = Arc::new(Mutex::new(Faucet::new_with_allowed_ips(
+    let mut faucet = Faucet::new_with_allowed_ips(
         faucet_keypair,
         time_slice,
         per_time_cap,
         per_request_cap,
         allowed_ips,
-    )));
+    )
+    .with_per_address_cap(per_address_cap);
+    if let Some(limits_path) = matches.value_of("limits_path") {
+        faucet = faucet
+            .with_limits_store(FaucetLimitsStore::new(limits_path))
+            .expect("failed to load faucet limits");
+    }
+    let faucet = Arc::new(Mutex::new(faucet));
 
     let faucet1 = faucet.clone();
     thread::spawn(move || loop {
-        let time = faucet1.lock().unwrap().time_slice;
+        let time = faucet1.lock().unwrap().time_until_clear();
         thread::sleep(time);
         debug!("clearing ip cache");
         faucet1.lock().unwrap().clear_caches();
     });
 
+    if let Some(json_rpc_port) = json_rpc_port {
+        let json_rpc_addr = socketaddr!(0, json_rpc_port);
+        tokio::spawn(run_faucet_json_rpc(
+            faucet.clone(),
+            json_rpc_addr,
+            json_rpc_allowed_origins,
+            None,
+        ));
+    }
+
     run_faucet(faucet, faucet_addr, None).await;
 }
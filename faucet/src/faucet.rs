@@ -1,10 +1,12 @@
 //! The `faucet` module provides an object for launching a Solana Faucet,
 //! which is the custodian of any remaining lamports in a mint.
 //! The Solana Faucet builds and sends airdrop transactions,
-//! checking requests against a single-request cap and a per-IP limit
-//! for a given time time_slice.
+//! checking requests against a single-request cap and per-IP and per-recipient
+//! limits for a given time time_slice. Request totals may optionally be persisted
+//! to a `FaucetLimitsStore` so that they survive a restart.
 
 use {
+    crate::limits_store::{FaucetLimits, FaucetLimitsStore, FaucetLimitsUpdate},
     bincode::{deserialize, serialize, serialized_size},
     byteorder::{ByteOrder, LittleEndian},
     log::*,
@@ -27,7 +29,7 @@ use {
         net::{IpAddr, Ipv4Addr, SocketAddr, TcpStream},
         sync::{mpsc::Sender, Arc, Mutex},
         thread,
-        time::Duration,
+        time::{Duration, SystemTime},
     },
     thiserror::Error,
     tokio::{
@@ -106,7 +108,10 @@ pub struct Faucet {
     pub time_slice: Duration,
     per_time_cap: Option<u64>,
     per_request_cap: Option<u64>,
+    per_address_cap: Option<u64>,
     allowed_ips: HashSet<IpAddr>,
+    slice_start: SystemTime,
+    limits_store: Option<FaucetLimitsStore>,
 }
 
 impl Faucet {
@@ -150,10 +155,45 @@ impl Faucet {
             time_slice,
             per_time_cap,
             per_request_cap,
+            per_address_cap: None,
             allowed_ips,
+            slice_start: SystemTime::now(),
+            limits_store: None,
         }
     }
 
+    /// Limits the lamports any single recipient address may receive per time slice,
+    /// independently of the per-IP limit. Falls back to `per_time_cap` if unset.
+    pub fn with_per_address_cap(mut self, per_address_cap: Option<u64>) -> Self {
+        self.per_address_cap = per_address_cap;
+        self
+    }
+
+    /// Appends every new request total to `limits_store`, first restoring any totals it holds
+    /// for a time slice that has not yet elapsed
+    pub fn with_limits_store(
+        mut self,
+        mut limits_store: FaucetLimitsStore,
+    ) -> Result<Self, FaucetError> {
+        match limits_store.load(self.time_slice)? {
+            Some(limits) => {
+                info!(
+                    "Restored faucet limits for {} IPs and {} addresses from {:?}",
+                    limits.ip_cache.len(),
+                    limits.address_cache.len(),
+                    limits_store.path(),
+                );
+                self.slice_start = limits.slice_start();
+                self.ip_cache = limits.ip_cache;
+                self.address_cache = limits.address_cache;
+            }
+            // Start the store for this time slice, for updates to be appended to
+            None => limits_store.save(&FaucetLimits::new(self.slice_start))?,
+        }
+        self.limits_store = Some(limits_store);
+        Ok(self)
+    }
+
     pub fn check_time_request_limit<T: LimitByTime + std::fmt::Display>(
         &mut self,
         request_amount: u64,
@@ -161,7 +201,8 @@ impl Faucet {
     ) -> Result<(), FaucetError> {
         let new_total = to.check_cache(self, request_amount);
         to.datapoint_info(request_amount, new_total);
-        if let Some(cap) = self.per_time_cap {
+        self.append_limits_update(to.limits_update(new_total));
+        if let Some(cap) = to.time_cap(self) {
             if new_total > cap {
                 return Err(FaucetError::PerTimeCapExceeded(
                     lamports_to_sol(request_amount),
@@ -177,6 +218,51 @@ impl Faucet {
     pub fn clear_caches(&mut self) {
         self.ip_cache.clear();
         self.address_cache.clear();
+        self.slice_start = SystemTime::now();
+        if let Some(limits_store) = &mut self.limits_store {
+            if let Err(err) = limits_store.save(&FaucetLimits::new(self.slice_start)) {
+                warn!(
+                    "Failed to save faucet limits to {:?}: {}",
+                    limits_store.path(),
+                    err
+                );
+            }
+        }
+    }
+
+    /// Returns the time remaining before the current time slice elapses and the caches should
+    /// be cleared
+    pub fn time_until_clear(&self) -> Duration {
+        let elapsed = self.slice_start.elapsed().unwrap_or_default();
+        self.time_slice.checked_sub(elapsed).unwrap_or_default()
+    }
+
+    fn append_limits_update(&mut self, update: FaucetLimitsUpdate) {
+        if let Some(limits_store) = &mut self.limits_store {
+            if let Err(err) = limits_store.append(update) {
+                warn!(
+                    "Failed to append faucet limits to {:?}: {}",
+                    limits_store.path(),
+                    err
+                );
+            }
+        }
+    }
+
+    fn check_request_limits(
+        &mut self,
+        request_amount: u64,
+        to: Pubkey,
+        ip: IpAddr,
+    ) -> Result<(), FaucetError> {
+        // Don't rely on a caller clearing the caches, or they grow with every address ever seen
+        if self.time_until_clear() == Duration::ZERO {
+            self.clear_caches();
+        }
+        if !ip.is_loopback() && !self.allowed_ips.contains(&ip) {
+            self.check_time_request_limit(request_amount, ip)?;
+        }
+        self.check_time_request_limit(request_amount, to)
     }
 
     /// Checks per-request and per-time-ip limits; if both pass, this method returns a signed
@@ -223,10 +309,7 @@ impl Faucet {
                         )));
                     }
                 }
-                if !ip.is_loopback() && !self.allowed_ips.contains(&ip) {
-                    self.check_time_request_limit(lamports, ip)?;
-                }
-                self.check_time_request_limit(lamports, to)?;
+                self.check_request_limits(lamports, to, ip)?;
 
                 let transfer_instruction =
                     system_instruction::transfer(&mint_pubkey, &to, lamports);
@@ -451,7 +534,9 @@ async fn process(
 
 pub trait LimitByTime {
     fn check_cache(&self, faucet: &mut Faucet, request_amount: u64) -> u64;
+    fn time_cap(&self, faucet: &Faucet) -> Option<u64>;
     fn datapoint_info(&self, request_amount: u64, new_total: u64);
+    fn limits_update(&self, new_total: u64) -> FaucetLimitsUpdate;
 }
 
 impl LimitByTime for IpAddr {
@@ -463,6 +548,10 @@ impl LimitByTime for IpAddr {
             .or_insert(request_amount)
     }
 
+    fn time_cap(&self, faucet: &Faucet) -> Option<u64> {
+        faucet.per_time_cap
+    }
+
     fn datapoint_info(&self, request_amount: u64, new_total: u64) {
         datapoint_info!(
             "faucet-airdrop",
@@ -471,6 +560,10 @@ impl LimitByTime for IpAddr {
             ("new_total", new_total, i64)
         );
     }
+
+    fn limits_update(&self, new_total: u64) -> FaucetLimitsUpdate {
+        FaucetLimitsUpdate::Ip(*self, new_total)
+    }
 }
 
 impl LimitByTime for Pubkey {
@@ -482,6 +575,10 @@ impl LimitByTime for Pubkey {
             .or_insert(request_amount)
     }
 
+    fn time_cap(&self, faucet: &Faucet) -> Option<u64> {
+        faucet.per_address_cap.or(faucet.per_time_cap)
+    }
+
     fn datapoint_info(&self, request_amount: u64, new_total: u64) {
         datapoint_info!(
             "faucet-airdrop",
@@ -490,6 +587,10 @@ impl LimitByTime for Pubkey {
             ("new_total", new_total, i64)
         );
     }
+
+    fn limits_update(&self, new_total: u64) -> FaucetLimitsUpdate {
+        FaucetLimitsUpdate::Address(*self, new_total)
+    }
 }
 
 #[cfg(test)]
@@ -513,6 +614,54 @@ mod tests {
         assert!(faucet.check_time_request_limit(1, address).is_err());
     }
 
+    #[test]
+    fn test_check_time_request_limit_per_address_cap() {
+        let keypair = Keypair::new();
+        let mut faucet = Faucet::new(keypair, None, Some(3), None).with_per_address_cap(Some(1));
+        let ip = socketaddr!([203, 0, 113, 1], 1234).ip();
+        assert!(faucet.check_time_request_limit(2, ip).is_ok());
+        assert!(faucet.check_time_request_limit(2, ip).is_err());
+
+        let address = Pubkey::new_unique();
+        assert!(faucet.check_time_request_limit(1, address).is_ok());
+        assert!(faucet.check_time_request_limit(1, address).is_err());
+    }
+
+    #[test]
+    fn test_limits_store_survives_restart() {
+        let store_dir = tempfile::tempdir().unwrap();
+        let store_path = store_dir.path().join("faucet-limits.bin");
+        let to = Pubkey::new_unique();
+        let request = FaucetRequest::GetAirdrop {
+            lamports: 2,
+            to,
+            blockhash: Hash::default(),
+        };
+        let ip = socketaddr!([203, 0, 113, 1], 1234).ip();
+
+        let mint = Keypair::new();
+        let mut faucet = Faucet::new(mint, None, Some(2), None)
+            .with_limits_store(FaucetLimitsStore::new(&store_path))
+            .unwrap();
+        assert!(faucet.build_airdrop_transaction(request, ip).is_ok());
+        drop(faucet);
+
+        let mint = Keypair::new();
+        let mut faucet = Faucet::new(mint, None, Some(2), None)
+            .with_limits_store(FaucetLimitsStore::new(&store_path))
+            .unwrap();
+        assert_eq!(faucet.address_cache.get(&to), Some(&2));
+        assert!(faucet.build_airdrop_transaction(request, ip).is_err());
+
+        faucet.clear_caches();
+        let mint = Keypair::new();
+        let faucet = Faucet::new(mint, None, Some(2), None)
+            .with_limits_store(FaucetLimitsStore::new(&store_path))
+            .unwrap();
+        assert!(faucet.address_cache.is_empty());
+        assert!(faucet.ip_cache.is_empty());
+    }
+
     #[test]
     fn test_clear_caches() {
         let keypair = Keypair::new();
@@ -534,6 +683,20 @@ mod tests {
         assert!(faucet.address_cache.is_empty());
     }
 
+    #[test]
+    fn test_caches_cleared_after_time_slice() {
+        let keypair = Keypair::new();
+        let mut faucet = Faucet::new(keypair, Some(0), Some(1), None);
+        let ip = socketaddr!([203, 0, 113, 1], 1234).ip();
+        for _ in 0..3 {
+            faucet
+                .check_request_limits(1, Pubkey::new_unique(), ip)
+                .unwrap();
+            assert_eq!(faucet.ip_cache.len(), 1);
+            assert_eq!(faucet.address_cache.len(), 1);
+        }
+    }
+
     #[test]
     fn test_faucet_default_init() {
         let keypair = Keypair::new();
@@ -0,0 +1,421 @@
+//! The `json_rpc` module serves airdrop requests as JSON-RPC 2.0 over HTTP, so that
+//! browser-based tools can use the faucet without speaking its binary TCP protocol.
+//!
+//! The only supported method is `requestAirdrop`, whose params are a base58 recipient pubkey,
+//! a lamports amount and a base58 recent blockhash. The result holds the signed airdrop
+//! transaction as base64-encoded bincode, which the caller submits to the cluster itself.
+//!
+//! Browsers may only call the faucet from the origins it is started with.
+
+use {
+    crate::faucet::{Faucet, FaucetRequest, FaucetTransaction},
+    bincode::serialize,
+    log::*,
+    serde_json::{json, Value},
+    solana_sdk::{hash::Hash, pubkey::Pubkey},
+    std::{
+        collections::HashSet,
+        io,
+        net::{IpAddr, SocketAddr},
+        str::FromStr,
+        sync::{mpsc::Sender, Arc, Mutex},
+        time::Duration,
+    },
+    tokio::{
+        io::{AsyncReadExt, AsyncWriteExt},
+        net::{TcpListener, TcpStream},
+        time::{timeout, timeout_at, Instant},
+    },
+};
+
+const MAX_HEADER_SIZE: usize = 8 * 1024;
+const MAX_BODY_SIZE: usize = 4 * 1024;
+// Clients get this long to send a whole request, however slowly its bytes trickle in
+const READ_TIMEOUT: Duration = Duration::from_secs(10);
+const WRITE_TIMEOUT: Duration = Duration::from_secs(10);
+
+// Error codes defined by the JSON-RPC 2.0 specification
+const PARSE_ERROR: i64 = -32700;
+const INVALID_REQUEST: i64 = -32600;
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+// Implementation-defined server error, returned when the faucet declines an airdrop
+const AIRDROP_DECLINED: i64 = -32000;
+
+/// Serves `faucet` on `json_rpc_addr`, allowing cross-origin requests from `allowed_origins`
+pub async fn run_faucet_json_rpc(
+    faucet: Arc<Mutex<Faucet>>,
+    json_rpc_addr: SocketAddr,
+    allowed_origins: HashSet<String>,
+    sender: Option<Sender<Result<SocketAddr, String>>>,
+) {
+    let listener = TcpListener::bind(&json_rpc_addr).await;
+    if let Some(sender) = sender {
+        sender
+            .send(
+                listener
+                    .as_ref()
+                    .map(|listener| listener.local_addr().unwrap())
+                    .map_err(|err| {
+                        format!(
+                            "Unable to bind faucet JSON RPC to {:?}, check the address is not already in use: {}",
+                            json_rpc_addr, err
+                        )
+                    }),
+            )
+            .unwrap();
+    }
+
+    let listener = match listener {
+        Err(err) => {
+            error!("Faucet JSON RPC failed to start: {}", err);
+            return;
+        }
+        Ok(listener) => listener,
+    };
+    info!("Faucet JSON RPC started. Listening on: {}", json_rpc_addr);
+
+    let allowed_origins = Arc::new(allowed_origins);
+    loop {
+        let faucet = faucet.clone();
+        let allowed_origins = allowed_origins.clone();
+        match listener.accept().await {
+            Ok((stream, _)) => {
+                tokio::spawn(async move {
+                    if let Err(e) = process_http(stream, faucet, &allowed_origins).await {
+                        info!("failed to process JSON RPC request; error = {:?}", e);
+                    }
+                });
+            }
+            Err(e) => debug!("failed to accept socket; error = {:?}", e),
+        }
+    }
+}
+
+/// Handles a single HTTP request; connections are closed after every response
+async fn process_http(
+    mut stream: TcpStream,
+    faucet: Arc<Mutex<Faucet>>,
+    allowed_origins: &HashSet<String>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let ip = stream.peer_addr()?.ip();
+    let read_deadline = Instant::now() + READ_TIMEOUT;
+
+    let mut buffer = Vec::new();
+    let mut chunk = [0u8; 1024];
+    let header_end = loop {
+        if let Some(header_end) = find_header_end(&buffer) {
+            break header_end;
+        }
+        if buffer.len() > MAX_HEADER_SIZE {
+            let status = "431 Request Header Fields Too Large";
+            return write_response(&mut stream, status, None, None).await;
+        }
+        let bytes_read = read_until(&mut stream, &mut chunk, read_deadline).await?;
+        if bytes_read == 0 {
+            return Ok(());
+        }
+        buffer.extend_from_slice(&chunk[..bytes_read]);
+    };
+
+    let header = String::from_utf8_lossy(&buffer[..header_end]).into_owned();
+    let mut lines = header.split("\r\n");
+    let method = lines
+        .next()
+        .and_then(|request_line| request_line.split_whitespace().next())
+        .unwrap_or_default();
+    let headers: Vec<_> = lines
+        .filter_map(|line| line.split_once(':'))
+        .map(|(name, value)| (name.trim(), value.trim()))
+        .collect();
+    let header_value = |header: &str| {
+        headers
+            .iter()
+            .find(|(name, _)| name.eq_ignore_ascii_case(header))
+            .map(|(_, value)| *value)
+    };
+    let content_length = header_value("content-length")
+        .and_then(|value| value.parse::<usize>().ok())
+        .unwrap_or_default();
+    let allowed_origin = header_value("origin").filter(|origin| allowed_origins.contains(*origin));
+
+    match method {
+        "OPTIONS" => {
+            return write_response(&mut stream, "204 No Content", allowed_origin, None).await
+        }
+        "POST" => {}
+        _ => {
+            let status = "405 Method Not Allowed";
+            return write_response(&mut stream, status, allowed_origin, None).await;
+        }
+    }
+    if content_length > MAX_BODY_SIZE {
+        let status = "413 Payload Too Large";
+        return write_response(&mut stream, status, allowed_origin, None).await;
+    }
+
+    let body_start = header_end + 4;
+    while buffer.len() < body_start + content_length {
+        let bytes_read = read_until(&mut stream, &mut chunk, read_deadline).await?;
+        if bytes_read == 0 {
+            return Ok(());
+        }
+        buffer.extend_from_slice(&chunk[..bytes_read]);
+    }
+    let body = &buffer[body_start..body_start + content_length];
+
+    info!("JSON RPC request IP: {:?}", ip);
+    let response = process_json_rpc_request(&mut faucet.lock().unwrap(), body, ip);
+    let response = response.to_string();
+    write_response(&mut stream, "200 OK", allowed_origin, Some(&response)).await
+}
+
+/// Reads into `buffer`, failing once `deadline` has passed
+async fn read_until(
+    stream: &mut TcpStream,
+    buffer: &mut [u8],
+    deadline: Instant,
+) -> io::Result<usize> {
+    timeout_at(deadline, stream.read(buffer))
+        .await
+        .map_err(|_| io::Error::new(io::ErrorKind::TimedOut, "request read timed out"))?
+}
+
+fn find_header_end(buffer: &[u8]) -> Option<usize> {
+    buffer.windows(4).position(|window| window == b"\r\n\r\n")
+}
+
+/// Writes a response, allowing cross-origin reads only from `allowed_origin`
+async fn write_response(
+    stream: &mut TcpStream,
+    status: &str,
+    allowed_origin: Option<&str>,
+    body: Option<&str>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let body = body.unwrap_or_default();
+    let cors_headers = allowed_origin
+        .map(|origin| {
+            format!(
+                "Access-Control-Allow-Origin: {}\r\n\
+                Access-Control-Allow-Methods: POST, OPTIONS\r\n\
+                Access-Control-Allow-Headers: Content-Type\r\n",
+                origin
+            )
+        })
+        .unwrap_or_default();
+    let response = format!(
+        "HTTP/1.1 {}\r\n\
+        {}\
+        Vary: Origin\r\n\
+        Content-Type: application/json\r\n\
+        Content-Length: {}\r\n\
+        Connection: close\r\n\
+        \r\n\
+        {}",
+        status,
+        cors_headers,
+        body.len(),
+        body
+    );
+    timeout(WRITE_TIMEOUT, stream.write_all(response.as_bytes()))
+        .await
+        .map_err(|_| io::Error::new(io::ErrorKind::TimedOut, "response write timed out"))??;
+    Ok(())
+}
+
+/// Processes a JSON-RPC request body, returning the JSON-RPC response to send back
+pub fn process_json_rpc_request(faucet: &mut Faucet, body: &[u8], ip: IpAddr) -> Value {
+    let request: Value = match serde_json::from_slice(body) {
+        Ok(request) => request,
+        Err(err) => return error_response(Value::Null, PARSE_ERROR, err.to_string()),
+    };
+    let id = request.get("id").cloned().unwrap_or(Value::Null);
+    let method = match request.get("method").and_then(Value::as_str) {
+        Some(method) => method,
+        None => return error_response(id, INVALID_REQUEST, "missing method".to_string()),
+    };
+    if method != "requestAirdrop" {
+        return error_response(id, METHOD_NOT_FOUND, format!("unknown method: {}", method));
+    }
+    let faucet_request = match parse_airdrop_params(request.get("params")) {
+        Ok(faucet_request) => faucet_request,
+        Err(err) => return error_response(id, INVALID_PARAMS, err),
+    };
+
+    info!("Airdrop transaction requested...{:?}", faucet_request);
+    match faucet.build_airdrop_transaction(faucet_request, ip) {
+        Ok(FaucetTransaction::Airdrop(tx)) => {
+            info!("Airdrop transaction granted");
+            let transaction = match serialize(&tx) {
+                Ok(transaction) => base64::encode(transaction),
+                Err(err) => return error_response(id, AIRDROP_DECLINED, err.to_string()),
+            };
+            json!({
+                "jsonrpc": "2.0",
+                "id": id,
+                "result": {
+                    "signature": tx.signatures[0].to_string(),
+                    "transaction": transaction,
+                },
+            })
+        }
+        Ok(FaucetTransaction::Memo((_, memo))) => {
+            warn!("Airdrop declined: {}", memo);
+            error_response(id, AIRDROP_DECLINED, memo)
+        }
+        Err(err) => {
+            warn!("Airdrop transaction failed: {}", err);
+            error_response(id, AIRDROP_DECLINED, err.to_string())
+        }
+    }
+}
+
+fn parse_airdrop_params(params: Option<&Value>) -> Result<FaucetRequest, String> {
+    let params = params
+        .and_then(Value::as_array)
+        .filter(|params| params.len() == 3)
+        .ok_or_else(|| "expected params [pubkey, lamports, recentBlockhash]".to_string())?;
+    let to = params[0]
+        .as_str()
+        .and_then(|to| Pubkey::from_str(to).ok())
+        .ok_or_else(|| "invalid pubkey".to_string())?;
+    let lamports = params[1]
+        .as_u64()
+        .ok_or_else(|| "invalid lamports".to_string())?;
+    let blockhash = params[2]
+        .as_str()
+        .and_then(|blockhash| Hash::from_str(blockhash).ok())
+        .ok_or_else(|| "invalid recentBlockhash".to_string())?;
+    Ok(FaucetRequest::GetAirdrop {
+        lamports,
+        to,
+        blockhash,
+    })
+}
+
+fn error_response(id: Value, code: i64, message: String) -> Value {
+    json!({
+        "jsonrpc": "2.0",
+        "id": id,
+        "error": {
+            "code": code,
+            "message": message,
+        },
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        bincode::deserialize,
+        solana_sdk::{signature::Keypair, system_transaction, transaction::Transaction},
+        std::net::Ipv4Addr,
+    };
+
+    #[test]
+    fn test_process_json_rpc_request() {
+        let keypair = Keypair::new();
+        let to = Pubkey::new_unique();
+        let blockhash = Hash::new_unique();
+        let expected_tx = system_transaction::transfer(&keypair, &to, 50, blockhash);
+        let ip = IpAddr::V4(Ipv4Addr::new(203, 0, 113, 1));
+        let mut faucet = Faucet::new(keypair, None, Some(60), None);
+
+        let body = json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": "requestAirdrop",
+            "params": [to.to_string(), 50, blockhash.to_string()],
+        })
+        .to_string();
+        let response = process_json_rpc_request(&mut faucet, body.as_bytes(), ip);
+        assert_eq!(response["id"], 1);
+        let transaction =
+            base64::decode(response["result"]["transaction"].as_str().unwrap()).unwrap();
+        let transaction: Transaction = deserialize(&transaction).unwrap();
+        assert_eq!(transaction, expected_tx);
+        assert_eq!(
+            response["result"]["signature"],
+            expected_tx.signatures[0].to_string()
+        );
+
+        // Second request exceeds the per-time cap
+        let response = process_json_rpc_request(&mut faucet, body.as_bytes(), ip);
+        assert_eq!(response["error"]["code"], AIRDROP_DECLINED);
+    }
+
+    #[test]
+    fn test_process_json_rpc_request_errors() {
+        let mut faucet = Faucet::new(Keypair::new(), None, None, None);
+        let ip = IpAddr::V4(Ipv4Addr::new(203, 0, 113, 1));
+
+        let response = process_json_rpc_request(&mut faucet, b"bad bytes", ip);
+        assert_eq!(response["error"]["code"], PARSE_ERROR);
+
+        let body = json!({"jsonrpc": "2.0", "id": 2, "method": "getBalance", "params": []});
+        let response = process_json_rpc_request(&mut faucet, body.to_string().as_bytes(), ip);
+        assert_eq!(response["id"], 2);
+        assert_eq!(response["error"]["code"], METHOD_NOT_FOUND);
+
+        let body = json!({
+            "jsonrpc": "2.0",
+            "id": 3,
+            "method": "requestAirdrop",
+            "params": ["not a pubkey", 50, Hash::default().to_string()],
+        });
+        let response = process_json_rpc_request(&mut faucet, body.to_string().as_bytes(), ip);
+        assert_eq!(response["error"]["code"], INVALID_PARAMS);
+    }
+
+    // Multi-threaded, so that the server runs while the test blocks on its address
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_json_rpc_cors() {
+        let faucet = Arc::new(Mutex::new(Faucet::new(Keypair::new(), None, None, None)));
+        let allowed_origins = vec!["https://faucet.example".to_string()]
+            .into_iter()
+            .collect();
+        let (sender, receiver) = std::sync::mpsc::channel();
+        tokio::spawn(run_faucet_json_rpc(
+            faucet,
+            "127.0.0.1:0".parse().unwrap(),
+            allowed_origins,
+            Some(sender),
+        ));
+        let addr = receiver.recv().unwrap().unwrap();
+
+        let preflight = |origin: &str| {
+            format!(
+                "OPTIONS / HTTP/1.1\r\nOrigin: {}\r\nContent-Length: 0\r\n\r\n",
+                origin
+            )
+        };
+        for (origin, allowed) in [
+            ("https://faucet.example", true),
+            ("https://elsewhere.example", false),
+        ] {
+            let mut stream = TcpStream::connect(addr).await.unwrap();
+            stream
+                .write_all(preflight(origin).as_bytes())
+                .await
+                .unwrap();
+            let mut response = String::new();
+            stream.read_to_string(&mut response).await.unwrap();
+            assert!(response.starts_with("HTTP/1.1 204"));
+            assert_eq!(
+                response.contains(&format!("Access-Control-Allow-Origin: {}\r\n", origin)),
+                allowed
+            );
+            assert!(!response.contains("Access-Control-Allow-Origin: *"));
+        }
+    }
+
+    #[test]
+    fn test_find_header_end() {
+        assert_eq!(find_header_end(b"POST / HTTP/1.1\r\n"), None);
+        assert_eq!(
+            find_header_end(b"POST / HTTP/1.1\r\nContent-Length: 2\r\n\r\n{}"),
+            Some(34)
+        );
+    }
+}
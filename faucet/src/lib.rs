@@ -1,2 +1,4 @@
 pub mod faucet;
 pub mod faucet_mock;
+pub mod json_rpc;
+pub mod limits_store;
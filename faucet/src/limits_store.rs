@@ -0,0 +1,206 @@
+//! The `limits_store` module persists the faucet's per-IP and per-address request totals to disk,
+//! so that a restarted faucet resumes the current time slice rather than starting from zero.
+//!
+//! The store holds the totals as of the start of the time slice, followed by every total updated
+//! since, so that requests only append a small record rather than rewrite the whole store.
+
+use {
+    crate::faucet::FaucetError,
+    bincode::{deserialize_from, serialize},
+    log::*,
+    serde_derive::{Deserialize, Serialize},
+    solana_sdk::pubkey::Pubkey,
+    std::{
+        collections::HashMap,
+        fs::{self, File, OpenOptions},
+        io::{ErrorKind, Write},
+        net::IpAddr,
+        path::{Path, PathBuf},
+        time::{Duration, SystemTime, UNIX_EPOCH},
+    },
+};
+
+#[derive(Serialize, Deserialize, Debug, Default, PartialEq)]
+pub struct FaucetLimits {
+    /// Seconds since the Unix epoch at which the current time slice began
+    pub slice_start: u64,
+    pub ip_cache: HashMap<IpAddr, u64>,
+    pub address_cache: HashMap<Pubkey, u64>,
+}
+
+impl FaucetLimits {
+    pub fn new(slice_start: SystemTime) -> Self {
+        Self {
+            slice_start: unix_timestamp(slice_start),
+            ..Self::default()
+        }
+    }
+
+    pub fn slice_start(&self) -> SystemTime {
+        UNIX_EPOCH + Duration::from_secs(self.slice_start)
+    }
+
+    pub fn apply(&mut self, update: FaucetLimitsUpdate) {
+        match update {
+            FaucetLimitsUpdate::Ip(ip, total) => {
+                self.ip_cache.insert(ip, total);
+            }
+            FaucetLimitsUpdate::Address(address, total) => {
+                self.address_cache.insert(address, total);
+            }
+        }
+    }
+
+    /// Returns true if the time slice these totals were recorded in has elapsed as of `now`
+    pub fn is_expired(&self, time_slice: Duration, now: SystemTime) -> bool {
+        now.duration_since(self.slice_start())
+            .map(|elapsed| elapsed >= time_slice)
+            // A slice start in the future means the clock moved backwards; don't trust it
+            .unwrap_or(true)
+    }
+}
+
+/// A new request total for an IP or recipient address
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub enum FaucetLimitsUpdate {
+    Ip(IpAddr, u64),
+    Address(Pubkey, u64),
+}
+
+pub struct FaucetLimitsStore {
+    path: PathBuf,
+    /// Opened on the first append after the store is saved
+    file: Option<File>,
+}
+
+impl FaucetLimitsStore {
+    pub fn new<P: AsRef<Path>>(path: P) -> Self {
+        Self {
+            path: path.as_ref().to_path_buf(),
+            file: None,
+        }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Loads the stored totals, returning `None` if no store exists yet or if the stored time
+    /// slice has already elapsed
+    pub fn load(&self, time_slice: Duration) -> Result<Option<FaucetLimits>, FaucetError> {
+        let bytes = match fs::read(&self.path) {
+            Ok(bytes) => bytes,
+            Err(err) if err.kind() == ErrorKind::NotFound => return Ok(None),
+            Err(err) => return Err(err.into()),
+        };
+        let mut reader = &bytes[..];
+        let mut limits: FaucetLimits = deserialize_from(&mut reader)?;
+        if limits.is_expired(time_slice, SystemTime::now()) {
+            debug!("discarding expired faucet limits from {:?}", self.path);
+            return Ok(None);
+        }
+        while !reader.is_empty() {
+            match deserialize_from(&mut reader) {
+                Ok(update) => limits.apply(update),
+                Err(err) => {
+                    // Only the last update can be partially written
+                    warn!("ignoring truncated faucet limits update: {}", err);
+                    break;
+                }
+            }
+        }
+        Ok(Some(limits))
+    }
+
+    /// Replaces the store with `limits`, discarding any updates appended to it. The totals are
+    /// written to a temporary file alongside the store and renamed into place, so that a crash
+    /// mid-write never leaves a truncated store behind.
+    pub fn save(&mut self, limits: &FaucetLimits) -> Result<(), FaucetError> {
+        let bytes = serialize(limits)?;
+        let tmp_path = self.path.with_extension("tmp");
+        fs::write(&tmp_path, &bytes)?;
+        self.file = None;
+        fs::rename(&tmp_path, &self.path)?;
+        Ok(())
+    }
+
+    /// Appends `update` to the totals last saved
+    pub fn append(&mut self, update: FaucetLimitsUpdate) -> Result<(), FaucetError> {
+        if self.file.is_none() {
+            self.file = Some(OpenOptions::new().append(true).open(&self.path)?);
+        }
+        let file = self.file.as_mut().unwrap();
+        // A single write, so that a crash can only truncate the last update
+        file.write_all(&serialize(&update)?)?;
+        Ok(())
+    }
+}
+
+fn unix_timestamp(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, std::net::Ipv4Addr};
+
+    #[test]
+    fn test_limits_store_round_trip() {
+        let store_dir = tempfile::tempdir().unwrap();
+        let mut store = FaucetLimitsStore::new(store_dir.path().join("faucet-limits.bin"));
+        let time_slice = Duration::from_secs(60);
+        assert_eq!(store.load(time_slice).unwrap(), None);
+
+        let ip = IpAddr::V4(Ipv4Addr::new(203, 0, 113, 1));
+        let mut limits = FaucetLimits::new(SystemTime::now());
+        limits.ip_cache.insert(ip, 42);
+        limits.address_cache.insert(Pubkey::new_unique(), 7);
+        store.save(&limits).unwrap();
+        assert_eq!(store.load(time_slice).unwrap(), Some(limits));
+
+        let mut limits = store.load(time_slice).unwrap().unwrap();
+        let address = Pubkey::new_unique();
+        for update in [
+            FaucetLimitsUpdate::Ip(ip, 43),
+            FaucetLimitsUpdate::Address(address, 1),
+            FaucetLimitsUpdate::Ip(ip, 44),
+        ] {
+            store.append(update).unwrap();
+            limits.apply(update);
+        }
+        assert_eq!(limits.ip_cache[&ip], 44);
+        assert_eq!(store.load(time_slice).unwrap(), Some(limits));
+
+        // A partially written update is dropped, and saving discards all updates
+        let mut bytes = fs::read(store.path()).unwrap();
+        let full_len = bytes.len();
+        bytes.truncate(full_len - 1);
+        fs::write(store.path(), &bytes).unwrap();
+        let limits = store.load(time_slice).unwrap().unwrap();
+        assert_eq!(limits.ip_cache[&ip], 43);
+        assert_eq!(limits.address_cache[&address], 1);
+
+        let limits = FaucetLimits::new(SystemTime::now());
+        store.save(&limits).unwrap();
+        store.append(FaucetLimitsUpdate::Ip(ip, 1)).unwrap();
+        assert_eq!(
+            store.load(time_slice).unwrap().unwrap().ip_cache.get(&ip),
+            Some(&1)
+        );
+    }
+
+    #[test]
+    fn test_limits_store_discards_expired_slice() {
+        let store_dir = tempfile::tempdir().unwrap();
+        let mut store = FaucetLimitsStore::new(store_dir.path().join("faucet-limits.bin"));
+        let time_slice = Duration::from_secs(60);
+
+        let mut limits = FaucetLimits::new(SystemTime::now() - Duration::from_secs(120));
+        limits.address_cache.insert(Pubkey::new_unique(), 7);
+        store.save(&limits).unwrap();
+        assert_eq!(store.load(time_slice).unwrap(), None);
+        assert_eq!(store.load(Duration::from_secs(600)).unwrap(), Some(limits));
+    }
+}
@@ -18,7 +18,7 @@ use {
             submit_gossip_stats, Counter, GossipStats, ScopedTimer, TimedGuard,
         },
         contact_info::ContactInfo,
-        crds::{Crds, Cursor},
+        crds::{Crds, Cursor, VersionedCrdsValue},
         crds_gossip::CrdsGossip,
         crds_gossip_error::CrdsGossipError,
         crds_gossip_pull::{CrdsFilter, ProcessPullStats, CRDS_GOSSIP_PULL_CRDS_TIMEOUT_MS},
@@ -37,7 +37,7 @@ use {
     itertools::Itertools,
     rand::{seq::SliceRandom, thread_rng, CryptoRng, Rng},
     rayon::{prelude::*, ThreadPool, ThreadPoolBuilder},
-    serde::ser::Serialize,
+    serde::{de::DeserializeOwned, ser::Serialize},
     solana_ledger::shred::Shred,
     solana_measure::measure::Measure,
     solana_metrics::{inc_new_counter_debug, inc_new_counter_error},
@@ -89,6 +89,8 @@ use {
     },
 };
 
+pub use crate::cluster_info_metrics::GossipPushPullStats;
+
 pub const VALIDATOR_PORT_RANGE: PortRange = (8000, 10_000);
 pub const MINIMUM_VALIDATOR_PORT_RANGE_WIDTH: u16 = 10; // VALIDATOR_PORT_RANGE must be at least this wide
 
@@ -131,6 +133,15 @@ pub(crate) const CRDS_UNIQUE_PUBKEY_CAPACITY: usize = 4096;
 const MIN_STAKE_FOR_GOSSIP: u64 = solana_sdk::native_token::LAMPORTS_PER_SOL;
 /// Minimum number of staked nodes for enforcing stakes in gossip.
 const MIN_NUM_STAKED_NODES: usize = 500;
+/// Name of the file, under the contact info path, that holds the peers saved
+/// across restarts.
+const SAVED_PEERS_FILENAME: &str = "gossip-peers.bin";
+/// File written by earlier versions, holding the saved peers' ContactInfo
+/// without stakes. Read only if no SAVED_PEERS_FILENAME file exists yet.
+const LEGACY_SAVED_PEERS_FILENAME: &str = "contact-info.bin";
+/// How long peers restored on startup are pinged before they are abandoned if
+/// they have not responded with a valid pong.
+const RESTORED_PEERS_VERIFY_TIMEOUT: Duration = Duration::from_secs(600);
 
 #[derive(Debug, PartialEq, Eq)]
 pub enum ClusterInfoError {
@@ -158,6 +169,25 @@ pub struct ClusterInfo {
     instance: RwLock<NodeInstance>,
     contact_info_path: PathBuf,
     socket_addr_space: SocketAddrSpace,
+    restored_peers: Mutex<RestoredPeers>,
+}
+
+/// Peers saved by a previous run along with their stake at the time, which
+/// are reloaded on startup so that the node can rejoin the cluster even if
+/// the entrypoints are unreachable.
+#[derive(Default, Deserialize, Serialize)]
+struct SavedPeers {
+    nodes: Vec<(CrdsValue, /*stake:*/ u64)>,
+}
+
+/// Saved peers pending verification. A restored ContactInfo is inserted into
+/// the crds table only once the node has responded to a ping at its gossip
+/// address, so stale or spoofed addresses from disk are never trusted.
+#[derive(Default)]
+struct RestoredPeers {
+    // Sorted by stake in descending order.
+    nodes: Vec<(CrdsValue, /*stake:*/ u64)>,
+    deadline: Option<Instant>,
 }
 
 #[derive(Clone, Debug, Default, Deserialize, Serialize, AbiExample)]
@@ -410,6 +440,7 @@ impl ClusterInfo {
             contact_info_path: PathBuf::default(),
             contact_save_interval: 0, // disabled
             socket_addr_space,
+            restored_peers: Mutex::default(),
         };
         me.insert_self();
         me.push_self(&HashMap::new(), None);
@@ -439,6 +470,7 @@ impl ClusterInfo {
             instance: RwLock::new(NodeInstance::new(&mut thread_rng(), *new_id, timestamp())),
             contact_info_path: PathBuf::default(),
             contact_save_interval: 0, // disabled
+            restored_peers: Mutex::default(),
             ..*self
         }
     }
@@ -497,8 +529,10 @@ impl ClusterInfo {
         *self.entrypoints.write().unwrap() = entrypoints;
     }
 
-    pub fn save_contact_info(&self) {
-        let nodes = {
+    /// Saves the ContactInfo of known peers along with their stake, so that
+    /// `restore_contact_info` can reload them on the next startup.
+    pub fn save_contact_info(&self, stakes: &HashMap<Pubkey, u64>) {
+        let mut nodes = {
             let entrypoint_gossip_addrs = self
                 .entrypoints
                 .read()
@@ -521,7 +555,8 @@ impl ClusterInfo {
                     if contact_info.id != self_pubkey
                         && !entrypoint_gossip_addrs.contains(&contact_info.gossip)
                     {
-                        return Some(v.value.clone());
+                        let stake = stakes.get(&contact_info.id).copied().unwrap_or_default();
+                        return Some((v.value.clone(), stake));
                     }
                     None
                 })
@@ -531,13 +566,15 @@ impl ClusterInfo {
         if nodes.is_empty() {
             return;
         }
+        nodes.sort_unstable_by(|(_, a), (_, b)| b.cmp(a));
+        let saved_peers = SavedPeers { nodes };
 
-        let filename = self.contact_info_path.join("contact-info.bin");
+        let filename = self.contact_info_path.join(SAVED_PEERS_FILENAME);
         let tmp_filename = &filename.with_extension("tmp");
 
         match File::create(&tmp_filename) {
             Ok(mut file) => {
-                if let Err(err) = bincode::serialize_into(&mut file, &nodes) {
+                if let Err(err) = bincode::serialize_into(&mut file, &saved_peers) {
                     warn!(
                         "Failed to serialize contact info info {}: {}",
                         tmp_filename.display(),
@@ -556,7 +593,7 @@ impl ClusterInfo {
             Ok(()) => {
                 info!(
                     "Saved contact info for {} nodes into {}",
-                    nodes.len(),
+                    saved_peers.nodes.len(),
                     filename.display()
                 );
             }
@@ -571,38 +608,151 @@ impl ClusterInfo {
         }
     }
 
-    pub fn restore_contact_info(&mut self, contact_info_path: &Path, contact_save_interval: u64) {
-        self.contact_info_path = contact_info_path.into();
-        self.contact_save_interval = contact_save_interval;
+    /// Like `save_contact_info`, for callers without a bank to weigh peers
+    /// by, such as the validator before it has loaded a snapshot. Peers keep
+    /// the stake they were last saved with, so that the saved stakes survive
+    /// until the next save from a bank.
+    pub fn save_contact_info_with_saved_stakes(&self) {
+        let stakes = Self::load_saved_peers(&self.contact_info_path)
+            .map(|(_, saved_peers)| saved_peers.nodes)
+            .unwrap_or_default()
+            .into_iter()
+            .filter_map(|(value, stake)| Some((value.contact_info()?.id, stake)))
+            .collect();
+        self.save_contact_info(&stakes);
+    }
 
-        let filename = contact_info_path.join("contact-info.bin");
-        if !filename.exists() {
-            return;
+    // Returns the saved peers along with the file they were read from, falling
+    // back on the legacy contact info file, which has no stakes.
+    fn load_saved_peers(contact_info_path: &Path) -> Option<(PathBuf, SavedPeers)> {
+        let filename = contact_info_path.join(SAVED_PEERS_FILENAME);
+        if filename.exists() {
+            let saved_peers = Self::read_saved_file(&filename).unwrap_or_default();
+            return Some((filename, saved_peers));
         }
+        let filename = contact_info_path.join(LEGACY_SAVED_PEERS_FILENAME);
+        if filename.exists() {
+            let nodes: Vec<CrdsValue> = Self::read_saved_file(&filename).unwrap_or_default();
+            let nodes = nodes.into_iter().map(|value| (value, 0)).collect();
+            return Some((filename, SavedPeers { nodes }));
+        }
+        None
+    }
 
-        let nodes: Vec<CrdsValue> = match File::open(&filename) {
-            Ok(file) => {
-                bincode::deserialize_from(&mut BufReader::new(file)).unwrap_or_else(|err| {
-                    warn!("Failed to deserialize {}: {}", filename.display(), err);
-                    vec![]
-                })
-            }
+    fn read_saved_file<T: DeserializeOwned>(filename: &Path) -> Option<T> {
+        match File::open(filename) {
+            Ok(file) => bincode::deserialize_from(&mut BufReader::new(file))
+                .map_err(|err| warn!("Failed to deserialize {}: {}", filename.display(), err))
+                .ok(),
             Err(err) => {
                 warn!("Failed to open {}: {}", filename.display(), err);
-                vec![]
+                None
             }
+        }
+    }
+
+    /// Loads the peers saved by `save_contact_info`. Restored peers are not
+    /// trusted until they respond to a ping; see `verify_restored_peers`.
+    pub fn restore_contact_info(&mut self, contact_info_path: &Path, contact_save_interval: u64) {
+        self.contact_info_path = contact_info_path.into();
+        self.contact_save_interval = contact_save_interval;
+
+        let (filename, saved_peers) = match Self::load_saved_peers(contact_info_path) {
+            Some(saved_peers) => saved_peers,
+            None => return,
         };
 
+        let self_pubkey = self.id();
+        let mut nodes: Vec<_> = saved_peers
+            .nodes
+            .into_iter()
+            .filter(|(value, _)| {
+                value.verify()
+                    && value
+                        .contact_info()
+                        .map(|node| node.id != self_pubkey)
+                        .unwrap_or_default()
+            })
+            .collect();
+        nodes.sort_unstable_by(|(_, a), (_, b)| b.cmp(a));
         info!(
             "Loaded contact info for {} nodes from {}",
             nodes.len(),
             filename.display()
         );
+        *self.restored_peers.get_mut().unwrap() = RestoredPeers {
+            nodes,
+            deadline: Some(Instant::now() + RESTORED_PEERS_VERIFY_TIMEOUT),
+        };
+    }
+
+    // Pings peers restored from the saved contact info in order of stake, and
+    // inserts those which have responded with a valid pong into the crds
+    // table, where they become eligible for pull requests.
+    fn verify_restored_peers(&self, pings: &mut Vec<(SocketAddr, Ping)>) {
+        let mut restored_peers = self.restored_peers.lock().unwrap();
+        if restored_peers.nodes.is_empty() {
+            return;
+        }
+        let now = Instant::now();
+        if restored_peers
+            .deadline
+            .map(|deadline| now > deadline)
+            .unwrap_or(true)
+        {
+            info!(
+                "Abandoning {} unverified restored peers",
+                restored_peers.nodes.len()
+            );
+            restored_peers.nodes.clear();
+            return;
+        }
+        // Peers already rediscovered through gossip need no verification.
+        let known_peers: HashSet<Pubkey> = {
+            let gossip_crds = self.gossip.crds.read().unwrap();
+            restored_peers
+                .nodes
+                .iter()
+                .map(|(value, _)| value.pubkey())
+                .filter(|pubkey| gossip_crds.get::<&ContactInfo>(*pubkey).is_some())
+                .collect()
+        };
+        let self_shred_version = self.my_shred_version();
+        let mut verified = Vec::new();
+        {
+            let keypair = self.keypair();
+            let mut rng = rand::thread_rng();
+            let mut pingf = || Ping::new_rand(&mut rng, &keypair).ok();
+            let mut ping_cache = self.ping_cache.lock().unwrap();
+            restored_peers.nodes.retain(|(value, _)| {
+                let node = value.contact_info().unwrap();
+                if known_peers.contains(&node.id)
+                    || (self_shred_version != 0 && node.shred_version != self_shred_version)
+                    || !ContactInfo::is_valid_address(&node.gossip, &self.socket_addr_space)
+                {
+                    return false;
+                }
+                let (check, ping) = ping_cache.check(now, (node.id, node.gossip), &mut pingf);
+                if let Some(ping) = ping {
+                    pings.push((node.gossip, ping));
+                }
+                if check {
+                    verified.push(value.clone());
+                }
+                !check
+            });
+        }
+        if verified.is_empty() {
+            return;
+        }
+        self.stats
+            .restored_peers_verified_count
+            .add_relaxed(verified.len() as u64);
         let now = timestamp();
         let mut gossip_crds = self.gossip.crds.write().unwrap();
-        for node in nodes {
-            if let Err(err) = gossip_crds.insert(node, now) {
-                warn!("crds insert failed {:?}", err);
+        for value in verified {
+            if let Err(err) = gossip_crds.insert(value, now) {
+                debug!("crds insert of restored peer failed {:?}", err);
             }
         }
     }
@@ -1130,6 +1280,33 @@ impl ClusterInfo {
         Some(version.version.clone().into())
     }
 
+    /// Returns crds values, optionally restricted to those from the given
+    /// origin and of the given kind (see `CrdsValueLabel::KINDS`), along with
+    /// the local timestamp at which each was inserted.
+    pub fn get_crds_values(
+        &self,
+        origin: Option<&Pubkey>,
+        kind: Option<&str>,
+    ) -> Vec<(CrdsValue, /*local_timestamp:*/ u64)> {
+        let gossip_crds = self.gossip.crds.read().unwrap();
+        let entries: Box<dyn Iterator<Item = &VersionedCrdsValue>> = match origin {
+            Some(origin) => Box::new(gossip_crds.get_records(origin)),
+            None => Box::new(gossip_crds.values()),
+        };
+        entries
+            .filter(|entry| match kind {
+                Some(kind) => entry.value.label().kind() == kind,
+                None => true,
+            })
+            .map(|entry| (entry.value.clone(), entry.local_timestamp))
+            .collect()
+    }
+
+    /// Returns push and pull message totals since startup.
+    pub fn push_pull_stats(&self) -> GossipPushPullStats {
+        self.stats.push_pull_stats()
+    }
+
     /// all validators that have a valid rpc port regardless of `shred_version`.
     pub fn all_rpc_peers(&self) -> Vec<ContactInfo> {
         let self_pubkey = self.id();
@@ -1403,6 +1580,7 @@ impl ClusterInfo {
             }
         };
         self.append_entrypoint_to_pulls(thread_pool, &mut pulls);
+        self.verify_restored_peers(&mut pings);
         let num_requests = pulls.iter().map(|(_, filters)| filters.len() as u64).sum();
         self.stats.new_pull_requests_count.add_relaxed(num_requests);
         {
@@ -1673,13 +1851,6 @@ impl ClusterInfo {
                         last_contact_info_trace = start;
                     }
 
-                    if self.contact_save_interval != 0
-                        && start - last_contact_info_save > self.contact_save_interval
-                    {
-                        self.save_contact_info();
-                        last_contact_info_save = start;
-                    }
-
                     let (stakes, feature_set) = match bank_forks {
                         Some(ref bank_forks) => {
                             let root_bank = bank_forks.read().unwrap().root_bank();
@@ -1690,6 +1861,13 @@ impl ClusterInfo {
                         }
                         None => (Arc::default(), None),
                     };
+
+                    if self.contact_save_interval != 0
+                        && start - last_contact_info_save > self.contact_save_interval
+                    {
+                        self.save_contact_info(&stakes);
+                        last_contact_info_save = start;
+                    }
                     let require_stake_for_gossip =
                         self.require_stake_for_gossip(feature_set.as_deref(), &stakes);
                     let _ = self.run_gossip(
@@ -3998,6 +4176,161 @@ mod tests {
         assert!(pulls.into_iter().all(|(addr, _)| addr == other_node.gossip));
     }
 
+    #[test]
+    fn test_save_and_restore_contact_info() {
+        let contact_info_dir = tempfile::tempdir().unwrap();
+        let node_keypair = Arc::new(Keypair::new());
+        let mut cluster_info = ClusterInfo::new(
+            ContactInfo::new_localhost(&node_keypair.pubkey(), timestamp()),
+            node_keypair.clone(),
+            SocketAddrSpace::Unspecified,
+        );
+        cluster_info.restore_contact_info(contact_info_dir.path(), 0);
+        let peer_keypairs: Vec<_> = repeat_with(Keypair::new).take(3).collect();
+        let mut stakes = HashMap::new();
+        for (stake, keypair) in peer_keypairs.iter().enumerate() {
+            let node = ContactInfo::new_localhost(&keypair.pubkey(), timestamp());
+            let value = CrdsValue::new_signed(CrdsData::ContactInfo(node), keypair);
+            let mut gossip_crds = cluster_info.gossip.crds.write().unwrap();
+            gossip_crds.insert(value, timestamp()).unwrap();
+            stakes.insert(keypair.pubkey(), stake as u64);
+        }
+        cluster_info.save_contact_info(&stakes);
+        // Saving again without a bank keeps the saved stakes
+        cluster_info.save_contact_info_with_saved_stakes();
+
+        let mut cluster_info = ClusterInfo::new(
+            ContactInfo::new_localhost(&node_keypair.pubkey(), timestamp()),
+            node_keypair,
+            SocketAddrSpace::Unspecified,
+        );
+        cluster_info.restore_contact_info(contact_info_dir.path(), 0);
+        // Restored peers are ordered by stake, and are not in the crds table
+        // until verified.
+        let restored_peers: Vec<_> = cluster_info
+            .restored_peers
+            .lock()
+            .unwrap()
+            .nodes
+            .iter()
+            .map(|(value, stake)| (value.pubkey(), *stake))
+            .collect();
+        let expected_peers: Vec<_> = peer_keypairs
+            .iter()
+            .enumerate()
+            .rev()
+            .map(|(stake, keypair)| (keypair.pubkey(), stake as u64))
+            .collect();
+        assert_eq!(restored_peers, expected_peers);
+        for keypair in &peer_keypairs {
+            assert!(cluster_info
+                .lookup_contact_info(&keypair.pubkey(), |_| ())
+                .is_none());
+        }
+
+        let mut pings = Vec::new();
+        cluster_info.verify_restored_peers(&mut pings);
+        assert_eq!(pings.len(), peer_keypairs.len());
+
+        // Only the peer which has responded to the ping is inserted.
+        let verified_peer = ContactInfo::new_localhost(&peer_keypairs[1].pubkey(), 0);
+        cluster_info.ping_cache.lock().unwrap().mock_pong(
+            verified_peer.id,
+            verified_peer.gossip,
+            Instant::now(),
+        );
+        let mut pings = Vec::new();
+        cluster_info.verify_restored_peers(&mut pings);
+        assert!(pings.is_empty());
+        assert!(cluster_info
+            .lookup_contact_info(&verified_peer.id, |_| ())
+            .is_some());
+        assert!(cluster_info
+            .lookup_contact_info(&peer_keypairs[0].pubkey(), |_| ())
+            .is_none());
+        assert_eq!(cluster_info.restored_peers.lock().unwrap().nodes.len(), 2);
+    }
+
+    #[test]
+    fn test_restore_legacy_contact_info() {
+        let contact_info_dir = tempfile::tempdir().unwrap();
+        let peer_keypairs: Vec<_> = repeat_with(Keypair::new).take(2).collect();
+        let nodes: Vec<_> = peer_keypairs
+            .iter()
+            .map(|keypair| {
+                let node = ContactInfo::new_localhost(&keypair.pubkey(), timestamp());
+                CrdsValue::new_signed(CrdsData::ContactInfo(node), keypair)
+            })
+            .collect();
+        let file = File::create(contact_info_dir.path().join(LEGACY_SAVED_PEERS_FILENAME)).unwrap();
+        bincode::serialize_into(file, &nodes).unwrap();
+
+        let node_keypair = Arc::new(Keypair::new());
+        let mut cluster_info = ClusterInfo::new(
+            ContactInfo::new_localhost(&node_keypair.pubkey(), timestamp()),
+            node_keypair,
+            SocketAddrSpace::Unspecified,
+        );
+        cluster_info.restore_contact_info(contact_info_dir.path(), 0);
+        let mut restored_peers: Vec<_> = cluster_info
+            .restored_peers
+            .lock()
+            .unwrap()
+            .nodes
+            .iter()
+            .map(|(value, stake)| (value.pubkey(), *stake))
+            .collect();
+        restored_peers.sort_unstable();
+        let mut expected_peers: Vec<_> = peer_keypairs
+            .iter()
+            .map(|keypair| (keypair.pubkey(), 0))
+            .collect();
+        expected_peers.sort_unstable();
+        assert_eq!(restored_peers, expected_peers);
+    }
+
+    #[test]
+    fn test_get_crds_values() {
+        let node_keypair = Arc::new(Keypair::new());
+        let cluster_info = ClusterInfo::new(
+            ContactInfo::new_localhost(&node_keypair.pubkey(), timestamp()),
+            node_keypair.clone(),
+            SocketAddrSpace::Unspecified,
+        );
+        let peer_keypair = Keypair::new();
+        let peer = ContactInfo::new_localhost(&peer_keypair.pubkey(), timestamp());
+        let version = Version::new(peer_keypair.pubkey());
+        {
+            let mut gossip_crds = cluster_info.gossip.crds.write().unwrap();
+            for data in [CrdsData::ContactInfo(peer), CrdsData::Version(version)] {
+                let value = CrdsValue::new_signed(data, &peer_keypair);
+                gossip_crds.insert(value, 1234).unwrap();
+            }
+        }
+        let num_values = cluster_info.gossip.crds.read().unwrap().len();
+        assert_eq!(cluster_info.get_crds_values(None, None).len(), num_values);
+
+        let values = cluster_info.get_crds_values(Some(&peer_keypair.pubkey()), None);
+        assert_eq!(values.len(), 2);
+        assert!(values.iter().all(|(value, local_timestamp)| value.pubkey()
+            == peer_keypair.pubkey()
+            && *local_timestamp == 1234));
+
+        let values = cluster_info.get_crds_values(None, Some("ContactInfo"));
+        let mut origins: Vec<_> = values.iter().map(|(value, _)| value.pubkey()).collect();
+        origins.sort();
+        let mut expected_origins = vec![node_keypair.pubkey(), peer_keypair.pubkey()];
+        expected_origins.sort();
+        assert_eq!(origins, expected_origins);
+
+        let values = cluster_info.get_crds_values(Some(&peer_keypair.pubkey()), Some("Version"));
+        assert_eq!(values.len(), 1);
+        assert_eq!(values[0].0.label().kind(), "Version");
+        assert!(cluster_info
+            .get_crds_values(Some(&peer_keypair.pubkey()), Some("EpochSlots"))
+            .is_empty());
+    }
+
     #[test]
     fn test_repair_peers() {
         let node_keypair = Arc::new(Keypair::new());
@@ -1,5 +1,6 @@
 use {
     crate::crds_gossip::CrdsGossip,
+    serde_derive::{Deserialize, Serialize},
     solana_measure::measure::Measure,
     solana_sdk::pubkey::Pubkey,
     std::{
@@ -11,18 +12,27 @@ use {
 };
 
 #[derive(Default)]
-pub(crate) struct Counter(AtomicU64);
+pub(crate) struct Counter {
+    value: AtomicU64,
+    // Running total since startup, which is not reset when metrics are
+    // submitted.
+    total: AtomicU64,
+}
 
 impl Counter {
     pub(crate) fn add_measure(&self, x: &mut Measure) {
         x.stop();
-        self.0.fetch_add(x.as_us(), Ordering::Relaxed);
+        self.add_relaxed(x.as_us());
     }
     pub(crate) fn add_relaxed(&self, x: u64) {
-        self.0.fetch_add(x, Ordering::Relaxed);
+        self.value.fetch_add(x, Ordering::Relaxed);
+        self.total.fetch_add(x, Ordering::Relaxed);
     }
     fn clear(&self) -> u64 {
-        self.0.swap(0, Ordering::Relaxed)
+        self.value.swap(0, Ordering::Relaxed)
+    }
+    fn total(&self) -> u64 {
+        self.total.load(Ordering::Relaxed)
     }
 }
 
@@ -34,7 +44,7 @@ pub(crate) struct TimedGuard<'a, T> {
 
 pub(crate) struct ScopedTimer<'a> {
     clock: Instant,
-    metric: &'a AtomicU64,
+    metric: &'a Counter,
 }
 
 impl<'a> From<&'a Counter> for ScopedTimer<'a> {
@@ -44,7 +54,7 @@ impl<'a> From<&'a Counter> for ScopedTimer<'a> {
     fn from(counter: &'a Counter) -> Self {
         Self {
             clock: Instant::now(),
-            metric: &counter.0,
+            metric: counter,
         }
     }
 }
@@ -52,7 +62,7 @@ impl<'a> From<&'a Counter> for ScopedTimer<'a> {
 impl Drop for ScopedTimer<'_> {
     fn drop(&mut self) {
         let micros = self.clock.elapsed().as_micros();
-        self.metric.fetch_add(micros as u64, Ordering::Relaxed);
+        self.metric.add_relaxed(micros as u64);
     }
 }
 
@@ -145,6 +155,7 @@ pub(crate) struct GossipStats {
     pub(crate) repair_peers: Counter,
     pub(crate) require_stake_for_gossip_unknown_feature_set: Counter,
     pub(crate) require_stake_for_gossip_unknown_stakes: Counter,
+    pub(crate) restored_peers_verified_count: Counter,
     pub(crate) skip_pull_response_shred_version: Counter,
     pub(crate) skip_pull_shred_version: Counter,
     pub(crate) skip_push_message_shred_version: Counter,
@@ -155,6 +166,45 @@ pub(crate) struct GossipStats {
     pub(crate) verify_gossip_packets_time: Counter,
 }
 
+/// Push and pull message totals since startup, used to inspect gossip on a
+/// running node.
+#[derive(Clone, Debug, Default, PartialEq, Deserialize, Serialize)]
+pub struct GossipPushPullStats {
+    pub push_messages_received: u64,
+    pub push_message_values_received: u64,
+    pub push_messages_sent: u64,
+    pub prune_messages_received: u64,
+    pub prune_messages_sent: u64,
+    pub pull_requests_received: u64,
+    pub pull_requests_sent: u64,
+    pub pull_requests_from_entrypoint: u64,
+    pub pull_responses_received: u64,
+    pub pull_responses_sent: u64,
+    pub pull_response_values_inserted: u64,
+    pub pull_response_values_failed_insert: u64,
+    pub pull_response_values_timed_out: u64,
+}
+
+impl GossipStats {
+    pub(crate) fn push_pull_stats(&self) -> GossipPushPullStats {
+        GossipPushPullStats {
+            push_messages_received: self.packets_received_push_messages_count.total(),
+            push_message_values_received: self.push_message_value_count.total(),
+            push_messages_sent: self.packets_sent_push_messages_count.total(),
+            prune_messages_received: self.packets_received_prune_messages_count.total(),
+            prune_messages_sent: self.packets_sent_prune_messages_count.total(),
+            pull_requests_received: self.packets_received_pull_requests_count.total(),
+            pull_requests_sent: self.packets_sent_pull_requests_count.total(),
+            pull_requests_from_entrypoint: self.pull_from_entrypoint_count.total(),
+            pull_responses_received: self.packets_received_pull_responses_count.total(),
+            pull_responses_sent: self.packets_sent_pull_responses_count.total(),
+            pull_response_values_inserted: self.process_pull_response_success.total(),
+            pull_response_values_failed_insert: self.process_pull_response_fail_insert.total(),
+            pull_response_values_timed_out: self.process_pull_response_fail_timeout.total(),
+        }
+    }
+}
+
 pub(crate) fn submit_gossip_stats(
     stats: &GossipStats,
     gossip: &CrdsGossip,
@@ -353,6 +403,11 @@ pub(crate) fn submit_gossip_stats(
             stats.pull_from_entrypoint_count.clear(),
             i64
         ),
+        (
+            "restored_peers_verified_count",
+            stats.restored_peers_verified_count.clear(),
+            i64
+        ),
         (
             "prune_message_count",
             stats.prune_message_count.clear(),
@@ -326,7 +326,8 @@ impl Crds {
         self.table.is_empty()
     }
 
-    #[cfg(test)]
+    /// Returns all values in the table, in no particular order. Used by
+    /// `ClusterInfo::get_crds_values` to dump the table for inspection.
     pub(crate) fn values(&self) -> impl Iterator<Item = &VersionedCrdsValue> {
         self.table.values()
     }
@@ -486,6 +486,37 @@ impl fmt::Display for CrdsValueLabel {
     }
 }
 
+// Defines CrdsValueLabel::KINDS and CrdsValueLabel::kind from a single list
+// of the label variants, which the match in kind keeps exhaustive.
+macro_rules! impl_crds_value_label_kinds (
+    ($($kind:ident),* $(,)?) => {
+        impl CrdsValueLabel {
+            /// Names of the kinds of values, as returned by `CrdsValueLabel::kind`.
+            pub const KINDS: &'static [&'static str] = &[$(stringify!($kind)),*];
+
+            /// Name of the kind of value this label refers to, e.g. "ContactInfo".
+            pub fn kind(&self) -> &'static str {
+                match self {
+                    $(CrdsValueLabel::$kind(..) => stringify!($kind),)*
+                }
+            }
+        }
+    };
+);
+
+impl_crds_value_label_kinds!(
+    ContactInfo,
+    Vote,
+    LowestSlot,
+    SnapshotHashes,
+    EpochSlots,
+    AccountsHashes,
+    LegacyVersion,
+    Version,
+    NodeInstance,
+    DuplicateShred,
+);
+
 impl CrdsValueLabel {
     pub fn pubkey(&self) -> Pubkey {
         match self {
@@ -662,6 +693,22 @@ mod test {
         std::{cmp::Ordering, iter::repeat_with},
     };
 
+    #[test]
+    fn test_crds_data_json() {
+        let mut rng = rand::thread_rng();
+        let mut values: Vec<_> = repeat_with(|| CrdsData::new_rand(&mut rng, None))
+            .take(32)
+            .collect();
+        values.push(CrdsData::Vote(
+            0,
+            Vote::new(Pubkey::default(), test_tx(), 0),
+        ));
+        for data in values {
+            let json = serde_json::to_value(&data).unwrap();
+            assert_eq!(serde_json::from_value::<CrdsData>(json).unwrap(), data);
+        }
+    }
+
     #[test]
     fn test_keys_and_values() {
         let v = CrdsValue::new_unsigned(CrdsData::ContactInfo(ContactInfo::default()));
@@ -2,7 +2,7 @@
 use clap::{value_t, value_t_or_exit, App, AppSettings, Arg, ArgMatches, SubCommand};
 use solana_clap_utils::{
     input_parsers::pubkey_of,
-    input_validators::{is_slot, is_valid_pubkey},
+    input_validators::{is_parsable, is_slot, is_valid_pubkey},
 };
 use solana_cli_output::{
     display::println_transaction, CliBlock, CliTransaction, CliTransactionConfirmation,
@@ -10,24 +10,44 @@ use solana_cli_output::{
 };
 use solana_ledger::{blockstore::Blockstore, blockstore_db::AccessType};
 use solana_sdk::{clock::Slot, pubkey::Pubkey, signature::Signature};
+use solana_storage_bigtable::{BigTableLedgerStorage, LedgerStorage, LocalLedgerStorage, RowKey};
 use solana_transaction_status::{ConfirmedBlock, EncodedTransaction, UiTransactionEncoding};
 use std::{
+    collections::HashMap,
+    fs::{self, File},
     path::Path,
     process::exit,
     result::Result,
     sync::{atomic::AtomicBool, Arc},
 };
 
+async fn open_ledger_storage(
+    local_storage: Option<&Path>,
+    read_only: bool,
+) -> Result<Arc<dyn LedgerStorage>, Box<dyn std::error::Error>> {
+    let ledger_storage: Arc<dyn LedgerStorage> = match local_storage {
+        Some(path) => Arc::new(
+            LocalLedgerStorage::open(path, read_only)
+                .map_err(|err| format!("Failed to open local storage: {:?}", err))?,
+        ),
+        None => Arc::new(
+            BigTableLedgerStorage::new(read_only, None)
+                .await
+                .map_err(|err| format!("Failed to connect to storage: {:?}", err))?,
+        ),
+    };
+    Ok(ledger_storage)
+}
+
 async fn upload(
+    local_storage: Option<&Path>,
     blockstore: Blockstore,
     starting_slot: Slot,
     ending_slot: Option<Slot>,
     allow_missing_metadata: bool,
     force_reupload: bool,
 ) -> Result<(), Box<dyn std::error::Error>> {
-    let bigtable = solana_storage_bigtable::LedgerStorage::new(false, None)
-        .await
-        .map_err(|err| format!("Failed to connect to storage: {:?}", err))?;
+    let bigtable = open_ledger_storage(local_storage, false).await?;
 
     solana_ledger::bigtable_upload::upload_confirmed_blocks(
         Arc::new(blockstore),
@@ -41,8 +61,10 @@ async fn upload(
     .await
 }
 
-async fn first_available_block() -> Result<(), Box<dyn std::error::Error>> {
-    let bigtable = solana_storage_bigtable::LedgerStorage::new(true, None).await?;
+async fn first_available_block(
+    local_storage: Option<&Path>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let bigtable = open_ledger_storage(local_storage, true).await?;
     match bigtable.get_first_available_block().await? {
         Some(block) => println!("{}", block),
         None => println!("No blocks available"),
@@ -51,10 +73,12 @@ async fn first_available_block() -> Result<(), Box<dyn std::error::Error>> {
     Ok(())
 }
 
-async fn block(slot: Slot, output_format: OutputFormat) -> Result<(), Box<dyn std::error::Error>> {
-    let bigtable = solana_storage_bigtable::LedgerStorage::new(false, None)
-        .await
-        .map_err(|err| format!("Failed to connect to storage: {:?}", err))?;
+async fn block(
+    local_storage: Option<&Path>,
+    slot: Slot,
+    output_format: OutputFormat,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let bigtable = open_ledger_storage(local_storage, false).await?;
 
     let block = bigtable.get_confirmed_block(slot).await?;
 
@@ -66,10 +90,12 @@ async fn block(slot: Slot, output_format: OutputFormat) -> Result<(), Box<dyn st
     Ok(())
 }
 
-async fn blocks(starting_slot: Slot, limit: usize) -> Result<(), Box<dyn std::error::Error>> {
-    let bigtable = solana_storage_bigtable::LedgerStorage::new(false, None)
-        .await
-        .map_err(|err| format!("Failed to connect to storage: {:?}", err))?;
+async fn blocks(
+    local_storage: Option<&Path>,
+    starting_slot: Slot,
+    limit: usize,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let bigtable = open_ledger_storage(local_storage, false).await?;
 
     let slots = bigtable.get_confirmed_blocks(starting_slot, limit).await?;
     println!("{:?}", slots);
@@ -79,13 +105,12 @@ async fn blocks(starting_slot: Slot, limit: usize) -> Result<(), Box<dyn std::er
 }
 
 async fn confirm(
+    local_storage: Option<&Path>,
     signature: &Signature,
     verbose: bool,
     output_format: OutputFormat,
 ) -> Result<(), Box<dyn std::error::Error>> {
-    let bigtable = solana_storage_bigtable::LedgerStorage::new(false, None)
-        .await
-        .map_err(|err| format!("Failed to connect to storage: {:?}", err))?;
+    let bigtable = open_ledger_storage(local_storage, false).await?;
 
     let transaction_status = bigtable.get_signature_status(signature).await?;
 
@@ -124,6 +149,7 @@ async fn confirm(
 }
 
 pub async fn transaction_history(
+    local_storage: Option<&Path>,
     address: &Pubkey,
     mut limit: usize,
     mut before: Option<Signature>,
@@ -132,7 +158,7 @@ pub async fn transaction_history(
     show_transactions: bool,
     query_chunk_size: usize,
 ) -> Result<(), Box<dyn std::error::Error>> {
-    let bigtable = solana_storage_bigtable::LedgerStorage::new(true, None).await?;
+    let bigtable = open_ledger_storage(local_storage, true).await?;
 
     let mut loaded_block: Option<(Slot, ConfirmedBlock)> = None;
     while limit > 0 {
@@ -212,6 +238,73 @@ pub async fn transaction_history(
     Ok(())
 }
 
+// The last row key migrated, by table
+type MigrateCheckpoint = HashMap<String, RowKey>;
+
+fn write_migrate_checkpoint(
+    checkpoint_file: &Path,
+    checkpoint: &MigrateCheckpoint,
+) -> Result<(), Box<dyn std::error::Error>> {
+    // Replace the file whole so an interruption never leaves a partial checkpoint
+    let tmp_file = checkpoint_file.with_extension("tmp");
+    fs::write(&tmp_file, serde_json::to_vec(checkpoint)?)?;
+    fs::rename(&tmp_file, checkpoint_file)?;
+    Ok(())
+}
+
+async fn migrate(
+    local_storage: Option<&Path>,
+    table_names: Vec<&str>,
+    batch_size: usize,
+    checkpoint_file: Option<&Path>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let bigtable = open_ledger_storage(local_storage, false).await?;
+
+    let mut checkpoint: MigrateCheckpoint = match checkpoint_file {
+        Some(checkpoint_file) if checkpoint_file.exists() => {
+            serde_json::from_reader(File::open(checkpoint_file)?)?
+        }
+        _ => MigrateCheckpoint::default(),
+    };
+
+    for table_name in table_names {
+        if let Some(row_key) = checkpoint.get(table_name) {
+            println!("{}: resuming after row {}", table_name, row_key);
+        }
+        let mut rows_read = 0;
+        let mut rows_migrated = 0;
+        loop {
+            let result = bigtable
+                .migrate_rows_to_protobuf(
+                    table_name,
+                    checkpoint.get(table_name).cloned(),
+                    batch_size as i64,
+                )
+                .await?;
+            let last_row_key = match result.last_row_key {
+                Some(last_row_key) => last_row_key,
+                None => break,
+            };
+            rows_read += result.rows_read;
+            rows_migrated += result.rows_migrated;
+            println!(
+                "{}: {} rows read, {} rows migrated, up to row {}",
+                table_name, rows_read, rows_migrated, last_row_key
+            );
+
+            checkpoint.insert(table_name.to_string(), last_row_key);
+            if let Some(checkpoint_file) = checkpoint_file {
+                write_migrate_checkpoint(checkpoint_file, &checkpoint)?;
+            }
+        }
+        println!(
+            "{}: done, {} rows read, {} rows migrated",
+            table_name, rows_read, rows_migrated
+        );
+    }
+    Ok(())
+}
+
 pub trait BigTableSubCommand {
     fn bigtable_subcommand(self) -> Self;
 }
@@ -223,6 +316,16 @@ impl BigTableSubCommand for App<'_, '_> {
                 .about("Ledger data on a BigTable instance")
                 .setting(AppSettings::InferSubcommands)
                 .setting(AppSettings::SubcommandRequiredElseHelp)
+                .arg(
+                    Arg::with_name("local_storage")
+                        .long("local-storage")
+                        .value_name("DIR")
+                        .takes_value(true)
+                        .help(
+                            "Use the local ledger storage database at DIR \
+                             instead of the BigTable instance",
+                        ),
+                )
                 .subcommand(
                     SubCommand::with_name("upload")
                         .about("Upload the ledger to BigTable")
@@ -263,6 +366,47 @@ impl BigTableSubCommand for App<'_, '_> {
                                 ),
                         ),
                 )
+                .subcommand(
+                    SubCommand::with_name("migrate")
+                        .about("Rewrite legacy bincode rows as protobuf")
+                        .arg(
+                            Arg::with_name("table")
+                                .long("table")
+                                .value_name("TABLE")
+                                .takes_value(true)
+                                .multiple(true)
+                                .possible_values(&["blocks", "tx-by-addr"])
+                                .default_value("blocks,tx-by-addr")
+                                .use_delimiter(true)
+                                .help("Tables to migrate"),
+                        )
+                        .arg(
+                            Arg::with_name("batch_size")
+                                .long("batch-size")
+                                .value_name("ROWS")
+                                .validator(|value| {
+                                    is_parsable::<usize>(value.clone())?;
+                                    if value.parse::<usize>() == Ok(0) {
+                                        Err(String::from("batch size must be greater than zero"))
+                                    } else {
+                                        Ok(())
+                                    }
+                                })
+                                .takes_value(true)
+                                .default_value("1000")
+                                .help("Number of rows to migrate at once"),
+                        )
+                        .arg(
+                            Arg::with_name("checkpoint_file")
+                                .long("checkpoint-file")
+                                .value_name("FILE")
+                                .takes_value(true)
+                                .help(
+                                    "Record progress in FILE after every batch, and resume \
+                                     from it if it exists",
+                                ),
+                        ),
+                )
                 .subcommand(
                     SubCommand::with_name("first-available-block")
                         .about("Get the first available block in the storage"),
@@ -386,6 +530,7 @@ pub fn bigtable_process_command(ledger_path: &Path, matches: &ArgMatches<'_>) {
 
     let verbose = matches.is_present("verbose");
     let output_format = OutputFormat::from_matches(matches, "output_format", verbose);
+    let local_storage = matches.value_of("local_storage").map(Path::new);
 
     let future = match matches.subcommand() {
         ("upload", Some(arg_matches)) => {
@@ -397,6 +542,7 @@ pub fn bigtable_process_command(ledger_path: &Path, matches: &ArgMatches<'_>) {
                 crate::open_blockstore(ledger_path, AccessType::TryPrimaryThenSecondary, None);
 
             runtime.block_on(upload(
+                local_storage,
                 blockstore,
                 starting_slot,
                 ending_slot,
@@ -404,16 +550,30 @@ pub fn bigtable_process_command(ledger_path: &Path, matches: &ArgMatches<'_>) {
                 force_reupload,
             ))
         }
-        ("first-available-block", Some(_arg_matches)) => runtime.block_on(first_available_block()),
+        ("migrate", Some(arg_matches)) => {
+            let table_names = arg_matches.values_of("table").unwrap().collect();
+            let batch_size = value_t_or_exit!(arg_matches, "batch_size", usize);
+            let checkpoint_file = arg_matches.value_of("checkpoint_file").map(Path::new);
+
+            runtime.block_on(migrate(
+                local_storage,
+                table_names,
+                batch_size,
+                checkpoint_file,
+            ))
+        }
+        ("first-available-block", Some(_arg_matches)) => {
+            runtime.block_on(first_available_block(local_storage))
+        }
         ("block", Some(arg_matches)) => {
             let slot = value_t_or_exit!(arg_matches, "slot", Slot);
-            runtime.block_on(block(slot, output_format))
+            runtime.block_on(block(local_storage, slot, output_format))
         }
         ("blocks", Some(arg_matches)) => {
             let starting_slot = value_t_or_exit!(arg_matches, "starting_slot", Slot);
             let limit = value_t_or_exit!(arg_matches, "limit", usize);
 
-            runtime.block_on(blocks(starting_slot, limit))
+            runtime.block_on(blocks(local_storage, starting_slot, limit))
         }
         ("confirm", Some(arg_matches)) => {
             let signature = arg_matches
@@ -422,7 +582,7 @@ pub fn bigtable_process_command(ledger_path: &Path, matches: &ArgMatches<'_>) {
                 .parse()
                 .expect("Invalid signature");
 
-            runtime.block_on(confirm(&signature, verbose, output_format))
+            runtime.block_on(confirm(local_storage, &signature, verbose, output_format))
         }
         ("transaction-history", Some(arg_matches)) => {
             let address = pubkey_of(arg_matches, "address").unwrap();
@@ -437,6 +597,7 @@ pub fn bigtable_process_command(ledger_path: &Path, matches: &ArgMatches<'_>) {
             let show_transactions = arg_matches.is_present("show_transactions");
 
             runtime.block_on(transaction_history(
+                local_storage,
                 &address,
                 limit,
                 before,
@@ -1,7 +1,7 @@
 #![allow(clippy::integer_arithmetic)]
 use clap::{
     crate_description, crate_name, value_t, value_t_or_exit, values_t_or_exit, App, AppSettings,
-    Arg, ArgMatches, SubCommand,
+    Arg, ArgGroup, ArgMatches, SubCommand,
 };
 use itertools::Itertools;
 use log::*;
@@ -14,21 +14,29 @@ use solana_clap_utils::{
         is_bin, is_parsable, is_pubkey, is_pubkey_or_keypair, is_slot, is_valid_percentage,
     },
 };
+use solana_core::cluster_nodes::get_turbine_tree_from_stakes;
 use solana_core::cost_model::CostModel;
 use solana_core::cost_tracker::CostTracker;
 use solana_entry::entry::Entry;
+use solana_gossip::cluster_info::DATA_PLANE_FANOUT;
 use solana_ledger::{
     ancestor_iterator::AncestorIterator,
     bank_forks_utils,
     blockstore::{create_new_ledger, Blockstore, PurgeType},
+    blockstore_account_write_recorder::BlockstoreAccountWriteRecorder,
     blockstore_db::{self, AccessType, BlockstoreRecoveryMode, Column, Database},
     blockstore_processor::ProcessOptions,
-    shred::Shred,
+    duplicate_slot_evidence::{DuplicateSlotEvidence, DuplicateSlotEvidenceError},
+    leader_schedule_cache::LeaderScheduleCache,
+    shred::{self, Shred},
 };
+use solana_measure::measure::Measure;
 use solana_runtime::{
+    account_write_log::{AccountWrite, AccountWriteRecorder},
     accounts_index::AccountsIndexConfig,
     bank::{Bank, RewardCalculationEvent},
     bank_forks::BankForks,
+    compute_profiler::ComputeProfile,
     hardened_unpack::{open_genesis_config, MAX_GENESIS_ARCHIVE_UNPACKED_SIZE},
     snapshot_archive_info::SnapshotArchiveInfoGetter,
     snapshot_config::SnapshotConfig,
@@ -65,7 +73,7 @@ use std::{
     path::{Path, PathBuf},
     process::{exit, Command, Stdio},
     str::FromStr,
-    sync::{Arc, RwLock},
+    sync::{Arc, Mutex, RwLock},
 };
 
 mod bigtable;
@@ -754,6 +762,34 @@ fn load_bank_forks(
     )
 }
 
+// Load the root bank without replaying any slots. Its leader schedule is all that is needed
+// to look up the slot leaders.
+fn load_leader_schedule(
+    arg_matches: &ArgMatches,
+    blockstore: &Blockstore,
+    snapshot_archive_path: Option<PathBuf>,
+) -> (Arc<Bank>, LeaderScheduleCache) {
+    let process_options = ProcessOptions {
+        dev_halt_at_slot: Some(0),
+        new_hard_forks: hardforks_of(arg_matches, "hard_forks"),
+        poh_verify: false,
+        ..ProcessOptions::default()
+    };
+    let genesis_config = open_genesis_config_by(blockstore.ledger_path(), arg_matches);
+    let (bank_forks, leader_schedule_cache, _snapshot_hash) = load_bank_forks(
+        arg_matches,
+        &genesis_config,
+        blockstore,
+        process_options,
+        snapshot_archive_path,
+    )
+    .unwrap_or_else(|err| {
+        eprintln!("Failed to load ledger: {:?}", err);
+        exit(1);
+    });
+    (bank_forks.root_bank(), leader_schedule_cache)
+}
+
 fn compute_slot_cost(blockstore: &Blockstore, slot: Slot) -> Result<(), String> {
     if blockstore.is_dead(slot) {
         return Err("Dead slot".to_string());
@@ -936,6 +972,7 @@ fn main() {
         .help("Output snapshot version");
 
     let default_max_snapshot_to_retain = &DEFAULT_MAX_FULL_SNAPSHOT_ARCHIVES_TO_RETAIN.to_string();
+    let default_data_plane_fanout = DATA_PLANE_FANOUT.to_string();
     let maximum_snapshots_to_retain_arg = Arg::with_name("maximum_snapshots_to_retain")
         .long("maximum-snapshots-to-retain")
         .value_name("NUMBER")
@@ -1069,6 +1106,83 @@ fn main() {
             .arg(&starting_slot_arg)
             .about("Print all the duplicate slots in the ledger")
         )
+        .subcommand(
+            SubCommand::with_name("account-writes")
+            .about("Print the account writes recorded in the ledger, either those of a slot \
+                    or the latest writes of an account")
+            .arg(
+                Arg::with_name("slot")
+                    .long("slot")
+                    .value_name("SLOT")
+                    .takes_value(true)
+                    .validator(is_slot)
+                    .help("Print the account writes of this slot"),
+            )
+            .arg(
+                Arg::with_name("pubkey")
+                    .long("pubkey")
+                    .value_name("PUBKEY")
+                    .takes_value(true)
+                    .validator(is_pubkey)
+                    .help("Print the latest writes of this account in rooted slots"),
+            )
+            .group(
+                ArgGroup::with_name("account_writes_query")
+                    .args(&["slot", "pubkey"])
+                    .required(true),
+            )
+            .arg(
+                Arg::with_name("before_slot")
+                    .long("before-slot")
+                    .value_name("SLOT")
+                    .takes_value(true)
+                    .requires("pubkey")
+                    .validator(is_slot)
+                    .help("Only print writes of the account in slots before this one"),
+            )
+            .arg(
+                Arg::with_name("limit")
+                    .long("limit")
+                    .value_name("NUM")
+                    .takes_value(true)
+                    .default_value("20")
+                    .requires("pubkey")
+                    .validator(is_parsable::<usize>)
+                    .help("Maximum number of writes of the account to print"),
+            )
+        )
+        .subcommand(
+            SubCommand::with_name("duplicate-slot-proofs")
+            .about("Print the duplicate slot proofs in the ledger, \
+                    checking each shred signature against the slot leader")
+            .arg(&starting_slot_arg)
+            .arg(&hard_forks_arg)
+            .arg(&max_genesis_archive_unpacked_size_arg)
+            .arg(
+                Arg::with_name("output_directory")
+                    .long("output-dir")
+                    .value_name("DIR")
+                    .takes_value(true)
+                    .help("Export each proof to DIR/duplicate-slot-<SLOT>.json, \
+                           which can be checked with verify-duplicate-slot-proof"),
+            )
+        )
+        .subcommand(
+            SubCommand::with_name("verify-duplicate-slot-proof")
+            .about("Verify exported duplicate slot proofs against the leader schedule of the \
+                    ledger")
+            .arg(&hard_forks_arg)
+            .arg(&max_genesis_archive_unpacked_size_arg)
+            .arg(
+                Arg::with_name("proof_files")
+                    .index(1)
+                    .value_name("FILE")
+                    .takes_value(true)
+                    .multiple(true)
+                    .required(true)
+                    .help("Proof files written by duplicate-slot-proofs"),
+            )
+        )
         .subcommand(
             SubCommand::with_name("set-dead-slot")
             .about("Mark one or more slots dead")
@@ -1190,6 +1304,29 @@ fn main() {
                     .takes_value(false)
                     .help("After verifying the ledger, print some information about the account stores"),
             )
+            .arg(
+                Arg::with_name("record_account_writes")
+                    .long("record-account-writes")
+                    .takes_value(false)
+                    .help("Record the accounts written by each replayed transaction in the \
+                           ledger, for use with the account-writes command"),
+            )
+            .arg(
+                Arg::with_name("record_account_writes_data")
+                    .long("record-account-writes-data")
+                    .takes_value(false)
+                    .requires("record_account_writes")
+                    .help("Also record account data along with the account writes"),
+            )
+            .arg(
+                Arg::with_name("compute_profile")
+                    .long("compute-profile")
+                    .value_name("FILENAME")
+                    .takes_value(true)
+                    .help("Profile the compute units consumed by BPF programs in the replayed slots \
+                           and write them to FILENAME as flamegraph-compatible folded stacks. \
+                           Disables the BPF JIT"),
+            )
         ).subcommand(
             SubCommand::with_name("graph")
             .about("Create a Graphviz rendering of the ledger")
@@ -1210,6 +1347,76 @@ fn main() {
                     .takes_value(true)
                     .help("Output file"),
             )
+        ).subcommand(
+            SubCommand::with_name("turbine-tree")
+            .about("Show the turbine retransmit tree of a shred")
+            .arg(&no_snapshot_arg)
+            .arg(&account_paths_arg)
+            .arg(&hard_forks_arg)
+            .arg(&max_genesis_archive_unpacked_size_arg)
+            .arg(
+                Arg::with_name("slot")
+                    .index(1)
+                    .value_name("SLOT")
+                    .validator(is_slot)
+                    .takes_value(true)
+                    .required(true)
+                    .help("Slot of the shred"),
+            )
+            .arg(
+                Arg::with_name("shred_index")
+                    .index(2)
+                    .value_name("INDEX")
+                    .validator(is_parsable::<u32>)
+                    .takes_value(true)
+                    .required(true)
+                    .help("Index of the data shred within the slot"),
+            )
+            .arg(
+                Arg::with_name("tree_filename")
+                    .index(3)
+                    .value_name("FILENAME")
+                    .takes_value(true)
+                    .help("Write the tree to FILENAME: JSON if it ends in .json, \
+                           rendered with Graphviz if it ends in .pdf or .png, DOT otherwise"),
+            )
+            .arg(
+                Arg::with_name("node")
+                    .long("node")
+                    .value_name("PUBKEY")
+                    .validator(is_pubkey)
+                    .takes_value(true)
+                    .help("Show the position of this node in the tree"),
+            )
+            .arg(
+                Arg::with_name("slot_leader")
+                    .long("slot-leader")
+                    .value_name("PUBKEY")
+                    .validator(is_pubkey)
+                    .takes_value(true)
+                    .help("Slot leader [default: from the leader schedule]"),
+            )
+            .arg(
+                Arg::with_name("stake_distribution")
+                    .long("stake-distribution")
+                    .value_name("FILE")
+                    .takes_value(true)
+                    .help("JSON object mapping node pubkeys to stake, \
+                           to use instead of the staked nodes of the slot's epoch"),
+            )
+            .arg(
+                Arg::with_name("fanout")
+                    .long("fanout")
+                    .value_name("NUM")
+                    .validator(|value| match value.parse::<usize>() {
+                        Ok(0) => Err("fanout must be greater than 0".to_string()),
+                        Ok(_) => Ok(()),
+                        Err(err) => Err(format!("error parsing '{}': {}", value, err)),
+                    })
+                    .takes_value(true)
+                    .default_value(&default_data_plane_fanout)
+                    .help("Turbine neighborhood size"),
+            )
         ).subcommand(
             SubCommand::with_name("create-snapshot")
             .about("Create a new ledger snapshot")
@@ -1358,6 +1565,86 @@ fn main() {
                     .help("Exclude account data (useful for large number of accounts)"),
             )
             .arg(&max_genesis_archive_unpacked_size_arg)
+        ).subcommand(
+            SubCommand::with_name("rent-paying-accounts")
+            .about("Print the accounts that are charged rent after processing in the ledger, \
+                    grouped by owner")
+            .arg(&no_snapshot_arg)
+            .arg(&account_paths_arg)
+            .arg(&halt_at_slot_arg)
+            .arg(&hard_forks_arg)
+            .arg(&max_genesis_archive_unpacked_size_arg)
+            .arg(
+                Arg::with_name("owner")
+                    .long("owner")
+                    .value_name("PUBKEY")
+                    .takes_value(true)
+                    .validator(is_pubkey)
+                    .help("Only print accounts owned by this program"),
+            )
+        ).subcommand(
+            SubCommand::with_name("storage-report")
+            .about("Print the fragmentation of the accounts storages after processing in the ledger")
+            .arg(&no_snapshot_arg)
+            .arg(&account_paths_arg)
+            .arg(&halt_at_slot_arg)
+            .arg(&hard_forks_arg)
+            .arg(&max_genesis_archive_unpacked_size_arg)
+            .arg(
+                Arg::with_name("limit")
+                    .long("limit")
+                    .value_name("COUNT")
+                    .takes_value(true)
+                    .validator(is_parsable::<usize>)
+                    .default_value("20")
+                    .help("Number of the sparsest storages to list"),
+            )
+            .arg(
+                Arg::with_name("shrink_to_alive_ratio")
+                    .long("shrink-to-alive-ratio")
+                    .value_name("RATIO")
+                    .takes_value(true)
+                    .validator(is_parsable::<f64>)
+                    .help("Then shrink the sparsest storages until this ratio of their bytes \
+                           is alive, and print the report again"),
+            )
+        ).subcommand(
+            SubCommand::with_name("account-proof")
+            .about("Print a proof that an account is included in the accounts hash \
+                    after processing in the ledger")
+            .arg(&no_snapshot_arg)
+            .arg(&account_paths_arg)
+            .arg(&halt_at_slot_arg)
+            .arg(&hard_forks_arg)
+            .arg(&max_genesis_archive_unpacked_size_arg)
+            .arg(
+                Arg::with_name("pubkey")
+                    .index(1)
+                    .value_name("PUBKEY")
+                    .takes_value(true)
+                    .required(true)
+                    .validator(is_pubkey)
+                    .help("The account to prove"),
+            )
+        ).subcommand(
+            SubCommand::with_name("verify-accounts-hash")
+            .about("Recalculate the accounts hash from the account storages and check it. \
+                    A snapshot is checked while it loads; otherwise the hash of the bank \
+                    after processing in the ledger is checked against the accounts index. \
+                    An interrupted run resumes from the pubkey bins it finished")
+            .arg(&no_snapshot_arg)
+            .arg(&account_paths_arg)
+            .arg(&halt_at_slot_arg)
+            .arg(&hard_forks_arg)
+            .arg(&max_genesis_archive_unpacked_size_arg)
+            .arg(
+                Arg::with_name("accounts_hash_cache_path")
+                    .long("accounts-hash-cache-path")
+                    .value_name("DIR")
+                    .takes_value(true)
+                    .help("Keep the finished pubkey bins of the calculation in DIR \
+                           [default: LEDGER/accounts_hash_cache]"),
+            )
         ).subcommand(
             SubCommand::with_name("capitalization")
             .about("Print capitalization (aka, total supply) while checksumming it")
@@ -1807,6 +2094,178 @@ fn main() {
                 println!("{}", slot);
             }
         }
+        ("account-writes", Some(arg_matches)) => {
+            let blockstore = open_blockstore(
+                &ledger_path,
+                AccessType::TryPrimaryThenSecondary,
+                wal_recovery_mode,
+            );
+            let print_write = |write: &AccountWrite| {
+                print!(
+                    "  {} lamports: {} hash: {}",
+                    write.pubkey, write.lamports, write.hash
+                );
+                if let Some(data) = &write.data {
+                    print!(" data: {} bytes", data.len());
+                }
+                println!();
+            };
+            if let Ok(slot) = value_t!(arg_matches, "slot", Slot) {
+                let writes = blockstore.get_account_writes(slot).unwrap_or_else(|err| {
+                    eprintln!("Failed to read account writes of slot {}: {:?}", slot, err);
+                    exit(1);
+                });
+                if writes.is_empty() {
+                    println!("No account writes recorded for slot {}", slot);
+                } else if !blockstore.is_root(slot) {
+                    println!("Slot {} is not rooted", slot);
+                }
+                for transaction_writes in writes {
+                    println!(
+                        "{} (ordinal {}):",
+                        transaction_writes.signature, transaction_writes.ordinal
+                    );
+                    transaction_writes.accounts.iter().for_each(print_write);
+                }
+            } else {
+                let pubkey = pubkey_of(arg_matches, "pubkey").unwrap();
+                let before_slot = value_t!(arg_matches, "before_slot", Slot).ok();
+                let limit = value_t_or_exit!(arg_matches, "limit", usize);
+                let writes = blockstore
+                    .find_account_writes(&pubkey, before_slot, limit)
+                    .unwrap_or_else(|err| {
+                        eprintln!("Failed to read account writes of {}: {:?}", pubkey, err);
+                        exit(1);
+                    });
+                if writes.is_empty() {
+                    println!("No account writes recorded for {}", pubkey);
+                }
+                for (slot, signature, write) in writes {
+                    println!("slot {}: {}", slot, signature);
+                    print_write(&write);
+                }
+            }
+        }
+        ("duplicate-slot-proofs", Some(arg_matches)) => {
+            let starting_slot = value_t_or_exit!(arg_matches, "starting_slot", Slot);
+            let output_directory = arg_matches.value_of("output_directory").map(PathBuf::from);
+            if let Some(output_directory) = &output_directory {
+                fs::create_dir_all(output_directory).unwrap_or_else(|err| {
+                    eprintln!(
+                        "Unable to create output directory {}: {}",
+                        output_directory.display(),
+                        err
+                    );
+                    exit(1);
+                });
+            }
+
+            let blockstore = open_blockstore(
+                &ledger_path,
+                AccessType::TryPrimaryThenSecondary,
+                wal_recovery_mode,
+            );
+            let (root_bank, leader_schedule_cache) =
+                load_leader_schedule(arg_matches, &blockstore, snapshot_archive_path);
+
+            for slot in blockstore.duplicate_slots_iterator(starting_slot).unwrap() {
+                let proof = match blockstore.get_duplicate_slot(slot) {
+                    Some(proof) => proof,
+                    None => continue,
+                };
+                let leader = leader_schedule_cache.slot_leader_at(slot, Some(&root_bank));
+                let evidence = match DuplicateSlotEvidence::new(slot, &proof, leader) {
+                    Ok(evidence) => evidence,
+                    Err(err) => {
+                        eprintln!(
+                            "Slot {}: unable to decode duplicate slot proof: {}",
+                            slot, err
+                        );
+                        continue;
+                    }
+                };
+                println!("Slot {}", slot);
+                println!(
+                    "  Leader: {}",
+                    evidence.leader.as_deref().unwrap_or("unknown")
+                );
+                for (i, shred) in [&evidence.shred1, &evidence.shred2].iter().enumerate() {
+                    println!(
+                        "  Shred {}: index={} type={} version={} signature={} verified={}",
+                        i + 1,
+                        shred.index,
+                        shred.shred_type,
+                        shred.version,
+                        shred.signature,
+                        shred
+                            .signature_verified
+                            .map(|verified| verified.to_string())
+                            .unwrap_or_else(|| "unknown".to_string()),
+                    );
+                }
+                let result = leader
+                    .ok_or(DuplicateSlotEvidenceError::UnknownSlotLeader)
+                    .and_then(|leader| evidence.verify(&leader));
+                match result {
+                    Ok(()) => println!("  Proof: valid"),
+                    Err(err) => println!("  Proof: invalid ({})", err),
+                }
+                if let Some(output_directory) = &output_directory {
+                    let path = output_directory.join(format!("duplicate-slot-{}.json", slot));
+                    let result = serde_json::to_string_pretty(&evidence)
+                        .map_err(|err| err.to_string())
+                        .and_then(|json| fs::write(&path, json).map_err(|err| err.to_string()));
+                    match result {
+                        Ok(()) => println!("  Exported to {}", path.display()),
+                        Err(err) => {
+                            eprintln!("Unable to write {}: {}", path.display(), err);
+                            exit(1);
+                        }
+                    }
+                }
+            }
+        }
+        ("verify-duplicate-slot-proof", Some(arg_matches)) => {
+            let blockstore = open_blockstore(
+                &ledger_path,
+                AccessType::TryPrimaryThenSecondary,
+                wal_recovery_mode,
+            );
+            let (root_bank, leader_schedule_cache) =
+                load_leader_schedule(arg_matches, &blockstore, snapshot_archive_path);
+            let mut num_invalid = 0;
+            for path in values_t_or_exit!(arg_matches, "proof_files", PathBuf) {
+                let result = fs::read(&path)
+                    .map_err(|err| err.to_string())
+                    .and_then(|data| {
+                        serde_json::from_slice::<DuplicateSlotEvidence>(&data)
+                            .map_err(|err| err.to_string())
+                    })
+                    .and_then(|evidence| {
+                        let leader = leader_schedule_cache
+                            .slot_leader_at(evidence.slot, Some(&root_bank))
+                            .ok_or(DuplicateSlotEvidenceError::UnknownSlotLeader)
+                            .and_then(|leader| evidence.verify(&leader).map(|()| leader))
+                            .map_err(|err| err.to_string())?;
+                        Ok((evidence.slot, leader))
+                    });
+                match result {
+                    Ok((slot, leader)) => println!(
+                        "{}: valid, leader {} produced duplicate shreds in slot {}",
+                        path.display(),
+                        leader,
+                        slot
+                    ),
+                    Err(err) => {
+                        println!("{}: invalid, {}", path.display(), err);
+                        num_invalid += 1;
+                    }
+                }
+            }
+            if num_invalid > 0 {
+                exit(1);
+            }
+        }
         ("set-dead-slot", Some(arg_matches)) => {
             let slots = values_t_or_exit!(arg_matches, "slots", Slot);
             let blockstore =
@@ -1886,13 +2345,39 @@ fn main() {
         ("verify", Some(arg_matches)) => {
             let accounts_index_config = value_t!(arg_matches, "accounts_index_bins", usize)
                 .ok()
-                .map(|bins| AccountsIndexConfig { bins: Some(bins) });
+                .map(|bins| AccountsIndexConfig {
+                    bins: Some(bins),
+                    ..AccountsIndexConfig::default()
+                });
+
+            let compute_profile_file = value_t!(arg_matches, "compute_profile", String).ok();
+            let compute_profile = compute_profile_file
+                .as_ref()
+                .map(|_| Arc::new(Mutex::new(ComputeProfile::default())));
+
+            let record_account_writes = arg_matches.is_present("record_account_writes");
+            let blockstore = Arc::new(open_blockstore(
+                &ledger_path,
+                if record_account_writes {
+                    AccessType::PrimaryOnly
+                } else {
+                    AccessType::TryPrimaryThenSecondary
+                },
+                wal_recovery_mode,
+            ));
+            let account_write_recorder = record_account_writes.then(|| {
+                Arc::new(BlockstoreAccountWriteRecorder::new(
+                    blockstore.clone(),
+                    arg_matches.is_present("record_account_writes_data"),
+                )) as Arc<dyn AccountWriteRecorder>
+            });
 
             let process_options = ProcessOptions {
                 dev_halt_at_slot: value_t!(arg_matches, "halt_at_slot", Slot).ok(),
                 new_hard_forks: hardforks_of(arg_matches, "hard_forks"),
                 poh_verify: !arg_matches.is_present("skip_poh_verify"),
-                bpf_jit: !matches.is_present("no_bpf_jit"),
+                // Per-function counts come from the interpreter's instruction trace
+                bpf_jit: !matches.is_present("no_bpf_jit") && compute_profile.is_none(),
                 accounts_db_caching_enabled: !arg_matches.is_present("no_accounts_db_caching"),
                 limit_load_slot_count_from_snapshot: value_t!(
                     arg_matches,
@@ -1905,6 +2390,8 @@ fn main() {
                 allow_dead_slots: arg_matches.is_present("allow_dead_slots"),
                 accounts_db_test_hash_calculation: arg_matches
                     .is_present("accounts_db_test_hash_calculation"),
+                compute_profile: compute_profile.clone(),
+                account_write_recorder,
                 ..ProcessOptions::default()
             };
             let print_accounts_stats = arg_matches.is_present("print_accounts_stats");
@@ -1913,11 +2400,6 @@ fn main() {
                 open_genesis_config_by(&ledger_path, arg_matches).hash()
             );
 
-            let blockstore = open_blockstore(
-                &ledger_path,
-                AccessType::TryPrimaryThenSecondary,
-                wal_recovery_mode,
-            );
             let (bank_forks, _, _) = load_bank_forks(
                 arg_matches,
                 &open_genesis_config_by(&ledger_path, arg_matches),
@@ -1933,6 +2415,27 @@ fn main() {
                 let working_bank = bank_forks.working_bank();
                 working_bank.print_accounts_stats();
             }
+            if let (Some(compute_profile), Some(compute_profile_file)) =
+                (compute_profile, compute_profile_file)
+            {
+                let compute_profile = compute_profile.lock().unwrap();
+                let result = File::create(&compute_profile_file).and_then(|mut file| {
+                    compute_profile.write_folded_stacks(&mut file, &HashMap::new())
+                });
+                if let Err(err) = result {
+                    eprintln!(
+                        "Unable to write compute profile to {}: {}",
+                        compute_profile_file, err
+                    );
+                    exit(1);
+                }
+                println!(
+                    "Compute profile of {} program invocations ({} compute units) written to {}",
+                    compute_profile.invocations().len(),
+                    compute_profile.total_units(),
+                    compute_profile_file
+                );
+            }
             println!("Ok");
         }
         ("graph", Some(arg_matches)) => {
@@ -1981,6 +2484,166 @@ fn main() {
                 }
             }
         }
+        ("turbine-tree", Some(arg_matches)) => {
+            let slot = value_t_or_exit!(arg_matches, "slot", Slot);
+            let shred_index = value_t_or_exit!(arg_matches, "shred_index", u32);
+            let fanout = value_t_or_exit!(arg_matches, "fanout", usize);
+
+            // Only the leader schedule and epoch stakes of the root bank are
+            // needed, so no slots are replayed
+            let process_options = ProcessOptions {
+                dev_halt_at_slot: Some(0),
+                new_hard_forks: hardforks_of(arg_matches, "hard_forks"),
+                poh_verify: false,
+                ..ProcessOptions::default()
+            };
+            let genesis_config = open_genesis_config_by(&ledger_path, arg_matches);
+            let blockstore = open_blockstore(
+                &ledger_path,
+                AccessType::TryPrimaryThenSecondary,
+                wal_recovery_mode,
+            );
+            let (bank_forks, leader_schedule_cache, _snapshot_hash) = load_bank_forks(
+                arg_matches,
+                &genesis_config,
+                &blockstore,
+                process_options,
+                snapshot_archive_path,
+            )
+            .unwrap_or_else(|err| {
+                eprintln!("Failed to load ledger: {:?}", err);
+                exit(1);
+            });
+            let root_bank = bank_forks.root_bank();
+
+            let slot_leader = pubkey_of(arg_matches, "slot_leader")
+                .or_else(|| leader_schedule_cache.slot_leader_at(slot, Some(&root_bank)))
+                .unwrap_or_else(|| {
+                    eprintln!(
+                        "Leader of slot {} is unknown at root slot {}, use --slot-leader",
+                        slot,
+                        root_bank.slot()
+                    );
+                    exit(1);
+                });
+            let stakes: HashMap<Pubkey, u64> = match arg_matches.value_of("stake_distribution") {
+                Some(path) => fs::read(path)
+                    .map_err(|err| err.to_string())
+                    .and_then(|data| {
+                        serde_json::from_slice::<HashMap<String, u64>>(&data)
+                            .map_err(|err| err.to_string())
+                    })
+                    .and_then(|stakes| {
+                        stakes
+                            .into_iter()
+                            .map(|(pubkey, stake)| {
+                                Pubkey::from_str(&pubkey)
+                                    .map(|pubkey| (pubkey, stake))
+                                    .map_err(|err| format!("Invalid pubkey {}: {}", pubkey, err))
+                            })
+                            .collect()
+                    })
+                    .unwrap_or_else(|err| {
+                        eprintln!("Unable to read stake distribution {}: {}", path, err);
+                        exit(1);
+                    }),
+                None => {
+                    let epoch = root_bank.get_leader_schedule_epoch(slot);
+                    match root_bank.epoch_staked_nodes(epoch) {
+                        Some(stakes) => stakes.as_ref().clone(),
+                        None => {
+                            eprintln!(
+                                "Stakes of epoch {} are unknown at root slot {}, \
+                                 use --stake-distribution",
+                                epoch,
+                                root_bank.slot()
+                            );
+                            exit(1);
+                        }
+                    }
+                }
+            };
+            let shred_seed = match blockstore.get_data_shred(slot, u64::from(shred_index)) {
+                Ok(Some(payload)) => Shred::new_from_serialized_shred(payload)
+                    .map(|shred| shred.seed(Some(slot_leader), &root_bank))
+                    .unwrap_or_else(|err| {
+                        eprintln!("Invalid shred {} in slot {}: {:?}", shred_index, slot, err);
+                        exit(1);
+                    }),
+                _ if shred::enable_deterministic_seed(slot, &root_bank) => {
+                    Shred::deterministic_seed(slot, shred_index, &slot_leader)
+                }
+                _ => {
+                    eprintln!(
+                        "Shred {} of slot {} is not in the ledger, and its retransmit \
+                         order depends on its signature",
+                        shred_index, slot
+                    );
+                    exit(1);
+                }
+            };
+
+            let tree = get_turbine_tree_from_stakes(&stakes, shred_seed, fanout, slot_leader);
+            println!(
+                "Slot {} shred {}: leader {}, {} staked nodes, {} layers",
+                slot,
+                shred_index,
+                slot_leader,
+                tree.nodes.len(),
+                tree.nodes
+                    .last()
+                    .map(|node| node.layer + 1)
+                    .unwrap_or_default(),
+            );
+            if let Some(node) = pubkey_of(arg_matches, "node") {
+                match tree.get_node(&node) {
+                    Some(node) => {
+                        println!("Node {}", node.pubkey);
+                        println!("  Stake: {}", node.stake);
+                        println!("  Position: {}", node.position);
+                        println!("  Layer: {}", node.layer);
+                        println!(
+                            "  Neighborhood: {}{}",
+                            node.neighborhood,
+                            if node.anchor { " (anchor)" } else { "" }
+                        );
+                        println!(
+                            "  Receives from: {}",
+                            node.parent.as_deref().unwrap_or("unknown")
+                        );
+                        println!("  Retransmits to: {} nodes", node.retransmit_peers.len());
+                    }
+                    None if node == slot_leader => println!("Node {} is the slot leader", node),
+                    None => println!("Node {} is not a staked node", node),
+                }
+            }
+
+            if let Some(output_file) = arg_matches.value_of("tree_filename") {
+                let extension = Path::new(output_file).extension();
+                let result = if extension == Some(OsStr::new("json")) {
+                    File::create(output_file).and_then(|file| {
+                        serde_json::to_writer_pretty(file, &tree).map_err(io::Error::from)
+                    })
+                } else {
+                    let dot = tree.to_dot(pubkey_of(arg_matches, "node").as_ref());
+                    if extension == Some(OsStr::new("pdf")) {
+                        render_dot(dot, output_file, "pdf")
+                    } else if extension == Some(OsStr::new("png")) {
+                        render_dot(dot, output_file, "png")
+                    } else {
+                        File::create(output_file)
+                            .and_then(|mut file| file.write_all(&dot.into_bytes()))
+                    }
+                };
+                match result {
+                    Ok(_) => println!("Wrote {}", output_file),
+                    Err(err) => {
+                        eprintln!("Unable to write {}: {}", output_file, err);
+                        exit(1);
+                    }
+                }
+            }
+        }
         ("create-snapshot", Some(arg_matches)) => {
             let output_directory = value_t!(arg_matches, "output_directory", PathBuf)
                 .unwrap_or_else(|_| ledger_path.clone());
@@ -2328,6 +2991,280 @@ fn main() {
                 }
             }
         }
+        ("rent-paying-accounts", Some(arg_matches)) => {
+            let owner = pubkey_of(arg_matches, "owner");
+            let process_options = ProcessOptions {
+                dev_halt_at_slot: value_t!(arg_matches, "halt_at_slot", Slot).ok(),
+                new_hard_forks: hardforks_of(arg_matches, "hard_forks"),
+                poh_verify: false,
+                ..ProcessOptions::default()
+            };
+            let genesis_config = open_genesis_config_by(&ledger_path, arg_matches);
+            let blockstore = open_blockstore(
+                &ledger_path,
+                AccessType::TryPrimaryThenSecondary,
+                wal_recovery_mode,
+            );
+            match load_bank_forks(
+                arg_matches,
+                &genesis_config,
+                &blockstore,
+                process_options,
+                snapshot_archive_path,
+            ) {
+                Ok((bank_forks, _leader_schedule_cache, _snapshot_hash)) => {
+                    let bank = bank_forks.working_bank();
+                    let accounts = bank
+                        .get_rent_paying_accounts(owner.as_ref())
+                        .unwrap_or_else(|err| {
+                            eprintln!("Failed to scan accounts: {:?}", err);
+                            exit(1);
+                        });
+
+                    println!("---");
+                    println!("slot: {}", bank.slot());
+                    println!("epoch: {}", bank.epoch());
+                    for (owner, accounts) in &accounts.iter().group_by(|account| account.owner) {
+                        let accounts: Vec<_> = accounts.collect();
+                        println!("{}:", owner);
+                        println!("  - accounts: {}", accounts.len());
+                        println!(
+                            "  - to exempt: {} SOL",
+                            lamports_to_sol(
+                                accounts
+                                    .iter()
+                                    .map(|account| account.lamports_to_exempt())
+                                    .sum()
+                            )
+                        );
+                        for account in accounts {
+                            println!("  - {}:", account.pubkey);
+                            println!("    - balance: {} SOL", lamports_to_sol(account.lamports));
+                            println!("    - data_len: {}", account.data_len);
+                            println!(
+                                "    - rent_exempt_minimum: {} SOL",
+                                lamports_to_sol(account.rent_exempt_minimum)
+                            );
+                            println!(
+                                "    - rent_per_epoch: {} SOL",
+                                lamports_to_sol(account.rent_per_epoch)
+                            );
+                            println!(
+                                "    - epochs_until_deletion: {}",
+                                account.epochs_until_deletion
+                            );
+                        }
+                    }
+                    println!("total_accounts: {}", accounts.len());
+                    println!(
+                        "total_to_exempt: {} SOL",
+                        lamports_to_sol(
+                            accounts
+                                .iter()
+                                .map(|account| account.lamports_to_exempt())
+                                .sum()
+                        )
+                    );
+                }
+                Err(err) => {
+                    eprintln!("Failed to load ledger: {:?}", err);
+                    exit(1);
+                }
+            }
+        }
+        ("storage-report", Some(arg_matches)) => {
+            let limit = value_t_or_exit!(arg_matches, "limit", usize);
+            let shrink_to_alive_ratio = value_t!(arg_matches, "shrink_to_alive_ratio", f64).ok();
+            if let Some(ratio) = shrink_to_alive_ratio {
+                if !(0.0..=1.0).contains(&ratio) {
+                    eprintln!("--shrink-to-alive-ratio must be between 0 and 1");
+                    exit(1);
+                }
+            }
+            let process_options = ProcessOptions {
+                dev_halt_at_slot: value_t!(arg_matches, "halt_at_slot", Slot).ok(),
+                new_hard_forks: hardforks_of(arg_matches, "hard_forks"),
+                poh_verify: false,
+                ..ProcessOptions::default()
+            };
+            let genesis_config = open_genesis_config_by(&ledger_path, arg_matches);
+            let blockstore = open_blockstore(
+                &ledger_path,
+                AccessType::TryPrimaryThenSecondary,
+                wal_recovery_mode,
+            );
+            match load_bank_forks(
+                arg_matches,
+                &genesis_config,
+                &blockstore,
+                process_options,
+                snapshot_archive_path,
+            ) {
+                Ok((bank_forks, _leader_schedule_cache, _snapshot_hash)) => {
+                    let bank = bank_forks.working_bank();
+                    // Move the replayed slots from the accounts cache into storages
+                    bank.squash();
+                    bank.force_flush_accounts_cache();
+
+                    print!("{}", bank.storage_report(limit));
+                    if let Some(ratio) = shrink_to_alive_ratio {
+                        let mut measure = Measure::start("shrink");
+                        let num_slots = bank.shrink_to_alive_ratio(ratio);
+                        measure.stop();
+                        println!();
+                        println!("Shrunk {} slots in {}", num_slots, measure);
+                        println!();
+                        print!("{}", bank.storage_report(limit));
+                    }
+                }
+                Err(err) => {
+                    eprintln!("Failed to load ledger: {:?}", err);
+                    exit(1);
+                }
+            }
+        }
+        ("account-proof", Some(arg_matches)) => {
+            let pubkey = pubkey_of(arg_matches, "pubkey").unwrap();
+            let dev_halt_at_slot = value_t!(arg_matches, "halt_at_slot", Slot).ok();
+            let process_options = ProcessOptions {
+                dev_halt_at_slot,
+                new_hard_forks: hardforks_of(arg_matches, "hard_forks"),
+                poh_verify: false,
+                ..ProcessOptions::default()
+            };
+            let genesis_config = open_genesis_config_by(&ledger_path, arg_matches);
+            let blockstore = open_blockstore(
+                &ledger_path,
+                AccessType::TryPrimaryThenSecondary,
+                wal_recovery_mode,
+            );
+            match load_bank_forks(
+                arg_matches,
+                &genesis_config,
+                &blockstore,
+                process_options,
+                snapshot_archive_path,
+            ) {
+                Ok((bank_forks, _leader_schedule_cache, snapshot_hash)) => {
+                    let bank = bank_forks.working_bank();
+                    let tree = bank.build_accounts_merkle_tree();
+                    let accounts_hash = tree.root();
+                    let proof = tree.get_account_proof(&pubkey).unwrap_or_else(|| {
+                        eprintln!("Error: {} not found at slot {}", pubkey, bank.slot());
+                        exit(1);
+                    });
+                    assert!(proof.verify(&accounts_hash));
+
+                    println!("---");
+                    println!("bank_slot: {}", bank.slot());
+                    println!("pubkey: {}", proof.pubkey);
+                    println!("slot: {}", proof.slot);
+                    println!("account_hash: {}", proof.account_hash);
+                    println!("levels:");
+                    for level in &proof.levels {
+                        println!("  - index: {}", level.index);
+                        println!("    siblings:");
+                        for sibling in &level.siblings {
+                            println!("      - {}", sibling);
+                        }
+                    }
+                    println!("accounts_hash: {}", accounts_hash);
+
+                    match snapshot_hash {
+                        Some((snapshot_slot, snapshot_hash)) if snapshot_slot == bank.slot() => {
+                            if snapshot_hash == accounts_hash {
+                                info!("Accounts hash matches snapshot {}", snapshot_slot);
+                            } else {
+                                eprintln!(
+                                    "Error: accounts hash {} does not match snapshot {} hash {}",
+                                    accounts_hash, snapshot_slot, snapshot_hash
+                                );
+                                exit(1);
+                            }
+                        }
+                        Some((snapshot_slot, _)) => info!(
+                            "Bank slot {} is not snapshot slot {}; use --halt-at-slot {} \
+                             to prove against the snapshot accounts hash",
+                            bank.slot(),
+                            snapshot_slot,
+                            snapshot_slot
+                        ),
+                        None => {}
+                    }
+                }
+                Err(err) => {
+                    eprintln!("Failed to load ledger: {:?}", err);
+                    exit(1);
+                }
+            }
+        }
+        ("verify-accounts-hash", Some(arg_matches)) => {
+            let accounts_hash_cache_path =
+                value_t!(arg_matches, "accounts_hash_cache_path", String)
+                    .map(PathBuf::from)
+                    .unwrap_or_else(|_| ledger_path.join(snapshot_utils::ACCOUNTS_HASH_CACHE_DIR));
+            let process_options = ProcessOptions {
+                dev_halt_at_slot: value_t!(arg_matches, "halt_at_slot", Slot).ok(),
+                new_hard_forks: hardforks_of(arg_matches, "hard_forks"),
+                poh_verify: false,
+                accounts_hash_cache_path: Some(accounts_hash_cache_path.clone()),
+                ..ProcessOptions::default()
+            };
+            let genesis_config = open_genesis_config_by(&ledger_path, arg_matches);
+            let blockstore = open_blockstore(
+                &ledger_path,
+                AccessType::TryPrimaryThenSecondary,
+                wal_recovery_mode,
+            );
+            let mut measure = Measure::start("verify accounts hash");
+            match load_bank_forks(
+                arg_matches,
+                &genesis_config,
+                &blockstore,
+                process_options,
+                snapshot_archive_path,
+            ) {
+                Ok((bank_forks, _leader_schedule_cache, snapshot_hash)) => {
+                    let bank = bank_forks.working_bank();
+                    match snapshot_hash {
+                        Some((snapshot_slot, snapshot_hash)) if snapshot_slot == bank.slot() => {
+                            // loading the snapshot panics if its accounts hash does not verify
+                            println!(
+                                "Accounts hash {} of snapshot slot {} verified",
+                                snapshot_hash, snapshot_slot
+                            );
+                        }
+                        _ => {
+                            bank.squash();
+                            bank.force_flush_accounts_cache();
+                            let accounts_hash = bank.update_accounts_hash();
+                            bank.set_accounts_hash_cache_path(Some(accounts_hash_cache_path));
+                            if !bank.verify_bank_hash(false) {
+                                eprintln!(
+                                    "Error: accounts hash {} of slot {} did not verify \
+                                     against the account storages",
+                                    accounts_hash,
+                                    bank.slot()
+                                );
+                                exit(1);
+                            }
+                            println!(
+                                "Accounts hash {} of slot {} verified",
+                                accounts_hash,
+                                bank.slot()
+                            );
+                        }
+                    }
+                    measure.stop();
+                    println!("capitalization: {}", bank.capitalization());
+                    println!("{}", measure);
+                }
+                Err(err) => {
+                    eprintln!("Failed to load ledger: {:?}", err);
+                    exit(1);
+                }
+            }
+        }
         ("capitalization", Some(arg_matches)) => {
             let dev_halt_at_slot = value_t!(arg_matches, "halt_at_slot", Slot).ok();
             let process_options = ProcessOptions {
@@ -25,6 +25,18 @@ fn bad_arguments() {
     assert!(!run_ledger_tool(&["-l", "invalid_ledger", "verify"])
         .status
         .success());
+    // A turbine tree needs a fanout of at least one
+    let output = run_ledger_tool(&[
+        "-l",
+        "invalid_ledger",
+        "turbine-tree",
+        "1",
+        "0",
+        "--fanout",
+        "0",
+    ]);
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("fanout must be greater than 0"));
 }
 
 #[test]
@@ -42,6 +54,10 @@ fn nominal() {
     let output = run_ledger_tool(&["-l", ledger_path, "verify"]);
     assert!(output.status.success());
 
+    // Recalculate the accounts hash from storage
+    let output = run_ledger_tool(&["-l", ledger_path, "verify-accounts-hash"]);
+    assert!(output.status.success());
+
     // Print everything
     let output = run_ledger_tool(&["-l", ledger_path, "print", "-vvv"]);
     assert!(output.status.success());
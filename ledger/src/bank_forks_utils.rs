@@ -132,7 +132,8 @@ fn load_from_snapshot(
         process_options.accounts_db_test_hash_calculation,
         process_options.accounts_db_skip_shrink,
         process_options.verify_index,
-        process_options.accounts_index_config,
+        process_options.accounts_index_config.clone(),
+        process_options.accounts_hash_cache_path.clone(),
     )
     .expect("Load from snapshot failed");
 
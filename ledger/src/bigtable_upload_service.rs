@@ -1,6 +1,7 @@
 use {
     crate::{bigtable_upload, blockstore::Blockstore},
     solana_runtime::commitment::BlockCommitmentCache,
+    solana_storage_bigtable::LedgerStorage,
     std::{
         sync::atomic::{AtomicBool, Ordering},
         sync::{Arc, RwLock},
@@ -24,7 +25,7 @@ pub struct BigTableUploadService {
 impl BigTableUploadService {
     pub fn new(
         runtime: Arc<Runtime>,
-        bigtable_ledger_storage: solana_storage_bigtable::LedgerStorage,
+        bigtable_ledger_storage: Arc<dyn LedgerStorage>,
         blockstore: Arc<Blockstore>,
         block_commitment_cache: Arc<RwLock<BlockCommitmentCache>>,
         exit: Arc<AtomicBool>,
@@ -48,7 +49,7 @@ impl BigTableUploadService {
 
     fn run(
         runtime: Arc<Runtime>,
-        bigtable_ledger_storage: solana_storage_bigtable::LedgerStorage,
+        bigtable_ledger_storage: Arc<dyn LedgerStorage>,
         blockstore: Arc<Blockstore>,
         block_commitment_cache: Arc<RwLock<BlockCommitmentCache>>,
         exit: Arc<AtomicBool>,
@@ -22,11 +22,18 @@ use {
         ThreadPool,
     },
     rocksdb::DBRawIterator,
-    solana_entry::entry::{create_ticks, Entry},
+    serde::Deserialize,
+    solana_entry::{
+        entry::{create_ticks, Entry},
+        transaction_proof::TransactionProof,
+    },
     solana_measure::measure::Measure,
     solana_metrics::{datapoint_debug, datapoint_error},
     solana_rayon_threadlimit::get_thread_count,
-    solana_runtime::hardened_unpack::{unpack_genesis_archive, MAX_GENESIS_ARCHIVE_UNPACKED_SIZE},
+    solana_runtime::{
+        account_write_log::{AccountWrite, TransactionAccountWrites},
+        hardened_unpack::{unpack_genesis_archive, MAX_GENESIS_ARCHIVE_UNPACKED_SIZE},
+    },
     solana_sdk::{
         clock::{Slot, UnixTimestamp, DEFAULT_TICKS_PER_SECOND, MS_PER_TICK},
         genesis_config::{GenesisConfig, DEFAULT_GENESIS_ARCHIVE, DEFAULT_GENESIS_FILE},
@@ -148,6 +155,7 @@ pub struct Blockstore {
     block_height_cf: LedgerColumn<cf::BlockHeight>,
     program_costs_cf: LedgerColumn<cf::ProgramCosts>,
     bank_hash_cf: LedgerColumn<cf::BankHash>,
+    account_writes_cf: LedgerColumn<cf::AccountWrites>,
     last_root: Arc<RwLock<Slot>>,
     insert_shreds_lock: Arc<Mutex<()>>,
     pub new_shreds_signals: Vec<SyncSender<bool>>,
@@ -349,6 +357,7 @@ impl Blockstore {
         let block_height_cf = db.column();
         let program_costs_cf = db.column();
         let bank_hash_cf = db.column();
+        let account_writes_cf = db.column();
 
         let db = Arc::new(db);
 
@@ -399,6 +408,7 @@ impl Blockstore {
             block_height_cf,
             program_costs_cf,
             bank_hash_cf,
+            account_writes_cf,
             new_shreds_signals: vec![],
             completed_slots_senders: vec![],
             insert_shreds_lock: Arc::new(Mutex::new(())),
@@ -2207,6 +2217,45 @@ impl Blockstore {
             .map(|(status, _)| status)
     }
 
+    /// Returns a proof that a transaction is included in the root it was processed in
+    pub fn get_rooted_transaction_proof(
+        &self,
+        signature: Signature,
+    ) -> Result<Option<TransactionProof>> {
+        datapoint_info!(
+            "blockstore-rpc-api",
+            ("method", "get_rooted_transaction_proof".to_string(), String)
+        );
+        let slot = match self.get_rooted_transaction_status(signature)? {
+            Some((slot, _status)) => slot,
+            None => return Ok(None),
+        };
+        let slot_meta = match self.meta(slot)? {
+            Some(slot_meta) if slot_meta.is_full() => slot_meta,
+            _ => return Err(BlockstoreError::SlotUnavailable),
+        };
+        let slot_entries = self.get_slot_entries(slot, 0)?;
+        let previous_blockhash = if slot_meta.parent_slot == slot {
+            None
+        } else {
+            self.get_slot_entries(slot_meta.parent_slot, 0)?
+                .last()
+                .map(|entry| entry.hash)
+        };
+        let proof = TransactionProof::new(
+            slot,
+            &slot_entries,
+            previous_blockhash.unwrap_or_default(),
+            &signature,
+        );
+        match proof {
+            Some(proof) if proof.entry_index == 0 && previous_blockhash.is_none() => {
+                Err(BlockstoreError::ParentEntriesUnavailable)
+            }
+            proof => Ok(proof),
+        }
+    }
+
     /// Returns a complete transaction if it was processed in a root
     pub fn get_rooted_transaction(
         &self,
@@ -2707,6 +2756,90 @@ impl Blockstore {
         self.program_costs_cf.delete(*key)
     }
 
+    pub fn write_account_writes(
+        &self,
+        slot: Slot,
+        account_writes: &[TransactionAccountWrites],
+    ) -> Result<()> {
+        let mut write_batch = self.db.batch()?;
+        for writes in account_writes {
+            write_batch.put::<cf::AccountWrites>((slot, writes.ordinal), writes)?;
+        }
+        self.db.write(write_batch)
+    }
+
+    /// Deletes the recorded account writes of `slot`
+    pub fn delete_account_writes(&self, slot: Slot) -> Result<()> {
+        let mut write_batch = self.db.batch()?;
+        self.db
+            .delete_range_cf::<cf::AccountWrites>(&mut write_batch, slot, slot + 1)?;
+        self.db.write(write_batch)
+    }
+
+    /// Returns the recorded account writes of `slot`, in commit order
+    pub fn get_account_writes(&self, slot: Slot) -> Result<Vec<TransactionAccountWrites>> {
+        self.account_writes_cf
+            .iter(IteratorMode::From((slot, 0), IteratorDirection::Forward))?
+            .take_while(|((write_slot, _), _)| *write_slot == slot)
+            .map(|(_, data)| deserialize(&data).map_err(BlockstoreError::from))
+            .collect()
+    }
+
+    /// Returns the recorded writes of `pubkey` in `slot`, keyed by the signature of the writing
+    /// transaction. Account data is only copied out if `include_data` is set.
+    pub fn get_account_writes_for_address(
+        &self,
+        slot: Slot,
+        pubkey: &Pubkey,
+        include_data: bool,
+    ) -> Result<HashMap<Signature, AccountWrite>> {
+        let mut found = HashMap::new();
+        for ((write_slot, _), data) in self
+            .account_writes_cf
+            .iter(IteratorMode::From((slot, 0), IteratorDirection::Forward))?
+        {
+            if write_slot != slot {
+                break;
+            }
+            if let Some((signature, write)) = find_account_write(&data, pubkey, include_data)? {
+                found.insert(signature, write);
+            }
+        }
+        Ok(found)
+    }
+
+    /// Returns up to `limit` recorded writes of `pubkey` in rooted slots before `before_slot`,
+    /// newest first. Each write is returned with its slot and the writing transaction's signature.
+    pub fn find_account_writes(
+        &self,
+        pubkey: &Pubkey,
+        before_slot: Option<Slot>,
+        limit: usize,
+    ) -> Result<Vec<(Slot, Signature, AccountWrite)>> {
+        let mut found = vec![];
+        if limit == 0 {
+            return Ok(found);
+        }
+        let iterator_mode = match before_slot {
+            Some(0) => return Ok(found),
+            Some(slot) => IteratorMode::From((slot - 1, u64::MAX), IteratorDirection::Reverse),
+            None => IteratorMode::End,
+        };
+        for ((slot, _), data) in self.account_writes_cf.iter(iterator_mode)? {
+            // Writes of abandoned forks are kept until they are purged
+            if !self.is_root(slot) {
+                continue;
+            }
+            if let Some((signature, write)) = find_account_write(&data, pubkey, true)? {
+                found.push((slot, signature, write));
+                if found.len() == limit {
+                    break;
+                }
+            }
+        }
+        Ok(found)
+    }
+
     /// Returns the entry vector for the slot starting with `shred_start_index`
     pub fn get_slot_entries(&self, slot: Slot, shred_start_index: u64) -> Result<Vec<Entry>> {
         self.get_slot_entries_with_shred_info(slot, shred_start_index, false)
@@ -3188,6 +3321,51 @@ impl Blockstore {
     }
 }
 
+/// Mirrors `TransactionAccountWrites`, borrowing the account data from the serialized row
+#[derive(Deserialize)]
+struct TransactionAccountWritesRef<'a> {
+    _ordinal: u64,
+    signature: Signature,
+    #[serde(borrow)]
+    accounts: Vec<AccountWriteRef<'a>>,
+}
+
+#[derive(Deserialize)]
+struct AccountWriteRef<'a> {
+    pubkey: Pubkey,
+    lamports: u64,
+    hash: Hash,
+    #[serde(borrow)]
+    data: Option<&'a [u8]>,
+}
+
+/// Looks up the write of `pubkey` in a serialized `TransactionAccountWrites` without copying the
+/// data of the other accounts
+fn find_account_write(
+    data: &[u8],
+    pubkey: &Pubkey,
+    include_data: bool,
+) -> Result<Option<(Signature, AccountWrite)>> {
+    let TransactionAccountWritesRef {
+        signature,
+        accounts,
+        ..
+    } = deserialize(data)?;
+    // A transaction writes each account at most once
+    Ok(accounts
+        .into_iter()
+        .find(|write| write.pubkey == *pubkey)
+        .map(|write| {
+            let write = AccountWrite {
+                pubkey: write.pubkey,
+                lamports: write.lamports,
+                hash: write.hash,
+                data: write.data.filter(|_| include_data).map(<[u8]>::to_vec),
+            };
+            (signature, write)
+        }))
+}
+
 // Update the `completed_data_indexes` with a new shred `new_shred_index`. If a
 // data set is complete, return the range of shred indexes [start_index, end_index]
 // for that completed data set.
@@ -7148,6 +7326,59 @@ pub mod tests {
         }
     }
 
+    #[test]
+    fn test_get_rooted_transaction_proof() {
+        let slot = 2;
+        let parent_entries = create_ticks(2, 4, Hash::default());
+        let mut poh_hash = parent_entries.last().unwrap().hash;
+        let mut entries = vec![];
+        for _ in 0..3 {
+            let transactions = (0..3)
+                .map(|_| {
+                    Transaction::new_with_compiled_instructions(
+                        &[&Keypair::new()],
+                        &[solana_sdk::pubkey::new_rand()],
+                        Hash::default(),
+                        vec![solana_sdk::pubkey::new_rand()],
+                        vec![CompiledInstruction::new(1, &(), vec![0])],
+                    )
+                })
+                .collect();
+            entries.push(next_entry_mut(&mut poh_hash, 2, transactions));
+            entries.push(next_entry_mut(&mut poh_hash, 4, vec![]));
+        }
+        let blockhash = entries.last().unwrap().hash;
+
+        let ledger_path = get_tmp_ledger_path!();
+        let blockstore = Blockstore::open(&ledger_path).unwrap();
+        let shreds = entries_to_test_shreds(parent_entries, slot - 1, 0, true, 0);
+        blockstore.insert_shreds(shreds, None, false).unwrap();
+        let shreds = entries_to_test_shreds(entries.clone(), slot, slot - 1, true, 0);
+        blockstore.insert_shreds(shreds, None, false).unwrap();
+        blockstore.set_roots([slot - 1, slot].iter()).unwrap();
+
+        for transaction in entries.iter().flat_map(|entry| entry.transactions.iter()) {
+            let signature = transaction.signatures[0];
+            let status = TransactionStatusMeta::default().into();
+            blockstore
+                .transaction_status_cf
+                .put_protobuf((0, signature, slot), &status)
+                .unwrap();
+            let proof = blockstore
+                .get_rooted_transaction_proof(signature)
+                .unwrap()
+                .unwrap();
+            assert_eq!(proof.slot, slot);
+            assert!(proof.verify(&blockhash));
+        }
+        assert_eq!(
+            blockstore
+                .get_rooted_transaction_proof(Signature::default())
+                .unwrap(),
+            None
+        );
+    }
+
     #[test]
     fn test_get_complete_transaction() {
         let slot = 2;
@@ -8010,6 +8241,112 @@ pub mod tests {
         Blockstore::destroy(&blockstore_path).expect("Expected successful database destruction");
     }
 
+    #[test]
+    fn test_write_find_account_writes() {
+        let blockstore_path = get_tmp_ledger_path!();
+        {
+            let blockstore = Blockstore::open(&blockstore_path).unwrap();
+            let pubkey = Pubkey::new_unique();
+            let other_pubkey = Pubkey::new_unique();
+            let account_write = |pubkey: Pubkey, lamports: u64| AccountWrite {
+                pubkey,
+                lamports,
+                hash: Hash::new_unique(),
+                data: Some(vec![lamports as u8]),
+            };
+            let slot_writes = |slot: Slot| {
+                vec![
+                    TransactionAccountWrites {
+                        ordinal: 0,
+                        signature: Signature::new(&[slot as u8; 64]),
+                        accounts: vec![account_write(pubkey, slot), account_write(other_pubkey, 1)],
+                    },
+                    TransactionAccountWrites {
+                        ordinal: 2,
+                        signature: Signature::new(&[slot as u8 + 100; 64]),
+                        accounts: vec![account_write(other_pubkey, 2)],
+                    },
+                ]
+            };
+            for slot in 1..=4 {
+                blockstore
+                    .write_account_writes(slot, &slot_writes(slot))
+                    .unwrap();
+            }
+            // Slot 4 is not rooted
+            blockstore.set_roots([1, 2, 3].iter()).unwrap();
+
+            assert_eq!(blockstore.get_account_writes(0).unwrap(), vec![]);
+            let writes = blockstore.get_account_writes(2).unwrap();
+            assert_eq!(writes.len(), 2);
+            assert_eq!(writes[0].ordinal, 0);
+            assert_eq!(writes[1].ordinal, 2);
+            assert_eq!(writes[1].accounts[0].pubkey, other_pubkey);
+
+            let found = blockstore
+                .get_account_writes_for_address(2, &other_pubkey, false)
+                .unwrap();
+            assert_eq!(found.len(), 2);
+            assert_eq!(found[&Signature::new(&[102; 64])].lamports, 2);
+            assert!(found.values().all(|write| write.data.is_none()));
+            let found = blockstore
+                .get_account_writes_for_address(2, &pubkey, true)
+                .unwrap();
+            assert_eq!(found.len(), 1);
+            assert_eq!(found[&Signature::new(&[2; 64])].data, Some(vec![2]));
+            assert!(blockstore
+                .get_account_writes_for_address(5, &pubkey, true)
+                .unwrap()
+                .is_empty());
+
+            let found = blockstore.find_account_writes(&pubkey, None, 2).unwrap();
+            assert_eq!(
+                found
+                    .iter()
+                    .map(|(slot, signature, write)| (*slot, *signature, write.lamports))
+                    .collect::<Vec<_>>(),
+                vec![
+                    (3, Signature::new(&[3; 64]), 3),
+                    (2, Signature::new(&[2; 64]), 2)
+                ]
+            );
+            let found = blockstore
+                .find_account_writes(&pubkey, Some(3), 10)
+                .unwrap();
+            assert_eq!(
+                found.iter().map(|(slot, ..)| *slot).collect::<Vec<_>>(),
+                vec![2, 1]
+            );
+            let found = blockstore
+                .find_account_writes(&other_pubkey, Some(2), 10)
+                .unwrap();
+            assert_eq!(
+                found
+                    .iter()
+                    .map(|(_, _, write)| write.lamports)
+                    .collect::<Vec<_>>(),
+                vec![2, 1]
+            );
+            assert!(blockstore
+                .find_account_writes(&pubkey, Some(0), 10)
+                .unwrap()
+                .is_empty());
+
+            // Replaying a slot deletes its earlier writes
+            assert_eq!(blockstore.get_account_writes(4).unwrap().len(), 2);
+            blockstore.delete_account_writes(4).unwrap();
+            assert!(blockstore.get_account_writes(4).unwrap().is_empty());
+            assert_eq!(blockstore.get_account_writes(3).unwrap().len(), 2);
+
+            blockstore.run_purge(0, 2, PurgeType::PrimaryIndex).unwrap();
+            assert!(blockstore.get_account_writes(2).unwrap().is_empty());
+            let found = blockstore.find_account_writes(&pubkey, None, 10).unwrap();
+            assert_eq!(found.len(), 1);
+            assert_eq!(found[0].0, 3);
+        }
+        Blockstore::destroy(&blockstore_path).expect("Expected successful database destruction");
+    }
+
     #[test]
     fn test_lowest_slot() {
         let blockstore_path = get_tmp_ledger_path!();
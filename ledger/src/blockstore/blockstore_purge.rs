@@ -186,6 +186,10 @@ impl Blockstore {
             & self
                 .db
                 .delete_range_cf::<cf::BlockHeight>(&mut write_batch, from_slot, to_slot)
+                .is_ok()
+            & self
+                .db
+                .delete_range_cf::<cf::AccountWrites>(&mut write_batch, from_slot, to_slot)
                 .is_ok();
         let mut w_active_transaction_status_index =
             self.active_transaction_status_index.write().unwrap();
@@ -303,6 +307,10 @@ impl Blockstore {
             && self
                 .block_height_cf
                 .compact_range(from_slot, to_slot)
+                .unwrap_or(false)
+            && self
+                .account_writes_cf
+                .compact_range(from_slot, to_slot)
                 .unwrap_or(false);
         compact_timer.stop();
         if !result {
@@ -0,0 +1,54 @@
+//! Stores the account writes recorded by banks in the blockstore
+use {
+    crate::blockstore::Blockstore,
+    solana_runtime::account_write_log::{AccountWriteRecorder, TransactionAccountWrites},
+    solana_sdk::clock::Slot,
+    std::{fmt, sync::Arc},
+};
+
+pub struct BlockstoreAccountWriteRecorder {
+    blockstore: Arc<Blockstore>,
+    include_data: bool,
+}
+
+impl BlockstoreAccountWriteRecorder {
+    pub fn new(blockstore: Arc<Blockstore>, include_data: bool) -> Self {
+        Self {
+            blockstore,
+            include_data,
+        }
+    }
+}
+
+impl fmt::Debug for BlockstoreAccountWriteRecorder {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("BlockstoreAccountWriteRecorder")
+            .field("ledger_path", &self.blockstore.ledger_path())
+            .field("include_data", &self.include_data)
+            .finish()
+    }
+}
+
+impl AccountWriteRecorder for BlockstoreAccountWriteRecorder {
+    fn include_data(&self) -> bool {
+        self.include_data
+    }
+
+    fn begin_slot(&self, slot: Slot) {
+        if let Err(err) = self.blockstore.delete_account_writes(slot) {
+            error!(
+                "failed to delete stale account writes for slot {}: {:?}",
+                slot, err
+            );
+        }
+    }
+
+    fn record(&self, slot: Slot, writes: Vec<TransactionAccountWrites>) {
+        if let Err(err) = self.blockstore.write_account_writes(slot, &writes) {
+            error!(
+                "failed to write account writes for slot {}: {:?}",
+                slot, err
+            );
+        }
+    }
+}
@@ -14,7 +14,7 @@ use rocksdb::{
 
 use serde::de::DeserializeOwned;
 use serde::Serialize;
-use solana_runtime::hardened_unpack::UnpackError;
+use solana_runtime::{account_write_log::TransactionAccountWrites, hardened_unpack::UnpackError};
 use solana_sdk::{
     clock::{Slot, UnixTimestamp},
     pubkey::Pubkey,
@@ -75,6 +75,8 @@ const PERF_SAMPLES_CF: &str = "perf_samples";
 const BLOCK_HEIGHT_CF: &str = "block_height";
 /// Column family for ProgramCosts
 const PROGRAM_COSTS_CF: &str = "program_costs";
+/// Column family for AccountWrites
+const ACCOUNT_WRITES_CF: &str = "account_writes";
 
 // 1 day is chosen for the same reasoning of DEFAULT_COMPACTION_SLOT_INTERVAL
 const PERIODIC_COMPACTION_SECONDS: u64 = 60 * 60 * 24;
@@ -187,6 +189,10 @@ pub mod columns {
     #[derive(Debug)]
     // The program costs column
     pub struct ProgramCosts;
+
+    #[derive(Debug)]
+    /// The account writes column
+    pub struct AccountWrites;
 }
 
 pub enum AccessType {
@@ -357,6 +363,10 @@ impl Rocks {
             ProgramCosts::NAME,
             get_cf_options::<ProgramCosts>(&access_type, &oldest_slot),
         );
+        let account_writes_cf_descriptor = ColumnFamilyDescriptor::new(
+            AccountWrites::NAME,
+            get_cf_options::<AccountWrites>(&access_type, &oldest_slot),
+        );
         // Don't forget to add to both run_purge_with_stats() and
         // compact_storage() in ledger/src/blockstore/blockstore_purge.rs!!
 
@@ -382,6 +392,7 @@ impl Rocks {
             (PerfSamples::NAME, perf_samples_cf_descriptor),
             (BlockHeight::NAME, block_height_cf_descriptor),
             (ProgramCosts::NAME, program_costs_cf_descriptor),
+            (AccountWrites::NAME, account_writes_cf_descriptor),
         ];
         let cf_names: Vec<_> = cfs.iter().map(|c| c.0).collect();
 
@@ -501,6 +512,7 @@ impl Rocks {
             PerfSamples::NAME,
             BlockHeight::NAME,
             ProgramCosts::NAME,
+            AccountWrites::NAME,
         ]
     }
 
@@ -805,6 +817,39 @@ impl Column for columns::ProgramCosts {
     }
 }
 
+impl Column for columns::AccountWrites {
+    type Index = (Slot, u64);
+
+    fn key((slot, ordinal): (Slot, u64)) -> Vec<u8> {
+        let mut key = vec![0; 16]; // size_of Slot + size_of u64
+        BigEndian::write_u64(&mut key[0..8], slot);
+        BigEndian::write_u64(&mut key[8..16], ordinal);
+        key
+    }
+
+    fn index(key: &[u8]) -> (Slot, u64) {
+        let slot = BigEndian::read_u64(&key[0..8]);
+        let ordinal = BigEndian::read_u64(&key[8..16]);
+        (slot, ordinal)
+    }
+
+    fn primary_index(index: Self::Index) -> Slot {
+        index.0
+    }
+
+    #[allow(clippy::wrong_self_convention)]
+    fn as_index(slot: Slot) -> Self::Index {
+        (slot, 0)
+    }
+}
+
+impl ColumnName for columns::AccountWrites {
+    const NAME: &'static str = ACCOUNT_WRITES_CF;
+}
+impl TypedColumn for columns::AccountWrites {
+    type Type = TransactionAccountWrites;
+}
+
 impl Column for columns::ShredCode {
     type Index = (u64, u64);
 
@@ -16,6 +16,7 @@ use solana_measure::measure::Measure;
 use solana_metrics::{datapoint_error, inc_new_counter_debug};
 use solana_rayon_threadlimit::get_thread_count;
 use solana_runtime::{
+    account_write_log::AccountWriteRecorder,
     accounts_db::AccountShrinkThreshold,
     accounts_index::{AccountSecondaryIndexes, AccountsIndexConfig},
     bank::{
@@ -25,6 +26,7 @@ use solana_runtime::{
     bank_forks::BankForks,
     bank_utils,
     commitment::VOTE_THRESHOLD_SIZE,
+    compute_profiler::ComputeProfile,
     snapshot_utils::BankFromArchiveTimings,
     transaction_batch::TransactionBatch,
     vote_account::VoteAccount,
@@ -49,7 +51,7 @@ use std::{
     collections::{HashMap, HashSet},
     path::PathBuf,
     result,
-    sync::{Arc, RwLock},
+    sync::{Arc, Mutex, RwLock},
     time::{Duration, Instant},
 };
 use thiserror::Error;
@@ -468,8 +470,11 @@ pub struct ProcessOptions {
     pub accounts_db_test_hash_calculation: bool,
     pub accounts_db_skip_shrink: bool,
     pub accounts_index_config: Option<AccountsIndexConfig>,
+    pub accounts_hash_cache_path: Option<PathBuf>,
     pub verify_index: bool,
     pub shrink_ratio: AccountShrinkThreshold,
+    pub compute_profile: Option<Arc<Mutex<ComputeProfile>>>,
+    pub account_write_recorder: Option<Arc<dyn AccountWriteRecorder>>,
 }
 
 pub fn process_blockstore(
@@ -499,7 +504,7 @@ pub fn process_blockstore(
         opts.accounts_db_caching_enabled,
         opts.shrink_ratio,
         false,
-        opts.accounts_index_config,
+        opts.accounts_index_config.clone(),
     );
     let bank0 = Arc::new(bank0);
     info!("processing ledger for slot 0...");
@@ -575,6 +580,13 @@ fn do_process_blockstore_from_root(
         }
     }
 
+    if opts.compute_profile.is_some() {
+        bank.set_compute_profile(opts.compute_profile.clone());
+    }
+    if opts.account_write_recorder.is_some() {
+        bank.set_account_write_recorder(opts.account_write_recorder.clone());
+    }
+
     // ensure start_slot is rooted for correct replay
     if blockstore.is_primary_access() {
         blockstore
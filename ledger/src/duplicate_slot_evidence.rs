@@ -0,0 +1,254 @@
+//! The `duplicate_slot_evidence` module turns the `DuplicateSlotProof`s recorded in blockstore
+//! into a self-contained record of leader equivocation. The record carries both conflicting
+//! shreds in full along with the slot leader, so it can be checked by anyone who knows the
+//! leader schedule, without access to the ledger it was taken from.
+
+use {
+    crate::{
+        blockstore_meta::DuplicateSlotProof,
+        shred::{Shred, ShredError},
+    },
+    serde::{Deserialize, Serialize},
+    solana_sdk::{clock::Slot, pubkey::Pubkey},
+    std::str::FromStr,
+    thiserror::Error,
+};
+
+#[derive(Debug, Error)]
+pub enum DuplicateSlotEvidenceError {
+    #[error("invalid shred payload encoding: {0}")]
+    InvalidPayloadEncoding(#[from] base64::DecodeError),
+    #[error("invalid shred: {0}")]
+    InvalidShred(#[from] ShredError),
+    #[error("invalid leader pubkey: {0}")]
+    InvalidLeader(String),
+    #[error("unknown slot leader")]
+    UnknownSlotLeader,
+    #[error("evidence names {0} as the leader, but the slot leader is {1}")]
+    LeaderMismatch(Pubkey, Pubkey),
+    #[error("shreds are identical")]
+    IdenticalShreds,
+    #[error("slot mismatch")]
+    SlotMismatch,
+    #[error("shred index mismatch")]
+    ShredIndexMismatch,
+    #[error("shred type mismatch")]
+    ShredTypeMismatch,
+    #[error("invalid signature on shred {0}")]
+    InvalidSignature(usize),
+    #[error("evidence does not match the shred payloads")]
+    Inconsistent,
+}
+
+/// One of the two conflicting shreds of a duplicate slot
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DuplicateShredEvidence {
+    pub index: u32,
+    /// Either "data" or "coding"
+    pub shred_type: String,
+    pub version: u16,
+    pub signature: String,
+    /// Whether the shred is signed by the slot leader, or `None` if the leader is unknown
+    pub signature_verified: Option<bool>,
+    /// The shred as stored in blockstore, base64 encoded
+    pub payload: String,
+}
+
+/// Evidence that the leader of `slot` signed two different shreds with the same index and type
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DuplicateSlotEvidence {
+    pub slot: Slot,
+    /// Base58 pubkey of the slot leader, if known
+    pub leader: Option<String>,
+    pub shred1: DuplicateShredEvidence,
+    pub shred2: DuplicateShredEvidence,
+}
+
+impl DuplicateShredEvidence {
+    fn new(shred: &Shred, payload: &[u8], leader: Option<&Pubkey>) -> Self {
+        Self {
+            index: shred.index(),
+            shred_type: if shred.is_data() { "data" } else { "coding" }.to_string(),
+            version: shred.version(),
+            signature: shred.signature().to_string(),
+            signature_verified: leader.map(|leader| shred.verify(leader)),
+            payload: base64::encode(payload),
+        }
+    }
+}
+
+impl DuplicateSlotEvidence {
+    pub fn new(
+        slot: Slot,
+        proof: &DuplicateSlotProof,
+        leader: Option<Pubkey>,
+    ) -> Result<Self, DuplicateSlotEvidenceError> {
+        let shred1 = Shred::new_from_serialized_shred(proof.shred1.clone())?;
+        let shred2 = Shred::new_from_serialized_shred(proof.shred2.clone())?;
+        if shred1.slot() != slot || shred2.slot() != slot {
+            return Err(DuplicateSlotEvidenceError::SlotMismatch);
+        }
+        Ok(Self {
+            slot,
+            leader: leader.map(|leader| leader.to_string()),
+            shred1: DuplicateShredEvidence::new(&shred1, &proof.shred1, leader.as_ref()),
+            shred2: DuplicateShredEvidence::new(&shred2, &proof.shred2, leader.as_ref()),
+        })
+    }
+
+    /// Checks the evidence against `slot_leader`, the leader of the slot according to the leader
+    /// schedule: the evidence must name it as the leader, and both shreds must decode, share the
+    /// slot, index and type, differ in content, and carry valid signatures from it. Every other
+    /// field must match what is derived from the shred payloads.
+    pub fn verify(&self, slot_leader: &Pubkey) -> Result<(), DuplicateSlotEvidenceError> {
+        let leader = self
+            .leader
+            .as_ref()
+            .ok_or(DuplicateSlotEvidenceError::UnknownSlotLeader)?;
+        let leader = Pubkey::from_str(leader)
+            .map_err(|_| DuplicateSlotEvidenceError::InvalidLeader(leader.clone()))?;
+        if leader != *slot_leader {
+            return Err(DuplicateSlotEvidenceError::LeaderMismatch(
+                leader,
+                *slot_leader,
+            ));
+        }
+        let proof = DuplicateSlotProof::new(
+            base64::decode(&self.shred1.payload)?,
+            base64::decode(&self.shred2.payload)?,
+        );
+        let shred1 = Shred::new_from_serialized_shred(proof.shred1.clone())?;
+        let shred2 = Shred::new_from_serialized_shred(proof.shred2.clone())?;
+        if shred1.slot() != self.slot || shred2.slot() != self.slot {
+            return Err(DuplicateSlotEvidenceError::SlotMismatch);
+        }
+        if shred1.index() != shred2.index() {
+            return Err(DuplicateSlotEvidenceError::ShredIndexMismatch);
+        }
+        if shred1.common_header.shred_type != shred2.common_header.shred_type {
+            return Err(DuplicateSlotEvidenceError::ShredTypeMismatch);
+        }
+        if shred1.payload == shred2.payload {
+            return Err(DuplicateSlotEvidenceError::IdenticalShreds);
+        }
+        if !shred1.verify(&leader) {
+            return Err(DuplicateSlotEvidenceError::InvalidSignature(1));
+        }
+        if !shred2.verify(&leader) {
+            return Err(DuplicateSlotEvidenceError::InvalidSignature(2));
+        }
+        if Self::new(self.slot, &proof, Some(leader))? != *self {
+            return Err(DuplicateSlotEvidenceError::Inconsistent);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::shred::Shredder,
+        assert_matches::assert_matches,
+        solana_entry::entry::Entry,
+        solana_sdk::{
+            hash::Hash,
+            signature::{Keypair, Signer},
+            system_transaction,
+        },
+    };
+
+    fn new_data_shred(slot: Slot, keypair: &Keypair) -> Shred {
+        let entries = vec![Entry::new(
+            &Hash::default(),
+            0,
+            vec![system_transaction::transfer(
+                keypair,
+                &Pubkey::new_unique(),
+                1,
+                Hash::new_unique(),
+            )],
+        )];
+        let shredder = Shredder::new(slot, slot - 1, 0, 0).unwrap();
+        let (data_shreds, _, _) = shredder.entries_to_shreds(keypair, &entries, true, 0);
+        data_shreds.into_iter().next().unwrap()
+    }
+
+    #[test]
+    fn test_duplicate_slot_evidence() {
+        let slot = 42;
+        let leader = Keypair::new();
+        let shred1 = new_data_shred(slot, &leader);
+        let shred2 = new_data_shred(slot, &leader);
+        let proof = DuplicateSlotProof::new(shred1.payload.clone(), shred2.payload);
+
+        let evidence = DuplicateSlotEvidence::new(slot, &proof, None).unwrap();
+        assert_eq!(evidence.shred1.signature_verified, None);
+        assert_matches!(
+            evidence.verify(&leader.pubkey()),
+            Err(DuplicateSlotEvidenceError::UnknownSlotLeader)
+        );
+
+        let evidence = DuplicateSlotEvidence::new(slot, &proof, Some(leader.pubkey())).unwrap();
+        assert_eq!(evidence.shred1.signature_verified, Some(true));
+        assert_eq!(evidence.shred2.signature_verified, Some(true));
+        assert_eq!(evidence.shred1.shred_type, "data");
+        evidence.verify(&leader.pubkey()).unwrap();
+
+        // Evidence survives a round trip through its exported form
+        let exported = serde_json::to_string(&evidence).unwrap();
+        let imported: DuplicateSlotEvidence = serde_json::from_str(&exported).unwrap();
+        imported.verify(&leader.pubkey()).unwrap();
+
+        // Tampering with any field is detected
+        let mut tampered = evidence.clone();
+        tampered.shred2.index += 1;
+        assert_matches!(
+            tampered.verify(&leader.pubkey()),
+            Err(DuplicateSlotEvidenceError::Inconsistent)
+        );
+        let mut tampered = evidence.clone();
+        tampered.leader = Some(Pubkey::new_unique().to_string());
+        assert_matches!(
+            tampered.verify(&leader.pubkey()),
+            Err(DuplicateSlotEvidenceError::LeaderMismatch(..))
+        );
+        let mut tampered = evidence;
+        tampered.shred2.payload = tampered.shred1.payload.clone();
+        assert_matches!(
+            tampered.verify(&leader.pubkey()),
+            Err(DuplicateSlotEvidenceError::IdenticalShreds)
+        );
+
+        // Shreds signed by someone other than the leader are rejected
+        let proof = DuplicateSlotProof::new(
+            shred1.payload,
+            new_data_shred(slot, &Keypair::new()).payload,
+        );
+        let evidence = DuplicateSlotEvidence::new(slot, &proof, Some(leader.pubkey())).unwrap();
+        assert_eq!(evidence.shred2.signature_verified, Some(false));
+        assert_matches!(
+            evidence.verify(&leader.pubkey()),
+            Err(DuplicateSlotEvidenceError::InvalidSignature(2))
+        );
+
+        // Duplicate shreds signed by a node that did not lead the slot, which names itself as
+        // the leader, blame no one
+        let impostor = Keypair::new();
+        let proof = DuplicateSlotProof::new(
+            new_data_shred(slot, &impostor).payload,
+            new_data_shred(slot, &impostor).payload,
+        );
+        let evidence = DuplicateSlotEvidence::new(slot, &proof, Some(impostor.pubkey())).unwrap();
+        assert_eq!(evidence.shred1.signature_verified, Some(true));
+        assert_eq!(evidence.shred2.signature_verified, Some(true));
+        evidence.verify(&impostor.pubkey()).unwrap();
+        assert_matches!(
+            evidence.verify(&leader.pubkey()),
+            Err(DuplicateSlotEvidenceError::LeaderMismatch(named, slot_leader))
+                if named == impostor.pubkey() && slot_leader == leader.pubkey()
+        );
+    }
+}
@@ -11,10 +11,12 @@ pub mod block_error;
 pub mod blockstore;
 pub mod ancestor_iterator;
 pub mod block_cost_limits;
+pub mod blockstore_account_write_recorder;
 pub mod blockstore_db;
 pub mod blockstore_meta;
 pub mod blockstore_processor;
 pub mod builtins;
+pub mod duplicate_slot_evidence;
 pub mod erasure;
 pub mod genesis_utils;
 pub mod leader_schedule;
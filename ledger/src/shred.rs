@@ -469,12 +469,7 @@ impl Shred {
     pub fn seed(&self, leader_pubkey: Option<Pubkey>, root_bank: &Bank) -> [u8; 32] {
         if let Some(leader_pubkey) = leader_pubkey {
             if enable_deterministic_seed(self.slot(), root_bank) {
-                let h = hashv(&[
-                    &self.slot().to_le_bytes(),
-                    &self.index().to_le_bytes(),
-                    &leader_pubkey.to_bytes(),
-                ]);
-                return h.to_bytes();
+                return Self::deterministic_seed(self.slot(), self.index(), &leader_pubkey);
             }
         }
 
@@ -485,6 +480,17 @@ impl Shred {
         seed
     }
 
+    /// The seed of the shred at `index` in `slot` once deterministic shred seeds
+    /// are enabled, which does not depend on the shred payload.
+    pub fn deterministic_seed(slot: Slot, index: u32, leader_pubkey: &Pubkey) -> [u8; 32] {
+        hashv(&[
+            &slot.to_le_bytes(),
+            &index.to_le_bytes(),
+            &leader_pubkey.to_bytes(),
+        ])
+        .to_bytes()
+    }
+
     pub fn is_data(&self) -> bool {
         self.common_header.shred_type == ShredType(DATA_SHRED)
     }
@@ -567,7 +573,7 @@ impl Shred {
     }
 }
 
-fn enable_deterministic_seed(shred_slot: Slot, bank: &Bank) -> bool {
+pub fn enable_deterministic_seed(shred_slot: Slot, bank: &Bank) -> bool {
     let feature_slot = bank
         .feature_set
         .activated_slot(&feature_set::deterministic_shred_seed_enabled::id());
@@ -57,7 +57,10 @@ pub fn safe_clone_config(config: &ValidatorConfig) -> ValidatorConfig {
         poh_hashes_per_batch: config.poh_hashes_per_batch,
         no_wait_for_vote_to_start_leader: config.no_wait_for_vote_to_start_leader,
         accounts_shrink_ratio: config.accounts_shrink_ratio,
-        accounts_index_config: config.accounts_index_config,
+        accounts_index_config: config.accounts_index_config.clone(),
+        account_write_log: config.account_write_log,
+        account_write_log_data: config.account_write_log_data,
+        accounts_hash_cache_path: config.accounts_hash_cache_path.clone(),
     }
 }
 
@@ -1,14 +1,22 @@
 #![allow(clippy::integer_arithmetic)]
 extern crate byte_unit;
 
+mod timeline;
+mod validator_log;
+
 use byte_unit::Byte;
-use clap::{crate_description, crate_name, value_t_or_exit, App, Arg, ArgMatches, SubCommand};
+use clap::{
+    crate_description, crate_name, value_t, value_t_or_exit, values_t_or_exit, App, Arg,
+    ArgMatches, SubCommand,
+};
 
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::fs;
+use std::io::{BufRead, BufReader};
 use std::ops::Sub;
 use std::path::PathBuf;
+use timeline::Timeline;
 
 #[derive(Deserialize, Serialize, Debug)]
 struct IpAddrMapping {
@@ -196,6 +204,47 @@ fn analyze_logs(matches: &ArgMatches) {
     });
 }
 
+fn process_validator_logs(matches: &ArgMatches) {
+    let files = values_t_or_exit!(matches, "file", String);
+    let start_slot = value_t!(matches, "start_slot", u64).ok();
+    let end_slot = value_t!(matches, "end_slot", u64).ok();
+    let mut timeline = Timeline::new(start_slot, end_slot);
+
+    // Validator logs are commonly many gigabytes, so stream them line by line. Lines are
+    // decoded lossily since truncated or rotated logs may contain partial utf-8 sequences.
+    let mut buf = vec![];
+    for file in files {
+        let file = fs::File::open(&file)
+            .unwrap_or_else(|err| panic!("Unable to open log file {}: {}", file, err));
+        let mut reader = BufReader::new(file);
+        loop {
+            buf.clear();
+            if reader
+                .read_until(b'\n', &mut buf)
+                .expect("Unable to read log file")
+                == 0
+            {
+                break;
+            }
+            let line = String::from_utf8_lossy(&buf);
+            if let Some((timestamp, events)) = validator_log::parse_line(line.trim_end()) {
+                for event in events {
+                    timeline.record(timestamp, event);
+                }
+            }
+        }
+    }
+
+    let output = match matches.value_of("output_format") {
+        Some("html") => timeline.to_html(),
+        _ => timeline.to_json(),
+    };
+    match matches.value_of("output") {
+        Some(path) => fs::write(path, output).expect("Unable to write output file"),
+        None => println!("{}", output),
+    }
+}
+
 fn main() {
     solana_logger::setup();
 
@@ -246,11 +295,58 @@ fn main() {
                         .help("List all differences"),
                 ),
         )
+        .subcommand(
+            SubCommand::with_name("validator")
+                .about("Build a per-slot timeline from solana-validator log files")
+                .arg(
+                    Arg::with_name("file")
+                        .short("f")
+                        .long("file")
+                        .value_name("FILE")
+                        .takes_value(true)
+                        .multiple(true)
+                        .number_of_values(1)
+                        .required(true)
+                        .help("Validator log file, may be specified multiple times in log order"),
+                )
+                .arg(
+                    Arg::with_name("output_format")
+                        .long("output-format")
+                        .value_name("FORMAT")
+                        .takes_value(true)
+                        .possible_values(&["json", "html"])
+                        .default_value("json")
+                        .help("Output format of the timeline"),
+                )
+                .arg(
+                    Arg::with_name("start_slot")
+                        .long("start-slot")
+                        .value_name("SLOT")
+                        .takes_value(true)
+                        .help("Ignore slots before this slot"),
+                )
+                .arg(
+                    Arg::with_name("end_slot")
+                        .long("end-slot")
+                        .value_name("SLOT")
+                        .takes_value(true)
+                        .help("Ignore slots after this slot"),
+                )
+                .arg(
+                    Arg::with_name("output")
+                        .short("o")
+                        .long("output")
+                        .value_name("FILE")
+                        .takes_value(true)
+                        .help("Write the timeline to this file instead of stdout"),
+                ),
+        )
         .get_matches();
 
     match matches.subcommand() {
         ("iftop", Some(args_matches)) => process_iftop_logs(args_matches),
         ("analyze", Some(args_matches)) => analyze_logs(args_matches),
+        ("validator", Some(args_matches)) => process_validator_logs(args_matches),
         _ => {}
     };
 }
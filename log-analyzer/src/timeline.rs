@@ -0,0 +1,282 @@
+//! Per-slot timeline assembled from parsed validator log events
+
+use {
+    crate::validator_log::{Event, Slot},
+    serde::Serialize,
+    std::{collections::BTreeMap, fmt::Write},
+};
+
+#[derive(Debug, PartialEq, Serialize)]
+pub struct TimelineEvent {
+    pub timestamp: String,
+    pub event: &'static str,
+    #[serde(skip_serializing_if = "String::is_empty")]
+    pub details: String,
+}
+
+#[derive(Debug, Default, PartialEq, Serialize)]
+pub struct SlotTimeline {
+    pub slot: Slot,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub parent: Option<Slot>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub leader: Option<String>,
+    pub my_leader_slot: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub created: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub frozen: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub voted: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rooted: Option<String>,
+    /// Fields of the `replay-slot-stats` datapoint, times in microseconds
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub replay_stats: BTreeMap<String, i64>,
+    pub repair_requests: u64,
+    pub events: Vec<TimelineEvent>,
+    /// Whether any event of the slot is notable, see `Event::is_notable`
+    #[serde(skip)]
+    pub notable: bool,
+}
+
+#[derive(Debug, Default)]
+pub struct Timeline {
+    start_slot: Option<Slot>,
+    end_slot: Option<Slot>,
+    slots: BTreeMap<Slot, SlotTimeline>,
+}
+
+impl Timeline {
+    /// A timeline of the slots in `start_slot..=end_slot`
+    pub fn new(start_slot: Option<Slot>, end_slot: Option<Slot>) -> Self {
+        Self {
+            start_slot,
+            end_slot,
+            ..Self::default()
+        }
+    }
+
+    pub fn record(&mut self, timestamp: &str, event: Event) {
+        let slot = event.slot();
+        if self.start_slot.map(|start| slot < start).unwrap_or(false)
+            || self.end_slot.map(|end| slot > end).unwrap_or(false)
+        {
+            return;
+        }
+        let timeline = self.slots.entry(slot).or_insert_with(|| SlotTimeline {
+            slot,
+            ..SlotTimeline::default()
+        });
+        timeline.notable |= event.is_notable();
+        let timestamp = Some(timestamp.to_string());
+        match &event {
+            Event::NewFork { parent, leader, .. } => {
+                timeline.parent = Some(*parent);
+                timeline.my_leader_slot |= *leader;
+                timeline.created = timestamp.clone();
+            }
+            Event::LeaderChange { leader, .. } => timeline.leader = Some(leader.clone()),
+            Event::BankFrozen { .. } => timeline.frozen = timestamp.clone(),
+            Event::Vote { .. } => timeline.voted = timestamp.clone(),
+            Event::NewRoot { .. } => timeline.rooted = timestamp.clone(),
+            Event::ReplayStats { fields, .. } => {
+                timeline.replay_stats.extend(fields.iter().cloned());
+                // Captured in full by `replay_stats`
+                return;
+            }
+            Event::RepairRequests { count, .. } => {
+                timeline.repair_requests += count;
+                return;
+            }
+            Event::ForkSwitch { .. }
+            | Event::WaitingToSwitch { .. }
+            | Event::PartitionDetected { .. }
+            | Event::PartitionResolved { .. } => {}
+        }
+        timeline.events.push(TimelineEvent {
+            timestamp: timestamp.unwrap(),
+            event: event.name(),
+            details: event.details(),
+        });
+    }
+
+    pub fn slots(&self) -> impl Iterator<Item = &SlotTimeline> {
+        self.slots.values()
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(&self.slots().collect::<Vec<_>>()).unwrap()
+    }
+
+    /// Render a standalone HTML page with a row per slot. Leader slots and slots with fork
+    /// switches or partitions are highlighted.
+    pub fn to_html(&self) -> String {
+        let mut html = String::from(
+            "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n\
+             <title>Validator timeline</title>\n<style>\n\
+             body { font-family: monospace; }\n\
+             table { border-collapse: collapse; }\n\
+             th, td { border: 1px solid #ccc; padding: 2px 6px; vertical-align: top; }\n\
+             tr.leader { background: #e8f0ff; }\n\
+             tr.notable { background: #ffe8e8; }\n\
+             </style>\n</head>\n<body>\n<table>\n<tr><th>Slot</th><th>Parent</th>\
+             <th>Leader</th><th>Created</th><th>Frozen</th><th>Replay (ms)</th><th>Voted</th>\
+             <th>Rooted</th><th>Repairs</th><th>Events</th></tr>\n",
+        );
+        for slot in self.slots() {
+            let class = if slot.notable {
+                " class=\"notable\""
+            } else if slot.my_leader_slot {
+                " class=\"leader\""
+            } else {
+                ""
+            };
+            let replay_ms = slot
+                .replay_stats
+                .get("replay_total_elapsed")
+                .map(|us| format!("{:.1}", *us as f64 / 1000.))
+                .unwrap_or_default();
+            let events = slot
+                .events
+                .iter()
+                .map(|event| {
+                    format!(
+                        "{} {} {}",
+                        escape(&event.timestamp),
+                        event.event,
+                        escape(&event.details)
+                    )
+                })
+                .collect::<Vec<_>>()
+                .join("<br>");
+            writeln!(
+                html,
+                "<tr{}><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td>\
+                 <td>{}</td><td>{}</td><td>{}</td><td>{}</td></tr>",
+                class,
+                slot.slot,
+                slot.parent
+                    .map(|parent| parent.to_string())
+                    .unwrap_or_default(),
+                escape(slot.leader.as_deref().unwrap_or_default()),
+                escape(slot.created.as_deref().unwrap_or_default()),
+                escape(slot.frozen.as_deref().unwrap_or_default()),
+                replay_ms,
+                escape(slot.voted.as_deref().unwrap_or_default()),
+                escape(slot.rooted.as_deref().unwrap_or_default()),
+                slot.repair_requests,
+                events,
+            )
+            .unwrap();
+        }
+        html.push_str("</table>\n</body>\n</html>\n");
+        html
+    }
+}
+
+fn escape(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, crate::validator_log::parse_line};
+
+    fn timeline(log: &str, start_slot: Option<Slot>, end_slot: Option<Slot>) -> Timeline {
+        let mut timeline = Timeline::new(start_slot, end_slot);
+        for line in log.lines() {
+            if let Some((timestamp, events)) = parse_line(line) {
+                for event in events {
+                    timeline.record(timestamp, event);
+                }
+            }
+        }
+        timeline
+    }
+
+    const LOG: &str = "\
+[t1 INFO  solana_core::replay_stage] new fork:11 parent:10 (leader) root:5
+[t2 INFO  solana_core::repair_service] repair_stats: [(12, 2)]
+[t3 INFO  solana_core::replay_stage] bank frozen: 11
+[t4 INFO  solana_metrics::metrics] datapoint: replay-slot-stats slot=11i replay_total_elapsed=2500i
+[t5 INFO  solana_core::replay_stage] voting: 11 100
+[t6 INFO  solana_core::replay_stage] new fork:12 parent:10 root:5
+[t7 INFO  solana_core::replay_stage] vote bank: Some((12, SwitchProof(1111))) reset bank: 12
+[t8 INFO  solana_core::replay_stage] new root 11
+";
+
+    #[test]
+    fn test_timeline() {
+        let timeline = timeline(LOG, None, None);
+        let slots: Vec<_> = timeline.slots().collect();
+        assert_eq!(slots.len(), 2);
+        assert_eq!(
+            slots[0],
+            &SlotTimeline {
+                slot: 11,
+                parent: Some(10),
+                my_leader_slot: true,
+                created: Some("t1".to_string()),
+                frozen: Some("t3".to_string()),
+                voted: Some("t5".to_string()),
+                rooted: Some("t8".to_string()),
+                replay_stats: vec![("replay_total_elapsed".to_string(), 2500)]
+                    .into_iter()
+                    .collect(),
+                events: vec![
+                    TimelineEvent {
+                        timestamp: "t1".to_string(),
+                        event: "leader_slot",
+                        details: "parent 10 root 5".to_string(),
+                    },
+                    TimelineEvent {
+                        timestamp: "t3".to_string(),
+                        event: "bank_frozen",
+                        details: "".to_string(),
+                    },
+                    TimelineEvent {
+                        timestamp: "t5".to_string(),
+                        event: "vote",
+                        details: "".to_string(),
+                    },
+                    TimelineEvent {
+                        timestamp: "t8".to_string(),
+                        event: "new_root",
+                        details: "".to_string(),
+                    },
+                ],
+                ..SlotTimeline::default()
+            }
+        );
+        assert_eq!(slots[1].repair_requests, 2);
+        assert!(slots[1].notable);
+
+        let json: serde_json::Value = serde_json::from_str(&timeline.to_json()).unwrap();
+        assert_eq!(json[0]["slot"], 11);
+        assert_eq!(json[0]["replay_stats"]["replay_total_elapsed"], 2500);
+        assert_eq!(json[1]["events"][1]["event"], "fork_switch");
+
+        let html = timeline.to_html();
+        assert!(html.contains("<tr class=\"leader\"><td>11</td><td>10</td>"));
+        assert!(html.contains("<td>2.5</td>"));
+        assert!(html.contains("<tr class=\"notable\"><td>12</td>"));
+    }
+
+    #[test]
+    fn test_timeline_slot_range() {
+        let slots: Vec<_> = timeline(LOG, Some(12), None)
+            .slots()
+            .map(|slot| slot.slot)
+            .collect();
+        assert_eq!(slots, vec![12]);
+        let slots: Vec<_> = timeline(LOG, None, Some(11))
+            .slots()
+            .map(|slot| slot.slot)
+            .collect();
+        assert_eq!(slots, vec![11]);
+    }
+}
@@ -0,0 +1,387 @@
+//! Parser for `solana-validator` log lines
+//!
+//! Lines are expected in the format written by `solana_logger`:
+//! `[2021-10-19T03:00:00.123456789Z INFO  solana_core::replay_stage] voting: 1234 5678`
+
+pub type Slot = u64;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Event {
+    /// A bank was created for `slot`, by this validator if `leader` is set
+    NewFork {
+        slot: Slot,
+        parent: Slot,
+        root: Slot,
+        leader: bool,
+    },
+    LeaderChange {
+        slot: Slot,
+        leader: String,
+    },
+    BankFrozen {
+        slot: Slot,
+    },
+    /// Numeric fields of the `replay-slot-stats` datapoint
+    ReplayStats {
+        slot: Slot,
+        fields: Vec<(String, i64)>,
+    },
+    Vote {
+        slot: Slot,
+    },
+    NewRoot {
+        slot: Slot,
+    },
+    /// Voting on `slot` switched forks, as recorded by the switch fork decision of the vote
+    ForkSwitch {
+        slot: Slot,
+        reset_slot: Slot,
+        decision: String,
+    },
+    /// Voting on the heaviest fork at `slot` is delayed until the switch threshold is met
+    WaitingToSwitch {
+        slot: Slot,
+        reset_slot: Option<Slot>,
+    },
+    PartitionDetected {
+        slot: Slot,
+        reset_slot: Slot,
+    },
+    PartitionResolved {
+        slot: Slot,
+        reset_slot: Slot,
+    },
+    RepairRequests {
+        slot: Slot,
+        count: u64,
+    },
+}
+
+impl Event {
+    pub fn slot(&self) -> Slot {
+        match self {
+            Event::NewFork { slot, .. }
+            | Event::LeaderChange { slot, .. }
+            | Event::BankFrozen { slot }
+            | Event::ReplayStats { slot, .. }
+            | Event::Vote { slot }
+            | Event::NewRoot { slot }
+            | Event::ForkSwitch { slot, .. }
+            | Event::WaitingToSwitch { slot, .. }
+            | Event::PartitionDetected { slot, .. }
+            | Event::PartitionResolved { slot, .. }
+            | Event::RepairRequests { slot, .. } => *slot,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Event::NewFork { leader: true, .. } => "leader_slot",
+            Event::NewFork { leader: false, .. } => "new_fork",
+            Event::LeaderChange { .. } => "leader_change",
+            Event::BankFrozen { .. } => "bank_frozen",
+            Event::ReplayStats { .. } => "replay_stats",
+            Event::Vote { .. } => "vote",
+            Event::NewRoot { .. } => "new_root",
+            Event::ForkSwitch { .. } => "fork_switch",
+            Event::WaitingToSwitch { .. } => "waiting_to_switch",
+            Event::PartitionDetected { .. } => "partition_detected",
+            Event::PartitionResolved { .. } => "partition_resolved",
+            Event::RepairRequests { .. } => "repair_requests",
+        }
+    }
+
+    /// Whether the event points at a fork choice problem worth highlighting
+    pub fn is_notable(&self) -> bool {
+        matches!(
+            self,
+            Event::ForkSwitch { .. }
+                | Event::WaitingToSwitch { .. }
+                | Event::PartitionDetected { .. }
+                | Event::PartitionResolved { .. }
+        )
+    }
+
+    pub fn details(&self) -> String {
+        match self {
+            Event::NewFork { parent, root, .. } => format!("parent {} root {}", parent, root),
+            Event::LeaderChange { leader, .. } => leader.clone(),
+            Event::ReplayStats { fields, .. } => fields
+                .iter()
+                .map(|(name, value)| format!("{}={}", name, value))
+                .collect::<Vec<_>>()
+                .join(" "),
+            Event::ForkSwitch {
+                reset_slot,
+                decision,
+                ..
+            } => format!("{}, reset to {}", decision, reset_slot),
+            Event::WaitingToSwitch { reset_slot, .. } => match reset_slot {
+                Some(reset_slot) => format!("reset to {}", reset_slot),
+                None => "no reset bank".to_string(),
+            },
+            Event::PartitionDetected { reset_slot, .. }
+            | Event::PartitionResolved { reset_slot, .. } => format!("reset to {}", reset_slot),
+            Event::RepairRequests { count, .. } => count.to_string(),
+            Event::BankFrozen { .. } | Event::Vote { .. } | Event::NewRoot { .. } => {
+                String::default()
+            }
+        }
+    }
+}
+
+/// Split a log line into its timestamp, target and message
+fn split_line(line: &str) -> Option<(&str, &str, &str)> {
+    let line = line.strip_prefix('[')?;
+    let (header, message) = line.split_at(line.find("] ")?);
+    let mut header = header.split_whitespace();
+    let timestamp = header.next()?;
+    let _level = header.next()?;
+    let target = header.next()?;
+    Some((timestamp, target, &message[2..]))
+}
+
+/// Parse the slot number at the start of `s`
+fn leading_slot(s: &str) -> Option<Slot> {
+    let end = s.find(|c: char| !c.is_ascii_digit()).unwrap_or(s.len());
+    s[..end].parse().ok()
+}
+
+/// Parse the slot following `prefix` in `s`
+fn slot_after(s: &str, prefix: &str) -> Option<Slot> {
+    leading_slot(&s[s.find(prefix)? + prefix.len()..])
+}
+
+/// Parse `Some(123)` or `None`
+fn optional_slot(s: &str) -> Option<Option<Slot>> {
+    if s.starts_with("None") {
+        Some(None)
+    } else {
+        leading_slot(s.strip_prefix("Some(")?).map(Some)
+    }
+}
+
+fn parse_replay_stage(message: &str) -> Option<Event> {
+    if let Some(rest) = message.strip_prefix("voting: ") {
+        return Some(Event::Vote {
+            slot: leading_slot(rest)?,
+        });
+    }
+    if let Some(rest) = message.strip_prefix("new root ") {
+        return Some(Event::NewRoot {
+            slot: leading_slot(rest)?,
+        });
+    }
+    if let Some(rest) = message.strip_prefix("bank frozen: ") {
+        return Some(Event::BankFrozen {
+            slot: leading_slot(rest)?,
+        });
+    }
+    if let Some(rest) = message.strip_prefix("new fork:") {
+        return Some(Event::NewFork {
+            slot: leading_slot(rest)?,
+            parent: slot_after(rest, "parent:")?,
+            root: slot_after(rest, "root:")?,
+            leader: rest.contains("(leader)"),
+        });
+    }
+    if let Some(rest) = message.strip_prefix("LEADER CHANGE at slot: ") {
+        let leader = rest.split(" leader: ").nth(1)?;
+        return Some(Event::LeaderChange {
+            slot: leading_slot(rest)?,
+            leader: leader
+                .split(|c: char| !c.is_ascii_alphanumeric())
+                .next()?
+                .to_string(),
+        });
+    }
+    if let Some(rest) = message.strip_prefix("vote bank: Some((") {
+        // vote bank: Some((123, SwitchProof(hash))) reset bank: 123
+        let (slot, rest) = rest.split_at(rest.find(", ")?);
+        let decision_end = rest.find(")) reset bank: ")?;
+        let decision = &rest[2..decision_end];
+        if decision == "SameFork" {
+            return None;
+        }
+        return Some(Event::ForkSwitch {
+            slot: slot.parse().ok()?,
+            reset_slot: slot_after(rest, "reset bank: ")?,
+            decision: decision.to_string(),
+        });
+    }
+    if let Some(rest) = message.strip_prefix("Waiting to switch vote to ") {
+        return Some(Event::WaitingToSwitch {
+            slot: leading_slot(rest)?,
+            reset_slot: optional_slot(&rest[rest.find("resetting to slot ")? + 18..])?,
+        });
+    }
+    if let Some(rest) = message.strip_prefix("PARTITION DETECTED waiting to join heaviest fork: ") {
+        return Some(Event::PartitionDetected {
+            slot: leading_slot(rest)?,
+            reset_slot: slot_after(rest, "reset slot: ")?,
+        });
+    }
+    if let Some(rest) = message.strip_prefix("PARTITION resolved heaviest fork: ") {
+        return Some(Event::PartitionResolved {
+            slot: leading_slot(rest)?,
+            reset_slot: slot_after(rest, "reset slot: ")?,
+        });
+    }
+    None
+}
+
+/// Parse `datapoint: replay-slot-stats slot=123i replay_time=456i ...`
+fn parse_replay_stats(message: &str) -> Option<Event> {
+    let fields = message.strip_prefix("datapoint: replay-slot-stats ")?;
+    let mut slot = None;
+    let mut numeric_fields = vec![];
+    for field in fields.split(' ') {
+        let mut field = field.splitn(2, '=');
+        let (name, value) = (field.next()?, field.next()?);
+        let value = match value.trim_end_matches('i').parse::<i64>() {
+            Ok(value) => value,
+            Err(_) => continue,
+        };
+        if name == "slot" {
+            slot = Some(value as Slot);
+        } else {
+            numeric_fields.push((name.to_string(), value));
+        }
+    }
+    Some(Event::ReplayStats {
+        slot: slot?,
+        fields: numeric_fields,
+    })
+}
+
+/// Parse `repair_stats: [(123, 4), (124, 1)]`
+fn parse_repair_stats(message: &str) -> Vec<Event> {
+    let stats = match message.strip_prefix("repair_stats: [") {
+        Some(stats) => stats.trim_end_matches(']'),
+        None => return vec![],
+    };
+    stats
+        .split("), ")
+        .filter_map(|entry| {
+            let entry = entry.trim_start_matches('(').trim_end_matches(')');
+            let mut entry = entry.split(", ");
+            Some(Event::RepairRequests {
+                slot: entry.next()?.parse().ok()?,
+                count: entry.next()?.parse().ok()?,
+            })
+        })
+        .collect()
+}
+
+/// Parse the events of a log line, along with the line's timestamp
+pub fn parse_line(line: &str) -> Option<(&str, Vec<Event>)> {
+    let (timestamp, target, message) = split_line(line)?;
+    let events = match target {
+        "solana_core::replay_stage" => parse_replay_stage(message).into_iter().collect(),
+        "solana_core::repair_service" => parse_repair_stats(message),
+        "solana_metrics::metrics" => parse_replay_stats(message).into_iter().collect(),
+        _ => vec![],
+    };
+    if events.is_empty() {
+        None
+    } else {
+        Some((timestamp, events))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(line: &str) -> Vec<Event> {
+        parse_line(line)
+            .map(|(_, events)| events)
+            .unwrap_or_default()
+    }
+
+    #[test]
+    fn test_parse_replay_stage() {
+        let line =
+            "[2021-10-19T03:00:00.123456789Z INFO  solana_core::replay_stage] voting: 1234 5678";
+        assert_eq!(
+            parse_line(line),
+            Some((
+                "2021-10-19T03:00:00.123456789Z",
+                vec![Event::Vote { slot: 1234 }]
+            ))
+        );
+        assert_eq!(
+            parse("[t INFO  solana_core::replay_stage] new fork:12 parent:10 (leader) root:5"),
+            vec![Event::NewFork {
+                slot: 12,
+                parent: 10,
+                root: 5,
+                leader: true
+            }]
+        );
+        assert_eq!(
+            parse("[t INFO  solana_core::replay_stage] new fork:13 parent:12 root:5"),
+            vec![Event::NewFork {
+                slot: 13,
+                parent: 12,
+                root: 5,
+                leader: false
+            }]
+        );
+        assert_eq!(
+            parse("[t INFO  solana_core::replay_stage] LEADER CHANGE at slot: 16 leader: 4vJ9JU1bJJE96FWSJKvHsmmFADCg4gpZQff4P3bkLKi. I am now the leader"),
+            vec![Event::LeaderChange {
+                slot: 16,
+                leader: "4vJ9JU1bJJE96FWSJKvHsmmFADCg4gpZQff4P3bkLKi".to_string()
+            }]
+        );
+        assert_eq!(
+            parse("[t INFO  solana_core::replay_stage] vote bank: Some((20, SwitchProof(11111111111111111111111111111111))) reset bank: 20"),
+            vec![Event::ForkSwitch {
+                slot: 20,
+                reset_slot: 20,
+                decision: "SwitchProof(11111111111111111111111111111111)".to_string()
+            }]
+        );
+        assert!(parse(
+            "[t INFO  solana_core::replay_stage] vote bank: Some((20, SameFork)) reset bank: 20"
+        )
+        .is_empty());
+        assert_eq!(
+            parse("[t INFO  solana_core::replay_stage] Waiting to switch vote to 21, resetting to slot Some(19) for now"),
+            vec![Event::WaitingToSwitch {
+                slot: 21,
+                reset_slot: Some(19)
+            }]
+        );
+        assert_eq!(
+            parse("[t WARN  solana_core::replay_stage] PARTITION DETECTED waiting to join heaviest fork: 22 last vote: Some(18), reset slot: 19"),
+            vec![Event::PartitionDetected {
+                slot: 22,
+                reset_slot: 19
+            }]
+        );
+        assert!(parse("[t INFO  solana_core::replay_stage] unrelated 12").is_empty());
+        assert!(parse("[t INFO  solana_core::validator] voting: 12 0").is_empty());
+        assert!(parse("not a log line").is_empty());
+    }
+
+    #[test]
+    fn test_parse_datapoints() {
+        assert_eq!(
+            parse("[t INFO  solana_metrics::metrics] datapoint: replay-slot-stats slot=7i replay_time=1500i leader=\"abc\""),
+            vec![Event::ReplayStats {
+                slot: 7,
+                fields: vec![("replay_time".to_string(), 1500)]
+            }]
+        );
+        assert_eq!(
+            parse("[t INFO  solana_core::repair_service] repair_stats: [(7, 3), (9, 1)]"),
+            vec![
+                Event::RepairRequests { slot: 7, count: 3 },
+                Event::RepairRequests { slot: 9, count: 1 },
+            ]
+        );
+        assert!(parse("[t INFO  solana_core::repair_service] repair_stats: []").is_empty());
+    }
+}
@@ -5,4 +5,4 @@
 extern crate matches;
 
 pub mod merkle_tree;
-pub use merkle_tree::MerkleTree;
+pub use merkle_tree::{compute_root_from_path, MerkleTree, PathSibling};
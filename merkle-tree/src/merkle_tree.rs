@@ -41,6 +41,24 @@ impl<'a> ProofEntry<'a> {
 #[derive(Debug, Default, PartialEq)]
 pub struct Proof<'a>(Vec<ProofEntry<'a>>);
 
+/// A sibling of the node on a path from a leaf to the root, and the side it is hashed on
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PathSibling {
+    Left(Hash),
+    Right(Hash),
+}
+
+/// The root reached by hashing the leaf `item` up through `siblings`
+pub fn compute_root_from_path<T: AsRef<[u8]>>(item: T, siblings: &[PathSibling]) -> Hash {
+    let item = item.as_ref();
+    siblings
+        .iter()
+        .fold(hash_leaf!(item), |node, sibling| match sibling {
+            PathSibling::Left(lsib) => hash_intermediate!(lsib, node),
+            PathSibling::Right(rsib) => hash_intermediate!(node, rsib),
+        })
+}
+
 impl<'a> Proof<'a> {
     pub fn push(&mut self, entry: ProofEntry<'a>) {
         self.0.push(entry)
@@ -60,6 +78,18 @@ impl<'a> Proof<'a> {
         });
         matches!(result, Some(_))
     }
+
+    /// The siblings along the path, leaving out the nodes on it, which
+    /// `compute_root_from_path()` recomputes
+    pub fn siblings(&self) -> Vec<PathSibling> {
+        self.0
+            .iter()
+            .map(|pe| match (pe.1, pe.2) {
+                (Some(lsib), _) => PathSibling::Left(*lsib),
+                (None, rsib) => PathSibling::Right(*rsib.unwrap()),
+            })
+            .collect()
+    }
 }
 
 impl MerkleTree {
@@ -247,6 +277,19 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_path_siblings() {
+        for len in 1..=TEST.len() {
+            let mt = MerkleTree::new(&TEST[..len]);
+            let root = mt.get_root().unwrap();
+            for (i, s) in TEST[..len].iter().enumerate() {
+                let siblings = mt.find_path(i).unwrap().siblings();
+                assert_eq!(compute_root_from_path(s, &siblings), *root);
+                assert_ne!(compute_root_from_path(BAD[0], &siblings), *root);
+            }
+        }
+    }
+
     #[test]
     fn test_proof_entry_instantiation_lsib_set() {
         ProofEntry::new(&Hash::default(), Some(&Hash::default()), None);
@@ -1,6 +1,6 @@
 //! The `metrics` module enables sending measurements to an `InfluxDB` instance
 
-use crate::{counter::CounterPoint, datapoint::DataPoint};
+use crate::{counter::CounterPoint, datapoint::DataPoint, prometheus};
 use gethostname::gethostname;
 use lazy_static::lazy_static;
 use log::*;
@@ -243,6 +243,9 @@ impl MetricsAgent {
                     }
                     MetricsCommand::Submit(point, level) => {
                         log!(level, "{}", point);
+                        if prometheus::is_enabled() {
+                            prometheus::record_datapoint(&point);
+                        }
                         let (_, points) = points_map
                             .entry(level)
                             .or_insert((HashMap::new(), Vec::new()));
@@ -250,6 +253,9 @@ impl MetricsAgent {
                     }
                     MetricsCommand::SubmitCounter(counter, level, bucket) => {
                         debug!("{:?}", counter);
+                        if prometheus::is_enabled() {
+                            prometheus::record_counter(&counter);
+                        }
                         let (counters, _) = points_map
                             .entry(level)
                             .or_insert((HashMap::new(), Vec::new()));
@@ -343,6 +349,10 @@ pub fn set_host_id(host_id: String) {
     *HOST_ID.write().unwrap() = host_id;
 }
 
+pub(crate) fn host_id() -> String {
+    HOST_ID.read().unwrap().clone()
+}
+
 /// Submits a new point from any thread.  Note that points are internally queued
 /// and transmitted periodically in batches.
 pub fn submit(point: DataPoint, level: log::Level) {
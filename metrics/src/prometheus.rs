@@ -0,0 +1,368 @@
+//! The `prometheus` module mirrors submitted datapoints and counters into an in-process registry
+//! that can be scraped by Prometheus.
+//!
+//! Each numeric field of a datapoint becomes a gauge named `solana_<datapoint>_<field>` holding
+//! the last submitted value, and each counter becomes a `solana_<counter>_total` counter.  String
+//! fields are not exported.
+//!
+//! `MetricsWriter` and `MetricsEndpoint` are also usable on their own, by programs that export
+//! metrics of their own rather than the datapoint registry.
+
+use {
+    crate::{counter::CounterPoint, datapoint::DataPoint},
+    lazy_static::lazy_static,
+    log::*,
+    std::{
+        collections::BTreeMap,
+        fmt::Write as _,
+        io::{self, BufRead, BufReader, Write},
+        net::{SocketAddr, TcpListener, TcpStream},
+        sync::{
+            atomic::{AtomicBool, Ordering},
+            RwLock,
+        },
+        thread::{self, JoinHandle},
+        time::Duration,
+    },
+};
+
+lazy_static! {
+    static ref REGISTRY: RwLock<PrometheusRegistry> = RwLock::new(PrometheusRegistry::default());
+}
+static ENABLED: AtomicBool = AtomicBool::new(false);
+
+#[derive(Debug, Default)]
+pub struct PrometheusRegistry {
+    gauges: BTreeMap<String, f64>,
+    counters: BTreeMap<String, f64>,
+}
+
+impl PrometheusRegistry {
+    pub fn record_datapoint(&mut self, point: &DataPoint) {
+        let prefix = metric_name(point.name);
+        for (field, value) in &point.fields {
+            if let Some(value) = parse_field_value(value) {
+                self.gauges
+                    .insert(format!("{}_{}", prefix, sanitize(field)), value);
+            }
+        }
+    }
+
+    pub fn record_counter(&mut self, counter: &CounterPoint) {
+        *self
+            .counters
+            .entry(format!("{}_total", metric_name(counter.name)))
+            .or_default() += counter.count as f64;
+    }
+
+    /// Render the registry in the Prometheus text exposition format, labelling every sample
+    /// with `host_id`
+    pub fn render(&self, host_id: &str) -> String {
+        let mut metrics = MetricsWriter::default();
+        let labels = [("host_id", host_id)];
+        for (name, value) in &self.gauges {
+            metrics.sample(
+                name,
+                "Last submitted datapoint field value",
+                MetricType::Gauge,
+                &labels,
+                *value,
+            );
+        }
+        for (name, value) in &self.counters {
+            metrics.sample(
+                name,
+                "Total of the submitted counter",
+                MetricType::Counter,
+                &labels,
+                *value,
+            );
+        }
+        metrics.render()
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum MetricType {
+    Counter,
+    Gauge,
+}
+
+impl MetricType {
+    fn name(&self) -> &'static str {
+        match self {
+            MetricType::Counter => "counter",
+            MetricType::Gauge => "gauge",
+        }
+    }
+}
+
+struct Metric {
+    help: String,
+    metric_type: MetricType,
+    samples: Vec<(String, f64)>,
+}
+
+/// Accumulates samples, grouped by metric family, for rendering in one pass
+#[derive(Default)]
+pub struct MetricsWriter {
+    metrics: BTreeMap<String, Metric>,
+}
+
+impl MetricsWriter {
+    pub fn sample(
+        &mut self,
+        name: &str,
+        help: &str,
+        metric_type: MetricType,
+        labels: &[(&str, &str)],
+        value: f64,
+    ) {
+        let labels = labels
+            .iter()
+            .map(|(name, value)| format!("{}=\"{}\"", name, escape_label_value(value)))
+            .collect::<Vec<_>>()
+            .join(",");
+        self.metrics
+            .entry(name.to_string())
+            .or_insert_with(|| Metric {
+                help: help.to_string(),
+                metric_type,
+                samples: vec![],
+            })
+            .samples
+            .push((labels, value));
+    }
+
+    /// Render in the Prometheus text exposition format, with the `# HELP` and `# TYPE` lines
+    /// of each metric family ahead of its samples
+    pub fn render(&self) -> String {
+        let mut out = String::new();
+        for (name, metric) in &self.metrics {
+            writeln!(out, "# HELP {} {}", name, metric.help).unwrap();
+            writeln!(out, "# TYPE {} {}", name, metric.metric_type.name()).unwrap();
+            for (labels, value) in &metric.samples {
+                if labels.is_empty() {
+                    writeln!(out, "{} {}", name, value).unwrap();
+                } else {
+                    writeln!(out, "{}{{{}}} {}", name, labels, value).unwrap();
+                }
+            }
+        }
+        out
+    }
+}
+
+fn escape_label_value(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+/// Replace characters invalid in Prometheus metric names
+fn sanitize(name: &str) -> String {
+    name.chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect()
+}
+
+fn metric_name(name: &str) -> String {
+    format!("solana_{}", sanitize(name))
+}
+
+/// Numeric value of a field as formatted for InfluxDB's line protocol
+fn parse_field_value(value: &str) -> Option<f64> {
+    match value {
+        "true" => Some(1.),
+        "false" => Some(0.),
+        _ if value.starts_with('"') => None,
+        _ => value.trim_end_matches('i').parse().ok(),
+    }
+}
+
+pub(crate) fn is_enabled() -> bool {
+    ENABLED.load(Ordering::Relaxed)
+}
+
+pub(crate) fn record_datapoint(point: &DataPoint) {
+    REGISTRY.write().unwrap().record_datapoint(point);
+}
+
+pub(crate) fn record_counter(counter: &CounterPoint) {
+    REGISTRY.write().unwrap().record_counter(counter);
+}
+
+/// Serves the metrics returned by a render function on `GET /metrics`
+pub struct MetricsEndpoint {
+    local_addr: SocketAddr,
+    _thread: JoinHandle<()>,
+}
+
+impl MetricsEndpoint {
+    pub fn new<F>(bind_address: SocketAddr, render: F) -> io::Result<Self>
+    where
+        F: Fn() -> String + Send + 'static,
+    {
+        let listener = TcpListener::bind(bind_address)?;
+        let local_addr = listener.local_addr()?;
+        let thread = thread::Builder::new()
+            .name("solPrometheus".to_string())
+            .spawn(move || {
+                for stream in listener.incoming() {
+                    match stream {
+                        Ok(stream) => {
+                            if let Err(err) = serve(stream, &render) {
+                                debug!("prometheus request failed: {}", err);
+                            }
+                        }
+                        Err(err) => warn!("prometheus connection failed: {}", err),
+                    }
+                }
+            })?;
+        Ok(Self {
+            local_addr,
+            _thread: thread,
+        })
+    }
+
+    pub fn local_addr(&self) -> SocketAddr {
+        self.local_addr
+    }
+}
+
+/// Start mirroring datapoints and counters, and serve them on `GET /metrics` at `bind_address`
+pub fn serve_prometheus(bind_address: SocketAddr) -> io::Result<MetricsEndpoint> {
+    let endpoint = MetricsEndpoint::new(bind_address, || {
+        REGISTRY.read().unwrap().render(&crate::metrics::host_id())
+    })?;
+    ENABLED.store(true, Ordering::Relaxed);
+    info!(
+        "Serving Prometheus metrics on http://{}/metrics",
+        endpoint.local_addr()
+    );
+    Ok(endpoint)
+}
+
+fn serve(mut stream: TcpStream, render: &dyn Fn() -> String) -> io::Result<()> {
+    stream.set_read_timeout(Some(Duration::from_secs(5)))?;
+    let mut reader = BufReader::new(stream.try_clone()?);
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line)?;
+    // Drain the headers
+    let mut line = String::new();
+    while reader.read_line(&mut line)? > 2 {
+        line.clear();
+    }
+
+    let mut parts = request_line.split_whitespace();
+    let (status, content_type, body) = match (parts.next(), parts.next()) {
+        (Some("GET"), Some("/metrics")) => ("200 OK", "text/plain; version=0.0.4", render()),
+        _ => ("404 Not Found", "text/plain", "Not Found\n".to_string()),
+    };
+    write!(
+        stream,
+        "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        content_type,
+        body.len(),
+        body
+    )?;
+    stream.flush()
+}
+
+#[cfg(test)]
+mod test {
+    use {super::*, std::io::Read};
+
+    #[test]
+    fn test_registry() {
+        let mut registry = PrometheusRegistry::default();
+        registry.record_datapoint(
+            DataPoint::new("replay-slot-stats")
+                .add_field_i64("slot", 5)
+                .add_field_f64("ratio", 0.5)
+                .add_field_bool("ok", true)
+                .add_field_str("leader", "abc"),
+        );
+        registry.record_datapoint(DataPoint::new("replay-slot-stats").add_field_i64("slot", 6));
+        let mut counter = CounterPoint::new("bank-process_transactions");
+        counter.count = 3;
+        registry.record_counter(&counter);
+        registry.record_counter(&counter);
+
+        assert_eq!(
+            registry.render("host"),
+            "# HELP solana_bank_process_transactions_total Total of the submitted counter\n\
+             # TYPE solana_bank_process_transactions_total counter\n\
+             solana_bank_process_transactions_total{host_id=\"host\"} 6\n\
+             # HELP solana_replay_slot_stats_ok Last submitted datapoint field value\n\
+             # TYPE solana_replay_slot_stats_ok gauge\n\
+             solana_replay_slot_stats_ok{host_id=\"host\"} 1\n\
+             # HELP solana_replay_slot_stats_ratio Last submitted datapoint field value\n\
+             # TYPE solana_replay_slot_stats_ratio gauge\n\
+             solana_replay_slot_stats_ratio{host_id=\"host\"} 0.5\n\
+             # HELP solana_replay_slot_stats_slot Last submitted datapoint field value\n\
+             # TYPE solana_replay_slot_stats_slot gauge\n\
+             solana_replay_slot_stats_slot{host_id=\"host\"} 6\n"
+        );
+    }
+
+    #[test]
+    fn test_metrics_writer() {
+        let mut metrics = MetricsWriter::default();
+        metrics.sample(
+            "watchtower_validator_vote_lag_slots",
+            "Slots behind",
+            MetricType::Gauge,
+            &[("identity", "abc"), ("label", "a \"quoted\" label")],
+            3.,
+        );
+        metrics.sample(
+            "watchtower_cluster_transaction_count",
+            "Transactions",
+            MetricType::Counter,
+            &[],
+            10.,
+        );
+        metrics.sample(
+            "watchtower_validator_vote_lag_slots",
+            "Slots behind",
+            MetricType::Gauge,
+            &[("identity", "def"), ("label", "")],
+            0.5,
+        );
+        assert_eq!(
+            metrics.render(),
+            "# HELP watchtower_cluster_transaction_count Transactions\n\
+             # TYPE watchtower_cluster_transaction_count counter\n\
+             watchtower_cluster_transaction_count 10\n\
+             # HELP watchtower_validator_vote_lag_slots Slots behind\n\
+             # TYPE watchtower_validator_vote_lag_slots gauge\n\
+             watchtower_validator_vote_lag_slots{identity=\"abc\",label=\"a \\\"quoted\\\" label\"} 3\n\
+             watchtower_validator_vote_lag_slots{identity=\"def\",label=\"\"} 0.5\n"
+        );
+    }
+
+    #[test]
+    fn test_serve_prometheus() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        drop(listener);
+        serve_prometheus(addr).unwrap();
+        assert!(is_enabled());
+        record_datapoint(DataPoint::new("test-serve-prometheus").add_field_i64("value", 7));
+
+        let get = |path: &str| {
+            let mut stream = TcpStream::connect(addr).unwrap();
+            write!(stream, "GET {} HTTP/1.1\r\n\r\n", path).unwrap();
+            let mut response = String::new();
+            stream.read_to_string(&mut response).unwrap();
+            response
+        };
+        let response = get("/metrics");
+        assert!(response.starts_with("HTTP/1.1 200 OK\r\n"));
+        assert!(response.contains("\nsolana_test_serve_prometheus_value{host_id=\""));
+        assert!(get("/").starts_with("HTTP/1.1 404 Not Found\r\n"));
+    }
+}
@@ -17,10 +17,131 @@
 /// ```bash
 /// export TWILIO_CONFIG='ACCOUNT=<account>,TOKEN=<securityToken>,TO=<receivingNumber>,FROM=<sendingNumber>'
 /// ```
+///
+/// To open and resolve PagerDuty incidents, define the routing key of a PagerDuty Events API v2
+/// integration:
+/// ```bash
+/// export PAGERDUTY_ROUTING_KEY=...
+/// ```
+///
+/// To post alerts as JSON to any other HTTP endpoint, define the URL and optionally a JSON
+/// template.  `{{summary}}`, `{{severity}}`, `{{dedup_key}}`, `{{action}}` and `{{source}}` are
+/// substituted in the template's strings:
+/// ```bash
+/// export WEBHOOK_URL=...
+/// export WEBHOOK_TEMPLATE='{"text": "[{{severity}}] {{summary}}"}'
+/// ```
 use log::*;
-use reqwest::{blocking::Client, StatusCode};
-use serde_json::json;
-use std::{env, str::FromStr, thread::sleep, time::Duration};
+use reqwest::{
+    blocking::{Client, RequestBuilder},
+    StatusCode,
+};
+use serde_json::{json, Value};
+use std::{env, fmt, str::FromStr, thread::sleep, time::Duration};
+
+const PAGERDUTY_EVENTS_URL: &str = "https://events.pagerduty.com/v2/enqueue";
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+    Info,
+    Warning,
+    Error,
+    Critical,
+}
+
+impl Severity {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Severity::Info => "info",
+            Severity::Warning => "warning",
+            Severity::Error => "error",
+            Severity::Critical => "critical",
+        }
+    }
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AlertAction {
+    Trigger,
+    Resolve,
+}
+
+impl AlertAction {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AlertAction::Trigger => "trigger",
+            AlertAction::Resolve => "resolve",
+        }
+    }
+}
+
+/// A structured notification. Alerts sharing a `dedup_key` refer to the same incident, which
+/// sinks that support it (PagerDuty) open on `Trigger` and close on `Resolve`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Alert {
+    pub summary: String,
+    pub severity: Severity,
+    pub action: AlertAction,
+    pub dedup_key: Option<String>,
+    pub source: Option<String>,
+}
+
+impl Alert {
+    pub fn trigger(summary: &str, severity: Severity) -> Self {
+        Self {
+            summary: summary.to_string(),
+            severity,
+            action: AlertAction::Trigger,
+            dedup_key: None,
+            source: None,
+        }
+    }
+
+    pub fn resolve(summary: &str, dedup_key: &str) -> Self {
+        Self {
+            summary: summary.to_string(),
+            severity: Severity::Info,
+            action: AlertAction::Resolve,
+            dedup_key: Some(dedup_key.to_string()),
+            source: None,
+        }
+    }
+
+    pub fn dedup_key(mut self, dedup_key: &str) -> Self {
+        self.dedup_key = Some(dedup_key.to_string());
+        self
+    }
+
+    pub fn source(mut self, source: &str) -> Self {
+        self.source = Some(source.to_string());
+        self
+    }
+}
+
+/// How failed deliveries are retried: transport errors, 429 and 5xx responses are retried up to
+/// `max_attempts` times in total, doubling the delay between attempts
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct RetryPolicy {
+    pub max_attempts: usize,
+    pub initial_backoff: Duration,
+    pub max_backoff: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 5,
+            initial_backoff: Duration::from_millis(500),
+            max_backoff: Duration::from_secs(30),
+        }
+    }
+}
 
 struct TelegramWebHook {
     bot_token: String,
@@ -80,12 +201,72 @@ enum NotificationType {
     Slack(String),
     Telegram(TelegramWebHook),
     Twilio(TwilioWebHook),
+    PagerDuty(PagerDutyConfig),
+    Webhook(WebhookConfig),
     Log(Level),
 }
 
+struct PagerDutyConfig {
+    routing_key: String,
+    events_url: String,
+    source: String,
+}
+
+struct WebhookConfig {
+    url: String,
+    template: Value,
+}
+
+fn default_webhook_template() -> Value {
+    json!({
+        "summary": "{{summary}}",
+        "severity": "{{severity}}",
+        "dedupKey": "{{dedup_key}}",
+        "action": "{{action}}",
+        "source": "{{source}}",
+    })
+}
+
+/// Substitute the alert's fields into the strings of `template`
+fn render_template(template: &Value, alert: &Alert, source: &str) -> Value {
+    match template {
+        Value::String(s) => Value::String(
+            s.replace("{{summary}}", &alert.summary)
+                .replace("{{severity}}", alert.severity.as_str())
+                .replace(
+                    "{{dedup_key}}",
+                    alert.dedup_key.as_deref().unwrap_or_default(),
+                )
+                .replace("{{action}}", alert.action.as_str())
+                .replace("{{source}}", source),
+        ),
+        Value::Array(values) => Value::Array(
+            values
+                .iter()
+                .map(|value| render_template(value, alert, source))
+                .collect(),
+        ),
+        Value::Object(map) => Value::Object(
+            map.iter()
+                .map(|(key, value)| (key.clone(), render_template(value, alert, source)))
+                .collect(),
+        ),
+        value => value.clone(),
+    }
+}
+
+/// Text sent to sinks without structured alert support
+fn alert_text(alert: &Alert) -> String {
+    match alert.action {
+        AlertAction::Trigger => alert.summary.clone(),
+        AlertAction::Resolve => format!("RESOLVED: {}", alert.summary),
+    }
+}
+
 pub struct Notifier {
     client: Client,
     notifiers: Vec<NotificationType>,
+    retry_policy: RetryPolicy,
 }
 
 impl Notifier {
@@ -119,6 +300,30 @@ impl Notifier {
             notifiers.push(NotificationType::Twilio(webhook));
         }
 
+        if let Ok(routing_key) = env::var(format!("{}PAGERDUTY_ROUTING_KEY", env_prefix)) {
+            notifiers.push(NotificationType::PagerDuty(PagerDutyConfig {
+                routing_key,
+                events_url: env::var(format!("{}PAGERDUTY_EVENTS_URL", env_prefix))
+                    .unwrap_or_else(|_| PAGERDUTY_EVENTS_URL.to_string()),
+                source: env::var(format!("{}PAGERDUTY_SOURCE", env_prefix))
+                    .unwrap_or_else(|_| "solana".to_string()),
+            }));
+        }
+
+        if let Ok(url) = env::var(format!("{}WEBHOOK_URL", env_prefix)) {
+            let template = match env::var(format!("{}WEBHOOK_TEMPLATE", env_prefix)) {
+                Ok(template) => serde_json::from_str(&template).unwrap_or_else(|err| {
+                    warn!(
+                        "could not parse webhook template, using the default: {}",
+                        err
+                    );
+                    default_webhook_template()
+                }),
+                Err(_) => default_webhook_template(),
+            };
+            notifiers.push(NotificationType::Webhook(WebhookConfig { url, template }));
+        }
+
         if let Ok(log_level) = env::var(format!("{}LOG_NOTIFIER_LEVEL", env_prefix)) {
             match Level::from_str(&log_level) {
                 Ok(level) => notifiers.push(NotificationType::Log(level)),
@@ -134,14 +339,26 @@ impl Notifier {
         Notifier {
             client: Client::new(),
             notifiers,
+            retry_policy: RetryPolicy::default(),
         }
     }
 
+    pub fn with_retry_policy(mut self, retry_policy: RetryPolicy) -> Self {
+        self.retry_policy = retry_policy;
+        self
+    }
+
     pub fn is_empty(&self) -> bool {
         self.notifiers.is_empty()
     }
 
+    /// Send an unstructured message, as an error alert without a dedup key
     pub fn send(&self, msg: &str) {
+        self.send_alert(&Alert::trigger(msg, Severity::Error));
+    }
+
+    pub fn send_alert(&self, alert: &Alert) {
+        let msg = &alert_text(alert);
         for notifier in &self.notifiers {
             match notifier {
                 NotificationType::Discord(webhook) => {
@@ -151,40 +368,18 @@ impl Notifier {
 
                         info!("Sending {}", line);
                         let data = json!({ "content": line });
-
-                        loop {
-                            let response = self.client.post(webhook).json(&data).send();
-
-                            if let Err(err) = response {
-                                warn!("Failed to send Discord message: \"{}\": {:?}", line, err);
-                                break;
-                            } else if let Ok(response) = response {
-                                info!("response status: {}", response.status());
-                                if response.status() == StatusCode::TOO_MANY_REQUESTS {
-                                    warn!("rate limited!...");
-                                    warn!("response text: {:?}", response.text());
-                                    sleep(Duration::from_secs(2));
-                                } else {
-                                    break;
-                                }
-                            }
-                        }
+                        self.post_with_retry("Discord", || self.client.post(webhook).json(&data));
                     }
                 }
                 NotificationType::Slack(webhook) => {
                     let data = json!({ "text": msg });
-                    if let Err(err) = self.client.post(webhook).json(&data).send() {
-                        warn!("Failed to send Slack message: {:?}", err);
-                    }
+                    self.post_with_retry("Slack", || self.client.post(webhook).json(&data));
                 }
 
                 NotificationType::Telegram(TelegramWebHook { chat_id, bot_token }) => {
                     let data = json!({ "chat_id": chat_id, "text": msg });
                     let url = format!("https://api.telegram.org/bot{}/sendMessage", bot_token);
-
-                    if let Err(err) = self.client.post(&url).json(&data).send() {
-                        warn!("Failed to send Telegram message: {:?}", err);
-                    }
+                    self.post_with_retry("Telegram", || self.client.post(&url).json(&data));
                 }
 
                 NotificationType::Twilio(TwilioWebHook {
@@ -198,9 +393,36 @@ impl Notifier {
                         account, token, account
                     );
                     let params = [("To", to), ("From", from), ("Body", &msg.to_string())];
-                    if let Err(err) = self.client.post(&url).form(&params).send() {
-                        warn!("Failed to send Twilio message: {:?}", err);
+                    self.post_with_retry("Twilio", || self.client.post(&url).form(&params));
+                }
+                NotificationType::PagerDuty(PagerDutyConfig {
+                    routing_key,
+                    events_url,
+                    source,
+                }) => {
+                    let mut data = json!({
+                        "routing_key": routing_key,
+                        "event_action": alert.action.as_str(),
+                        "payload": {
+                            "summary": alert.summary,
+                            "source": alert.source.as_ref().unwrap_or(source),
+                            "severity": alert.severity.as_str(),
+                        },
+                    });
+                    match &alert.dedup_key {
+                        Some(dedup_key) => data["dedup_key"] = json!(dedup_key),
+                        None if alert.action == AlertAction::Resolve => {
+                            warn!("Not sending PagerDuty resolve event without a dedup key");
+                            continue;
+                        }
+                        None => {}
                     }
+                    self.post_with_retry("PagerDuty", || self.client.post(events_url).json(&data));
+                }
+                NotificationType::Webhook(WebhookConfig { url, template }) => {
+                    let source = alert.source.as_deref().unwrap_or_default();
+                    let data = render_template(template, alert, source);
+                    self.post_with_retry("webhook", || self.client.post(url).json(&data));
                 }
                 NotificationType::Log(level) => {
                     log!(*level, "{}", msg)
@@ -208,4 +430,71 @@ impl Notifier {
             }
         }
     }
+
+    /// Send the request built by `request`, retrying with exponential backoff according to the
+    /// retry policy.  Returns whether the request eventually succeeded.
+    fn post_with_retry<F>(&self, sink: &str, request: F) -> bool
+    where
+        F: Fn() -> RequestBuilder,
+    {
+        let mut backoff = self.retry_policy.initial_backoff;
+        for attempt in 1..=self.retry_policy.max_attempts {
+            let retryable = match request().send() {
+                Ok(response) if response.status().is_success() => return true,
+                Ok(response) => {
+                    let status = response.status();
+                    warn!(
+                        "Failed to send {} notification, attempt {}: {} {:?}",
+                        sink,
+                        attempt,
+                        status,
+                        response.text()
+                    );
+                    status == StatusCode::TOO_MANY_REQUESTS || status.is_server_error()
+                }
+                Err(err) => {
+                    warn!(
+                        "Failed to send {} notification, attempt {}: {:?}",
+                        sink, attempt, err
+                    );
+                    true
+                }
+            };
+            if !retryable || attempt == self.retry_policy.max_attempts {
+                break;
+            }
+            sleep(backoff);
+            backoff = (backoff * 2).min(self.retry_policy.max_backoff);
+        }
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_template() {
+        let template = json!({
+            "text": "[{{severity}}] {{summary}}",
+            "labels": ["{{dedup_key}}", 1],
+            "nested": {"action": "{{action}}", "source": "{{source}}"},
+        });
+        let alert = Alert::trigger("node \"a\" delinquent", Severity::Critical).dedup_key("a");
+        assert_eq!(
+            render_template(&template, &alert, "watchtower"),
+            json!({
+                "text": "[critical] node \"a\" delinquent",
+                "labels": ["a", 1],
+                "nested": {"action": "trigger", "source": "watchtower"},
+            })
+        );
+    }
+
+    #[test]
+    fn test_alert_text() {
+        assert_eq!(alert_text(&Alert::trigger("down", Severity::Error)), "down");
+        assert_eq!(alert_text(&Alert::resolve("up", "key")), "RESOLVED: up");
+    }
 }
@@ -0,0 +1,180 @@
+use {
+    serde_json::{json, Value},
+    solana_notifier::{Alert, Notifier, RetryPolicy, Severity},
+    std::{
+        env,
+        io::{BufRead, BufReader, Read, Write},
+        net::{SocketAddr, TcpListener},
+        sync::{Arc, Mutex},
+        thread,
+        time::Duration,
+    },
+};
+
+/// Local HTTP stand-in for a notification service. Answers requests with the given status codes
+/// in turn, then with 200, and records the JSON body of each request.
+struct StandIn {
+    addr: SocketAddr,
+    requests: Arc<Mutex<Vec<Value>>>,
+}
+
+impl StandIn {
+    fn new(statuses: Vec<u16>) -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let requests = Arc::new(Mutex::new(vec![]));
+        let thread_requests = requests.clone();
+        thread::spawn(move || {
+            let mut statuses = statuses.into_iter();
+            for stream in listener.incoming() {
+                let mut stream = stream.unwrap();
+                let mut reader = BufReader::new(stream.try_clone().unwrap());
+                let mut content_length = 0;
+                loop {
+                    let mut line = String::new();
+                    reader.read_line(&mut line).unwrap();
+                    if line.trim().is_empty() {
+                        break;
+                    }
+                    let line = line.to_ascii_lowercase();
+                    if let Some(length) = line.strip_prefix("content-length:") {
+                        content_length = length.trim().parse().unwrap();
+                    }
+                }
+                let mut body = vec![0; content_length];
+                reader.read_exact(&mut body).unwrap();
+                thread_requests
+                    .lock()
+                    .unwrap()
+                    .push(serde_json::from_slice(&body).unwrap());
+
+                let status = statuses.next().unwrap_or(200);
+                write!(
+                    stream,
+                    "HTTP/1.1 {} Stand-In\r\nContent-Length: 0\r\nConnection: close\r\n\r\n",
+                    status
+                )
+                .unwrap();
+            }
+        });
+        Self { addr, requests }
+    }
+
+    fn url(&self) -> String {
+        format!("http://{}/", self.addr)
+    }
+
+    fn requests(&self) -> Vec<Value> {
+        self.requests.lock().unwrap().clone()
+    }
+}
+
+fn fast_retry(max_attempts: usize) -> RetryPolicy {
+    RetryPolicy {
+        max_attempts,
+        initial_backoff: Duration::from_millis(1),
+        max_backoff: Duration::from_millis(10),
+    }
+}
+
+#[test]
+fn test_pagerduty_trigger_and_resolve() {
+    let stand_in = StandIn::new(vec![]);
+    env::set_var("PD_TEST_PAGERDUTY_ROUTING_KEY", "routing-key");
+    env::set_var("PD_TEST_PAGERDUTY_EVENTS_URL", stand_in.url());
+    let notifier = Notifier::new("PD_TEST_").with_retry_policy(fast_retry(1));
+
+    notifier.send_alert(
+        &Alert::trigger("validator delinquent", Severity::Critical)
+            .dedup_key("validator-delinquent")
+            .source("watchtower"),
+    );
+    notifier.send_alert(&Alert::resolve("validator voting", "validator-delinquent"));
+    // Resolve events can't be matched to an incident without a dedup key
+    notifier.send_alert(&Alert {
+        dedup_key: None,
+        ..Alert::resolve("ignored", "")
+    });
+    notifier.send("unstructured");
+
+    assert_eq!(
+        stand_in.requests(),
+        vec![
+            json!({
+                "routing_key": "routing-key",
+                "event_action": "trigger",
+                "dedup_key": "validator-delinquent",
+                "payload": {
+                    "summary": "validator delinquent",
+                    "source": "watchtower",
+                    "severity": "critical",
+                },
+            }),
+            json!({
+                "routing_key": "routing-key",
+                "event_action": "resolve",
+                "dedup_key": "validator-delinquent",
+                "payload": {
+                    "summary": "validator voting",
+                    "source": "solana",
+                    "severity": "info",
+                },
+            }),
+            json!({
+                "routing_key": "routing-key",
+                "event_action": "trigger",
+                "payload": {
+                    "summary": "unstructured",
+                    "source": "solana",
+                    "severity": "error",
+                },
+            }),
+        ]
+    );
+}
+
+#[test]
+fn test_webhook_template() {
+    let stand_in = StandIn::new(vec![]);
+    env::set_var("HOOK_TEST_WEBHOOK_URL", stand_in.url());
+    env::set_var(
+        "HOOK_TEST_WEBHOOK_TEMPLATE",
+        r#"{"text": "[{{severity}}] {{summary}}", "key": "{{dedup_key}}", "state": "{{action}}"}"#,
+    );
+    let notifier = Notifier::new("HOOK_TEST_").with_retry_policy(fast_retry(1));
+
+    notifier.send_alert(&Alert::trigger("low balance", Severity::Warning).dedup_key("balance"));
+    notifier.send_alert(&Alert::resolve("balance restored", "balance"));
+    assert_eq!(
+        stand_in.requests(),
+        vec![
+            json!({"text": "[warning] low balance", "key": "balance", "state": "trigger"}),
+            json!({"text": "[info] balance restored", "key": "balance", "state": "resolve"}),
+        ]
+    );
+}
+
+#[test]
+fn test_retry_with_backoff() {
+    // Server errors and rate limiting are retried
+    let stand_in = StandIn::new(vec![503, 429]);
+    env::set_var("RETRY_TEST_WEBHOOK_URL", stand_in.url());
+    let notifier = Notifier::new("RETRY_TEST_").with_retry_policy(fast_retry(3));
+    notifier.send("retried");
+    assert_eq!(stand_in.requests().len(), 3);
+
+    // Until the attempts run out
+    let stand_in = StandIn::new(vec![500, 500, 500, 500]);
+    env::set_var("EXHAUST_TEST_WEBHOOK_URL", stand_in.url());
+    let notifier = Notifier::new("EXHAUST_TEST_").with_retry_policy(fast_retry(2));
+    notifier.send("dropped");
+    assert_eq!(stand_in.requests().len(), 2);
+
+    // Client errors are not
+    let stand_in = StandIn::new(vec![400]);
+    env::set_var("REJECT_TEST_WEBHOOK_URL", stand_in.url());
+    let notifier = Notifier::new("REJECT_TEST_").with_retry_policy(fast_retry(3));
+    notifier.send("rejected");
+    assert_eq!(stand_in.requests().len(), 1);
+    assert_eq!(stand_in.requests()[0]["summary"], json!("rejected"));
+}
@@ -13,12 +13,13 @@ use {
         bank::{Bank, Builtin, ExecuteTimings},
         bank_forks::BankForks,
         commitment::BlockCommitmentCache,
+        compute_profiler::ComputeProfile,
         genesis_utils::{create_genesis_config_with_leader_ex, GenesisConfigInfo},
     },
     solana_sdk::{
         account::{Account, AccountSharedData, ReadableAccount, WritableAccount},
         account_info::AccountInfo,
-        clock::{Clock, Slot},
+        clock::{Clock, Epoch, Slot, UnixTimestamp},
         compute_budget::ComputeBudget,
         entrypoint::{ProgramResult, SUCCESS},
         epoch_schedule::EpochSchedule,
@@ -35,7 +36,7 @@ use {
         rent::Rent,
         signature::{Keypair, Signer},
         sysvar::{
-            clock, epoch_schedule,
+            self, clock, epoch_schedule,
             fees::{self},
             rent, Sysvar,
         },
@@ -46,13 +47,13 @@ use {
         collections::HashMap,
         convert::TryFrom,
         fs::File,
-        io::{self, Read},
+        io::{self, Read, Write},
         mem::transmute,
         path::{Path, PathBuf},
         rc::Rc,
         sync::{
             atomic::{AtomicBool, Ordering},
-            Arc, RwLock,
+            Arc, Mutex, RwLock,
         },
         time::{Duration, Instant},
     },
@@ -77,6 +78,9 @@ pub enum ProgramTestError {
     /// The chosen warp slot is not in the future, so warp is not performed
     #[error("Warp slot not in the future")]
     InvalidWarpSlot,
+    /// The chosen warp epoch is not in the future, so warp is not performed
+    #[error("Warp epoch not in the future")]
+    InvalidWarpEpoch,
 }
 
 thread_local! {
@@ -467,6 +471,8 @@ pub struct ProgramTest {
     compute_max_units: Option<u64>,
     prefer_bpf: bool,
     use_bpf_jit: bool,
+    compute_profiling: bool,
+    program_names: HashMap<Pubkey, String>,
 }
 
 impl Default for ProgramTest {
@@ -497,6 +503,8 @@ impl Default for ProgramTest {
             compute_max_units: None,
             prefer_bpf,
             use_bpf_jit: false,
+            compute_profiling: false,
+            program_names: HashMap::new(),
         }
     }
 }
@@ -541,6 +549,15 @@ impl ProgramTest {
         self.use_bpf_jit = use_bpf_jit;
     }
 
+    /// Profile the compute units consumed by BPF programs, see
+    /// `ProgramTestContext::write_compute_profile`
+    ///
+    /// Programs loaded as native code are not profiled, and per-function counts are only
+    /// available for interpreted programs with symbols
+    pub fn enable_compute_profiling(&mut self, compute_profiling: bool) {
+        self.compute_profiling = compute_profiling;
+    }
+
     /// Add an account to the test environment
     pub fn add_account(&mut self, address: Pubkey, account: Account) {
         self.accounts
@@ -604,6 +621,9 @@ impl ProgramTest {
         program_id: Pubkey,
         process_instruction: Option<ProcessInstructionWithContext>,
     ) {
+        self.program_names
+            .insert(program_id, program_name.to_string());
+
         let add_bpf = |this: &mut ProgramTest, program_file: PathBuf| {
             let data = read_file(&program_file);
             info!(
@@ -798,6 +818,9 @@ impl ProgramTest {
                 ..ComputeBudget::default()
             }));
         }
+        if self.compute_profiling {
+            bank.set_compute_profile(Some(Arc::new(Mutex::new(ComputeProfile::default()))));
+        }
         let bank = setup_fee_calculator(bank);
         let slot = bank.slot();
         let last_blockhash = bank.last_blockhash();
@@ -862,6 +885,7 @@ impl ProgramTest {
             banks_client,
             last_blockhash,
             gci,
+            self.program_names,
         )
     }
 }
@@ -911,6 +935,21 @@ impl<T> Drop for DroppableTask<T> {
     }
 }
 
+/// The account state of a `ProgramTestContext` at some point in a test,
+/// which can be restored later with `ProgramTestContext::restore_snapshot`
+#[derive(Clone, Debug)]
+pub struct ProgramTestSnapshot {
+    slot: Slot,
+    accounts: HashMap<Pubkey, AccountSharedData>,
+}
+
+impl ProgramTestSnapshot {
+    /// The slot of the working bank when the snapshot was taken
+    pub fn slot(&self) -> Slot {
+        self.slot
+    }
+}
+
 pub struct ProgramTestContext {
     pub banks_client: BanksClient,
     pub last_blockhash: Hash,
@@ -918,6 +957,7 @@ pub struct ProgramTestContext {
     genesis_config: GenesisConfig,
     bank_forks: Arc<RwLock<BankForks>>,
     block_commitment_cache: Arc<RwLock<BlockCommitmentCache>>,
+    program_names: HashMap<Pubkey, String>,
     _bank_task: DroppableTask<()>,
 }
 
@@ -928,6 +968,7 @@ impl ProgramTestContext {
         banks_client: BanksClient,
         last_blockhash: Hash,
         genesis_config_info: GenesisConfigInfo,
+        program_names: HashMap<Pubkey, String>,
     ) -> Self {
         // Run a simulated PohService to provide the client with new blockhashes.  New blockhashes
         // are required when sending multiple otherwise identical transactions in series from a
@@ -962,6 +1003,7 @@ impl ProgramTestContext {
             genesis_config: genesis_config_info.genesis_config,
             bank_forks,
             block_commitment_cache,
+            program_names,
             _bank_task: bank_task,
         }
     }
@@ -970,6 +1012,26 @@ impl ProgramTestContext {
         &self.genesis_config
     }
 
+    /// Compute profile of the transactions processed so far, if enabled with
+    /// `ProgramTest::enable_compute_profiling`
+    pub fn compute_profile(&self) -> Option<ComputeProfile> {
+        self.bank_forks
+            .read()
+            .unwrap()
+            .working_bank()
+            .compute_profile()
+            .map(|compute_profile| compute_profile.lock().unwrap().clone())
+    }
+
+    /// Write the compute profile as flamegraph-compatible folded stacks, naming the programs
+    /// added with `ProgramTest::add_program`
+    pub fn write_compute_profile<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        let compute_profile = self.compute_profile().ok_or_else(|| {
+            io::Error::new(io::ErrorKind::Other, "Compute profiling is not enabled")
+        })?;
+        compute_profile.write_folded_stacks(writer, &self.program_names)
+    }
+
     /// Manually increment vote credits for the current epoch in the specified vote account to simulate validator voting activity
     pub fn increment_vote_account_credits(
         &mut self,
@@ -992,6 +1054,88 @@ impl ProgramTestContext {
         bank.store_account(vote_account_address, &vote_account);
     }
 
+    /// Create or overwrite an account in the working bank, subverting normal runtime checks
+    pub fn set_account(&mut self, address: &Pubkey, account: &AccountSharedData) {
+        let bank_forks = self.bank_forks.read().unwrap();
+        let bank = bank_forks.working_bank();
+        bank.store_account_and_update_capitalization_for_tests(address, account);
+    }
+
+    /// Overwrite a sysvar in the working bank, subverting normal runtime checks
+    pub fn set_sysvar<T: Sysvar>(&self, sysvar: &T) {
+        let bank_forks = self.bank_forks.read().unwrap();
+        let bank = bank_forks.working_bank();
+        bank.set_sysvar_for_tests(sysvar);
+    }
+
+    /// Override the `unix_timestamp` of the `Clock` sysvar. Later slots carry the
+    /// timestamp forward, since there are no validator votes to correct it.
+    pub fn set_clock_unix_timestamp(&mut self, unix_timestamp: UnixTimestamp) {
+        let bank_forks = self.bank_forks.read().unwrap();
+        let bank = bank_forks.working_bank();
+        let clock = Clock {
+            unix_timestamp,
+            ..bank.clock()
+        };
+        bank.set_sysvar_for_tests(&clock);
+    }
+
+    /// Force the working bank ahead to the first slot of a new epoch. Each epoch
+    /// boundary in between is crossed in turn, so that stake activation, stake
+    /// history and rewards are processed for every epoch, as on a real cluster.
+    pub fn warp_to_epoch(&mut self, warp_epoch: Epoch) -> Result<(), ProgramTestError> {
+        let (epoch_schedule, epoch) = {
+            let bank = self.bank_forks.read().unwrap().working_bank();
+            (bank.epoch_schedule().clone(), bank.epoch())
+        };
+        if warp_epoch <= epoch {
+            return Err(ProgramTestError::InvalidWarpEpoch);
+        }
+        for epoch in epoch + 1..=warp_epoch {
+            self.warp_to_slot(epoch_schedule.get_first_slot_in_epoch(epoch))?;
+        }
+        Ok(())
+    }
+
+    /// Record the state of all non-sysvar accounts in the working bank
+    pub fn take_snapshot(&self) -> ProgramTestSnapshot {
+        let bank = self.bank_forks.read().unwrap().working_bank();
+        ProgramTestSnapshot {
+            slot: bank.slot(),
+            accounts: Self::get_non_sysvar_accounts(&bank),
+        }
+    }
+
+    /// Return all non-sysvar accounts to their state in the snapshot. Accounts
+    /// created since are removed. The slot, blockhash and sysvars are not
+    /// rewound, so the test keeps moving forward in time.
+    pub fn restore_snapshot(&mut self, snapshot: &ProgramTestSnapshot) {
+        let bank = self.bank_forks.read().unwrap().working_bank();
+        let accounts = Self::get_non_sysvar_accounts(&bank);
+        for address in accounts.keys() {
+            if !snapshot.accounts.contains_key(address) {
+                bank.store_account_and_update_capitalization_for_tests(
+                    address,
+                    &AccountSharedData::default(),
+                );
+            }
+        }
+        for (address, account) in &snapshot.accounts {
+            if accounts.get(address) != Some(account) {
+                bank.store_account_and_update_capitalization_for_tests(address, account);
+            }
+        }
+    }
+
+    fn get_non_sysvar_accounts(bank: &Bank) -> HashMap<Pubkey, AccountSharedData> {
+        bank.get_all_accounts_with_modified_slots()
+            .unwrap()
+            .into_iter()
+            .filter(|(_, account, _)| account.owner() != &sysvar::id())
+            .map(|(address, account, _)| (address, account))
+            .collect()
+    }
+
     /// Force the working bank ahead to a new slot
     pub fn warp_to_slot(&mut self, warp_slot: Slot) -> Result<(), ProgramTestError> {
         let mut bank_forks = self.bank_forks.write().unwrap();
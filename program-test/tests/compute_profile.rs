@@ -0,0 +1,64 @@
+use {
+    solana_program_test::ProgramTest,
+    solana_sdk::{
+        instruction::Instruction, pubkey::Pubkey, signature::Signer, transaction::Transaction,
+    },
+    std::str::FromStr,
+};
+
+#[tokio::test]
+async fn compute_profile_bpf_program() {
+    // spl-memo v3, loaded into every program test environment as a BPF program
+    let memo_program_id = Pubkey::from_str("MemoSq4gqABAXKb96qnH8TysNcWxMyWCqXgDLGmfcHr").unwrap();
+    let mut program_test = ProgramTest::default();
+    program_test.enable_compute_profiling(true);
+    let mut context = program_test.start_with_context().await;
+    assert!(context.compute_profile().unwrap().invocations().is_empty());
+
+    let transaction = Transaction::new_signed_with_payer(
+        &[Instruction::new_with_bytes(
+            memo_program_id,
+            b"profile",
+            vec![],
+        )],
+        Some(&context.payer.pubkey()),
+        &[&context.payer],
+        context.last_blockhash,
+    );
+    context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap();
+
+    let compute_profile = context.compute_profile().unwrap();
+    let invocations = compute_profile.invocations();
+    assert_eq!(invocations.len(), 1);
+    assert_eq!(invocations[0].program_id, memo_program_id);
+    assert_eq!(invocations[0].depth, 1);
+    assert!(invocations[0].units_consumed > 0);
+    assert_eq!(
+        compute_profile.stacks().values().sum::<u64>(),
+        compute_profile.total_units()
+    );
+
+    let mut folded = vec![];
+    context.write_compute_profile(&mut folded).unwrap();
+    let folded = String::from_utf8(folded).unwrap();
+    let total_units: u64 = folded
+        .lines()
+        .map(|line| {
+            let (stack, units) = line.rsplit_once(' ').unwrap();
+            assert!(stack.starts_with(&memo_program_id.to_string()));
+            units.parse::<u64>().unwrap()
+        })
+        .sum();
+    assert_eq!(total_units, compute_profile.total_units());
+}
+
+#[tokio::test]
+async fn compute_profile_disabled() {
+    let context = ProgramTest::default().start_with_context().await;
+    assert!(context.compute_profile().is_none());
+    assert!(context.write_compute_profile(&mut vec![]).is_err());
+}
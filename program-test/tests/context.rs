@@ -0,0 +1,95 @@
+use {
+    solana_program_test::ProgramTest,
+    solana_sdk::{
+        account::{AccountSharedData, ReadableAccount, WritableAccount},
+        clock::Clock,
+        pubkey::Pubkey,
+        signature::Signer,
+        system_instruction,
+        transaction::Transaction,
+    },
+};
+
+#[tokio::test]
+async fn set_account_and_clock() {
+    let mut context = ProgramTest::default().start_with_context().await;
+
+    let address = Pubkey::new_unique();
+    let owner = Pubkey::new_unique();
+    let mut account = AccountSharedData::new(42, 3, &owner);
+    account.set_data(vec![1, 2, 3]);
+    context.set_account(&address, &account);
+    let fetched = context
+        .banks_client
+        .get_account(address)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(fetched.lamports, 42);
+    assert_eq!(fetched.data(), &[1, 2, 3]);
+    assert_eq!(fetched.owner, owner);
+
+    let unix_timestamp = 1_000_000_000;
+    context.set_clock_unix_timestamp(unix_timestamp);
+    let clock: Clock = context.banks_client.get_sysvar().await.unwrap();
+    assert_eq!(clock.unix_timestamp, unix_timestamp);
+
+    // Later slots keep counting from the overridden timestamp
+    context.warp_to_slot(clock.slot + 10).unwrap();
+    let clock: Clock = context.banks_client.get_sysvar().await.unwrap();
+    assert!(clock.unix_timestamp >= unix_timestamp);
+}
+
+#[tokio::test]
+async fn snapshot_and_restore() {
+    let mut context = ProgramTest::default().start_with_context().await;
+    let payer = context.payer.pubkey();
+    let recipient = Pubkey::new_unique();
+    let payer_lamports = context.banks_client.get_balance(payer).await.unwrap();
+
+    let snapshot = context.take_snapshot();
+
+    let transaction = Transaction::new_signed_with_payer(
+        &[system_instruction::transfer(&payer, &recipient, 1_000_000)],
+        Some(&payer),
+        &[&context.payer],
+        context.last_blockhash,
+    );
+    context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap();
+    assert_eq!(
+        context.banks_client.get_balance(recipient).await.unwrap(),
+        1_000_000
+    );
+
+    context.restore_snapshot(&snapshot);
+    assert_eq!(
+        context.banks_client.get_account(recipient).await.unwrap(),
+        None
+    );
+    assert_eq!(
+        context.banks_client.get_balance(payer).await.unwrap(),
+        payer_lamports
+    );
+
+    // The same transfer can be run again from the restored state
+    context.warp_to_slot(snapshot.slot() + 2).unwrap();
+    let transaction = Transaction::new_signed_with_payer(
+        &[system_instruction::transfer(&payer, &recipient, 1_000_000)],
+        Some(&payer),
+        &[&context.payer],
+        context.last_blockhash,
+    );
+    context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap();
+    assert_eq!(
+        context.banks_client.get_balance(recipient).await.unwrap(),
+        1_000_000
+    );
+}
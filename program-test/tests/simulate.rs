@@ -0,0 +1,100 @@
+use {
+    solana_program_test::{processor, ProgramTest},
+    solana_sdk::{
+        account_info::AccountInfo,
+        entrypoint::ProgramResult,
+        instruction::{Instruction, InstructionError},
+        msg,
+        program_error::ProgramError,
+        pubkey::Pubkey,
+        signature::Signer,
+        transaction::{Transaction, TransactionError},
+    },
+    std::str::FromStr,
+};
+
+fn process_instruction(
+    _program_id: &Pubkey,
+    _accounts: &[AccountInfo],
+    input: &[u8],
+) -> ProgramResult {
+    msg!("Processing instruction {:?}", input);
+    if input.is_empty() {
+        Err(ProgramError::InvalidInstructionData)
+    } else {
+        Ok(())
+    }
+}
+
+#[tokio::test]
+async fn simulate_transaction_logs() {
+    let program_id = Pubkey::new_unique();
+    let program_test = ProgramTest::new(
+        "program-test-simulate",
+        program_id,
+        processor!(process_instruction),
+    );
+    let (mut banks_client, payer, recent_blockhash) = program_test.start().await;
+
+    let transaction = Transaction::new_signed_with_payer(
+        &[Instruction::new_with_bytes(program_id, &[1, 2], vec![])],
+        Some(&payer.pubkey()),
+        &[&payer],
+        recent_blockhash,
+    );
+    let simulation = banks_client
+        .simulate_transaction(transaction)
+        .await
+        .unwrap();
+    assert_eq!(simulation.result, Ok(()));
+    assert!(simulation
+        .logs
+        .iter()
+        .any(|log| log.contains("Processing instruction [1, 2]")));
+
+    let transaction = Transaction::new_signed_with_payer(
+        &[Instruction::new_with_bytes(program_id, &[], vec![])],
+        Some(&payer.pubkey()),
+        &[&payer],
+        recent_blockhash,
+    );
+    let simulation = banks_client
+        .simulate_transaction(transaction)
+        .await
+        .unwrap();
+    assert_eq!(
+        simulation.result,
+        Err(TransactionError::InstructionError(
+            0,
+            InstructionError::InvalidInstructionData
+        ))
+    );
+    assert!(simulation
+        .logs
+        .iter()
+        .any(|log| log.contains("Processing instruction []")));
+}
+
+#[tokio::test]
+async fn simulate_transaction_units_consumed() {
+    // spl-memo v3, loaded into every program test environment as a BPF program
+    let memo_program_id = Pubkey::from_str("MemoSq4gqABAXKb96qnH8TysNcWxMyWCqXgDLGmfcHr").unwrap();
+    let (mut banks_client, payer, recent_blockhash) = ProgramTest::default().start().await;
+
+    let transaction = Transaction::new_signed_with_payer(
+        &[Instruction::new_with_bytes(
+            memo_program_id,
+            b"simulate",
+            vec![],
+        )],
+        Some(&payer.pubkey()),
+        &[&payer],
+        recent_blockhash,
+    );
+    let simulation = banks_client
+        .simulate_transaction(transaction)
+        .await
+        .unwrap();
+    assert_eq!(simulation.result, Ok(()));
+    assert!(simulation.units_consumed > 0);
+}
@@ -281,3 +281,91 @@ async fn stake_rewards_from_warp() {
         (_, 0, 0)
     );
 }
+
+#[tokio::test]
+async fn stake_activation_from_warp_to_epoch() {
+    let program_test = ProgramTest::default();
+    let mut context = program_test.start_with_context().await;
+    let first_normal_epoch = context.genesis_config().epoch_schedule.first_normal_epoch;
+    context.warp_to_epoch(first_normal_epoch).unwrap();
+
+    let vote_keypair = Keypair::new();
+    let validator_keypair = Keypair::new();
+    let user_keypair = Keypair::new();
+    let stake_keypair = Keypair::new();
+    let stake_lamports = 1_000_000_000_000;
+    let mut instructions = vote_instruction::create_account(
+        &context.payer.pubkey(),
+        &vote_keypair.pubkey(),
+        &VoteInit {
+            node_pubkey: validator_keypair.pubkey(),
+            authorized_voter: user_keypair.pubkey(),
+            ..VoteInit::default()
+        },
+        Rent::default().minimum_balance(VoteState::size_of()),
+    );
+    instructions.append(&mut stake_instruction::create_account_and_delegate_stake(
+        &context.payer.pubkey(),
+        &stake_keypair.pubkey(),
+        &vote_keypair.pubkey(),
+        &Authorized::auto(&user_keypair.pubkey()),
+        &Lockup::default(),
+        stake_lamports,
+    ));
+    let transaction = Transaction::new_signed_with_payer(
+        &instructions,
+        Some(&context.payer.pubkey()),
+        &vec![
+            &context.payer,
+            &validator_keypair,
+            &vote_keypair,
+            &stake_keypair,
+            &user_keypair,
+        ],
+        context.last_blockhash,
+    );
+    context
+        .banks_client
+        .process_transaction(transaction)
+        .await
+        .unwrap();
+
+    let warp_epoch = first_normal_epoch + 3;
+    context.warp_to_epoch(warp_epoch).unwrap();
+    let clock: Clock = context.banks_client.get_sysvar().await.unwrap();
+    assert_eq!(clock.epoch, warp_epoch);
+    assert_eq!(
+        clock.slot,
+        context
+            .genesis_config()
+            .epoch_schedule
+            .get_first_slot_in_epoch(warp_epoch)
+    );
+
+    // Every epoch crossed is recorded in the stake history
+    let stake_history: StakeHistory = context.banks_client.get_sysvar().await.unwrap();
+    for epoch in first_normal_epoch..warp_epoch {
+        assert!(stake_history.get(&epoch).is_some());
+    }
+
+    let account = context
+        .banks_client
+        .get_account(stake_keypair.pubkey())
+        .await
+        .unwrap()
+        .unwrap();
+    let stake_state: StakeState = deserialize(&account.data).unwrap();
+    assert_matches!(
+        stake_state
+            .stake()
+            .unwrap()
+            .delegation
+            .stake_activating_and_deactivating(clock.epoch, Some(&stake_history), true),
+        (_, 0, 0)
+    );
+
+    assert_eq!(
+        context.warp_to_epoch(warp_epoch).unwrap_err(),
+        ProgramTestError::InvalidWarpEpoch,
+    );
+}
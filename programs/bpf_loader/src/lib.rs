@@ -16,11 +16,11 @@ use log::{log_enabled, trace, Level::Trace};
 use solana_measure::measure::Measure;
 use solana_rbpf::{
     aligned_memory::AlignedMemory,
-    ebpf::HOST_ALIGN,
+    ebpf::{self, HOST_ALIGN},
     error::{EbpfError, UserDefinedError},
     static_analysis::Analysis,
     verifier::{self, VerifierError},
-    vm::{Config, EbpfVm, Executable, InstructionMeter},
+    vm::{Config, EbpfVm, Executable, InstructionMeter, Tracer},
 };
 use solana_runtime::message_processor::MessageProcessor;
 use solana_sdk::{
@@ -36,14 +36,14 @@ use solana_sdk::{
     keyed_account::{from_keyed_account, keyed_account_at_index},
     loader_instruction::LoaderInstruction,
     loader_upgradeable_instruction::UpgradeableLoaderInstruction,
-    process_instruction::{stable_log, ComputeMeter, Executor, InvokeContext},
+    process_instruction::{stable_log, ComputeMeter, ComputeProfiler, Executor, InvokeContext},
     program_error::{ACCOUNT_NOT_RENT_EXEMPT, BORSH_IO_ERROR},
     program_utils::limited_deserialize,
     pubkey::Pubkey,
     rent::Rent,
     system_instruction::{self, MAX_PERMITTED_DATA_LENGTH},
 };
-use std::{cell::RefCell, fmt::Debug, rc::Rc, sync::Arc};
+use std::{cell::RefCell, collections::HashMap, fmt::Debug, rc::Rc, sync::Arc};
 use thiserror::Error;
 
 solana_sdk::declare_builtin!(
@@ -81,7 +81,8 @@ pub fn create_executor(
     let config = Config {
         max_call_depth: compute_budget.max_call_depth,
         stack_frame_size: compute_budget.stack_frame_size,
-        enable_instruction_tracing: log_enabled!(Trace),
+        enable_instruction_tracing: log_enabled!(Trace)
+            || invoke_context.get_compute_profiler().is_some(),
         verify_mul64_imm_nonzero: !invoke_context
             .is_feature_active(&stop_verify_mul64_imm_nonzero::id()), // TODO: Feature gate and then remove me
         ..Config::default()
@@ -725,6 +726,66 @@ impl InstructionMeter for ThisInstructionMeter {
     }
 }
 
+/// Attribute the instructions in the VM's trace to the function call stacks they executed in
+///
+/// Functions are named by the program's symbols, so stripped programs only report the
+/// entrypoint and anonymous functions.  The trace is empty unless the executable was created
+/// with instruction tracing enabled and run by the interpreter.
+fn record_call_stacks(
+    executable: &dyn Executable<BpfError, ThisInstructionMeter>,
+    tracer: &Tracer,
+    compute_profiler: &mut dyn ComputeProfiler,
+) {
+    let entry_pc = match tracer.log.first() {
+        Some(entry) => entry[11] as usize,
+        None => return,
+    };
+    let text_bytes = executable.get_text_bytes().1;
+    let (_syscalls, functions) = executable.get_symbols();
+    let function_name = |pc: usize| -> String {
+        functions
+            .range(..=pc)
+            .next_back()
+            .map(|(_, (_, name))| name.clone())
+            .unwrap_or_else(|| format!("function_{}", pc))
+    };
+
+    let mut call_stacks = HashMap::<Vec<String>, u64>::new();
+    let mut call_stack = vec![function_name(entry_pc)];
+    let mut instructions = 0;
+    for (index, entry) in tracer.log.iter().enumerate() {
+        instructions += 1;
+        let pc = entry[11] as usize;
+        let opc = text_bytes
+            .get(pc * ebpf::INSN_SIZE)
+            .copied()
+            .unwrap_or_default();
+        let next_pc = tracer.log.get(index + 1).map(|entry| entry[11] as usize);
+        let callee = match (opc, next_pc) {
+            (ebpf::CALL_IMM, Some(next_pc)) | (ebpf::CALL_REG, Some(next_pc)) => functions
+                .get(&next_pc)
+                .map(|(_, name)| name.clone())
+                .or_else(|| (next_pc != pc + 1).then(|| format!("function_{}", next_pc))),
+            _ => None,
+        };
+        if let Some(callee) = callee {
+            *call_stacks.entry(call_stack.clone()).or_default() += instructions;
+            instructions = 0;
+            call_stack.push(callee);
+        } else if opc == ebpf::EXIT && call_stack.len() > 1 {
+            *call_stacks.entry(call_stack.clone()).or_default() += instructions;
+            instructions = 0;
+            call_stack.pop();
+        }
+    }
+    *call_stacks.entry(call_stack).or_default() += instructions;
+
+    for (call_stack, instructions) in call_stacks {
+        let call_stack = call_stack.iter().map(String::as_str).collect::<Vec<_>>();
+        compute_profiler.record_call_stack(&call_stack, instructions);
+    }
+}
+
 /// BPF Loader's Executor implementation
 pub struct BpfExecutor {
     executable: Box<dyn Executable<BpfError, ThisInstructionMeter>>,
@@ -747,6 +808,7 @@ impl Executor for BpfExecutor {
         use_jit: bool,
     ) -> Result<(), InstructionError> {
         let logger = invoke_context.get_logger();
+        let compute_profiler = invoke_context.get_compute_profiler();
         let invoke_depth = invoke_context.invoke_depth();
         let add_missing_program_error_mappings =
             invoke_context.is_feature_active(&add_missing_program_error_mappings::id());
@@ -780,6 +842,9 @@ impl Executor for BpfExecutor {
             stable_log::program_invoke(&logger, program_id, invoke_depth);
             let mut instruction_meter = ThisInstructionMeter::new(compute_meter.clone());
             let before = compute_meter.borrow().get_remaining();
+            if let Some(compute_profiler) = &compute_profiler {
+                compute_profiler.borrow_mut().enter(program_id, before);
+            }
             let result = if use_jit {
                 vm.execute_program_jit(&mut instruction_meter)
             } else {
@@ -800,6 +865,15 @@ impl Executor for BpfExecutor {
                 let trace_string = String::from_utf8(trace_buffer).unwrap();
                 trace!("BPF Program Instruction Trace:\n{}", trace_string);
             }
+            if let Some(compute_profiler) = &compute_profiler {
+                let mut compute_profiler = compute_profiler.borrow_mut();
+                record_call_stacks(
+                    self.executable.as_ref(),
+                    vm.get_tracer(),
+                    &mut *compute_profiler,
+                );
+                compute_profiler.exit(after);
+            }
             match result {
                 Ok(status) => {
                     if status != SUCCESS {
@@ -14,7 +14,7 @@ use {
     serde::{Deserialize, Serialize},
     solana_account_decoder::{
         parse_token::{spl_token_id_v2_0, token_amount_to_ui_amount, UiTokenAmount},
-        UiAccount, UiAccountEncoding, UiDataSliceConfig,
+        UiAccount, UiAccountData, UiAccountEncoding, UiDataSliceConfig,
     },
     solana_client::{
         rpc_cache::LargestAccountsCache,
@@ -23,11 +23,11 @@ use {
         rpc_deprecated_config::*,
         rpc_filter::{Memcmp, MemcmpEncodedBytes, RpcFilterType},
         rpc_request::{
-            TokenAccountsFilter, DELINQUENT_VALIDATOR_SLOT_DISTANCE,
+            TokenAccountsFilter, DELINQUENT_VALIDATOR_SLOT_DISTANCE, MAX_GET_ACCOUNT_HISTORY_LIMIT,
             MAX_GET_CONFIRMED_BLOCKS_RANGE, MAX_GET_CONFIRMED_SIGNATURES_FOR_ADDRESS2_LIMIT,
-            MAX_GET_CONFIRMED_SIGNATURES_FOR_ADDRESS_SLOT_RANGE, MAX_GET_PROGRAM_ACCOUNT_FILTERS,
-            MAX_GET_SIGNATURE_STATUSES_QUERY_ITEMS, MAX_GET_SLOT_LEADERS, MAX_MULTIPLE_ACCOUNTS,
-            NUM_LARGEST_ACCOUNTS,
+            MAX_GET_CONFIRMED_SIGNATURES_FOR_ADDRESS_SLOT_RANGE, MAX_GET_DUPLICATE_SLOT_PROOFS,
+            MAX_GET_PROGRAM_ACCOUNT_FILTERS, MAX_GET_SIGNATURE_STATUSES_QUERY_ITEMS,
+            MAX_GET_SLOT_LEADERS, MAX_MULTIPLE_ACCOUNTS, NUM_LARGEST_ACCOUNTS,
         },
         rpc_response::Response as RpcResponse,
         rpc_response::*,
@@ -35,7 +35,8 @@ use {
     solana_faucet::faucet::request_airdrop_transaction,
     solana_gossip::{cluster_info::ClusterInfo, contact_info::ContactInfo},
     solana_ledger::{
-        blockstore::Blockstore, blockstore_db::BlockstoreError, get_tmp_ledger_path,
+        blockstore::Blockstore, blockstore_db::BlockstoreError,
+        duplicate_slot_evidence::DuplicateSlotEvidence, get_tmp_ledger_path,
         leader_schedule_cache::LeaderScheduleCache,
     },
     solana_metrics::inc_new_counter_info,
@@ -69,10 +70,12 @@ use {
         sysvar::stake_history,
         transaction::{self, SanitizedTransaction, TransactionError, VersionedTransaction},
     },
+    solana_storage_bigtable::LedgerStorage,
     solana_streamer::socket::SocketAddrSpace,
     solana_transaction_status::{
-        ConfirmedBlock, EncodedConfirmedTransaction, Reward, RewardType,
-        TransactionConfirmationStatus, TransactionStatus, UiConfirmedBlock, UiTransactionEncoding,
+        ConfirmedBlock, ConfirmedTransaction, EncodedConfirmedTransaction, Reward, RewardType,
+        TransactionConfirmationStatus, TransactionStatus, TransactionTokenBalance,
+        UiConfirmedBlock, UiTransactionEncoding,
     },
     solana_vote_program::vote_state::{VoteState, MAX_LOCKOUT_HISTORY},
     spl_token_v2_0::{
@@ -85,6 +88,7 @@ use {
         collections::{HashMap, HashSet},
         convert::TryFrom,
         net::SocketAddr,
+        path::PathBuf,
         str::FromStr,
         sync::{
             atomic::{AtomicBool, AtomicU64, Ordering},
@@ -141,9 +145,12 @@ pub struct JsonRpcConfig {
     pub account_indexes: AccountSecondaryIndexes,
     pub rpc_threads: usize,
     pub rpc_bigtable_timeout: Option<Duration>,
+    /// Keep long-term ledger storage in a local database at this path instead of BigTable
+    pub local_ledger_storage_path: Option<PathBuf>,
     pub minimal_api: bool,
     pub obsolete_v1_7_api: bool,
     pub rpc_scan_and_fix_roots: bool,
+    pub enable_rpc_account_proofs: bool,
 }
 
 #[derive(Clone)]
@@ -158,7 +165,7 @@ pub struct JsonRpcRequestProcessor {
     cluster_info: Arc<ClusterInfo>,
     genesis_hash: Hash,
     transaction_sender: Arc<Mutex<Sender<TransactionInfo>>>,
-    bigtable_ledger_storage: Option<solana_storage_bigtable::LedgerStorage>,
+    bigtable_ledger_storage: Option<Arc<dyn LedgerStorage>>,
     optimistically_confirmed_bank: Arc<RwLock<OptimisticallyConfirmedBank>>,
     largest_accounts_cache: Arc<RwLock<LargestAccountsCache>>,
     max_slots: Arc<MaxSlots>,
@@ -247,7 +254,7 @@ impl JsonRpcRequestProcessor {
         health: Arc<RpcHealth>,
         cluster_info: Arc<ClusterInfo>,
         genesis_hash: Hash,
-        bigtable_ledger_storage: Option<solana_storage_bigtable::LedgerStorage>,
+        bigtable_ledger_storage: Option<Arc<dyn LedgerStorage>>,
         optimistically_confirmed_bank: Arc<RwLock<OptimisticallyConfirmedBank>>,
         largest_accounts_cache: Arc<RwLock<LargestAccountsCache>>,
         max_slots: Arc<MaxSlots>,
@@ -532,6 +539,29 @@ impl JsonRpcRequestProcessor {
         new_response(&bank, bank.get_balance(pubkey))
     }
 
+    /// Prove `pubkey` against the accounts hash of the last snapshot, whose slot is the
+    /// response context slot
+    pub fn get_account_proof(
+        &self,
+        pubkey: &Pubkey,
+    ) -> Result<RpcResponse<Option<RpcAccountProof>>> {
+        if !self.config.enable_rpc_account_proofs {
+            return Err(RpcCustomError::AccountProofsNotAvailable.into());
+        }
+        let tree = self
+            .bank(Some(CommitmentConfig::finalized()))
+            .accounts_merkle_tree()
+            .ok_or(RpcCustomError::AccountProofsNotAvailable)?;
+        let accounts_hash = tree.root();
+        let proof = tree
+            .get_account_proof(pubkey)
+            .map(|proof| RpcAccountProof::new(&proof, &accounts_hash));
+        Ok(Response {
+            context: RpcResponseContext { slot: tree.slot },
+            value: proof,
+        })
+    }
+
     fn get_recent_blockhash(
         &self,
         commitment: Option<CommitmentConfig>,
@@ -635,6 +665,37 @@ impl JsonRpcRequestProcessor {
         self.max_slots.shred_insert.load(Ordering::Relaxed)
     }
 
+    fn get_duplicate_slot_proofs(
+        &self,
+        start_slot: Slot,
+        limit: usize,
+    ) -> Result<Vec<DuplicateSlotEvidence>> {
+        let bank = self.bank(None);
+        let slots = self
+            .blockstore
+            .duplicate_slots_iterator(start_slot)
+            .map_err(|err| Error::invalid_params(format!("{}", err)))?;
+        let mut proofs = vec![];
+        for slot in slots {
+            if proofs.len() >= limit {
+                break;
+            }
+            let proof = match self.blockstore.get_duplicate_slot(slot) {
+                Some(proof) => proof,
+                None => continue,
+            };
+            let leader = self.leader_schedule_cache.slot_leader_at(slot, Some(&bank));
+            match DuplicateSlotEvidence::new(slot, &proof, leader) {
+                Ok(evidence) => proofs.push(evidence),
+                Err(err) => warn!(
+                    "Unable to decode duplicate slot proof for {}: {}",
+                    slot, err
+                ),
+            }
+        }
+        Ok(proofs)
+    }
+
     fn get_slot_leader(&self, commitment: Option<CommitmentConfig>) -> String {
         self.bank(commitment).collector_id().to_string()
     }
@@ -761,6 +822,31 @@ impl JsonRpcRequestProcessor {
         }
     }
 
+    fn get_rent_paying_accounts(
+        &self,
+        owner: &Pubkey,
+        commitment: Option<CommitmentConfig>,
+    ) -> RpcCustomResult<RpcResponse<Vec<RpcRentPayingAccount>>> {
+        let bank = self.bank(commitment);
+        let accounts = bank
+            .get_rent_paying_accounts(Some(owner))
+            .map_err(|e| RpcCustomError::ScanError {
+                message: e.to_string(),
+            })?
+            .into_iter()
+            .map(|account| RpcRentPayingAccount {
+                address: account.pubkey.to_string(),
+                owner: account.owner.to_string(),
+                lamports: account.lamports,
+                space: account.data_len as u64,
+                rent_exempt_minimum: account.rent_exempt_minimum,
+                rent_per_epoch: account.rent_per_epoch,
+                epochs_until_deletion: account.epochs_until_deletion,
+            })
+            .collect();
+        Ok(new_response(&bank, accounts))
+    }
+
     fn get_supply(
         &self,
         config: Option<RpcSupplyConfig>,
@@ -1340,53 +1426,86 @@ impl JsonRpcRequestProcessor {
         check_is_at_least_confirmed(commitment)?;
 
         if self.config.enable_rpc_transaction_history {
-            let confirmed_bank = self.bank(Some(CommitmentConfig::confirmed()));
-            let transaction = if commitment.is_confirmed() {
-                let highest_confirmed_slot = confirmed_bank.slot();
-                self.blockstore
-                    .get_complete_transaction(signature, highest_confirmed_slot)
-            } else {
-                self.blockstore.get_rooted_transaction(signature)
-            };
-            match transaction.unwrap_or(None) {
-                Some(mut confirmed_transaction) => {
-                    if commitment.is_confirmed()
-                        && confirmed_bank // should be redundant
-                            .status_cache_ancestors()
-                            .contains(&confirmed_transaction.slot)
-                    {
-                        if confirmed_transaction.block_time.is_none() {
-                            let r_bank_forks = self.bank_forks.read().unwrap();
-                            confirmed_transaction.block_time = r_bank_forks
-                                .get(confirmed_transaction.slot)
-                                .map(|bank| bank.clock().unix_timestamp);
-                        }
-                        return Ok(Some(confirmed_transaction.encode(encoding)));
-                    }
-                    if confirmed_transaction.slot
-                        <= self
-                            .block_commitment_cache
-                            .read()
-                            .unwrap()
-                            .highest_confirmed_root()
-                    {
-                        return Ok(Some(confirmed_transaction.encode(encoding)));
+            Ok(self
+                .get_confirmed_transaction(signature, commitment)
+                .await
+                .map(|confirmed_transaction| confirmed_transaction.encode(encoding)))
+        } else {
+            Err(RpcCustomError::TransactionHistoryNotAvailable.into())
+        }
+    }
+
+    /// Looks up a transaction that is at least confirmed, from the blockstore or else from
+    /// bigtable
+    async fn get_confirmed_transaction(
+        &self,
+        signature: Signature,
+        commitment: CommitmentConfig,
+    ) -> Option<ConfirmedTransaction> {
+        let confirmed_bank = self.bank(Some(CommitmentConfig::confirmed()));
+        let transaction = if commitment.is_confirmed() {
+            let highest_confirmed_slot = confirmed_bank.slot();
+            self.blockstore
+                .get_complete_transaction(signature, highest_confirmed_slot)
+        } else {
+            self.blockstore.get_rooted_transaction(signature)
+        };
+        match transaction.unwrap_or(None) {
+            Some(mut confirmed_transaction) => {
+                if commitment.is_confirmed()
+                    && confirmed_bank // should be redundant
+                        .status_cache_ancestors()
+                        .contains(&confirmed_transaction.slot)
+                {
+                    if confirmed_transaction.block_time.is_none() {
+                        let r_bank_forks = self.bank_forks.read().unwrap();
+                        confirmed_transaction.block_time = r_bank_forks
+                            .get(confirmed_transaction.slot)
+                            .map(|bank| bank.clock().unix_timestamp);
                     }
+                    return Some(confirmed_transaction);
                 }
-                None => {
-                    if let Some(bigtable_ledger_storage) = &self.bigtable_ledger_storage {
-                        return Ok(bigtable_ledger_storage
-                            .get_confirmed_transaction(&signature)
-                            .await
-                            .unwrap_or(None)
-                            .map(|confirmed| confirmed.encode(encoding)));
-                    }
+                if confirmed_transaction.slot
+                    <= self
+                        .block_commitment_cache
+                        .read()
+                        .unwrap()
+                        .highest_confirmed_root()
+                {
+                    return Some(confirmed_transaction);
                 }
             }
-        } else {
+            None => {
+                if let Some(bigtable_ledger_storage) = &self.bigtable_ledger_storage {
+                    return bigtable_ledger_storage
+                        .get_confirmed_transaction(&signature)
+                        .await
+                        .unwrap_or(None);
+                }
+            }
+        }
+        None
+    }
+
+    pub fn get_transaction_proof(
+        &self,
+        signature: Signature,
+    ) -> Result<Option<RpcTransactionProof>> {
+        if !self.config.enable_rpc_transaction_history {
             return Err(RpcCustomError::TransactionHistoryNotAvailable.into());
         }
-        Ok(None)
+        let proof = self
+            .blockstore
+            .get_rooted_transaction_proof(signature)
+            .map_err(|_| Error::internal_error())?;
+        let highest_confirmed_root = self
+            .block_commitment_cache
+            .read()
+            .unwrap()
+            .highest_confirmed_root();
+        Ok(proof
+            .filter(|proof| proof.slot <= highest_confirmed_root)
+            .map(|proof| RpcTransactionProof::from(&proof)))
     }
 
     pub fn get_confirmed_signatures_for_address(
@@ -1491,6 +1610,93 @@ impl JsonRpcRequestProcessor {
         }
     }
 
+    pub async fn get_account_history(
+        &self,
+        address: Pubkey,
+        before: Option<Signature>,
+        until: Option<Signature>,
+        limit: usize,
+        include_data: bool,
+        commitment: Option<CommitmentConfig>,
+    ) -> Result<Vec<RpcAccountHistoryEntry>> {
+        let statuses = self
+            .get_signatures_for_address(address, before, until, limit, commitment)
+            .await?;
+        let commitment = commitment.unwrap_or_default();
+
+        let mut history = Vec::with_capacity(statuses.len());
+        // Only nodes that record account writes know the hash and data
+        let mut slot_account_writes = HashMap::new();
+        for status in statuses {
+            let signature = verify_signature(&status.signature)?;
+            let mut entry = RpcAccountHistoryEntry {
+                signature: status.signature,
+                slot: status.slot,
+                err: status.err,
+                block_time: status.block_time,
+                pre_lamports: None,
+                post_lamports: None,
+                token_mint: None,
+                pre_token_amount: None,
+                post_token_amount: None,
+                hash: None,
+                data: None,
+            };
+
+            let transaction = self.get_confirmed_transaction(signature, commitment).await;
+            let account_index = transaction.as_ref().and_then(|transaction| {
+                transaction
+                    .transaction
+                    .transaction
+                    .message
+                    .account_keys
+                    .iter()
+                    .position(|key| *key == address)
+            });
+            if let (Some(meta), Some(account_index)) = (
+                transaction.and_then(|transaction| transaction.transaction.meta),
+                account_index,
+            ) {
+                entry.pre_lamports = meta.pre_balances.get(account_index).copied();
+                entry.post_lamports = meta.post_balances.get(account_index).copied();
+                let token_balance = |token_balances: Option<Vec<TransactionTokenBalance>>| {
+                    token_balances
+                        .unwrap_or_default()
+                        .into_iter()
+                        .find(|token_balance| token_balance.account_index as usize == account_index)
+                };
+                let pre_token_balance = token_balance(meta.pre_token_balances);
+                let post_token_balance = token_balance(meta.post_token_balances);
+                entry.token_mint = pre_token_balance
+                    .as_ref()
+                    .or(post_token_balance.as_ref())
+                    .map(|token_balance| token_balance.mint.clone());
+                entry.pre_token_amount =
+                    pre_token_balance.map(|token_balance| token_balance.ui_token_amount);
+                entry.post_token_amount =
+                    post_token_balance.map(|token_balance| token_balance.ui_token_amount);
+            }
+
+            let account_write = slot_account_writes
+                .entry(entry.slot)
+                .or_insert_with(|| {
+                    self.blockstore
+                        .get_account_writes_for_address(entry.slot, &address, include_data)
+                        .unwrap_or_default()
+                })
+                .remove(&signature);
+            if let Some(account_write) = account_write {
+                entry.post_lamports = entry.post_lamports.or(Some(account_write.lamports));
+                entry.hash = Some(account_write.hash.to_string());
+                entry.data = account_write.data.map(|data| {
+                    UiAccountData::Binary(base64::encode(data), UiAccountEncoding::Base64)
+                });
+            }
+            history.push(entry);
+        }
+        Ok(history)
+    }
+
     pub async fn get_first_available_block(&self) -> Slot {
         let slot = self
             .blockstore
@@ -2025,18 +2231,34 @@ fn verify_and_parse_signatures_for_address_params(
     before: Option<String>,
     until: Option<String>,
     limit: Option<usize>,
+) -> Result<(Pubkey, Option<Signature>, Option<Signature>, usize)> {
+    verify_and_parse_address_history_params(
+        address,
+        before,
+        until,
+        limit,
+        MAX_GET_CONFIRMED_SIGNATURES_FOR_ADDRESS2_LIMIT,
+    )
+}
+
+fn verify_and_parse_address_history_params(
+    address: String,
+    before: Option<String>,
+    until: Option<String>,
+    limit: Option<usize>,
+    max_limit: usize,
 ) -> Result<(Pubkey, Option<Signature>, Option<Signature>, usize)> {
     let address = verify_pubkey(&address)?;
     let before = before
         .map(|ref before| verify_signature(before))
         .transpose()?;
     let until = until.map(|ref until| verify_signature(until)).transpose()?;
-    let limit = limit.unwrap_or(MAX_GET_CONFIRMED_SIGNATURES_FOR_ADDRESS2_LIMIT);
+    let limit = limit.unwrap_or(max_limit);
 
-    if limit == 0 || limit > MAX_GET_CONFIRMED_SIGNATURES_FOR_ADDRESS2_LIMIT {
+    if limit == 0 || limit > max_limit {
         return Err(Error::invalid_params(format!(
             "Invalid limit; max {}",
-            MAX_GET_CONFIRMED_SIGNATURES_FOR_ADDRESS2_LIMIT
+            max_limit
         )));
     }
     Ok((address, before, until, limit))
@@ -2683,6 +2905,13 @@ pub mod rpc_accounts {
             config: Option<RpcAccountInfoConfig>,
         ) -> Result<RpcResponse<Vec<Option<UiAccount>>>>;
 
+        #[rpc(meta, name = "getAccountProof")]
+        fn get_account_proof(
+            &self,
+            meta: Self::Metadata,
+            pubkey_str: String,
+        ) -> Result<RpcResponse<Option<RpcAccountProof>>>;
+
         #[rpc(meta, name = "getProgramAccounts")]
         fn get_program_accounts(
             &self,
@@ -2705,6 +2934,14 @@ pub mod rpc_accounts {
             config: Option<RpcLargestAccountsConfig>,
         ) -> Result<RpcResponse<Vec<RpcAccountBalance>>>;
 
+        #[rpc(meta, name = "getRentPayingAccounts")]
+        fn get_rent_paying_accounts(
+            &self,
+            meta: Self::Metadata,
+            owner_str: String,
+            config: Option<RpcRentPayingAccountsConfig>,
+        ) -> Result<RpcResponse<Vec<RpcRentPayingAccount>>>;
+
         #[rpc(meta, name = "getSupply")]
         fn get_supply(
             &self,
@@ -2782,6 +3019,16 @@ pub mod rpc_accounts {
             meta.get_account_info(&pubkey, config)
         }
 
+        fn get_account_proof(
+            &self,
+            meta: Self::Metadata,
+            pubkey_str: String,
+        ) -> Result<RpcResponse<Option<RpcAccountProof>>> {
+            debug!("get_account_proof rpc request received: {:?}", pubkey_str);
+            let pubkey = verify_pubkey(&pubkey_str)?;
+            meta.get_account_proof(&pubkey)
+        }
+
         fn get_multiple_accounts(
             &self,
             meta: Self::Metadata,
@@ -2860,6 +3107,21 @@ pub mod rpc_accounts {
             Ok(meta.get_largest_accounts(config)?)
         }
 
+        fn get_rent_paying_accounts(
+            &self,
+            meta: Self::Metadata,
+            owner_str: String,
+            config: Option<RpcRentPayingAccountsConfig>,
+        ) -> Result<RpcResponse<Vec<RpcRentPayingAccount>>> {
+            debug!(
+                "get_rent_paying_accounts rpc request received: {:?}",
+                owner_str
+            );
+            let owner = verify_pubkey(&owner_str)?;
+            let config = config.unwrap_or_default();
+            Ok(meta.get_rent_paying_accounts(&owner, config.commitment)?)
+        }
+
         fn get_supply(
             &self,
             meta: Self::Metadata,
@@ -2999,6 +3261,14 @@ pub mod rpc_full {
         #[rpc(meta, name = "getMaxShredInsertSlot")]
         fn get_max_shred_insert_slot(&self, meta: Self::Metadata) -> Result<Slot>;
 
+        #[rpc(meta, name = "getDuplicateSlotProofs")]
+        fn get_duplicate_slot_proofs(
+            &self,
+            meta: Self::Metadata,
+            start_slot: Option<Slot>,
+            limit: Option<usize>,
+        ) -> Result<Vec<DuplicateSlotEvidence>>;
+
         #[rpc(meta, name = "requestAirdrop")]
         fn request_airdrop(
             &self,
@@ -3068,6 +3338,13 @@ pub mod rpc_full {
             config: Option<RpcEncodingConfigWrapper<RpcTransactionConfig>>,
         ) -> BoxFuture<Result<Option<EncodedConfirmedTransaction>>>;
 
+        #[rpc(meta, name = "getTransactionProof")]
+        fn get_transaction_proof(
+            &self,
+            meta: Self::Metadata,
+            signature_str: String,
+        ) -> Result<Option<RpcTransactionProof>>;
+
         #[rpc(meta, name = "getSignaturesForAddress")]
         fn get_signatures_for_address(
             &self,
@@ -3076,6 +3353,14 @@ pub mod rpc_full {
             config: Option<RpcSignaturesForAddressConfig>,
         ) -> BoxFuture<Result<Vec<RpcConfirmedTransactionStatusWithSignature>>>;
 
+        #[rpc(meta, name = "getAccountHistory")]
+        fn get_account_history(
+            &self,
+            meta: Self::Metadata,
+            address: String,
+            config: Option<RpcAccountHistoryConfig>,
+        ) -> BoxFuture<Result<Vec<RpcAccountHistoryEntry>>>;
+
         #[rpc(meta, name = "getFirstAvailableBlock")]
         fn get_first_available_block(&self, meta: Self::Metadata) -> BoxFuture<Result<Slot>>;
 
@@ -3227,6 +3512,23 @@ pub mod rpc_full {
             Ok(meta.get_max_shred_insert_slot())
         }
 
+        fn get_duplicate_slot_proofs(
+            &self,
+            meta: Self::Metadata,
+            start_slot: Option<Slot>,
+            limit: Option<usize>,
+        ) -> Result<Vec<DuplicateSlotEvidence>> {
+            debug!("get_duplicate_slot_proofs rpc request received");
+            let limit = limit.unwrap_or(MAX_GET_DUPLICATE_SLOT_PROOFS);
+            if limit > MAX_GET_DUPLICATE_SLOT_PROOFS {
+                return Err(Error::invalid_params(format!(
+                    "Invalid limit; max {}",
+                    MAX_GET_DUPLICATE_SLOT_PROOFS
+                )));
+            }
+            meta.get_duplicate_slot_proofs(start_slot.unwrap_or_default(), limit)
+        }
+
         fn request_airdrop(
             &self,
             meta: Self::Metadata,
@@ -3540,6 +3842,19 @@ pub mod rpc_full {
             Box::pin(async move { meta.get_transaction(signature.unwrap(), config).await })
         }
 
+        fn get_transaction_proof(
+            &self,
+            meta: Self::Metadata,
+            signature_str: String,
+        ) -> Result<Option<RpcTransactionProof>> {
+            debug!(
+                "get_transaction_proof rpc request received: {:?}",
+                signature_str
+            );
+            let signature = verify_signature(&signature_str)?;
+            meta.get_transaction_proof(signature)
+        }
+
         fn get_signatures_for_address(
             &self,
             meta: Self::Metadata,
@@ -3564,6 +3879,40 @@ pub mod rpc_full {
             }
         }
 
+        fn get_account_history(
+            &self,
+            meta: Self::Metadata,
+            address: String,
+            config: Option<RpcAccountHistoryConfig>,
+        ) -> BoxFuture<Result<Vec<RpcAccountHistoryEntry>>> {
+            debug!("get_account_history rpc request received: {:?}", address);
+            let config = config.unwrap_or_default();
+            let commitment = config.commitment;
+            let include_data = config.include_data.unwrap_or_default();
+            let verification = verify_and_parse_address_history_params(
+                address,
+                config.before,
+                config.until,
+                config.limit,
+                MAX_GET_ACCOUNT_HISTORY_LIMIT,
+            );
+
+            match verification {
+                Err(err) => Box::pin(future::err(err)),
+                Ok((address, before, until, limit)) => Box::pin(async move {
+                    meta.get_account_history(
+                        address,
+                        before,
+                        until,
+                        limit,
+                        include_data,
+                        commitment,
+                    )
+                    .await
+                }),
+            }
+        }
+
         fn get_first_available_block(&self, meta: Self::Metadata) -> BoxFuture<Result<Slot>> {
             debug!("get_first_available_block rpc request received");
             Box::pin(async move { Ok(meta.get_first_available_block().await) })
@@ -4181,15 +4530,24 @@ pub mod tests {
         bincode::deserialize,
         jsonrpc_core::{futures, ErrorCode, MetaIoHandler, Output, Response, Value},
         jsonrpc_core_client::transports::local,
-        solana_client::rpc_filter::{Memcmp, MemcmpEncodedBytes},
+        solana_client::{
+            rpc_custom_error::{
+                JSON_RPC_SERVER_ERROR_ACCOUNT_PROOFS_NOT_AVAILABLE,
+                JSON_RPC_SERVER_ERROR_TRANSACTION_HISTORY_NOT_AVAILABLE,
+            },
+            rpc_filter::{Memcmp, MemcmpEncodedBytes},
+        },
         solana_gossip::{contact_info::ContactInfo, socketaddr},
         solana_ledger::{
             blockstore_meta::PerfSample,
             blockstore_processor::fill_blockstore_slot_with_ticks,
             genesis_utils::{create_genesis_config, GenesisConfigInfo},
+            shred::Shredder,
         },
         solana_runtime::{
-            accounts_background_service::AbsRequestSender, commitment::BlockCommitment,
+            account_write_log::{AccountWrite, TransactionAccountWrites},
+            accounts_background_service::AbsRequestSender,
+            commitment::BlockCommitment,
             non_circulating_supply::non_circulating_accounts,
         },
         solana_sdk::{
@@ -4199,7 +4557,9 @@ pub mod tests {
             hash::{hash, Hash},
             instruction::InstructionError,
             message::Message,
-            nonce, rpc_port,
+            nonce,
+            rent::Rent,
+            rpc_port,
             signature::{Keypair, Signer},
             system_program, system_transaction,
             timing::slot_duration_from_slots_per_year,
@@ -4207,7 +4567,7 @@ pub mod tests {
         },
         solana_transaction_status::{
             EncodedConfirmedBlock, EncodedTransaction, EncodedTransactionWithStatusMeta,
-            TransactionDetails, UiMessage,
+            TransactionDetails, TransactionStatusMeta, UiMessage,
         },
         solana_vote_program::{
             vote_instruction,
@@ -4461,6 +4821,68 @@ pub mod tests {
         assert_eq!(expected, result);
     }
 
+    #[test]
+    fn test_rpc_get_account_proof() {
+        let genesis = create_genesis_config(20);
+        let mint_pubkey = genesis.mint_keypair.pubkey();
+        let bank = Arc::new(Bank::new_for_tests(&genesis.genesis_config));
+        let mut meta = JsonRpcRequestProcessor::new_from_bank(&bank, SocketAddrSpace::Unspecified);
+
+        let mut io = MetaIoHandler::default();
+        io.extend_with(rpc_accounts::AccountsDataImpl.to_delegate());
+
+        let req = format!(
+            r#"{{"jsonrpc":"2.0","id":1,"method":"getAccountProof","params":["{}"]}}"#,
+            mint_pubkey
+        );
+        let res = io.handle_request_sync(&req, meta.clone());
+        let result: Value = serde_json::from_str(&res.expect("actual response"))
+            .expect("actual response deserialization");
+        assert_eq!(
+            result["error"]["code"],
+            json!(JSON_RPC_SERVER_ERROR_ACCOUNT_PROOFS_NOT_AVAILABLE)
+        );
+
+        // Nor until an accounts hash has been computed with its tree retained
+        meta.config.enable_rpc_account_proofs = true;
+        let res = io.handle_request_sync(&req, meta.clone());
+        let result: Value = serde_json::from_str(&res.expect("actual response"))
+            .expect("actual response deserialization");
+        assert_eq!(
+            result["error"]["code"],
+            json!(JSON_RPC_SERVER_ERROR_ACCOUNT_PROOFS_NOT_AVAILABLE)
+        );
+
+        bank.set_retain_accounts_merkle_tree(true);
+        bank.update_accounts_hash();
+        let res = io.handle_request_sync(&req, meta.clone());
+        let result: Response = serde_json::from_str(&res.expect("actual response"))
+            .expect("actual response deserialization");
+        let rpc_proof: RpcAccountProof = if let Response::Single(res) = result {
+            if let Output::Success(res) = res {
+                assert_eq!(res.result["context"]["slot"], json!(bank.slot()));
+                serde_json::from_value(res.result["value"].clone()).unwrap()
+            } else {
+                panic!("Expected success, got {:?}", res);
+            }
+        } else {
+            panic!("Expected single response");
+        };
+        let (proof, accounts_hash) = rpc_proof.parse().unwrap();
+        assert_eq!(accounts_hash, bank.get_accounts_hash());
+        assert_eq!(proof.pubkey, mint_pubkey);
+        assert!(proof.verify_account(&bank.get_account(&mint_pubkey).unwrap(), &accounts_hash));
+
+        let req = format!(
+            r#"{{"jsonrpc":"2.0","id":1,"method":"getAccountProof","params":["{}"]}}"#,
+            solana_sdk::pubkey::new_rand()
+        );
+        let res = io.handle_request_sync(&req, meta);
+        let result: Value = serde_json::from_str(&res.expect("actual response"))
+            .expect("actual response deserialization");
+        assert_eq!(result["result"]["value"], Value::Null);
+    }
+
     #[test]
     fn test_rpc_get_balance_via_client() {
         let genesis = create_genesis_config(20);
@@ -4677,6 +5099,56 @@ pub mod tests {
         assert!(supply.non_circulating_accounts.is_empty());
     }
 
+    #[test]
+    fn test_rpc_get_rent_paying_accounts() {
+        let mut genesis = create_genesis_config(1_000_000);
+        genesis.genesis_config.rent = Rent::default();
+        let bank = Arc::new(Bank::new_for_tests(&genesis.genesis_config));
+        let owner = solana_sdk::pubkey::new_rand();
+        let rent_paying_pubkey = solana_sdk::pubkey::new_rand();
+        bank.store_account(
+            &rent_paying_pubkey,
+            &AccountSharedData::new(5_000, 10, &owner),
+        );
+        let meta = JsonRpcRequestProcessor::new_from_bank(&bank, SocketAddrSpace::Unspecified);
+
+        let mut io = MetaIoHandler::default();
+        io.extend_with(rpc_accounts::AccountsDataImpl.to_delegate());
+
+        let req = format!(
+            r#"{{"jsonrpc":"2.0","id":1,"method":"getRentPayingAccounts","params":["{}"]}}"#,
+            owner
+        );
+        let res = io.handle_request_sync(&req, meta.clone());
+        let json: Value = serde_json::from_str(&res.unwrap()).unwrap();
+        let accounts: Vec<RpcRentPayingAccount> =
+            serde_json::from_value(json["result"]["value"].clone())
+                .expect("actual response deserialization");
+        assert_eq!(
+            accounts,
+            vec![RpcRentPayingAccount {
+                address: rent_paying_pubkey.to_string(),
+                owner: owner.to_string(),
+                lamports: 5_000,
+                space: 10,
+                rent_exempt_minimum: Rent::default().minimum_balance(10),
+                rent_per_epoch: accounts[0].rent_per_epoch,
+                epochs_until_deletion: accounts[0].epochs_until_deletion,
+            }]
+        );
+
+        // Scanning the accounts of every owner is not offered
+        let req = r#"{"jsonrpc":"2.0","id":1,"method":"getRentPayingAccounts"}"#;
+        let res = io.handle_request_sync(req, meta.clone());
+        let json: Value = serde_json::from_str(&res.unwrap()).unwrap();
+        assert_eq!(json["error"]["code"], json!(-32602));
+
+        let req = r#"{"jsonrpc":"2.0","id":1,"method":"getRentPayingAccounts","params":["bad"]}"#;
+        let res = io.handle_request_sync(req, meta);
+        let json: Value = serde_json::from_str(&res.unwrap()).unwrap();
+        assert_eq!(json["error"]["code"], json!(-32602));
+    }
+
     #[test]
     fn test_get_largest_accounts() {
         let bob_pubkey = solana_sdk::pubkey::new_rand();
@@ -6190,6 +6662,66 @@ pub mod tests {
         test_basic_slot("getMaxShredInsertSlot", 43);
     }
 
+    #[test]
+    fn test_rpc_get_duplicate_slot_proofs() {
+        let bob_pubkey = solana_sdk::pubkey::new_rand();
+        let RpcHandler {
+            io,
+            meta,
+            leader_pubkey,
+            ..
+        } = start_rpc_handler_with_tx(&bob_pubkey);
+
+        let slot = 7;
+        let new_shred_payload = |keypair: &Keypair| {
+            let entries = vec![solana_entry::entry::next_entry(
+                &Hash::new_unique(),
+                1,
+                vec![],
+            )];
+            let shredder = Shredder::new(slot, slot - 1, 0, 0).unwrap();
+            let (data_shreds, _, _) = shredder.entries_to_shreds(keypair, &entries, true, 0);
+            data_shreds[0].payload.clone()
+        };
+        // The leader keypair is not available here, so the shreds do not verify
+        let keypair = Keypair::new();
+        meta.blockstore
+            .store_duplicate_slot(
+                slot,
+                new_shred_payload(&keypair),
+                new_shred_payload(&keypair),
+            )
+            .unwrap();
+
+        let req = r#"{"jsonrpc":"2.0","id":1,"method":"getDuplicateSlotProofs"}"#;
+        let res = io.handle_request_sync(req, meta.clone());
+        let json: Value = serde_json::from_str(&res.unwrap()).unwrap();
+        let proofs: Vec<DuplicateSlotEvidence> =
+            serde_json::from_value(json["result"].clone()).unwrap();
+        assert_eq!(proofs.len(), 1);
+        assert_eq!(proofs[0].slot, slot);
+        assert_eq!(proofs[0].leader, Some(leader_pubkey.to_string()));
+        assert_eq!(proofs[0].shred1.signature_verified, Some(false));
+        assert_eq!(proofs[0].shred2.signature_verified, Some(false));
+        assert!(proofs[0].verify(&leader_pubkey).is_err());
+
+        let req = format!(
+            r#"{{"jsonrpc":"2.0","id":1,"method":"getDuplicateSlotProofs","params":[{}]}}"#,
+            slot + 1
+        );
+        let res = io.handle_request_sync(&req, meta.clone());
+        let json: Value = serde_json::from_str(&res.unwrap()).unwrap();
+        assert_eq!(json["result"], json!([]));
+
+        let req = format!(
+            r#"{{"jsonrpc":"2.0","id":1,"method":"getDuplicateSlotProofs","params":[0, {}]}}"#,
+            MAX_GET_DUPLICATE_SLOT_PROOFS + 1
+        );
+        let res = io.handle_request_sync(&req, meta);
+        let json: Value = serde_json::from_str(&res.unwrap()).unwrap();
+        assert_eq!(json["error"]["code"], json!(-32602));
+    }
+
     #[test]
     fn test_rpc_get_version() {
         let bob_pubkey = solana_sdk::pubkey::new_rand();
@@ -6337,6 +6869,271 @@ pub mod tests {
         assert_eq!(commitment_response.total_stake, 10);
     }
 
+    #[test]
+    fn test_get_transaction_proof() {
+        let GenesisConfigInfo {
+            genesis_config,
+            mint_keypair,
+            ..
+        } = create_genesis_config(100);
+        let bank = Arc::new(Bank::new_for_tests(&genesis_config));
+        let mut meta = JsonRpcRequestProcessor::new_from_bank(&bank, SocketAddrSpace::Unspecified);
+        let mut io = MetaIoHandler::default();
+        io.extend_with(rpc_full::FullImpl.to_delegate());
+
+        let slot = 2;
+        let mut poh_hash =
+            fill_blockstore_slot_with_ticks(&meta.blockstore, 4, slot - 1, 0, Hash::default());
+        let mut entries = vec![];
+        for _ in 0..3 {
+            let transactions = (0..2)
+                .map(|_| {
+                    system_transaction::transfer(&mint_keypair, &Pubkey::new_unique(), 1, poh_hash)
+                })
+                .collect();
+            entries.push(solana_entry::entry::next_entry_mut(
+                &mut poh_hash,
+                2,
+                transactions,
+            ));
+            entries.push(solana_entry::entry::next_entry_mut(
+                &mut poh_hash,
+                4,
+                vec![],
+            ));
+        }
+        let blockhash = poh_hash;
+        let shreds = solana_ledger::blockstore::entries_to_test_shreds(
+            entries.clone(),
+            slot,
+            slot - 1,
+            true,
+            0,
+        );
+        meta.blockstore.insert_shreds(shreds, None, false).unwrap();
+        meta.blockstore.set_roots([slot - 1, slot].iter()).unwrap();
+        let signatures: Vec<_> = entries
+            .iter()
+            .flat_map(|entry| entry.transactions.iter())
+            .map(|transaction| transaction.signatures[0])
+            .collect();
+        for signature in &signatures {
+            meta.blockstore
+                .write_transaction_status(
+                    slot,
+                    *signature,
+                    vec![],
+                    vec![],
+                    TransactionStatusMeta::default(),
+                )
+                .unwrap();
+        }
+
+        let request = |signature: &Signature| {
+            format!(
+                r#"{{"jsonrpc":"2.0","id":1,"method":"getTransactionProof","params":["{}"]}}"#,
+                signature
+            )
+        };
+        let res = io.handle_request_sync(&request(&signatures[0]), meta.clone());
+        let result: Value = serde_json::from_str(&res.expect("actual response"))
+            .expect("actual response deserialization");
+        assert_eq!(
+            result["error"]["code"],
+            json!(JSON_RPC_SERVER_ERROR_TRANSACTION_HISTORY_NOT_AVAILABLE)
+        );
+
+        meta.config.enable_rpc_transaction_history = true;
+        // Not finalized yet
+        let res = io.handle_request_sync(&request(&signatures[0]), meta.clone());
+        let result: Value = serde_json::from_str(&res.expect("actual response"))
+            .expect("actual response deserialization");
+        assert_eq!(result["result"], Value::Null);
+
+        meta.block_commitment_cache = Arc::new(RwLock::new(BlockCommitmentCache::new(
+            HashMap::new(),
+            0,
+            CommitmentSlots {
+                slot,
+                root: slot,
+                highest_confirmed_slot: slot,
+                highest_confirmed_root: slot,
+            },
+        )));
+        for signature in &signatures {
+            let res = io.handle_request_sync(&request(signature), meta.clone());
+            let result: Value = serde_json::from_str(&res.expect("actual response"))
+                .expect("actual response deserialization");
+            let rpc_proof: RpcTransactionProof =
+                serde_json::from_value(result["result"].clone()).unwrap();
+            let proof = rpc_proof.parse().unwrap();
+            assert_eq!(proof.signature, *signature);
+            assert_eq!(proof.slot, slot);
+            assert!(proof.verify(&blockhash));
+        }
+
+        let res = io.handle_request_sync(&request(&Signature::default()), meta);
+        let result: Value = serde_json::from_str(&res.expect("actual response"))
+            .expect("actual response deserialization");
+        assert_eq!(result["result"], Value::Null);
+    }
+
+    #[test]
+    fn test_get_account_history() {
+        let GenesisConfigInfo {
+            genesis_config,
+            mint_keypair,
+            ..
+        } = create_genesis_config(100);
+        let bank = Arc::new(Bank::new_for_tests(&genesis_config));
+        let mut meta = JsonRpcRequestProcessor::new_from_bank(&bank, SocketAddrSpace::Unspecified);
+        meta.config.enable_rpc_transaction_history = true;
+        let mut io = MetaIoHandler::default();
+        io.extend_with(rpc_full::FullImpl.to_delegate());
+
+        let slot = 2;
+        let bob_pubkey = Pubkey::new_unique();
+        let mut poh_hash =
+            fill_blockstore_slot_with_ticks(&meta.blockstore, 4, slot - 1, 0, Hash::default());
+        let transactions: Vec<_> = (1..=2)
+            .map(|lamports| {
+                system_transaction::transfer(&mint_keypair, &bob_pubkey, lamports, poh_hash)
+            })
+            .collect();
+        let signatures: Vec<_> = transactions
+            .iter()
+            .map(|transaction| transaction.signatures[0])
+            .collect();
+        let entries = vec![solana_entry::entry::next_entry_mut(
+            &mut poh_hash,
+            2,
+            transactions,
+        )];
+        let shreds =
+            solana_ledger::blockstore::entries_to_test_shreds(entries, slot, slot - 1, true, 0);
+        meta.blockstore.insert_shreds(shreds, None, false).unwrap();
+        meta.blockstore.set_roots([slot - 1, slot].iter()).unwrap();
+        meta.block_commitment_cache = Arc::new(RwLock::new(BlockCommitmentCache::new(
+            HashMap::new(),
+            0,
+            CommitmentSlots {
+                slot,
+                root: slot,
+                highest_confirmed_slot: slot,
+                highest_confirmed_root: slot,
+            },
+        )));
+
+        let mint = Pubkey::new_unique().to_string();
+        let token_balance = |amount: u64| TransactionTokenBalance {
+            account_index: 1,
+            mint: mint.clone(),
+            ui_token_amount: token_amount_to_ui_amount(amount, 2),
+        };
+        let statuses = [
+            TransactionStatusMeta {
+                pre_balances: vec![100, 0, 1],
+                post_balances: vec![99, 1, 1],
+                ..TransactionStatusMeta::default()
+            },
+            TransactionStatusMeta {
+                pre_balances: vec![99, 1, 1],
+                post_balances: vec![97, 3, 1],
+                pre_token_balances: Some(vec![token_balance(5)]),
+                post_token_balances: Some(vec![token_balance(7)]),
+                ..TransactionStatusMeta::default()
+            },
+        ];
+        for (signature, status) in signatures.iter().zip(statuses) {
+            meta.blockstore
+                .write_transaction_status(
+                    slot,
+                    *signature,
+                    vec![&mint_keypair.pubkey(), &bob_pubkey],
+                    vec![],
+                    status,
+                )
+                .unwrap();
+        }
+        let hash = Hash::new_unique();
+        meta.blockstore
+            .write_account_writes(
+                slot,
+                &[TransactionAccountWrites {
+                    ordinal: 1,
+                    signature: signatures[1],
+                    accounts: vec![AccountWrite {
+                        pubkey: bob_pubkey,
+                        lamports: 3,
+                        hash,
+                        data: Some(vec![1, 2, 3]),
+                    }],
+                }],
+            )
+            .unwrap();
+
+        let request = |config: Value| {
+            format!(
+                r#"{{"jsonrpc":"2.0","id":1,"method":"getAccountHistory","params":["{}",{}]}}"#,
+                bob_pubkey, config
+            )
+        };
+        let get_history = |config: Value| {
+            let res = io.handle_request_sync(&request(config), meta.clone());
+            let result: Value = serde_json::from_str(&res.expect("actual response"))
+                .expect("actual response deserialization");
+            let history: Vec<RpcAccountHistoryEntry> =
+                serde_json::from_value(result["result"].clone()).unwrap();
+            history
+                .into_iter()
+                .map(|entry| (entry.signature.clone(), entry))
+                .collect::<HashMap<_, _>>()
+        };
+
+        let history = get_history(json!({}));
+        assert_eq!(history.len(), 2);
+        let entry = &history[&signatures[0].to_string()];
+        assert_eq!(entry.slot, slot);
+        assert_eq!(entry.pre_lamports, Some(0));
+        assert_eq!(entry.post_lamports, Some(1));
+        assert_eq!(entry.token_mint, None);
+        assert_eq!(entry.hash, None);
+        let entry = &history[&signatures[1].to_string()];
+        assert_eq!(entry.pre_lamports, Some(1));
+        assert_eq!(entry.post_lamports, Some(3));
+        assert_eq!(entry.token_mint, Some(mint.clone()));
+        assert_eq!(
+            entry.pre_token_amount,
+            Some(token_amount_to_ui_amount(5, 2))
+        );
+        assert_eq!(
+            entry.post_token_amount,
+            Some(token_amount_to_ui_amount(7, 2))
+        );
+        assert_eq!(entry.hash, Some(hash.to_string()));
+        assert_eq!(entry.data, None);
+
+        let history = get_history(json!({"includeData": true}));
+        assert_eq!(
+            history[&signatures[1].to_string()].data,
+            Some(UiAccountData::Binary(
+                base64::encode(&[1, 2, 3]),
+                UiAccountEncoding::Base64
+            ))
+        );
+
+        let history = get_history(json!({"limit": 1}));
+        assert_eq!(history.len(), 1);
+
+        let res = io.handle_request_sync(
+            &request(json!({ "limit": MAX_GET_ACCOUNT_HISTORY_LIMIT + 1 })),
+            meta,
+        );
+        let result: Value = serde_json::from_str(&res.expect("actual response"))
+            .expect("actual response deserialization");
+        assert_eq!(result["error"]["code"], json!(-32602));
+    }
+
     #[test]
     fn test_get_block() {
         let bob_pubkey = solana_sdk::pubkey::new_rand();
@@ -34,6 +34,7 @@ use {
         exit::Exit, genesis_config::DEFAULT_GENESIS_DOWNLOAD_PATH, hash::Hash,
         native_token::lamports_to_sol, pubkey::Pubkey,
     },
+    solana_storage_bigtable::{BigTableLedgerStorage, LedgerStorage, LocalLedgerStorage},
     std::{
         collections::HashSet,
         net::SocketAddr,
@@ -326,11 +327,19 @@ impl JsonRpcService {
 
         let (bigtable_ledger_storage, _bigtable_ledger_upload_service) =
             if config.enable_bigtable_ledger_storage || config.enable_bigtable_ledger_upload {
-                runtime
-                    .block_on(solana_storage_bigtable::LedgerStorage::new(
-                        !config.enable_bigtable_ledger_upload,
-                        config.rpc_bigtable_timeout,
-                    ))
+                let read_only = !config.enable_bigtable_ledger_upload;
+                let ledger_storage: solana_storage_bigtable::Result<Arc<dyn LedgerStorage>> =
+                    match &config.local_ledger_storage_path {
+                        Some(path) => LocalLedgerStorage::open(path, read_only)
+                            .map(|storage| Arc::new(storage) as Arc<dyn LedgerStorage>),
+                        None => runtime
+                            .block_on(BigTableLedgerStorage::new(
+                                read_only,
+                                config.rpc_bigtable_timeout,
+                            ))
+                            .map(|storage| Arc::new(storage) as Arc<dyn LedgerStorage>),
+                    };
+                ledger_storage
                     .map(|bigtable_ledger_storage| {
                         info!("BigTable ledger storage initialized");
 
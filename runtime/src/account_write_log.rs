@@ -0,0 +1,148 @@
+//! Opt-in log of the accounts written by each committed transaction, so that the history of an
+//! account can be looked up without replaying the ledger
+use {
+    crate::accounts_db::AccountsDb,
+    solana_sdk::{
+        account::{AccountSharedData, ReadableAccount},
+        clock::Slot,
+        hash::Hash,
+        pubkey::Pubkey,
+        signature::Signature,
+    },
+    std::{fmt, sync::Arc},
+};
+
+/// The state of an account after a transaction wrote it
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AccountWrite {
+    pub pubkey: Pubkey,
+    pub lamports: u64,
+    /// Hash of the account as stored in the slot
+    pub hash: Hash,
+    /// Only recorded if the recorder asks for it
+    pub data: Option<Vec<u8>>,
+}
+
+/// The accounts written by a committed transaction
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TransactionAccountWrites {
+    /// Orders the transactions of a slot. Transactions writing the same account were
+    /// committed in this order.
+    pub ordinal: u64,
+    pub signature: Signature,
+    pub accounts: Vec<AccountWrite>,
+}
+
+/// Receives the account writes of the transactions committed by a bank
+pub trait AccountWriteRecorder: fmt::Debug + Send + Sync {
+    /// Whether account data is recorded along with the hash
+    fn include_data(&self) -> bool;
+
+    /// Called when a bank for `slot` is created. Writes recorded for `slot` by an earlier bank,
+    /// e.g. when the slot is replayed again, are stale and must be discarded.
+    fn begin_slot(&self, slot: Slot);
+
+    fn record(&self, slot: Slot, writes: Vec<TransactionAccountWrites>);
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct OptionalAccountWriteRecorder(pub Option<Arc<dyn AccountWriteRecorder>>);
+
+#[cfg(RUSTC_WITH_SPECIALIZATION)]
+impl solana_frozen_abi::abi_example::AbiExample for OptionalAccountWriteRecorder {
+    fn example() -> Self {
+        Self(None)
+    }
+}
+
+/// Gathers the account writes of a batch of transactions while they are stored
+pub struct AccountWritesCollector {
+    slot: Slot,
+    include_data: bool,
+    /// Ordinal of each transaction of the batch
+    ordinals: Vec<u64>,
+    writes: Vec<TransactionAccountWrites>,
+}
+
+impl AccountWritesCollector {
+    pub fn new(slot: Slot, include_data: bool, ordinals: Vec<u64>) -> Self {
+        Self {
+            slot,
+            include_data,
+            ordinals,
+            writes: vec![],
+        }
+    }
+
+    /// Records that the `tx_index`th transaction of the batch wrote `account`. The writes of a
+    /// transaction must be pushed together.
+    pub fn push(
+        &mut self,
+        tx_index: usize,
+        signature: &Signature,
+        pubkey: &Pubkey,
+        account: &AccountSharedData,
+    ) {
+        let write = AccountWrite {
+            pubkey: *pubkey,
+            lamports: account.lamports(),
+            hash: AccountsDb::hash_account(self.slot, account, pubkey),
+            data: self.include_data.then(|| account.data().to_vec()),
+        };
+        let ordinal = self.ordinals[tx_index];
+        match self.writes.last_mut() {
+            Some(writes) if writes.ordinal == ordinal => writes.accounts.push(write),
+            _ => self.writes.push(TransactionAccountWrites {
+                ordinal,
+                signature: *signature,
+                accounts: vec![write],
+            }),
+        }
+    }
+
+    pub fn into_writes(self) -> Vec<TransactionAccountWrites> {
+        self.writes
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_account_writes_collector() {
+        let slot = 3;
+        let signatures = [Signature::new(&[1; 64]), Signature::new(&[2; 64])];
+        let pubkeys = [Pubkey::new_unique(), Pubkey::new_unique()];
+        let account = AccountSharedData::new(42, 3, &Pubkey::new_unique());
+
+        let mut collector = AccountWritesCollector::new(slot, false, vec![7, 9]);
+        collector.push(0, &signatures[0], &pubkeys[0], &account);
+        collector.push(0, &signatures[0], &pubkeys[1], &account);
+        collector.push(1, &signatures[1], &pubkeys[0], &account);
+        let writes = collector.into_writes();
+        assert_eq!(writes.len(), 2);
+        assert_eq!(writes[0].ordinal, 7);
+        assert_eq!(writes[0].signature, signatures[0]);
+        assert_eq!(writes[0].accounts.len(), 2);
+        assert_eq!(
+            writes[0].accounts[1],
+            AccountWrite {
+                pubkey: pubkeys[1],
+                lamports: 42,
+                hash: AccountsDb::hash_account(slot, &account, &pubkeys[1]),
+                data: None,
+            }
+        );
+        assert_eq!(writes[1].ordinal, 9);
+        assert_eq!(writes[1].signature, signatures[1]);
+        assert_eq!(writes[1].accounts.len(), 1);
+
+        let mut collector = AccountWritesCollector::new(slot, true, vec![0]);
+        collector.push(0, &signatures[0], &pubkeys[0], &account);
+        assert_eq!(
+            collector.into_writes()[0].accounts[0].data,
+            Some(vec![0; 3])
+        );
+    }
+}
@@ -1,4 +1,5 @@
 use crate::{
+    account_write_log::AccountWritesCollector,
     accounts_db::{
         AccountShrinkThreshold, AccountsDb, BankHashInfo, ErrorCounters, LoadHint, LoadedAccount,
         ScanStorageResult,
@@ -778,6 +779,21 @@ impl Accounts {
         )
     }
 
+    pub fn load_all_with_filter<F: Fn(&AccountSharedData) -> bool>(
+        &self,
+        ancestors: &Ancestors,
+        bank_id: BankId,
+        filter: F,
+    ) -> ScanResult<Vec<(Pubkey, AccountSharedData)>> {
+        self.accounts_db.scan_accounts(
+            ancestors,
+            bank_id,
+            |collector: &mut Vec<(Pubkey, AccountSharedData)>, some_account_tuple| {
+                Self::load_while_filtering(collector, some_account_tuple, &filter)
+            },
+        )
+    }
+
     pub fn load_to_collect_rent_eagerly<R: RangeBounds<Pubkey>>(
         &self,
         ancestors: &Ancestors,
@@ -916,6 +932,7 @@ impl Accounts {
         last_blockhash_with_fee_calculator: &(Hash, FeeCalculator),
         rent_for_sysvars: bool,
         merge_nonce_error_into_system_error: bool,
+        account_writes: Option<&mut AccountWritesCollector>,
     ) {
         let accounts_to_store = self.collect_accounts_to_store(
             txs,
@@ -925,6 +942,7 @@ impl Accounts {
             last_blockhash_with_fee_calculator,
             rent_for_sysvars,
             merge_nonce_error_into_system_error,
+            account_writes,
         );
         self.accounts_db.store_cached(slot, &accounts_to_store);
     }
@@ -950,13 +968,14 @@ impl Accounts {
         last_blockhash_with_fee_calculator: &(Hash, FeeCalculator),
         rent_for_sysvars: bool,
         merge_nonce_error_into_system_error: bool,
+        mut account_writes: Option<&mut AccountWritesCollector>,
     ) -> Vec<(&'a Pubkey, &'a AccountSharedData)> {
         let mut accounts = Vec::with_capacity(loaded.len());
-        for (i, ((raccs, _nonce_rollback), tx)) in loaded.iter_mut().zip(txs).enumerate() {
+        for (tx_index, ((raccs, _nonce_rollback), tx)) in loaded.iter_mut().zip(txs).enumerate() {
             if raccs.is_err() {
                 continue;
             }
-            let (res, nonce_rollback) = &res[i];
+            let (res, nonce_rollback) = &res[tx_index];
             let maybe_nonce_rollback = match (res, nonce_rollback) {
                 (Ok(_), Some(nonce_rollback)) => {
                     let pubkey = nonce_rollback.nonce_address();
@@ -1026,6 +1045,9 @@ impl Accounts {
                             .rent_debits
                             .push(key, rent, account.lamports());
                     }
+                    if let Some(account_writes) = account_writes.as_deref_mut() {
+                        account_writes.push(tx_index, tx.signature(), key, account);
+                    }
                     accounts.push((&*key, &*account));
                 }
             }
@@ -1977,6 +1999,7 @@ mod tests {
             &(Hash::default(), FeeCalculator::default()),
             true,
             true, // merge_nonce_error_into_system_error
+            None,
         );
         assert_eq!(collected_accounts.len(), 2);
         assert!(collected_accounts
@@ -2356,6 +2379,7 @@ mod tests {
             &(next_blockhash, FeeCalculator::default()),
             true,
             true, // merge_nonce_error_into_system_error
+            None,
         );
         assert_eq!(collected_accounts.len(), 2);
         assert_eq!(
@@ -2473,6 +2497,7 @@ mod tests {
             &(next_blockhash, FeeCalculator::default()),
             true,
             true, // merge_nonce_error_into_system_error
+            None,
         );
         assert_eq!(collected_accounts.len(), 1);
         let collected_nonce_account = collected_accounts
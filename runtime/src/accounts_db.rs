@@ -21,7 +21,11 @@
 use crate::{
     accounts_background_service::{DroppedSlotsSender, SendDroppedBankCallback},
     accounts_cache::{AccountsCache, CachedAccount, SlotCache},
-    accounts_hash::{AccountsHash, CalculateHashIntermediate, HashStats, PreviousPass},
+    accounts_hash::{
+        AccountsHash, AccountsMerkleTree, CalculateHashIntermediate, HashStats, PreviousPass,
+        MERKLE_FANOUT,
+    },
+    accounts_hash_cache::{AccountsHashCache, CACHED_NUM_SCAN_PASSES},
     accounts_index::{
         AccountIndexGetResult, AccountSecondaryIndexes, AccountsIndex, AccountsIndexConfig,
         AccountsIndexRootsStats, IndexKey, IsCached, RefCount, ScanResult, SlotList, SlotSlice,
@@ -53,7 +57,7 @@ use solana_sdk::{
     genesis_config::ClusterType,
     hash::{Hash, Hasher},
     pubkey::Pubkey,
-    timing::AtomicInterval,
+    timing::{timestamp, AtomicInterval},
 };
 use solana_vote_program::vote_state::MAX_LOCKOUT_HISTORY;
 use std::{
@@ -61,6 +65,7 @@ use std::{
     boxed::Box,
     collections::{hash_map::Entry, BTreeSet, HashMap, HashSet},
     convert::TryFrom,
+    fmt,
     io::{Error as IoError, Result as IoResult},
     ops::{Range, RangeBounds},
     path::{Path, PathBuf},
@@ -140,6 +145,119 @@ impl Default for AccountShrinkThreshold {
     }
 }
 
+/// Fragmentation of a single storage entry
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StorageEntryReport {
+    pub slot: Slot,
+    pub append_vec_id: AppendVecId,
+    pub alive_bytes: u64,
+    pub written_bytes: u64,
+    /// Size of the file on disk
+    pub total_bytes: u64,
+    pub alive_accounts: u64,
+    /// Accounts stored that have since been overwritten or purged, approximate
+    pub dead_accounts: u64,
+    /// Milliseconds since the slot was last shrunk, if it has been since startup
+    pub ms_since_last_shrink: Option<u64>,
+    pub is_shrink_candidate: bool,
+}
+
+impl StorageEntryReport {
+    pub fn alive_ratio(&self) -> f64 {
+        if self.total_bytes == 0 {
+            0.0
+        } else {
+            self.alive_bytes as f64 / self.total_bytes as f64
+        }
+    }
+}
+
+/// Fragmentation of the account storages, and how recently shrink and clean ran
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StorageReport {
+    pub num_slots: u64,
+    pub num_storages: u64,
+    pub alive_bytes: u64,
+    pub written_bytes: u64,
+    pub total_bytes: u64,
+    pub dead_accounts: u64,
+    pub num_shrink_candidates: u64,
+    /// Stores waiting to be examined by the next clean
+    pub num_dirty_stores: u64,
+    /// Emptied stores kept on disk for reuse
+    pub num_recycle_stores: u64,
+    pub recycle_stores_bytes: u64,
+    pub ms_since_last_clean: Option<u64>,
+    /// The sparsest storages, by alive ratio
+    pub storages: Vec<StorageEntryReport>,
+}
+
+impl fmt::Display for StorageReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let ms_ago = |ms: Option<u64>| match ms {
+            Some(ms) => format!("{:.1}s ago", ms as f64 / 1000.0),
+            None => "never".to_string(),
+        };
+        writeln!(
+            f,
+            "Storages: {} in {} slots",
+            self.num_storages, self.num_slots
+        )?;
+        writeln!(
+            f,
+            "  Alive bytes: {} of {} written, {} on disk (alive ratio {:.3})",
+            self.alive_bytes,
+            self.written_bytes,
+            self.total_bytes,
+            self.alive_bytes as f64 / self.total_bytes.max(1) as f64
+        )?;
+        writeln!(f, "  Dead accounts: {}", self.dead_accounts)?;
+        writeln!(f, "  Shrink candidates: {}", self.num_shrink_candidates)?;
+        writeln!(
+            f,
+            "  Dirty stores awaiting clean: {}",
+            self.num_dirty_stores
+        )?;
+        writeln!(
+            f,
+            "  Recycle stores: {} ({} bytes)",
+            self.num_recycle_stores, self.recycle_stores_bytes
+        )?;
+        writeln!(f, "  Last clean: {}", ms_ago(self.ms_since_last_clean))?;
+        if !self.storages.is_empty() {
+            writeln!(f, "Sparsest storages:")?;
+            writeln!(
+                f,
+                "  {:>12} {:>8} {:>12} {:>12} {:>12} {:>6} {:>8} {:>8}  Last shrink",
+                "Slot", "Id", "Alive", "Written", "Total", "Ratio", "Alive#", "Dead#"
+            )?;
+            for storage in &self.storages {
+                writeln!(
+                    f,
+                    "  {:>12} {:>8} {:>12} {:>12} {:>12} {:>6.3} {:>8} {:>8}  {}{}",
+                    storage.slot,
+                    storage.append_vec_id,
+                    storage.alive_bytes,
+                    storage.written_bytes,
+                    storage.total_bytes,
+                    storage.alive_ratio(),
+                    storage.alive_accounts,
+                    storage.dead_accounts,
+                    ms_ago(storage.ms_since_last_shrink),
+                    if storage.is_shrink_candidate {
+                        " (shrink candidate)"
+                    } else {
+                        ""
+                    },
+                )?;
+            }
+        }
+        Ok(())
+    }
+}
+
 pub enum ScanStorageResult<R, B> {
     Cached(Vec<R>),
     Stored(B),
@@ -212,7 +330,7 @@ impl GenerateIndexTimings {
     }
 }
 
-#[derive(Default, Debug, PartialEq, Clone)]
+#[derive(Default, Debug, PartialEq, Clone, Serialize, Deserialize)]
 pub struct AccountInfo {
     /// index identifying the append storage
     store_id: AppendVecId,
@@ -919,6 +1037,25 @@ pub struct AccountsDb {
 
     pub shrink_paths: RwLock<Option<Vec<PathBuf>>>,
 
+    /// Wallclock time, in milliseconds, at which each slot was last shrunk
+    last_shrink_timestamps: DashMap<Slot, u64>,
+
+    /// Wallclock time, in milliseconds, at which clean last finished, 0 if never
+    last_clean_timestamp: AtomicU64,
+
+    /// Target alive ratio of a shrink requested by `request_shrink()`, run by the next
+    /// `shrink_candidate_slots()`
+    requested_shrink_ratio: Mutex<Option<f64>>,
+
+    /// Where an accounts hash calculation from storages keeps the passes it has finished
+    accounts_hash_cache_path: RwLock<Option<PathBuf>>,
+
+    /// Whether `update_accounts_hash()` keeps the Merkle tree of the hash it computes
+    retain_accounts_merkle_tree: AtomicBool,
+
+    /// The Merkle tree of the last accounts hash computed, to serve account proofs from
+    accounts_merkle_tree: RwLock<Option<Arc<AccountsMerkleTree>>>,
+
     /// Directory of paths this accounts_db needs to hold/remove
     pub(crate) temp_paths: Option<Vec<TempDir>>,
 
@@ -1389,6 +1526,12 @@ impl AccountsDb {
             write_version: AtomicU64::new(0),
             paths: vec![],
             shrink_paths: RwLock::new(None),
+            last_shrink_timestamps: DashMap::new(),
+            last_clean_timestamp: AtomicU64::default(),
+            requested_shrink_ratio: Mutex::new(None),
+            accounts_hash_cache_path: RwLock::new(None),
+            retain_accounts_merkle_tree: AtomicBool::default(),
+            accounts_merkle_tree: RwLock::default(),
             temp_paths: None,
             file_size: DEFAULT_FILE_SIZE,
             thread_pool: rayon::ThreadPoolBuilder::new()
@@ -1482,6 +1625,25 @@ impl AccountsDb {
         *shrink_paths = Some(paths);
     }
 
+    pub fn set_accounts_hash_cache_path(&self, path: Option<PathBuf>) {
+        *self.accounts_hash_cache_path.write().unwrap() = path;
+    }
+
+    /// Keep the Merkle tree of each accounts hash computed from now on. This costs another scan
+    /// of the index per accounts hash, and memory for the hashes of all accounts.
+    pub fn set_retain_accounts_merkle_tree(&self, retain: bool) {
+        self.retain_accounts_merkle_tree
+            .store(retain, Ordering::Relaxed);
+        if !retain {
+            *self.accounts_merkle_tree.write().unwrap() = None;
+        }
+    }
+
+    /// The Merkle tree of the last accounts hash computed, if it was retained
+    pub fn accounts_merkle_tree(&self) -> Option<Arc<AccountsMerkleTree>> {
+        self.accounts_merkle_tree.read().unwrap().clone()
+    }
+
     pub fn file_size(&self) -> u64 {
         self.file_size
     }
@@ -2036,6 +2198,8 @@ impl AccountsDb {
             ("dirty_pubkeys_count", key_timings.dirty_pubkeys_count, i64),
             ("total_keys_count", total_keys_count, i64),
         );
+        self.last_clean_timestamp
+            .store(timestamp(), Ordering::Relaxed);
     }
 
     /// Removes the accounts in the input `reclaims` from the tracked "count" of
@@ -2451,6 +2615,7 @@ impl AccountsDb {
             .fetch_add(aligned_total, Ordering::Relaxed);
 
         self.shrink_stats.report();
+        self.last_shrink_timestamps.insert(slot, timestamp());
 
         total_accounts_after_shrink
     }
@@ -2552,7 +2717,45 @@ impl AccountsDb {
         shrink_slots
     }
 
+    /// Shrinks the sparsest storages of all slots, regardless of the configured shrink
+    /// threshold, until alive bytes make up at least `target_alive_ratio` of the storage
+    /// bytes. Returns the number of slots selected for shrinking.
+    pub fn shrink_to_alive_ratio(&self, target_alive_ratio: f64) -> usize {
+        let mut candidates: ShrinkCandidates = HashMap::new();
+        for slot in self.all_slots_in_storage() {
+            if let Some(stores_lock) = self.storage.get_slot_stores(slot) {
+                for (id, store) in stores_lock.read().unwrap().iter() {
+                    candidates
+                        .entry(slot)
+                        .or_default()
+                        .insert(*id, store.clone());
+                }
+            }
+        }
+        let shrink_slots = self.select_candidates_by_total_usage(&candidates, target_alive_ratio);
+        info!(
+            "shrinking {} slots to reach alive ratio {}",
+            shrink_slots.len(),
+            target_alive_ratio
+        );
+        let num_slots = shrink_slots.len();
+        for (slot, slot_stores) in shrink_slots {
+            self.do_shrink_slot_stores(slot, slot_stores.values(), false);
+        }
+        num_slots
+    }
+
+    /// Asks the next `shrink_candidate_slots()` to also `shrink_to_alive_ratio()`, so the
+    /// shrink runs on the thread that already does so in the background
+    pub fn request_shrink(&self, target_alive_ratio: f64) {
+        *self.requested_shrink_ratio.lock().unwrap() = Some(target_alive_ratio);
+    }
+
     pub fn shrink_candidate_slots(&self) -> usize {
+        let requested_shrink_ratio = self.requested_shrink_ratio.lock().unwrap().take();
+        if let Some(target_alive_ratio) = requested_shrink_ratio {
+            self.shrink_to_alive_ratio(target_alive_ratio);
+        }
         let shrink_candidates_slots =
             std::mem::take(&mut *self.shrink_candidate_slots.lock().unwrap());
         let shrink_slots = {
@@ -3618,6 +3821,7 @@ impl AccountsDb {
         let mut remove_storage_entries_elapsed = Measure::start("remove_storage_entries_elapsed");
         for remove_slot in removed_slots {
             // Remove the storage entries and collect some metrics
+            self.last_shrink_timestamps.remove(remove_slot);
             if let Some((_, slot_storages_to_be_removed)) = self.storage.0.remove(remove_slot) {
                 {
                     let r_slot_removed_storages = slot_storages_to_be_removed.read().unwrap();
@@ -4500,6 +4704,67 @@ impl AccountsDb {
         }
     }
 
+    /// Reports fragmentation of every storage, listing at most `limit` of the sparsest ones
+    pub fn storage_report(&self, limit: usize) -> StorageReport {
+        let now = timestamp();
+        let shrink_candidates = self.shrink_candidate_slots.lock().unwrap().clone();
+        let mut report = StorageReport::default();
+        let mut storages = vec![];
+        for slot in self.all_slots_in_storage() {
+            let stores = match self.storage.get_slot_stores(slot) {
+                Some(stores_lock) => stores_lock.read().unwrap().clone(),
+                None => continue,
+            };
+            let ms_since_last_shrink = self
+                .last_shrink_timestamps
+                .get(&slot)
+                .map(|last_shrink| now.saturating_sub(*last_shrink));
+            report.num_slots += 1;
+            for (id, store) in stores {
+                let is_shrink_candidate = shrink_candidates
+                    .get(&slot)
+                    .map(|slot_candidates| slot_candidates.contains_key(&id))
+                    .unwrap_or_default();
+                let entry = StorageEntryReport {
+                    slot,
+                    append_vec_id: id,
+                    alive_bytes: store.alive_bytes() as u64,
+                    written_bytes: store.written_bytes(),
+                    total_bytes: store.total_bytes(),
+                    alive_accounts: store.count() as u64,
+                    dead_accounts: store.approx_stored_count().saturating_sub(store.count()) as u64,
+                    ms_since_last_shrink,
+                    is_shrink_candidate,
+                };
+                report.num_storages += 1;
+                report.alive_bytes += entry.alive_bytes;
+                report.written_bytes += entry.written_bytes;
+                report.total_bytes += entry.total_bytes;
+                report.dead_accounts += entry.dead_accounts;
+                report.num_shrink_candidates += is_shrink_candidate as u64;
+                storages.push(entry);
+            }
+        }
+        storages.sort_by(|a, b| {
+            a.alive_ratio()
+                .partial_cmp(&b.alive_ratio())
+                .unwrap_or(std::cmp::Ordering::Equal)
+                .then(a.slot.cmp(&b.slot))
+        });
+        storages.truncate(limit);
+        report.storages = storages;
+
+        report.num_dirty_stores = self.dirty_stores.len() as u64;
+        {
+            let recycle_stores = self.recycle_stores.read().unwrap();
+            report.num_recycle_stores = recycle_stores.entry_count() as u64;
+            report.recycle_stores_bytes = recycle_stores.total_bytes();
+        }
+        let last_clean = self.last_clean_timestamp.load(Ordering::Relaxed);
+        report.ms_since_last_clean = (last_clean > 0).then(|| now.saturating_sub(last_clean));
+        report
+    }
+
     fn report_store_stats(&self) {
         let mut total_count = 0;
         let mut min = std::usize::MAX;
@@ -4573,6 +4838,83 @@ impl AccountsDb {
         AccountsHash::checked_cast_for_capitalization(balances.map(|b| b as u128).sum::<u128>())
     }
 
+    /// The slot, hash and lamports of `pubkey` that go into the accounts hash of `slot`, or None
+    /// if the account has no lamports or, when `check_hash` is set, its stored hash is wrong
+    fn load_hash_for_accounts_hash(
+        &self,
+        pubkey: &Pubkey,
+        slot: Slot,
+        ancestors: &Ancestors,
+        check_hash: bool,
+        mismatch_found: &AtomicU64,
+    ) -> Option<(Slot, Hash, u64)> {
+        if let AccountIndexGetResult::Found(lock, index) =
+            self.accounts_index.get(pubkey, Some(ancestors), Some(slot))
+        {
+            let (slot, account_info) = &lock.slot_list()[index];
+            if account_info.lamports == 0 {
+                return None;
+            }
+            // Because we're keeping the `lock' here, there is no need
+            // to use retry_to_get_account_accessor()
+            // In other words, flusher/shrinker/cleaner is blocked to
+            // cause any Accessor(None) situtation.
+            // Anyway this race condition concern is currently a moot
+            // point because calculate_accounts_hash() should not
+            // currently race with clean/shrink because the full hash
+            // is synchronous with clean/shrink in
+            // AccountsBackgroundService
+            self.get_account_accessor(*slot, pubkey, account_info.store_id, account_info.offset)
+                .get_loaded_account()
+                .and_then(|loaded_account| {
+                    let loaded_hash = loaded_account.loaded_hash();
+                    if check_hash {
+                        let computed_hash = loaded_account.compute_hash(*slot, pubkey);
+                        if computed_hash != loaded_hash {
+                            info!(
+                                "hash mismatch found: computed: {}, loaded: {}, pubkey: {}",
+                                computed_hash, loaded_hash, pubkey
+                            );
+                            mismatch_found.fetch_add(1, Ordering::Relaxed);
+                            return None;
+                        }
+                    }
+                    Some((*slot, loaded_hash, account_info.lamports))
+                })
+        } else {
+            None
+        }
+    }
+
+    /// The Merkle tree of the accounts hash of `slot`
+    ///
+    /// Like `calculate_accounts_hash()`, this scans the whole index.
+    pub fn build_accounts_merkle_tree(
+        &self,
+        slot: Slot,
+        ancestors: &Ancestors,
+    ) -> AccountsMerkleTree {
+        let mut keys: Vec<_> = self
+            .accounts_index
+            .account_maps
+            .iter()
+            .flat_map(|map| map.read().unwrap().keys())
+            .collect();
+        // bins are already sorted within and across, but the tree must be sorted by pubkey
+        keys.par_sort_unstable();
+
+        let mismatch_found = AtomicU64::new(0);
+        let accounts = self.thread_pool_clean.install(|| {
+            keys.par_iter()
+                .filter_map(|key| {
+                    self.load_hash_for_accounts_hash(key, slot, ancestors, false, &mismatch_found)
+                        .map(|(account_slot, hash, _lamports)| (*key, account_slot, hash))
+                })
+                .collect()
+        });
+        AccountsMerkleTree::new(slot, accounts, MERKLE_FANOUT)
+    }
+
     fn calculate_accounts_hash(
         &self,
         slot: Slot,
@@ -4585,8 +4927,7 @@ impl AccountsDb {
             .accounts_index
             .account_maps
             .iter()
-            .map(|btree| btree.read().unwrap().keys().cloned().collect::<Vec<_>>())
-            .flatten()
+            .flat_map(|map| map.read().unwrap().keys())
             .collect();
         collect.stop();
 
@@ -4594,7 +4935,7 @@ impl AccountsDb {
         let mismatch_found = AtomicU64::new(0);
         // Pick a chunk size big enough to allow us to produce output vectors that are smaller than the overall size.
         // We'll also accumulate the lamports within each chunk and fewer chunks results in less contention to accumulate the sum.
-        let chunks = crate::accounts_hash::MERKLE_FANOUT.pow(4);
+        let chunks = MERKLE_FANOUT.pow(4);
         let total_lamports = Mutex::<u64>::new(0);
         let get_hashes = || {
             keys.par_chunks(chunks)
@@ -4603,59 +4944,22 @@ impl AccountsDb {
                     let result: Vec<Hash> = pubkeys
                         .iter()
                         .filter_map(|pubkey| {
-                            if let AccountIndexGetResult::Found(lock, index) =
-                                self.accounts_index.get(pubkey, Some(ancestors), Some(slot))
-                            {
-                                let (slot, account_info) = &lock.slot_list()[index];
-                                if account_info.lamports != 0 {
-                                    // Because we're keeping the `lock' here, there is no need
-                                    // to use retry_to_get_account_accessor()
-                                    // In other words, flusher/shrinker/cleaner is blocked to
-                                    // cause any Accessor(None) situtation.
-                                    // Anyway this race condition concern is currently a moot
-                                    // point because calculate_accounts_hash() should not
-                                    // currently race with clean/shrink because the full hash
-                                    // is synchronous with clean/shrink in
-                                    // AccountsBackgroundService
-                                    self.get_account_accessor(
-                                        *slot,
-                                        pubkey,
-                                        account_info.store_id,
-                                        account_info.offset,
-                                    )
-                                    .get_loaded_account()
-                                    .and_then(
-                                        |loaded_account| {
-                                            let loaded_hash = loaded_account.loaded_hash();
-                                            let balance = account_info.lamports;
-                                            if check_hash {
-                                                let computed_hash =
-                                                    loaded_account.compute_hash(*slot, pubkey);
-                                                if computed_hash != loaded_hash {
-                                                    info!("hash mismatch found: computed: {}, loaded: {}, pubkey: {}", computed_hash, loaded_hash, pubkey);
-                                                    mismatch_found
-                                                        .fetch_add(1, Ordering::Relaxed);
-                                                    return None;
-                                                }
-                                            }
-
-                                            sum += balance as u128;
-                                            Some(loaded_hash)
-                                        },
-                                    )
-                                } else {
-                                    None
-                                }
-                            } else {
-                                None
-                            }
+                            let (_slot, loaded_hash, balance) = self.load_hash_for_accounts_hash(
+                                pubkey,
+                                slot,
+                                ancestors,
+                                check_hash,
+                                &mismatch_found,
+                            )?;
+                            sum += balance as u128;
+                            Some(loaded_hash)
                         })
                         .collect();
                     let mut total = total_lamports.lock().unwrap();
-                    *total =
-                        AccountsHash::checked_cast_for_capitalization(*total as u128 + sum);
+                    *total = AccountsHash::checked_cast_for_capitalization(*total as u128 + sum);
                     result
-                }).collect()
+                })
+                .collect()
         };
 
         let hashes: Vec<Vec<Hash>> = if check_hash {
@@ -4852,12 +5156,19 @@ impl AccountsDb {
                 ..HashStats::default()
             };
 
+            let cache = self
+                .accounts_hash_cache_path
+                .read()
+                .unwrap()
+                .clone()
+                .map(AccountsHashCache::new);
             Self::calculate_accounts_hash_without_index(
                 &storages,
                 Some(&self.thread_pool_clean),
                 timings,
                 check_hash,
                 accounts_cache_and_ancestors,
+                cache.as_ref(),
             )
         } else {
             self.calculate_accounts_hash(slot, ancestors, check_hash)
@@ -4923,6 +5234,21 @@ impl AccountsDb {
         let mut bank_hashes = self.bank_hashes.write().unwrap();
         let mut bank_hash_info = bank_hashes.get_mut(&slot).unwrap();
         bank_hash_info.snapshot_hash = hash;
+        drop(bank_hashes);
+
+        if self.retain_accounts_merkle_tree.load(Ordering::Relaxed) {
+            let tree = self.build_accounts_merkle_tree(slot, ancestors);
+            if tree.root() == hash {
+                *self.accounts_merkle_tree.write().unwrap() = Some(Arc::new(tree));
+            } else {
+                warn!(
+                    "accounts Merkle tree root {} does not match accounts hash {} at slot {}",
+                    tree.root(),
+                    hash,
+                    slot
+                );
+            }
+        }
         (hash, total_lamports)
     }
 
@@ -5039,6 +5365,10 @@ impl AccountsDb {
 
     // modeled after get_accounts_delta_hash
     // intended to be faster than calculate_accounts_hash
+    //
+    // With a `cache`, the result of each finished pass is saved so that a later calculation over
+    // the same storages skips it. The write cache is not part of the key, so nothing is cached
+    // when `accounts_cache_and_ancestors` is given.
     pub fn calculate_accounts_hash_without_index(
         storages: &SortedStorages,
         thread_pool: Option<&ThreadPool>,
@@ -5049,7 +5379,9 @@ impl AccountsDb {
             &Ancestors,
             &AccountInfoAccountsIndex,
         )>,
+        cache: Option<&AccountsHashCache>,
     ) -> Result<(Hash, u64), BankHashVerificationError> {
+        let cache = cache.filter(|_| accounts_cache_and_ancestors.is_none());
         let mut scan_and_hash = move || {
             // When calculating hashes, it is helpful to break the pubkeys found into bins based on the pubkey value.
             // More bins means smaller vectors to sort, copy, etc.
@@ -5059,40 +5391,104 @@ impl AccountsDb {
             // higher passes = slower total time, lower dynamic memory usage
             // lower passes = faster total time, higher dynamic memory usage
             // passes=2 cuts dynamic memory usage in approximately half.
-            let num_scan_passes: usize = 2;
+            // When caching, every finished pass is also a point to resume from.
+            let num_scan_passes: usize = if cache.is_some() {
+                CACHED_NUM_SCAN_PASSES
+            } else {
+                2
+            };
 
             let bins_per_pass = PUBKEY_BINS_FOR_CALCULATING_HASHES / num_scan_passes;
             assert_eq!(
                 bins_per_pass * num_scan_passes,
                 PUBKEY_BINS_FOR_CALCULATING_HASHES
             ); // evenly divisible
+            let cache_key = cache.and_then(|cache| {
+                let key = AccountsHashCache::key(storages, check_hash, num_scan_passes);
+                cache
+                    .prepare(&key)
+                    .map_err(|err| {
+                        warn!(
+                            "accounts hash cache unavailable at {:?}: {}",
+                            cache.dir(),
+                            err
+                        )
+                    })
+                    .ok()
+                    .map(|_| (cache, key))
+            });
             let mut previous_pass = PreviousPass::default();
             let mut final_result = (Hash::default(), 0);
+            let start = Instant::now();
 
             for pass in 0..num_scan_passes {
-                let bounds = Range {
-                    start: pass * bins_per_pass,
-                    end: (pass + 1) * bins_per_pass,
+                let cached = cache_key.and_then(|(cache, key)| cache.load(&key, pass));
+                let from_cache = cached.is_some();
+                let (hashes, lamports) = match cached {
+                    Some(cached) => cached,
+                    None => {
+                        let bounds = Range {
+                            start: pass * bins_per_pass,
+                            end: (pass + 1) * bins_per_pass,
+                        };
+
+                        let result = Self::scan_snapshot_stores_with_cache(
+                            storages,
+                            &mut stats,
+                            PUBKEY_BINS_FOR_CALCULATING_HASHES,
+                            &bounds,
+                            check_hash,
+                            accounts_cache_and_ancestors,
+                        )?;
+                        let (hashes, lamports) = AccountsHash::de_dup_and_eliminate_zeros(
+                            result,
+                            &mut stats,
+                            bins_per_pass,
+                        );
+                        if let Some((cache, key)) = cache_key {
+                            if let Err(err) = cache.store(&key, pass, &hashes, lamports) {
+                                warn!("failed to cache accounts hash pass {}: {}", pass, err);
+                            }
+                        }
+                        (hashes, lamports)
+                    }
                 };
+                let num_hashes = hashes.iter().map(|bin| bin.len()).sum::<usize>();
+
+                let (hash, lamports, for_next_pass) =
+                    AccountsHash::rest_of_hash_calculation_from_hashes(
+                        hashes,
+                        lamports,
+                        &mut stats,
+                        pass == num_scan_passes - 1,
+                        previous_pass,
+                    );
+                previous_pass = for_next_pass;
+                final_result = (hash, lamports);
 
-                let result = Self::scan_snapshot_stores_with_cache(
-                    storages,
-                    &mut stats,
+                info!(
+                    "calculate_accounts_hash_without_index: pass {}/{} ({} of {} pubkey bins) done{}, {} accounts, {}ms elapsed",
+                    pass + 1,
+                    num_scan_passes,
+                    (pass + 1) * bins_per_pass,
                     PUBKEY_BINS_FOR_CALCULATING_HASHES,
-                    &bounds,
-                    check_hash,
-                    accounts_cache_and_ancestors,
-                )?;
-
-                let (hash, lamports, for_next_pass) = AccountsHash::rest_of_hash_calculation(
-                    result,
-                    &mut stats,
-                    pass == num_scan_passes - 1,
-                    previous_pass,
-                    bins_per_pass,
+                    if from_cache { " from cache" } else { "" },
+                    num_hashes,
+                    start.elapsed().as_millis(),
+                );
+                datapoint_info!(
+                    "calculate_accounts_hash_without_index-progress",
+                    ("pass", pass + 1, i64),
+                    ("num_passes", num_scan_passes, i64),
+                    ("from_cache", from_cache, bool),
+                    ("accounts", num_hashes, i64),
+                    ("elapsed_ms", start.elapsed().as_millis(), i64),
                 );
-                previous_pass = for_next_pass;
-                final_result = (hash, lamports);
+            }
+            if let Some((cache, key)) = cache_key {
+                if let Err(err) = cache.remove(&key) {
+                    warn!("failed to clear accounts hash cache: {}", err);
+                }
             }
             Ok(final_result)
         };
@@ -6271,7 +6667,7 @@ impl AccountsDb {
         roots.sort();
         info!("{}: accounts_index roots: {:?}", label, roots,);
         self.accounts_index.account_maps.iter().for_each(|i| {
-            for (pubkey, account_entry) in i.read().unwrap().iter() {
+            for (pubkey, account_entry) in i.read().unwrap().range(.., usize::MAX) {
                 info!("  key: {} ref_count: {}", pubkey, account_entry.ref_count(),);
                 info!(
                     "      slots: {:?}",
@@ -6897,6 +7293,7 @@ pub mod tests {
             HashStats::default(),
             false,
             None,
+            None,
         )
         .unwrap();
         let expected_hash = Hash::from_str("GKot5hBsd81kMupNCXHaqbhv3huEbxAFMLnpcX2hniwn").unwrap();
@@ -6919,12 +7316,52 @@ pub mod tests {
             HashStats::default(),
             false,
             None,
+            None,
         )
         .unwrap();
 
         assert_eq!(result, (expected_hash, sum));
     }
 
+    #[test]
+    fn test_accountsdb_calculate_accounts_hash_without_index_cached() {
+        solana_logger::setup();
+
+        let (storages, raw_expected) = sample_storages_and_accounts();
+        let storages = get_storage_refs(&storages);
+        let expected_hash =
+            AccountsHash::compute_merkle_root_loop(raw_expected.clone(), MERKLE_FANOUT, |item| {
+                item.hash
+            });
+        let sum = raw_expected.iter().map(|item| item.lamports).sum();
+        let tmp_dir = TempDir::new().unwrap();
+        let cache = AccountsHashCache::new(tmp_dir.path());
+        let calculate = || {
+            AccountsDb::calculate_accounts_hash_without_index(
+                &storages,
+                None,
+                HashStats::default(),
+                false,
+                None,
+                Some(&cache),
+            )
+            .unwrap()
+        };
+        assert_eq!(calculate(), (expected_hash, sum));
+
+        // a finished pass is not scanned again
+        let key = AccountsHashCache::key(&storages, false, CACHED_NUM_SCAN_PASSES);
+        cache.prepare(&key).unwrap();
+        cache
+            .store(&key, 0, &[vec![Hash::new_unique()]], 7)
+            .unwrap();
+        assert_ne!(calculate(), (expected_hash, sum));
+
+        // the cache is cleared once the calculation finishes
+        assert_eq!(cache.load(&key, 0), None);
+        assert_eq!(calculate(), (expected_hash, sum));
+    }
+
     fn sample_storage() -> (SnapshotStorages, usize, Slot) {
         let (_temp_dirs, paths) = get_temp_accounts_paths(1).unwrap();
         let slot_expected: Slot = 0;
@@ -9058,6 +9495,50 @@ pub mod tests {
         );
     }
 
+    #[test]
+    fn test_accounts_merkle_tree() {
+        let db = AccountsDb::new(Vec::new(), &ClusterType::Development);
+        let some_slot: Slot = 1;
+        let ancestors = vec![(some_slot, 0)].into_iter().collect();
+        let accounts: Vec<_> = (0..40)
+            .map(|i| {
+                (
+                    solana_sdk::pubkey::new_rand(),
+                    AccountSharedData::new(i + 1, 0, &Pubkey::default()),
+                )
+            })
+            .collect();
+        let zero_lamport_key = solana_sdk::pubkey::new_rand();
+        let zero_lamport_account = AccountSharedData::new(0, 0, &Pubkey::default());
+        for (key, account) in &accounts {
+            db.store_uncached(some_slot, &[(key, account)]);
+        }
+        db.store_uncached(some_slot, &[(&zero_lamport_key, &zero_lamport_account)]);
+        db.add_root(some_slot);
+
+        db.update_accounts_hash(some_slot, &ancestors);
+        assert!(db.accounts_merkle_tree().is_none());
+
+        db.set_retain_accounts_merkle_tree(true);
+        let (accounts_hash, _) = db.update_accounts_hash(some_slot, &ancestors);
+        let tree = db.accounts_merkle_tree().unwrap();
+        assert_eq!(tree.slot, some_slot);
+        assert_eq!(tree.root(), accounts_hash);
+        for (key, account) in &accounts {
+            let proof = tree.get_account_proof(key).unwrap();
+            assert_eq!(proof.pubkey, *key);
+            assert_eq!(proof.slot, some_slot);
+            assert!(proof.verify_account(account, &accounts_hash));
+        }
+        assert!(tree.get_account_proof(&zero_lamport_key).is_none());
+        assert!(tree
+            .get_account_proof(&solana_sdk::pubkey::new_rand())
+            .is_none());
+
+        db.set_retain_accounts_merkle_tree(false);
+        assert!(db.accounts_merkle_tree().is_none());
+    }
+
     #[test]
     fn test_verify_bank_hash() {
         use BankHashVerificationError::*;
@@ -9833,6 +10314,92 @@ pub mod tests {
         );
     }
 
+    #[test]
+    fn test_storage_report_and_requested_shrink() {
+        solana_logger::setup();
+
+        let accounts = AccountsDb::new_single_for_tests();
+        let report = accounts.storage_report(10);
+        assert_eq!(report.num_storages, 0);
+        assert_eq!(report.ms_since_last_clean, None);
+
+        let pubkey_count = 30000;
+        let pubkeys: Vec<_> = (0..pubkey_count)
+            .map(|_| solana_sdk::pubkey::new_rand())
+            .collect();
+        let account = AccountSharedData::new(223, 0, AccountSharedData::default().owner());
+
+        let shrink_slot = 1;
+        for pubkey in &pubkeys {
+            accounts.store_uncached(shrink_slot, &[(pubkey, &account)]);
+        }
+        accounts.get_accounts_delta_hash(shrink_slot);
+        accounts.add_root(shrink_slot);
+
+        let pubkey_count_after_shrink = 25000;
+        let updated_pubkeys = &pubkeys[0..pubkey_count - pubkey_count_after_shrink];
+        for pubkey in updated_pubkeys {
+            accounts.store_uncached(shrink_slot + 1, &[(pubkey, &account)]);
+        }
+        accounts.get_accounts_delta_hash(shrink_slot + 1);
+        accounts.add_root(shrink_slot + 1);
+        accounts.clean_accounts(None, false, None);
+
+        assert_eq!(accounts.storage_report(1).storages.len(), 1);
+        let report = accounts.storage_report(usize::MAX);
+        assert_eq!(report.num_slots, 2);
+        assert_eq!(report.num_storages as usize, report.storages.len());
+        assert_eq!(
+            report.dead_accounts,
+            (pubkey_count - pubkey_count_after_shrink) as u64
+        );
+        assert!(report.ms_since_last_clean.is_some());
+        assert!(report
+            .storages
+            .windows(2)
+            .all(|pair| pair[0].alive_ratio() <= pair[1].alive_ratio()));
+        let sparse = report
+            .storages
+            .iter()
+            .find(|storage| storage.slot == shrink_slot)
+            .unwrap();
+        assert_eq!(sparse.alive_accounts, pubkey_count_after_shrink as u64);
+        assert_eq!(
+            sparse.dead_accounts,
+            (pubkey_count - pubkey_count_after_shrink) as u64
+        );
+        assert!(sparse.alive_bytes < sparse.written_bytes);
+        assert_eq!(sparse.ms_since_last_shrink, None);
+        assert!(report.to_string().contains("Sparsest storages:"));
+
+        // the background shrink leaves the slot alone, but a requested one to a
+        // higher alive ratio shrinks it
+        accounts.shrink_candidate_slots();
+        assert_eq!(
+            pubkey_count,
+            accounts.all_account_count_in_append_vec(shrink_slot)
+        );
+        accounts.request_shrink(0.99);
+        accounts.shrink_candidate_slots();
+        assert_eq!(
+            pubkey_count_after_shrink,
+            accounts.all_account_count_in_append_vec(shrink_slot)
+        );
+
+        let report = accounts.storage_report(usize::MAX);
+        assert_eq!(report.dead_accounts, 0);
+        let shrunk = report
+            .storages
+            .iter()
+            .find(|storage| storage.slot == shrink_slot)
+            .unwrap();
+        assert_eq!(shrunk.alive_accounts, pubkey_count_after_shrink as u64);
+        assert!(shrunk.ms_since_last_shrink.is_some());
+
+        // the request is consumed
+        assert_eq!(*accounts.requested_shrink_ratio.lock().unwrap(), None);
+    }
+
     #[test]
     fn test_select_candidates_by_total_usage() {
         solana_logger::setup();
@@ -2,6 +2,8 @@ use log::*;
 use rayon::prelude::*;
 use solana_measure::measure::Measure;
 use solana_sdk::{
+    account_proof::{AccountProof, MerkleProofLevel},
+    clock::Slot,
     hash::{Hash, Hasher},
     pubkey::Pubkey,
 };
@@ -503,7 +505,7 @@ impl AccountsHash {
             .expect("overflow is detected while summing capitalization")
     }
 
-    fn de_dup_and_eliminate_zeros(
+    pub fn de_dup_and_eliminate_zeros(
         sorted_data_by_pubkey: Vec<Vec<Vec<CalculateHashIntermediate>>>,
         stats: &mut HashStats,
         max_bin: usize,
@@ -676,12 +678,29 @@ impl AccountsHash {
         data_sections_by_pubkey: Vec<Vec<Vec<CalculateHashIntermediate>>>,
         mut stats: &mut HashStats,
         is_last_pass: bool,
-        mut previous_state: PreviousPass,
+        previous_state: PreviousPass,
         max_bin: usize,
     ) -> (Hash, u64, PreviousPass) {
-        let (mut hashes, mut total_lamports) =
+        let (hashes, total_lamports) =
             Self::de_dup_and_eliminate_zeros(data_sections_by_pubkey, &mut stats, max_bin);
+        Self::rest_of_hash_calculation_from_hashes(
+            hashes,
+            total_lamports,
+            stats,
+            is_last_pass,
+            previous_state,
+        )
+    }
 
+    /// The part of `rest_of_hash_calculation` after de-duping: `hashes` are the pass's account
+    /// hashes in pubkey order and `total_lamports` is their lamports
+    pub fn rest_of_hash_calculation_from_hashes(
+        mut hashes: Vec<Vec<Hash>>,
+        mut total_lamports: u64,
+        stats: &mut HashStats,
+        is_last_pass: bool,
+        mut previous_state: PreviousPass,
+    ) -> (Hash, u64, PreviousPass) {
         total_lamports += previous_state.lamports;
 
         if !previous_state.remaining_unhashed.is_empty() {
@@ -780,6 +799,95 @@ impl AccountsHash {
     }
 }
 
+/// The whole Merkle tree of an accounts hash, kept so that account proofs can be served without
+/// rescanning the accounts
+#[derive(Debug, Default)]
+pub struct AccountsMerkleTree {
+    /// The slot whose accounts hash this is
+    pub slot: Slot,
+    fanout: usize,
+    /// Pubkeys and the slots they were last written in, sorted by pubkey
+    leaves: Vec<(Pubkey, Slot)>,
+    /// The nodes of each level, from the account hashes up to the root
+    levels: Vec<Vec<Hash>>,
+}
+
+impl AccountsMerkleTree {
+    /// The tree over the accounts of `slot`, given as `(pubkey, write slot, account hash)` and
+    /// sorted by pubkey
+    pub fn new(slot: Slot, accounts: Vec<(Pubkey, Slot, Hash)>, fanout: usize) -> Self {
+        let (leaves, mut hashes): (Vec<_>, Vec<_>) = accounts
+            .into_iter()
+            .map(|(pubkey, write_slot, hash)| ((pubkey, write_slot), hash))
+            .unzip();
+        let mut levels = vec![];
+        // Like `compute_merkle_root_loop()`, hash at least once, even a single account
+        while !hashes.is_empty() {
+            let next: Vec<_> = hashes
+                .par_chunks(fanout)
+                .map(|chunk| {
+                    let mut hasher = Hasher::default();
+                    for hash in chunk {
+                        hasher.hash(hash.as_ref());
+                    }
+                    hasher.result()
+                })
+                .collect();
+            levels.push(hashes);
+            if next.len() == 1 {
+                levels.push(next);
+                break;
+            }
+            hashes = next;
+        }
+        Self {
+            slot,
+            fanout,
+            leaves,
+            levels,
+        }
+    }
+
+    /// The accounts hash at the root of the tree
+    pub fn root(&self) -> Hash {
+        self.levels
+            .last()
+            .map(|root| root[0])
+            .unwrap_or_else(|| Hasher::default().result())
+    }
+
+    /// A proof that `pubkey` is included in the accounts hash, or None if it has no lamports
+    pub fn get_account_proof(&self, pubkey: &Pubkey) -> Option<AccountProof> {
+        let mut index = self
+            .leaves
+            .binary_search_by_key(pubkey, |(key, _slot)| *key)
+            .ok()?;
+        let (_key, write_slot) = self.leaves[index];
+        let account_hash = self.levels[0][index];
+        let levels = self.levels[..self.levels.len() - 1]
+            .iter()
+            .map(|hashes| {
+                let start = index / self.fanout * self.fanout;
+                let end = std::cmp::min(start + self.fanout, hashes.len());
+                let mut siblings = hashes[start..end].to_vec();
+                siblings.remove(index - start);
+                let level = MerkleProofLevel {
+                    index: index - start,
+                    siblings,
+                };
+                index /= self.fanout;
+                level
+            })
+            .collect();
+        Some(AccountProof {
+            pubkey: *pubkey,
+            slot: write_slot,
+            account_hash,
+            levels,
+        })
+    }
+}
+
 #[cfg(test)]
 pub mod tests {
     use super::*;
@@ -1691,6 +1799,36 @@ pub mod tests {
         result
     }
 
+    #[test]
+    fn test_accounts_merkle_tree() {
+        for count in [1, 2, 3, 16, 17, 256, 300] {
+            let mut accounts: Vec<_> = (0..count)
+                .map(|i| (Pubkey::new_unique(), i as Slot, Hash::new_unique()))
+                .collect();
+            accounts.sort_by_key(|(pubkey, _slot, _hash)| *pubkey);
+            let hashes = accounts
+                .iter()
+                .map(|(_pubkey, _slot, hash)| *hash)
+                .collect();
+            let root = AccountsHash::compute_merkle_root_recurse(hashes, MERKLE_FANOUT);
+            let tree = AccountsMerkleTree::new(7, accounts.clone(), MERKLE_FANOUT);
+            assert_eq!(tree.root(), root, "count: {}", count);
+            for index in [0, count / 2, count - 1] {
+                let (pubkey, slot, hash) = accounts[index];
+                let proof = tree.get_account_proof(&pubkey).unwrap();
+                assert_eq!(proof.slot, slot);
+                assert_eq!(proof.account_hash, hash);
+                assert!(proof.verify(&root), "count: {}, index: {}", count, index);
+            }
+            assert!(tree.get_account_proof(&Pubkey::new_unique()).is_none());
+        }
+        let tree = AccountsMerkleTree::new(7, vec![], MERKLE_FANOUT);
+        assert_eq!(
+            tree.root(),
+            AccountsHash::compute_merkle_root_recurse(vec![], MERKLE_FANOUT)
+        );
+    }
+
     #[test]
     fn test_accountsdb_compute_merkle_root_large() {
         solana_logger::setup();
@@ -0,0 +1,154 @@
+//! Results of the finished passes of an accounts hash calculation, kept on disk so that a
+//! calculation over the same storages which was interrupted resumes where it left off.
+
+use {
+    crate::sorted_storages::SortedStorages,
+    log::*,
+    solana_sdk::hash::{Hash, Hasher},
+    std::{
+        fs, io,
+        path::{Path, PathBuf},
+        str::FromStr,
+    },
+};
+
+/// Number of scan passes used when results are cached. Each finished pass is a point to resume
+/// from, at the cost of scanning the storages once more per pass.
+pub const CACHED_NUM_SCAN_PASSES: usize = 8;
+
+#[derive(Debug, Clone)]
+pub struct AccountsHashCache {
+    dir: PathBuf,
+}
+
+impl AccountsHashCache {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Identifies a calculation by the storages it scans and how it scans them
+    pub fn key(storages: &SortedStorages, check_hash: bool, num_scan_passes: usize) -> Hash {
+        let mut hasher = Hasher::default();
+        hasher.hash(&[check_hash as u8]);
+        hasher.hash(&(num_scan_passes as u64).to_le_bytes());
+        for slot in storages.range().clone() {
+            if let Some(storage) = storages.get(slot) {
+                hasher.hash(&slot.to_le_bytes());
+                for entry in storage {
+                    hasher.hash(&(entry.append_vec_id() as u64).to_le_bytes());
+                    hasher.hash(&entry.written_bytes().to_le_bytes());
+                }
+            }
+        }
+        hasher.result()
+    }
+
+    /// Readies the cache for the calculation `key`, dropping results of any other calculation.
+    /// Only directories named after a calculation key are removed, as the cache may share its
+    /// directory with other files.
+    pub fn prepare(&self, key: &Hash) -> io::Result<()> {
+        let key_dir = self.key_dir(key);
+        if self.dir.is_dir() {
+            for entry in fs::read_dir(&self.dir)? {
+                let entry = entry?;
+                let is_key_dir = entry.file_type()?.is_dir()
+                    && entry
+                        .file_name()
+                        .to_str()
+                        .and_then(|name| Hash::from_str(name).ok())
+                        .is_some();
+                let path = entry.path();
+                if is_key_dir && path != key_dir {
+                    info!("removing stale accounts hash cache {:?}", path);
+                    fs::remove_dir_all(&path)?;
+                }
+            }
+        }
+        fs::create_dir_all(&key_dir)
+    }
+
+    /// The account hashes, in pubkey order, and lamports found by `pass`, if it finished before
+    pub fn load(&self, key: &Hash, pass: usize) -> Option<(Vec<Vec<Hash>>, u64)> {
+        let path = self.pass_path(key, pass);
+        let data = fs::read(&path).ok()?;
+        bincode::deserialize(&data)
+            .map_err(|err| {
+                warn!(
+                    "ignoring unreadable accounts hash cache {:?}: {}",
+                    path, err
+                )
+            })
+            .ok()
+    }
+
+    pub fn store(
+        &self,
+        key: &Hash,
+        pass: usize,
+        hashes: &[Vec<Hash>],
+        lamports: u64,
+    ) -> io::Result<()> {
+        let path = self.pass_path(key, pass);
+        let tmp_path = path.with_extension("tmp");
+        let data = bincode::serialize(&(hashes, lamports))
+            .map_err(|err| io::Error::new(io::ErrorKind::Other, err))?;
+        fs::write(&tmp_path, data)?;
+        fs::rename(&tmp_path, &path)
+    }
+
+    /// Drops the results of the calculation `key` once it has finished
+    pub fn remove(&self, key: &Hash) -> io::Result<()> {
+        fs::remove_dir_all(self.key_dir(key))
+    }
+
+    fn key_dir(&self, key: &Hash) -> PathBuf {
+        self.dir.join(key.to_string())
+    }
+
+    fn pass_path(&self, key: &Hash, pass: usize) -> PathBuf {
+        self.key_dir(key).join(format!("pass-{}", pass))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_accounts_hash_cache() {
+        let tmp_dir = tempfile::TempDir::new().unwrap();
+        let cache = AccountsHashCache::new(tmp_dir.path().join("cache"));
+        let key = Hash::new_unique();
+        let unrelated_dir = cache.dir().join("unrelated");
+        let unrelated_file = cache.dir().join(Hash::new_unique().to_string());
+        fs::create_dir_all(&unrelated_dir).unwrap();
+        fs::write(&unrelated_file, b"unrelated").unwrap();
+        let other_key = Hash::new_unique();
+        let hashes = vec![vec![Hash::new_unique(), Hash::new_unique()], vec![]];
+
+        cache.prepare(&other_key).unwrap();
+        cache.store(&other_key, 0, &hashes, 3).unwrap();
+        assert_eq!(cache.load(&other_key, 0), Some((hashes.clone(), 3)));
+
+        cache.prepare(&key).unwrap();
+        assert_eq!(cache.load(&other_key, 0), None);
+        assert_eq!(cache.load(&key, 0), None);
+        cache.store(&key, 1, &hashes, 5).unwrap();
+        assert_eq!(cache.load(&key, 0), None);
+        assert_eq!(cache.load(&key, 1), Some((hashes, 5)));
+
+        fs::write(cache.pass_path(&key, 2), b"garbage").unwrap();
+        assert_eq!(cache.load(&key, 2), None);
+
+        cache.remove(&key).unwrap();
+        assert_eq!(cache.load(&key, 1), None);
+
+        // Entries of the directory that are not calculation results are left alone
+        assert!(unrelated_dir.is_dir());
+        assert!(unrelated_file.is_file());
+    }
+}
@@ -1,6 +1,8 @@
 use crate::{
     ancestors::Ancestors,
+    bucket_map_holder::{Age, BucketMapFlusher, BucketMapHolder},
     contains::Contains,
+    hybrid_btree_map::HybridBTreeMap,
     inline_spl_token_v2_0::{self, SPL_TOKEN_ACCOUNT_MINT_OFFSET, SPL_TOKEN_ACCOUNT_OWNER_OFFSET},
     pubkey_bins::PubkeyBinCalculator16,
     secondary_index::*,
@@ -8,6 +10,7 @@ use crate::{
 use bv::BitVec;
 use log::*;
 use ouroboros::self_referencing;
+use serde::{de::DeserializeOwned, Serialize};
 use solana_measure::measure::Measure;
 use solana_sdk::{
     clock::{BankId, Slot},
@@ -15,7 +18,7 @@ use solana_sdk::{
 };
 use std::{
     collections::{
-        btree_map::{self, BTreeMap, Entry},
+        btree_map::{BTreeMap, Entry},
         HashSet,
     },
     fmt::Debug,
@@ -24,8 +27,9 @@ use std::{
         Bound::{Excluded, Included, Unbounded},
         Range, RangeBounds,
     },
+    path::PathBuf,
     sync::{
-        atomic::{AtomicU64, Ordering},
+        atomic::{AtomicBool, AtomicU64, AtomicU8, Ordering},
         Arc, Mutex, RwLock, RwLockReadGuard, RwLockWriteGuard,
     },
 };
@@ -35,9 +39,13 @@ pub const ITER_BATCH_SIZE: usize = 1000;
 pub const BINS_DEFAULT: usize = 16;
 pub const ACCOUNTS_INDEX_CONFIG_FOR_TESTING: AccountsIndexConfig = AccountsIndexConfig {
     bins: Some(BINS_DEFAULT),
+    index_limit_mb: None,
+    drives: None,
 };
 pub const ACCOUNTS_INDEX_CONFIG_FOR_BENCHMARKS: AccountsIndexConfig = AccountsIndexConfig {
     bins: Some(BINS_DEFAULT),
+    index_limit_mb: None,
+    drives: None,
 };
 pub type ScanResult<T> = Result<T, ScanError>;
 pub type SlotList<T> = Vec<(Slot, T)>;
@@ -45,9 +53,11 @@ pub type SlotSlice<'s, T> = &'s [(Slot, T)];
 pub type RefCount = u64;
 pub type AccountMap<K, V> = BTreeMap<K, V>;
 
-type AccountMapEntry<T> = Arc<AccountMapEntryInner<T>>;
+pub(crate) type AccountMapEntry<T> = Arc<AccountMapEntryInner<T>>;
 
-pub trait IsCached: 'static + Clone + Debug + PartialEq + ZeroLamport {
+pub trait IsCached:
+    'static + Clone + Debug + PartialEq + ZeroLamport + Serialize + DeserializeOwned + Send + Sync
+{
     fn is_cached(&self) -> bool;
 }
 
@@ -82,9 +92,20 @@ pub struct AccountSecondaryIndexesIncludeExclude {
     pub keys: HashSet<Pubkey>,
 }
 
-#[derive(Debug, Default, Clone, Copy)]
+#[derive(Debug, Default, Clone)]
 pub struct AccountsIndexConfig {
     pub bins: Option<usize>,
+    /// Memory budget of the index. Entries beyond it are kept on disk.
+    pub index_limit_mb: Option<usize>,
+    /// Directories for the on-disk part of the index
+    pub drives: Option<Vec<PathBuf>>,
+}
+
+impl AccountsIndexConfig {
+    /// Whether entries may be kept on disk rather than all in memory
+    pub fn is_disk_backed(&self) -> bool {
+        self.index_limit_mb.is_some() || self.drives.is_some()
+    }
 }
 
 #[derive(Debug, Default, Clone)]
@@ -108,16 +129,58 @@ impl AccountSecondaryIndexes {
     }
 }
 
+/// Bookkeeping of a disk backed index
+#[derive(Debug, Default)]
+pub struct AccountMapEntryMeta {
+    /// Changed since last written to disk
+    pub dirty: AtomicBool,
+    /// Written to disk at least once
+    pub on_disk: AtomicBool,
+    /// Age of the index when the entry was last accessed
+    pub age: AtomicU8,
+}
+
+impl AccountMapEntryMeta {
+    pub fn new_dirty() -> Self {
+        Self {
+            dirty: AtomicBool::new(true),
+            ..Self::default()
+        }
+    }
+
+    /// An entry just read from disk
+    pub fn new_clean(age: Age) -> Self {
+        Self {
+            dirty: AtomicBool::new(false),
+            on_disk: AtomicBool::new(true),
+            age: AtomicU8::new(age),
+        }
+    }
+}
+
 #[derive(Debug)]
 pub struct AccountMapEntryInner<T> {
     ref_count: AtomicU64,
     pub slot_list: RwLock<SlotList<T>>,
+    pub meta: AccountMapEntryMeta,
 }
 
 impl<T> AccountMapEntryInner<T> {
+    pub fn new(slot_list: SlotList<T>, ref_count: RefCount, meta: AccountMapEntryMeta) -> Self {
+        Self {
+            ref_count: AtomicU64::new(ref_count),
+            slot_list: RwLock::new(slot_list),
+            meta,
+        }
+    }
+
     pub fn ref_count(&self) -> u64 {
         self.ref_count.load(Ordering::Relaxed)
     }
+
+    pub fn set_dirty(&self) {
+        self.meta.dirty.store(true, Ordering::Relaxed);
+    }
 }
 
 pub enum AccountIndexGetResult<'a, T: 'static> {
@@ -152,15 +215,15 @@ impl<T: Clone> ReadAccountMapEntry<T> {
     }
 
     pub fn unref(&self) {
-        self.borrow_owned_entry()
-            .ref_count
-            .fetch_sub(1, Ordering::Relaxed);
+        let entry = self.borrow_owned_entry();
+        entry.set_dirty();
+        entry.ref_count.fetch_sub(1, Ordering::Relaxed);
     }
 
     pub fn addref(&self) {
-        self.borrow_owned_entry()
-            .ref_count
-            .fetch_add(1, Ordering::Relaxed);
+        let entry = self.borrow_owned_entry();
+        entry.set_dirty();
+        entry.ref_count.fetch_add(1, Ordering::Relaxed);
     }
 }
 
@@ -174,6 +237,8 @@ pub struct WriteAccountMapEntry<T: 'static> {
 
 impl<T: IsCached> WriteAccountMapEntry<T> {
     pub fn from_account_map_entry(account_map_entry: AccountMapEntry<T>) -> Self {
+        // the slot list may change through the returned entry
+        account_map_entry.set_dirty();
         WriteAccountMapEntryBuilder {
             owned_entry: account_map_entry,
             slot_list_guard_builder: |lock| lock.slot_list.write().unwrap(),
@@ -202,10 +267,11 @@ impl<T: IsCached> WriteAccountMapEntry<T> {
     // This code is called when the first entry [ie. (slot,account_info)] for a pubkey is inserted into the index.
     pub fn new_entry_after_update(slot: Slot, account_info: T) -> AccountMapEntry<T> {
         let ref_count = if account_info.is_cached() { 0 } else { 1 };
-        Arc::new(AccountMapEntryInner {
-            ref_count: AtomicU64::new(ref_count),
-            slot_list: RwLock::new(vec![(slot, account_info)]),
-        })
+        Arc::new(AccountMapEntryInner::new(
+            vec![(slot, account_info)],
+            ref_count,
+            AccountMapEntryMeta::new_dirty(),
+        ))
     }
 
     fn addref(item: &AtomicU64) {
@@ -263,6 +329,7 @@ impl<T: IsCached> WriteAccountMapEntry<T> {
         reclaims: &mut SlotList<T>,
         previous_slot_entry_was_cached: bool,
     ) {
+        current.set_dirty();
         let mut slot_list = current.slot_list.write().unwrap();
         let (slot, new_entry) = new_value.slot_list.write().unwrap().remove(0);
         let addref = Self::update_slot_list(
@@ -690,7 +757,7 @@ impl<'a, T> AccountsIndexIterator<'a, T> {
     }
 }
 
-impl<'a, T: 'static + Clone> Iterator for AccountsIndexIterator<'a, T> {
+impl<'a, T: IsCached> Iterator for AccountsIndexIterator<'a, T> {
     type Item = Vec<(Pubkey, AccountMapEntry<T>)>;
     fn next(&mut self) -> Option<Self::Item> {
         if self.is_finished {
@@ -698,16 +765,14 @@ impl<'a, T: 'static + Clone> Iterator for AccountsIndexIterator<'a, T> {
         }
         let (start_bin, bin_range) = self.bin_start_and_range();
         let mut chunk: Vec<(Pubkey, AccountMapEntry<T>)> = Vec::with_capacity(ITER_BATCH_SIZE);
-        'outer: for i in self.account_maps.iter().skip(start_bin).take(bin_range) {
-            for (pubkey, account_map_entry) in
-                i.read().unwrap().range((self.start_bound, self.end_bound))
-            {
-                if chunk.len() >= ITER_BATCH_SIZE {
-                    break 'outer;
-                }
-                let item = (*pubkey, account_map_entry.clone());
-                chunk.push(item);
+        for i in self.account_maps.iter().skip(start_bin).take(bin_range) {
+            if chunk.len() >= ITER_BATCH_SIZE {
+                break;
             }
+            chunk.extend(i.read().unwrap().range(
+                (self.start_bound, self.end_bound),
+                ITER_BATCH_SIZE - chunk.len(),
+            ));
         }
 
         if chunk.is_empty() {
@@ -724,9 +789,9 @@ pub trait ZeroLamport {
     fn is_zero_lamport(&self) -> bool;
 }
 
-type MapType<T> = AccountMap<Pubkey, AccountMapEntry<T>>;
-type LockMapType<T> = Vec<RwLock<MapType<T>>>;
-type LockMapTypeSlice<T> = [RwLock<MapType<T>>];
+type MapType<T> = HybridBTreeMap<T>;
+type LockMapType<T> = Vec<Arc<RwLock<MapType<T>>>>;
+type LockMapTypeSlice<T> = [Arc<RwLock<MapType<T>>>];
 type AccountMapsWriteLock<'a, T> = RwLockWriteGuard<'a, MapType<T>>;
 type AccountMapsReadLock<'a, T> = RwLockReadGuard<'a, MapType<T>>;
 
@@ -766,6 +831,8 @@ pub struct AccountsIndex<T> {
     // on any of these slots fails. This is safe to purge once the associated Bank is dropped and
     // scanning the fork with that Bank at the tip is no longer possible.
     pub removed_bank_ids: Mutex<HashSet<BankId>>,
+    // Writes changed entries of a disk backed index to disk and evicts idle ones from memory
+    disk_flusher: Option<BucketMapFlusher>,
 }
 
 impl<T: IsCached> AccountsIndex<T> {
@@ -774,7 +841,7 @@ impl<T: IsCached> AccountsIndex<T> {
     }
 
     pub fn new(config: Option<AccountsIndexConfig>) -> Self {
-        let (account_maps, bin_calculator) = Self::allocate_accounts_index(config);
+        let (account_maps, bin_calculator, disk_flusher) = Self::allocate_accounts_index(config);
         Self {
             account_maps,
             bin_calculator,
@@ -790,21 +857,36 @@ impl<T: IsCached> AccountsIndex<T> {
             roots_tracker: RwLock::<RootsTracker>::default(),
             ongoing_scan_roots: RwLock::<BTreeMap<Slot, u64>>::default(),
             removed_bank_ids: Mutex::<HashSet<BankId>>::default(),
+            disk_flusher,
         }
     }
 
     fn allocate_accounts_index(
         config: Option<AccountsIndexConfig>,
-    ) -> (LockMapType<T>, PubkeyBinCalculator16) {
+    ) -> (
+        LockMapType<T>,
+        PubkeyBinCalculator16,
+        Option<BucketMapFlusher>,
+    ) {
         let bins = config
+            .as_ref()
             .and_then(|config| config.bins)
             .unwrap_or(BINS_DEFAULT);
+        let storage = config
+            .filter(|config| config.is_disk_backed())
+            .map(|config| Arc::new(BucketMapHolder::new(bins, &config)));
         let account_maps = (0..bins)
             .into_iter()
-            .map(|_| RwLock::new(AccountMap::default()))
+            .map(|bin| Arc::new(RwLock::new(HybridBTreeMap::new(storage.clone(), bin))))
             .collect::<Vec<_>>();
         let bin_calculator = PubkeyBinCalculator16::new(bins);
-        (account_maps, bin_calculator)
+        let disk_flusher =
+            storage.map(|storage| BucketMapFlusher::new(storage, account_maps.clone()));
+        (account_maps, bin_calculator, disk_flusher)
+    }
+
+    pub fn is_disk_backed(&self) -> bool {
+        self.disk_flusher.is_some()
     }
 
     fn iter<R>(&self, range: Option<R>) -> AccountsIndexIterator<T>
@@ -1146,9 +1228,7 @@ impl<T: IsCached> AccountsIndex<T> {
     }
 
     fn get_account_write_entry(&self, pubkey: &Pubkey) -> Option<WriteAccountMapEntry<T>> {
-        self.account_maps[self.bin_calculator.bin_from_pubkey(pubkey)]
-            .read()
-            .unwrap()
+        self.get_account_maps_read_lock(pubkey)
             .get(pubkey)
             .cloned()
             .map(WriteAccountMapEntry::from_account_map_entry)
@@ -1185,9 +1265,9 @@ impl<T: IsCached> AccountsIndex<T> {
         if !dead_keys.is_empty() {
             for key in dead_keys.iter() {
                 let mut w_index = self.get_account_maps_write_lock(key);
-                if let btree_map::Entry::Occupied(index_entry) = w_index.entry(**key) {
-                    if index_entry.get().slot_list.read().unwrap().is_empty() {
-                        index_entry.remove();
+                if let Some(index_entry) = w_index.page_in(key) {
+                    if index_entry.slot_list.read().unwrap().is_empty() {
+                        w_index.remove(key);
 
                         // Note it's only safe to remove all the entries for this key
                         // because we have the lock for this key's entry in the AccountsIndex,
@@ -1373,9 +1453,7 @@ impl<T: IsCached> AccountsIndex<T> {
         ancestors: Option<&Ancestors>,
         max_root: Option<Slot>,
     ) -> AccountIndexGetResult<'_, T> {
-        let read_lock = self.account_maps[self.bin_calculator.bin_from_pubkey(pubkey)]
-            .read()
-            .unwrap();
+        let read_lock = self.get_account_maps_read_lock(pubkey);
         let account = read_lock
             .get(pubkey)
             .cloned()
@@ -1475,10 +1553,18 @@ impl<T: IsCached> AccountsIndex<T> {
             .unwrap()
     }
 
+    /// The read lock of the bin of `pubkey`, with the entry of `pubkey` brought into memory
     pub(crate) fn get_account_maps_read_lock(&self, pubkey: &Pubkey) -> AccountMapsReadLock<T> {
-        self.account_maps[self.bin_calculator.bin_from_pubkey(pubkey)]
-            .read()
-            .unwrap()
+        let map = &self.account_maps[self.bin_calculator.bin_from_pubkey(pubkey)];
+        loop {
+            let read_lock = map.read().unwrap();
+            if !read_lock.needs_page_in(pubkey) {
+                return read_lock;
+            }
+            drop(read_lock);
+            // it may be evicted again before the read lock is taken, so check again
+            map.write().unwrap().page_in(pubkey);
+        }
     }
 
     pub fn bins(&self) -> usize {
@@ -1508,10 +1594,8 @@ impl<T: IsCached> AccountsIndex<T> {
             .collect::<Vec<_>>();
         items.for_each(|(pubkey, account_info)| {
             let bin = self.bin_calculator.bin_from_pubkey(&pubkey);
-            // this value is equivalent to what update() below would have created if we inserted a new item
             let is_zero_lamport = account_info.is_zero_lamport();
-            let info = WriteAccountMapEntry::new_entry_after_update(slot, account_info);
-            binned[bin].1.push((pubkey, info, is_zero_lamport));
+            binned[bin].1.push((pubkey, account_info, is_zero_lamport));
         });
         binned.retain(|x| !x.1.is_empty());
 
@@ -1527,9 +1611,27 @@ impl<T: IsCached> AccountsIndex<T> {
                 let mut dirty_pubkeys = Vec::with_capacity(items.len() / 10);
                 let mut w_account_maps = self.account_maps[pubkey_bin].write().unwrap();
                 let mut insert_time = Measure::start("insert_into_primary_index");
+                if w_account_maps.is_disk_backed() {
+                    // build the index straight on disk, the flusher brings in what gets used
+                    items
+                        .into_iter()
+                        .for_each(|(pubkey, account_info, is_zero_lamport)| {
+                            if w_account_maps.insert_on_disk(pubkey, slot, account_info)
+                                || is_zero_lamport
+                            {
+                                dirty_pubkeys.push(pubkey);
+                            }
+                        });
+                    insert_time.stop();
+                    insertion_time.fetch_add(insert_time.as_us(), Ordering::Relaxed);
+                    return dirty_pubkeys;
+                }
                 items
                     .into_iter()
-                    .for_each(|(pubkey, new_item, is_zero_lamport)| {
+                    .for_each(|(pubkey, account_info, is_zero_lamport)| {
+                        // this value is equivalent to what update() below would have created if we inserted a new item
+                        let new_item =
+                            WriteAccountMapEntry::new_entry_after_update(slot, account_info);
                         let already_exists = self.insert_new_entry_if_missing_with_lock(
                             pubkey,
                             &mut w_account_maps,
@@ -1580,7 +1682,7 @@ impl<T: IsCached> AccountsIndex<T> {
         let new_item = WriteAccountMapEntry::new_entry_after_update(slot, account_info);
         let map = &self.account_maps[self.bin_calculator.bin_from_pubkey(pubkey)];
 
-        let r_account_maps = map.read().unwrap();
+        let r_account_maps = self.get_account_maps_read_lock(pubkey);
         if !WriteAccountMapEntry::update_key_if_exists(
             r_account_maps,
             pubkey,
@@ -1674,7 +1776,7 @@ impl<T: IsCached> AccountsIndex<T> {
         // remove() below.
         if is_slot_list_empty {
             let mut w_maps = self.get_account_maps_write_lock(pubkey);
-            if let Some(x) = w_maps.get(pubkey) {
+            if let Some(x) = w_maps.page_in(pubkey) {
                 if x.slot_list.read().unwrap().is_empty() {
                     w_maps.remove(pubkey);
                 }
@@ -4037,6 +4139,102 @@ pub mod tests {
     #[test]
     #[should_panic(expected = "bins.is_power_of_two()")]
     fn test_illegal_bins() {
-        AccountsIndex::<bool>::new(Some(AccountsIndexConfig { bins: Some(3) }));
+        AccountsIndex::<bool>::new(Some(AccountsIndexConfig {
+            bins: Some(3),
+            ..AccountsIndexConfig::default()
+        }));
+    }
+
+    #[test]
+    fn test_disk_backed_index() {
+        let index = AccountsIndex::<u64>::new(Some(AccountsIndexConfig {
+            bins: Some(2),
+            index_limit_mb: Some(1),
+            drives: None,
+        }));
+        assert!(index.is_disk_backed());
+        let age_out = || {
+            let map = index.account_maps[0].read().unwrap();
+            let holder = map.storage().unwrap().clone();
+            drop(map);
+            for _ in 0..=crate::bucket_map_holder::AGES_TO_STAY_IN_CACHE {
+                holder.increment_age();
+                index
+                    .account_maps
+                    .iter()
+                    .for_each(|map| map.write().unwrap().flush());
+            }
+        };
+        let in_mem_len = || {
+            index
+                .account_maps
+                .iter()
+                .map(|map| map.read().unwrap().in_mem_len())
+                .sum::<usize>()
+        };
+
+        // built at startup
+        let slot = 1;
+        let mut keys: Vec<_> = (0..20).map(|_| solana_sdk::pubkey::new_rand()).collect();
+        keys.sort();
+        let (dirty_pubkeys, _) = index.insert_new_if_missing_into_primary_index(
+            slot,
+            keys.len(),
+            keys.iter().map(|key| (*key, slot)),
+        );
+        assert!(dirty_pubkeys.is_empty());
+        index.add_root(slot, false);
+        assert_eq!(in_mem_len(), 0);
+
+        // paged in on access
+        let mut reclaims = vec![];
+        index.upsert(
+            slot + 1,
+            &keys[0],
+            &Pubkey::default(),
+            &[],
+            &AccountSecondaryIndexes::default(),
+            slot + 1,
+            &mut reclaims,
+            false,
+        );
+        index.add_root(slot + 1, false);
+        assert_eq!(in_mem_len(), 1);
+        age_out();
+        assert_eq!(in_mem_len(), 0);
+
+        let (entry, found_index) = index.get(&keys[0], None, None).unwrap();
+        assert_eq!(entry.slot_list(), &vec![(slot, slot), (slot + 1, slot + 1)]);
+        assert_eq!(found_index, 1);
+        assert_eq!(entry.ref_count(), 2);
+        drop(entry);
+        assert_eq!(in_mem_len(), 1);
+
+        // scans see entries in memory and on disk
+        let scanned: Vec<_> = index
+            .iter(None::<Range<Pubkey>>)
+            .flatten()
+            .map(|(pubkey, _)| pubkey)
+            .collect();
+        assert_eq!(scanned, keys);
+        assert_eq!(in_mem_len(), 1);
+
+        // purged entries are removed from disk too
+        let slots_to_purge = vec![slot].into_iter().collect::<HashSet<Slot>>();
+        index.purge_exact(&keys[1], &slots_to_purge, &mut reclaims);
+        index.handle_dead_keys(&[&keys[1]], &AccountSecondaryIndexes::default());
+        age_out();
+        assert!(matches!(
+            index.get(&keys[1], None, None),
+            AccountIndexGetResult::Missing(_)
+        ));
+        assert_eq!(
+            index
+                .account_maps
+                .iter()
+                .map(|map| map.read().unwrap().len())
+                .sum::<usize>(),
+            keys.len() - 1
+        );
     }
 }
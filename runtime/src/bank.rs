@@ -34,11 +34,15 @@
 //! on behalf of the caller, and a low-level API for when they have
 //! already been signed and verified.
 use crate::{
+    account_write_log::{
+        AccountWriteRecorder, AccountWritesCollector, OptionalAccountWriteRecorder,
+    },
     accounts::{
         AccountAddressFilter, Accounts, TransactionAccounts, TransactionLoadResult,
         TransactionLoaders,
     },
-    accounts_db::{AccountShrinkThreshold, ErrorCounters, SnapshotStorages},
+    accounts_db::{AccountShrinkThreshold, ErrorCounters, SnapshotStorages, StorageReport},
+    accounts_hash::AccountsMerkleTree,
     accounts_index::{
         AccountSecondaryIndexes, AccountsIndexConfig, IndexKey, ScanResult,
         ACCOUNTS_INDEX_CONFIG_FOR_BENCHMARKS, ACCOUNTS_INDEX_CONFIG_FOR_TESTING,
@@ -46,12 +50,13 @@ use crate::{
     ancestors::{Ancestors, AncestorsForSerialization},
     blockhash_queue::BlockhashQueue,
     builtins::{self, ActivationType},
+    compute_profiler::{ComputeProfile, ThisComputeProfiler},
     epoch_stakes::{EpochStakes, NodeVoteAccounts},
     inline_spl_token_v2_0,
     instruction_recorder::InstructionRecorder,
     log_collector::LogCollector,
     message_processor::{ExecuteDetailsTimings, Executors, MessageProcessor},
-    rent_collector::RentCollector,
+    rent_collector::{RentCollector, RentPayingAccount},
     stake_weighted_timestamp::{
         calculate_stake_weighted_timestamp, MaxAllowableDrift, MAX_ALLOWABLE_DRIFT_PERCENTAGE,
         MAX_ALLOWABLE_DRIFT_PERCENTAGE_FAST, MAX_ALLOWABLE_DRIFT_PERCENTAGE_SLOW,
@@ -107,7 +112,7 @@ use solana_sdk::{
     slot_history::SlotHistory,
     stake::{self, state::Delegation},
     system_transaction,
-    sysvar::{self},
+    sysvar::{self, Sysvar},
     timing::years_as_slots,
     transaction::{
         Result, SanitizedTransaction, Transaction, TransactionError, VersionedTransaction,
@@ -130,7 +135,7 @@ use std::{
     rc::Rc,
     sync::{
         atomic::{AtomicBool, AtomicU64, Ordering::Relaxed},
-        LockResult, RwLockWriteGuard, {Arc, RwLock, RwLockReadGuard},
+        LockResult, Mutex, RwLockWriteGuard, {Arc, RwLock, RwLockReadGuard},
     },
     time::Duration,
     time::Instant,
@@ -1038,6 +1043,13 @@ pub struct Bank {
     // `transaction_log_collector_config`
     pub transaction_log_collector: Arc<RwLock<TransactionLogCollector>>,
 
+    // Compute profile of the transactions executed by this Bank, shared with descendant banks
+    compute_profile: RwLock<Option<Arc<Mutex<ComputeProfile>>>>,
+
+    // Receives the accounts written by each transaction committed by this Bank, shared with
+    // descendant banks
+    account_write_recorder: RwLock<OptionalAccountWriteRecorder>,
+
     pub feature_set: Arc<FeatureSet>,
 
     pub drop_callback: RwLock<OptionalDropCallback>,
@@ -1166,6 +1178,8 @@ impl Bank {
             transaction_log_collector_config: Arc::<RwLock<TransactionLogCollectorConfig>>::default(
             ),
             transaction_log_collector: Arc::<RwLock<TransactionLogCollector>>::default(),
+            compute_profile: RwLock::<Option<Arc<Mutex<ComputeProfile>>>>::default(),
+            account_write_recorder: RwLock::<OptionalAccountWriteRecorder>::default(),
             feature_set: Arc::<FeatureSet>::default(),
             drop_callback: RwLock::<OptionalDropCallback>::default(),
             freeze_started: AtomicBool::default(),
@@ -1377,6 +1391,10 @@ impl Bank {
             transaction_debug_keys: parent.transaction_debug_keys.clone(),
             transaction_log_collector_config: parent.transaction_log_collector_config.clone(),
             transaction_log_collector: Arc::new(RwLock::new(TransactionLogCollector::default())),
+            compute_profile: RwLock::new(parent.compute_profile()),
+            account_write_recorder: RwLock::new(OptionalAccountWriteRecorder(
+                parent.account_write_recorder(),
+            )),
             feature_set: parent.feature_set.clone(),
             drop_callback: RwLock::new(OptionalDropCallback(
                 parent
@@ -1424,6 +1442,9 @@ impl Bank {
         new.update_stake_history(Some(parent_epoch));
         new.update_clock(Some(parent_epoch));
         new.update_fees();
+        if let Some(recorder) = new.account_write_recorder() {
+            recorder.begin_slot(slot);
+        }
         new
     }
 
@@ -1532,6 +1553,8 @@ impl Bank {
             transaction_debug_keys: debug_keys,
             transaction_log_collector_config: new(),
             transaction_log_collector: new(),
+            compute_profile: new(),
+            account_write_recorder: new(),
             feature_set: new(),
             drop_callback: RwLock::new(OptionalDropCallback(None)),
             freeze_started: AtomicBool::new(fields.hash != Hash::default()),
@@ -1741,6 +1764,16 @@ impl Bank {
         (rooted_slot + (unused - 1)) / unused
     }
 
+    /// Overwrite a sysvar account, for tests that need to control its value
+    pub fn set_sysvar_for_tests<T: Sysvar>(&self, sysvar: &T) {
+        self.update_sysvar_account(&T::id(), |account| {
+            create_account(
+                sysvar,
+                self.inherit_specially_retained_account_fields(account),
+            )
+        });
+    }
+
     pub fn clock(&self) -> sysvar::clock::Clock {
         from_account(&self.get_account(&sysvar::clock::id()).unwrap_or_default())
             .unwrap_or_default()
@@ -2914,7 +2947,15 @@ impl Bank {
         transaction: SanitizedTransaction,
     ) -> TransactionSimulationResult {
         assert!(self.is_frozen(), "simulation bank must be frozen");
+        self.simulate_transaction_unchecked(transaction)
+    }
 
+    /// Run transactions against a bank without committing the results; does not check if the bank
+    /// is frozen, enabling use in single-Bank test frameworks
+    pub fn simulate_transaction_unchecked(
+        &self,
+        transaction: SanitizedTransaction,
+    ) -> TransactionSimulationResult {
         let batch = self.prepare_simulation_batch(transaction);
         let mut timings = ExecuteTimings::default();
 
@@ -2983,6 +3024,20 @@ impl Bank {
         self.rc.accounts.accounts_db.set_shrink_paths(paths);
     }
 
+    pub fn set_accounts_hash_cache_path(&self, path: Option<PathBuf>) {
+        self.rc
+            .accounts
+            .accounts_db
+            .set_accounts_hash_cache_path(path);
+    }
+
+    pub fn set_retain_accounts_merkle_tree(&self, retain: bool) {
+        self.rc
+            .accounts
+            .accounts_db
+            .set_retain_accounts_merkle_tree(retain);
+    }
+
     fn check_age<'a>(
         &self,
         txs: impl Iterator<Item = &'a SanitizedTransaction>,
@@ -3267,6 +3322,21 @@ impl Bank {
         Ok(())
     }
 
+    fn collect_compute_profile(
+        compute_profile: Option<&Arc<Mutex<ComputeProfile>>>,
+        compute_profiler: Option<Rc<RefCell<ThisComputeProfiler>>>,
+    ) {
+        if let (Some(compute_profile), Some(compute_profiler)) = (compute_profile, compute_profiler)
+        {
+            if let Ok(compute_profiler) = Rc::try_unwrap(compute_profiler) {
+                compute_profile
+                    .lock()
+                    .unwrap()
+                    .merge(compute_profiler.into_inner().into());
+            }
+        }
+    }
+
     fn collect_log_messages(
         log_collector: Option<Rc<LogCollector>>,
     ) -> Option<TransactionLogMessages> {
@@ -3400,6 +3470,7 @@ impl Bank {
             Vec::with_capacity(sanitized_txs.len());
         let mut transaction_log_messages: Vec<Option<Vec<String>>> =
             Vec::with_capacity(sanitized_txs.len());
+        let compute_profile = self.compute_profile();
 
         let executed: Vec<TransactionExecutionResult> = loaded_txs
             .iter_mut()
@@ -3446,6 +3517,10 @@ impl Bank {
                             None
                         };
 
+                        let compute_profiler = compute_profile.as_ref().map(|_| {
+                            Rc::new(RefCell::new(ThisComputeProfiler::new(*tx.signature())))
+                        });
+
                         let compute_meter = Rc::new(RefCell::new(TransactionComputeMeter::new(
                             compute_budget.max_units,
                         )));
@@ -3470,6 +3545,7 @@ impl Bank {
                                 &account_refcells,
                                 &self.rent_collector,
                                 log_collector.clone(),
+                                compute_profiler.clone(),
                                 executors.clone(),
                                 instruction_recorders.as_deref(),
                                 feature_set,
@@ -3486,6 +3562,7 @@ impl Bank {
                             process_result = Err(TransactionError::UnsupportedVersion);
                         }
 
+                        Self::collect_compute_profile(compute_profile.as_ref(), compute_profiler);
                         transaction_log_messages.push(Self::collect_log_messages(log_collector));
                         inner_instructions.push(Self::compile_recorded_instructions(
                             instruction_recorders,
@@ -3697,7 +3774,7 @@ impl Bank {
         );
 
         self.increment_transaction_count(tx_count);
-        self.increment_signature_count(signature_count);
+        let first_ordinal = self.increment_signature_count(signature_count);
 
         inc_new_counter_info!("bank-process_transactions-txs", tx_count as usize);
         inc_new_counter_info!("bank-process_transactions-sigs", signature_count as usize);
@@ -3720,6 +3797,14 @@ impl Bank {
         }
 
         let mut write_time = Measure::start("write_time");
+        let account_write_recorder = self.account_write_recorder();
+        let mut account_writes = account_write_recorder.as_ref().map(|recorder| {
+            AccountWritesCollector::new(
+                self.slot(),
+                recorder.include_data(),
+                Self::account_write_ordinals(first_ordinal, sanitized_txs, loaded_txs),
+            )
+        });
         #[allow(deprecated)]
         self.rc.accounts.store_cached(
             self.slot(),
@@ -3730,6 +3815,7 @@ impl Bank {
             &self.last_blockhash_with_fee_calculator(),
             self.rent_for_sysvars(),
             self.merge_nonce_error_into_system_error(),
+            account_writes.as_mut(),
         );
         let rent_debits = self.collect_rent(executed, loaded_txs);
 
@@ -3747,6 +3833,30 @@ impl Bank {
         self.update_transaction_statuses(sanitized_txs, executed);
         let fee_collection_results =
             self.filter_program_errors_and_collect_fee(sanitized_txs, executed);
+        if let (Some(recorder), Some(mut account_writes)) = (account_write_recorder, account_writes)
+        {
+            // The fee of a failed transaction is withdrawn from its fee payer apart from the
+            // accounts stored above
+            for (tx_index, (tx, ((res, nonce_rollback), fee_result))) in sanitized_txs
+                .iter()
+                .zip(executed.iter().zip(&fee_collection_results))
+                .enumerate()
+            {
+                if matches!(res, Err(TransactionError::InstructionError(_, _)))
+                    && nonce_rollback.is_none()
+                    && fee_result.is_ok()
+                {
+                    let fee_payer = tx.message().fee_payer();
+                    if let Some(account) = self.get_account_with_fixed_root(fee_payer) {
+                        account_writes.push(tx_index, tx.signature(), fee_payer, &account);
+                    }
+                }
+            }
+            let account_writes = account_writes.into_writes();
+            if !account_writes.is_empty() {
+                recorder.record(self.slot(), account_writes);
+            }
+        }
 
         TransactionResults {
             fee_collection_results,
@@ -4537,6 +4647,16 @@ impl Bank {
         self.rc.accounts.accounts_db.expire_old_recycle_stores()
     }
 
+    /// Create or overwrite an account while keeping capitalization consistent, for tests and
+    /// test harnesses that need to set up arbitrary account state
+    pub fn store_account_and_update_capitalization_for_tests(
+        &self,
+        pubkey: &Pubkey,
+        new_account: &AccountSharedData,
+    ) {
+        self.store_account_and_update_capitalization(pubkey, new_account)
+    }
+
     /// Technically this issues (or even burns!) new lamports,
     /// so be extra careful for its usage
     fn store_account_and_update_capitalization(
@@ -4789,6 +4909,47 @@ impl Bank {
             .load_by_program_slot(self.slot(), Some(program_id))
     }
 
+    /// Collect the compute profile of the transactions executed by this bank and the banks
+    /// created from it afterwards into `compute_profile`, or stop profiling if `None`
+    pub fn set_compute_profile(&self, compute_profile: Option<Arc<Mutex<ComputeProfile>>>) {
+        *self.compute_profile.write().unwrap() = compute_profile;
+    }
+
+    pub fn compute_profile(&self) -> Option<Arc<Mutex<ComputeProfile>>> {
+        self.compute_profile.read().unwrap().clone()
+    }
+
+    /// Pass the accounts written by each transaction committed by this bank and the banks
+    /// created from it afterwards to `recorder`, or stop recording if `None`
+    pub fn set_account_write_recorder(&self, recorder: Option<Arc<dyn AccountWriteRecorder>>) {
+        *self.account_write_recorder.write().unwrap() = OptionalAccountWriteRecorder(recorder);
+    }
+
+    pub fn account_write_recorder(&self) -> Option<Arc<dyn AccountWriteRecorder>> {
+        self.account_write_recorder.read().unwrap().0.clone()
+    }
+
+    /// Ordinal of each transaction of a batch in the slot, the first being `first_ordinal`.
+    /// Only executed transactions are committed, and those take up one ordinal per signature.
+    fn account_write_ordinals(
+        first_ordinal: u64,
+        sanitized_txs: &[SanitizedTransaction],
+        loaded_txs: &[TransactionLoadResult],
+    ) -> Vec<u64> {
+        let mut next_ordinal = first_ordinal;
+        sanitized_txs
+            .iter()
+            .zip(loaded_txs)
+            .map(|(tx, (loaded_transaction, _nonce_rollback))| {
+                let ordinal = next_ordinal;
+                if loaded_transaction.is_ok() {
+                    next_ordinal += u64::from(tx.message().header().num_required_signatures);
+                }
+                ordinal
+            })
+            .collect()
+    }
+
     pub fn get_transaction_logs(
         &self,
         address: Option<&Pubkey>,
@@ -4842,6 +5003,34 @@ impl Bank {
         )
     }
 
+    /// Accounts that are charged rent, optionally only those owned by `owner`, ordered by owner
+    pub fn get_rent_paying_accounts(
+        &self,
+        owner: Option<&Pubkey>,
+    ) -> ScanResult<Vec<RentPayingAccount>> {
+        let rent_for_sysvars = self.rent_for_sysvars();
+        let rent = &self.rent_collector.rent;
+        let filter = |account: &AccountSharedData| {
+            !account.executable() && !rent.is_exempt(account.lamports(), account.data().len())
+        };
+        let accounts = match owner {
+            Some(owner) => self.get_filtered_program_accounts(owner, filter)?,
+            None => self
+                .rc
+                .accounts
+                .load_all_with_filter(&self.ancestors, self.bank_id, filter)?,
+        };
+        let mut rent_paying_accounts: Vec<_> = accounts
+            .iter()
+            .filter_map(|(pubkey, account)| {
+                self.rent_collector
+                    .rent_paying_account(pubkey, account, rent_for_sysvars)
+            })
+            .collect();
+        rent_paying_accounts.sort_by_key(|account| (account.owner, account.pubkey));
+        Ok(rent_paying_accounts)
+    }
+
     pub fn transaction_count(&self) -> u64 {
         self.transaction_count.load(Relaxed)
     }
@@ -4866,8 +5055,9 @@ impl Bank {
         self.signature_count.load(Relaxed)
     }
 
-    fn increment_signature_count(&self, signature_count: u64) {
-        self.signature_count.fetch_add(signature_count, Relaxed);
+    /// Returns the signature count before the increment
+    fn increment_signature_count(&self, signature_count: u64) -> u64 {
+        self.signature_count.fetch_add(signature_count, Relaxed)
     }
 
     pub fn get_signature_status_processed_since_parent(
@@ -4952,7 +5142,7 @@ impl Bank {
     /// Recalculate the hash_internal_state from the account stores. Would be used to verify a
     /// snapshot.
     #[must_use]
-    fn verify_bank_hash(&self, test_hash_calculation: bool) -> bool {
+    pub fn verify_bank_hash(&self, test_hash_calculation: bool) -> bool {
         self.rc.accounts.verify_bank_hash_and_lamports(
             self.slot(),
             &self.ancestors,
@@ -5098,6 +5288,19 @@ impl Bank {
         self.update_accounts_hash_with_index_option(true, false)
     }
 
+    /// The Merkle tree of the accounts hash of this bank, to prove accounts against
+    pub fn build_accounts_merkle_tree(&self) -> AccountsMerkleTree {
+        self.rc
+            .accounts
+            .accounts_db
+            .build_accounts_merkle_tree(self.slot(), &self.ancestors)
+    }
+
+    /// The Merkle tree of the last accounts hash computed, if trees are retained
+    pub fn accounts_merkle_tree(&self) -> Option<Arc<AccountsMerkleTree>> {
+        self.rc.accounts.accounts_db.accounts_merkle_tree()
+    }
+
     /// A snapshot bank should be purged of 0 lamport accounts which are not part of the hash
     /// calculation and could shield other real accounts.
     pub fn verify_snapshot_bank(
@@ -5442,6 +5645,24 @@ impl Bank {
         self.rc.accounts.accounts_db.shrink_candidate_slots()
     }
 
+    pub fn shrink_to_alive_ratio(&self, target_alive_ratio: f64) -> usize {
+        self.rc
+            .accounts
+            .accounts_db
+            .shrink_to_alive_ratio(target_alive_ratio)
+    }
+
+    pub fn request_shrink(&self, target_alive_ratio: f64) {
+        self.rc
+            .accounts
+            .accounts_db
+            .request_shrink(target_alive_ratio)
+    }
+
+    pub fn storage_report(&self, limit: usize) -> StorageReport {
+        self.rc.accounts.accounts_db.storage_report(limit)
+    }
+
     pub fn no_overflow_rent_distribution_enabled(&self) -> bool {
         self.feature_set
             .is_active(&feature_set::no_overflow_rent_distribution::id())
@@ -5818,8 +6039,9 @@ fn is_simple_vote_transaction(transaction: &SanitizedTransaction) -> bool {
 pub(crate) mod tests {
     use super::*;
     use crate::{
+        account_write_log::{AccountWrite, TransactionAccountWrites},
         accounts_background_service::{AbsRequestHandler, SendDroppedBankCallback},
-        accounts_db::DEFAULT_ACCOUNTS_SHRINK_RATIO,
+        accounts_db::{AccountsDb, DEFAULT_ACCOUNTS_SHRINK_RATIO},
         accounts_index::{AccountIndex, AccountSecondaryIndexes, ScanError, ITER_BATCH_SIZE},
         ancestors::Ancestors,
         genesis_utils::{
@@ -8936,6 +9158,28 @@ pub(crate) mod tests {
         assert!(!bank.verify_snapshot_bank(true, false, None));
     }
 
+    #[test]
+    fn test_verify_bank_hash_with_accounts_hash_cache() {
+        solana_logger::setup();
+        let (genesis_config, mint_keypair) = create_genesis_config(2_000);
+        let bank = Bank::new_for_tests(&genesis_config);
+        bank.transfer(1_000, &mint_keypair, &solana_sdk::pubkey::new_rand())
+            .unwrap();
+        bank.freeze();
+        bank.squash();
+        bank.force_flush_accounts_cache();
+        bank.update_accounts_hash();
+
+        let cache_dir = tempfile::TempDir::new().unwrap();
+        bank.set_accounts_hash_cache_path(Some(cache_dir.path().to_path_buf()));
+        assert!(bank.verify_bank_hash(false));
+        // the finished calculation leaves nothing behind
+        assert_eq!(std::fs::read_dir(cache_dir.path()).unwrap().count(), 0);
+
+        bank.set_accounts_hash_cache_path(None);
+        assert!(bank.verify_bank_hash(false));
+    }
+
     // Test that two bank forks with the same accounts should not hash to the same value.
     #[test]
     fn test_bank_hash_internal_state_same_account_different_fork() {
@@ -13822,6 +14066,141 @@ pub(crate) mod tests {
         assert!(failure_log.contains(&"failed".to_string()));
     }
 
+    #[derive(Debug, Default)]
+    struct TestAccountWriteRecorder {
+        writes: Mutex<Vec<(Slot, Vec<TransactionAccountWrites>)>>,
+    }
+
+    impl AccountWriteRecorder for TestAccountWriteRecorder {
+        fn include_data(&self) -> bool {
+            false
+        }
+
+        fn begin_slot(&self, slot: Slot) {
+            self.writes
+                .lock()
+                .unwrap()
+                .retain(|(write_slot, _)| *write_slot != slot);
+        }
+
+        fn record(&self, slot: Slot, writes: Vec<TransactionAccountWrites>) {
+            self.writes.lock().unwrap().push((slot, writes));
+        }
+    }
+
+    #[test]
+    fn test_account_write_recorder() {
+        let (genesis_config, mint_keypair) = create_genesis_config(1_000_000);
+        let bank0 = Arc::new(Bank::new_for_tests(&genesis_config));
+        let recorder = Arc::new(TestAccountWriteRecorder::default());
+        bank0.set_account_write_recorder(Some(recorder.clone()));
+        let bank = Arc::new(new_from_parent(&bank0));
+        assert!(bank.account_write_recorder().is_some());
+
+        let to = solana_sdk::pubkey::new_rand();
+        let tx = system_transaction::transfer(&mint_keypair, &to, 1_000, bank.last_blockhash());
+        bank.process_transaction(&tx).unwrap();
+        // fails, but the fee payer is still written
+        let failed_tx =
+            system_transaction::transfer(&mint_keypair, &to, 10_000_000, bank.last_blockhash());
+        assert!(bank.process_transaction(&failed_tx).is_err());
+
+        let account_write = |pubkey: &Pubkey| {
+            let account = bank.get_account(pubkey).unwrap();
+            AccountWrite {
+                pubkey: *pubkey,
+                lamports: account.lamports(),
+                hash: AccountsDb::hash_account(bank.slot(), &account, pubkey),
+                data: None,
+            }
+        };
+        let writes = recorder.writes.lock().unwrap();
+        assert_eq!(
+            *writes,
+            vec![
+                (
+                    bank.slot(),
+                    vec![TransactionAccountWrites {
+                        ordinal: 0,
+                        signature: tx.signatures[0],
+                        accounts: vec![
+                            AccountWrite {
+                                lamports: 1_000_000 - 1_000,
+                                hash: writes[0].1[0].accounts[0].hash,
+                                ..account_write(&mint_keypair.pubkey())
+                            },
+                            account_write(&to),
+                        ],
+                    }],
+                ),
+                (
+                    bank.slot(),
+                    vec![TransactionAccountWrites {
+                        ordinal: 1,
+                        signature: failed_tx.signatures[0],
+                        accounts: vec![account_write(&mint_keypair.pubkey())],
+                    }],
+                ),
+            ]
+        );
+        drop(writes);
+
+        bank.set_account_write_recorder(None);
+        let tx = system_transaction::transfer(&mint_keypair, &to, 2_000, bank.last_blockhash());
+        bank.process_transaction(&tx).unwrap();
+        assert_eq!(recorder.writes.lock().unwrap().len(), 2);
+
+        // Replaying the slot discards the writes recorded by the earlier bank
+        let replayed_bank = new_from_parent(&bank0);
+        assert_eq!(replayed_bank.slot(), bank.slot());
+        assert!(recorder.writes.lock().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_get_rent_paying_accounts() {
+        let (mut genesis_config, _mint_keypair) = create_genesis_config(1_000_000);
+        genesis_config.rent = Rent::default();
+        let bank = Bank::new_for_tests(&genesis_config);
+        let owner = solana_sdk::pubkey::new_rand();
+        let rent_paying_pubkey = solana_sdk::pubkey::new_rand();
+        let owned_rent_paying_pubkey = solana_sdk::pubkey::new_rand();
+        let exempt_pubkey = solana_sdk::pubkey::new_rand();
+        bank.store_account(
+            &rent_paying_pubkey,
+            &AccountSharedData::new(1_000, 0, &system_program::id()),
+        );
+        bank.store_account(
+            &owned_rent_paying_pubkey,
+            &AccountSharedData::new(5_000, 10, &owner),
+        );
+        bank.store_account(
+            &exempt_pubkey,
+            &AccountSharedData::new(genesis_config.rent.minimum_balance(10), 10, &owner),
+        );
+
+        let rent_paying_accounts = bank.get_rent_paying_accounts(Some(&owner)).unwrap();
+        assert_eq!(rent_paying_accounts.len(), 1);
+        let rent_paying_account = &rent_paying_accounts[0];
+        assert_eq!(rent_paying_account.pubkey, owned_rent_paying_pubkey);
+        assert_eq!(rent_paying_account.lamports, 5_000);
+        assert_eq!(
+            rent_paying_account.rent_exempt_minimum,
+            genesis_config.rent.minimum_balance(10)
+        );
+
+        let rent_paying_accounts = bank.get_rent_paying_accounts(None).unwrap();
+        let pubkeys: Vec<_> = rent_paying_accounts
+            .iter()
+            .map(|account| account.pubkey)
+            .collect();
+        assert!(pubkeys.contains(&rent_paying_pubkey));
+        assert!(pubkeys.contains(&owned_rent_paying_pubkey));
+        assert!(!pubkeys.contains(&exempt_pubkey));
+        assert!(rent_paying_accounts
+            .windows(2)
+            .all(|pair| pair[0].owner <= pair[1].owner));
+    }
+
     #[test]
     fn test_get_largest_accounts() {
         let GenesisConfigInfo { genesis_config, .. } =
@@ -0,0 +1,184 @@
+//! State shared by the bins of a disk backed accounts index, and the thread flushing them
+//!
+//! Each bin keeps recently used entries in memory and the rest in a `BucketMap` on disk.
+//! Entries remember the age of the index when they were last accessed. The age advances every
+//! `AGE_MS`, and the flusher then writes changed entries to disk and evicts those which haven't
+//! been accessed for `AGES_TO_STAY_IN_CACHE` ages, or more if a bin is over its memory budget.
+
+use {
+    crate::{
+        accounts_index::{AccountsIndexConfig, IsCached},
+        hybrid_btree_map::HybridBTreeMap,
+        waitable_condvar::WaitableCondvar,
+    },
+    solana_bucket_map::bucket_map::{BucketMap, BucketMapConfig},
+    solana_sdk::{
+        clock::{Slot, DEFAULT_MS_PER_SLOT},
+        timing::AtomicInterval,
+    },
+    std::{
+        fmt::Debug,
+        sync::{
+            atomic::{AtomicBool, AtomicU64, AtomicU8, Ordering},
+            Arc, RwLock,
+        },
+        thread::{Builder, JoinHandle},
+        time::Duration,
+    },
+};
+
+pub type Age = u8;
+
+/// How often the age advances and the bins are flushed
+pub const AGE_MS: u64 = DEFAULT_MS_PER_SLOT;
+
+/// Entries which haven't been accessed for this many ages are evicted from memory
+pub const AGES_TO_STAY_IN_CACHE: Age = 5;
+
+/// Rough memory use of an in-memory index entry, to turn a memory budget into an entry count
+const ESTIMATED_BYTES_PER_ENTRY: usize = 200;
+
+const STATS_INTERVAL_MS: u64 = 10_000;
+
+#[derive(Debug, Default)]
+pub struct BucketMapHolderStats {
+    /// Entries read from disk into memory
+    pub pages_in: AtomicU64,
+    /// Entries written to disk
+    pub flushed: AtomicU64,
+    /// Entries evicted from memory
+    pub evicted: AtomicU64,
+    pub flush_us: AtomicU64,
+    last_report: AtomicInterval,
+}
+
+#[derive(Debug)]
+pub struct BucketMapHolder<T> {
+    pub disk: BucketMap<(Slot, T)>,
+    age: AtomicU8,
+    /// Maximum number of entries each bin keeps in memory, if memory is bounded
+    max_in_mem_per_bin: Option<usize>,
+    pub stats: BucketMapHolderStats,
+}
+
+impl<T: IsCached> BucketMapHolder<T> {
+    pub fn new(bins: usize, config: &AccountsIndexConfig) -> Self {
+        Self {
+            disk: BucketMap::new(BucketMapConfig {
+                drives: config.drives.clone(),
+                ..BucketMapConfig::new(bins)
+            }),
+            age: AtomicU8::default(),
+            max_in_mem_per_bin: config
+                .index_limit_mb
+                .map(|mb| mb * 1024 * 1024 / ESTIMATED_BYTES_PER_ENTRY / bins),
+            stats: BucketMapHolderStats::default(),
+        }
+    }
+
+    pub fn current_age(&self) -> Age {
+        self.age.load(Ordering::Relaxed)
+    }
+
+    pub fn increment_age(&self) {
+        self.age.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Ages elapsed since `age`
+    pub fn ages_since(&self, age: Age) -> Age {
+        self.current_age().wrapping_sub(age)
+    }
+
+    pub fn max_in_mem_per_bin(&self) -> Option<usize> {
+        self.max_in_mem_per_bin
+    }
+
+    fn maybe_report_stats(&self, bins: &[Arc<RwLock<HybridBTreeMap<T>>>]) {
+        if !self.stats.last_report.should_update(STATS_INTERVAL_MS) {
+            return;
+        }
+        let in_mem_entries: usize = bins
+            .iter()
+            .map(|bin| bin.read().unwrap().in_mem_len())
+            .sum();
+        let disk_entries: u64 = (0..self.disk.num_buckets())
+            .map(|ix| self.disk.bucket_len(ix))
+            .sum();
+        datapoint_info!(
+            "accounts_index_disk",
+            ("in_mem_entries", in_mem_entries, i64),
+            ("disk_entries", disk_entries, i64),
+            (
+                "pages_in",
+                self.stats.pages_in.swap(0, Ordering::Relaxed),
+                i64
+            ),
+            (
+                "flushed",
+                self.stats.flushed.swap(0, Ordering::Relaxed),
+                i64
+            ),
+            (
+                "evicted",
+                self.stats.evicted.swap(0, Ordering::Relaxed),
+                i64
+            ),
+            (
+                "flush_us",
+                self.stats.flush_us.swap(0, Ordering::Relaxed),
+                i64
+            ),
+        );
+    }
+}
+
+/// Background thread advancing the age of a disk backed index and flushing its bins
+#[derive(Debug)]
+pub struct BucketMapFlusher {
+    exit: Arc<AtomicBool>,
+    wait: Arc<WaitableCondvar>,
+    handle: Option<JoinHandle<()>>,
+}
+
+impl BucketMapFlusher {
+    pub fn new<T: IsCached>(
+        holder: Arc<BucketMapHolder<T>>,
+        bins: Vec<Arc<RwLock<HybridBTreeMap<T>>>>,
+    ) -> Self {
+        let exit = Arc::new(AtomicBool::default());
+        let wait = Arc::new(WaitableCondvar::default());
+        let handle = {
+            let exit = exit.clone();
+            let wait = wait.clone();
+            Builder::new()
+                .name("solana-accounts-index-flush".to_string())
+                .spawn(move || loop {
+                    wait.wait_timeout(Duration::from_millis(AGE_MS));
+                    if exit.load(Ordering::Relaxed) {
+                        break;
+                    }
+                    holder.increment_age();
+                    for bin in &bins {
+                        bin.write().unwrap().flush();
+                    }
+                    holder.maybe_report_stats(&bins);
+                })
+                .unwrap()
+        };
+        Self {
+            exit,
+            wait,
+            handle: Some(handle),
+        }
+    }
+}
+
+impl Drop for BucketMapFlusher {
+    fn drop(&mut self) {
+        self.exit.store(true, Ordering::Relaxed);
+        self.wait.notify_all();
+        if let Some(handle) = self.handle.take() {
+            handle.join().unwrap();
+        }
+    }
+}
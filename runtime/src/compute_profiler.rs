@@ -0,0 +1,254 @@
+//! Opt-in profiling of the compute units consumed by executed programs
+use {
+    solana_sdk::{process_instruction::ComputeProfiler, pubkey::Pubkey, signature::Signature},
+    std::{
+        collections::HashMap,
+        io::{self, Write},
+    },
+};
+
+/// Compute units consumed by a single program invocation
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ProgramInvocation {
+    pub signature: Signature,
+    pub instruction_index: usize,
+    /// 1 for a top-level instruction, incremented by each cross-program invocation
+    pub depth: usize,
+    pub program_id: Pubkey,
+    /// Units consumed, including those of nested cross-program invocations
+    pub units_consumed: u64,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum StackFrame {
+    Program(Pubkey),
+    Function(String),
+}
+
+/// Compute units consumed by executed transactions
+#[derive(Clone, Debug, Default)]
+pub struct ComputeProfile {
+    /// Units consumed by the innermost frame of each stack
+    stacks: HashMap<Vec<StackFrame>, u64>,
+    invocations: Vec<ProgramInvocation>,
+}
+
+impl ComputeProfile {
+    pub fn stacks(&self) -> &HashMap<Vec<StackFrame>, u64> {
+        &self.stacks
+    }
+
+    /// Program invocations in execution order
+    pub fn invocations(&self) -> &[ProgramInvocation] {
+        &self.invocations
+    }
+
+    /// Total units consumed by all top-level instructions
+    pub fn total_units(&self) -> u64 {
+        self.invocations
+            .iter()
+            .filter(|invocation| invocation.depth == 1)
+            .map(|invocation| invocation.units_consumed)
+            .sum()
+    }
+
+    pub fn merge(&mut self, other: ComputeProfile) {
+        for (stack, units) in other.stacks {
+            *self.stacks.entry(stack).or_default() += units;
+        }
+        self.invocations.extend(other.invocations);
+    }
+
+    /// Write the stacks in the folded format read by flamegraph tools, one `frame;frame units`
+    /// line per stack. Programs are named from `program_names` when present.
+    pub fn write_folded_stacks<W: Write>(
+        &self,
+        writer: &mut W,
+        program_names: &HashMap<Pubkey, String>,
+    ) -> io::Result<()> {
+        let mut lines = self
+            .stacks
+            .iter()
+            .filter(|(_, units)| **units > 0)
+            .map(|(stack, units)| {
+                let stack = stack
+                    .iter()
+                    .map(|frame| match frame {
+                        StackFrame::Program(program_id) => program_names
+                            .get(program_id)
+                            .cloned()
+                            .unwrap_or_else(|| program_id.to_string()),
+                        StackFrame::Function(name) => name.replace(';', ":"),
+                    })
+                    .collect::<Vec<_>>()
+                    .join(";");
+                (stack, *units)
+            })
+            .collect::<Vec<_>>();
+        lines.sort();
+        for (stack, units) in lines {
+            writeln!(writer, "{} {}", stack, units)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(RUSTC_WITH_SPECIALIZATION)]
+impl solana_frozen_abi::abi_example::AbiExample for ComputeProfile {
+    fn example() -> Self {
+        Self::default()
+    }
+}
+
+struct ProfilerFrame {
+    stack: Vec<StackFrame>,
+    invocation_index: usize,
+    remaining_units: u64,
+    /// Units already attributed to functions and nested invocations of this frame
+    attributed_units: u64,
+}
+
+/// Collects the `ComputeProfile` of a single transaction
+pub struct ThisComputeProfiler {
+    signature: Signature,
+    instruction_index: usize,
+    frames: Vec<ProfilerFrame>,
+    profile: ComputeProfile,
+}
+
+impl ThisComputeProfiler {
+    pub fn new(signature: Signature) -> Self {
+        Self {
+            signature,
+            instruction_index: 0,
+            frames: vec![],
+            profile: ComputeProfile::default(),
+        }
+    }
+
+    pub fn set_instruction_index(&mut self, instruction_index: usize) {
+        self.instruction_index = instruction_index;
+    }
+}
+
+impl ComputeProfiler for ThisComputeProfiler {
+    fn enter(&mut self, program_id: &Pubkey, remaining_units: u64) {
+        let mut stack = self
+            .frames
+            .last()
+            .map(|frame| frame.stack.clone())
+            .unwrap_or_default();
+        stack.push(StackFrame::Program(*program_id));
+        self.frames.push(ProfilerFrame {
+            stack,
+            invocation_index: self.profile.invocations.len(),
+            remaining_units,
+            attributed_units: 0,
+        });
+        self.profile.invocations.push(ProgramInvocation {
+            signature: self.signature,
+            instruction_index: self.instruction_index,
+            depth: self.frames.len(),
+            program_id: *program_id,
+            units_consumed: 0,
+        });
+    }
+
+    fn record_call_stack(&mut self, call_stack: &[&str], instructions: u64) {
+        if let Some(frame) = self.frames.last_mut() {
+            let mut stack = frame.stack.clone();
+            stack.extend(
+                call_stack
+                    .iter()
+                    .map(|function| StackFrame::Function(function.to_string())),
+            );
+            *self.profile.stacks.entry(stack).or_default() += instructions;
+            frame.attributed_units = frame.attributed_units.saturating_add(instructions);
+        }
+    }
+
+    fn exit(&mut self, remaining_units: u64) {
+        if let Some(frame) = self.frames.pop() {
+            let units_consumed = frame.remaining_units.saturating_sub(remaining_units);
+            *self.profile.stacks.entry(frame.stack).or_default() +=
+                units_consumed.saturating_sub(frame.attributed_units);
+            self.profile.invocations[frame.invocation_index].units_consumed = units_consumed;
+            if let Some(parent) = self.frames.last_mut() {
+                parent.attributed_units = parent.attributed_units.saturating_add(units_consumed);
+            }
+        }
+    }
+}
+
+impl From<ThisComputeProfiler> for ComputeProfile {
+    fn from(profiler: ThisComputeProfiler) -> Self {
+        profiler.profile
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_compute_profiler() {
+        let caller = Pubkey::new_unique();
+        let callee = Pubkey::new_unique();
+        let signature = Signature::new(&[1; 64]);
+        let mut profiler = ThisComputeProfiler::new(signature);
+
+        // caller consumes 1000 units of which 300 are spent in callee
+        profiler.set_instruction_index(1);
+        profiler.enter(&caller, 10_000);
+        profiler.record_call_stack(&["entrypoint"], 400);
+        profiler.record_call_stack(&["entrypoint", "process;instruction"], 200);
+        profiler.enter(&callee, 9_500);
+        profiler.record_call_stack(&["entrypoint"], 250);
+        profiler.exit(9_200);
+        profiler.exit(9_000);
+
+        let mut profile = ComputeProfile::from(profiler);
+        assert_eq!(
+            profile.invocations(),
+            &[
+                ProgramInvocation {
+                    signature,
+                    instruction_index: 1,
+                    depth: 1,
+                    program_id: caller,
+                    units_consumed: 1000,
+                },
+                ProgramInvocation {
+                    signature,
+                    instruction_index: 1,
+                    depth: 2,
+                    program_id: callee,
+                    units_consumed: 300,
+                },
+            ]
+        );
+        assert_eq!(profile.total_units(), 1000);
+
+        let mut program_names = HashMap::new();
+        program_names.insert(callee, "callee".to_string());
+        let mut folded = vec![];
+        profile
+            .write_folded_stacks(&mut folded, &program_names)
+            .unwrap();
+        assert_eq!(
+            String::from_utf8(folded).unwrap(),
+            format!(
+                "{0} 100\n\
+                 {0};callee 50\n\
+                 {0};callee;entrypoint 250\n\
+                 {0};entrypoint 400\n\
+                 {0};entrypoint;process:instruction 200\n",
+                caller
+            )
+        );
+
+        profile.merge(profile.clone());
+        assert_eq!(profile.total_units(), 2000);
+        assert_eq!(profile.stacks()[&vec![StackFrame::Program(caller)]], 200);
+    }
+}
@@ -1,458 +1,429 @@
-use crate::accounts_index::AccountMapEntry;
-use crate::accounts_index::{IsCached, RefCount, SlotList, ACCOUNTS_INDEX_CONFIG_FOR_TESTING};
-use crate::bucket_map_holder::{BucketMapWriteHolder};
-
-use crate::pubkey_bins::PubkeyBinCalculator16;
-use solana_bucket_map::bucket_map::BucketMap;
-use solana_sdk::clock::Slot;
-use solana_sdk::pubkey::Pubkey;
-use std::collections::btree_map::BTreeMap;
-use std::fmt::Debug;
-use std::ops::Bound;
-use std::ops::{Range, RangeBounds};
-use std::sync::Arc;
-
-type K = Pubkey;
-
-#[derive(Clone, Debug)]
-pub struct HybridAccountEntry<V: Clone + Debug> {
-    entry: V,
-    //exists_on_disk: bool,
-}
-//type V2<T: Clone + Debug> = HybridAccountEntry<T>;
-pub type V2<T> = AccountMapEntry<T>;
-/*
-trait RealEntry<T: Clone + Debug> {
-    fn real_entry(&self) -> T;
-}
-
-impl<T:Clone + Debug> RealEntry<T> for T {
-    fn real_entry(&self) -> T
-    {
-        self
-    }
-}
-*/
-pub type SlotT<T> = (Slot, T);
+//! A bin of the accounts index, optionally spilling entries to disk
+//!
+//! Without disk storage this is a plain `BTreeMap`. With it, the map holds the entries in
+//! use, and the rest live in the bin's bucket of the shared `BucketMap`. Entries are paged in
+//! from disk before they are accessed, and written back and evicted by `flush`.
+
+use {
+    crate::{
+        accounts_index::{
+            AccountMapEntry, AccountMapEntryInner, AccountMapEntryMeta, IsCached, SlotList,
+            WriteAccountMapEntry,
+        },
+        bucket_map_holder::{BucketMapHolder, AGES_TO_STAY_IN_CACHE},
+    },
+    solana_measure::measure::Measure,
+    solana_sdk::{clock::Slot, pubkey::Pubkey},
+    std::{
+        cmp::Reverse,
+        collections::btree_map::{BTreeMap, Entry},
+        fmt::Debug,
+        ops::RangeBounds,
+        sync::{atomic::Ordering, Arc},
+    },
+};
 
 #[derive(Debug)]
-pub struct HybridBTreeMap<V: 'static + Clone + IsCached + Debug> {
-    in_memory: BTreeMap<K, V2<V>>,
-    disk: Arc<BucketMapWriteHolder<V>>,
-    bin_index: usize,
-    bins: usize,
+pub struct HybridBTreeMap<T> {
+    in_mem: BTreeMap<Pubkey, AccountMapEntry<T>>,
+    storage: Option<Arc<BucketMapHolder<T>>>,
+    bin: usize,
 }
 
-// TODO: we need a bit for 'exists on disk' for updates
-/*
-impl<V: Clone + Debug> Default for HybridBTreeMap<V> {
-    /// Creates an empty `BTreeMap`.
-    fn default() -> HybridBTreeMap<V> {
+impl<T: IsCached> HybridBTreeMap<T> {
+    pub fn new(storage: Option<Arc<BucketMapHolder<T>>>, bin: usize) -> Self {
         Self {
-            in_memory: BTreeMap::default(),
-            disk: BucketMap::new_buckets(PubkeyBinCalculator16::log_2(BINS as u32) as u8),
+            in_mem: BTreeMap::default(),
+            storage,
+            bin,
         }
     }
-}
-*/
-
-/*
-impl<'a, K: 'a, V: 'a> Iterator for HybridBTreeMap<'a, V> {
-    type Item = (&'a K, &'a V);
-
-    fn next(&mut self) -> Option<(&'a K, &'a V)> {
-        if self.length == 0 {
-            None
-        } else {
-            self.length -= 1;
-            Some(unsafe { self.range.inner.next_unchecked() })
-        }
-    }
-
-    fn size_hint(&self) -> (usize, Option<usize>) {
-        (self.length, Some(self.length))
-    }
 
-    fn last(mut self) -> Option<(&'a K, &'a V)> {
-        self.next_back()
+    pub fn is_disk_backed(&self) -> bool {
+        self.storage.is_some()
     }
 
-    fn min(mut self) -> Option<(&'a K, &'a V)> {
-        self.next()
+    #[cfg(test)]
+    pub(crate) fn storage(&self) -> Option<&Arc<BucketMapHolder<T>>> {
+        self.storage.as_ref()
     }
 
-    fn max(mut self) -> Option<(&'a K, &'a V)> {
-        self.next_back()
+    /// Number of entries held in memory
+    pub fn in_mem_len(&self) -> usize {
+        self.in_mem.len()
     }
-}
-*/
-
-pub enum HybridEntry<'a, V: 'static + Clone + IsCached + Debug> {
-    /// A vacant entry.
-    Vacant(HybridVacantEntry<'a, V>),
 
-    /// An occupied entry.
-    Occupied(HybridOccupiedEntry<'a, V>),
-}
-
-pub struct Keys {
-    keys: Vec<K>,
-    index: usize,
-}
-
-impl Keys {
+    /// Number of entries, in memory or on disk
     pub fn len(&self) -> usize {
-        self.keys.len()
-    }
-}
-
-impl Iterator for Keys {
-    type Item = Pubkey;
-    fn next(&mut self) -> Option<Self::Item> {
-        if self.index >= self.keys.len() {
-            None
-        } else {
-            let r = Some(self.keys[self.index]);
-            self.index += 1;
-            r
-        }
-    }
-}
-
-pub struct Values<V: Clone + std::fmt::Debug> {
-    values: Vec<SlotList<V>>,
-    index: usize,
-}
-
-impl<V: Clone + std::fmt::Debug> Iterator for Values<V> {
-    type Item = V2<V>;
-    fn next(&mut self) -> Option<Self::Item> {
-        if self.index >= self.values.len() {
-            None
-        } else {
-            let r = Some(AccountMapEntry {
-                slot_list: self.values[self.index].clone(),
-                ref_count: RefCount::MAX, // todo: no clone
-            });
-            self.index += 1;
-            r
+        match &self.storage {
+            Some(storage) => {
+                let in_mem_only = self
+                    .in_mem
+                    .values()
+                    .filter(|entry| !entry.meta.on_disk.load(Ordering::Relaxed))
+                    .count();
+                in_mem_only + storage.disk.bucket_len(self.bin) as usize
+            }
+            None => self.in_mem.len(),
         }
     }
-}
 
-pub struct HybridOccupiedEntry<'a, V: 'static + Clone + IsCached + Debug> {
-    pubkey: Pubkey,
-    entry: V2<V>,
-    map: &'a HybridBTreeMap<V>,
-}
-pub struct HybridVacantEntry<'a, V: 'static + Clone + IsCached + Debug> {
-    pubkey: Pubkey,
-    map: &'a HybridBTreeMap<V>,
-}
-
-impl<'a, V: 'a + Clone + IsCached + Debug> HybridOccupiedEntry<'a, V> {
-    pub fn get(&self) -> &V2<V> {
-        &self.entry
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
     }
-    pub fn update(&mut self, new_data: &SlotList<V>, new_rc: Option<RefCount>) {
-        //error!("update: {}", self.pubkey);
-        self.map.disk.update(
-            &self.pubkey,
-            |previous| {
-                if previous.is_some() {
-                    //error!("update {} to {:?}", self.pubkey, new_data);
-                }
-                Some((new_data.clone(), new_rc.unwrap_or(self.entry.ref_count)))
-                // TODO no clone here
-            },
-            Some(&self.entry),
-        );
-        let g = self.map.disk.get(&self.pubkey).unwrap();
-        assert_eq!(format!("{:?}", g.1), format!("{:?}", new_data));
-    }
-    pub fn addref(&mut self) {
-        self.entry.ref_count += 1;
 
-        self.map
-            .disk
-            .addref(&self.pubkey, self.entry.ref_count, &self.entry.slot_list);
-        //error!("addref: {}, {}, {:?}", self.pubkey, self.entry.ref_count(), result);
-    }
-    pub fn unref(&mut self) {
-        self.entry.ref_count -= 1;
-        self.map
-            .disk
-            .unref(&self.pubkey, self.entry.ref_count, &self.entry.slot_list);
-        //error!("addref: {}, {}, {:?}", self.pubkey, self.entry.ref_count(), result);
-    }
-    /*
-    pub fn get_mut(&mut self) -> &mut V2<V> {
-        self.entry.get_mut()
-    }
-    */
-    pub fn key(&self) -> &K {
-        &self.pubkey
-    }
-    pub fn remove(self) {
-        self.map.disk.delete_key(&self.pubkey)
+    fn touch(&self, entry: &AccountMapEntry<T>) {
+        if let Some(storage) = &self.storage {
+            entry
+                .meta
+                .age
+                .store(storage.current_age(), Ordering::Relaxed);
+        }
     }
-}
 
-impl<'a, V: 'a + Clone + Debug + IsCached> HybridVacantEntry<'a, V> {
-    pub fn insert(self, value: V2<V>) {
-        // -> &'a mut V2<V> {
-        /*
-        let value = V2::<V> {
-            entry: value,
-            //exists_on_disk: false,
-        };
-        */
-        //let mut sl = SlotList::default();
-        //std::mem::swap(&mut sl, &mut value.slot_list);
-        self.map.disk.update(
-            &self.pubkey,
-            |_previous| {
-                Some((value.slot_list.clone() /* todo bad */, value.ref_count))
-            },
-            None,
-        );
+    /// The entry of `pubkey`, if it is in memory. Entries on disk have to be paged in first,
+    /// see `needs_page_in()`.
+    pub fn get(&self, pubkey: &Pubkey) -> Option<&AccountMapEntry<T>> {
+        let entry = self.in_mem.get(pubkey)?;
+        self.touch(entry);
+        Some(entry)
     }
-}
 
-impl<V: IsCached> HybridBTreeMap<V> {
-    /// Creates an empty `BTreeMap`.
-    pub fn new2(bucket_map: &Arc<BucketMapWriteHolder<V>>, bin_index: usize, bins: usize) -> Self {
-        Self {
-            in_memory: BTreeMap::default(),
-            disk: bucket_map.clone(),
-            bin_index,
-            bins: bins, //bucket_map.num_buckets(),
+    /// Whether `pubkey` is on disk but not in memory
+    pub fn needs_page_in(&self, pubkey: &Pubkey) -> bool {
+        match &self.storage {
+            Some(storage) => !self.in_mem.contains_key(pubkey) && storage.disk.contains_key(pubkey),
+            None => false,
         }
     }
 
-    pub fn new_for_testing() -> Self {
-        let map = Self::new_bucket_map(ACCOUNTS_INDEX_CONFIG_FOR_TESTING);
-        Self::new2(&map, 0, 1)
+    /// Bring the entry of `pubkey` into memory if it is on disk, and return it
+    pub fn page_in(&mut self, pubkey: &Pubkey) -> Option<&AccountMapEntry<T>> {
+        if !self.in_mem.contains_key(pubkey) {
+            let storage = self.storage.as_ref()?;
+            let (slot_list, ref_count) = storage.disk.read_value(pubkey)?;
+            storage.stats.pages_in.fetch_add(1, Ordering::Relaxed);
+            let meta = AccountMapEntryMeta::new_clean(storage.current_age());
+            self.in_mem.insert(
+                *pubkey,
+                Arc::new(AccountMapEntryInner::new(slot_list, ref_count, meta)),
+            );
+        }
+        self.get(pubkey)
     }
 
-    pub fn new_bucket_map(bins: usize) -> Arc<BucketMapWriteHolder<V>> {
-        let buckets = PubkeyBinCalculator16::log_2(bins as u32) as u8; // make more buckets to try to spread things out
-                                                                       // 15 hopefully avoids too many files open problem
-                                                                       //buckets = std::cmp::min(buckets + 11, 15); // max # that works with open file handles and such
-                                                                       //buckets =
-                                                                       //error!("creating: {} for {}", buckets, BUCKET_BINS);
-        Arc::new(BucketMapWriteHolder::new(BucketMap::new_buckets(buckets)))
+    pub fn entry(&mut self, pubkey: Pubkey) -> Entry<'_, Pubkey, AccountMapEntry<T>> {
+        self.page_in(&pubkey);
+        self.in_mem.entry(pubkey)
     }
 
-    pub fn flush(&self) -> usize {
-        let num_buckets = self.disk.num_buckets();
-        let mystart = num_buckets * self.bin_index / self.bins;
-        let myend = num_buckets * (self.bin_index + 1) / self.bins;
-        assert_eq!(myend - mystart, 1, "{}", self.bin_index);
-        (mystart..myend)
-            .map(|ix| self.disk.flush(ix, false, None).1)
-            .sum()
-
-        /*
-        {
-            // put entire contents of this map into the disk backing
-            let mut keys = Vec::with_capacity(self.in_memory.len());
-            for k in self.in_memory.keys() {
-                keys.push(k);
-            }
-            self.disk.update_batch(&keys[..], |previous, key, orig_i| {
-                let item = self.in_memory.get(key);
-                item.map(|item| (item.slot_list.clone(), item.ref_count()))
-            });
-            self.in_memory.clear();
-        }*/
-    }
-    pub fn distribution(&self) {
-        self.disk.distribution();
-    }
-    fn bound<'a, T>(bound: Bound<&'a T>, unbounded: &'a T) -> &'a T {
-        match bound {
-            Bound::Included(b) | Bound::Excluded(b) => b,
-            _ => unbounded,
+    pub fn remove(&mut self, pubkey: &Pubkey) {
+        self.in_mem.remove(pubkey);
+        if let Some(storage) = &self.storage {
+            storage.disk.delete_key(pubkey);
         }
     }
-    pub fn range<R>(&self, range: Option<R>) -> Vec<(Pubkey, SlotList<V>)>
-    where
-        R: RangeBounds<Pubkey>,
-    {
-        //self.disk.range.fetch_add(1, Ordering::Relaxed);
 
-        let num_buckets = self.disk.num_buckets();
-        if self.bin_index != 0 && self.disk.unified_backing {
-            return vec![];
-        }
-        let mut start = 0;
-        let mut end = num_buckets;
-        if let Some(range) = &range {
-            let default = Pubkey::default();
-            let max = Pubkey::new(&[0xff; 32]);
-            let start_bound = Self::bound(range.start_bound(), &default);
-            start = self.disk.bucket_ix(start_bound);
-            // end is exclusive, so it is end + 1 we care about here
-            let end_bound = Self::bound(range.end_bound(), &max);
-            end = std::cmp::min(num_buckets, 1 + self.disk.bucket_ix(end_bound)); // ugly
-            assert!(
-                start_bound <= end_bound,
-                "range start is greater than range end"
+    /// Add `account_info` at `slot` to the entry of `pubkey` without bringing it into memory.
+    /// Used to build a disk backed index at startup. Returns true if `pubkey` already existed.
+    pub fn insert_on_disk(&mut self, pubkey: Pubkey, slot: Slot, account_info: T) -> bool {
+        let mut reclaims = SlotList::new();
+        if let Some(entry) = self.in_mem.get(&pubkey) {
+            WriteAccountMapEntry::from_account_map_entry(entry.clone()).update(
+                slot,
+                account_info,
+                &mut reclaims,
             );
+            return true;
         }
-        let len = (start..end)
-            .into_iter()
-            .map(|ix| self.disk.bucket_len(ix) as usize)
-            .sum::<usize>();
-
-        let mystart = num_buckets * self.bin_index / self.bins;
-        let myend = num_buckets * (self.bin_index + 1) / self.bins;
-        start = std::cmp::max(start, mystart);
-        end = std::cmp::min(end, myend);
-        let mut keys = Vec::with_capacity(len);
-        (start..end).into_iter().for_each(|ix| {
-            let mut ks = self.disk.range(ix, range.as_ref());
-            keys.append(&mut ks);
+        let storage = self
+            .storage
+            .as_ref()
+            .expect("insert_on_disk() requires a disk backed map");
+        let mut existed = false;
+        storage.disk.update(&pubkey, |current| {
+            Some(match current {
+                Some((mut slot_list, mut ref_count)) => {
+                    existed = true;
+                    if WriteAccountMapEntry::update_slot_list(
+                        &mut slot_list,
+                        slot,
+                        account_info,
+                        &mut reclaims,
+                        false,
+                    ) {
+                        ref_count += 1;
+                    }
+                    (slot_list, ref_count)
+                }
+                // the same ref count as `WriteAccountMapEntry::new_entry_after_update()`
+                None => {
+                    let ref_count = if account_info.is_cached() { 0 } else { 1 };
+                    (vec![(slot, account_info)], ref_count)
+                }
+            })
         });
-        keys.sort_unstable_by(|a, b| a.0.cmp(&b.0));
-        keys
+        existed
     }
 
-    pub fn keys2(&self) -> Keys {
-        // used still?
-        let num_buckets = self.disk.num_buckets();
-        let start = num_buckets * self.bin_index / self.bins;
-        let end = num_buckets * (self.bin_index + 1) / self.bins;
-        let len = (start..end)
-            .into_iter()
-            .map(|ix| self.disk.bucket_len(ix) as usize)
-            .sum::<usize>();
-        let mut keys = Vec::with_capacity(len);
-        let _len = (start..end).into_iter().for_each(|ix| {
-            keys.append(
-                &mut self
-                    .disk
-                    .keys3(ix, None::<&Range<Pubkey>>)
-                    .unwrap_or_default(),
-            )
-        });
-        keys.sort_unstable();
-        Keys { keys, index: 0 }
+    /// Up to `limit` of the lowest entries in `range`, sorted by pubkey. Entries read from disk
+    /// are not brought into memory.
+    pub fn range<R>(&self, range: R, limit: usize) -> Vec<(Pubkey, AccountMapEntry<T>)>
+    where
+        R: RangeBounds<Pubkey> + Clone,
+    {
+        let mut items: Vec<_> = self
+            .in_mem
+            .range(range.clone())
+            .take(limit)
+            .map(|(pubkey, entry)| (*pubkey, entry.clone()))
+            .collect();
+        let storage = match &self.storage {
+            Some(storage) => storage,
+            None => return items,
+        };
+        // The lowest `limit` entries of the map are among the lowest `limit` of each part
+        let age = storage.current_age();
+        items.extend(
+            storage
+                .disk
+                .items_in_range(self.bin, Some(&range), limit)
+                .into_iter()
+                .filter(|item| !self.in_mem.contains_key(&item.pubkey))
+                .map(|item| {
+                    let meta = AccountMapEntryMeta::new_clean(age);
+                    let entry = AccountMapEntryInner::new(item.slot_list, item.ref_count, meta);
+                    (item.pubkey, Arc::new(entry))
+                }),
+        );
+        items.sort_unstable_by_key(|(pubkey, _)| *pubkey);
+        items.truncate(limit);
+        items
     }
-    pub fn values(&self) -> Values<V> {
-        let num_buckets = self.disk.num_buckets();
-        if self.bin_index != 0 && self.disk.unified_backing {
-            return Values {
-                values: vec![],
-                index: 0,
-            };
-        }
-        // todo: this may be unsafe if we are asking for things with an update cache active. thankfully, we only call values at startup right now
-        let start = num_buckets * self.bin_index / self.bins;
-        let end = num_buckets * (self.bin_index + 1) / self.bins;
-        let len = (start..end)
-            .into_iter()
-            .map(|ix| self.disk.bucket_len(ix) as usize)
-            .sum::<usize>();
-        let mut values = Vec::with_capacity(len);
-        (start..end).into_iter().for_each(|ix| {
-            values.append(
-                &mut self
+
+    /// All pubkeys, sorted
+    pub fn keys(&self) -> Vec<Pubkey> {
+        let mut keys: Vec<_> = self.in_mem.keys().cloned().collect();
+        if let Some(storage) = &self.storage {
+            keys.extend(
+                storage
                     .disk
-                    .values(ix, None::<&Range<Pubkey>>)
-                    .unwrap_or_default(),
-            )
-        });
-        //error!("getting values: {}, bin: {}, bins: {}, start: {}, end: {}", values.len(), self.bin_index, self.bins, start, end);
-        //keys.sort_unstable();
-        if self.bin_index == 0 {
-            //error!("getting values: {}, {}, {}", values.len(), start, end);
+                    .keys(self.bin)
+                    .into_iter()
+                    .filter(|pubkey| !self.in_mem.contains_key(pubkey)),
+            );
+            keys.sort_unstable();
         }
-        Values { values, index: 0 }
-    }
-
-    pub fn upsert(
-        &self,
-        pubkey: &Pubkey,
-        new_value: AccountMapEntry<V>,
-        reclaims: &mut SlotList<V>,
-        reclaims_must_be_empty: bool,
-    ) {
-        self.disk
-            .upsert(pubkey, new_value, reclaims, reclaims_must_be_empty);
+        keys
     }
 
-    pub fn entry(&mut self, key: K) -> HybridEntry<'_, V> {
-        match self.disk.get(&key) {
-            Some(entry) => HybridEntry::Occupied(HybridOccupiedEntry {
-                pubkey: key,
-                entry: AccountMapEntry::<V> {
-                    slot_list: entry.1,
-                    ref_count: entry.0,
-                },
-                map: self,
-            }),
-            None => HybridEntry::Vacant(HybridVacantEntry {
-                pubkey: key,
-                map: self,
-            }),
+    /// Write changed entries to disk, then evict the entries which haven't been accessed for
+    /// `AGES_TO_STAY_IN_CACHE` ages. If the bin is over its memory budget, the least recently
+    /// accessed entries are evicted until it isn't. Entries referenced outside the map may be
+    /// in the middle of an update, so they are left alone.
+    pub fn flush(&mut self) {
+        let storage = match &self.storage {
+            Some(storage) => storage.clone(),
+            None => return,
+        };
+        let mut flush_time = Measure::start("flush");
+        let mut flushed = 0;
+        // (ages since last access, pubkey)
+        let mut evictable = vec![];
+        for (pubkey, entry) in self.in_mem.iter() {
+            if Arc::strong_count(entry) > 1 {
+                continue;
+            }
+            if entry.meta.dirty.swap(false, Ordering::Relaxed) {
+                let slot_list = entry.slot_list.read().unwrap();
+                storage.disk.insert(pubkey, (&slot_list, entry.ref_count()));
+                flushed += 1;
+                if !entry.meta.on_disk.swap(true, Ordering::Relaxed) {
+                    // new entries get a full stay in memory from their first flush
+                    entry
+                        .meta
+                        .age
+                        .store(storage.current_age(), Ordering::Relaxed);
+                    continue;
+                }
+            }
+            let ages = storage.ages_since(entry.meta.age.load(Ordering::Relaxed));
+            evictable.push((ages, *pubkey));
         }
-    }
 
-    pub fn insert2(&mut self, key: K, value: V2<V>) {
-        match self.entry(key) {
-            HybridEntry::Occupied(_occupied) => {
-                panic!("");
+        let mut evict = evictable
+            .iter()
+            .filter(|(ages, _)| *ages >= AGES_TO_STAY_IN_CACHE)
+            .count();
+        if let Some(max_in_mem) = storage.max_in_mem_per_bin() {
+            let over_budget = self.in_mem.len().saturating_sub(max_in_mem);
+            evict = evict.max(over_budget.min(evictable.len()));
+        }
+        if evict > 0 {
+            // least recently accessed first
+            evictable.sort_unstable_by_key(|(ages, _)| Reverse(*ages));
+            for (_, pubkey) in evictable.into_iter().take(evict) {
+                self.in_mem.remove(&pubkey);
             }
-            HybridEntry::Vacant(vacant) => vacant.insert(value),
         }
+        flush_time.stop();
+
+        let stats = &storage.stats;
+        stats.flushed.fetch_add(flushed, Ordering::Relaxed);
+        stats.evicted.fetch_add(evict as u64, Ordering::Relaxed);
+        stats
+            .flush_us
+            .fetch_add(flush_time.as_us(), Ordering::Relaxed);
     }
+}
 
-    pub fn get(&self, key: &K) -> Option<V2<V>> {
-        let lookup = || {
-            let disk = self.disk.get(key);
-            disk.map(|disk| AccountMapEntry {
-                ref_count: disk.0,
-                slot_list: disk.1,
-            })
-        };
+#[cfg(test)]
+mod tests {
+    use {super::*, crate::accounts_index::AccountsIndexConfig};
 
-        if true {
-            lookup()
-        } else {
-            let in_mem = self.in_memory.get(key);
-            match in_mem {
-                Some(in_mem) => Some(in_mem.clone()),
-                None => {
-                    // we have to load this into the in-mem cache so we can get a ref_count, if nothing else
-                    lookup()
-                    /*
-                    disk.map(|item| {
-                        self.in_memory.entry(*key).map(|entry| {
-
-                        }
-                    })*/
-                }
-            }
-        }
-    }
-    pub fn remove(&mut self, key: &K) {
-        self.disk.delete_key(key); //.map(|x| x.entry)
+    fn new_disk_map(
+        index_limit_mb: Option<usize>,
+    ) -> (HybridBTreeMap<u64>, Arc<BucketMapHolder<u64>>) {
+        let config = AccountsIndexConfig {
+            index_limit_mb,
+            ..AccountsIndexConfig::default()
+        };
+        let holder = Arc::new(BucketMapHolder::new(1, &config));
+        (HybridBTreeMap::new(Some(holder.clone()), 0), holder)
     }
-    pub fn len(&self) -> usize {
-        self.disk.keys3(self.bin_index, None::<&Range<Pubkey>>).map(|x| x.len()).unwrap_or_default()
+
+    fn insert(map: &mut HybridBTreeMap<u64>, pubkey: Pubkey, slot: Slot, info: u64) {
+        map.entry(pubkey)
+            .or_insert_with(|| WriteAccountMapEntry::new_entry_after_update(slot, info));
     }
 
-    pub fn set_startup(&self, startup: bool) {
-        self.disk.set_startup(startup);
+    fn age_out(map: &mut HybridBTreeMap<u64>, holder: &BucketMapHolder<u64>) {
+        for _ in 0..AGES_TO_STAY_IN_CACHE {
+            holder.increment_age();
+        }
+        map.flush();
+    }
+
+    #[test]
+    fn test_flush_and_page_in() {
+        let (mut map, holder) = new_disk_map(None);
+        let pubkey = Pubkey::new_unique();
+        insert(&mut map, pubkey, 1, 10);
+        assert_eq!(map.len(), 1);
+        assert!(!map.needs_page_in(&pubkey));
+
+        // the first flush writes the new entry but keeps it in memory
+        map.flush();
+        assert_eq!(holder.disk.read_value(&pubkey), Some((vec![(1, 10)], 1)));
+        assert_eq!(map.in_mem_len(), 1);
+        assert_eq!(map.len(), 1);
+
+        age_out(&mut map, &holder);
+        assert_eq!(map.in_mem_len(), 0);
+        assert_eq!(map.len(), 1);
+        assert!(map.get(&pubkey).is_none());
+        assert!(map.needs_page_in(&pubkey));
+
+        // changes to a paged in entry are written back before it is evicted again
+        let entry = map.page_in(&pubkey).unwrap().clone();
+        assert_eq!(entry.ref_count(), 1);
+        WriteAccountMapEntry::from_account_map_entry(entry).update(2, 20, &mut vec![]);
+        assert!(!map.needs_page_in(&pubkey));
+        age_out(&mut map, &holder);
+        assert_eq!(map.in_mem_len(), 0);
+        assert_eq!(
+            holder.disk.read_value(&pubkey),
+            Some((vec![(1, 10), (2, 20)], 2))
+        );
+        assert_eq!(holder.stats.pages_in.load(Ordering::Relaxed), 1);
+
+        // referenced entries stay in memory
+        let entry = map.page_in(&pubkey).unwrap().clone();
+        age_out(&mut map, &holder);
+        assert_eq!(map.in_mem_len(), 1);
+        drop(entry);
+
+        map.remove(&pubkey);
+        assert!(map.is_empty());
+        assert!(!holder.disk.contains_key(&pubkey));
+    }
+
+    #[test]
+    fn test_flush_memory_budget() {
+        let (mut map, holder) = new_disk_map(Some(1));
+        let max_in_mem = holder.max_in_mem_per_bin().unwrap();
+        let pubkeys: Vec<_> = (0..max_in_mem + 10).map(|_| Pubkey::new_unique()).collect();
+        for pubkey in &pubkeys {
+            insert(&mut map, *pubkey, 1, 10);
+        }
+        map.flush();
+        holder.increment_age();
+        // touch the first entries, which makes them the most recently used
+        for pubkey in &pubkeys[..5] {
+            assert!(map.get(pubkey).is_some());
+        }
+        map.flush();
+        assert_eq!(map.in_mem_len(), max_in_mem);
+        assert_eq!(map.len(), pubkeys.len());
+        for pubkey in &pubkeys[..5] {
+            assert!(!map.needs_page_in(pubkey));
+        }
     }
 
-    pub fn update_or_insert_async(&self, pubkey: Pubkey, new_entry: AccountMapEntry<V>) {
-        self.disk
-            .update_or_insert_async(self.bin_index, pubkey, new_entry);
+    #[test]
+    fn test_insert_on_disk() {
+        let (mut map, holder) = new_disk_map(None);
+        let pubkey = Pubkey::new_unique();
+        assert!(!map.insert_on_disk(pubkey, 1, 10));
+        assert!(map.insert_on_disk(pubkey, 2, 20));
+        assert_eq!(map.in_mem_len(), 0);
+        assert_eq!(
+            holder.disk.read_value(&pubkey),
+            Some((vec![(1, 10), (2, 20)], 2))
+        );
+
+        // resident entries are updated in memory
+        map.page_in(&pubkey);
+        assert!(map.insert_on_disk(pubkey, 2, 21));
+        assert_eq!(
+            *map.get(&pubkey).unwrap().slot_list.read().unwrap(),
+            vec![(1, 10), (2, 21)]
+        );
     }
-    pub fn dump_metrics(&self) {
-        self.disk.dump_metrics();
+
+    #[test]
+    fn test_range_and_keys() {
+        let (mut map, holder) = new_disk_map(None);
+        let mut pubkeys: Vec<_> = (0..6).map(|_| Pubkey::new_unique()).collect();
+        pubkeys.sort();
+        for pubkey in &pubkeys[..3] {
+            insert(&mut map, *pubkey, 1, 10);
+        }
+        map.flush();
+        age_out(&mut map, &holder);
+        for pubkey in &pubkeys[3..] {
+            insert(&mut map, *pubkey, 1, 10);
+        }
+        assert_eq!(map.in_mem_len(), 3);
+        assert_eq!(map.keys(), pubkeys);
+        let range = |limit| {
+            map.range(pubkeys[1]..pubkeys[5], limit)
+                .into_iter()
+                .map(|(pubkey, _)| pubkey)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(range(usize::MAX), pubkeys[1..5].to_vec());
+        // the limit applies across memory and disk
+        assert_eq!(range(1), pubkeys[1..2].to_vec());
+        assert_eq!(range(3), pubkeys[1..4].to_vec());
+        // nothing was paged in
+        assert_eq!(map.in_mem_len(), 3);
+
+        let mut in_mem_map = HybridBTreeMap::<u64>::new(None, 0);
+        for pubkey in &pubkeys {
+            insert(&mut in_mem_map, *pubkey, 1, 10);
+        }
+        assert_eq!(in_mem_map.range(.., 2).len(), 2);
+        assert_eq!(in_mem_map.keys(), pubkeys);
     }
 }
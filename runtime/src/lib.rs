@@ -1,10 +1,12 @@
 #![cfg_attr(RUSTC_WITH_SPECIALIZATION, feature(min_specialization))]
 #![allow(clippy::integer_arithmetic)]
+pub mod account_write_log;
 pub mod accounts;
 pub mod accounts_background_service;
 pub mod accounts_cache;
 pub mod accounts_db;
 pub mod accounts_hash;
+pub mod accounts_hash_cache;
 pub mod accounts_index;
 pub mod ancestors;
 pub mod append_vec;
@@ -14,12 +16,15 @@ pub mod bank_forks;
 pub mod bank_utils;
 pub mod blockhash_queue;
 pub mod bloom;
+mod bucket_map_holder;
 pub mod builtins;
 pub mod commitment;
+pub mod compute_profiler;
 pub mod contains;
 pub mod epoch_stakes;
 pub mod genesis_utils;
 pub mod hardened_unpack;
+mod hybrid_btree_map;
 pub mod inline_spl_token_v2_0;
 pub mod instruction_recorder;
 pub mod loader_utils;
@@ -1,6 +1,7 @@
 use crate::{
-    accounts::Accounts, ancestors::Ancestors, instruction_recorder::InstructionRecorder,
-    log_collector::LogCollector, native_loader::NativeLoader, rent_collector::RentCollector,
+    accounts::Accounts, ancestors::Ancestors, compute_profiler::ThisComputeProfiler,
+    instruction_recorder::InstructionRecorder, log_collector::LogCollector,
+    native_loader::NativeLoader, rent_collector::RentCollector,
 };
 use log::*;
 use serde::{Deserialize, Serialize};
@@ -22,7 +23,7 @@ use solana_sdk::{
     message::Message,
     native_loader,
     process_instruction::{
-        ComputeMeter, Executor, InvokeContext, InvokeContextStackFrame, Logger,
+        ComputeMeter, ComputeProfiler, Executor, InvokeContext, InvokeContextStackFrame, Logger,
         ProcessInstructionWithContext,
     },
     pubkey::Pubkey,
@@ -297,6 +298,7 @@ pub struct ThisInvokeContext<'a> {
     #[allow(deprecated)]
     bpf_compute_budget: solana_sdk::process_instruction::BpfComputeBudget,
     compute_meter: Rc<RefCell<dyn ComputeMeter>>,
+    compute_profiler: Option<Rc<RefCell<ThisComputeProfiler>>>,
     executors: Rc<RefCell<Executors>>,
     instruction_recorder: Option<InstructionRecorder>,
     feature_set: Arc<FeatureSet>,
@@ -319,6 +321,7 @@ impl<'a> ThisInvokeContext<'a> {
         accounts: &'a [(Pubkey, Rc<RefCell<AccountSharedData>>)],
         programs: &'a [(Pubkey, ProcessInstructionWithContext)],
         log_collector: Option<Rc<LogCollector>>,
+        compute_profiler: Option<Rc<RefCell<ThisComputeProfiler>>>,
         compute_budget: ComputeBudget,
         compute_meter: Rc<RefCell<dyn ComputeMeter>>,
         executors: Rc<RefCell<Executors>>,
@@ -353,6 +356,7 @@ impl<'a> ThisInvokeContext<'a> {
             compute_budget,
             bpf_compute_budget: compute_budget.into(),
             compute_meter,
+            compute_profiler,
             executors,
             instruction_recorder,
             feature_set,
@@ -553,6 +557,11 @@ impl<'a> InvokeContext for ThisInvokeContext<'a> {
     fn get_fee_calculator(&self) -> &FeeCalculator {
         self.fee_calculator
     }
+    fn get_compute_profiler(&self) -> Option<Rc<RefCell<dyn ComputeProfiler>>> {
+        self.compute_profiler
+            .clone()
+            .map(|compute_profiler| compute_profiler as Rc<RefCell<dyn ComputeProfiler>>)
+    }
 }
 pub struct ThisLogger {
     log_collector: Option<Rc<LogCollector>>,
@@ -1173,6 +1182,7 @@ impl MessageProcessor {
         accounts: &[(Pubkey, Rc<RefCell<AccountSharedData>>)],
         rent_collector: &RentCollector,
         log_collector: Option<Rc<LogCollector>>,
+        compute_profiler: Option<Rc<RefCell<ThisComputeProfiler>>>,
         executors: Rc<RefCell<Executors>>,
         instruction_recorder: Option<InstructionRecorder>,
         instruction_index: usize,
@@ -1220,6 +1230,7 @@ impl MessageProcessor {
             accounts,
             &self.programs,
             log_collector,
+            compute_profiler,
             compute_budget,
             compute_meter,
             executors,
@@ -1260,6 +1271,7 @@ impl MessageProcessor {
         accounts: &[(Pubkey, Rc<RefCell<AccountSharedData>>)],
         rent_collector: &RentCollector,
         log_collector: Option<Rc<LogCollector>>,
+        compute_profiler: Option<Rc<RefCell<ThisComputeProfiler>>>,
         executors: Rc<RefCell<Executors>>,
         instruction_recorders: Option<&[InstructionRecorder]>,
         feature_set: Arc<FeatureSet>,
@@ -1277,6 +1289,11 @@ impl MessageProcessor {
             let instruction_recorder = instruction_recorders
                 .as_ref()
                 .map(|recorders| recorders[instruction_index].clone());
+            if let Some(compute_profiler) = &compute_profiler {
+                compute_profiler
+                    .borrow_mut()
+                    .set_instruction_index(instruction_index);
+            }
             let err = self
                 .execute_instruction(
                     message,
@@ -1285,6 +1302,7 @@ impl MessageProcessor {
                     accounts,
                     rent_collector,
                     log_collector.clone(),
+                    compute_profiler.clone(),
                     executors.clone(),
                     instruction_recorder,
                     instruction_index,
@@ -1370,6 +1388,7 @@ mod tests {
             &accounts,
             &[],
             None,
+            None,
             ComputeBudget::default(),
             Rc::new(RefCell::new(MockComputeMeter::default())),
             Rc::new(RefCell::new(Executors::default())),
@@ -1983,6 +2002,7 @@ mod tests {
             &accounts,
             &rent_collector,
             None,
+            None,
             executors.clone(),
             None,
             Arc::new(FeatureSet::all_enabled()),
@@ -2013,6 +2033,7 @@ mod tests {
             &accounts,
             &rent_collector,
             None,
+            None,
             executors.clone(),
             None,
             Arc::new(FeatureSet::all_enabled()),
@@ -2047,6 +2068,7 @@ mod tests {
             &accounts,
             &rent_collector,
             None,
+            None,
             executors,
             None,
             Arc::new(FeatureSet::all_enabled()),
@@ -2173,6 +2195,7 @@ mod tests {
             &accounts,
             &rent_collector,
             None,
+            None,
             executors.clone(),
             None,
             Arc::new(FeatureSet::all_enabled()),
@@ -2207,6 +2230,7 @@ mod tests {
             &accounts,
             &rent_collector,
             None,
+            None,
             executors.clone(),
             None,
             Arc::new(FeatureSet::all_enabled()),
@@ -2239,6 +2263,7 @@ mod tests {
             &accounts,
             &rent_collector,
             None,
+            None,
             executors,
             None,
             Arc::new(FeatureSet::all_enabled()),
@@ -2348,6 +2373,7 @@ mod tests {
             &accounts,
             programs.as_slice(),
             None,
+            None,
             ComputeBudget::default(),
             Rc::new(RefCell::new(MockComputeMeter::default())),
             Rc::new(RefCell::new(Executors::default())),
@@ -2409,6 +2435,7 @@ mod tests {
                 &accounts,
                 programs.as_slice(),
                 None,
+                None,
                 ComputeBudget::default(),
                 Rc::new(RefCell::new(MockComputeMeter::default())),
                 Rc::new(RefCell::new(Executors::default())),
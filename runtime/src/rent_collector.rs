@@ -6,10 +6,32 @@ use solana_sdk::{
     genesis_config::GenesisConfig,
     incinerator,
     pubkey::Pubkey,
-    rent::Rent,
+    rent::{Rent, ACCOUNT_STORAGE_OVERHEAD},
     sysvar,
 };
 
+/// An account that is charged rent and what it is charged
+#[derive(Clone, PartialEq, Debug)]
+pub struct RentPayingAccount {
+    pub pubkey: Pubkey,
+    pub owner: Pubkey,
+    pub lamports: u64,
+    pub data_len: usize,
+    /// Balance at which the account would be rent exempt
+    pub rent_exempt_minimum: u64,
+    /// Rent charged for the next epoch, in whole lamports
+    pub rent_per_epoch: u64,
+    /// Epochs of rent the balance pays for before the account is deleted
+    pub epochs_until_deletion: u64,
+}
+
+impl RentPayingAccount {
+    /// Lamports to add to make the account rent exempt
+    pub fn lamports_to_exempt(&self) -> u64 {
+        self.rent_exempt_minimum.saturating_sub(self.lamports)
+    }
+}
+
 #[derive(Serialize, Deserialize, Clone, PartialEq, Debug, AbiExample)]
 pub struct RentCollector {
     pub epoch: Epoch,
@@ -111,6 +133,45 @@ impl RentCollector {
         }
     }
 
+    /// The rent `account` is charged if it is not exempt from rent, projected from the length of
+    /// the next epoch
+    pub fn rent_paying_account(
+        &self,
+        address: &Pubkey,
+        account: &AccountSharedData,
+        rent_for_sysvars: bool,
+    ) -> Option<RentPayingAccount> {
+        if account.executable()
+            || account.lamports() == 0
+            || (!rent_for_sysvars && sysvar::check_id(account.owner()))
+            || *address == incinerator::id()
+            || self.slots_per_year == 0.0
+        {
+            return None;
+        }
+        let years_per_epoch =
+            self.epoch_schedule.get_slots_in_epoch(self.epoch + 1) as f64 / self.slots_per_year;
+        let (rent_per_epoch, exempt) =
+            self.rent
+                .due(account.lamports(), account.data().len(), years_per_epoch);
+        // rent under a lamport per epoch is not lost; it adds up until it is collected
+        let exact_rent_per_epoch = self.rent.lamports_per_byte_year as f64
+            * (account.data().len() as u64 + ACCOUNT_STORAGE_OVERHEAD) as f64
+            * years_per_epoch;
+        if exempt || exact_rent_per_epoch == 0.0 {
+            return None;
+        }
+        Some(RentPayingAccount {
+            pubkey: *address,
+            owner: *account.owner(),
+            lamports: account.lamports(),
+            data_len: account.data().len(),
+            rent_exempt_minimum: self.rent.minimum_balance(account.data().len()),
+            rent_per_epoch,
+            epochs_until_deletion: (account.lamports() as f64 / exact_rent_per_epoch) as u64,
+        })
+    }
+
     #[must_use = "add to Bank::collected_rent"]
     pub fn collect_from_created_account(
         &self,
@@ -172,6 +233,56 @@ mod tests {
         assert_eq!(created_account.rent_epoch(), existing_account.rent_epoch());
     }
 
+    #[test]
+    fn test_rent_paying_account() {
+        let rent_collector = RentCollector::default().clone_with_epoch(3);
+        let pubkey = solana_sdk::pubkey::new_rand();
+        let owner = solana_sdk::pubkey::new_rand();
+        let rent_exempt_minimum = rent_collector.rent.minimum_balance(10);
+        let mut account = AccountSharedData::new(rent_exempt_minimum / 2, 10, &owner);
+
+        let rent_paying = rent_collector
+            .rent_paying_account(&pubkey, &account, true)
+            .unwrap();
+        assert_eq!(rent_paying.pubkey, pubkey);
+        assert_eq!(rent_paying.owner, owner);
+        assert_eq!(rent_paying.data_len, 10);
+        assert_eq!(rent_paying.rent_exempt_minimum, rent_exempt_minimum);
+        assert_eq!(
+            rent_paying.lamports_to_exempt(),
+            rent_exempt_minimum - rent_exempt_minimum / 2
+        );
+        assert!(rent_paying.rent_per_epoch > 0);
+        assert!(rent_paying.epochs_until_deletion > 0);
+        assert!(
+            rent_paying.epochs_until_deletion * rent_paying.rent_per_epoch <= account.lamports()
+        );
+
+        // the rent charged for the next epoch matches the projection
+        let mut collected_account = account.clone();
+        collected_account.set_rent_epoch(3);
+        let collected =
+            rent_collector.collect_from_existing_account(&pubkey, &mut collected_account, true);
+        assert_eq!(collected, rent_paying.rent_per_epoch);
+
+        account.set_lamports(rent_exempt_minimum);
+        assert_eq!(
+            rent_collector.rent_paying_account(&pubkey, &account, true),
+            None
+        );
+        account.set_lamports(1);
+        account.set_executable(true);
+        assert_eq!(
+            rent_collector.rent_paying_account(&pubkey, &account, true),
+            None
+        );
+        account.set_executable(false);
+        assert_eq!(
+            rent_collector.rent_paying_account(&incinerator::id(), &account, true),
+            None
+        );
+    }
+
     #[test]
     fn test_rent_exempt_temporal_escape() {
         let mut account = AccountSharedData::default();
@@ -44,6 +44,9 @@ use {
 };
 
 pub const SNAPSHOT_STATUS_CACHE_FILE_NAME: &str = "status_cache";
+/// Under the ledger dir, where `solana-ledger-tool verify-accounts-hash` keeps its progress by
+/// default in case it is interrupted
+pub const ACCOUNTS_HASH_CACHE_DIR: &str = "accounts_hash_cache";
 
 pub const MAX_BANK_SNAPSHOTS: usize = 8; // Save some snapshots but not too many
 const MAX_SNAPSHOT_DATA_FILE_SIZE: u64 = 32 * 1024 * 1024 * 1024; // 32 GiB
@@ -729,6 +732,7 @@ pub fn bank_from_snapshot_archives(
     accounts_db_skip_shrink: bool,
     verify_index: bool,
     accounts_index_config: Option<AccountsIndexConfig>,
+    accounts_hash_cache_path: Option<PathBuf>,
 ) -> Result<(Bank, BankFromArchiveTimings)> {
     check_are_snapshots_compatible(
         full_snapshot_archive_info,
@@ -798,12 +802,14 @@ pub fn bank_from_snapshot_archives(
     info!("{}", measure_rebuild);
 
     let mut measure_verify = Measure::start("verify");
-    if !bank.verify_snapshot_bank(
+    bank.set_accounts_hash_cache_path(accounts_hash_cache_path);
+    let verified = bank.verify_snapshot_bank(
         test_hash_calculation,
         accounts_db_skip_shrink,
         Some(full_snapshot_archive_info.slot()),
-    ) && limit_load_slot_count_from_snapshot.is_none()
-    {
+    );
+    bank.set_accounts_hash_cache_path(None);
+    if !verified && limit_load_slot_count_from_snapshot.is_none() {
         panic!("Snapshot bank for slot {} failed to verify", bank.slot());
     }
     measure_verify.stop();
@@ -839,6 +845,7 @@ pub fn bank_from_latest_snapshot_archives(
     accounts_db_skip_shrink: bool,
     verify_index: bool,
     accounts_index_config: Option<AccountsIndexConfig>,
+    accounts_hash_cache_path: Option<PathBuf>,
 ) -> Result<(Bank, BankFromArchiveTimings)> {
     let full_snapshot_archive_info = get_highest_full_snapshot_archive_info(&snapshot_archives_dir)
         .ok_or(SnapshotError::NoSnapshotArchives)?;
@@ -877,6 +884,7 @@ pub fn bank_from_latest_snapshot_archives(
         accounts_db_skip_shrink,
         verify_index,
         accounts_index_config,
+        accounts_hash_cache_path,
     )?;
 
     verify_bank_against_expected_slot_hash(
@@ -1766,6 +1774,7 @@ pub fn process_accounts_package(
             crate::accounts_hash::HashStats::default(),
             false,
             None,
+            None,
         )
         .unwrap();
 
@@ -2526,6 +2535,7 @@ mod tests {
             false,
             false,
             Some(crate::accounts_index::ACCOUNTS_INDEX_CONFIG_FOR_TESTING),
+            None,
         )
         .unwrap();
 
@@ -2617,6 +2627,7 @@ mod tests {
             false,
             false,
             Some(crate::accounts_index::ACCOUNTS_INDEX_CONFIG_FOR_TESTING),
+            None,
         )
         .unwrap();
 
@@ -2727,6 +2738,7 @@ mod tests {
             false,
             false,
             Some(crate::accounts_index::ACCOUNTS_INDEX_CONFIG_FOR_TESTING),
+            None,
         )
         .unwrap();
 
@@ -2826,6 +2838,7 @@ mod tests {
             false,
             false,
             Some(crate::accounts_index::ACCOUNTS_INDEX_CONFIG_FOR_TESTING),
+            None,
         )
         .unwrap();
 
@@ -2967,6 +2980,7 @@ mod tests {
             false,
             false,
             Some(crate::accounts_index::ACCOUNTS_INDEX_CONFIG_FOR_TESTING),
+            None,
         )
         .unwrap();
         assert_eq!(
@@ -3029,6 +3043,7 @@ mod tests {
             false,
             false,
             Some(crate::accounts_index::ACCOUNTS_INDEX_CONFIG_FOR_TESTING),
+            None,
         )
         .unwrap();
         assert_eq!(
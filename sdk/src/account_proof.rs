@@ -0,0 +1,176 @@
+//! Merkle proofs that an account is included in an accounts hash
+//!
+//! The accounts hash of a slot is the root of a Merkle tree whose leaves are the hashes of all
+//! accounts with lamports, sorted by pubkey. Each node hashes up to `fanout` consecutive nodes of
+//! the level below, and the tree is built until a single node is left. An `AccountProof` holds,
+//! for each level from the leaves up, the other nodes hashed together with the account's.
+
+use {
+    crate::{
+        account::ReadableAccount,
+        blake3,
+        clock::{Epoch, Slot},
+        hash::{Hash, Hasher},
+        pubkey::Pubkey,
+    },
+    serde_derive::{Deserialize, Serialize},
+};
+
+/// The nodes hashed together with the node on the path to the root, at one level of the tree
+#[derive(Serialize, Deserialize, Debug, Default, Clone, PartialEq, Eq)]
+pub struct MerkleProofLevel {
+    /// Position of the node on the path among the nodes hashed together
+    pub index: usize,
+    /// The other nodes hashed together, in order
+    pub siblings: Vec<Hash>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Default, Clone, PartialEq, Eq)]
+pub struct AccountProof {
+    pub pubkey: Pubkey,
+    /// Slot the account was last written in, which its hash commits to
+    pub slot: Slot,
+    pub account_hash: Hash,
+    /// Levels from the leaves up to the root
+    pub levels: Vec<MerkleProofLevel>,
+}
+
+impl AccountProof {
+    /// The Merkle root `account_hash` leads to, or None if the proof is malformed
+    pub fn root(&self) -> Option<Hash> {
+        if self.levels.is_empty() {
+            return None;
+        }
+        self.levels
+            .iter()
+            .try_fold(self.account_hash, |node, level| {
+                if level.index > level.siblings.len() {
+                    return None;
+                }
+                let mut hasher = Hasher::default();
+                for sibling in &level.siblings[..level.index] {
+                    hasher.hash(sibling.as_ref());
+                }
+                hasher.hash(node.as_ref());
+                for sibling in &level.siblings[level.index..] {
+                    hasher.hash(sibling.as_ref());
+                }
+                Some(hasher.result())
+            })
+    }
+
+    /// Whether `account_hash` is included in `accounts_hash`
+    pub fn verify(&self, accounts_hash: &Hash) -> bool {
+        self.root().as_ref() == Some(accounts_hash)
+    }
+
+    /// Whether `account` is the state of `pubkey` included in `accounts_hash`
+    pub fn verify_account<T: ReadableAccount>(&self, account: &T, accounts_hash: &Hash) -> bool {
+        account.lamports() != 0
+            && hash_account(self.slot, account, &self.pubkey) == self.account_hash
+            && self.verify(accounts_hash)
+    }
+}
+
+/// The hash of an account as it appears in the accounts hash, stored in `slot`
+pub fn hash_account<T: ReadableAccount>(slot: Slot, account: &T, pubkey: &Pubkey) -> Hash {
+    hash_account_data(
+        slot,
+        account.lamports(),
+        account.owner(),
+        account.executable(),
+        account.rent_epoch(),
+        account.data(),
+        pubkey,
+    )
+}
+
+fn hash_account_data(
+    slot: Slot,
+    lamports: u64,
+    owner: &Pubkey,
+    executable: bool,
+    rent_epoch: Epoch,
+    data: &[u8],
+    pubkey: &Pubkey,
+) -> Hash {
+    if lamports == 0 {
+        return Hash::default();
+    }
+    let mut hasher = blake3::Hasher::default();
+    hasher.hash(&lamports.to_le_bytes());
+    hasher.hash(&slot.to_le_bytes());
+    hasher.hash(&rent_epoch.to_le_bytes());
+    hasher.hash(data);
+    hasher.hash(&[executable as u8]);
+    hasher.hash(owner.as_ref());
+    hasher.hash(pubkey.as_ref());
+    Hash::new_from_array(hasher.result().0)
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::account::{AccountSharedData, WritableAccount},
+    };
+
+    fn hashv(hashes: &[Hash]) -> Hash {
+        let mut hasher = Hasher::default();
+        for hash in hashes {
+            hasher.hash(hash.as_ref());
+        }
+        hasher.result()
+    }
+
+    #[test]
+    fn test_account_proof() {
+        let pubkey = Pubkey::new_unique();
+        let account = AccountSharedData::new(42, 3, &Pubkey::new_unique());
+        let account_hash = hash_account(5, &account, &pubkey);
+        let (a, b, c) = (Hash::new_unique(), Hash::new_unique(), Hash::new_unique());
+        let level_1 = hashv(&[a, account_hash, b]);
+        let root = hashv(&[level_1, c]);
+        let mut proof = AccountProof {
+            pubkey,
+            slot: 5,
+            account_hash,
+            levels: vec![
+                MerkleProofLevel {
+                    index: 1,
+                    siblings: vec![a, b],
+                },
+                MerkleProofLevel {
+                    index: 0,
+                    siblings: vec![c],
+                },
+            ],
+        };
+        assert_eq!(proof.root(), Some(root));
+        assert!(proof.verify(&root));
+        assert!(proof.verify_account(&account, &root));
+        assert!(!proof.verify(&level_1));
+
+        let mut other_account = account.clone();
+        other_account.set_lamports(43);
+        assert!(!proof.verify_account(&other_account, &root));
+        proof.slot = 6;
+        assert!(!proof.verify_account(&account, &root));
+
+        proof.levels[0].index = 3;
+        assert_eq!(proof.root(), None);
+        proof.levels.clear();
+        assert_eq!(proof.root(), None);
+    }
+
+    #[test]
+    fn test_single_account_proof() {
+        let account_hash = Hash::new_unique();
+        let proof = AccountProof {
+            account_hash,
+            levels: vec![MerkleProofLevel::default()],
+            ..AccountProof::default()
+        };
+        assert!(proof.verify(&hashv(&[account_hash])));
+    }
+}
@@ -10,6 +10,7 @@ pub use signer::signers;
 pub use solana_program::*;
 
 pub mod account;
+pub mod account_proof;
 pub mod account_utils;
 pub mod builtins;
 pub mod client;
@@ -113,6 +113,10 @@ pub trait InvokeContext {
     fn get_blockhash(&self) -> &Hash;
     /// Get this invocation's `FeeCalculator`
     fn get_fee_calculator(&self) -> &FeeCalculator;
+    /// Get this invocation's compute profiler, if profiling is enabled
+    fn get_compute_profiler(&self) -> Option<Rc<RefCell<dyn ComputeProfiler>>> {
+        None
+    }
 }
 
 /// Convenience macro to log a message with an `Rc<RefCell<dyn Logger>>`
@@ -253,6 +257,16 @@ impl BpfComputeBudget {
     }
 }
 
+/// Compute profiler, notified by loaders as programs execute
+pub trait ComputeProfiler {
+    /// A program starts executing with `remaining_units` left on the compute meter
+    fn enter(&mut self, program_id: &Pubkey, remaining_units: u64);
+    /// Record instructions the current program executed in the given function call stack
+    fn record_call_stack(&mut self, call_stack: &[&str], instructions: u64);
+    /// The current program finished executing with `remaining_units` left on the compute meter
+    fn exit(&mut self, remaining_units: u64);
+}
+
 /// Compute meter
 pub trait ComputeMeter {
     /// Consume compute units
@@ -77,6 +77,9 @@ pub enum Error {
 
     #[error("Timeout")]
     Timeout,
+
+    #[error("Local storage: {0}")]
+    LocalStorage(String),
 }
 
 impl std::convert::From<std::io::Error> for Error {
@@ -210,34 +213,28 @@ impl BigTableConnection {
         }
     }
 
-    pub async fn put_bincode_cells_with_retry<T>(
+    pub async fn put_row_data_with_retry(
         &self,
         table: &str,
-        cells: &[(RowKey, T)],
-    ) -> Result<usize>
-    where
-        T: serde::ser::Serialize,
-    {
+        row_data: &[(RowKey, RowData)],
+    ) -> Result<()> {
         use backoff::{future::retry, ExponentialBackoff};
         retry(ExponentialBackoff::default(), || async {
             let mut client = self.client();
-            Ok(client.put_bincode_cells(table, cells).await?)
+            Ok(client.put_row_data(table, "x", row_data).await?)
         })
         .await
     }
 
-    pub async fn put_protobuf_cells_with_retry<T>(
+    pub async fn delete_row_cells_with_retry(
         &self,
         table: &str,
-        cells: &[(RowKey, T)],
-    ) -> Result<usize>
-    where
-        T: prost::Message,
-    {
+        cells: &[(RowKey, Vec<CellName>)],
+    ) -> Result<()> {
         use backoff::{future::retry, ExponentialBackoff};
         retry(ExponentialBackoff::default(), || async {
             let mut client = self.client();
-            Ok(client.put_protobuf_cells(table, cells).await?)
+            Ok(client.delete_row_cells(table, "x", cells).await?)
         })
         .await
     }
@@ -486,10 +483,8 @@ impl<F: FnMut(Request<()>) -> InterceptedRequestResult> BigTable<F> {
         &mut self,
         table_name: &str,
         family_name: &str,
-        row_data: &[(&RowKey, RowData)],
+        row_data: &[(RowKey, RowData)],
     ) -> Result<()> {
-        self.refresh_access_token().await;
-
         let mut entries = vec![];
         for (row_key, row_data) in row_data {
             let mutations = row_data
@@ -505,11 +500,52 @@ impl<F: FnMut(Request<()>) -> InterceptedRequestResult> BigTable<F> {
                 .collect();
 
             entries.push(mutate_rows_request::Entry {
-                row_key: (*row_key).clone().into_bytes(),
+                row_key: row_key.clone().into_bytes(),
                 mutations,
             });
         }
 
+        self.mutate_rows(table_name, entries).await
+    }
+
+    /// Delete the named cells of one or more `table` rows in the `family_name` Column family
+    async fn delete_row_cells(
+        &mut self,
+        table_name: &str,
+        family_name: &str,
+        cells: &[(RowKey, Vec<CellName>)],
+    ) -> Result<()> {
+        let mut entries = vec![];
+        for (row_key, cell_names) in cells {
+            let mutations = cell_names
+                .iter()
+                .map(|cell_name| Mutation {
+                    mutation: Some(mutation::Mutation::DeleteFromColumn(
+                        mutation::DeleteFromColumn {
+                            family_name: family_name.to_string(),
+                            column_qualifier: cell_name.clone().into_bytes(),
+                            time_range: None, // all versions
+                        },
+                    )),
+                })
+                .collect();
+
+            entries.push(mutate_rows_request::Entry {
+                row_key: row_key.clone().into_bytes(),
+                mutations,
+            });
+        }
+
+        self.mutate_rows(table_name, entries).await
+    }
+
+    async fn mutate_rows(
+        &mut self,
+        table_name: &str,
+        entries: Vec<mutate_rows_request::Entry>,
+    ) -> Result<()> {
+        self.refresh_access_token().await;
+
         let mut response = self
             .client
             .mutate_rows(MutateRowsRequest {
@@ -524,8 +560,8 @@ impl<F: FnMut(Request<()>) -> InterceptedRequestResult> BigTable<F> {
             for entry in res.entries {
                 if let Some(status) = entry.status {
                     if status.code != 0 {
-                        eprintln!("put_row_data error {}: {}", status.code, status.message);
-                        warn!("put_row_data error {}: {}", status.code, status.message);
+                        eprintln!("mutate_rows error {}: {}", status.code, status.message);
+                        warn!("mutate_rows error {}: {}", status.code, status.message);
                         return Err(Error::RowWriteFailed);
                     }
                 }
@@ -534,69 +570,24 @@ impl<F: FnMut(Request<()>) -> InterceptedRequestResult> BigTable<F> {
 
         Ok(())
     }
+}
 
-    pub async fn get_bincode_cell<T>(&mut self, table: &str, key: RowKey) -> Result<T>
-    where
-        T: serde::de::DeserializeOwned,
-    {
-        let row_data = self.get_single_row_data(table, key.clone()).await?;
-        deserialize_bincode_cell_data(&row_data, table, key.to_string())
-    }
-
-    pub async fn get_protobuf_or_bincode_cell<B, P>(
-        &mut self,
-        table: &str,
-        key: RowKey,
-    ) -> Result<CellData<B, P>>
-    where
-        B: serde::de::DeserializeOwned,
-        P: prost::Message + Default,
-    {
-        let row_data = self.get_single_row_data(table, key.clone()).await?;
-        deserialize_protobuf_or_bincode_cell_data(&row_data, table, key)
-    }
-
-    pub async fn put_bincode_cells<T>(
-        &mut self,
-        table: &str,
-        cells: &[(RowKey, T)],
-    ) -> Result<usize>
-    where
-        T: serde::ser::Serialize,
-    {
-        let mut bytes_written = 0;
-        let mut new_row_data = vec![];
-        for (row_key, data) in cells {
-            let data = compress_best(&bincode::serialize(&data).unwrap())?;
-            bytes_written += data.len();
-            new_row_data.push((row_key, vec![("bin".to_string(), data)]));
-        }
-
-        self.put_row_data(table, "x", &new_row_data).await?;
-        Ok(bytes_written)
-    }
-
-    pub async fn put_protobuf_cells<T>(
-        &mut self,
-        table: &str,
-        cells: &[(RowKey, T)],
-    ) -> Result<usize>
-    where
-        T: prost::Message,
-    {
-        let mut bytes_written = 0;
-        let mut new_row_data = vec![];
-        for (row_key, data) in cells {
-            let mut buf = Vec::with_capacity(data.encoded_len());
-            data.encode(&mut buf).unwrap();
-            let data = compress_best(&buf)?;
-            bytes_written += data.len();
-            new_row_data.push((row_key, vec![("proto".to_string(), data)]));
-        }
+pub(crate) fn bincode_cell_data<T>(data: &T) -> Result<(CellName, CellValue)>
+where
+    T: serde::ser::Serialize,
+{
+    let data = compress_best(&bincode::serialize(data).unwrap())?;
+    Ok(("bin".to_string(), data))
+}
 
-        self.put_row_data(table, "x", &new_row_data).await?;
-        Ok(bytes_written)
-    }
+pub(crate) fn protobuf_cell_data<T>(data: &T) -> Result<(CellName, CellValue)>
+where
+    T: prost::Message,
+{
+    let mut buf = Vec::with_capacity(data.encoded_len());
+    data.encode(&mut buf).unwrap();
+    let data = compress_best(&buf)?;
+    Ok(("proto".to_string(), data))
 }
 
 pub(crate) fn deserialize_protobuf_or_bincode_cell_data<B, P>(
@@ -1,5 +1,6 @@
 #![allow(clippy::integer_arithmetic)]
 use {
+    futures::future::BoxFuture,
     log::*,
     serde::{Deserialize, Serialize},
     solana_sdk::{
@@ -26,8 +27,14 @@ extern crate serde_derive;
 mod access_token;
 mod bigtable;
 mod compression;
+mod local_storage;
 mod root_ca_certificate;
 
+pub use {
+    bigtable::{CellName, CellValue, RowData, RowDataSlice, RowKey},
+    local_storage::LocalLedgerStorage,
+};
+
 #[derive(Debug, Error)]
 pub enum Error {
     #[error("BigTable: {0}")]
@@ -44,6 +51,9 @@ pub enum Error {
 
     #[error("Signature not found")]
     SignatureNotFound,
+
+    #[error("Table has no protobuf encoding: {0}")]
+    MigrationNotSupported(String),
 }
 
 impl std::convert::From<bigtable::Error> for Error {
@@ -305,26 +315,70 @@ impl From<LegacyTransactionByAddrInfo> for TransactionByAddrInfo {
     }
 }
 
-#[derive(Clone)]
-pub struct LedgerStorage {
-    connection: bigtable::BigTableConnection,
+/// Outcome of `LedgerStorage::migrate_rows_to_protobuf()`
+#[derive(Debug, Default, PartialEq)]
+pub struct MigrateRowsResult {
+    /// Number of rows read
+    pub rows_read: usize,
+    /// Number of rows that held a bincode cell and now only hold a protobuf cell
+    pub rows_migrated: usize,
+    /// Key of the last row read, None if no rows were read
+    pub last_row_key: Option<RowKey>,
 }
 
-impl LedgerStorage {
-    pub async fn new(read_only: bool, timeout: Option<std::time::Duration>) -> Result<Self> {
-        let connection =
-            bigtable::BigTableConnection::new("solana-ledger", read_only, timeout).await?;
-        Ok(Self { connection })
-    }
+/// Long-term storage of confirmed blocks in the `blocks`, `tx` and `tx-by-addr` tables. The
+/// backends provide BigTable-style row access, rows being ordered lexically by key, and share the
+/// row encoding.
+pub trait LedgerStorage: Send + Sync {
+    /// Get `table` row keys in lexical order, from `start_at` to `end_at` inclusive. If
+    /// `rows_limit` is zero, the listing continues until `end_at` or the end of the table.
+    fn get_row_keys<'a>(
+        &'a self,
+        table_name: &'a str,
+        start_at: Option<RowKey>,
+        end_at: Option<RowKey>,
+        rows_limit: i64,
+    ) -> BoxFuture<'a, bigtable::Result<Vec<RowKey>>>;
+
+    /// Get `table` rows in lexical order, with the same bounds as `get_row_keys`
+    fn get_row_data<'a>(
+        &'a self,
+        table_name: &'a str,
+        start_at: Option<RowKey>,
+        end_at: Option<RowKey>,
+        rows_limit: i64,
+    ) -> BoxFuture<'a, bigtable::Result<Vec<(RowKey, RowData)>>>;
+
+    /// Get a single row of `table`. Returns `RowNotFound` if the row does not exist.
+    fn get_single_row_data<'a>(
+        &'a self,
+        table_name: &'a str,
+        row_key: RowKey,
+    ) -> BoxFuture<'a, bigtable::Result<RowData>>;
+
+    /// Store cells of one or more `table` rows, replacing any cells of the same name
+    fn put_row_data<'a>(
+        &'a self,
+        table_name: &'a str,
+        row_data: &'a [(RowKey, RowData)],
+    ) -> BoxFuture<'a, bigtable::Result<()>>;
+
+    /// Delete the named cells of one or more `table` rows
+    fn delete_row_cells<'a>(
+        &'a self,
+        table_name: &'a str,
+        cells: &'a [(RowKey, Vec<CellName>)],
+    ) -> BoxFuture<'a, bigtable::Result<()>>;
 
     /// Return the available slot that contains a block
-    pub async fn get_first_available_block(&self) -> Result<Option<Slot>> {
-        let mut bigtable = self.connection.client();
-        let blocks = bigtable.get_row_keys("blocks", None, None, 1).await?;
-        if blocks.is_empty() {
-            return Ok(None);
-        }
-        Ok(key_to_slot(&blocks[0]))
+    fn get_first_available_block(&self) -> BoxFuture<'_, Result<Option<Slot>>> {
+        Box::pin(async move {
+            let blocks = self.get_row_keys("blocks", None, None, 1).await?;
+            if blocks.is_empty() {
+                return Ok(None);
+            }
+            Ok(key_to_slot(&blocks[0]))
+        })
     }
 
     /// Fetch the next slots after the provided slot that contains a block
@@ -332,86 +386,96 @@ impl LedgerStorage {
     /// start_slot: slot to start the search from (inclusive)
     /// limit: stop after this many slots have been found; if limit==0, all records in the table
     /// after start_slot will be read
-    pub async fn get_confirmed_blocks(&self, start_slot: Slot, limit: usize) -> Result<Vec<Slot>> {
-        let mut bigtable = self.connection.client();
-        let blocks = bigtable
-            .get_row_keys("blocks", Some(slot_to_key(start_slot)), None, limit as i64)
-            .await?;
-        Ok(blocks.into_iter().filter_map(|s| key_to_slot(&s)).collect())
+    fn get_confirmed_blocks(
+        &self,
+        start_slot: Slot,
+        limit: usize,
+    ) -> BoxFuture<'_, Result<Vec<Slot>>> {
+        Box::pin(async move {
+            let blocks = self
+                .get_row_keys("blocks", Some(slot_to_key(start_slot)), None, limit as i64)
+                .await?;
+            Ok(blocks.into_iter().filter_map(|s| key_to_slot(&s)).collect())
+        })
     }
 
     /// Fetch the confirmed block from the desired slot
-    pub async fn get_confirmed_block(&self, slot: Slot) -> Result<ConfirmedBlock> {
-        let mut bigtable = self.connection.client();
-        let block_cell_data = bigtable
-            .get_protobuf_or_bincode_cell::<StoredConfirmedBlock, generated::ConfirmedBlock>(
-                "blocks",
-                slot_to_key(slot),
-            )
+    fn get_confirmed_block(&self, slot: Slot) -> BoxFuture<'_, Result<ConfirmedBlock>> {
+        Box::pin(async move {
+            let block_cell_data = get_protobuf_or_bincode_cell::<
+                _,
+                StoredConfirmedBlock,
+                generated::ConfirmedBlock,
+            >(self, "blocks", slot_to_key(slot))
             .await
             .map_err(|err| match err {
                 bigtable::Error::RowNotFound => Error::BlockNotFound(slot),
                 _ => err.into(),
             })?;
-        Ok(match block_cell_data {
-            bigtable::CellData::Bincode(block) => block.into(),
-            bigtable::CellData::Protobuf(block) => block.try_into().map_err(|_err| {
-                bigtable::Error::ObjectCorrupt(format!("blocks/{}", slot_to_key(slot)))
-            })?,
+            Ok(match block_cell_data {
+                bigtable::CellData::Bincode(block) => block.into(),
+                bigtable::CellData::Protobuf(block) => block.try_into().map_err(|_err| {
+                    bigtable::Error::ObjectCorrupt(format!("blocks/{}", slot_to_key(slot)))
+                })?,
+            })
         })
     }
 
-    pub async fn get_signature_status(&self, signature: &Signature) -> Result<TransactionStatus> {
-        let mut bigtable = self.connection.client();
-        let transaction_info = bigtable
-            .get_bincode_cell::<TransactionInfo>("tx", signature.to_string())
-            .await
-            .map_err(|err| match err {
-                bigtable::Error::RowNotFound => Error::SignatureNotFound,
-                _ => err.into(),
-            })?;
-        Ok(transaction_info.into())
+    fn get_signature_status<'a>(
+        &'a self,
+        signature: &'a Signature,
+    ) -> BoxFuture<'a, Result<TransactionStatus>> {
+        Box::pin(async move {
+            let transaction_info =
+                get_bincode_cell::<_, TransactionInfo>(self, "tx", signature.to_string())
+                    .await
+                    .map_err(|err| match err {
+                        bigtable::Error::RowNotFound => Error::SignatureNotFound,
+                        _ => err.into(),
+                    })?;
+            Ok(transaction_info.into())
+        })
     }
 
     /// Fetch a confirmed transaction
-    pub async fn get_confirmed_transaction(
-        &self,
-        signature: &Signature,
-    ) -> Result<Option<ConfirmedTransaction>> {
-        let mut bigtable = self.connection.client();
-
-        // Figure out which block the transaction is located in
-        let TransactionInfo { slot, index, .. } = bigtable
-            .get_bincode_cell("tx", signature.to_string())
-            .await
-            .map_err(|err| match err {
-                bigtable::Error::RowNotFound => Error::SignatureNotFound,
-                _ => err.into(),
-            })?;
-
-        // Load the block and return the transaction
-        let block = self.get_confirmed_block(slot).await?;
-        match block.transactions.into_iter().nth(index as usize) {
-            None => {
-                warn!("Transaction info for {} is corrupt", signature);
-                Ok(None)
-            }
-            Some(bucket_block_transaction) => {
-                if bucket_block_transaction.transaction.signatures[0] != *signature {
-                    warn!(
-                        "Transaction info or confirmed block for {} is corrupt",
-                        signature
-                    );
+    fn get_confirmed_transaction<'a>(
+        &'a self,
+        signature: &'a Signature,
+    ) -> BoxFuture<'a, Result<Option<ConfirmedTransaction>>> {
+        Box::pin(async move {
+            // Figure out which block the transaction is located in
+            let TransactionInfo { slot, index, .. } =
+                get_bincode_cell(self, "tx", signature.to_string())
+                    .await
+                    .map_err(|err| match err {
+                        bigtable::Error::RowNotFound => Error::SignatureNotFound,
+                        _ => err.into(),
+                    })?;
+
+            // Load the block and return the transaction
+            let block = self.get_confirmed_block(slot).await?;
+            match block.transactions.into_iter().nth(index as usize) {
+                None => {
+                    warn!("Transaction info for {} is corrupt", signature);
                     Ok(None)
-                } else {
-                    Ok(Some(ConfirmedTransaction {
-                        slot,
-                        transaction: bucket_block_transaction,
-                        block_time: block.block_time,
-                    }))
+                }
+                Some(bucket_block_transaction) => {
+                    if bucket_block_transaction.transaction.signatures[0] != *signature {
+                        warn!(
+                            "Transaction info or confirmed block for {} is corrupt",
+                            signature
+                        );
+                        Ok(None)
+                    } else {
+                        Ok(Some(ConfirmedTransaction {
+                            slot,
+                            transaction: bucket_block_transaction,
+                            block_time: block.block_time,
+                        }))
+                    }
                 }
             }
-        }
+        })
     }
 
     /// Get confirmed signatures for the provided address, in descending ledger order
@@ -420,230 +484,688 @@ impl LedgerStorage {
     /// before_signature: start with the first signature older than this one
     /// until_signature: end with the last signature more recent than this one
     /// limit: stop after this many signatures; if limit==0, all records in the table will be read
-    pub async fn get_confirmed_signatures_for_address(
-        &self,
-        address: &Pubkey,
-        before_signature: Option<&Signature>,
-        until_signature: Option<&Signature>,
+    fn get_confirmed_signatures_for_address<'a>(
+        &'a self,
+        address: &'a Pubkey,
+        before_signature: Option<&'a Signature>,
+        until_signature: Option<&'a Signature>,
         limit: usize,
-    ) -> Result<
-        Vec<(
-            ConfirmedTransactionStatusWithSignature,
-            u32, /*slot index*/
-        )>,
+    ) -> BoxFuture<
+        'a,
+        Result<
+            Vec<(
+                ConfirmedTransactionStatusWithSignature,
+                u32, /*slot index*/
+            )>,
+        >,
     > {
-        let mut bigtable = self.connection.client();
-        let address_prefix = format!("{}/", address);
-
-        // Figure out where to start listing from based on `before_signature`
-        let (first_slot, before_transaction_index) = match before_signature {
-            None => (Slot::MAX, 0),
-            Some(before_signature) => {
-                let TransactionInfo { slot, index, .. } = bigtable
-                    .get_bincode_cell("tx", before_signature.to_string())
-                    .await?;
-
-                (slot, index)
-            }
-        };
+        Box::pin(async move {
+            let address_prefix = format!("{}/", address);
 
-        // Figure out where to end listing from based on `until_signature`
-        let (last_slot, until_transaction_index) = match until_signature {
-            None => (0, u32::MAX),
-            Some(until_signature) => {
-                let TransactionInfo { slot, index, .. } = bigtable
-                    .get_bincode_cell("tx", until_signature.to_string())
-                    .await?;
+            // Figure out where to start listing from based on `before_signature`
+            let (first_slot, before_transaction_index) = match before_signature {
+                None => (Slot::MAX, 0),
+                Some(before_signature) => {
+                    let TransactionInfo { slot, index, .. } =
+                        get_bincode_cell(self, "tx", before_signature.to_string()).await?;
 
-                (slot, index)
-            }
-        };
+                    (slot, index)
+                }
+            };
+
+            // Figure out where to end listing from based on `until_signature`
+            let (last_slot, until_transaction_index) = match until_signature {
+                None => (0, u32::MAX),
+                Some(until_signature) => {
+                    let TransactionInfo { slot, index, .. } =
+                        get_bincode_cell(self, "tx", until_signature.to_string()).await?;
+
+                    (slot, index)
+                }
+            };
 
-        let mut infos = vec![];
+            let mut infos = vec![];
 
-        let starting_slot_tx_len = bigtable
-            .get_protobuf_or_bincode_cell::<Vec<LegacyTransactionByAddrInfo>, tx_by_addr::TransactionByAddr>(
+            let starting_slot_tx_len = get_protobuf_or_bincode_cell::<
+                _,
+                Vec<LegacyTransactionByAddrInfo>,
+                tx_by_addr::TransactionByAddr,
+            >(
+                self,
                 "tx-by-addr",
                 format!("{}{}", address_prefix, slot_to_key(!first_slot)),
             )
             .await
-            .map(|cell_data| {
-                match cell_data {
-                    bigtable::CellData::Bincode(tx_by_addr) => tx_by_addr.len(),
-                    bigtable::CellData::Protobuf(tx_by_addr) => tx_by_addr.tx_by_addrs.len(),
-                }
+            .map(|cell_data| match cell_data {
+                bigtable::CellData::Bincode(tx_by_addr) => tx_by_addr.len(),
+                bigtable::CellData::Protobuf(tx_by_addr) => tx_by_addr.tx_by_addrs.len(),
             })
             .unwrap_or(0);
 
-        // Return the next tx-by-addr data of amount `limit` plus extra to account for the largest
-        // number that might be flitered out
-        let tx_by_addr_data = bigtable
-            .get_row_data(
-                "tx-by-addr",
-                Some(format!("{}{}", address_prefix, slot_to_key(!first_slot))),
-                Some(format!("{}{}", address_prefix, slot_to_key(!last_slot))),
-                limit as i64 + starting_slot_tx_len as i64,
-            )
-            .await?;
-
-        'outer: for (row_key, data) in tx_by_addr_data {
-            let slot = !key_to_slot(&row_key[address_prefix.len()..]).ok_or_else(|| {
-                bigtable::Error::ObjectCorrupt(format!(
-                    "Failed to convert key to slot: tx-by-addr/{}",
-                    row_key
-                ))
-            })?;
-
-            let deserialized_cell_data = bigtable::deserialize_protobuf_or_bincode_cell_data::<
-                Vec<LegacyTransactionByAddrInfo>,
-                tx_by_addr::TransactionByAddr,
-            >(&data, "tx-by-addr", row_key.clone())?;
+            // Return the next tx-by-addr data of amount `limit` plus extra to account for the
+            // largest number that might be flitered out
+            let tx_by_addr_data = self
+                .get_row_data(
+                    "tx-by-addr",
+                    Some(format!("{}{}", address_prefix, slot_to_key(!first_slot))),
+                    Some(format!("{}{}", address_prefix, slot_to_key(!last_slot))),
+                    limit as i64 + starting_slot_tx_len as i64,
+                )
+                .await?;
 
-            let mut cell_data: Vec<TransactionByAddrInfo> = match deserialized_cell_data {
-                bigtable::CellData::Bincode(tx_by_addr) => {
-                    tx_by_addr.into_iter().map(|legacy| legacy.into()).collect()
-                }
-                bigtable::CellData::Protobuf(tx_by_addr) => {
-                    tx_by_addr.try_into().map_err(|error| {
-                        bigtable::Error::ObjectCorrupt(format!(
-                            "Failed to deserialize: {}: tx-by-addr/{}",
-                            error,
-                            row_key.clone()
-                        ))
-                    })?
+            'outer: for (row_key, data) in tx_by_addr_data {
+                let slot = !key_to_slot(&row_key[address_prefix.len()..]).ok_or_else(|| {
+                    bigtable::Error::ObjectCorrupt(format!(
+                        "Failed to convert key to slot: tx-by-addr/{}",
+                        row_key
+                    ))
+                })?;
+
+                let mut cell_data = deserialize_tx_by_addr_cell_data(&data, &row_key)?;
+
+                cell_data.reverse();
+                for tx_by_addr_info in cell_data.into_iter() {
+                    // Filter out records before `before_transaction_index`
+                    if slot == first_slot && tx_by_addr_info.index >= before_transaction_index {
+                        continue;
+                    }
+                    // Filter out records after `until_transaction_index`
+                    if slot == last_slot && tx_by_addr_info.index <= until_transaction_index {
+                        continue;
+                    }
+                    infos.push((
+                        ConfirmedTransactionStatusWithSignature {
+                            signature: tx_by_addr_info.signature,
+                            slot,
+                            err: tx_by_addr_info.err,
+                            memo: tx_by_addr_info.memo,
+                            block_time: tx_by_addr_info.block_time,
+                        },
+                        tx_by_addr_info.index,
+                    ));
+                    // Respect limit
+                    if infos.len() >= limit {
+                        break 'outer;
+                    }
                 }
-            };
+            }
+            Ok(infos)
+        })
+    }
 
-            cell_data.reverse();
-            for tx_by_addr_info in cell_data.into_iter() {
-                // Filter out records before `before_transaction_index`
-                if slot == first_slot && tx_by_addr_info.index >= before_transaction_index {
-                    continue;
-                }
-                // Filter out records after `until_transaction_index`
-                if slot == last_slot && tx_by_addr_info.index <= until_transaction_index {
-                    continue;
+    // Upload a new confirmed block and associated meta data.
+    fn upload_confirmed_block(
+        &self,
+        slot: Slot,
+        confirmed_block: ConfirmedBlock,
+    ) -> BoxFuture<'_, Result<()>> {
+        Box::pin(async move {
+            let mut bytes_written = 0;
+
+            let mut by_addr: HashMap<&Pubkey, Vec<TransactionByAddrInfo>> = HashMap::new();
+
+            let mut tx_cells = vec![];
+            for (index, transaction_with_meta) in confirmed_block.transactions.iter().enumerate() {
+                let TransactionWithStatusMeta { meta, transaction } = transaction_with_meta;
+                let err = meta.as_ref().and_then(|meta| meta.status.clone().err());
+                let index = index as u32;
+                let signature = transaction.signatures[0];
+
+                for address in &transaction.message.account_keys {
+                    if !is_sysvar_id(address) {
+                        by_addr
+                            .entry(address)
+                            .or_default()
+                            .push(TransactionByAddrInfo {
+                                signature,
+                                err: err.clone(),
+                                index,
+                                memo: None, // TODO
+                                block_time: confirmed_block.block_time,
+                            });
+                    }
                 }
-                infos.push((
-                    ConfirmedTransactionStatusWithSignature {
-                        signature: tx_by_addr_info.signature,
+
+                tx_cells.push((
+                    signature.to_string(),
+                    TransactionInfo {
                         slot,
-                        err: tx_by_addr_info.err,
-                        memo: tx_by_addr_info.memo,
-                        block_time: tx_by_addr_info.block_time,
+                        index,
+                        err,
+                        memo: None, // TODO
                     },
-                    tx_by_addr_info.index,
                 ));
-                // Respect limit
-                if infos.len() >= limit {
-                    break 'outer;
-                }
             }
-        }
-        Ok(infos)
+
+            let tx_by_addr_cells: Vec<_> = by_addr
+                .into_iter()
+                .map(|(address, transaction_info_by_addr)| {
+                    (
+                        format!("{}/{}", address, slot_to_key(!slot)),
+                        tx_by_addr::TransactionByAddr {
+                            tx_by_addrs: transaction_info_by_addr
+                                .into_iter()
+                                .map(|by_addr| by_addr.into())
+                                .collect(),
+                        },
+                    )
+                })
+                .collect();
+
+            if !tx_cells.is_empty() {
+                bytes_written += put_bincode_cells(self, "tx", &tx_cells).await?;
+            }
+
+            if !tx_by_addr_cells.is_empty() {
+                bytes_written += put_protobuf_cells(self, "tx-by-addr", &tx_by_addr_cells).await?;
+            }
+
+            let num_transactions = confirmed_block.transactions.len();
+
+            // Store the block itself last, after all other metadata about the block has been
+            // successfully stored.  This avoids partial uploaded blocks from becoming visible to
+            // `get_confirmed_block()` and `get_confirmed_blocks()`
+            let blocks_cells = [(
+                slot_to_key(slot),
+                generated::ConfirmedBlock::from(confirmed_block),
+            )];
+            bytes_written += put_protobuf_cells(self, "blocks", &blocks_cells).await?;
+            info!(
+                "uploaded block for slot {}: {} transactions, {} bytes",
+                slot, num_transactions, bytes_written
+            );
+
+            Ok(())
+        })
     }
 
-    // Upload a new confirmed block and associated meta data.
-    pub async fn upload_confirmed_block(
-        &self,
-        slot: Slot,
-        confirmed_block: ConfirmedBlock,
-    ) -> Result<()> {
-        let mut bytes_written = 0;
-
-        let mut by_addr: HashMap<&Pubkey, Vec<TransactionByAddrInfo>> = HashMap::new();
-
-        let mut tx_cells = vec![];
-        for (index, transaction_with_meta) in confirmed_block.transactions.iter().enumerate() {
-            let TransactionWithStatusMeta { meta, transaction } = transaction_with_meta;
-            let err = meta.as_ref().and_then(|meta| meta.status.clone().err());
-            let index = index as u32;
-            let signature = transaction.signatures[0];
-
-            for address in &transaction.message.account_keys {
-                if !is_sysvar_id(address) {
-                    by_addr
-                        .entry(address)
-                        .or_default()
-                        .push(TransactionByAddrInfo {
-                            signature,
-                            err: err.clone(),
-                            index,
-                            memo: None, // TODO
-                            block_time: confirmed_block.block_time,
-                        });
+    /// Rewrite the legacy bincode rows of the `blocks` or `tx-by-addr` table as protobuf, reading
+    /// up to `rows_limit` rows after `start_after`. The bincode cell of a row is only deleted once
+    /// its protobuf cell has been stored, so an interrupted migration can resume from any earlier
+    /// row.
+    fn migrate_rows_to_protobuf<'a>(
+        &'a self,
+        table_name: &'a str,
+        start_after: Option<RowKey>,
+        rows_limit: i64,
+    ) -> BoxFuture<'a, Result<MigrateRowsResult>> {
+        Box::pin(async move {
+            if !matches!(table_name, "blocks" | "tx-by-addr") {
+                return Err(Error::MigrationNotSupported(table_name.to_string()));
+            }
+
+            // Appending a NUL yields the smallest row key that follows `start_after`
+            let start_at = start_after.map(|row_key| format!("{}\0", row_key));
+            let rows = self
+                .get_row_data(table_name, start_at, None, rows_limit)
+                .await?;
+
+            let mut protobuf_cells = vec![];
+            let mut bincode_cells = vec![];
+            for (row_key, row_data) in &rows {
+                let has_cell = |name: &str| row_data.iter().any(|(cell_name, _)| cell_name == name);
+                if !has_cell("bin") {
+                    continue;
                 }
+                // A row holding both cells was interrupted after storing its protobuf cell
+                if !has_cell("proto") {
+                    let cell = if table_name == "blocks" {
+                        let block: StoredConfirmedBlock = bigtable::deserialize_bincode_cell_data(
+                            row_data,
+                            table_name,
+                            row_key.clone(),
+                        )?;
+                        bigtable::protobuf_cell_data(&generated::ConfirmedBlock::from(
+                            ConfirmedBlock::from(block),
+                        ))?
+                    } else {
+                        let tx_by_addrs: Vec<LegacyTransactionByAddrInfo> =
+                            bigtable::deserialize_bincode_cell_data(
+                                row_data,
+                                table_name,
+                                row_key.clone(),
+                            )?;
+                        bigtable::protobuf_cell_data(&tx_by_addr::TransactionByAddr {
+                            tx_by_addrs: tx_by_addrs
+                                .into_iter()
+                                .map(|legacy| TransactionByAddrInfo::from(legacy).into())
+                                .collect(),
+                        })?
+                    };
+                    protobuf_cells.push((row_key.clone(), vec![cell]));
+                }
+                bincode_cells.push((row_key.clone(), vec!["bin".to_string()]));
             }
 
-            tx_cells.push((
-                signature.to_string(),
-                TransactionInfo {
-                    slot,
-                    index,
-                    err,
-                    memo: None, // TODO
-                },
-            ));
-        }
+            if !protobuf_cells.is_empty() {
+                self.put_row_data(table_name, &protobuf_cells).await?;
+            }
+            if !bincode_cells.is_empty() {
+                self.delete_row_cells(table_name, &bincode_cells).await?;
+            }
 
-        let tx_by_addr_cells: Vec<_> = by_addr
-            .into_iter()
-            .map(|(address, transaction_info_by_addr)| {
-                (
-                    format!("{}/{}", address, slot_to_key(!slot)),
-                    tx_by_addr::TransactionByAddr {
-                        tx_by_addrs: transaction_info_by_addr
-                            .into_iter()
-                            .map(|by_addr| by_addr.into())
-                            .collect(),
-                    },
-                )
+            Ok(MigrateRowsResult {
+                rows_read: rows.len(),
+                rows_migrated: bincode_cells.len(),
+                last_row_key: rows.last().map(|(row_key, _)| row_key.clone()),
             })
-            .collect();
+        })
+    }
+}
 
-        if !tx_cells.is_empty() {
-            bytes_written += self
-                .connection
-                .put_bincode_cells_with_retry::<TransactionInfo>("tx", &tx_cells)
-                .await?;
-        }
+/// Ledger storage on a BigTable instance
+#[derive(Clone)]
+pub struct BigTableLedgerStorage {
+    connection: bigtable::BigTableConnection,
+}
 
-        if !tx_by_addr_cells.is_empty() {
-            bytes_written += self
-                .connection
-                .put_protobuf_cells_with_retry::<tx_by_addr::TransactionByAddr>(
-                    "tx-by-addr",
-                    &tx_by_addr_cells,
-                )
-                .await?;
-        }
+impl BigTableLedgerStorage {
+    pub async fn new(read_only: bool, timeout: Option<std::time::Duration>) -> Result<Self> {
+        let connection =
+            bigtable::BigTableConnection::new("solana-ledger", read_only, timeout).await?;
+        Ok(Self { connection })
+    }
+}
 
-        let num_transactions = confirmed_block.transactions.len();
-
-        // Store the block itself last, after all other metadata about the block has been
-        // successfully stored.  This avoids partial uploaded blocks from becoming visible to
-        // `get_confirmed_block()` and `get_confirmed_blocks()`
-        let blocks_cells = [(slot_to_key(slot), confirmed_block.into())];
-        bytes_written += self
-            .connection
-            .put_protobuf_cells_with_retry::<generated::ConfirmedBlock>("blocks", &blocks_cells)
-            .await?;
-        info!(
-            "uploaded block for slot {}: {} transactions, {} bytes",
-            slot, num_transactions, bytes_written
-        );
+impl LedgerStorage for BigTableLedgerStorage {
+    fn get_row_keys<'a>(
+        &'a self,
+        table_name: &'a str,
+        start_at: Option<RowKey>,
+        end_at: Option<RowKey>,
+        rows_limit: i64,
+    ) -> BoxFuture<'a, bigtable::Result<Vec<RowKey>>> {
+        Box::pin(async move {
+            let mut bigtable = self.connection.client();
+            bigtable
+                .get_row_keys(table_name, start_at, end_at, rows_limit)
+                .await
+        })
+    }
+
+    fn get_row_data<'a>(
+        &'a self,
+        table_name: &'a str,
+        start_at: Option<RowKey>,
+        end_at: Option<RowKey>,
+        rows_limit: i64,
+    ) -> BoxFuture<'a, bigtable::Result<Vec<(RowKey, RowData)>>> {
+        Box::pin(async move {
+            let mut bigtable = self.connection.client();
+            bigtable
+                .get_row_data(table_name, start_at, end_at, rows_limit)
+                .await
+        })
+    }
+
+    fn get_single_row_data<'a>(
+        &'a self,
+        table_name: &'a str,
+        row_key: RowKey,
+    ) -> BoxFuture<'a, bigtable::Result<RowData>> {
+        Box::pin(async move {
+            let mut bigtable = self.connection.client();
+            bigtable.get_single_row_data(table_name, row_key).await
+        })
+    }
+
+    fn put_row_data<'a>(
+        &'a self,
+        table_name: &'a str,
+        row_data: &'a [(RowKey, RowData)],
+    ) -> BoxFuture<'a, bigtable::Result<()>> {
+        Box::pin(
+            self.connection
+                .put_row_data_with_retry(table_name, row_data),
+        )
+    }
+
+    fn delete_row_cells<'a>(
+        &'a self,
+        table_name: &'a str,
+        cells: &'a [(RowKey, Vec<CellName>)],
+    ) -> BoxFuture<'a, bigtable::Result<()>> {
+        Box::pin(
+            self.connection
+                .delete_row_cells_with_retry(table_name, cells),
+        )
+    }
+}
+
+async fn get_bincode_cell<S, T>(storage: &S, table: &str, key: RowKey) -> bigtable::Result<T>
+where
+    S: LedgerStorage + ?Sized,
+    T: serde::de::DeserializeOwned,
+{
+    let row_data = storage.get_single_row_data(table, key.clone()).await?;
+    bigtable::deserialize_bincode_cell_data(&row_data, table, key)
+}
+
+async fn get_protobuf_or_bincode_cell<S, B, P>(
+    storage: &S,
+    table: &str,
+    key: RowKey,
+) -> bigtable::Result<bigtable::CellData<B, P>>
+where
+    S: LedgerStorage + ?Sized,
+    B: serde::de::DeserializeOwned,
+    P: prost::Message + Default,
+{
+    let row_data = storage.get_single_row_data(table, key.clone()).await?;
+    bigtable::deserialize_protobuf_or_bincode_cell_data(&row_data, table, key)
+}
 
-        Ok(())
+async fn put_bincode_cells<S, T>(
+    storage: &S,
+    table: &str,
+    cells: &[(RowKey, T)],
+) -> bigtable::Result<usize>
+where
+    S: LedgerStorage + ?Sized,
+    T: serde::ser::Serialize,
+{
+    let mut bytes_written = 0;
+    let mut new_row_data = vec![];
+    for (row_key, data) in cells {
+        let cell = bigtable::bincode_cell_data(data)?;
+        bytes_written += cell.1.len();
+        new_row_data.push((row_key.clone(), vec![cell]));
     }
+    storage.put_row_data(table, &new_row_data).await?;
+    Ok(bytes_written)
+}
+
+async fn put_protobuf_cells<S, T>(
+    storage: &S,
+    table: &str,
+    cells: &[(RowKey, T)],
+) -> bigtable::Result<usize>
+where
+    S: LedgerStorage + ?Sized,
+    T: prost::Message,
+{
+    let mut bytes_written = 0;
+    let mut new_row_data = vec![];
+    for (row_key, data) in cells {
+        let cell = bigtable::protobuf_cell_data(data)?;
+        bytes_written += cell.1.len();
+        new_row_data.push((row_key.clone(), vec![cell]));
+    }
+    storage.put_row_data(table, &new_row_data).await?;
+    Ok(bytes_written)
+}
+
+fn deserialize_tx_by_addr_cell_data(
+    row_data: RowDataSlice,
+    row_key: &str,
+) -> bigtable::Result<Vec<TransactionByAddrInfo>> {
+    let deserialized_cell_data = bigtable::deserialize_protobuf_or_bincode_cell_data::<
+        Vec<LegacyTransactionByAddrInfo>,
+        tx_by_addr::TransactionByAddr,
+    >(row_data, "tx-by-addr", row_key.to_string())?;
+
+    Ok(match deserialized_cell_data {
+        bigtable::CellData::Bincode(tx_by_addr) => {
+            tx_by_addr.into_iter().map(|legacy| legacy.into()).collect()
+        }
+        bigtable::CellData::Protobuf(tx_by_addr) => tx_by_addr.try_into().map_err(|error| {
+            bigtable::Error::ObjectCorrupt(format!(
+                "Failed to deserialize: {}: tx-by-addr/{}",
+                error, row_key
+            ))
+        })?,
+    })
 }
 
 #[cfg(test)]
 mod test {
-    use super::*;
+    use {
+        super::*,
+        solana_sdk::{
+            hash::Hash,
+            signature::{Keypair, Signer},
+            system_transaction,
+        },
+        std::convert::TryFrom,
+    };
+
+    // A block that survives the legacy bincode encoding unchanged
+    fn legacy_confirmed_block(from: &Keypair, slot: Slot) -> ConfirmedBlock {
+        let recipient = solana_sdk::pubkey::new_rand();
+        let transaction = system_transaction::transfer(from, &recipient, 42, Hash::default());
+        ConfirmedBlock {
+            transactions: vec![TransactionWithStatusMeta {
+                transaction,
+                meta: Some(TransactionStatusMeta {
+                    status: Ok(()),
+                    fee: 1,
+                    pre_balances: vec![43, 0, 1],
+                    post_balances: vec![0, 42, 1],
+                    inner_instructions: None,
+                    log_messages: None,
+                    pre_token_balances: None,
+                    post_token_balances: None,
+                    rewards: None,
+                }),
+            }],
+            parent_slot: slot - 1,
+            blockhash: Hash::new_unique().to_string(),
+            previous_blockhash: Hash::new_unique().to_string(),
+            rewards: vec![Reward {
+                pubkey: from.pubkey().to_string(),
+                lamports: 10,
+                post_balance: 0,
+                reward_type: None,
+                commission: None,
+            }],
+            block_time: Some(1_234_567_890),
+            block_height: Some(slot),
+        }
+    }
+
+    fn cell_names(row_data: &[(CellName, CellValue)]) -> Vec<&str> {
+        row_data.iter().map(|(name, _)| name.as_str()).collect()
+    }
+
+    // Store `slot` as legacy bincode rows, migrate them and check that nothing but the encoding
+    // changed
+    async fn check_migrate_rows_to_protobuf(storage: &dyn LedgerStorage, slot: Slot) {
+        let from = Keypair::new();
+        let block = legacy_confirmed_block(&from, slot);
+        let signature = block.transactions[0].transaction.signatures[0];
+        let tx_by_addr_key = format!("{}/{}", from.pubkey(), slot_to_key(!slot));
+        let tx_by_addr = vec![LegacyTransactionByAddrInfo {
+            signature,
+            err: None,
+            index: 0,
+            memo: None,
+        }];
+        put_bincode_cells(
+            storage,
+            "tx",
+            &[(
+                signature.to_string(),
+                TransactionInfo {
+                    slot,
+                    index: 0,
+                    err: None,
+                    memo: None,
+                },
+            )],
+        )
+        .await
+        .unwrap();
+        put_bincode_cells(
+            storage,
+            "tx-by-addr",
+            &[(tx_by_addr_key.clone(), tx_by_addr)],
+        )
+        .await
+        .unwrap();
+        put_bincode_cells(
+            storage,
+            "blocks",
+            &[(slot_to_key(slot), StoredConfirmedBlock::from(block.clone()))],
+        )
+        .await
+        .unwrap();
+
+        let signatures = storage
+            .get_confirmed_signatures_for_address(&from.pubkey(), None, None, 10)
+            .await
+            .unwrap();
+        assert_eq!(storage.get_confirmed_block(slot).await.unwrap(), block);
+
+        for table_name in &["blocks", "tx-by-addr"] {
+            let mut start_after = None;
+            loop {
+                let result = storage
+                    .migrate_rows_to_protobuf(table_name, start_after, 1)
+                    .await
+                    .unwrap();
+                if result.rows_read == 0 {
+                    break;
+                }
+                assert_eq!(result.rows_read, 1);
+                start_after = result.last_row_key;
+            }
+        }
+
+        let row_data = storage
+            .get_single_row_data("blocks", slot_to_key(slot))
+            .await
+            .unwrap();
+        assert_eq!(cell_names(&row_data), vec!["proto"]);
+        let row_data = storage
+            .get_single_row_data("tx-by-addr", tx_by_addr_key)
+            .await
+            .unwrap();
+        assert_eq!(cell_names(&row_data), vec!["proto"]);
+        assert_eq!(storage.get_confirmed_block(slot).await.unwrap(), block);
+        assert_eq!(
+            storage
+                .get_confirmed_signatures_for_address(&from.pubkey(), None, None, 10)
+                .await
+                .unwrap(),
+            signatures
+        );
+        assert_eq!(
+            storage
+                .get_confirmed_transaction(&signature)
+                .await
+                .unwrap()
+                .unwrap()
+                .transaction,
+            block.transactions[0]
+        );
+    }
 
     #[test]
     fn test_slot_to_key() {
         assert_eq!(slot_to_key(0), "0000000000000000");
         assert_eq!(slot_to_key(!0), "ffffffffffffffff");
     }
+
+    #[test]
+    fn test_stored_confirmed_block_round_trip() {
+        let block = legacy_confirmed_block(&Keypair::new(), 1);
+        let (name, data) =
+            bigtable::bincode_cell_data(&StoredConfirmedBlock::from(block.clone())).unwrap();
+        assert_eq!(name, "bin");
+        let stored: StoredConfirmedBlock =
+            bigtable::deserialize_bincode_cell_data(&[(name, data)], "blocks", "".to_string())
+                .unwrap();
+        assert_eq!(ConfirmedBlock::from(stored), block);
+
+        let (name, data) =
+            bigtable::protobuf_cell_data(&generated::ConfirmedBlock::from(block.clone())).unwrap();
+        assert_eq!(name, "proto");
+        let protobuf: generated::ConfirmedBlock =
+            bigtable::deserialize_protobuf_cell_data(&[(name, data)], "blocks", "".to_string())
+                .unwrap();
+        assert_eq!(ConfirmedBlock::try_from(protobuf).unwrap(), block);
+    }
+
+    #[test]
+    fn test_tx_by_addr_round_trip() {
+        let legacy = LegacyTransactionByAddrInfo {
+            signature: Signature::new(&[1; 64]),
+            err: Some(TransactionError::AccountInUse),
+            index: 3,
+            memo: Some("memo".to_string()),
+        };
+        let info = TransactionByAddrInfo::from(legacy.clone());
+        let bincode_row = vec![bigtable::bincode_cell_data(&vec![legacy]).unwrap()];
+        assert_eq!(
+            deserialize_tx_by_addr_cell_data(&bincode_row, "key").unwrap(),
+            vec![info.clone()]
+        );
+
+        let protobuf_row = vec![
+            bigtable::protobuf_cell_data(&tx_by_addr::TransactionByAddr {
+                tx_by_addrs: vec![info.clone().into()],
+            })
+            .unwrap(),
+        ];
+        assert_eq!(
+            deserialize_tx_by_addr_cell_data(&protobuf_row, "key").unwrap(),
+            vec![info]
+        );
+    }
+
+    #[test]
+    fn test_migrate_rows_to_protobuf() {
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        let ledger_path = tempfile::TempDir::new().unwrap();
+        let storage = LocalLedgerStorage::open(ledger_path.path(), false).unwrap();
+        runtime.block_on(check_migrate_rows_to_protobuf(&storage, 10));
+
+        // Migrated rows are left alone
+        let result = runtime
+            .block_on(storage.migrate_rows_to_protobuf("blocks", None, 0))
+            .unwrap();
+        assert_eq!(result.rows_read, 1);
+        assert_eq!(result.rows_migrated, 0);
+        assert!(matches!(
+            runtime.block_on(storage.migrate_rows_to_protobuf("tx", None, 0)),
+            Err(Error::MigrationNotSupported(_))
+        ));
+    }
+
+    // Needs the BigTable emulator named by BIGTABLE_EMULATOR_HOST, configured with
+    // `init-bigtable.sh`
+    #[test]
+    #[ignore]
+    fn test_bigtable_emulator() {
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        let storage = runtime
+            .block_on(BigTableLedgerStorage::new(false, None))
+            .unwrap();
+
+        let slot = 1_000_000;
+        let from = Keypair::new();
+        let mut block = legacy_confirmed_block(&from, slot);
+        block.rewards[0].reward_type = Some(solana_transaction_status::RewardType::Fee);
+        let signature = block.transactions[0].transaction.signatures[0];
+        runtime
+            .block_on(storage.upload_confirmed_block(slot, block.clone()))
+            .unwrap();
+        assert_eq!(
+            runtime.block_on(storage.get_confirmed_block(slot)).unwrap(),
+            block
+        );
+        assert_eq!(
+            runtime
+                .block_on(storage.get_signature_status(&signature))
+                .unwrap()
+                .slot,
+            slot
+        );
+        let signatures = runtime
+            .block_on(storage.get_confirmed_signatures_for_address(&from.pubkey(), None, None, 1))
+            .unwrap();
+        assert_eq!(signatures[0].0.signature, signature);
+
+        runtime.block_on(check_migrate_rows_to_protobuf(&storage, slot + 1));
+    }
 }
@@ -0,0 +1,422 @@
+// Ledger storage kept in a local RocksDB database instead of BigTable, holding the same rows
+
+use {
+    crate::{
+        bigtable::{CellName, Error, Result, RowData, RowKey},
+        LedgerStorage,
+    },
+    futures::future::BoxFuture,
+    rocksdb::{ColumnFamily, Direction, IteratorMode, Options, WriteBatch, DB},
+    std::{
+        path::Path,
+        sync::{Arc, Mutex},
+    },
+};
+
+// Each table is stored in a column family of the same name
+const TABLES: &[&str] = &["blocks", "tx", "tx-by-addr"];
+
+fn local_storage_error(err: impl std::fmt::Display) -> Error {
+    Error::LocalStorage(err.to_string())
+}
+
+#[derive(Clone)]
+pub struct LocalLedgerStorage {
+    db: Arc<DB>,
+    // Read-only storage is a RocksDB secondary instance that follows the writer
+    secondary: bool,
+    // Serializes the read-modify-write of rows in `put_row_data`
+    write_lock: Arc<Mutex<()>>,
+}
+
+impl LocalLedgerStorage {
+    /// Open the storage at `path`, creating it if it does not exist unless `read_only`
+    pub fn open(path: &Path, read_only: bool) -> crate::Result<Self> {
+        let mut options = Options::default();
+        let db = if read_only {
+            // This is needed according to https://github.com/facebook/rocksdb/wiki/Secondary-instance
+            options.set_max_open_files(-1);
+            let secondary_path = path.join("solana-secondary");
+            DB::open_cf_as_secondary(&options, path, &secondary_path, TABLES)
+        } else {
+            options.create_if_missing(true);
+            options.create_missing_column_families(true);
+            DB::open_cf(&options, path, TABLES)
+        }
+        .map_err(local_storage_error)?;
+        Ok(Self {
+            db: Arc::new(db),
+            secondary: read_only,
+            write_lock: Arc::default(),
+        })
+    }
+
+    /// Run the blocking RocksDB calls of `f` off the async runtime. A secondary first catches up
+    /// with the writes of the primary.
+    async fn run_blocking<T: Send + 'static>(
+        &self,
+        f: impl FnOnce(&Self) -> Result<T> + Send + 'static,
+    ) -> Result<T> {
+        let storage = self.clone();
+        tokio::task::spawn_blocking(move || {
+            if storage.secondary {
+                storage
+                    .db
+                    .try_catch_up_with_primary()
+                    .map_err(local_storage_error)?;
+            }
+            f(&storage)
+        })
+        .await
+        .map_err(local_storage_error)?
+    }
+
+    fn table(&self, table_name: &str) -> Result<&ColumnFamily> {
+        self.db
+            .cf_handle(table_name)
+            .ok_or_else(|| Error::ObjectNotFound(table_name.to_string()))
+    }
+
+    fn get_row(&self, table_name: &str, row_key: &str) -> Result<Option<RowData>> {
+        let table = self.table(table_name)?;
+        self.db
+            .get_cf(table, row_key)
+            .map_err(local_storage_error)?
+            .map(|data| deserialize_row_data(table_name, row_key, &data))
+            .transpose()
+    }
+
+    /// Visit the rows of `table_name` in lexical order, from `start_at` to `end_at` inclusive
+    fn scan<T>(
+        &self,
+        table_name: &str,
+        start_at: Option<RowKey>,
+        end_at: Option<RowKey>,
+        rows_limit: i64,
+        mut f: impl FnMut(RowKey, &[u8]) -> Result<T>,
+    ) -> Result<Vec<T>> {
+        let table = self.table(table_name)?;
+        let iterator_mode = match &start_at {
+            Some(start_at) => IteratorMode::From(start_at.as_bytes(), Direction::Forward),
+            None => IteratorMode::Start,
+        };
+        let mut rows = vec![];
+        for (key, data) in self.db.iterator_cf(table, iterator_mode) {
+            let row_key = String::from_utf8(key.into_vec()).map_err(|err| {
+                Error::ObjectCorrupt(format!("{}: invalid row key: {}", table_name, err))
+            })?;
+            if matches!(&end_at, Some(end_at) if row_key > *end_at) {
+                break;
+            }
+            rows.push(f(row_key, &data)?);
+            if rows_limit > 0 && rows.len() as i64 >= rows_limit {
+                break;
+            }
+        }
+        Ok(rows)
+    }
+}
+
+fn deserialize_row_data(table_name: &str, row_key: &str, data: &[u8]) -> Result<RowData> {
+    bincode::deserialize(data)
+        .map_err(|_| Error::ObjectCorrupt(format!("{}/{}", table_name, row_key)))
+}
+
+impl LedgerStorage for LocalLedgerStorage {
+    fn get_row_keys<'a>(
+        &'a self,
+        table_name: &'a str,
+        start_at: Option<RowKey>,
+        end_at: Option<RowKey>,
+        rows_limit: i64,
+    ) -> BoxFuture<'a, Result<Vec<RowKey>>> {
+        let table_name = table_name.to_string();
+        Box::pin(self.run_blocking(move |storage| {
+            storage.scan(&table_name, start_at, end_at, rows_limit, |row_key, _| {
+                Ok(row_key)
+            })
+        }))
+    }
+
+    fn get_row_data<'a>(
+        &'a self,
+        table_name: &'a str,
+        start_at: Option<RowKey>,
+        end_at: Option<RowKey>,
+        rows_limit: i64,
+    ) -> BoxFuture<'a, Result<Vec<(RowKey, RowData)>>> {
+        let table_name = table_name.to_string();
+        Box::pin(self.run_blocking(move |storage| {
+            storage.scan(
+                &table_name,
+                start_at,
+                end_at,
+                rows_limit,
+                |row_key, data| {
+                    let row_data = deserialize_row_data(&table_name, &row_key, data)?;
+                    Ok((row_key, row_data))
+                },
+            )
+        }))
+    }
+
+    fn get_single_row_data<'a>(
+        &'a self,
+        table_name: &'a str,
+        row_key: RowKey,
+    ) -> BoxFuture<'a, Result<RowData>> {
+        let table_name = table_name.to_string();
+        Box::pin(self.run_blocking(move |storage| {
+            storage
+                .get_row(&table_name, &row_key)?
+                .ok_or(Error::RowNotFound)
+        }))
+    }
+
+    fn put_row_data<'a>(
+        &'a self,
+        table_name: &'a str,
+        row_data: &'a [(RowKey, RowData)],
+    ) -> BoxFuture<'a, Result<()>> {
+        let table_name = table_name.to_string();
+        let row_data = row_data.to_vec();
+        Box::pin(self.run_blocking(move |storage| {
+            let table = storage.table(&table_name)?;
+            let _write_lock = storage.write_lock.lock().unwrap();
+            let mut batch = WriteBatch::default();
+            for (row_key, cells) in row_data {
+                let mut row = storage.get_row(&table_name, &row_key)?.unwrap_or_default();
+                for (cell_name, cell_value) in cells {
+                    row.retain(|(name, _)| *name != cell_name);
+                    row.push((cell_name, cell_value));
+                }
+                batch.put_cf(table, row_key, bincode::serialize(&row).unwrap());
+            }
+            storage.db.write(batch).map_err(local_storage_error)
+        }))
+    }
+
+    fn delete_row_cells<'a>(
+        &'a self,
+        table_name: &'a str,
+        cells: &'a [(RowKey, Vec<CellName>)],
+    ) -> BoxFuture<'a, Result<()>> {
+        let table_name = table_name.to_string();
+        let cells = cells.to_vec();
+        Box::pin(self.run_blocking(move |storage| {
+            let table = storage.table(&table_name)?;
+            let _write_lock = storage.write_lock.lock().unwrap();
+            let mut batch = WriteBatch::default();
+            for (row_key, cell_names) in cells {
+                if let Some(mut row) = storage.get_row(&table_name, &row_key)? {
+                    row.retain(|(name, _)| !cell_names.contains(name));
+                    // Like BigTable, a row goes away with its last cell
+                    if row.is_empty() {
+                        batch.delete_cf(table, row_key);
+                    } else {
+                        batch.put_cf(table, row_key, bincode::serialize(&row).unwrap());
+                    }
+                }
+            }
+            storage.db.write(batch).map_err(local_storage_error)
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        solana_sdk::{
+            hash::Hash,
+            signature::{Keypair, Signer},
+            system_transaction,
+        },
+        solana_transaction_status::{
+            ConfirmedBlock, TransactionStatusMeta, TransactionWithStatusMeta,
+        },
+    };
+
+    fn confirmed_block(keypair: &Keypair, parent_slot: u64, lamports: u64) -> ConfirmedBlock {
+        let recipient = solana_sdk::pubkey::new_rand();
+        let transaction =
+            system_transaction::transfer(keypair, &recipient, lamports, Hash::default());
+        ConfirmedBlock {
+            transactions: vec![TransactionWithStatusMeta {
+                transaction,
+                meta: Some(TransactionStatusMeta {
+                    status: Ok(()),
+                    fee: 1,
+                    pre_balances: vec![100, 0, 1],
+                    post_balances: vec![99 - lamports, lamports, 1],
+                    inner_instructions: Some(vec![]),
+                    log_messages: Some(vec![]),
+                    pre_token_balances: Some(vec![]),
+                    post_token_balances: Some(vec![]),
+                    rewards: Some(vec![]),
+                }),
+            }],
+            parent_slot,
+            blockhash: Hash::new_unique().to_string(),
+            previous_blockhash: Hash::new_unique().to_string(),
+            rewards: vec![],
+            block_time: Some(1_234_567_890),
+            block_height: Some(parent_slot + 1),
+        }
+    }
+
+    #[test]
+    fn test_local_ledger_storage() {
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        let ledger_path = tempfile::TempDir::new().unwrap();
+        let storage = LocalLedgerStorage::open(ledger_path.path(), false).unwrap();
+        assert_eq!(
+            runtime
+                .block_on(storage.get_first_available_block())
+                .unwrap(),
+            None
+        );
+
+        let keypair = Keypair::new();
+        let blocks: Vec<_> = (1..=3)
+            .map(|slot| (slot * 2, confirmed_block(&keypair, slot * 2 - 1, slot)))
+            .collect();
+        for (slot, block) in &blocks {
+            runtime
+                .block_on(storage.upload_confirmed_block(*slot, block.clone()))
+                .unwrap();
+        }
+
+        assert_eq!(
+            runtime
+                .block_on(storage.get_first_available_block())
+                .unwrap(),
+            Some(2)
+        );
+        assert_eq!(
+            runtime
+                .block_on(storage.get_confirmed_blocks(3, 0))
+                .unwrap(),
+            vec![4, 6]
+        );
+        assert_eq!(
+            runtime
+                .block_on(storage.get_confirmed_blocks(0, 2))
+                .unwrap(),
+            vec![2, 4]
+        );
+        assert_eq!(
+            runtime.block_on(storage.get_confirmed_block(4)).unwrap(),
+            blocks[1].1
+        );
+        assert!(matches!(
+            runtime.block_on(storage.get_confirmed_block(5)),
+            Err(crate::Error::BlockNotFound(5))
+        ));
+
+        let signature = blocks[2].1.transactions[0].transaction.signatures[0];
+        let status = runtime
+            .block_on(storage.get_signature_status(&signature))
+            .unwrap();
+        assert_eq!(status.slot, 6);
+        let transaction = runtime
+            .block_on(storage.get_confirmed_transaction(&signature))
+            .unwrap()
+            .unwrap();
+        assert_eq!(transaction.slot, 6);
+        assert_eq!(transaction.transaction, blocks[2].1.transactions[0]);
+
+        // Newest first
+        let signatures: Vec<_> = blocks
+            .iter()
+            .rev()
+            .map(|(_, block)| block.transactions[0].transaction.signatures[0])
+            .collect();
+        let results = runtime
+            .block_on(storage.get_confirmed_signatures_for_address(
+                &keypair.pubkey(),
+                None,
+                None,
+                10,
+            ))
+            .unwrap();
+        assert_eq!(
+            results
+                .iter()
+                .map(|(status, _)| status.signature)
+                .collect::<Vec<_>>(),
+            signatures
+        );
+        let results = runtime
+            .block_on(storage.get_confirmed_signatures_for_address(
+                &keypair.pubkey(),
+                Some(&signatures[0]),
+                None,
+                1,
+            ))
+            .unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].0.signature, signatures[1]);
+        assert_eq!(results[0].0.slot, 4);
+
+        // Read-only storage sees the same rows, and follows later writes
+        let read_only_storage = LocalLedgerStorage::open(ledger_path.path(), true).unwrap();
+        assert_eq!(
+            runtime
+                .block_on(read_only_storage.get_confirmed_blocks(0, 0))
+                .unwrap(),
+            vec![2, 4, 6]
+        );
+        runtime
+            .block_on(storage.upload_confirmed_block(8, confirmed_block(&keypair, 7, 4)))
+            .unwrap();
+        assert_eq!(
+            runtime
+                .block_on(read_only_storage.get_confirmed_blocks(0, 0))
+                .unwrap(),
+            vec![2, 4, 6, 8]
+        );
+    }
+
+    #[test]
+    fn test_local_ledger_storage_put_row_data() {
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        let ledger_path = tempfile::TempDir::new().unwrap();
+        let storage = LocalLedgerStorage::open(ledger_path.path(), false).unwrap();
+        let row_key = "key".to_string();
+        runtime
+            .block_on(storage.put_row_data(
+                "tx",
+                &[(row_key.clone(), vec![("bin".to_string(), vec![1])])],
+            ))
+            .unwrap();
+        runtime
+            .block_on(storage.put_row_data(
+                "tx",
+                &[(row_key.clone(), vec![("proto".to_string(), vec![2])])],
+            ))
+            .unwrap();
+        assert_eq!(
+            runtime
+                .block_on(storage.get_single_row_data("tx", row_key.clone()))
+                .unwrap(),
+            vec![("bin".to_string(), vec![1]), ("proto".to_string(), vec![2])]
+        );
+        runtime
+            .block_on(storage.put_row_data(
+                "tx",
+                &[(row_key.clone(), vec![("bin".to_string(), vec![3])])],
+            ))
+            .unwrap();
+        assert_eq!(
+            runtime
+                .block_on(storage.get_single_row_data("tx", row_key))
+                .unwrap(),
+            vec![("proto".to_string(), vec![2]), ("bin".to_string(), vec![3])]
+        );
+        assert!(matches!(
+            runtime.block_on(storage.get_single_row_data("tx", "missing".to_string())),
+            Err(Error::RowNotFound)
+        ));
+    }
+}
@@ -283,22 +283,27 @@ impl From<TransactionStatusMeta> for generated::TransactionStatusMeta {
                 err: bincode::serialize(&err).expect("transaction error to serialize to bytes"),
             }),
         };
+        let inner_instructions_none = inner_instructions.is_none();
         let inner_instructions = inner_instructions
             .unwrap_or_default()
             .into_iter()
             .map(|ii| ii.into())
             .collect();
+        let log_messages_none = log_messages.is_none();
         let log_messages = log_messages.unwrap_or_default();
+        let pre_token_balances_none = pre_token_balances.is_none();
         let pre_token_balances = pre_token_balances
             .unwrap_or_default()
             .into_iter()
             .map(|balance| balance.into())
             .collect();
+        let post_token_balances_none = post_token_balances.is_none();
         let post_token_balances = post_token_balances
             .unwrap_or_default()
             .into_iter()
             .map(|balance| balance.into())
             .collect();
+        let rewards_none = rewards.is_none();
         let rewards = rewards
             .unwrap_or_default()
             .into_iter()
@@ -315,6 +320,11 @@ impl From<TransactionStatusMeta> for generated::TransactionStatusMeta {
             pre_token_balances,
             post_token_balances,
             rewards,
+            inner_instructions_none,
+            log_messages_none,
+            pre_token_balances_none,
+            post_token_balances_none,
+            rewards_none,
         }
     }
 }
@@ -340,31 +350,56 @@ impl TryFrom<generated::TransactionStatusMeta> for TransactionStatusMeta {
             pre_token_balances,
             post_token_balances,
             rewards,
+            inner_instructions_none,
+            log_messages_none,
+            pre_token_balances_none,
+            post_token_balances_none,
+            rewards_none,
         } = value;
         let status = match &err {
             None => Ok(()),
             Some(tx_error) => Err(bincode::deserialize(&tx_error.err)?),
         };
-        let inner_instructions = Some(
-            inner_instructions
-                .into_iter()
-                .map(|inner| inner.into())
-                .collect(),
-        );
-        let log_messages = Some(log_messages);
-        let pre_token_balances = Some(
-            pre_token_balances
-                .into_iter()
-                .map(|balance| balance.into())
-                .collect(),
-        );
-        let post_token_balances = Some(
-            post_token_balances
-                .into_iter()
-                .map(|balance| balance.into())
-                .collect(),
-        );
-        let rewards = Some(rewards.into_iter().map(|reward| reward.into()).collect());
+        let inner_instructions = if inner_instructions_none {
+            None
+        } else {
+            Some(
+                inner_instructions
+                    .into_iter()
+                    .map(|inner| inner.into())
+                    .collect(),
+            )
+        };
+        let log_messages = if log_messages_none {
+            None
+        } else {
+            Some(log_messages)
+        };
+        let pre_token_balances = if pre_token_balances_none {
+            None
+        } else {
+            Some(
+                pre_token_balances
+                    .into_iter()
+                    .map(|balance| balance.into())
+                    .collect(),
+            )
+        };
+        let post_token_balances = if post_token_balances_none {
+            None
+        } else {
+            Some(
+                post_token_balances
+                    .into_iter()
+                    .map(|balance| balance.into())
+                    .collect(),
+            )
+        };
+        let rewards = if rewards_none {
+            None
+        } else {
+            Some(rewards.into_iter().map(|reward| reward.into()).collect())
+        };
         Ok(Self {
             status,
             fee,
@@ -873,6 +908,31 @@ mod test {
         assert_eq!(reward, gen_reward.into());
     }
 
+    #[test]
+    fn test_transaction_status_meta_encode() {
+        let mut meta = TransactionStatusMeta {
+            status: Ok(()),
+            fee: 5000,
+            pre_balances: vec![10, 0],
+            post_balances: vec![5, 5000],
+            inner_instructions: None,
+            log_messages: None,
+            pre_token_balances: None,
+            post_token_balances: None,
+            rewards: None,
+        };
+        let gen_meta: generated::TransactionStatusMeta = meta.clone().into();
+        assert_eq!(meta, gen_meta.try_into().unwrap());
+
+        meta.inner_instructions = Some(vec![]);
+        meta.log_messages = Some(vec![]);
+        meta.pre_token_balances = Some(vec![]);
+        meta.post_token_balances = Some(vec![]);
+        meta.rewards = Some(vec![]);
+        let gen_meta: generated::TransactionStatusMeta = meta.clone().into();
+        assert_eq!(meta, gen_meta.try_into().unwrap());
+    }
+
     #[test]
     fn test_transaction_by_addr_encode() {
         let info = TransactionByAddrInfo {
@@ -1,6 +1,6 @@
 use crate::args::{
-    Args, BalancesArgs, Command, DistributeTokensArgs, SenderStakeArgs, SplTokenArgs, StakeArgs,
-    TransactionLogArgs,
+    Args, BalancesArgs, Command, DistributeTokensArgs, ReconcileArgs, SenderStakeArgs,
+    SplTokenArgs, StakeArgs, TransactionLogArgs,
 };
 use clap::{
     crate_description, crate_name, value_t, value_t_or_exit, App, Arg, ArgMatches, SubCommand,
@@ -354,6 +354,16 @@ where
                         .takes_value(true)
                         .value_name("FILE")
                         .help("Allocations CSV file"),
+                )
+                .arg(
+                    Arg::with_name("db_path")
+                        .long("db-path")
+                        .takes_value(true)
+                        .value_name("FILE")
+                        .help(
+                            "Transactions database of a stake distribution, to also report \
+                            the lockup status of each stake account",
+                        ),
                 ),
         )
         .subcommand(
@@ -397,6 +407,26 @@ where
                         .help("Output file"),
                 ),
         )
+        .subcommand(
+            SubCommand::with_name("reconcile")
+                .about(
+                    "Resolve unfinalized transactions by their status in the cluster's \
+                    transaction history, and report recipient balances",
+                )
+                .arg(
+                    Arg::with_name("db_path")
+                        .long("db-path")
+                        .required(true)
+                        .takes_value(true)
+                        .value_name("FILE")
+                        .help("Location of database to reconcile"),
+                )
+                .arg(
+                    Arg::with_name("dry_run")
+                        .long("dry-run")
+                        .help("Report, but do not update the database"),
+                ),
+        )
         .get_matches_from(args)
 }
 
@@ -626,6 +656,7 @@ fn parse_balances_args(matches: &ArgMatches<'_>) -> Result<BalancesArgs, Box<dyn
     Ok(BalancesArgs {
         input_csv: value_t_or_exit!(matches, "input_csv", String),
         spl_token_args,
+        transaction_db: value_t!(matches, "db_path", String).ok(),
     })
 }
 
@@ -636,6 +667,13 @@ fn parse_transaction_log_args(matches: &ArgMatches<'_>) -> TransactionLogArgs {
     }
 }
 
+fn parse_reconcile_args(matches: &ArgMatches<'_>) -> ReconcileArgs {
+    ReconcileArgs {
+        transaction_db: value_t_or_exit!(matches, "db_path", String),
+        dry_run: matches.is_present("dry_run"),
+    }
+}
+
 pub fn parse_args<I, T>(args: I) -> Result<Args, Box<dyn Error>>
 where
     I: IntoIterator<Item = T>,
@@ -663,6 +701,7 @@ where
         ("transaction-log", Some(matches)) => {
             Command::TransactionLog(parse_transaction_log_args(matches))
         }
+        ("reconcile", Some(matches)) => Command::Reconcile(parse_reconcile_args(matches)),
         _ => {
             eprintln!("{}", matches.usage());
             exit(1);
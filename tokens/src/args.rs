@@ -35,6 +35,7 @@ pub struct SplTokenArgs {
 pub struct BalancesArgs {
     pub input_csv: String,
     pub spl_token_args: Option<SplTokenArgs>,
+    pub transaction_db: Option<String>,
 }
 
 pub struct TransactionLogArgs {
@@ -42,10 +43,16 @@ pub struct TransactionLogArgs {
     pub output_path: String,
 }
 
+pub struct ReconcileArgs {
+    pub transaction_db: String,
+    pub dry_run: bool,
+}
+
 pub enum Command {
     DistributeTokens(DistributeTokensArgs),
     Balances(BalancesArgs),
     TransactionLog(TransactionLogArgs),
+    Reconcile(ReconcileArgs),
 }
 
 pub struct Args {
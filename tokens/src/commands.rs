@@ -1,5 +1,8 @@
 use crate::{
-    args::{BalancesArgs, DistributeTokensArgs, SenderStakeArgs, StakeArgs, TransactionLogArgs},
+    args::{
+        BalancesArgs, DistributeTokensArgs, ReconcileArgs, SenderStakeArgs, StakeArgs,
+        TransactionLogArgs,
+    },
     db::{self, TransactionInfo},
     spl_token::*,
     token_display::Token,
@@ -17,11 +20,12 @@ use solana_account_decoder::parse_token::{
 use solana_client::{
     client_error::{ClientError, Result as ClientResult},
     rpc_client::RpcClient,
-    rpc_config::RpcSendTransactionConfig,
+    rpc_config::{RpcBlockConfig, RpcSendTransactionConfig},
     rpc_request::MAX_GET_SIGNATURE_STATUSES_QUERY_ITEMS,
 };
 use solana_sdk::{
-    clock::Slot,
+    account::from_account,
+    clock::{Clock, Epoch, Slot, MAX_RECENT_BLOCKHASHES},
     commitment_config::CommitmentConfig,
     instruction::Instruction,
     message::Message,
@@ -31,10 +35,10 @@ use solana_sdk::{
         instruction::{self as stake_instruction, LockupArgs},
         state::{Authorized, Lockup, StakeAuthorize},
     },
-    system_instruction,
+    system_instruction, sysvar,
     transaction::Transaction,
 };
-use solana_transaction_status::TransactionStatus;
+use solana_transaction_status::{TransactionDetails, TransactionStatus};
 use spl_associated_token_account_v1_0::get_associated_token_address;
 use spl_token_v2_0::solana_program::program_error::ProgramError;
 use std::{
@@ -53,6 +57,14 @@ pub struct Allocation {
     pub recipient: String,
     pub amount: u64,
     pub lockup_date: String,
+    pub lockup_epoch: Option<Epoch>,
+    pub custodian: Option<Pubkey>,
+}
+
+impl Allocation {
+    fn has_lockup(&self) -> bool {
+        !self.lockup_date.is_empty() || self.lockup_epoch.is_some() || self.custodian.is_some()
+    }
 }
 
 #[derive(Debug, PartialEq)]
@@ -99,6 +111,8 @@ pub enum Error {
     CsvError(#[from] csv::Error),
     #[error("PickleDb error")]
     PickleDbError(#[from] pickledb::error::Error),
+    #[error("Transaction database error")]
+    DbError(#[from] db::DbError),
     #[error("Transport error")]
     ClientError(#[from] ClientError),
     #[error("Missing lockup authority")]
@@ -120,6 +134,8 @@ fn merge_allocations(allocations: &[Allocation]) -> Vec<Allocation> {
                 recipient: allocation.recipient.clone(),
                 amount: 0,
                 lockup_date: "".to_string(),
+                lockup_epoch: None,
+                custodian: None,
             })
             .amount += allocation.amount;
     }
@@ -130,6 +146,7 @@ fn merge_allocations(allocations: &[Allocation]) -> Vec<Allocation> {
 fn has_same_recipient(allocation: &Allocation, transaction_info: &TransactionInfo) -> bool {
     allocation.recipient == transaction_info.recipient.to_string()
         && allocation.lockup_date.parse().ok() == transaction_info.lockup_date
+        && allocation.lockup_epoch == transaction_info.lockup_epoch
 }
 
 fn apply_previous_transactions(
@@ -210,8 +227,11 @@ fn distribution_instructions(
                     if let Some(lockup_date) = lockup_date {
                         lockup.unix_timestamp = lockup_date.timestamp();
                     }
-                    if let Some(lockup_authority) = stake_args.lockup_authority {
-                        lockup.custodian = lockup_authority;
+                    if let Some(lockup_epoch) = allocation.lockup_epoch {
+                        lockup.epoch = lockup_epoch;
+                    }
+                    if let Some(custodian) = allocation.custodian.or(stake_args.lockup_authority) {
+                        lockup.custodian = custodian;
                     }
                     stake_instruction::create_account(
                         &sender_pubkey,
@@ -253,11 +273,11 @@ fn distribution_instructions(
                     ));
 
                     // Add lockup
-                    if let Some(lockup_date) = lockup_date {
+                    if allocation.has_lockup() {
                         let lockup = LockupArgs {
-                            unix_timestamp: Some(lockup_date.timestamp()),
-                            epoch: None,
-                            custodian: None,
+                            unix_timestamp: lockup_date.map(|lockup_date| lockup_date.timestamp()),
+                            epoch: allocation.lockup_epoch,
+                            custodian: allocation.custodian,
                         };
                         instructions.push(stake_instruction::set_lockup(
                             new_stake_account_address,
@@ -360,7 +380,7 @@ fn send_messages(
         allocations.iter().zip(messages).zip(stake_extras)
     {
         if exit.load(Ordering::SeqCst) {
-            db.dump()?;
+            db::dump_db(db, &args.transaction_db)?;
             return Err(Error::ExitSignal);
         }
         let new_stake_account_address = new_stake_account_keypair.pubkey();
@@ -372,7 +392,7 @@ fn send_messages(
                 signers.push(&*sender_stake_args.stake_authority);
                 signers.push(&*sender_stake_args.withdraw_authority);
                 signers.push(&new_stake_account_keypair);
-                if !allocation.lockup_date.is_empty() {
+                if allocation.has_lockup() {
                     if let Some(lockup_authority) = &sender_stake_args.lockup_authority {
                         signers.push(&**lockup_authority);
                     } else {
@@ -382,40 +402,43 @@ fn send_messages(
             }
         }
         let signers = unique_signers(signers);
-        let result: ClientResult<(Transaction, u64)> = {
-            if args.dry_run {
-                Ok((Transaction::new_unsigned(message), std::u64::MAX))
-            } else {
-                let (blockhash, last_valid_block_height) =
-                    client.get_latest_blockhash_with_commitment(CommitmentConfig::default())?;
-                let transaction = Transaction::new(&signers, message, blockhash);
-                let config = RpcSendTransactionConfig {
-                    skip_preflight: true,
-                    ..RpcSendTransactionConfig::default()
-                };
-                client.send_transaction_with_config(&transaction, config)?;
-                Ok((transaction, last_valid_block_height))
-            }
+        let (transaction, last_valid_block_height) = if args.dry_run {
+            (Transaction::new_unsigned(message), std::u64::MAX)
+        } else {
+            let (blockhash, last_valid_block_height) =
+                client.get_latest_blockhash_with_commitment(CommitmentConfig::default())?;
+            (
+                Transaction::new(&signers, message, blockhash),
+                last_valid_block_height,
+            )
         };
-        match result {
-            Ok((transaction, last_valid_block_height)) => {
-                let new_stake_account_address_option =
-                    args.stake_args.as_ref().map(|_| &new_stake_account_address);
-                db::set_transaction_info(
-                    db,
-                    &allocation.recipient.parse().unwrap(),
-                    allocation.amount,
-                    &transaction,
-                    new_stake_account_address_option,
-                    false,
-                    last_valid_block_height,
-                    lockup_date,
-                )?;
-            }
-            Err(e) => {
+        let new_stake_account_address_option =
+            args.stake_args.as_ref().map(|_| &new_stake_account_address);
+        let transaction_info = db::set_transaction_info(
+            db,
+            &allocation.recipient.parse().unwrap(),
+            allocation.amount,
+            &transaction,
+            new_stake_account_address_option,
+            false,
+            last_valid_block_height,
+            lockup_date,
+            allocation.lockup_epoch,
+        )?;
+        if !args.dry_run {
+            // Write the signed transaction to disk before broadcasting it. If the process dies
+            // before the transaction is recorded, a restart would otherwise pay the allocation
+            // twice. Records of transactions that never landed are cleared by `reconcile`.
+            db::append_to_wal(&args.transaction_db, &transaction_info)?;
+            let config = RpcSendTransactionConfig {
+                skip_preflight: true,
+                ..RpcSendTransactionConfig::default()
+            };
+            if let Err(e) = client.send_transaction_with_config(&transaction, config) {
                 eprintln!("Error sending tokens to {}: {}", allocation.recipient, e);
+                return Err(e.into());
             }
-        };
+        }
     }
     Ok(())
 }
@@ -450,7 +473,7 @@ fn distribute_allocations(
 
     send_messages(client, db, allocations, args, exit, messages, stake_extras)?;
 
-    db.dump()?;
+    db::dump_db(db, &args.transaction_db)?;
     Ok(())
 }
 
@@ -473,21 +496,12 @@ fn read_allocations(
                 recipient,
                 amount,
                 lockup_date: "".to_string(),
+                lockup_epoch: None,
+                custodian: None,
             })
             .collect()
     } else if require_lockup_heading {
-        let recipients: Vec<(String, f64, String)> = rdr
-            .deserialize()
-            .map(|recipient| recipient.unwrap())
-            .collect();
-        recipients
-            .into_iter()
-            .map(|(recipient, amount, lockup_date)| Allocation {
-                recipient,
-                amount: sol_to_lamports(amount),
-                lockup_date,
-            })
-            .collect()
+        read_stake_allocations(&mut rdr)?
     } else if raw_amount {
         let recipients: Vec<(String, u64)> = rdr
             .deserialize()
@@ -499,6 +513,8 @@ fn read_allocations(
                 recipient,
                 amount,
                 lockup_date: "".to_string(),
+                lockup_epoch: None,
+                custodian: None,
             })
             .collect()
     } else {
@@ -512,12 +528,145 @@ fn read_allocations(
                 recipient,
                 amount: sol_to_lamports(amount),
                 lockup_date: "".to_string(),
+                lockup_epoch: None,
+                custodian: None,
             })
             .collect()
     };
     Ok(allocations)
 }
 
+fn invalid_data<E>(error: E) -> io::Error
+where
+    E: Into<Box<dyn std::error::Error + Send + Sync>>,
+{
+    io::Error::new(io::ErrorKind::InvalidData, error)
+}
+
+/// Read stake allocations. The leading `recipient`, `amount` and `lockup_date` columns are
+/// positional; the optional `lockup_epoch`, `custodian`, `vesting_tranches` and
+/// `vesting_period_days` columns are located by their heading.
+fn read_stake_allocations<R: io::Read>(rdr: &mut csv::Reader<R>) -> io::Result<Vec<Allocation>> {
+    let headers = rdr.headers()?.clone();
+    let column = |name: &str| headers.iter().position(|heading| heading == name);
+    let lockup_epoch_column = column("lockup_epoch");
+    let custodian_column = column("custodian");
+    let vesting_tranches_column = column("vesting_tranches");
+    let vesting_period_days_column = column("vesting_period_days");
+
+    let mut allocations = vec![];
+    for record in rdr.records() {
+        let record = record?;
+        let field = |column: Option<usize>| {
+            column
+                .and_then(|column| record.get(column))
+                .filter(|value| !value.is_empty())
+        };
+        let (recipient, amount, lockup_date) = match (record.get(0), record.get(1), record.get(2)) {
+            (Some(recipient), Some(amount), Some(lockup_date)) => (recipient, amount, lockup_date),
+            _ => return Err(invalid_data(format!("missing lockup_date in {:?}", record))),
+        };
+        let amount = amount
+            .parse::<f64>()
+            .map_err(|err| invalid_data(format!("invalid amount {}: {}", amount, err)))?;
+        let lockup_epoch = field(lockup_epoch_column)
+            .map(|epoch| {
+                epoch
+                    .parse()
+                    .map_err(|err| invalid_data(format!("invalid lockup_epoch {}: {}", epoch, err)))
+            })
+            .transpose()?;
+        let custodian = field(custodian_column)
+            .map(|custodian| {
+                custodian.parse().map_err(|err| {
+                    invalid_data(format!("invalid custodian {}: {:?}", custodian, err))
+                })
+            })
+            .transpose()?;
+        let allocation = Allocation {
+            recipient: recipient.to_string(),
+            amount: sol_to_lamports(amount),
+            lockup_date: lockup_date.to_string(),
+            lockup_epoch,
+            custodian,
+        };
+
+        let tranches = field(vesting_tranches_column)
+            .map(|tranches| {
+                tranches.parse::<u32>().map_err(|err| {
+                    invalid_data(format!("invalid vesting_tranches {}: {}", tranches, err))
+                })
+            })
+            .transpose()?
+            .unwrap_or(1);
+        let period_days = field(vesting_period_days_column)
+            .map(|days| {
+                days.parse::<u32>().map_err(|err| {
+                    invalid_data(format!("invalid vesting_period_days {}: {}", days, err))
+                })
+            })
+            .transpose()?;
+        allocations.extend(vesting_schedule(allocation, tranches, period_days)?);
+    }
+    Ok(allocations)
+}
+
+/// Split an allocation into `tranches` equal allocations, the first locked up until the
+/// allocation's lockup date and each following one `period_days` later. Any remainder of the
+/// split is added to the last tranche.
+fn vesting_schedule(
+    allocation: Allocation,
+    tranches: u32,
+    period_days: Option<u32>,
+) -> io::Result<Vec<Allocation>> {
+    if tranches == 0 {
+        return Err(invalid_data(format!(
+            "vesting_tranches for {} must be at least 1",
+            allocation.recipient
+        )));
+    }
+    if tranches == 1 {
+        return Ok(vec![allocation]);
+    }
+    let period_days = match period_days {
+        Some(period_days) if period_days > 0 => period_days,
+        _ => {
+            return Err(invalid_data(format!(
+                "vesting tranches for {} require a vesting_period_days of at least 1",
+                allocation.recipient
+            )))
+        }
+    };
+    let first_unlock = allocation
+        .lockup_date
+        .parse::<DateTime<Utc>>()
+        .map_err(|err| {
+            invalid_data(format!(
+                "vesting tranches for {} require a valid lockup_date: {}",
+                allocation.recipient, err
+            ))
+        })?;
+
+    let tranche_amount = allocation.amount / u64::from(tranches);
+    let remainder = allocation.amount % u64::from(tranches);
+    Ok((0..tranches)
+        .map(|tranche| {
+            let lockup_date =
+                first_unlock + chrono::Duration::days(i64::from(tranche * period_days));
+            let amount = if tranche == tranches - 1 {
+                tranche_amount + remainder
+            } else {
+                tranche_amount
+            };
+            Allocation {
+                amount,
+                lockup_date: lockup_date.to_rfc3339_opts(SecondsFormat::Secs, true),
+                ..allocation.clone()
+            }
+        })
+        .collect())
+}
+
 fn new_spinner_progress_bar() -> ProgressBar {
     let progress_bar = ProgressBar::new(42);
     progress_bar
@@ -849,6 +998,78 @@ pub fn process_balances(client: &RpcClient, args: &BalancesArgs) -> Result<(), E
         }
     }
 
+    if let Some(transaction_db) = &args.transaction_db {
+        let db = db::open_db(transaction_db, true)?;
+        print_stake_lockups(client, &db)?;
+    }
+
+    Ok(())
+}
+
+#[derive(Debug, PartialEq)]
+enum LockupStatus {
+    /// The distribution transaction is not yet finalized
+    Pending,
+    Locked,
+    Unlocked,
+}
+
+fn lockup_status(info: &TransactionInfo, clock: &Clock) -> LockupStatus {
+    if info.finalized_date.is_none() {
+        return LockupStatus::Pending;
+    }
+    let locked_by_date = info
+        .lockup_date
+        .map(|lockup_date| lockup_date.timestamp() > clock.unix_timestamp)
+        .unwrap_or(false);
+    let locked_by_epoch = info
+        .lockup_epoch
+        .map(|lockup_epoch| lockup_epoch > clock.epoch)
+        .unwrap_or(false);
+    if locked_by_date || locked_by_epoch {
+        LockupStatus::Locked
+    } else {
+        LockupStatus::Unlocked
+    }
+}
+
+/// Print the lockup of each stake account of a stake distribution, one row per vesting tranche
+fn print_stake_lockups(client: &RpcClient, db: &PickleDb) -> Result<(), Error> {
+    let mut transaction_infos: Vec<_> = db::read_transaction_infos(db)
+        .into_iter()
+        .filter(|info| info.new_stake_account_address.is_some())
+        .collect();
+    transaction_infos.sort_by(|a, b| {
+        (a.recipient.to_string(), a.lockup_date, a.lockup_epoch).cmp(&(
+            b.recipient.to_string(),
+            b.lockup_date,
+            b.lockup_epoch,
+        ))
+    });
+    let clock_account = client.get_account(&sysvar::clock::id())?;
+    let clock: Clock = from_account(&clock_account).unwrap();
+
+    println!(
+        "{}",
+        style(format!(
+            "{:<44}  {:<44}  {:<32}  {:>24}  {:>24}  {}",
+            "Recipient", "Stake Account", "Lockup", "Amount", "Actual Balance", "Status"
+        ))
+        .bold()
+    );
+    for info in &transaction_infos {
+        let stake_account_address = info.new_stake_account_address.unwrap();
+        let actual = lamports_to_sol(client.get_balance(&stake_account_address)?);
+        println!(
+            "{:<44}  {:<44}  {:<32}  {:>24.9}  {:>24.9}  {:?}",
+            info.recipient.to_string(),
+            stake_account_address.to_string(),
+            db::lockup_description(info),
+            lamports_to_sol(info.amount),
+            actual,
+            lockup_status(info, &clock),
+        );
+    }
     Ok(())
 }
 
@@ -858,6 +1079,123 @@ pub fn process_transaction_log(args: &TransactionLogArgs) -> Result<(), Error> {
     Ok(())
 }
 
+/// Block height of the oldest block in the history of the node, or None if unknown
+fn get_first_available_block_height(client: &RpcClient) -> Result<Option<u64>, Error> {
+    let slot = client.get_first_available_block()?;
+    if slot == 0 {
+        return Ok(Some(0));
+    }
+    let config = RpcBlockConfig {
+        transaction_details: Some(TransactionDetails::None),
+        rewards: Some(false),
+        commitment: Some(CommitmentConfig::finalized()),
+        ..RpcBlockConfig::default()
+    };
+    Ok(client.get_block_with_config(slot, config)?.block_height)
+}
+
+/// Resolve every unfinalized transaction in the database, then report the recorded and actual
+/// balance of each recipient. Records of transactions that failed or can no longer land are
+/// removed, so that the next distribution resends them. A transaction can no longer land once
+/// its blockhash has expired, if the history of the node covers the lifetime of the blockhash
+/// and holds no status for it.
+pub fn process_reconcile(client: &RpcClient, args: &ReconcileArgs) -> Result<(), Error> {
+    let mut db = db::open_db(&args.transaction_db, args.dry_run)?;
+    let unfinalized_transactions: Vec<_> = db::read_transaction_infos(&db)
+        .into_iter()
+        .filter(|info| info.finalized_date.is_none())
+        .filter(|info| info.transaction.signatures[0] != Signature::default()) // Filter out dry-run signatures
+        .collect();
+    let signatures: Vec<_> = unfinalized_transactions
+        .iter()
+        .map(|info| info.transaction.signatures[0])
+        .collect();
+    let mut statuses = vec![];
+    for signatures_chunk in signatures.chunks(MAX_GET_SIGNATURE_STATUSES_QUERY_ITEMS) {
+        statuses.extend(
+            client
+                .get_signature_statuses_with_history(signatures_chunk)?
+                .value,
+        );
+    }
+    let finalized_block_height = client.get_block_height()?;
+    let first_available_block_height = get_first_available_block_height(client)?;
+
+    println!(
+        "{}",
+        style(format!(
+            "{:<88}  {:<44}  {:>24}  {}",
+            "Signature", "Recipient", "Amount", "Status"
+        ))
+        .bold()
+    );
+    for (info, opt_transaction_status) in unfinalized_transactions.iter().zip(statuses) {
+        // The transaction could only have landed while its blockhash was valid, so the node
+        // must hold the status of every block since then. The genesis block holds none.
+        let first_block_height = info
+            .last_valid_block_height
+            .saturating_sub(MAX_RECENT_BLOCKHASHES as u64)
+            .max(1);
+        let history_covers_transaction =
+            first_available_block_height.map_or(false, |height| height <= first_block_height);
+        let status = db::reconcile_transaction(
+            &mut db,
+            &info.transaction.signatures[0],
+            opt_transaction_status,
+            info.last_valid_block_height,
+            finalized_block_height,
+            history_covers_transaction,
+        )?;
+        println!(
+            "{:<88}  {:<44}  {:>24.9}  {:?}",
+            info.transaction.signatures[0].to_string(),
+            info.recipient.to_string(),
+            lamports_to_sol(info.amount),
+            status,
+        );
+    }
+    db.dump()?;
+
+    let mut transaction_infos = db::read_transaction_infos(&db);
+    transaction_infos.sort_by(db::compare_transaction_infos);
+    let mut recipients = IndexMap::new();
+    for info in &transaction_infos {
+        let (finalized, pending, accounts) =
+            recipients
+                .entry(info.recipient)
+                .or_insert((0, 0, vec![info.recipient]));
+        if info.finalized_date.is_some() {
+            *finalized += info.amount;
+        } else {
+            *pending += info.amount;
+        }
+        accounts.extend(info.new_stake_account_address);
+    }
+
+    println!(
+        "{}",
+        style(format!(
+            "{:<44}  {:>24}  {:>24}  {:>24}",
+            "Recipient", "Finalized", "Pending", "Actual Balance"
+        ))
+        .bold()
+    );
+    for (recipient, (finalized, pending, accounts)) in recipients {
+        let mut actual = 0;
+        for account in &accounts {
+            actual += client.get_balance(account)?;
+        }
+        println!(
+            "{:<44}  {:>24.9}  {:>24.9}  {:>24.9}",
+            recipient.to_string(),
+            lamports_to_sol(finalized),
+            lamports_to_sol(pending),
+            lamports_to_sol(actual),
+        );
+    }
+    Ok(())
+}
+
 use crate::db::check_output_file;
 use solana_sdk::{pubkey::Pubkey, signature::Keypair};
 use tempfile::{tempdir, NamedTempFile};
@@ -948,6 +1286,95 @@ pub fn test_process_distribute_tokens_with_client(
     check_output_file(&output_path, &db::open_db(&transaction_db, true).unwrap());
 }
 
+pub fn test_process_reconcile_with_client(client: &RpcClient, sender_keypair: Keypair) {
+    let exit = Arc::new(AtomicBool::default());
+    let fee_payer = Keypair::new();
+    let transaction = transfer(
+        client,
+        sol_to_lamports(1.0),
+        &sender_keypair,
+        &fee_payer.pubkey(),
+    )
+    .unwrap();
+    client
+        .send_and_confirm_transaction_with_spinner(&transaction)
+        .unwrap();
+
+    let expected_amount = sol_to_lamports(1000.0);
+    let alice_pubkey = solana_sdk::pubkey::new_rand();
+    let allocations_file = NamedTempFile::new().unwrap();
+    let input_csv = allocations_file.path().to_str().unwrap().to_string();
+    let mut wtr = csv::WriterBuilder::new().from_writer(allocations_file);
+    wtr.write_record(&["recipient", "amount"]).unwrap();
+    wtr.write_record(&[
+        alice_pubkey.to_string(),
+        lamports_to_sol(expected_amount).to_string(),
+    ])
+    .unwrap();
+    wtr.flush().unwrap();
+
+    let dir = tempdir().unwrap();
+    let transaction_db = dir
+        .path()
+        .join("transactions.db")
+        .to_str()
+        .unwrap()
+        .to_string();
+
+    // Simulate a crash after a transaction was written ahead, but before it was broadcast
+    let unsent_transaction =
+        transfer(client, expected_amount, &sender_keypair, &alice_pubkey).unwrap();
+    let unsent_transaction_info = TransactionInfo {
+        recipient: alice_pubkey,
+        amount: expected_amount,
+        transaction: unsent_transaction,
+        ..TransactionInfo::default()
+    };
+    db::append_to_wal(&transaction_db, &unsent_transaction_info).unwrap();
+
+    let args = DistributeTokensArgs {
+        sender_keypair: Box::new(sender_keypair),
+        fee_payer: Box::new(fee_payer),
+        dry_run: false,
+        input_csv,
+        transaction_db: transaction_db.clone(),
+        output_path: None,
+        stake_args: None,
+        spl_token_args: None,
+        transfer_amount: None,
+    };
+
+    // The unsent transaction may have landed, so a restart doesn't pay alice again
+    process_allocations(client, &args, exit.clone()).unwrap();
+    assert_eq!(client.get_balance(&alice_pubkey).unwrap(), 0);
+
+    // The node's history has no record of the transaction and its blockhash expired, so
+    // reconcile drops it. A dry run only reports.
+    let mut reconcile_args = ReconcileArgs {
+        transaction_db: transaction_db.clone(),
+        dry_run: true,
+    };
+    process_reconcile(client, &reconcile_args).unwrap();
+    let transaction_infos =
+        db::read_transaction_infos(&db::open_db(&transaction_db, true).unwrap());
+    assert_eq!(transaction_infos.len(), 1);
+    reconcile_args.dry_run = false;
+    process_reconcile(client, &reconcile_args).unwrap();
+    let transaction_infos =
+        db::read_transaction_infos(&db::open_db(&transaction_db, true).unwrap());
+    assert!(transaction_infos.is_empty());
+
+    // Now the allocation is paid, exactly once
+    process_allocations(client, &args, exit.clone()).unwrap();
+    process_reconcile(client, &reconcile_args).unwrap();
+    process_allocations(client, &args, exit).unwrap();
+    let transaction_infos =
+        db::read_transaction_infos(&db::open_db(&transaction_db, true).unwrap());
+    assert_eq!(transaction_infos.len(), 1);
+    assert!(transaction_infos[0].finalized_date.is_some());
+    assert_eq!(client.get_balance(&alice_pubkey).unwrap(), expected_amount);
+}
+
 pub fn test_process_create_stake_with_client(client: &RpcClient, sender_keypair: Keypair) {
     let exit = Arc::new(AtomicBool::default());
     let fee_payer = Keypair::new();
@@ -1201,8 +1628,10 @@ pub fn test_process_distribute_stake_with_client(client: &RpcClient, sender_keyp
 #[cfg(test)]
 mod tests {
     use super::*;
-    use solana_core::test_validator::TestValidator;
+    use solana_core::test_validator::{TestValidator, TestValidatorGenesis};
+    use solana_rpc::rpc::JsonRpcConfig;
     use solana_sdk::{
+        fee_calculator::FeeRateGovernor,
         instruction::AccountMeta,
         signature::{read_keypair_file, write_keypair_file, Signer},
         stake::instruction::StakeInstruction,
@@ -1243,6 +1672,24 @@ mod tests {
         test_process_distribute_tokens_with_client(&client, alice, Some(sol_to_lamports(1.5)));
     }
 
+    #[test]
+    fn test_process_reconcile() {
+        let alice = Keypair::new();
+        // Reconciling searches the transaction history
+        let test_validator = TestValidatorGenesis::default()
+            .fee_rate_governor(FeeRateGovernor::new(0, 0))
+            .rpc_config(JsonRpcConfig {
+                enable_rpc_transaction_history: true,
+                ..JsonRpcConfig::default()
+            })
+            .start_with_mint_address(alice.pubkey(), SocketAddrSpace::Unspecified)
+            .unwrap();
+        let url = test_validator.rpc_url();
+
+        let client = RpcClient::new_with_commitment(url, CommitmentConfig::processed());
+        test_process_reconcile_with_client(&client, alice);
+    }
+
     #[test]
     fn test_create_stake_allocations() {
         let alice = Keypair::new();
@@ -1272,6 +1719,8 @@ mod tests {
             recipient: alice_pubkey.to_string(),
             amount: 42,
             lockup_date: "".to_string(),
+            lockup_epoch: None,
+            custodian: None,
         };
         let file = NamedTempFile::new().unwrap();
         let input_csv = file.path().to_str().unwrap().to_string();
@@ -1288,6 +1737,8 @@ mod tests {
             recipient: alice_pubkey.to_string(),
             amount: sol_to_lamports(42.0),
             lockup_date: "".to_string(),
+            lockup_epoch: None,
+            custodian: None,
         };
 
         assert_eq!(
@@ -1322,11 +1773,15 @@ mod tests {
                 recipient: pubkey0.to_string(),
                 amount: sol_to_lamports(42.0),
                 lockup_date: "".to_string(),
+                lockup_epoch: None,
+                custodian: None,
             },
             Allocation {
                 recipient: pubkey1.to_string(),
                 amount: sol_to_lamports(43.0),
                 lockup_date: "".to_string(),
+                lockup_epoch: None,
+                custodian: None,
             },
         ];
         assert_eq!(
@@ -1354,11 +1809,15 @@ mod tests {
                 recipient: pubkey0.to_string(),
                 amount: sol_to_lamports(42.0),
                 lockup_date: "".to_string(),
+                lockup_epoch: None,
+                custodian: None,
             },
             Allocation {
                 recipient: pubkey1.to_string(),
                 amount: sol_to_lamports(43.0),
                 lockup_date: "".to_string(),
+                lockup_epoch: None,
+                custodian: None,
             },
         ];
         assert_eq!(
@@ -1388,16 +1847,22 @@ mod tests {
                 recipient: pubkey0.to_string(),
                 amount,
                 lockup_date: "".to_string(),
+                lockup_epoch: None,
+                custodian: None,
             },
             Allocation {
                 recipient: pubkey1.to_string(),
                 amount,
                 lockup_date: "".to_string(),
+                lockup_epoch: None,
+                custodian: None,
             },
             Allocation {
                 recipient: pubkey2.to_string(),
                 amount,
                 lockup_date: "".to_string(),
+                lockup_epoch: None,
+                custodian: None,
             },
         ];
         assert_eq!(
@@ -1406,6 +1871,127 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_read_allocations_vesting() {
+        let pubkey0 = solana_sdk::pubkey::new_rand();
+        let pubkey1 = solana_sdk::pubkey::new_rand();
+        let custodian = solana_sdk::pubkey::new_rand();
+        let file = NamedTempFile::new().unwrap();
+        let input_csv = file.path().to_str().unwrap().to_string();
+        let mut wtr = csv::WriterBuilder::new().from_writer(file);
+        wtr.write_record(&[
+            "recipient",
+            "amount",
+            "lockup_date",
+            "vesting_tranches",
+            "vesting_period_days",
+            "custodian",
+            "lockup_epoch",
+        ])
+        .unwrap();
+        wtr.write_record(&[
+            &pubkey0.to_string(),
+            "10",
+            "2021-01-07T00:00:00Z",
+            "3",
+            "30",
+            &custodian.to_string(),
+            "",
+        ])
+        .unwrap();
+        wtr.write_record(&[&pubkey1.to_string(), "42", "", "", "", "", "300"])
+            .unwrap();
+        wtr.flush().unwrap();
+
+        let tranche = |amount, lockup_date: &str| Allocation {
+            recipient: pubkey0.to_string(),
+            amount,
+            lockup_date: lockup_date.to_string(),
+            lockup_epoch: None,
+            custodian: Some(custodian),
+        };
+        assert_eq!(
+            read_allocations(&input_csv, None, true, false).unwrap(),
+            vec![
+                tranche(3_333_333_333, "2021-01-07T00:00:00Z"),
+                tranche(3_333_333_333, "2021-02-06T00:00:00Z"),
+                tranche(3_333_333_334, "2021-03-08T00:00:00Z"),
+                Allocation {
+                    recipient: pubkey1.to_string(),
+                    amount: sol_to_lamports(42.0),
+                    lockup_date: "".to_string(),
+                    lockup_epoch: Some(300),
+                    custodian: None,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_vesting_schedule_invalid() {
+        let allocation = Allocation {
+            recipient: solana_sdk::pubkey::new_rand().to_string(),
+            amount: 42,
+            lockup_date: "2021-01-07T00:00:00Z".to_string(),
+            lockup_epoch: None,
+            custodian: None,
+        };
+        assert_eq!(
+            vesting_schedule(allocation.clone(), 1, None).unwrap(),
+            vec![allocation.clone()]
+        );
+        assert!(vesting_schedule(allocation.clone(), 0, Some(30)).is_err());
+        assert!(vesting_schedule(allocation.clone(), 2, None).is_err());
+        assert!(vesting_schedule(allocation.clone(), 2, Some(0)).is_err());
+        let no_lockup_date = Allocation {
+            lockup_date: "".to_string(),
+            ..allocation
+        };
+        assert!(vesting_schedule(no_lockup_date, 2, Some(30)).is_err());
+    }
+
+    #[test]
+    fn test_lockup_status() {
+        let clock = Clock {
+            epoch: 10,
+            unix_timestamp: "2021-01-07T00:00:00Z"
+                .parse::<DateTime<Utc>>()
+                .unwrap()
+                .timestamp(),
+            ..Clock::default()
+        };
+        let finalized = TransactionInfo {
+            finalized_date: Some(Utc::now()),
+            ..TransactionInfo::default()
+        };
+        assert_eq!(
+            lockup_status(&TransactionInfo::default(), &clock),
+            LockupStatus::Pending
+        );
+        assert_eq!(lockup_status(&finalized, &clock), LockupStatus::Unlocked);
+        let locked_by_date = TransactionInfo {
+            lockup_date: "2021-01-08T00:00:00Z".parse().ok(),
+            ..finalized.clone()
+        };
+        assert_eq!(lockup_status(&locked_by_date, &clock), LockupStatus::Locked);
+        let unlocked_by_date = TransactionInfo {
+            lockup_date: "2021-01-06T00:00:00Z".parse().ok(),
+            ..finalized.clone()
+        };
+        assert_eq!(
+            lockup_status(&unlocked_by_date, &clock),
+            LockupStatus::Unlocked
+        );
+        let locked_by_epoch = TransactionInfo {
+            lockup_epoch: Some(11),
+            ..unlocked_by_date
+        };
+        assert_eq!(
+            lockup_status(&locked_by_epoch, &clock),
+            LockupStatus::Locked
+        );
+    }
+
     #[test]
     fn test_apply_previous_transactions() {
         let alice = solana_sdk::pubkey::new_rand();
@@ -1415,11 +2001,15 @@ mod tests {
                 recipient: alice.to_string(),
                 amount: sol_to_lamports(1.0),
                 lockup_date: "".to_string(),
+                lockup_epoch: None,
+                custodian: None,
             },
             Allocation {
                 recipient: bob.to_string(),
                 amount: sol_to_lamports(1.0),
                 lockup_date: "".to_string(),
+                lockup_epoch: None,
+                custodian: None,
             },
         ];
         let transaction_infos = vec![TransactionInfo {
@@ -1445,11 +2035,15 @@ mod tests {
             recipient: alice_pubkey.to_string(),
             amount: sol_to_lamports(1.0),
             lockup_date: "".to_string(),
+            lockup_epoch: None,
+            custodian: None,
         };
         let alice_alloc_lockup0 = Allocation {
             recipient: alice_pubkey.to_string(),
             amount: sol_to_lamports(1.0),
             lockup_date: lockup0.clone(),
+            lockup_epoch: None,
+            custodian: None,
         };
         let alice_info = TransactionInfo {
             recipient: alice_pubkey,
@@ -1482,6 +2076,11 @@ mod tests {
             &alice_alloc_lockup0,
             &alice_info_lockup0
         )); // Same recipient, same lockups
+        let alice_alloc_epoch = Allocation {
+            lockup_epoch: Some(300),
+            ..alice_alloc_lockup0.clone()
+        };
+        assert!(!has_same_recipient(&alice_alloc_epoch, &alice_info_lockup0)); // Same lockup date, different lockup epochs
     }
 
     const SET_LOCKUP_INDEX: usize = 5;
@@ -1493,6 +2092,8 @@ mod tests {
             recipient: Pubkey::default().to_string(),
             amount: sol_to_lamports(1.0),
             lockup_date: lockup_date_str.to_string(),
+            lockup_epoch: None,
+            custodian: None,
         };
         let stake_account_address = solana_sdk::pubkey::new_rand();
         let new_stake_account_address = solana_sdk::pubkey::new_rand();
@@ -1557,6 +2158,8 @@ mod tests {
             recipient: recipient.to_string(),
             amount: allocation_amount,
             lockup_date: "".to_string(),
+            lockup_epoch: None,
+            custodian: None,
         }];
         let args = DistributeTokensArgs {
             sender_keypair: read_keypair_file(sender_keypair_file).unwrap().into(),
@@ -1830,6 +2433,8 @@ mod tests {
             recipient: solana_sdk::pubkey::new_rand().to_string(),
             amount: sol_to_lamports(expensive_allocation_amount),
             lockup_date: "".to_string(),
+            lockup_epoch: None,
+            custodian: None,
         }];
         let err_result = check_payer_balances(
             &[one_signer_message()],
@@ -2026,6 +2631,7 @@ mod tests {
             false,
             last_valid_block_height,
             None,
+            None,
         )
         .unwrap();
 
@@ -2049,6 +2655,8 @@ mod tests {
             recipient: recipient.to_string(),
             amount: sol_to_lamports(1.0),
             lockup_date: "".to_string(),
+            lockup_epoch: None,
+            custodian: None,
         };
 
         let mut messages: Vec<Message> = vec![];
@@ -2115,6 +2723,7 @@ mod tests {
                 transaction,
                 last_valid_block_height,
                 lockup_date: None,
+                lockup_epoch: None,
             }
         );
         assert_eq!(messages.len(), 0);
@@ -2148,6 +2757,7 @@ mod tests {
             false,
             last_valid_block_height,
             None,
+            None,
         )
         .unwrap();
 
@@ -2171,6 +2781,8 @@ mod tests {
             recipient: recipient.to_string(),
             amount: sol_to_lamports(1.0),
             lockup_date: "".to_string(),
+            lockup_epoch: None,
+            custodian: None,
         };
         let message = transaction.message.clone();
 
@@ -2219,6 +2831,7 @@ mod tests {
             transaction,
             last_valid_block_height,
             lockup_date: None,
+            lockup_epoch: None,
         }));
         assert!(transaction_info.contains(&TransactionInfo {
             recipient,
@@ -2228,6 +2841,7 @@ mod tests {
             transaction: Transaction::new_unsigned(message),
             last_valid_block_height: std::u64::MAX,
             lockup_date: None,
+            lockup_epoch: None,
         }));
 
         // Next dump should write record written in last send_messages call
@@ -2274,6 +2888,8 @@ mod tests {
             recipient: recipient.to_string(),
             amount: sol_to_lamports(1.0),
             lockup_date: "".to_string(),
+            lockup_epoch: None,
+            custodian: None,
         };
         // This is just dummy data; Args will not affect messages
         let args = DistributeTokensArgs {
@@ -2325,6 +2941,7 @@ mod tests {
             false,
             last_valid_block_height,
             None,
+            None,
         )
         .unwrap();
 
@@ -2418,6 +3035,7 @@ mod tests {
             false,
             last_valid_block_height,
             None,
+            None,
         )
         .unwrap();
 
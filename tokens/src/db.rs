@@ -1,9 +1,19 @@
 use chrono::prelude::*;
 use pickledb::{error::Error, PickleDb, PickleDbDumpPolicy};
 use serde::{Deserialize, Serialize};
-use solana_sdk::{clock::Slot, pubkey::Pubkey, signature::Signature, transaction::Transaction};
+use solana_sdk::{
+    clock::{Epoch, Slot},
+    pubkey::Pubkey,
+    signature::Signature,
+    transaction::Transaction,
+};
 use solana_transaction_status::TransactionStatus;
-use std::{cmp::Ordering, fs, io, path::Path};
+use std::{
+    cmp::Ordering,
+    fs::{self, File, OpenOptions},
+    io::{self, BufReader},
+    path::{Path, PathBuf},
+};
 
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 pub struct TransactionInfo {
@@ -14,6 +24,8 @@ pub struct TransactionInfo {
     pub transaction: Transaction,
     pub last_valid_block_height: Slot,
     pub lockup_date: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub lockup_epoch: Option<Epoch>,
 }
 
 #[derive(Serialize, Deserialize, Debug, Default, PartialEq)]
@@ -24,6 +36,45 @@ struct SignedTransactionInfo {
     new_stake_account_address: String,
     finalized_date: Option<DateTime<Utc>>,
     signature: String,
+    #[serde(skip_serializing_if = "String::is_empty", default)]
+    lockup: String,
+}
+
+impl From<&TransactionInfo> for SignedTransactionInfo {
+    fn from(info: &TransactionInfo) -> Self {
+        // Stake distributions log the lockup of every stake account, so that each vesting
+        // tranche can be told apart
+        let lockup = if info.new_stake_account_address.is_some() {
+            lockup_description(info)
+        } else {
+            "".to_string()
+        };
+        Self {
+            recipient: info.recipient.to_string(),
+            amount: info.amount,
+            new_stake_account_address: info
+                .new_stake_account_address
+                .map(|x| x.to_string())
+                .unwrap_or_else(|| "".to_string()),
+            finalized_date: info.finalized_date,
+            signature: info.transaction.signatures[0].to_string(),
+            lockup,
+        }
+    }
+}
+
+/// Describe the lockup of a stake distribution, eg. `2021-01-07T00:00:00Z, epoch 300`
+pub fn lockup_description(info: &TransactionInfo) -> String {
+    let lockup_date = info
+        .lockup_date
+        .map(|date| date.to_rfc3339_opts(SecondsFormat::Secs, true));
+    let lockup_epoch = info.lockup_epoch.map(|epoch| format!("epoch {}", epoch));
+    let lockup: Vec<_> = lockup_date.into_iter().chain(lockup_epoch).collect();
+    if lockup.is_empty() {
+        "none".to_string()
+    } else {
+        lockup.join(", ")
+    }
 }
 
 impl Default for TransactionInfo {
@@ -40,18 +91,28 @@ impl Default for TransactionInfo {
             transaction,
             last_valid_block_height: 0,
             lockup_date: None,
+            lockup_epoch: None,
         }
     }
 }
 
-pub fn open_db(path: &str, dry_run: bool) -> Result<PickleDb, Error> {
+#[derive(thiserror::Error, Debug)]
+pub enum DbError {
+    #[error("PickleDb error")]
+    PickleDbError(#[from] Error),
+    #[error("I/O error")]
+    IoError(#[from] io::Error),
+}
+
+/// Open the database at `path`, including the records of its write-ahead log
+pub fn open_db(path: &str, dry_run: bool) -> Result<PickleDb, DbError> {
     let policy = if dry_run {
         PickleDbDumpPolicy::NeverDump
     } else {
         PickleDbDumpPolicy::DumpUponRequest
     };
     let path = Path::new(path);
-    let db = if path.exists() {
+    let mut db = if path.exists() {
         PickleDb::load_yaml(path, policy)?
     } else {
         if let Some(parent) = path.parent() {
@@ -59,9 +120,61 @@ pub fn open_db(path: &str, dry_run: bool) -> Result<PickleDb, Error> {
         }
         PickleDb::new_yaml(path, policy)
     };
+
+    let wal_path = wal_path(path);
+    if wal_path.exists() {
+        let mut wal = BufReader::new(File::open(&wal_path)?);
+        loop {
+            match bincode::deserialize_from::<_, TransactionInfo>(&mut wal) {
+                Ok(info) => db.set(&info.transaction.signatures[0].to_string(), &info)?,
+                // The log ends, possibly with a record torn by a crash. Records are synced
+                // before their transaction is sent, so a torn record was never broadcast.
+                Err(err) if is_eof(&err) => break,
+                Err(err) => return Err(io::Error::new(io::ErrorKind::InvalidData, err).into()),
+            }
+        }
+        if !dry_run {
+            dump_db(&mut db, path)?;
+        }
+    }
     Ok(db)
 }
 
+fn is_eof(err: &bincode::Error) -> bool {
+    matches!(&**err, bincode::ErrorKind::Io(err) if err.kind() == io::ErrorKind::UnexpectedEof)
+}
+
+/// Path of the write-ahead log of the database at `db_path`, which holds the records of
+/// transactions signed since the database was last dumped
+fn wal_path(db_path: &Path) -> PathBuf {
+    let mut path = db_path.as_os_str().to_owned();
+    path.push(".wal");
+    path.into()
+}
+
+/// Append the record of a signed transaction to the write-ahead log of the database at
+/// `db_path` and sync it to disk. Unlike a dump, the cost does not grow with the database.
+pub fn append_to_wal<P: AsRef<Path>>(db_path: P, info: &TransactionInfo) -> io::Result<()> {
+    let mut wal = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(wal_path(db_path.as_ref()))?;
+    bincode::serialize_into(&mut wal, info)
+        .map_err(|err| io::Error::new(io::ErrorKind::Other, err))?;
+    wal.sync_data()
+}
+
+/// Dump the database at `db_path`, then remove its write-ahead log, whose records the
+/// database must already hold
+pub fn dump_db<P: AsRef<Path>>(db: &mut PickleDb, db_path: P) -> Result<(), DbError> {
+    db.dump()?;
+    let wal_path = wal_path(db_path.as_ref());
+    if wal_path.exists() {
+        fs::remove_file(wal_path)?;
+    }
+    Ok(())
+}
+
 pub fn compare_transaction_infos(a: &TransactionInfo, b: &TransactionInfo) -> Ordering {
     let ordering = match (a.finalized_date, b.finalized_date) {
         (Some(a), Some(b)) => a.cmp(&b),
@@ -80,17 +193,7 @@ pub fn write_transaction_log<P: AsRef<Path>>(db: &PickleDb, path: &P) -> Result<
     let mut transaction_infos = read_transaction_infos(db);
     transaction_infos.sort_by(compare_transaction_infos);
     for info in transaction_infos {
-        let signed_info = SignedTransactionInfo {
-            recipient: info.recipient.to_string(),
-            amount: info.amount,
-            new_stake_account_address: info
-                .new_stake_account_address
-                .map(|x| x.to_string())
-                .unwrap_or_else(|| "".to_string()),
-            finalized_date: info.finalized_date,
-            signature: info.transaction.signatures[0].to_string(),
-        };
-        wtr.serialize(&signed_info)?;
+        wtr.serialize(&SignedTransactionInfo::from(&info))?;
     }
     wtr.flush()
 }
@@ -101,6 +204,7 @@ pub fn read_transaction_infos(db: &PickleDb) -> Vec<TransactionInfo> {
         .collect()
 }
 
+#[allow(clippy::too_many_arguments)]
 pub fn set_transaction_info(
     db: &mut PickleDb,
     recipient: &Pubkey,
@@ -110,7 +214,8 @@ pub fn set_transaction_info(
     finalized: bool,
     last_valid_block_height: u64,
     lockup_date: Option<DateTime<Utc>>,
-) -> Result<(), Error> {
+    lockup_epoch: Option<Epoch>,
+) -> Result<TransactionInfo, Error> {
     let finalized_date = if finalized { Some(Utc::now()) } else { None };
     let transaction_info = TransactionInfo {
         recipient: *recipient,
@@ -120,10 +225,11 @@ pub fn set_transaction_info(
         transaction: transaction.clone(),
         last_valid_block_height,
         lockup_date,
+        lockup_epoch,
     };
     let signature = transaction.signatures[0];
     db.set(&signature.to_string(), &transaction_info)?;
-    Ok(())
+    Ok(transaction_info)
 }
 
 // Set the finalized bit in the database if the transaction is rooted.
@@ -140,7 +246,7 @@ pub fn update_finalized_transaction(
     if opt_transaction_status.is_none() {
         if finalized_block_height > last_valid_block_height {
             eprintln!(
-                "Signature not found {} and blockhash expired. Transaction either dropped or the validator purged the transaction status.",
+                "Signature not found {} and blockhash expired. Transaction either dropped or the validator purged the transaction status. Run `solana-tokens reconcile` to resolve it.",
                 signature
             );
 
@@ -179,6 +285,62 @@ pub fn update_finalized_transaction(
     Ok(None)
 }
 
+#[derive(Debug, PartialEq)]
+pub enum ReconcileStatus {
+    /// The transaction is rooted
+    Finalized,
+    /// The transaction is not rooted yet, or may still land
+    Pending,
+    /// The transaction was rooted, but execution failed. The record was removed.
+    Failed,
+    /// The transaction was never processed and its blockhash has expired, so it can no longer
+    /// land. The record was removed.
+    Dropped,
+    /// The transaction is unknown to the cluster and its blockhash has expired, but the history of
+    /// the node doesn't cover the lifetime of the blockhash, so it may have landed. The record
+    /// was kept.
+    Unresolved,
+}
+
+// Resolve a transaction that may never have been broadcast. Unlike
+// `update_finalized_transaction`, the record of a transaction that is unknown to the cluster
+// is discarded once its blockhash expires, but only if `history_covers_transaction`, the
+// history the status was looked up in, spans the lifetime of the blockhash. A node reports no
+// status for a transaction that landed before the start of its history.
+pub fn reconcile_transaction(
+    db: &mut PickleDb,
+    signature: &Signature,
+    opt_transaction_status: Option<TransactionStatus>,
+    last_valid_block_height: u64,
+    finalized_block_height: u64,
+    history_covers_transaction: bool,
+) -> Result<ReconcileStatus, Error> {
+    let transaction_status = match opt_transaction_status {
+        Some(transaction_status) => transaction_status,
+        None if finalized_block_height > last_valid_block_height => {
+            if !history_covers_transaction {
+                return Ok(ReconcileStatus::Unresolved);
+            }
+            db.rem(&signature.to_string())?;
+            return Ok(ReconcileStatus::Dropped);
+        }
+        None => return Ok(ReconcileStatus::Pending),
+    };
+    if transaction_status.confirmations.is_some() {
+        return Ok(ReconcileStatus::Pending);
+    }
+    if transaction_status.err.is_some() {
+        db.rem(&signature.to_string())?;
+        return Ok(ReconcileStatus::Failed);
+    }
+    let mut transaction_info = db.get::<TransactionInfo>(&signature.to_string()).unwrap();
+    if transaction_info.finalized_date.is_none() {
+        transaction_info.finalized_date = Some(Utc::now());
+        db.set(&signature.to_string(), &transaction_info)?;
+    }
+    Ok(ReconcileStatus::Finalized)
+}
+
 use csv::{ReaderBuilder, Trim};
 pub(crate) fn check_output_file(path: &str, db: &PickleDb) {
     let mut rdr = ReaderBuilder::new()
@@ -192,16 +354,7 @@ pub(crate) fn check_output_file(path: &str, db: &PickleDb) {
     transaction_infos.sort_by(compare_transaction_infos);
     let transaction_infos: Vec<SignedTransactionInfo> = transaction_infos
         .iter()
-        .map(|info| SignedTransactionInfo {
-            recipient: info.recipient.to_string(),
-            amount: info.amount,
-            new_stake_account_address: info
-                .new_stake_account_address
-                .map(|x| x.to_string())
-                .unwrap_or_else(|| "".to_string()),
-            finalized_date: info.finalized_date,
-            signature: info.transaction.signatures[0].to_string(),
-        })
+        .map(SignedTransactionInfo::from)
         .collect();
     assert_eq!(logged_infos, transaction_infos);
 }
@@ -263,6 +416,209 @@ mod tests {
         assert_eq!(signed_infos, vec![signed_info]);
     }
 
+    #[test]
+    fn test_write_transaction_log_stake_lockups() {
+        let mut db =
+            PickleDb::new_yaml(NamedTempFile::new().unwrap(), PickleDbDumpPolicy::NeverDump);
+        let recipient = solana_sdk::pubkey::new_rand();
+        let stake_account_address = solana_sdk::pubkey::new_rand();
+        let lockup_date = Utc.ymd(2021, 1, 7).and_hms(0, 0, 0);
+        let lockups = [
+            (None, None, "none"),
+            (Some(lockup_date), None, "2021-01-07T00:00:00Z"),
+            (
+                Some(lockup_date),
+                Some(300),
+                "2021-01-07T00:00:00Z, epoch 300",
+            ),
+        ];
+        for (i, (lockup_date, lockup_epoch, _)) in lockups.iter().enumerate() {
+            let transaction = Transaction {
+                signatures: vec![Signature::new(&[i as u8; 64])],
+                ..Transaction::default()
+            };
+            set_transaction_info(
+                &mut db,
+                &recipient,
+                42,
+                &transaction,
+                Some(&stake_account_address),
+                false,
+                0,
+                *lockup_date,
+                *lockup_epoch,
+            )
+            .unwrap();
+        }
+
+        let csv_file = NamedTempFile::new().unwrap();
+        write_transaction_log(&db, &csv_file).unwrap();
+        let mut rdr = ReaderBuilder::new().trim(Trim::All).from_reader(csv_file);
+        let mut logged_lockups: Vec<String> = rdr
+            .deserialize::<SignedTransactionInfo>()
+            .map(|entry| entry.unwrap().lockup)
+            .collect();
+        logged_lockups.sort();
+        let mut expected_lockups: Vec<_> = lockups.iter().map(|(_, _, x)| x.to_string()).collect();
+        expected_lockups.sort();
+        assert_eq!(logged_lockups, expected_lockups);
+    }
+
+    #[test]
+    fn test_reconcile_transaction() {
+        let mut db =
+            PickleDb::new_yaml(NamedTempFile::new().unwrap(), PickleDbDumpPolicy::NeverDump);
+        let signature = Signature::default();
+        let transaction_info = TransactionInfo::default();
+        db.set(&signature.to_string(), &transaction_info).unwrap();
+        let history_covers_transaction = true;
+        let finalized_status = TransactionStatus {
+            slot: 0,
+            confirmations: None,
+            err: None,
+            status: Ok(()),
+            confirmation_status: Some(TransactionConfirmationStatus::Finalized),
+        };
+
+        // Unknown to the cluster, but the blockhash is still valid
+        assert_eq!(
+            reconcile_transaction(&mut db, &signature, None, 1, 1, history_covers_transaction)
+                .unwrap(),
+            ReconcileStatus::Pending
+        );
+        assert_eq!(
+            db.get::<TransactionInfo>(&signature.to_string()).unwrap(),
+            transaction_info
+        );
+
+        // Confirmed, but not yet rooted
+        let confirmed_status = TransactionStatus {
+            confirmations: Some(1),
+            confirmation_status: Some(TransactionConfirmationStatus::Confirmed),
+            ..finalized_status.clone()
+        };
+        assert_eq!(
+            reconcile_transaction(
+                &mut db,
+                &signature,
+                Some(confirmed_status),
+                1,
+                2,
+                history_covers_transaction
+            )
+            .unwrap(),
+            ReconcileStatus::Pending
+        );
+
+        // Rooted
+        assert_eq!(
+            reconcile_transaction(
+                &mut db,
+                &signature,
+                Some(finalized_status.clone()),
+                1,
+                2,
+                history_covers_transaction
+            )
+            .unwrap(),
+            ReconcileStatus::Finalized
+        );
+        let finalized_date = db
+            .get::<TransactionInfo>(&signature.to_string())
+            .unwrap()
+            .finalized_date;
+        assert!(finalized_date.is_some());
+
+        // Rooted, but execution failed
+        let failed_status = TransactionStatus {
+            err: Some(TransactionError::AccountNotFound),
+            ..finalized_status
+        };
+        assert_eq!(
+            reconcile_transaction(
+                &mut db,
+                &signature,
+                Some(failed_status),
+                1,
+                2,
+                history_covers_transaction
+            )
+            .unwrap(),
+            ReconcileStatus::Failed
+        );
+        assert!(db.get::<TransactionInfo>(&signature.to_string()).is_none());
+
+        // Unknown to the node and the blockhash has expired, but it may have landed before the
+        // history of the node begins
+        db.set(&signature.to_string(), &transaction_info).unwrap();
+        assert_eq!(
+            reconcile_transaction(&mut db, &signature, None, 1, 2, false).unwrap(),
+            ReconcileStatus::Unresolved
+        );
+        assert_eq!(
+            db.get::<TransactionInfo>(&signature.to_string()).unwrap(),
+            transaction_info
+        );
+
+        // Unknown to the cluster and the blockhash has expired, so it can no longer land
+        assert_eq!(
+            reconcile_transaction(&mut db, &signature, None, 1, 2, history_covers_transaction)
+                .unwrap(),
+            ReconcileStatus::Dropped
+        );
+        assert!(db.get::<TransactionInfo>(&signature.to_string()).is_none());
+    }
+
+    #[test]
+    fn test_open_db_replays_wal() {
+        let dir = tempfile::tempdir().unwrap();
+        let db_path = dir.path().join("transactions.db");
+        let db_path = db_path.to_str().unwrap();
+        let mut db = open_db(db_path, false).unwrap();
+        let info0 = TransactionInfo {
+            recipient: solana_sdk::pubkey::new_rand(),
+            ..TransactionInfo::default()
+        };
+        db.set("0", &info0).unwrap();
+        dump_db(&mut db, db_path).unwrap();
+
+        let transaction = Transaction {
+            signatures: vec![Signature::new(&[1; 64])],
+            ..Transaction::default()
+        };
+        let info1 = TransactionInfo {
+            recipient: solana_sdk::pubkey::new_rand(),
+            amount: 42,
+            transaction,
+            last_valid_block_height: 7,
+            ..TransactionInfo::default()
+        };
+        append_to_wal(db_path, &info1).unwrap();
+        // A record torn by a crash is ignored
+        let mut wal = OpenOptions::new()
+            .append(true)
+            .open(wal_path(Path::new(db_path)))
+            .unwrap();
+        io::Write::write_all(&mut wal, &[1, 2, 3]).unwrap();
+
+        let signature = info1.transaction.signatures[0].to_string();
+
+        // Reading the database includes the log, without consuming it
+        let db = open_db(db_path, true).unwrap();
+        assert_eq!(db.total_keys(), 2);
+        assert_eq!(db.get::<TransactionInfo>("0").unwrap(), info0);
+        assert_eq!(db.get::<TransactionInfo>(&signature).unwrap(), info1);
+        assert!(wal_path(Path::new(db_path)).exists());
+
+        // Opening it for writing moves the log into the database
+        open_db(db_path, false).unwrap();
+        assert!(!wal_path(Path::new(db_path)).exists());
+        let db = open_db(db_path, true).unwrap();
+        assert_eq!(db.total_keys(), 2);
+        assert_eq!(db.get::<TransactionInfo>("0").unwrap(), info0);
+        assert_eq!(db.get::<TransactionInfo>(&signature).unwrap(), info1);
+    }
+
     #[test]
     fn test_update_finalized_transaction_not_landed() {
         // Keep waiting for a transaction that hasn't landed yet.
@@ -47,6 +47,9 @@ fn main() -> Result<(), Box<dyn Error>> {
         Command::TransactionLog(args) => {
             commands::process_transaction_log(&args)?;
         }
+        Command::Reconcile(args) => {
+            commands::process_reconcile(&client, &args)?;
+        }
     }
     Ok(())
 }
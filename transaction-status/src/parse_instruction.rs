@@ -8,7 +8,10 @@ use crate::{
 };
 use inflector::Inflector;
 use serde_json::Value;
-use solana_account_decoder::parse_token::spl_token_id_v2_0;
+use solana_account_decoder::{
+    parse_idl::{get_idl_parser, IdlError},
+    parse_token::spl_token_id_v2_0,
+};
 use solana_sdk::{instruction::CompiledInstruction, pubkey::Pubkey, stake, system_program};
 use std::{collections::HashMap, str::from_utf8};
 use thiserror::Error;
@@ -57,6 +60,12 @@ pub enum ParseInstructionError {
 
     #[error("Internal error, please report")]
     SerdeJsonError(#[from] serde_json::error::Error),
+
+    #[error("IDL error: {0}")]
+    IdlError(#[from] IdlError),
+
+    #[error("Instruction account index out of bounds")]
+    AccountIndexOutOfBounds,
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
@@ -94,9 +103,10 @@ pub fn parse(
     instruction: &CompiledInstruction,
     account_keys: &[Pubkey],
 ) -> Result<ParsedInstruction, ParseInstructionError> {
-    let program_name = PARSABLE_PROGRAM_IDS
-        .get(program_id)
-        .ok_or(ParseInstructionError::ProgramNotParsable)?;
+    let program_name = match PARSABLE_PROGRAM_IDS.get(program_id) {
+        Some(program_name) => program_name,
+        None => return parse_with_idl(program_id, instruction, account_keys),
+    };
     let parsed_json = match program_name {
         ParsableProgram::SplAssociatedTokenAccount => {
             serde_json::to_value(parse_associated_token(instruction, account_keys)?)?
@@ -120,6 +130,25 @@ pub fn parse(
     })
 }
 
+fn parse_with_idl(
+    program_id: &Pubkey,
+    instruction: &CompiledInstruction,
+    account_keys: &[Pubkey],
+) -> Result<ParsedInstruction, ParseInstructionError> {
+    let parser = get_idl_parser(program_id).ok_or(ParseInstructionError::ProgramNotParsable)?;
+    let accounts = instruction
+        .accounts
+        .iter()
+        .map(|index| account_keys.get(*index as usize).copied())
+        .collect::<Option<Vec<_>>>()
+        .ok_or(ParseInstructionError::AccountIndexOutOfBounds)?;
+    Ok(ParsedInstruction {
+        program: parser.program_name(),
+        program_id: program_id.to_string(),
+        parsed: parser.parse_instruction(&instruction.data, &accounts)?,
+    })
+}
+
 fn parse_memo(instruction: &CompiledInstruction) -> Result<Value, ParseInstructionError> {
     from_utf8(&instruction.data)
         .map(|s| Value::String(s.to_string()))
@@ -173,6 +202,52 @@ mod test {
         assert!(parse(&non_parsable_program_id, &memo_instruction, &[]).is_err());
     }
 
+    #[test]
+    fn test_parse_idl() {
+        let program_id = Pubkey::new(&[2; 32]);
+        let keys = vec![Pubkey::new(&[3; 32]), program_id];
+        let idl = serde_json::from_value(json!({
+            "name": "counter",
+            "instructions": [{
+                "name": "increment",
+                "accounts": [{"name": "counter", "isMut": true, "isSigner": false}],
+                "args": [{"name": "by", "type": "u32"}],
+                "discriminator": [1],
+            }],
+        }))
+        .unwrap();
+        solana_account_decoder::parse_idl::register_idl_parser(
+            program_id,
+            solana_account_decoder::parse_idl::IdlParser::new(idl).unwrap(),
+        );
+
+        let instruction = CompiledInstruction {
+            program_id_index: 1,
+            accounts: vec![0],
+            data: vec![1, 2, 0, 0, 0],
+        };
+        assert_eq!(
+            parse(&program_id, &instruction, &keys).unwrap(),
+            ParsedInstruction {
+                program: "counter".to_string(),
+                program_id: program_id.to_string(),
+                parsed: json!({
+                    "type": "increment",
+                    "info": {"by": 2, "counter": keys[0].to_string()},
+                }),
+            }
+        );
+
+        let bad_index = CompiledInstruction {
+            accounts: vec![2],
+            ..instruction
+        };
+        assert!(matches!(
+            parse(&program_id, &bad_index, &keys),
+            Err(ParseInstructionError::AccountIndexOutOfBounds)
+        ));
+    }
+
     #[test]
     fn test_parse_memo() {
         let good_memo = "good memo".to_string();
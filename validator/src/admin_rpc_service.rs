@@ -5,17 +5,24 @@ use {
     jsonrpc_ipc_server::{RequestContext, ServerBuilder},
     jsonrpc_server_utils::tokio,
     log::*,
+    serde::{Deserialize, Serialize},
     solana_core::{
         consensus::Tower, tower_storage::TowerStorage, validator::ValidatorStartProgress,
     },
-    solana_gossip::cluster_info::ClusterInfo,
+    solana_gossip::{
+        cluster_info::{ClusterInfo, GossipPushPullStats},
+        crds_value::{CrdsData, CrdsValueLabel},
+    },
+    solana_runtime::{accounts_db::StorageReport, bank::Bank, bank_forks::BankForks},
     solana_sdk::{
         exit::Exit,
+        pubkey::Pubkey,
         signature::{read_keypair_file, Keypair, Signer},
     },
     std::{
         net::SocketAddr,
         path::Path,
+        str::FromStr,
         sync::{Arc, RwLock},
         thread::{self, Builder},
         time::{Duration, SystemTime},
@@ -30,10 +37,57 @@ pub struct AdminRpcRequestMetadata {
     pub validator_exit: Arc<RwLock<Exit>>,
     pub authorized_voter_keypairs: Arc<RwLock<Vec<Arc<Keypair>>>>,
     pub cluster_info: Arc<RwLock<Option<Arc<ClusterInfo>>>>,
+    pub bank_forks: Arc<RwLock<Option<Arc<RwLock<BankForks>>>>>,
     pub tower_storage: Arc<dyn TowerStorage>,
 }
 impl Metadata for AdminRpcRequestMetadata {}
 
+impl AdminRpcRequestMetadata {
+    fn with_cluster_info<F, T>(&self, f: F) -> Result<T>
+    where
+        F: FnOnce(&ClusterInfo) -> T,
+    {
+        match self.cluster_info.read().unwrap().as_ref() {
+            Some(cluster_info) => Ok(f(cluster_info)),
+            None => Err(jsonrpc_core::error::Error::invalid_params(
+                "Retry once validator start up is complete",
+            )),
+        }
+    }
+
+    fn with_root_bank<F, T>(&self, f: F) -> Result<T>
+    where
+        F: FnOnce(&Bank) -> T,
+    {
+        match self.bank_forks.read().unwrap().as_ref() {
+            Some(bank_forks) => {
+                let root_bank = bank_forks.read().unwrap().root_bank();
+                Ok(f(&root_bank))
+            }
+            None => Err(jsonrpc_core::error::Error::invalid_params(
+                "Retry once validator start up is complete",
+            )),
+        }
+    }
+}
+
+/// Number of the sparsest storages listed by `accountsStorageReport` by default
+pub const DEFAULT_STORAGE_REPORT_LIMIT: usize = 20;
+
+/// A value from the node's gossip CRDS table
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AdminRpcCrdsValue {
+    pub label: String,
+    pub kind: String,
+    pub origin: String,
+    pub wallclock: u64,
+    /// Local time, in milliseconds since the UNIX epoch, at which the value
+    /// was inserted into the table
+    pub insert_timestamp: u64,
+    pub data: CrdsData,
+}
+
 #[rpc]
 pub trait AdminRpc {
     type Metadata;
@@ -61,6 +115,27 @@ pub trait AdminRpc {
 
     #[rpc(meta, name = "setIdentity")]
     fn set_identity(&self, meta: Self::Metadata, keypair_file: String) -> Result<()>;
+
+    #[rpc(meta, name = "gossipCrdsValues")]
+    fn gossip_crds_values(
+        &self,
+        meta: Self::Metadata,
+        kind: Option<String>,
+        origin: Option<String>,
+    ) -> Result<Vec<AdminRpcCrdsValue>>;
+
+    #[rpc(meta, name = "gossipPushPullStats")]
+    fn gossip_push_pull_stats(&self, meta: Self::Metadata) -> Result<GossipPushPullStats>;
+
+    #[rpc(meta, name = "accountsStorageReport")]
+    fn accounts_storage_report(
+        &self,
+        meta: Self::Metadata,
+        limit: Option<usize>,
+    ) -> Result<StorageReport>;
+
+    #[rpc(meta, name = "shrinkAccountsStorage")]
+    fn shrink_accounts_storage(&self, meta: Self::Metadata, target_alive_ratio: f64) -> Result<()>;
 }
 
 pub struct AdminRpcImpl;
@@ -167,6 +242,84 @@ impl AdminRpc for AdminRpcImpl {
             ))
         }
     }
+
+    fn gossip_crds_values(
+        &self,
+        meta: Self::Metadata,
+        kind: Option<String>,
+        origin: Option<String>,
+    ) -> Result<Vec<AdminRpcCrdsValue>> {
+        debug!("gossip_crds_values request received");
+
+        if let Some(kind) = &kind {
+            if !CrdsValueLabel::KINDS.contains(&kind.as_str()) {
+                return Err(jsonrpc_core::error::Error::invalid_params(format!(
+                    "Unknown kind {}, expected one of: {}",
+                    kind,
+                    CrdsValueLabel::KINDS.join(", ")
+                )));
+            }
+        }
+        let origin = origin
+            .map(|origin| {
+                Pubkey::from_str(&origin).map_err(|err| {
+                    jsonrpc_core::error::Error::invalid_params(format!(
+                        "Invalid origin {}: {}",
+                        origin, err
+                    ))
+                })
+            })
+            .transpose()?;
+
+        let values = meta.with_cluster_info(|cluster_info| {
+            cluster_info.get_crds_values(origin.as_ref(), kind.as_deref())
+        })?;
+        Ok(values
+            .into_iter()
+            .map(|(value, insert_timestamp)| {
+                let label = value.label();
+                AdminRpcCrdsValue {
+                    label: label.to_string(),
+                    kind: label.kind().to_string(),
+                    origin: value.pubkey().to_string(),
+                    wallclock: value.wallclock(),
+                    insert_timestamp,
+                    data: value.data,
+                }
+            })
+            .collect())
+    }
+
+    fn gossip_push_pull_stats(&self, meta: Self::Metadata) -> Result<GossipPushPullStats> {
+        debug!("gossip_push_pull_stats request received");
+        meta.with_cluster_info(ClusterInfo::push_pull_stats)
+    }
+
+    fn accounts_storage_report(
+        &self,
+        meta: Self::Metadata,
+        limit: Option<usize>,
+    ) -> Result<StorageReport> {
+        debug!("accounts_storage_report request received");
+        let limit = limit.unwrap_or(DEFAULT_STORAGE_REPORT_LIMIT);
+        meta.with_root_bank(|bank| bank.storage_report(limit))
+    }
+
+    fn shrink_accounts_storage(&self, meta: Self::Metadata, target_alive_ratio: f64) -> Result<()> {
+        debug!("shrink_accounts_storage request received");
+        if !(0.0..=1.0).contains(&target_alive_ratio) {
+            return Err(jsonrpc_core::error::Error::invalid_params(format!(
+                "Invalid target alive ratio {}, expected a value between 0 and 1",
+                target_alive_ratio
+            )));
+        }
+        meta.with_root_bank(|bank| bank.request_shrink(target_alive_ratio))?;
+        warn!(
+            "accounts storage shrink to alive ratio {} requested",
+            target_alive_ratio
+        );
+        Ok(())
+    }
 }
 
 // Start the Admin RPC interface
@@ -512,6 +512,7 @@ fn main() {
     let tower_storage = Arc::new(FileTowerStorage::new(ledger_path.clone()));
 
     let admin_service_cluster_info = Arc::new(RwLock::new(None));
+    let admin_service_bank_forks = Arc::new(RwLock::new(None));
     admin_rpc_service::run(
         &ledger_path,
         admin_rpc_service::AdminRpcRequestMetadata {
@@ -524,6 +525,7 @@ fn main() {
             validator_exit: genesis.validator_exit.clone(),
             authorized_voter_keypairs: genesis.authorized_voter_keypairs.clone(),
             cluster_info: admin_service_cluster_info.clone(),
+            bank_forks: admin_service_bank_forks.clone(),
             tower_storage: tower_storage.clone(),
         },
     );
@@ -599,6 +601,7 @@ fn main() {
     match genesis.start_with_mint_address(mint_address, socket_addr_space) {
         Ok(test_validator) => {
             *admin_service_cluster_info.write().unwrap() = Some(test_validator.cluster_info());
+            *admin_service_bank_forks.write().unwrap() = Some(test_validator.bank_forks());
             if let Some(dashboard) = dashboard {
                 dashboard.run(Duration::from_millis(250));
             }
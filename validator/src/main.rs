@@ -7,6 +7,7 @@ use {
     console::style,
     log::*,
     rand::{seq::SliceRandom, thread_rng, Rng},
+    solana_account_decoder::parse_idl::register_idl_file,
     solana_clap_utils::{
         input_parsers::{keypair_of, keypairs_of, pubkey_of, value_of},
         input_validators::{
@@ -32,6 +33,7 @@ use {
     solana_gossip::{
         cluster_info::{ClusterInfo, Node, VALIDATOR_PORT_RANGE},
         contact_info::ContactInfo,
+        crds_value::CrdsValueLabel,
         gossip_service::GossipService,
     },
     solana_ledger::blockstore_db::BlockstoreRecoveryMode,
@@ -67,7 +69,7 @@ use {
         redirect_stderr_to_file,
     },
     std::{
-        collections::{HashSet, VecDeque},
+        collections::{HashMap, HashSet, VecDeque},
         env,
         fs::{self, File},
         net::{IpAddr, SocketAddr, TcpListener, UdpSocket},
@@ -909,7 +911,7 @@ fn rpc_bootstrap(
                             info!("RPC node root slot: {}", slot);
                             let (cluster_info, gossip_exit_flag, gossip_service) =
                                 gossip.take().unwrap();
-                            cluster_info.save_contact_info();
+                            cluster_info.save_contact_info_with_saved_stakes();
                             gossip_exit_flag.store(true, Ordering::Relaxed);
                             let maximum_snapshots_to_retain = if let Some(snapshot_config) =
                                 validator_config.snapshot_config.as_ref()
@@ -1008,7 +1010,7 @@ fn rpc_bootstrap(
         blacklisted_rpc_nodes.insert(rpc_contact_info.id);
     }
     if let Some((cluster_info, gossip_exit_flag, gossip_service)) = gossip.take() {
-        cluster_info.save_contact_info();
+        cluster_info.save_contact_info_with_saved_stakes();
         gossip_exit_flag.store(true, Ordering::Relaxed);
         gossip_service.join().unwrap();
     }
@@ -1235,6 +1237,15 @@ pub fn main() {
                 .takes_value(false)
                 .help("Upload new confirmed blocks into a BigTable instance"),
         )
+        .arg(
+            Arg::with_name("local_ledger_storage_path")
+                .long("local-ledger-storage-path")
+                .value_name("DIR")
+                .takes_value(true)
+                .help("Use a local database at DIR in place of the BigTable instance \
+                       for --enable-rpc-bigtable-ledger-storage and \
+                       --enable-bigtable-ledger-upload"),
+        )
         .arg(
             Arg::with_name("enable_cpi_and_log_storage")
                 .long("enable-cpi-and-log-storage")
@@ -1644,6 +1655,15 @@ pub fn main() {
                 .validator(solana_net_utils::is_host)
                 .help("IP address to bind the RPC port [default: use --bind-address]"),
         )
+        .arg(
+            Arg::with_name("prometheus_bind_address")
+                .long("prometheus-bind-address")
+                .value_name("HOST:PORT")
+                .takes_value(true)
+                .validator(solana_net_utils::is_host_port)
+                .help("Mirror metrics into a Prometheus registry served on \
+                       http://HOST:PORT/metrics"),
+        )
         .arg(
             Arg::with_name("rpc_threads")
                 .long("rpc-threads")
@@ -1742,6 +1762,24 @@ pub fn main() {
                 .requires("enable_rpc_transaction_history")
                 .help("Verifies blockstore roots on boot and fixes any gaps"),
         )
+        .arg(
+            Arg::with_name("enable_rpc_account_proofs")
+                .long("enable-rpc-account-proofs")
+                .takes_value(false)
+                .help("Enable the getAccountProof RPC method, proving accounts against \
+                       the accounts hash of the last snapshot. Each snapshot scans the \
+                       accounts index again, and keeps the hashes of all accounts in memory"),
+        )
+        .arg(
+            Arg::with_name("rpc_parser_idl")
+                .long("rpc-parser-idl")
+                .value_name("[PROGRAM_ID=]FILE")
+                .takes_value(true)
+                .multiple(true)
+                .help("Decode accounts and instructions of a program for jsonParsed RPC \
+                       encoding using an Anchor-style IDL file. The program id defaults \
+                       to the IDL's metadata address. May be specified multiple times"),
+        )
         .arg(
             Arg::with_name("halt_on_trusted_validators_accounts_hash_mismatch")
                 .long("halt-on-trusted-validators-accounts-hash-mismatch")
@@ -1871,6 +1909,18 @@ pub fn main() {
                 .help("Enables faster starting of validators by skipping shrink. \
                       This option is for use during testing."),
         )
+        .arg(
+            Arg::with_name("account_write_log")
+                .long("account-write-log")
+                .help("Record the lamports and hash of every account written by each \
+                       transaction in the blockstore"),
+        )
+        .arg(
+            Arg::with_name("account_write_log_data")
+                .long("account-write-log-data")
+                .requires("account_write_log")
+                .help("Also record account data in the account write log"),
+        )
         .arg(
             Arg::with_name("accounts_index_bins")
                 .long("accounts-index-bins")
@@ -1879,6 +1929,36 @@ pub fn main() {
                 .takes_value(true)
                 .help("Number of bins to divide the accounts index into"),
         )
+        .arg(
+            Arg::with_name("accounts_index_memory_limit_mb")
+                .long("accounts-index-memory-limit-mb")
+                .value_name("MB")
+                .validator(is_parsable::<usize>)
+                .takes_value(true)
+                .help("How much memory the accounts index can consume. If this is exceeded, \
+                       some account index entries will be stored on disk. \
+                       [default: the whole index is kept in memory]"),
+        )
+        .arg(
+            Arg::with_name("accounts_index_path")
+                .long("accounts-index-path")
+                .value_name("PATH")
+                .takes_value(true)
+                .multiple(true)
+                .help("Scratch space for the disk-backed accounts index, which is \
+                       rebuilt at startup; existing contents are erased. \
+                       May be specified multiple times. \
+                       [default: [ledger]/accounts_index when the accounts index memory is limited]"),
+        )
+        .arg(
+            Arg::with_name("accounts_hash_cache_path")
+                .long("accounts-hash-cache-path")
+                .value_name("PATH")
+                .takes_value(true)
+                .help("Keep the finished passes of accounts hash calculations in PATH, so that \
+                       a calculation interrupted by a restart, such as the verification of the \
+                       startup snapshot, resumes where it left off"),
+        )
         .arg(
             Arg::with_name("accounts_db_test_hash_calculation")
                 .long("accounts-db-test-hash-calculation")
@@ -1996,6 +2076,33 @@ pub fn main() {
                              currently running validator instance")
             )
         )
+        .subcommand(
+            SubCommand::with_name("gossip-dump")
+            .about("Display the validator's gossip CRDS table and push/pull stats")
+            .arg(
+                Arg::with_name("kind")
+                    .long("kind")
+                    .value_name("KIND")
+                    .takes_value(true)
+                    .possible_values(CrdsValueLabel::KINDS)
+                    .help("Only display values of this kind")
+            )
+            .arg(
+                Arg::with_name("origin")
+                    .long("origin")
+                    .value_name("PUBKEY")
+                    .takes_value(true)
+                    .validator(is_pubkey)
+                    .help("Only display values from this origin node")
+            )
+            .arg(
+                Arg::with_name("stats_only")
+                    .long("stats-only")
+                    .takes_value(false)
+                    .conflicts_with_all(&["kind", "origin"])
+                    .help("Only display push and pull stats")
+            )
+        )
         .subcommand(
             SubCommand::with_name("init")
             .about("Initialize the ledger directory then exit")
@@ -2033,6 +2140,33 @@ pub fn main() {
             )
             .after_help("Note: the new filter only applies to the currently running validator instance")
         )
+        .subcommand(
+            SubCommand::with_name("shrink-storage")
+            .about("Shrink the sparsest accounts storages until a target share of their bytes is alive")
+            .arg(
+                Arg::with_name("target_alive_ratio")
+                    .takes_value(true)
+                    .index(1)
+                    .value_name("RATIO")
+                    .validator(is_parsable::<f64>)
+                    .required(true)
+                    .help("Ratio of alive bytes to storage bytes to reach, between 0 and 1")
+            )
+            .after_help("Note: the shrink runs in the background, check its progress with `storage-report`")
+        )
+        .subcommand(
+            SubCommand::with_name("storage-report")
+            .about("Display the fragmentation of the accounts storages and shrink and clean activity")
+            .arg(
+                Arg::with_name("limit")
+                    .long("limit")
+                    .takes_value(true)
+                    .value_name("COUNT")
+                    .validator(is_parsable::<usize>)
+                    .default_value("20")
+                    .help("Number of the sparsest storages to list")
+            )
+        )
         .subcommand(
             SubCommand::with_name("wait-for-restart-window")
             .about("Monitor the validator for a good time to restart")
@@ -2114,6 +2248,43 @@ pub fn main() {
                 _ => unreachable!(),
             }
         }
+        ("gossip-dump", Some(subcommand_matches)) => {
+            let kind = value_t!(subcommand_matches, "kind", String).ok();
+            let origin = value_t!(subcommand_matches, "origin", String).ok();
+            let stats_only = subcommand_matches.is_present("stats_only");
+
+            let admin_client = admin_rpc_service::connect(&ledger_path);
+            let (stats, values) = admin_rpc_service::runtime()
+                .block_on(async move {
+                    let admin_client = admin_client.await?;
+                    let stats = admin_client.gossip_push_pull_stats().await?;
+                    let values = if stats_only {
+                        vec![]
+                    } else {
+                        admin_client.gossip_crds_values(kind, origin).await?
+                    };
+                    Ok::<_, jsonrpc_core_client::RpcError>((stats, values))
+                })
+                .unwrap_or_else(|err| {
+                    println!("gossip dump request failed: {}", err);
+                    exit(1);
+                });
+
+            for value in &values {
+                println!("{}", value.label);
+                println!("  Origin: {}", value.origin);
+                println!("  Wallclock: {}", value.wallclock);
+                println!("  Inserted: {}", value.insert_timestamp);
+                println!("  Data: {:?}", value.data);
+            }
+            if !stats_only {
+                println!("{} values", values.len());
+                println!();
+            }
+            println!("Push and pull stats since startup:");
+            println!("{:#?}", stats);
+            return;
+        }
         ("init", _) => Operation::Initialize,
         ("exit", Some(subcommand_matches)) => {
             let min_idle_time = value_t_or_exit!(subcommand_matches, "min_idle_time", usize);
@@ -2179,6 +2350,41 @@ pub fn main() {
                 });
             return;
         }
+        ("shrink-storage", Some(subcommand_matches)) => {
+            let target_alive_ratio =
+                value_t_or_exit!(subcommand_matches, "target_alive_ratio", f64);
+            let admin_client = admin_rpc_service::connect(&ledger_path);
+            admin_rpc_service::runtime()
+                .block_on(async move {
+                    admin_client
+                        .await?
+                        .shrink_accounts_storage(target_alive_ratio)
+                        .await
+                })
+                .unwrap_or_else(|err| {
+                    println!("shrink storage request failed: {}", err);
+                    exit(1);
+                });
+            println!("Shrink to alive ratio {} requested", target_alive_ratio);
+            return;
+        }
+        ("storage-report", Some(subcommand_matches)) => {
+            let limit = value_t_or_exit!(subcommand_matches, "limit", usize);
+            let admin_client = admin_rpc_service::connect(&ledger_path);
+            let report = admin_rpc_service::runtime()
+                .block_on(async move {
+                    admin_client
+                        .await?
+                        .accounts_storage_report(Some(limit))
+                        .await
+                })
+                .unwrap_or_else(|err| {
+                    println!("storage report request failed: {}", err);
+                    exit(1);
+                });
+            print!("{}", report);
+            return;
+        }
         ("wait-for-restart-window", Some(subcommand_matches)) => {
             let min_idle_time = value_t_or_exit!(subcommand_matches, "min_idle_time", usize);
             let identity = pubkey_of(subcommand_matches, "identity");
@@ -2388,9 +2594,26 @@ pub fn main() {
             _ => unreachable!(),
         };
 
-    let accounts_index_config = value_t!(matches, "accounts_index_bins", usize)
+    for spec in values_t!(matches, "rpc_parser_idl", String).unwrap_or_default() {
+        match register_idl_file(&spec) {
+            Ok(program_id) => info!("Registered IDL parser for {} from {}", program_id, spec),
+            Err(err) => {
+                eprintln!("Failed to load IDL {}: {}", spec, err);
+                exit(1);
+            }
+        }
+    }
+
+    let index_limit_mb = value_t!(matches, "accounts_index_memory_limit_mb", usize).ok();
+    let accounts_index_drives = values_t!(matches, "accounts_index_path", String)
         .ok()
-        .map(|bins| AccountsIndexConfig { bins: Some(bins) });
+        .map(|paths| paths.into_iter().map(PathBuf::from).collect())
+        .or_else(|| index_limit_mb.map(|_| vec![ledger_path.join("accounts_index")]));
+    let accounts_index_config = Some(AccountsIndexConfig {
+        bins: value_t!(matches, "accounts_index_bins", usize).ok(),
+        index_limit_mb,
+        drives: accounts_index_drives,
+    });
 
     let mut validator_config = ValidatorConfig {
         require_tower: matches.is_present("require_tower"),
@@ -2429,8 +2652,12 @@ pub fn main() {
             rpc_bigtable_timeout: value_t!(matches, "rpc_bigtable_timeout", u64)
                 .ok()
                 .map(Duration::from_secs),
+            local_ledger_storage_path: matches
+                .value_of("local_ledger_storage_path")
+                .map(PathBuf::from),
             account_indexes: account_indexes.clone(),
             rpc_scan_and_fix_roots: matches.is_present("rpc_scan_and_fix_roots"),
+            enable_rpc_account_proofs: matches.is_present("enable_rpc_account_proofs"),
         },
         rpc_addrs: value_t!(matches, "rpc_port", u16).ok().map(|rpc_port| {
             (
@@ -2495,6 +2722,11 @@ pub fn main() {
         tpu_coalesce_ms,
         no_wait_for_vote_to_start_leader: matches.is_present("no_wait_for_vote_to_start_leader"),
         accounts_shrink_ratio,
+        account_write_log: matches.is_present("account_write_log"),
+        account_write_log_data: matches.is_present("account_write_log_data"),
+        accounts_hash_cache_path: value_t!(matches, "accounts_hash_cache_path", String)
+            .ok()
+            .map(PathBuf::from),
         ..ValidatorConfig::default()
     };
 
@@ -2673,6 +2905,7 @@ pub fn main() {
 
     let start_progress = Arc::new(RwLock::new(ValidatorStartProgress::default()));
     let admin_service_cluster_info = Arc::new(RwLock::new(None));
+    let admin_service_bank_forks = Arc::new(RwLock::new(None));
     admin_rpc_service::run(
         &ledger_path,
         admin_rpc_service::AdminRpcRequestMetadata {
@@ -2682,6 +2915,7 @@ pub fn main() {
             start_progress: start_progress.clone(),
             authorized_voter_keypairs: authorized_voter_keypairs.clone(),
             cluster_info: admin_service_cluster_info.clone(),
+            bank_forks: admin_service_bank_forks.clone(),
             tower_storage: validator_config.tower_storage.clone(),
         },
     );
@@ -2777,6 +3011,16 @@ pub fn main() {
 
     solana_metrics::set_host_id(identity_keypair.pubkey().to_string());
     solana_metrics::set_panic_hook("validator");
+    if let Some(address) = matches.value_of("prometheus_bind_address") {
+        let address = solana_net_utils::parse_host_port(address).unwrap_or_else(|err| {
+            eprintln!("Failed to parse --prometheus-bind-address: {}", err);
+            exit(1);
+        });
+        if let Err(err) = solana_metrics::serve_prometheus(address) {
+            eprintln!("Failed to serve Prometheus metrics on {}: {}", address, err);
+            exit(1);
+        }
+    }
 
     solana_entry::entry::init_poh();
     solana_runtime::snapshot_utils::remove_tmp_snapshot_archives(&snapshot_output_dir);
@@ -2825,6 +3069,7 @@ pub fn main() {
         socket_addr_space,
     );
     *admin_service_cluster_info.write().unwrap() = Some(validator.cluster_info.clone());
+    *admin_service_bank_forks.write().unwrap() = Some(validator.bank_forks.clone());
 
     if let Some(filename) = init_complete_file {
         File::create(filename).unwrap_or_else(|_| {
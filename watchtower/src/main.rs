@@ -1,7 +1,16 @@
 //! A command-line executable for monitoring the health of a cluster
 #![allow(clippy::integer_arithmetic)]
 
+#[macro_use]
+extern crate serde_derive;
+
+mod validator_monitor;
+
 use {
+    crate::validator_monitor::{
+        load_validators_config, update_baseline_commission, AlertTracker, AlertTransition, Check,
+        DelinquencyStats, MonitoredValidator, ValidatorStatus, ValidatorThresholds,
+    },
     clap::{crate_description, crate_name, value_t, value_t_or_exit, App, Arg},
     log::*,
     solana_clap_utils::{
@@ -9,31 +18,47 @@ use {
         input_validators::{is_parsable, is_pubkey_or_keypair, is_url},
     },
     solana_cli_output::display::format_labeled_address,
-    solana_client::{client_error, rpc_client::RpcClient, rpc_response::RpcVoteAccountStatus},
-    solana_metrics::{datapoint_error, datapoint_info},
-    solana_notifier::Notifier,
+    solana_client::{
+        client_error,
+        rpc_client::RpcClient,
+        rpc_response::{RpcBlockProduction, RpcVoteAccountStatus},
+    },
+    solana_metrics::{
+        datapoint_error, datapoint_info,
+        prometheus::{MetricType, MetricsEndpoint, MetricsWriter},
+    },
+    solana_notifier::{Alert, Notifier, Severity},
     solana_sdk::{
+        clock::Slot,
         hash::Hash,
-        native_token::{sol_to_lamports, Sol},
+        native_token::{lamports_to_sol, Sol},
         pubkey::Pubkey,
     },
     std::{
         collections::HashMap,
         error,
+        net::SocketAddr,
+        process::exit,
+        sync::{Arc, RwLock},
         thread::sleep,
         time::{Duration, Instant},
     },
 };
 
+/// Source reported with structured alerts
+const SOURCE: &str = "solana-watchtower";
+/// Dedup key of cluster sanity alerts, which track a single incident at a time
+const SANITY_DEDUP_KEY: &str = "solana-watchtower-sanity";
+
 struct Config {
     address_labels: HashMap<String, String>,
     ignore_http_bad_gateway: bool,
     interval: Duration,
     json_rpc_url: String,
-    minimum_validator_identity_balance: u64,
+    metrics_bind_address: Option<SocketAddr>,
     monitor_active_stake: bool,
     unhealthy_threshold: usize,
-    validator_identity_pubkeys: Vec<Pubkey>,
+    validators: Vec<MonitoredValidator>,
 }
 
 fn get_config() -> Config {
@@ -56,7 +81,19 @@ fn get_config() -> Config {
         and a sending number owned by that account,
         define environment variable before running `solana-watchtower`:
 
-        export TWILIO_CONFIG='ACCOUNT=<account>,TOKEN=<securityToken>,TO=<receivingNumber>,FROM=<sendingNumber>'")
+        export TWILIO_CONFIG='ACCOUNT=<account>,TOKEN=<securityToken>,TO=<receivingNumber>,FROM=<sendingNumber>'
+
+        To open PagerDuty incidents that resolve once the failure clears, define the
+        routing key of a PagerDuty Events API v2 integration:
+
+        export PAGERDUTY_ROUTING_KEY=...
+
+        To post alerts as JSON to another HTTP endpoint, define its URL and optionally
+        a template in which {{summary}}, {{severity}}, {{dedup_key}}, {{action}} and
+        {{source}} are substituted:
+
+        export WEBHOOK_URL=...
+        export WEBHOOK_TEMPLATE='{\"text\": \"[{{severity}}] {{summary}}\"}'")
         .arg({
             let arg = Arg::with_name("config_file")
                 .short("C")
@@ -104,6 +141,16 @@ fn get_config() -> Config {
                 .multiple(true)
                 .help("Validator identities to monitor for delinquency")
         )
+        .arg(
+            Arg::with_name("validators_config")
+                .long("validators-config")
+                .value_name("PATH")
+                .takes_value(true)
+                .help("YAML file listing validator identities to monitor, each with an optional \
+                    label and thresholds overriding the command-line defaults: \
+                    max-skip-rate, max-vote-lag, min-epoch-credits, min-balance and \
+                    expected-commission")
+        )
         .arg(
             Arg::with_name("minimum_validator_identity_balance")
                 .long("minimum-validator-identity-balance")
@@ -113,6 +160,40 @@ fn get_config() -> Config {
                 .validator(is_parsable::<f64>)
                 .help("Alert when the validator identity balance is less than this amount of SOL")
         )
+        .arg(
+            Arg::with_name("max_skip_rate")
+                .long("max-skip-rate")
+                .value_name("PERCENT")
+                .takes_value(true)
+                .validator(is_parsable::<f64>)
+                .help("Alert when a validator skips more than this percentage of its leader \
+                    slots in the current epoch")
+        )
+        .arg(
+            Arg::with_name("max_vote_lag")
+                .long("max-vote-lag")
+                .value_name("SLOTS")
+                .takes_value(true)
+                .validator(is_parsable::<u64>)
+                .help("Alert when a validator's last vote is more than this many slots behind")
+        )
+        .arg(
+            Arg::with_name("min_epoch_credits")
+                .long("min-epoch-credits")
+                .value_name("CREDITS")
+                .takes_value(true)
+                .validator(is_parsable::<u64>)
+                .help("Alert when a validator earned fewer vote credits than this in the last \
+                    completed epoch")
+        )
+        .arg(
+            Arg::with_name("metrics_bind_address")
+                .long("metrics-bind-address")
+                .value_name("HOST:PORT")
+                .takes_value(true)
+                .validator(is_parsable::<SocketAddr>)
+                .help("Serve Prometheus metrics on http://HOST:PORT/metrics")
+        )
         .arg(
             // Deprecated parameter, now always enabled
             Arg::with_name("no_duplicate_notifications")
@@ -144,62 +225,367 @@ fn get_config() -> Config {
 
     let interval = Duration::from_secs(value_t_or_exit!(matches, "interval", u64));
     let unhealthy_threshold = value_t_or_exit!(matches, "unhealthy_threshold", usize);
-    let minimum_validator_identity_balance = sol_to_lamports(value_t_or_exit!(
-        matches,
-        "minimum_validator_identity_balance",
-        f64
-    ));
+    let default_thresholds = ValidatorThresholds {
+        max_skip_rate: value_t!(matches, "max_skip_rate", f64).ok(),
+        max_vote_lag: value_t!(matches, "max_vote_lag", u64).ok(),
+        min_epoch_credits: value_t!(matches, "min_epoch_credits", u64).ok(),
+        min_balance: Some(value_t_or_exit!(
+            matches,
+            "minimum_validator_identity_balance",
+            f64
+        )),
+        expected_commission: None,
+    };
     let json_rpc_url =
         value_t!(matches, "json_rpc_url", String).unwrap_or_else(|_| config.json_rpc_url.clone());
-    let validator_identity_pubkeys: Vec<_> = pubkeys_of(&matches, "validator_identities")
-        .unwrap_or_else(Vec::new)
-        .into_iter()
-        .collect();
+
+    let mut address_labels = config.address_labels;
+    let mut validators = match matches.value_of("validators_config") {
+        Some(validators_config) => {
+            load_validators_config(validators_config).unwrap_or_else(|err| {
+                eprintln!("Unable to load {}: {}", validators_config, err);
+                exit(1);
+            })
+        }
+        None => vec![],
+    };
+    // Identities also listed in the config file keep their configured label and thresholds
+    for identity in pubkeys_of(&matches, "validator_identities").unwrap_or_else(Vec::new) {
+        if !validators
+            .iter()
+            .any(|validator| validator.identity == identity)
+        {
+            validators.push(MonitoredValidator {
+                identity,
+                label: None,
+                thresholds: ValidatorThresholds::default(),
+            });
+        }
+    }
+    for validator in validators.iter_mut() {
+        validator.thresholds = validator.thresholds.clone().or(&default_thresholds);
+        match &validator.label {
+            Some(label) => {
+                address_labels.insert(validator.identity.to_string(), label.clone());
+            }
+            None => validator.label = address_labels.get(&validator.identity.to_string()).cloned(),
+        }
+    }
 
     let monitor_active_stake = matches.is_present("monitor_active_stake");
     let ignore_http_bad_gateway = matches.is_present("ignore_http_bad_gateway");
+    let metrics_bind_address = value_t!(matches, "metrics_bind_address", SocketAddr).ok();
 
     let config = Config {
-        address_labels: config.address_labels,
+        address_labels,
         ignore_http_bad_gateway,
         interval,
         json_rpc_url,
-        minimum_validator_identity_balance,
+        metrics_bind_address,
         monitor_active_stake,
         unhealthy_threshold,
-        validator_identity_pubkeys,
+        validators,
     };
 
     info!("RPC URL: {}", config.json_rpc_url);
     info!(
         "Monitored validators: {:?}",
-        config.validator_identity_pubkeys
+        config
+            .validators
+            .iter()
+            .map(|validator| validator.identity)
+            .collect::<Vec<_>>()
     );
     config
 }
 
-fn get_cluster_info(
-    config: &Config,
-    rpc_client: &RpcClient,
-) -> client_error::Result<(u64, Hash, RpcVoteAccountStatus, HashMap<Pubkey, u64>)> {
+struct ClusterInfo {
+    transaction_count: u64,
+    recent_blockhash: Hash,
+    slot: Slot,
+    vote_accounts: RpcVoteAccountStatus,
+    block_production: RpcBlockProduction,
+    validator_balances: HashMap<Pubkey, u64>,
+}
+
+fn get_cluster_info(config: &Config, rpc_client: &RpcClient) -> client_error::Result<ClusterInfo> {
     let transaction_count = rpc_client.get_transaction_count()?;
     let recent_blockhash = rpc_client.get_latest_blockhash()?;
+    let slot = rpc_client.get_slot()?;
     let vote_accounts = rpc_client.get_vote_accounts()?;
+    let block_production = rpc_client.get_block_production()?.value;
 
     let mut validator_balances = HashMap::new();
-    for validator_identity in &config.validator_identity_pubkeys {
+    for validator in &config.validators {
         validator_balances.insert(
-            *validator_identity,
-            rpc_client.get_balance(validator_identity)?,
+            validator.identity,
+            rpc_client.get_balance(&validator.identity)?,
         );
     }
 
-    Ok((
+    Ok(ClusterInfo {
         transaction_count,
         recent_blockhash,
+        slot,
         vote_accounts,
+        block_production,
         validator_balances,
-    ))
+    })
+}
+
+/// Per-validator state carried across intervals
+#[derive(Default)]
+struct ValidatorHistory {
+    status: Option<ValidatorStatus>,
+    /// Commission compared against when no commission is expected explicitly
+    baseline_commission: Option<u8>,
+    delinquency: DelinquencyStats,
+}
+
+/// Evaluate the monitored validators, notifying on alert state transitions
+fn monitor_validators(
+    config: &Config,
+    cluster_info: &ClusterInfo,
+    notifier: &Notifier,
+    alert_tracker: &mut AlertTracker,
+    history: &mut HashMap<Pubkey, ValidatorHistory>,
+) {
+    for validator in &config.validators {
+        let status = ValidatorStatus::new(
+            &validator.identity,
+            cluster_info.slot,
+            &cluster_info.vote_accounts,
+            &cluster_info.block_production,
+            cluster_info
+                .validator_balances
+                .get(&validator.identity)
+                .cloned()
+                .unwrap_or_default(),
+        );
+        let validator_history = history.entry(validator.identity).or_default();
+        update_baseline_commission(
+            &mut validator_history.baseline_commission,
+            status.commission,
+            false,
+        );
+        validator_history
+            .delinquency
+            .record(status.delinquent || !status.found);
+
+        let failures =
+            status.failures(&validator.thresholds, validator_history.baseline_commission);
+        let formatted_validator_identity =
+            format_labeled_address(&validator.identity.to_string(), &config.address_labels);
+        for (check, message) in &failures {
+            error!(
+                "{} {} failure: {}",
+                formatted_validator_identity, check, message
+            );
+        }
+        info!(
+            "{}: delinquent {:.2}% of the last {} checks, {} delinquency episodes",
+            formatted_validator_identity,
+            validator_history.delinquency.recent_delinquency_percent(),
+            validator_history.delinquency.recent_checks(),
+            validator_history.delinquency.episodes,
+        );
+
+        for transition in alert_tracker.update(&validator.identity, &failures) {
+            let dedup_key =
+                |check: Check| format!("solana-watchtower-{}-{}", validator.identity, check.name());
+            match transition {
+                AlertTransition::Firing { check, message, .. } => {
+                    datapoint_error!(
+                        "watchtower-validator-alert",
+                        ("identity", validator.identity.to_string(), String),
+                        ("check", check.name(), String),
+                        ("err", message, String)
+                    );
+                    notifier.send_alert(
+                        &Alert::trigger(
+                            &format!(
+                                "solana-watchtower: Error: {} {}: {}",
+                                formatted_validator_identity, check, message
+                            ),
+                            check.severity(),
+                        )
+                        .dedup_key(&dedup_key(check))
+                        .source(SOURCE),
+                    );
+                }
+                AlertTransition::Resolved {
+                    check, duration, ..
+                } => {
+                    let duration = Duration::from_secs(duration.as_secs());
+                    notifier.send_alert(
+                        &Alert::resolve(
+                            &format!(
+                                "solana-watchtower: {} {} all clear after {}",
+                                formatted_validator_identity,
+                                check,
+                                humantime::format_duration(duration)
+                            ),
+                            &dedup_key(check),
+                        )
+                        .source(SOURCE),
+                    );
+                }
+            }
+        }
+        update_baseline_commission(
+            &mut validator_history.baseline_commission,
+            status.commission,
+            alert_tracker.is_firing(&validator.identity, Check::Commission),
+        );
+        validator_history.status = Some(status);
+    }
+}
+
+fn write_metrics(
+    config: &Config,
+    cluster_info: &ClusterInfo,
+    current_stake_percent: f64,
+    alert_tracker: &AlertTracker,
+    history: &HashMap<Pubkey, ValidatorHistory>,
+) -> MetricsWriter {
+    let mut metrics = MetricsWriter::default();
+    metrics.sample(
+        "watchtower_cluster_transaction_count",
+        "Cluster transaction count",
+        MetricType::Counter,
+        &[],
+        cluster_info.transaction_count as f64,
+    );
+    metrics.sample(
+        "watchtower_cluster_slot",
+        "Cluster slot",
+        MetricType::Gauge,
+        &[],
+        cluster_info.slot as f64,
+    );
+    metrics.sample(
+        "watchtower_cluster_current_stake_percent",
+        "Percentage of stake held by non-delinquent validators",
+        MetricType::Gauge,
+        &[],
+        current_stake_percent,
+    );
+
+    for validator in &config.validators {
+        let validator_history = match history.get(&validator.identity) {
+            Some(validator_history) => validator_history,
+            None => continue,
+        };
+        let status = match &validator_history.status {
+            Some(status) => status,
+            None => continue,
+        };
+        let identity = validator.identity.to_string();
+        let labels = [
+            ("identity", identity.as_str()),
+            ("label", validator.label.as_deref().unwrap_or_default()),
+        ];
+        let mut gauge =
+            |name, help, value: f64| metrics.sample(name, help, MetricType::Gauge, &labels, value);
+        gauge(
+            "watchtower_validator_delinquent",
+            "1 if the validator is delinquent or missing",
+            (status.delinquent || !status.found) as u8 as f64,
+        );
+        gauge(
+            "watchtower_validator_activated_stake_sol",
+            "Stake delegated to the validator's vote account",
+            lamports_to_sol(status.activated_stake),
+        );
+        gauge(
+            "watchtower_validator_identity_balance_sol",
+            "Balance of the validator identity account",
+            lamports_to_sol(status.balance),
+        );
+        gauge(
+            "watchtower_validator_leader_slots",
+            "Leader slots in the current epoch so far",
+            status.leader_slots as f64,
+        );
+        gauge(
+            "watchtower_validator_blocks_produced",
+            "Blocks produced in the current epoch so far",
+            status.blocks_produced as f64,
+        );
+        gauge(
+            "watchtower_validator_recent_delinquency_percent",
+            "Percentage of recent checks in which the validator was delinquent",
+            validator_history.delinquency.recent_delinquency_percent(),
+        );
+        if let Some(skip_rate) = status.skip_rate() {
+            gauge(
+                "watchtower_validator_skip_rate_percent",
+                "Percentage of leader slots skipped in the current epoch",
+                skip_rate,
+            );
+        }
+        if let Some(vote_lag) = status.vote_lag {
+            gauge(
+                "watchtower_validator_vote_lag_slots",
+                "Slots between the cluster slot and the validator's last vote",
+                vote_lag as f64,
+            );
+        }
+        if let Some(credits) = status.last_epoch_credits {
+            gauge(
+                "watchtower_validator_last_epoch_credits",
+                "Vote credits earned in the last completed epoch",
+                credits as f64,
+            );
+        }
+        if let Some(commission) = status.commission {
+            gauge(
+                "watchtower_validator_commission_percent",
+                "Vote account commission",
+                commission as f64,
+            );
+        }
+
+        metrics.sample(
+            "watchtower_validator_checks_total",
+            "Number of times the validator was checked",
+            MetricType::Counter,
+            &labels,
+            validator_history.delinquency.checks as f64,
+        );
+        metrics.sample(
+            "watchtower_validator_delinquent_checks_total",
+            "Number of checks in which the validator was delinquent",
+            MetricType::Counter,
+            &labels,
+            validator_history.delinquency.delinquent_checks as f64,
+        );
+        metrics.sample(
+            "watchtower_validator_delinquency_episodes_total",
+            "Number of times the validator became delinquent",
+            MetricType::Counter,
+            &labels,
+            validator_history.delinquency.episodes as f64,
+        );
+
+        for check in &[
+            Check::Delinquent,
+            Check::SkipRate,
+            Check::VoteLag,
+            Check::EpochCredits,
+            Check::Commission,
+            Check::Balance,
+        ] {
+            metrics.sample(
+                "watchtower_validator_alert",
+                "1 if the alert is firing",
+                MetricType::Gauge,
+                &[labels[0], labels[1], ("check", check.name())],
+                alert_tracker.is_firing(&validator.identity, *check) as u8 as f64,
+            );
+        }
+    }
+    metrics
 }
 
 fn main() -> Result<(), Box<dyn error::Error>> {
@@ -210,6 +596,25 @@ fn main() -> Result<(), Box<dyn error::Error>> {
 
     let rpc_client = RpcClient::new(config.json_rpc_url.clone());
     let notifier = Notifier::default();
+    // Metrics as of the last check, served by the metrics endpoint
+    let published_metrics = Arc::new(RwLock::new(String::new()));
+    let _metrics_endpoint = config.metrics_bind_address.map(|bind_address| {
+        let published_metrics = published_metrics.clone();
+        let metrics_endpoint = MetricsEndpoint::new(bind_address, move || {
+            published_metrics.read().unwrap().clone()
+        })
+        .unwrap_or_else(|err| {
+            eprintln!("Unable to serve metrics on {}: {}", bind_address, err);
+            exit(1);
+        });
+        info!(
+            "Serving metrics on http://{}/metrics",
+            metrics_endpoint.local_addr()
+        );
+        metrics_endpoint
+    });
+    let mut alert_tracker = AlertTracker::new(config.unhealthy_threshold);
+    let mut validator_history = HashMap::new();
     let mut last_transaction_count = 0;
     let mut last_recent_blockhash = Hash::default();
     let mut last_notification_msg = "".into();
@@ -218,7 +623,10 @@ fn main() -> Result<(), Box<dyn error::Error>> {
 
     loop {
         let failure = match get_cluster_info(&config, &rpc_client) {
-            Ok((transaction_count, recent_blockhash, vote_accounts, validator_balances)) => {
+            Ok(cluster_info) => {
+                let transaction_count = cluster_info.transaction_count;
+                let recent_blockhash = cluster_info.recent_blockhash;
+                let vote_accounts = &cluster_info.vote_accounts;
                 info!("Current transaction count: {}", transaction_count);
                 info!("Recent blockhash: {}", recent_blockhash);
                 info!("Current validator count: {}", vote_accounts.current.len());
@@ -278,39 +686,23 @@ fn main() -> Result<(), Box<dyn error::Error>> {
                     ));
                 }
 
-                let mut validator_errors = vec![];
-                for validator_identity in config.validator_identity_pubkeys.iter() {
-                    let formatted_validator_identity = format_labeled_address(
-                        &validator_identity.to_string(),
-                        &config.address_labels,
-                    );
-                    if vote_accounts
-                        .delinquent
-                        .iter()
-                        .any(|vai| vai.node_pubkey == *validator_identity.to_string())
-                    {
-                        validator_errors
-                            .push(format!("{} delinquent", formatted_validator_identity));
-                    } else if !vote_accounts
-                        .current
-                        .iter()
-                        .any(|vai| vai.node_pubkey == *validator_identity.to_string())
-                    {
-                        validator_errors.push(format!("{} missing", formatted_validator_identity));
-                    }
-
-                    if let Some(balance) = validator_balances.get(validator_identity) {
-                        if *balance < config.minimum_validator_identity_balance {
-                            failures.push((
-                                "balance",
-                                format!("{} has {}", formatted_validator_identity, Sol(*balance)),
-                            ));
-                        }
-                    }
-                }
-
-                if !validator_errors.is_empty() {
-                    failures.push(("delinquent", validator_errors.join(",")));
+                // Validator failures are notified individually as their alerts change state
+                monitor_validators(
+                    &config,
+                    &cluster_info,
+                    &notifier,
+                    &mut alert_tracker,
+                    &mut validator_history,
+                );
+                if config.metrics_bind_address.is_some() {
+                    *published_metrics.write().unwrap() = write_metrics(
+                        &config,
+                        &cluster_info,
+                        current_stake_percent,
+                        &alert_tracker,
+                        &validator_history,
+                    )
+                    .render();
                 }
 
                 for failure in failures.iter() {
@@ -344,7 +736,11 @@ fn main() -> Result<(), Box<dyn error::Error>> {
             if num_consecutive_failures > config.unhealthy_threshold {
                 datapoint_info!("watchtower-sanity", ("ok", false, bool));
                 if last_notification_msg != notification_msg {
-                    notifier.send(&notification_msg);
+                    notifier.send_alert(
+                        &Alert::trigger(&notification_msg, Severity::Critical)
+                            .dedup_key(SANITY_DEDUP_KEY)
+                            .source(SOURCE),
+                    );
                 }
                 datapoint_error!(
                     "watchtower-sanity-failure",
@@ -370,7 +766,13 @@ fn main() -> Result<(), Box<dyn error::Error>> {
                     humantime::format_duration(alarm_duration)
                 );
                 info!("{}", all_clear_msg);
-                notifier.send(&format!("solana-watchtower: {}", all_clear_msg));
+                notifier.send_alert(
+                    &Alert::resolve(
+                        &format!("solana-watchtower: {}", all_clear_msg),
+                        SANITY_DEDUP_KEY,
+                    )
+                    .source(SOURCE),
+                );
             }
             last_notification_msg = "".into();
             last_success = Instant::now();
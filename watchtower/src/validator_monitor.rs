@@ -0,0 +1,704 @@
+//! Per-validator health checks and alert state tracking
+use {
+    log::*,
+    solana_client::rpc_response::{RpcBlockProduction, RpcVoteAccountStatus},
+    solana_notifier::Severity,
+    solana_sdk::{clock::Slot, native_token::lamports_to_sol, pubkey::Pubkey},
+    std::{
+        collections::{HashMap, HashSet, VecDeque},
+        fmt,
+        fs::File,
+        io,
+        str::FromStr,
+        time::{Duration, Instant},
+    },
+};
+
+/// Thresholds applied to a monitored validator. Unset thresholds are not checked.
+#[derive(Clone, Debug, Default, Deserialize, PartialEq)]
+#[serde(rename_all = "kebab-case", default)]
+pub struct ValidatorThresholds {
+    /// Maximum percentage of leader slots skipped in the current epoch
+    pub max_skip_rate: Option<f64>,
+    /// Maximum number of slots the last vote may trail the cluster
+    pub max_vote_lag: Option<u64>,
+    /// Minimum vote credits earned in the last completed epoch
+    pub min_epoch_credits: Option<u64>,
+    /// Minimum identity account balance, in SOL
+    pub min_balance: Option<f64>,
+    /// Alert when the commission differs from this value. When unset, alert when the commission
+    /// changes, see `update_baseline_commission()`.
+    pub expected_commission: Option<u8>,
+}
+
+impl ValidatorThresholds {
+    /// Fill unset thresholds from `defaults`
+    pub fn or(self, defaults: &ValidatorThresholds) -> Self {
+        Self {
+            max_skip_rate: self.max_skip_rate.or(defaults.max_skip_rate),
+            max_vote_lag: self.max_vote_lag.or(defaults.max_vote_lag),
+            min_epoch_credits: self.min_epoch_credits.or(defaults.min_epoch_credits),
+            min_balance: self.min_balance.or(defaults.min_balance),
+            expected_commission: self.expected_commission.or(defaults.expected_commission),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct MonitoredValidator {
+    pub identity: Pubkey,
+    pub label: Option<String>,
+    pub thresholds: ValidatorThresholds,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+struct ValidatorConfigEntry {
+    identity: String,
+    label: Option<String>,
+    #[serde(flatten)]
+    thresholds: ValidatorThresholds,
+}
+
+#[derive(Debug, Deserialize)]
+struct ValidatorsConfig {
+    validators: Vec<ValidatorConfigEntry>,
+}
+
+/// Load the validators to monitor from a YAML file of the form
+///
+/// ```yaml
+/// validators:
+///   - identity: <PUBKEY>
+///     label: validator-1
+///     max-skip-rate: 25
+///     min-balance: 5
+/// ```
+pub fn load_validators_config(path: &str) -> io::Result<Vec<MonitoredValidator>> {
+    let file = File::open(path)?;
+    let config: ValidatorsConfig = serde_yaml::from_reader(file)
+        .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err.to_string()))?;
+    let mut identities = HashSet::new();
+    config
+        .validators
+        .into_iter()
+        .map(|entry| {
+            let identity = Pubkey::from_str(&entry.identity).map_err(|_| {
+                io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("Invalid validator identity: {}", entry.identity),
+                )
+            })?;
+            // Alerts are tracked by identity, so each validator may only be listed once
+            if !identities.insert(identity) {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("Duplicate validator identity: {}", identity),
+                ));
+            }
+            Ok(MonitoredValidator {
+                identity,
+                label: entry.label,
+                thresholds: entry.thresholds,
+            })
+        })
+        .collect()
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Check {
+    Delinquent,
+    SkipRate,
+    VoteLag,
+    EpochCredits,
+    Commission,
+    Balance,
+}
+
+impl Check {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Check::Delinquent => "delinquent",
+            Check::SkipRate => "skip-rate",
+            Check::VoteLag => "vote-lag",
+            Check::EpochCredits => "epoch-credits",
+            Check::Commission => "commission",
+            Check::Balance => "balance",
+        }
+    }
+
+    pub fn severity(&self) -> Severity {
+        match self {
+            Check::Delinquent => Severity::Critical,
+            Check::VoteLag => Severity::Error,
+            Check::SkipRate | Check::EpochCredits | Check::Commission | Check::Balance => {
+                Severity::Warning
+            }
+        }
+    }
+}
+
+impl fmt::Display for Check {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+/// A snapshot of a validator's state, gathered once per interval
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ValidatorStatus {
+    /// Whether the validator has a vote account at all
+    pub found: bool,
+    pub delinquent: bool,
+    pub activated_stake: u64,
+    pub commission: Option<u8>,
+    pub vote_lag: Option<u64>,
+    /// Leader slots and blocks produced so far in the current epoch
+    pub leader_slots: usize,
+    pub blocks_produced: usize,
+    /// Credits earned in the last completed epoch
+    pub last_epoch_credits: Option<u64>,
+    pub balance: u64,
+}
+
+impl ValidatorStatus {
+    pub fn new(
+        identity: &Pubkey,
+        slot: Slot,
+        vote_accounts: &RpcVoteAccountStatus,
+        block_production: &RpcBlockProduction,
+        balance: u64,
+    ) -> Self {
+        let identity = identity.to_string();
+        let (leader_slots, blocks_produced) = block_production
+            .by_identity
+            .get(&identity)
+            .cloned()
+            .unwrap_or_default();
+        let mut status = Self {
+            leader_slots,
+            blocks_produced,
+            balance,
+            ..Self::default()
+        };
+
+        let current = vote_accounts
+            .current
+            .iter()
+            .map(|vote_account| (vote_account, false));
+        let delinquent = vote_accounts
+            .delinquent
+            .iter()
+            .map(|vote_account| (vote_account, true));
+        if let Some((vote_account, delinquent)) = current
+            .chain(delinquent)
+            .find(|(vote_account, _)| vote_account.node_pubkey == identity)
+        {
+            status.found = true;
+            status.delinquent = delinquent;
+            status.activated_stake = vote_account.activated_stake;
+            status.commission = Some(vote_account.commission);
+            if vote_account.last_vote > 0 {
+                status.vote_lag = Some(slot.saturating_sub(vote_account.last_vote));
+            }
+            // The last entry is the current epoch, still in progress
+            let epoch_credits = &vote_account.epoch_credits;
+            if epoch_credits.len() >= 2 {
+                let (_, credits, prev_credits) = epoch_credits[epoch_credits.len() - 2];
+                status.last_epoch_credits = Some(credits.saturating_sub(prev_credits));
+            }
+        }
+        status
+    }
+
+    /// Percentage of leader slots skipped, if the validator has had any
+    pub fn skip_rate(&self) -> Option<f64> {
+        if self.leader_slots == 0 {
+            None
+        } else {
+            let skipped = self.leader_slots.saturating_sub(self.blocks_produced);
+            Some(skipped as f64 * 100. / self.leader_slots as f64)
+        }
+    }
+
+    /// Evaluate `thresholds`, returning the failing checks with a description of each
+    pub fn failures(
+        &self,
+        thresholds: &ValidatorThresholds,
+        baseline_commission: Option<u8>,
+    ) -> Vec<(Check, String)> {
+        let mut failures = vec![];
+        if !self.found {
+            failures.push((Check::Delinquent, "missing".to_string()));
+        } else if self.delinquent {
+            failures.push((Check::Delinquent, "delinquent".to_string()));
+        }
+
+        if let (Some(max_skip_rate), Some(skip_rate)) = (thresholds.max_skip_rate, self.skip_rate())
+        {
+            if skip_rate > max_skip_rate {
+                failures.push((
+                    Check::SkipRate,
+                    format!(
+                        "skip rate {:.2}% ({} of {} leader slots skipped)",
+                        skip_rate,
+                        self.leader_slots - self.blocks_produced,
+                        self.leader_slots
+                    ),
+                ));
+            }
+        }
+
+        if let (Some(max_vote_lag), Some(vote_lag)) = (thresholds.max_vote_lag, self.vote_lag) {
+            if vote_lag > max_vote_lag {
+                failures.push((
+                    Check::VoteLag,
+                    format!("last vote {} slots behind", vote_lag),
+                ));
+            }
+        }
+
+        if let (Some(min_epoch_credits), Some(credits)) =
+            (thresholds.min_epoch_credits, self.last_epoch_credits)
+        {
+            if credits < min_epoch_credits {
+                failures.push((
+                    Check::EpochCredits,
+                    format!("earned {} credits in the last epoch", credits),
+                ));
+            }
+        }
+
+        if let (Some(expected), Some(commission)) = (
+            thresholds.expected_commission.or(baseline_commission),
+            self.commission,
+        ) {
+            if commission != expected {
+                failures.push((
+                    Check::Commission,
+                    format!("commission changed from {}% to {}%", expected, commission),
+                ));
+            }
+        }
+
+        if let Some(min_balance) = thresholds.min_balance {
+            if lamports_to_sol(self.balance) < min_balance {
+                failures.push((
+                    Check::Balance,
+                    format!("identity balance is {} SOL", lamports_to_sol(self.balance)),
+                ));
+            }
+        }
+        failures
+    }
+}
+
+/// Update the commission compared against when none is expected explicitly. It starts as the
+/// first commission observed, and follows the commission once a change has been alerted on, so
+/// that the alert resolves rather than firing for as long as the new commission stays.
+pub fn update_baseline_commission(
+    baseline_commission: &mut Option<u8>,
+    commission: Option<u8>,
+    alerted: bool,
+) {
+    if baseline_commission.is_none() || alerted {
+        *baseline_commission = commission;
+    }
+}
+
+/// Number of recent intervals over which the delinquency trend is computed
+const DELINQUENCY_WINDOW: usize = 1_440;
+
+/// Delinquency history of a validator
+#[derive(Clone, Debug, Default)]
+pub struct DelinquencyStats {
+    pub checks: u64,
+    pub delinquent_checks: u64,
+    /// Number of times the validator became delinquent
+    pub episodes: u64,
+    recent: VecDeque<bool>,
+}
+
+impl DelinquencyStats {
+    pub fn record(&mut self, delinquent: bool) {
+        if delinquent && !self.recent.back().cloned().unwrap_or(false) {
+            self.episodes += 1;
+        }
+        self.checks += 1;
+        if delinquent {
+            self.delinquent_checks += 1;
+        }
+        if self.recent.len() == DELINQUENCY_WINDOW {
+            self.recent.pop_front();
+        }
+        self.recent.push_back(delinquent);
+    }
+
+    /// Number of recent intervals the trend is computed over
+    pub fn recent_checks(&self) -> usize {
+        self.recent.len()
+    }
+
+    /// Percentage of the recent intervals in which the validator was delinquent
+    pub fn recent_delinquency_percent(&self) -> f64 {
+        if self.recent.is_empty() {
+            0.
+        } else {
+            let delinquent = self.recent.iter().filter(|delinquent| **delinquent).count();
+            delinquent as f64 * 100. / self.recent.len() as f64
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum AlertTransition {
+    Firing {
+        identity: Pubkey,
+        check: Check,
+        message: String,
+    },
+    Resolved {
+        identity: Pubkey,
+        check: Check,
+        duration: Duration,
+    },
+}
+
+#[derive(Default)]
+struct AlertState {
+    consecutive_failures: usize,
+    firing_since: Option<Instant>,
+}
+
+/// Tracks the alert state of each (validator, check) pair, so that only state transitions are
+/// reported. An alert fires once a check has failed more than `unhealthy_threshold`
+/// consecutive times, and resolves on the first passing interval.
+pub struct AlertTracker {
+    unhealthy_threshold: usize,
+    alerts: HashMap<(Pubkey, Check), AlertState>,
+}
+
+impl AlertTracker {
+    pub fn new(unhealthy_threshold: usize) -> Self {
+        Self {
+            unhealthy_threshold,
+            alerts: HashMap::new(),
+        }
+    }
+
+    pub fn update(
+        &mut self,
+        identity: &Pubkey,
+        failures: &[(Check, String)],
+    ) -> Vec<AlertTransition> {
+        let mut transitions = vec![];
+        for (check, message) in failures {
+            let alert = self.alerts.entry((*identity, *check)).or_default();
+            alert.consecutive_failures += 1;
+            if alert.firing_since.is_none() && alert.consecutive_failures > self.unhealthy_threshold
+            {
+                alert.firing_since = Some(Instant::now());
+                transitions.push(AlertTransition::Firing {
+                    identity: *identity,
+                    check: *check,
+                    message: message.clone(),
+                });
+            }
+        }
+
+        let unhealthy_threshold = self.unhealthy_threshold;
+        self.alerts.retain(|(alert_identity, check), alert| {
+            if alert_identity != identity || failures.iter().any(|(failed, _)| failed == check) {
+                return true;
+            }
+            if let Some(firing_since) = alert.firing_since {
+                transitions.push(AlertTransition::Resolved {
+                    identity: *identity,
+                    check: *check,
+                    duration: firing_since.elapsed(),
+                });
+            } else if alert.consecutive_failures > 0 {
+                debug!(
+                    "{} {} recovered after {} of {} failures",
+                    identity, check, alert.consecutive_failures, unhealthy_threshold
+                );
+            }
+            false
+        });
+        transitions
+    }
+
+    pub fn is_firing(&self, identity: &Pubkey, check: Check) -> bool {
+        self.alerts
+            .get(&(*identity, check))
+            .map(|alert| alert.firing_since.is_some())
+            .unwrap_or(false)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        solana_client::rpc_response::{RpcBlockProductionRange, RpcVoteAccountInfo},
+        solana_sdk::native_token::sol_to_lamports,
+        std::io::Write,
+    };
+
+    fn vote_account_info(identity: &Pubkey, commission: u8, last_vote: Slot) -> RpcVoteAccountInfo {
+        RpcVoteAccountInfo {
+            vote_pubkey: Pubkey::new_unique().to_string(),
+            node_pubkey: identity.to_string(),
+            activated_stake: 42,
+            commission,
+            epoch_vote_account: true,
+            epoch_credits: vec![(4, 1_000, 0), (5, 1_100, 1_000), (6, 1_150, 1_100)],
+            last_vote,
+            root_slot: 0,
+        }
+    }
+
+    fn block_production(
+        identity: &Pubkey,
+        leader_slots: usize,
+        produced: usize,
+    ) -> RpcBlockProduction {
+        let mut by_identity = HashMap::new();
+        by_identity.insert(identity.to_string(), (leader_slots, produced));
+        RpcBlockProduction {
+            by_identity,
+            range: RpcBlockProductionRange {
+                first_slot: 0,
+                last_slot: 100,
+            },
+        }
+    }
+
+    #[test]
+    fn test_validator_status() {
+        let identity = Pubkey::new_unique();
+        let vote_accounts = RpcVoteAccountStatus {
+            current: vec![],
+            delinquent: vec![vote_account_info(&identity, 10, 900)],
+        };
+        let status = ValidatorStatus::new(
+            &identity,
+            1_000,
+            &vote_accounts,
+            &block_production(&identity, 8, 6),
+            sol_to_lamports(1.),
+        );
+        assert_eq!(
+            status,
+            ValidatorStatus {
+                found: true,
+                delinquent: true,
+                activated_stake: 42,
+                commission: Some(10),
+                vote_lag: Some(100),
+                leader_slots: 8,
+                blocks_produced: 6,
+                last_epoch_credits: Some(100),
+                balance: sol_to_lamports(1.),
+            }
+        );
+        assert_eq!(status.skip_rate(), Some(25.));
+
+        let thresholds = ValidatorThresholds {
+            max_skip_rate: Some(20.),
+            max_vote_lag: Some(150),
+            min_epoch_credits: Some(200),
+            min_balance: Some(2.),
+            expected_commission: None,
+        };
+        let checks = |failures: Vec<(Check, String)>| {
+            failures
+                .into_iter()
+                .map(|(check, _)| check)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            checks(status.failures(&thresholds, Some(10))),
+            vec![
+                Check::Delinquent,
+                Check::SkipRate,
+                Check::EpochCredits,
+                Check::Balance
+            ]
+        );
+        assert_eq!(
+            checks(status.failures(&thresholds, Some(5))),
+            vec![
+                Check::Delinquent,
+                Check::SkipRate,
+                Check::EpochCredits,
+                Check::Commission,
+                Check::Balance
+            ]
+        );
+
+        let missing = ValidatorStatus::new(
+            &Pubkey::new_unique(),
+            1_000,
+            &vote_accounts,
+            &block_production(&identity, 8, 6),
+            0,
+        );
+        assert_eq!(
+            missing.failures(&ValidatorThresholds::default(), None),
+            vec![(Check::Delinquent, "missing".to_string())]
+        );
+        assert_eq!(missing.skip_rate(), None);
+    }
+
+    #[test]
+    fn test_alert_tracker() {
+        let identity = Pubkey::new_unique();
+        let other = Pubkey::new_unique();
+        let mut tracker = AlertTracker::new(1);
+        let failing = vec![(Check::VoteLag, "behind".to_string())];
+
+        assert!(tracker.update(&identity, &failing).is_empty());
+        assert!(!tracker.is_firing(&identity, Check::VoteLag));
+        assert_eq!(
+            tracker.update(&identity, &failing),
+            vec![AlertTransition::Firing {
+                identity,
+                check: Check::VoteLag,
+                message: "behind".to_string(),
+            }]
+        );
+        assert!(tracker.is_firing(&identity, Check::VoteLag));
+
+        // Still failing, possibly with a different message: no new notification
+        assert!(tracker
+            .update(&identity, &[(Check::VoteLag, "further behind".to_string())])
+            .is_empty());
+        // Other validators don't affect this one
+        assert!(tracker.update(&other, &[]).is_empty());
+        assert!(tracker.is_firing(&identity, Check::VoteLag));
+
+        match tracker.update(&identity, &[]).as_slice() {
+            [AlertTransition::Resolved {
+                identity: resolved,
+                check: Check::VoteLag,
+                ..
+            }] => assert_eq!(*resolved, identity),
+            transitions => panic!("unexpected transitions {:?}", transitions),
+        }
+        assert!(!tracker.is_firing(&identity, Check::VoteLag));
+        assert!(tracker.update(&identity, &[]).is_empty());
+    }
+
+    #[test]
+    fn test_commission_change_alert_resolves() {
+        let identity = Pubkey::new_unique();
+        let thresholds = ValidatorThresholds::default();
+        let mut tracker = AlertTracker::new(1);
+        let mut baseline_commission = None;
+        let mut monitor = |commission| {
+            let status = ValidatorStatus {
+                found: true,
+                commission: Some(commission),
+                ..ValidatorStatus::default()
+            };
+            update_baseline_commission(&mut baseline_commission, status.commission, false);
+            let transitions = tracker.update(
+                &identity,
+                &status.failures(&thresholds, baseline_commission),
+            );
+            let alerted = tracker.is_firing(&identity, Check::Commission);
+            update_baseline_commission(&mut baseline_commission, status.commission, alerted);
+            transitions
+                .into_iter()
+                .map(|transition| match transition {
+                    AlertTransition::Firing { check, .. } => (check, true),
+                    AlertTransition::Resolved { check, .. } => (check, false),
+                })
+                .collect::<Vec<_>>()
+        };
+
+        assert!(monitor(10).is_empty());
+        assert!(monitor(10).is_empty());
+        // The change fires once it has been seen for long enough, then becomes the baseline
+        assert!(monitor(5).is_empty());
+        assert_eq!(monitor(5), vec![(Check::Commission, true)]);
+        assert_eq!(monitor(5), vec![(Check::Commission, false)]);
+        assert!(monitor(5).is_empty());
+    }
+
+    #[test]
+    fn test_delinquency_stats() {
+        let mut stats = DelinquencyStats::default();
+        assert_eq!(stats.recent_delinquency_percent(), 0.);
+        for delinquent in &[false, true, true, false, true] {
+            stats.record(*delinquent);
+        }
+        assert_eq!(stats.checks, 5);
+        assert_eq!(stats.delinquent_checks, 3);
+        assert_eq!(stats.episodes, 2);
+        assert_eq!(stats.recent_delinquency_percent(), 60.);
+
+        for _ in 0..DELINQUENCY_WINDOW {
+            stats.record(false);
+        }
+        assert_eq!(stats.delinquent_checks, 3);
+        assert_eq!(stats.recent_delinquency_percent(), 0.);
+    }
+
+    #[test]
+    fn test_load_validators_config() {
+        let identity = Pubkey::new_unique();
+        let other_identity = Pubkey::new_unique();
+        let mut file = tempfile::NamedTempFile::new().unwrap();
+        writeln!(
+            file,
+            "validators:\n  - identity: {}\n    label: validator-1\n    max-skip-rate: 25\n  - identity: {}",
+            identity, other_identity
+        )
+        .unwrap();
+        let validators = load_validators_config(file.path().to_str().unwrap()).unwrap();
+        assert_eq!(
+            validators,
+            vec![
+                MonitoredValidator {
+                    identity,
+                    label: Some("validator-1".to_string()),
+                    thresholds: ValidatorThresholds {
+                        max_skip_rate: Some(25.),
+                        ..ValidatorThresholds::default()
+                    },
+                },
+                MonitoredValidator {
+                    identity: other_identity,
+                    label: None,
+                    thresholds: ValidatorThresholds::default(),
+                },
+            ]
+        );
+
+        let defaults = ValidatorThresholds {
+            max_skip_rate: Some(50.),
+            min_balance: Some(10.),
+            ..ValidatorThresholds::default()
+        };
+        assert_eq!(
+            validators[0].thresholds.clone().or(&defaults),
+            ValidatorThresholds {
+                max_skip_rate: Some(25.),
+                min_balance: Some(10.),
+                ..ValidatorThresholds::default()
+            }
+        );
+
+        let mut file = tempfile::NamedTempFile::new().unwrap();
+        writeln!(
+            file,
+            "validators:\n  - identity: {}\n  - identity: {}",
+            identity, identity
+        )
+        .unwrap();
+        let err = load_validators_config(file.path().to_str().unwrap()).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    }
+}